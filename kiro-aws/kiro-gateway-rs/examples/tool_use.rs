@@ -61,7 +61,7 @@ async fn main() -> Result<()> {
                     .model("claude-sonnet-4.5")
                     .max_tokens(1024)
                     .user_message("What's the weather in Reykjavik?")
-                    .assistant_message(&response.text())
+                    .assistant_message(response.text())
                     .message(
                         Role::User,
                         vec![ContentBlock::ToolResult {
@@ -103,7 +103,12 @@ impl<'a> MessagesRequestBuilder<'a> {
     }
 
     /// Add a tool definition.
-    pub fn tool(mut self, name: impl Into<String>, description: impl Into<String>, input_schema: serde_json::Value) -> Self {
+    pub fn tool(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        input_schema: serde_json::Value,
+    ) -> Self {
         let tools = self.request.tools.get_or_insert_with(Vec::new);
         tools.push(Tool {
             name: name.into(),
@@ -152,6 +157,11 @@ impl<'a> MessagesRequestBuilder<'a> {
         self
     }
 
+    /// Estimate the input tokens of the request without sending it.
+    pub fn count_tokens(&self) -> u64 {
+        self.client.count_tokens(&self.request)
+    }
+
     /// Send the request and get a complete response.
     pub async fn send(self) -> Result<MessagesResponse> {
         self.client.send_messages(self.request).await
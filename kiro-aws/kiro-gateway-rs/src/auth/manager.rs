@@ -11,6 +11,7 @@ use crate::models::auth::{AuthType, KiroTokenInfo};
 use crate::storage::TokenStorage;
 
 use super::constants;
+use super::watcher::CredentialWatcher;
 
 /// Manages the Kiro token lifecycle.
 ///
@@ -26,6 +27,8 @@ pub struct KiroAuthManager {
     storage: Option<Arc<dyn TokenStorage>>,
     /// Provider identifier for storage.
     provider: String,
+    /// Reloads credentials refreshed out-of-band by kiro-cli or the IDE.
+    watcher: Option<CredentialWatcher>,
 }
 
 impl KiroAuthManager {
@@ -37,6 +40,7 @@ impl KiroAuthManager {
             fingerprint: constants::machine_fingerprint(),
             storage: None,
             provider: "kiro".to_string(),
+            watcher: None,
         }
     }
 
@@ -48,6 +52,7 @@ impl KiroAuthManager {
             fingerprint: constants::machine_fingerprint(),
             storage: None,
             provider: "kiro".to_string(),
+            watcher: None,
         }
     }
 
@@ -57,6 +62,13 @@ impl KiroAuthManager {
         self
     }
 
+    /// Watch the credentials file or SQLite database for out-of-band
+    /// refreshes, checking at most once per `interval`.
+    pub fn with_credential_watcher(mut self, interval: std::time::Duration) -> Self {
+        self.watcher = Some(CredentialWatcher::new(interval));
+        self
+    }
+
     /// Set the HTTP client (useful for testing or custom TLS config).
     pub fn with_client(mut self, client: reqwest::Client) -> Self {
         self.client = client;
@@ -92,6 +104,8 @@ impl KiroAuthManager {
     /// 2. Refreshes proactively if within the threshold
     /// 3. Returns the current access token
     pub async fn get_access_token(&self) -> Result<String> {
+        self.reload_if_changed(false).await;
+
         // First, try to get a valid cached token
         {
             let token = self.token.read().await;
@@ -155,8 +169,28 @@ impl KiroAuthManager {
         Ok(false)
     }
 
+    /// Replace the token with the one in its source if the source changed.
+    /// Returns true when credentials were reloaded.
+    async fn reload_if_changed(&self, force: bool) -> bool {
+        let Some(watcher) = &self.watcher else {
+            return false;
+        };
+        let Some(current) = self.token_info().await else {
+            return false;
+        };
+        let Some(reloaded) = watcher.poll(&current, force) else {
+            return false;
+        };
+        info!(source = %reloaded.source, "Reloaded credentials refreshed out-of-band");
+        *self.token.write().await = Some(reloaded);
+        true
+    }
+
     /// Refresh the token using the appropriate endpoint.
     async fn refresh(&self) -> Result<()> {
+        // kiro-cli may already have refreshed (and rotated) the token.
+        self.reload_if_changed(true).await;
+
         let mut token = self.token.write().await;
         let info = token.as_ref().ok_or(Error::NotAuthenticated)?;
 
@@ -232,6 +266,7 @@ impl std::fmt::Debug for KiroAuthManager {
             .field("fingerprint", &self.fingerprint)
             .field("provider", &self.provider)
             .field("has_storage", &self.storage.is_some())
+            .field("watching", &self.watcher.is_some())
             .finish()
     }
 }
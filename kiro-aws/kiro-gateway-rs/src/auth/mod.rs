@@ -1,11 +1,14 @@
 //! Authentication for the Kiro API.
 //!
-//! Handles token lifecycle: credential loading, refresh, caching.
+//! Handles token lifecycle: credential loading, refresh, caching, and
+//! reloading credentials refreshed out-of-band.
 
 pub mod aws_sso_oidc;
 pub mod constants;
 pub mod credentials;
 pub mod kiro_desktop;
 pub mod manager;
+pub mod watcher;
 
 pub use manager::KiroAuthManager;
+pub use watcher::CredentialWatcher;
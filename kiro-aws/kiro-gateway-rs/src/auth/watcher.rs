@@ -0,0 +1,179 @@
+//! Pick up credentials refreshed out-of-band.
+//!
+//! kiro-cli and the Kiro IDE refresh their tokens on their own schedule and
+//! may rotate the refresh token, which invalidates the copy held in memory.
+//! The watcher polls the modification time of the JSON file or SQLite
+//! database the credentials were loaded from, and reloads them when it
+//! changes.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{debug, warn};
+
+use crate::models::auth::{CredentialSource, KiroTokenInfo};
+
+use super::credentials;
+
+/// Modification times of the files backing a credential source.
+type Stamp = Vec<Option<SystemTime>>;
+
+/// Polls the credential source of a token for changes.
+#[derive(Debug)]
+pub struct CredentialWatcher {
+    interval: Duration,
+    state: Mutex<WatchState>,
+}
+
+#[derive(Debug, Default)]
+struct WatchState {
+    source: Option<CredentialSource>,
+    stamp: Stamp,
+    last_check: Option<Instant>,
+}
+
+impl CredentialWatcher {
+    /// Create a watcher that checks the source at most once per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            state: Mutex::new(WatchState::default()),
+        }
+    }
+
+    /// Check interval.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Return freshly loaded credentials if the source of `current` changed
+    /// since the last check. Unless `force` is set, checks are skipped until
+    /// the interval has passed.
+    ///
+    /// The region and, when the source has none, the profile ARN of
+    /// `current` are kept, since they may come from client configuration.
+    pub fn poll(&self, current: &KiroTokenInfo, force: bool) -> Option<KiroTokenInfo> {
+        let paths = source_paths(&current.source)?;
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        // First sight of this source: remember its state, nothing to reload.
+        if state.source.as_ref() != Some(&current.source) {
+            state.source = Some(current.source.clone());
+            state.stamp = stamp(&paths);
+            state.last_check = Some(Instant::now());
+            return None;
+        }
+
+        let due = state
+            .last_check
+            .is_none_or(|t| t.elapsed() >= self.interval);
+        if !force && !due {
+            return None;
+        }
+        state.last_check = Some(Instant::now());
+
+        let new_stamp = stamp(&paths);
+        if new_stamp == state.stamp {
+            return None;
+        }
+
+        debug!(source = %current.source, "Credential source changed, reloading");
+        let loaded = match &current.source {
+            CredentialSource::JsonFile(path) => credentials::load_from_json_file(path),
+            CredentialSource::SqliteDb(path) => credentials::load_from_sqlite(path),
+            _ => return None,
+        };
+        match loaded {
+            Ok(mut token) => {
+                // Only mark the change as seen once it loaded, so a file
+                // caught mid-write is read again on the next check.
+                state.stamp = new_stamp;
+                token.region = current.region.clone();
+                if token.profile_arn.is_none() {
+                    token.profile_arn = current.profile_arn.clone();
+                }
+                Some(token)
+            }
+            Err(e) => {
+                warn!(source = %current.source, error = %e, "Failed to reload changed credentials");
+                None
+            }
+        }
+    }
+}
+
+/// Files whose modification reveals new credentials. SQLite writes may only
+/// touch the write-ahead log until the next checkpoint.
+fn source_paths(source: &CredentialSource) -> Option<Vec<PathBuf>> {
+    match source {
+        CredentialSource::JsonFile(path) => Some(vec![PathBuf::from(path)]),
+        CredentialSource::SqliteDb(path) => Some(vec![
+            PathBuf::from(path),
+            PathBuf::from(format!("{path}-wal")),
+        ]),
+        CredentialSource::Direct | CredentialSource::Environment => None,
+    }
+}
+
+fn stamp(paths: &[PathBuf]) -> Stamp {
+    paths
+        .iter()
+        .map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_creds(path: &std::path::Path, refresh_token: &str, modified: SystemTime) {
+        let body = serde_json::json!({
+            "refreshToken": refresh_token,
+            "accessToken": format!("access-{refresh_token}"),
+            "expiresAt": "2099-01-01T00:00:00Z",
+        });
+        std::fs::write(path, body.to_string()).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reloads_changed_json_file() {
+        let path = std::env::temp_dir().join(format!("kiro-watch-{}.json", uuid::Uuid::new_v4()));
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        write_creds(&path, "first", t0);
+
+        let mut current = credentials::load_from_json_file(path.to_str().unwrap()).unwrap();
+        current.region = "eu-central-1".to_string();
+        let watcher = CredentialWatcher::new(Duration::from_secs(3600));
+
+        // The first check only records the source.
+        assert!(watcher.poll(&current, true).is_none());
+        assert!(watcher.poll(&current, true).is_none());
+
+        write_creds(&path, "second", t0 + Duration::from_secs(10));
+        // Not due yet without force.
+        assert!(watcher.poll(&current, false).is_none());
+
+        let reloaded = watcher.poll(&current, true).expect("changed file is reloaded");
+        assert_eq!(reloaded.refresh_token, "second");
+        assert_eq!(reloaded.access_token, "access-second");
+        assert_eq!(reloaded.region, "eu-central-1");
+        assert!(watcher.poll(&current, true).is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_ignores_sources_without_files() {
+        let watcher = CredentialWatcher::new(Duration::ZERO);
+        let token = KiroTokenInfo::new("refresh".to_string());
+        assert!(watcher.poll(&token, true).is_none());
+        assert!(watcher.poll(&token, true).is_none());
+    }
+}
@@ -12,11 +12,13 @@ use crate::auth::KiroAuthManager;
 use crate::convert::model_resolver::ModelResolver;
 use crate::convert::request::build_kiro_payload;
 use crate::convert::response::ResponseAccumulator;
+use crate::convert::tokenizer::count_request_tokens;
 use crate::error::{Error, Result};
 use crate::models::request::MessagesRequest;
 use crate::models::response::MessagesResponse;
 use crate::models::stream::StreamEvent;
 use crate::transport::http::KiroHttpClient;
+use crate::transport::retry::RetryConfig;
 use crate::transport::sse;
 
 /// Kiro API client.
@@ -62,6 +64,15 @@ impl KiroClient {
         MessagesRequestBuilder::new(self)
     }
 
+    /// Estimate the input tokens of a Messages API request.
+    ///
+    /// The Kiro API has no token counting endpoint, so this is a local
+    /// approximation (see [`crate::convert::tokenizer`]) meant for checking
+    /// requests against a context window before sending them.
+    pub fn count_tokens(&self, request: &MessagesRequest) -> u64 {
+        count_request_tokens(request)
+    }
+
     /// Send a Messages API request and get a complete response.
     pub async fn send_messages(&self, request: MessagesRequest) -> Result<MessagesResponse> {
         let model_id = self.model_resolver.resolve(&request.model);
@@ -69,15 +80,15 @@ impl KiroClient {
         let profile_arn = self.auth.profile_arn().await;
 
         let payload = build_kiro_payload(&request, &model_id, profile_arn.as_deref())?;
-        let url =
-            crate::config::generate_assistant_response_url(&region, profile_arn.as_deref())?;
+        let url = crate::config::generate_assistant_response_url(&region, profile_arn.as_deref())?;
 
         debug!(model = model_id.as_str(), "Sending Messages request");
 
         let response = self.http.post_streaming(&url, &payload).await?;
-        let body = response.text().await.map_err(|e| {
-            Error::Stream(format!("Failed to read response body: {}", e))
-        })?;
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Error::Stream(format!("Failed to read response body: {}", e)))?;
 
         // Parse the streaming response into a complete response
         let mut accumulator = ResponseAccumulator::new(&model_id);
@@ -99,10 +110,12 @@ impl KiroClient {
         let profile_arn = self.auth.profile_arn().await;
 
         let payload = build_kiro_payload(&request, &model_id, profile_arn.as_deref())?;
-        let url =
-            crate::config::generate_assistant_response_url(&region, profile_arn.as_deref())?;
+        let url = crate::config::generate_assistant_response_url(&region, profile_arn.as_deref())?;
 
-        debug!(model = model_id.as_str(), "Sending streaming Messages request");
+        debug!(
+            model = model_id.as_str(),
+            "Sending streaming Messages request"
+        );
 
         let response = self.http.post_streaming(&url, &payload).await?;
         let model_id_owned = model_id.clone();
@@ -206,6 +219,8 @@ pub struct KiroClientBuilder {
     profile_arn: Option<String>,
     storage: Option<Arc<dyn crate::storage::TokenStorage>>,
     reqwest_client: Option<reqwest::Client>,
+    retry: RetryConfig,
+    watch_interval: Option<std::time::Duration>,
 }
 
 impl KiroClientBuilder {
@@ -219,6 +234,8 @@ impl KiroClientBuilder {
             profile_arn: None,
             storage: None,
             reqwest_client: None,
+            retry: RetryConfig::default(),
+            watch_interval: Some(crate::config::CREDENTIALS_WATCH_INTERVAL),
         }
     }
 
@@ -264,6 +281,19 @@ impl KiroClientBuilder {
         self
     }
 
+    /// Set the retry policy for API requests (default: [`RetryConfig::default`]).
+    pub fn retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// How often to check the credentials file or SQLite database for tokens
+    /// refreshed by kiro-cli or the Kiro IDE; `None` disables the check.
+    pub fn watch_credentials(mut self, interval: Option<std::time::Duration>) -> Self {
+        self.watch_interval = interval;
+        self
+    }
+
     /// Build the client, loading and validating credentials.
     pub async fn build(self) -> Result<KiroClient> {
         let token_info = self.load_credentials()?;
@@ -275,9 +305,12 @@ impl KiroClientBuilder {
         if let Some(client) = &self.reqwest_client {
             auth_manager = auth_manager.with_client(client.clone());
         }
+        if let Some(interval) = self.watch_interval {
+            auth_manager = auth_manager.with_credential_watcher(interval);
+        }
 
         let auth = Arc::new(auth_manager);
-        let http = Arc::new(KiroHttpClient::new(Arc::clone(&auth)));
+        let http = Arc::new(KiroHttpClient::new(Arc::clone(&auth)).with_retry(self.retry));
         let model_resolver = Arc::new(ModelResolver::new());
 
         info!("KiroClient initialized");
@@ -8,6 +8,10 @@ pub const DEFAULT_REGION: &str = "us-east-1";
 /// Token refresh threshold - refresh when token expires within this window.
 pub const TOKEN_REFRESH_THRESHOLD: Duration = Duration::from_secs(600); // 10 minutes
 
+/// How often to check the credentials file or SQLite database for
+/// out-of-band refreshes.
+pub const CREDENTIALS_WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
 /// Safety margin for token expiry checks.
 pub const EXPIRY_SAFETY_MARGIN: Duration = Duration::from_secs(60);
 
@@ -17,6 +21,12 @@ pub const MAX_RETRIES: u32 = 3;
 /// Base delay between retry attempts (exponential backoff: delay * 2^attempt).
 pub const BASE_RETRY_DELAY: Duration = Duration::from_secs(1);
 
+/// Upper bound of a single backoff delay.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Total time budget for retrying one request.
+pub const MAX_RETRY_ELAPSED: Duration = Duration::from_secs(90);
+
 /// Timeout for first token in streaming responses.
 pub const FIRST_TOKEN_TIMEOUT: Duration = Duration::from_secs(15);
 
@@ -5,5 +5,6 @@ pub mod model_resolver;
 pub mod request;
 pub mod response;
 pub mod schema;
+pub mod tokenizer;
 
 pub use model_resolver::ModelResolver;
@@ -0,0 +1,128 @@
+//! Token counting for Messages API requests.
+//!
+//! Anthropic does not publish the Claude tokenizer and the Kiro API has no
+//! token counting endpoint, so requests are counted with the Claude
+//! approximation of `gaud-tokenizer`: `cl100k_base` scaled up by 15%, plus
+//! fixed amounts per message and image. gaud counts its Kiro requests with
+//! the same estimator, so both agree on a request's size.
+
+use gaud_tokenizer::{TextCounter, Tokenizer, IMAGE_TOKENS, MESSAGE_TOKENS};
+
+use crate::models::request::{ContentBlock, MessageContent, MessagesRequest, ToolResultContent};
+
+/// Estimate the input tokens of a request.
+pub fn count_request_tokens(request: &MessagesRequest) -> u64 {
+    let mut text = TextCounter::new();
+    let mut fixed = 0;
+
+    if let Some(system) = &request.system {
+        text.push(&system.text());
+    }
+
+    for tool in request.tools.iter().flatten() {
+        text.push(&tool.name);
+        if let Some(description) = &tool.description {
+            text.push(description);
+        }
+        text.push(&tool.input_schema.to_string());
+    }
+
+    for message in &request.messages {
+        fixed += MESSAGE_TOKENS;
+        match &message.content {
+            MessageContent::Text(t) => text.push(t),
+            MessageContent::Blocks(blocks) => {
+                for block in blocks {
+                    fixed += push_block(&mut text, block);
+                }
+            }
+        }
+    }
+
+    text.count(Tokenizer::Claude) + fixed
+}
+
+/// Add the text of `block` to `text`; returns its fixed tokens.
+fn push_block(text: &mut TextCounter, block: &ContentBlock) -> u64 {
+    match block {
+        ContentBlock::Text { text: t } => text.push(t),
+        ContentBlock::Image { .. } => return IMAGE_TOKENS,
+        ContentBlock::ToolUse { name, input, .. } => {
+            text.push(name);
+            text.push(&input.to_string());
+        }
+        ContentBlock::ToolResult { content, .. } => match content {
+            ToolResultContent::Text(t) => text.push(t),
+            ToolResultContent::Blocks(blocks) => {
+                return blocks.iter().map(|b| push_block(text, b)).sum();
+            }
+        },
+        ContentBlock::Thinking { thinking } => text.push(thinking),
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::request::{ImageSource, Message, Role, SystemPrompt, Tool};
+
+    #[test]
+    fn test_empty_request() {
+        assert_eq!(count_request_tokens(&MessagesRequest::new("auto", 1024)), 0);
+    }
+
+    #[test]
+    fn test_count_request_tokens() {
+        let mut request = MessagesRequest::new("claude-sonnet-4.5", 1024);
+        request.messages.push(Message {
+            role: Role::User,
+            content: MessageContent::Text("hello world".to_string()),
+        });
+        let user_only = Tokenizer::Claude.count("hello world") + MESSAGE_TOKENS;
+        assert_eq!(count_request_tokens(&request), user_only);
+
+        request.system = Some(SystemPrompt::Text("You are terse.".to_string()));
+        assert_eq!(
+            count_request_tokens(&request),
+            Tokenizer::Claude.count("You are terse.\nhello world") + MESSAGE_TOKENS
+        );
+    }
+
+    #[test]
+    fn test_tools_and_images_add_tokens() {
+        let mut request = MessagesRequest::new("auto", 1024);
+        request.messages.push(Message {
+            role: Role::User,
+            content: MessageContent::Blocks(vec![ContentBlock::Text {
+                text: "Describe".to_string(),
+            }]),
+        });
+        let text_only = count_request_tokens(&request);
+
+        request.messages[0].content = MessageContent::Blocks(vec![
+            ContentBlock::Text {
+                text: "Describe".to_string(),
+            },
+            ContentBlock::Image {
+                source: ImageSource {
+                    source_type: "base64".to_string(),
+                    media_type: "image/png".to_string(),
+                    data: "iVBORw0KGgo".to_string(),
+                },
+            },
+        ]);
+        let with_image = count_request_tokens(&request);
+        assert_eq!(with_image, text_only + IMAGE_TOKENS);
+
+        request.tools = Some(vec![Tool {
+            name: "get_weather".to_string(),
+            description: Some("Get the weather for a city".to_string()),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": { "city": { "type": "string" } }
+            }),
+        }]);
+        assert!(count_request_tokens(&request) > with_image);
+    }
+}
@@ -56,3 +56,4 @@ pub use models::request::{
 pub use models::response::{MessagesResponse, ResponseContentBlock, StopReason, Usage};
 pub use models::stream::{ContentDelta, MessageDelta, StreamEvent};
 pub use storage::TokenStorage;
+pub use transport::retry::RetryConfig;
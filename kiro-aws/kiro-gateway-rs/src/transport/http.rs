@@ -4,9 +4,10 @@ use std::time::Duration;
 use tracing::{debug, info, warn};
 
 use crate::auth::KiroAuthManager;
-use crate::config::{BASE_RETRY_DELAY, CONNECT_TIMEOUT, MAX_RETRIES, REQUEST_TIMEOUT};
+use crate::config::{CONNECT_TIMEOUT, REQUEST_TIMEOUT};
 use crate::error::{Error, Result};
 use crate::transport::headers;
+use crate::transport::retry::{is_retryable_error, is_retryable_status, RetryConfig, RetryState};
 
 /// HTTP client for Kiro API with retry and refresh logic.
 pub struct KiroHttpClient {
@@ -14,6 +15,7 @@ pub struct KiroHttpClient {
     /// Persistent client for streaming requests (no default timeout).
     stream_client: reqwest::Client,
     auth: std::sync::Arc<KiroAuthManager>,
+    retry: RetryConfig,
 }
 
 impl KiroHttpClient {
@@ -30,7 +32,12 @@ impl KiroHttpClient {
             .build()
             .expect("Failed to build streaming HTTP client");
 
-        Self { client, stream_client, auth }
+        Self {
+            client,
+            stream_client,
+            auth,
+            retry: RetryConfig::default(),
+        }
     }
 
     /// Create with a custom reqwest client.
@@ -40,194 +47,157 @@ impl KiroHttpClient {
             .build()
             .expect("Failed to build streaming HTTP client");
 
-        Self { client, stream_client, auth }
+        Self {
+            client,
+            stream_client,
+            auth,
+            retry: RetryConfig::default(),
+        }
+    }
+
+    /// Use a different retry policy.
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The retry policy applied to every request.
+    pub fn retry_config(&self) -> &RetryConfig {
+        &self.retry
     }
 
     /// Send a POST request with automatic retry and token refresh.
     ///
-    /// Retry strategy:
-    /// - 403 Forbidden: Force token refresh, then retry
-    /// - 429 Too Many Requests: Exponential backoff
-    /// - 5xx Server Error: Exponential backoff
+    /// Retry strategy (see [`RetryConfig`]):
+    /// - 403 Forbidden: Force token refresh, then retry once
+    /// - 429 Too Many Requests: `Retry-After`, else backoff with jitter
+    /// - 5xx, timeouts and dropped connections: Backoff with jitter
     pub async fn post_with_retry(
         &self,
         url: &str,
         body: &serde_json::Value,
     ) -> Result<reqwest::Response> {
-        let mut last_error = None;
-
-        for attempt in 0..MAX_RETRIES {
-            if attempt > 0 {
-                let delay = BASE_RETRY_DELAY * 2u32.pow(attempt - 1);
-                debug!(attempt, delay_ms = delay.as_millis(), "Retrying request");
-                tokio::time::sleep(delay).await;
-            }
-
-            let token = self.auth.get_access_token().await?;
-            let fingerprint = self.auth.fingerprint();
-            let hdrs = headers::kiro_api_headers(&token, fingerprint);
-
-            match self
-                .client
+        self.send_with_retry(|token, fingerprint| {
+            self.client
                 .post(url)
-                .headers(hdrs)
+                .headers(headers::kiro_api_headers(token, fingerprint))
                 .json(body)
-                .send()
-                .await
-            {
-                Ok(response) => {
-                    let status = response.status().as_u16();
-
-                    if response.status().is_success() {
-                        return Ok(response);
-                    }
-
-                    match status {
-                        403 => {
-                            warn!("Got 403 - refreshing token and retrying");
-                            if let Err(e) = self.auth.force_refresh().await {
-                                warn!("Token refresh failed: {}", e);
-                            }
-                            last_error = Some(Error::Api {
-                                status,
-                                message: "Forbidden - token may be expired".into(),
-                            });
-                        }
-                        429 => {
-                            let retry_after = response
-                                .headers()
-                                .get("retry-after")
-                                .and_then(|v| v.to_str().ok())
-                                .and_then(|v| v.parse::<u64>().ok())
-                                .map(Duration::from_secs);
-
-                            if let Some(delay) = retry_after {
-                                info!(delay_secs = delay.as_secs(), "Rate limited, waiting");
-                                tokio::time::sleep(delay).await;
-                            }
-
-                            last_error = Some(Error::RateLimited { retry_after });
-                        }
-                        500..=599 => {
-                            let body_text = response.text().await.unwrap_or_default();
-                            warn!(status, body = body_text.as_str(), "Server error, retrying");
-                            last_error = Some(Error::Api {
-                                status,
-                                message: body_text,
-                            });
-                        }
-                        _ => {
-                            let body_text = response.text().await.unwrap_or_default();
-                            return Err(Error::Api {
-                                status,
-                                message: body_text,
-                            });
-                        }
-                    }
-                }
-                Err(e) => {
-                    if e.is_timeout() {
-                        warn!("Request timed out (attempt {})", attempt + 1);
-                        last_error = Some(Error::Timeout);
-                    } else {
-                        warn!("Request failed (attempt {}): {}", attempt + 1, e);
-                        last_error = Some(Error::Network(e));
-                    }
-                }
-            }
-        }
-
-        Err(Error::RetriesExhausted {
-            attempts: MAX_RETRIES,
-            message: last_error
-                .map(|e| e.to_string())
-                .unwrap_or_else(|| "Unknown error".into()),
         })
+        .await
     }
 
     /// Send a streaming POST request. Returns the response for stream processing.
     ///
     /// Uses Connection: close to prevent CLOSE_WAIT socket leak.
-    /// Only retries on 403 (token refresh). Other errors are not retried for streaming.
+    /// Retries follow the same policy as [`Self::post_with_retry`] until a
+    /// successful response arrives; the stream itself is never retried.
     pub async fn post_streaming(
         &self,
         url: &str,
         body: &serde_json::Value,
     ) -> Result<reqwest::Response> {
-        for attempt in 0..2 {
-            let token = self.auth.get_access_token().await?;
-            let fingerprint = self.auth.fingerprint();
-            let hdrs = headers::kiro_streaming_headers(&token, fingerprint);
-
-            let response = self.stream_client
+        self.send_with_retry(|token, fingerprint| {
+            self.stream_client
                 .post(url)
-                .headers(hdrs)
+                .headers(headers::kiro_streaming_headers(token, fingerprint))
                 .json(body)
-                .send()
-                .await
-                .map_err(|e| {
-                    if e.is_timeout() {
-                        Error::Timeout
-                    } else {
-                        Error::Network(e)
-                    }
-                })?;
-
-            let status = response.status().as_u16();
-
-            if response.status().is_success() {
-                return Ok(response);
-            }
-
-            if status == 403 && attempt == 0 {
-                warn!("Got 403 on stream - refreshing token and retrying");
-                self.auth.force_refresh().await?;
-                continue;
-            }
-
-            let body_text = response.text().await.unwrap_or_default();
-            return Err(Error::Api {
-                status,
-                message: body_text,
-            });
-        }
-
-        Err(Error::RetriesExhausted {
-            attempts: 2,
-            message: "Streaming request failed".into(),
         })
+        .await
     }
 
     /// Send a GET request (for ListAvailableModels, etc.).
     pub async fn get(&self, url: &str) -> Result<reqwest::Response> {
-        let token = self.auth.get_access_token().await?;
-        let fingerprint = self.auth.fingerprint();
-        let hdrs = headers::kiro_api_headers(&token, fingerprint);
-
-        let response = self
-            .client
-            .get(url)
-            .headers(hdrs)
-            .send()
-            .await
-            .map_err(|e| {
-                if e.is_timeout() {
-                    Error::Timeout
-                } else {
-                    Error::Network(e)
+        self.send_with_retry(|token, fingerprint| {
+            self.client
+                .get(url)
+                .headers(headers::kiro_api_headers(token, fingerprint))
+        })
+        .await
+    }
+
+    /// Send the request built by `build` until it succeeds, fails with a
+    /// non-retryable error, or the retry policy gives up.
+    async fn send_with_retry<F>(&self, build: F) -> Result<reqwest::Response>
+    where
+        F: Fn(&str, &str) -> reqwest::RequestBuilder,
+    {
+        let mut state = RetryState::new(&self.retry);
+        let mut refreshed = false;
+
+        loop {
+            let token = self.auth.get_access_token().await?;
+            let fingerprint = self.auth.fingerprint();
+
+            let (error, retry_after) = match build(&token, fingerprint).send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    if status == 403 && !refreshed {
+                        warn!("Got 403 - refreshing token and retrying");
+                        refreshed = true;
+                        if let Err(e) = self.auth.force_refresh().await {
+                            warn!("Token refresh failed: {}", e);
+                        }
+                        // A token refresh does not count against the retry budget.
+                        continue;
+                    } else if status == 429 {
+                        let retry_after = response
+                            .headers()
+                            .get("retry-after")
+                            .and_then(|v| v.to_str().ok())
+                            .and_then(|v| v.parse::<u64>().ok())
+                            .map(Duration::from_secs);
+                        info!(retry_after_secs = retry_after.map(|d| d.as_secs()), "Rate limited");
+                        (Error::RateLimited { retry_after }, retry_after)
+                    } else if is_retryable_status(status) {
+                        let body_text = response.text().await.unwrap_or_default();
+                        warn!(status, body = body_text.as_str(), "Server error");
+                        let error = Error::Api {
+                            status,
+                            message: body_text,
+                        };
+                        (error, None)
+                    } else {
+                        let body_text = response.text().await.unwrap_or_default();
+                        return Err(Error::Api {
+                            status,
+                            message: body_text,
+                        });
+                    }
+                }
+                Err(e) => {
+                    let retryable = is_retryable_error(&e);
+                    warn!(attempt = state.attempts() + 1, retryable, "Request failed: {}", e);
+                    let error = if e.is_timeout() {
+                        Error::Timeout
+                    } else {
+                        Error::Network(e)
+                    };
+                    if !retryable {
+                        return Err(error);
+                    }
+                    (error, None)
                 }
-            })?;
-
-        if !response.status().is_success() {
-            let status = response.status().as_u16();
-            let body_text = response.text().await.unwrap_or_default();
-            return Err(Error::Api {
-                status,
-                message: body_text,
-            });
+            };
+
+            match state.next_delay(retry_after) {
+                Some(delay) => {
+                    debug!(
+                        attempt = state.attempts() + 1,
+                        delay_ms = delay.as_millis(),
+                        "Retrying request"
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                None if state.attempts() == 1 => return Err(error),
+                None => {
+                    return Err(Error::RetriesExhausted {
+                        attempts: state.attempts(),
+                        message: error.to_string(),
+                    });
+                }
+            }
         }
-
-        Ok(response)
     }
 }
 
@@ -235,6 +205,7 @@ impl std::fmt::Debug for KiroHttpClient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("KiroHttpClient")
             .field("auth", &self.auth)
+            .field("retry", &self.retry)
             .finish()
     }
 }
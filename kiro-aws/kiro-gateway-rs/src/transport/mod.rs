@@ -2,4 +2,5 @@
 
 pub mod headers;
 pub mod http;
+pub mod retry;
 pub mod sse;
@@ -0,0 +1,244 @@
+//! Retry policy for Kiro API requests.
+//!
+//! Kiro's backend throttles aggressively, so every request goes through the
+//! same policy: 429, 5xx, timeouts and dropped connections are retried with
+//! exponential backoff and jitter, bounded by an attempt count and a total
+//! elapsed time. A `Retry-After` header replaces the computed backoff.
+
+use std::time::{Duration, Instant};
+
+use crate::config::{BASE_RETRY_DELAY, MAX_RETRIES, MAX_RETRY_DELAY, MAX_RETRY_ELAPSED};
+
+/// Retry and backoff settings.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use kiro_gateway::transport::retry::RetryConfig;
+///
+/// let retry = RetryConfig::default()
+///     .max_attempts(5)
+///     .max_elapsed(Some(Duration::from_secs(120)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Total attempts per request, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every further retry.
+    pub base_delay: Duration,
+    /// Upper bound of a computed backoff delay.
+    pub max_delay: Duration,
+    /// Give up once the next retry would start later than this after the
+    /// first attempt. `None` only limits the attempt count.
+    pub max_elapsed: Option<Duration>,
+    /// Randomize each delay between half and the full backoff, so that
+    /// concurrent clients do not retry in lockstep.
+    pub jitter: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: MAX_RETRIES,
+            base_delay: BASE_RETRY_DELAY,
+            max_delay: MAX_RETRY_DELAY,
+            max_elapsed: Some(MAX_RETRY_ELAPSED),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Set the total number of attempts (at least 1).
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set the upper bound of a computed backoff delay.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Set the total time budget for retries.
+    pub fn max_elapsed(mut self, elapsed: Option<Duration>) -> Self {
+        self.max_elapsed = elapsed;
+        self
+    }
+
+    /// Enable or disable jitter.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Backoff before retry number `retry` (1 for the first retry).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = retry.saturating_sub(1).min(16);
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << exp)
+            .min(self.max_delay);
+        if self.jitter {
+            let half = delay / 2;
+            half + half.mul_f64(random_fraction())
+        } else {
+            delay
+        }
+    }
+}
+
+/// Whether a response status is worth retrying.
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..=599).contains(&status)
+}
+
+/// Whether a transport error is worth retrying: timeouts, failed connects
+/// and connections reset or closed by the peer.
+pub fn is_retryable_error(err: &reqwest::Error) -> bool {
+    if err.is_timeout() || err.is_connect() {
+        return true;
+    }
+    let mut source = std::error::Error::source(err);
+    while let Some(e) = source {
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// Attempt bookkeeping for one request.
+#[derive(Debug)]
+pub(crate) struct RetryState<'a> {
+    config: &'a RetryConfig,
+    attempts: u32,
+    started: Instant,
+}
+
+impl<'a> RetryState<'a> {
+    pub(crate) fn new(config: &'a RetryConfig) -> Self {
+        Self {
+            config,
+            attempts: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Attempts made so far.
+    pub(crate) fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Record a failed attempt and return how long to wait before the next
+    /// one, or `None` when the attempts or the time budget are used up.
+    pub(crate) fn next_delay(&mut self, retry_after: Option<Duration>) -> Option<Duration> {
+        self.attempts += 1;
+        if self.attempts >= self.config.max_attempts {
+            return None;
+        }
+        let delay = retry_after.unwrap_or_else(|| self.config.backoff(self.attempts));
+        if let Some(max) = self.config.max_elapsed {
+            if self.started.elapsed() + delay > max {
+                return None;
+            }
+        }
+        Some(delay)
+    }
+}
+
+/// A random number in `[0, 1)`.
+fn random_fraction() -> f64 {
+    let (bits, _) = uuid::Uuid::new_v4().as_u64_pair();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let config = RetryConfig::default()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500))
+            .jitter(false);
+        assert_eq!(config.backoff(1), Duration::from_millis(100));
+        assert_eq!(config.backoff(2), Duration::from_millis(200));
+        assert_eq!(config.backoff(3), Duration::from_millis(400));
+        assert_eq!(config.backoff(4), Duration::from_millis(500));
+        assert_eq!(config.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_within_bounds() {
+        let config = RetryConfig::default().base_delay(Duration::from_millis(1000));
+        for _ in 0..100 {
+            let delay = config.backoff(1);
+            assert!(delay >= Duration::from_millis(500));
+            assert!(delay <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn test_retryable_status() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(500));
+        assert!(is_retryable_status(503));
+        assert!(!is_retryable_status(400));
+        assert!(!is_retryable_status(403));
+        assert!(!is_retryable_status(404));
+    }
+
+    #[test]
+    fn test_state_limits_attempts() {
+        let config = RetryConfig::default()
+            .max_attempts(3)
+            .base_delay(Duration::from_millis(1))
+            .jitter(false);
+        let mut state = RetryState::new(&config);
+        assert_eq!(state.next_delay(None), Some(Duration::from_millis(1)));
+        assert_eq!(state.next_delay(None), Some(Duration::from_millis(2)));
+        assert_eq!(state.next_delay(None), None);
+        assert_eq!(state.attempts(), 3);
+
+        let none = RetryConfig::none();
+        assert_eq!(RetryState::new(&none).next_delay(None), None);
+    }
+
+    #[test]
+    fn test_state_honors_retry_after_and_elapsed_budget() {
+        let config = RetryConfig::default()
+            .max_attempts(10)
+            .max_elapsed(Some(Duration::from_secs(30)));
+        let mut state = RetryState::new(&config);
+        assert_eq!(
+            state.next_delay(Some(Duration::from_secs(5))),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(state.next_delay(Some(Duration::from_secs(60))), None);
+    }
+}
@@ -1,12 +1,36 @@
+use std::collections::BTreeMap;
+
 use axum::Extension;
 use axum::Json;
 use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, header};
+use axum::response::{IntoResponse, Response};
+use chrono::Timelike;
 use serde::{Deserialize, Serialize};
 
 use crate::AppState;
 use crate::auth::AuthUser;
-use crate::auth::users;
+use crate::auth::purge::{self, PurgeReport, PurgeTargets};
+use crate::auth::throttle::ClientIp;
+use crate::auth::{audit_log, export, totp, users};
+use crate::budget::attribution::{self, parse_tags};
+use crate::budget::chargeback;
+use crate::budget::quota::{QuotaScope, RateLimits};
+use crate::budget::reconcile::{self, ReconciliationEntry};
+use crate::budget::rollup::{self, TimeseriesQuery, TimeseriesResponse};
+use crate::budget::scheduler::{Priority, QueueReport};
 use crate::error::AppError;
+use crate::experiments::{self, Experiment, VariantStats};
+use crate::files::FileStore;
+use crate::providers::cost::{self, CostMarkup};
+use crate::providers::factory;
+use crate::providers::failures::FailureRecord;
+use crate::providers::pricing::{self, AudioPricing, ImagePricing, ModelPricing};
+use crate::providers::reasoning::ReasoningMode;
+use crate::providers::redact;
+use crate::providers::router::{RoutingSimulation, VIRTUAL_MODEL_PREFIX};
+use crate::providers::rules::{RequestFacts, RoutingRule};
+use crate::settings;
 
 // ---------------------------------------------------------------------------
 // Request / response types
@@ -16,12 +40,78 @@ use crate::error::AppError;
 pub struct CreateUserRequest {
     pub name: String,
     pub role: String,
+    /// Team for shared rate quotas.
+    #[serde(default)]
+    pub team: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateServiceAccountRequest {
+    pub name: String,
+    /// Team that owns the account.
+    pub team: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServiceAccountQuery {
+    /// Only accounts owned by this team.
+    pub team: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeQuery {
+    /// Report what would be removed without removing it.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetUserTeamRequest {
+    /// New team, or `null` to remove the user from their team.
+    pub team: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateApiKeyRequest {
     #[serde(default)]
     pub label: String,
+    /// Reasoning exposure policy; defaults to `forward`.
+    #[serde(default)]
+    pub reasoning_mode: Option<ReasoningMode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateApiKeyRequest {
+    #[serde(default)]
+    pub reasoning_mode: Option<ReasoningMode>,
+    /// Include the upstream status and body in provider errors.
+    #[serde(default)]
+    pub debug_errors: Option<bool>,
+    /// Scheduling priority; `default` follows the user's team.
+    #[serde(default)]
+    pub priority: Option<Priority>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StaleKeysQuery {
+    /// Keys unused for at least this many days are stale.
+    #[serde(default = "default_stale_days")]
+    pub days: u32,
+}
+
+fn default_stale_days() -> u32 {
+    90
+}
+
+impl StaleKeysQuery {
+    fn days(&self) -> Result<u32, AppError> {
+        if self.days == 0 {
+            return Err(AppError::BadRequest(
+                "'days' must be at least 1".to_string(),
+            ));
+        }
+        Ok(self.days)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -31,6 +121,7 @@ pub struct CreatedApiKeyResponse {
     pub key_prefix: String,
     pub label: String,
     pub created_at: String,
+    pub reasoning_mode: ReasoningMode,
     /// The full plaintext key. Shown exactly once.
     pub plaintext: String,
 }
@@ -39,20 +130,102 @@ pub struct CreatedApiKeyResponse {
 pub struct SetBudgetRequest {
     pub monthly_limit: Option<f64>,
     pub daily_limit: Option<f64>,
+    /// Cap on upstream premium requests (GitHub Copilot) per month.
+    #[serde(default)]
+    pub monthly_premium_limit: Option<f64>,
+    /// Cap on upstream premium requests per day.
+    #[serde(default)]
+    pub daily_premium_limit: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UsageQuery {
     pub user_id: Option<String>,
     pub provider: Option<String>,
+    /// Only entries attributed to this end user (OpenAI `user`).
+    pub end_user: Option<String>,
+    /// Only entries carrying all of these tags (`project=alpha,env=prod`).
+    pub tags: Option<String>,
+    /// Only entries made with this API key.
+    pub key_id: Option<String>,
+    /// Only entries from this client IP.
+    pub client_ip: Option<String>,
+    /// Only entries with this exact user agent.
+    pub user_agent: Option<String>,
+    /// Only entries for this request path (`/v1/chat/completions`).
+    pub path: Option<String>,
+    pub model: Option<String>,
+    /// Exact status, or `error` / `cache_hit` for every status of that kind.
+    pub status: Option<String>,
+    /// Case-insensitive substring of the entry's tag keys or values.
+    pub search: Option<String>,
+    pub min_cost: Option<f64>,
+    pub max_cost: Option<f64>,
+    pub min_latency_ms: Option<u64>,
+    pub max_latency_ms: Option<u64>,
     pub from: Option<String>,
     pub to: Option<String>,
+    /// `created_at` (default), `cost`, `latency_ms`, `input_tokens` or
+    /// `output_tokens`.
+    pub sort: Option<String>,
+    /// `desc` (default) or `asc`.
+    pub order: Option<String>,
     #[serde(default = "default_page")]
     pub page: u32,
     #[serde(default = "default_per_page")]
     pub per_page: u32,
 }
 
+impl Default for UsageQuery {
+    fn default() -> Self {
+        Self {
+            user_id: None,
+            provider: None,
+            end_user: None,
+            tags: None,
+            key_id: None,
+            client_ip: None,
+            user_agent: None,
+            path: None,
+            model: None,
+            status: None,
+            search: None,
+            min_cost: None,
+            max_cost: None,
+            min_latency_ms: None,
+            max_latency_ms: None,
+            from: None,
+            to: None,
+            sort: None,
+            order: None,
+            page: default_page(),
+            per_page: default_per_page(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsageSummaryQuery {
+    /// `user`, `provider`, `model`, `end_user`, `key`, `client_ip`,
+    /// `user_agent`, `path` or `tag:<key>`.
+    #[serde(default = "default_group_by")]
+    pub group_by: String,
+    pub user_id: Option<String>,
+    pub provider: Option<String>,
+    pub end_user: Option<String>,
+    pub tags: Option<String>,
+    pub key_id: Option<String>,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub path: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+fn default_group_by() -> String {
+    "model".to_string()
+}
+
 fn default_page() -> u32 {
     1
 }
@@ -69,10 +242,51 @@ pub struct UsageEntry {
     pub model: String,
     pub input_tokens: i64,
     pub output_tokens: i64,
+    pub audio_seconds: f64,
+    pub premium_requests: f64,
+    /// Cost charged to the user, including any markup.
     pub cost: f64,
+    /// Provider cost before markup (absent on rows logged before markups).
+    pub raw_cost: Option<f64>,
+    /// Estimated cost saved, for requests served from the cache.
+    pub cost_saved: f64,
     pub latency_ms: i64,
     pub status: String,
     pub created_at: String,
+    /// End user the client attributed the request to.
+    pub end_user: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// Admin who sent the request on the user's behalf.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<String>,
+    /// Why the model stopped, for requests that produced a completion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    /// Experiment and variant the request was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment_variant: Option<String>,
+    /// API key the request was made with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// Client IP, user agent and path of the request (absent on rows logged
+    /// before they were recorded).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChargebackQuery {
+    /// Month (`YYYY-MM`, UTC). Defaults to the current month.
+    pub period: Option<String>,
+    /// `json` (default) or `csv`.
+    pub format: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -81,6 +295,100 @@ pub struct UsageResponse {
     pub page: u32,
     pub per_page: u32,
     pub total: i64,
+    /// Currency of `cost` and `raw_cost` (`pricing.currency`).
+    pub currency: String,
+}
+
+/// Usage totals of one group in a usage summary.
+#[derive(Debug, Serialize)]
+pub struct UsageSummaryGroup {
+    /// Value of the grouping column; `null` for entries without it (such as
+    /// entries without the tag grouped by).
+    pub key: Option<String>,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub premium_requests: f64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageSummaryResponse {
+    pub group_by: String,
+    /// Groups, most expensive first.
+    pub data: Vec<UsageSummaryGroup>,
+    pub total_cost: f64,
+    /// Currency of `cost` (`pricing.currency`).
+    pub currency: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReconciliationQuery {
+    /// First day (`YYYY-MM-DD`) to include.
+    pub from: Option<String>,
+    /// Last day (`YYYY-MM-DD`) to include.
+    pub to: Option<String>,
+    /// Only return entries whose difference exceeds the tolerance.
+    #[serde(default)]
+    pub flagged: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReconciliationResponse {
+    pub data: Vec<ReconciliationEntry>,
+    /// Number of flagged entries in `data`.
+    pub flagged: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExperimentStatsQuery {
+    /// Earliest `created_at` (`YYYY-MM-DD[ HH:MM:SS]`) to include.
+    pub from: Option<String>,
+    /// Latest `created_at` to include.
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExperimentListResponse {
+    pub experiments: Vec<Experiment>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExperimentStatsResponse {
+    pub name: String,
+    /// The `[[experiments]]` entry, absent once removed from the config.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<Experiment>,
+    /// Variants `a` and `b`, in that order.
+    pub variants: Vec<VariantStats>,
+    /// Currency of `cost` (`pricing.currency`).
+    pub currency: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PricingResponse {
+    pub models: Vec<PricingEntry>,
+    pub images: Vec<ImagePricing>,
+    pub audio: Vec<AudioPricing>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PricingEntry {
+    #[serde(flatten)]
+    pub pricing: ModelPricing,
+    /// Whether the price was set through `PUT /admin/pricing/:model`.
+    pub overridden: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPricingRequest {
+    /// Defaults to the provider of the current price, if any.
+    #[serde(default)]
+    pub provider: Option<String>,
+    pub input_cost_per_million: f64,
+    pub output_cost_per_million: f64,
+    #[serde(default)]
+    pub cached_input_cost_per_million: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -111,10 +419,45 @@ pub async fn create_user(
     Json(body): Json<CreateUserRequest>,
 ) -> Result<Json<users::User>, AppError> {
     require_admin(&user)?;
-    let created = users::create_user(&state.db, &body.name, &body.role)?;
+    let mut created = users::create_user(&state.db, &body.name, &body.role)?;
+    if let Some(team) = body.team.filter(|t| !t.is_empty()) {
+        users::set_user_team(&state.db, &created.id, Some(&team))?;
+        created.team = Some(team);
+    }
+    audit_log::record(
+        &state.db,
+        &user,
+        "user.create",
+        &created.id,
+        None,
+        Some(json_value(&created)),
+    );
     Ok(Json(created))
 }
 
+/// PUT /admin/users/:id/team
+pub async fn set_user_team(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(body): Json<SetUserTeamRequest>,
+) -> Result<Json<users::User>, AppError> {
+    require_admin(&user)?;
+    let team = body.team.as_deref().filter(|t| !t.is_empty());
+    let before = users::get_user(&state.db, &id)?;
+    users::set_user_team(&state.db, &id, team)?;
+    let after = users::get_user(&state.db, &id)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "user.team",
+        &id,
+        Some(json_value(&before)),
+        Some(json_value(&after)),
+    );
+    Ok(Json(after))
+}
+
 /// GET /admin/users
 pub async fn list_users(
     State(state): State<AppState>,
@@ -125,6 +468,43 @@ pub async fn list_users(
     Ok(Json(all))
 }
 
+/// POST /admin/service-accounts
+///
+/// Create a service account: a user for a pipeline or bot, owned by a team.
+/// Keys, budgets and quotas are managed through the user endpoints.
+pub async fn create_service_account(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<CreateServiceAccountRequest>,
+) -> Result<Json<users::User>, AppError> {
+    require_admin(&user)?;
+    let created = users::create_service_account(&state.db, &body.name, &body.team)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "service_account.create",
+        &created.id,
+        None,
+        Some(json_value(&created)),
+    );
+    Ok(Json(created))
+}
+
+/// GET /admin/service-accounts
+pub async fn list_service_accounts(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<ServiceAccountQuery>,
+) -> Result<Json<Vec<users::User>>, AppError> {
+    require_admin(&user)?;
+    let accounts = users::list_users(&state.db)?
+        .into_iter()
+        .filter(|u| u.service_account)
+        .filter(|u| params.team.is_none() || u.team == params.team)
+        .collect();
+    Ok(Json(accounts))
+}
+
 /// DELETE /admin/users/:id
 pub async fn delete_user(
     State(state): State<AppState>,
@@ -132,7 +512,106 @@ pub async fn delete_user(
     Path(id): Path<String>,
 ) -> Result<Json<DeletedResponse>, AppError> {
     require_admin(&user)?;
+    let before = users::get_user(&state.db, &id)?;
     users::delete_user(&state.db, &id)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "user.delete",
+        &id,
+        Some(json_value(&before)),
+        None,
+    );
+    Ok(Json(DeletedResponse { deleted: true }))
+}
+
+/// GET /admin/users/export
+///
+/// Users, roles, teams, budget limits and API key metadata as JSON. Key
+/// secrets and hashes are never included.
+pub async fn export_users(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<export::UserExport>, AppError> {
+    require_admin(&user)?;
+    Ok(Json(export::export(&state.db, &state.budget)?))
+}
+
+/// POST /admin/users/import
+///
+/// Apply a document from `GET /admin/users/export`. Users are matched by
+/// name; keys that do not exist here are reported, not created.
+pub async fn import_users(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<export::UserExport>,
+) -> Result<Json<export::ImportSummary>, AppError> {
+    require_admin(&user)?;
+    let summary = export::import(&state.db, &state.budget, &body)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "user.import",
+        "",
+        None,
+        Some(json_value(&summary)),
+    );
+    Ok(Json(summary))
+}
+
+/// POST /admin/users/:id/purge
+///
+/// Erase the user's usage records, stored conversations, files, cache
+/// entries and API keys. `?dry_run=true` only reports the counts.
+pub async fn purge_user(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Query(params): Query<PurgeQuery>,
+) -> Result<Json<PurgeReport>, AppError> {
+    require_admin(&user)?;
+    users::get_user(&state.db, &id)?;
+    let files = FileStore::new(state.db.clone(), state.config.files.storage_dir.as_deref());
+    let targets = PurgeTargets {
+        files: &files,
+        cache: state.cache.as_deref(),
+        tail: &state.request_tail,
+    };
+    let report = purge::purge_user(&state.db, targets, &id, params.dry_run).await?;
+    if !params.dry_run {
+        audit_log::record(
+            &state.db,
+            &user,
+            "user.purge",
+            &id,
+            None,
+            Some(json_value(&report)),
+        );
+    }
+    Ok(Json(report))
+}
+
+/// DELETE /admin/users/:id/totp
+///
+/// Turn off another user's two-factor authentication, e.g. after they lost
+/// their authenticator and recovery codes.
+pub async fn reset_totp(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<DeletedResponse>, AppError> {
+    require_admin(&user)?;
+    users::get_user(&state.db, &id)?;
+    let before = totp::status(&state.db, &id)?;
+    totp::disable(&state.db, &id)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "user.totp_reset",
+        &id,
+        Some(json_value(&before)),
+        None,
+    );
     Ok(Json(DeletedResponse { deleted: true }))
 }
 
@@ -141,14 +620,38 @@ pub async fn delete_user(
 // ---------------------------------------------------------------------------
 
 /// POST /admin/users/:id/keys
+///
+/// Admins with two-factor enabled must send a current code in `X-TOTP-Code`.
 pub async fn create_api_key(
     State(state): State<AppState>,
     Extension(user): Extension<AuthUser>,
+    ClientIp(ip): ClientIp,
     Path(id): Path<String>,
+    headers: HeaderMap,
     Json(body): Json<CreateApiKeyRequest>,
 ) -> Result<Json<CreatedApiKeyResponse>, AppError> {
     require_admin(&user)?;
-    let created = users::create_api_key(&state.db, &id, &body.label)?;
+    state.login_throttle.require_second_factor(
+        ip,
+        &user,
+        totp::header_code(&headers),
+        "admin_key_create_totp",
+    )?;
+    let mut created = users::create_api_key(&state.db, &id, &body.label)?;
+    let reasoning_mode = body.reasoning_mode.unwrap_or_default();
+    if reasoning_mode != created.info.reasoning_mode {
+        users::set_api_key_reasoning_mode(&state.db, &created.info.id, reasoning_mode)?;
+        created.info.reasoning_mode = reasoning_mode;
+    }
+    // Metadata only: the plaintext key never reaches the log.
+    audit_log::record(
+        &state.db,
+        &user,
+        "key.create",
+        &created.info.id,
+        None,
+        Some(json_value(&created.info)),
+    );
 
     Ok(Json(CreatedApiKeyResponse {
         id: created.info.id,
@@ -156,6 +659,7 @@ pub async fn create_api_key(
         key_prefix: created.info.key_prefix,
         label: created.info.label,
         created_at: created.info.created_at,
+        reasoning_mode,
         plaintext: created.plaintext,
     }))
 }
@@ -171,6 +675,96 @@ pub async fn list_api_keys(
     Ok(Json(keys))
 }
 
+/// PUT /admin/keys/:id
+pub async fn update_api_key(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateApiKeyRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&user)?;
+    let before = users::get_api_key(&state.db, &id)?;
+    if let Some(mode) = body.reasoning_mode {
+        users::set_api_key_reasoning_mode(&state.db, &id, mode)?;
+    }
+    if let Some(enabled) = body.debug_errors {
+        users::set_api_key_debug_errors(&state.db, &id, enabled)?;
+    }
+    if let Some(priority) = body.priority {
+        users::set_api_key_priority(&state.db, &id, priority)?;
+    }
+    let after = users::get_api_key(&state.db, &id)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "key.update",
+        &id,
+        Some(json_value(&before)),
+        Some(json_value(&after)),
+    );
+    Ok(Json(serde_json::json!({
+        "id": id,
+        "reasoning_mode": after.reasoning_mode,
+        "debug_errors": after.debug_errors,
+        "priority": after.priority,
+    })))
+}
+
+/// GET /admin/keys/stale
+///
+/// Enabled keys not used in the last `days` days (default 90).
+pub async fn stale_api_keys(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<StaleKeysQuery>,
+) -> Result<Json<Vec<users::StaleApiKey>>, AppError> {
+    require_admin(&user)?;
+    Ok(Json(users::stale_api_keys(&state.db, params.days()?)?))
+}
+
+/// POST /admin/keys/stale/disable
+///
+/// Disable the keys `GET /admin/keys/stale` reports, returning them.
+pub async fn disable_stale_api_keys(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<StaleKeysQuery>,
+) -> Result<Json<Vec<users::StaleApiKey>>, AppError> {
+    require_admin(&user)?;
+    let disabled = users::disable_stale_api_keys(&state.db, params.days()?)?;
+    for stale in &disabled {
+        audit_log::record(
+            &state.db,
+            &user,
+            "key.disable_stale",
+            &stale.key.id,
+            None,
+            Some(json_value(stale)),
+        );
+    }
+    Ok(Json(disabled))
+}
+
+/// POST /admin/keys/:id/enable
+pub async fn enable_api_key(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<users::ApiKeyInfo>, AppError> {
+    require_admin(&user)?;
+    let before = users::get_api_key(&state.db, &id)?;
+    let key = users::enable_api_key(&state.db, &id)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "key.enable",
+        &id,
+        Some(json_value(&before)),
+        Some(json_value(&key)),
+    );
+    Ok(Json(key))
+}
+
 /// DELETE /admin/keys/:id
 pub async fn revoke_api_key(
     State(state): State<AppState>,
@@ -178,49 +772,575 @@ pub async fn revoke_api_key(
     Path(id): Path<String>,
 ) -> Result<Json<DeletedResponse>, AppError> {
     require_admin(&user)?;
-    users::revoke_api_key(&state.db, &id)?;
-    Ok(Json(DeletedResponse { deleted: true }))
+    let before = users::get_api_key(&state.db, &id)?;
+    users::revoke_api_key(&state.db, &id)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "key.revoke",
+        &id,
+        Some(json_value(&before)),
+        None,
+    );
+    Ok(Json(DeletedResponse { deleted: true }))
+}
+
+// ---------------------------------------------------------------------------
+// Budget management
+// ---------------------------------------------------------------------------
+
+/// PUT /admin/budgets/:user_id
+pub async fn set_budget(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(user_id): Path<String>,
+    Json(body): Json<SetBudgetRequest>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&user)?;
+
+    // Verify target user exists.
+    users::get_user(&state.db, &user_id)?;
+    let before = state.budget.get_budget(&user_id)?;
+
+    state
+        .budget
+        .set_budget(&user_id, body.monthly_limit, body.daily_limit)?;
+    state.budget.set_premium_limits(
+        &user_id,
+        body.monthly_premium_limit,
+        body.daily_premium_limit,
+    )?;
+
+    let budget = state.budget.get_budget(&user_id)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "budget.update",
+        &user_id,
+        before.as_ref().map(json_value),
+        budget.as_ref().map(json_value),
+    );
+    Ok(Json(serde_json::to_value(budget).unwrap()))
+}
+
+/// GET /admin/budgets/:user_id
+pub async fn get_budget(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(user_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&user)?;
+
+    let budget = state.budget.get_budget(&user_id)?;
+    match budget {
+        Some(b) => Ok(Json(serde_json::to_value(b).unwrap())),
+        None => Err(AppError::NotFound(format!(
+            "No budget found for user '{user_id}'"
+        ))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Usage aggregates
+// ---------------------------------------------------------------------------
+
+/// GET /admin/usage/timeseries
+///
+/// Requests, tokens, cost and p95 latency per provider and hour or day, from
+/// the hourly aggregates.
+pub async fn usage_timeseries(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<TimeseriesQuery>,
+) -> Result<Json<TimeseriesResponse>, AppError> {
+    require_admin(&user)?;
+    let response = rollup::query_timeseries(&state.db, params, state.cost_calculator.currency())?;
+    Ok(Json(response))
+}
+
+// ---------------------------------------------------------------------------
+// Cost reconciliation
+// ---------------------------------------------------------------------------
+
+/// GET /admin/reconciliation/litellm
+///
+/// Compare gaud's computed costs with the spend LiteLLM recorded, per model
+/// and day, as of the last scheduled reconciliation.
+pub async fn litellm_reconciliation(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<ReconciliationQuery>,
+) -> Result<Json<ReconciliationResponse>, AppError> {
+    require_admin(&user)?;
+
+    let data = reconcile::reconciliation_report(
+        &state.db,
+        reconcile::LITELLM,
+        params.from.as_deref(),
+        params.to.as_deref(),
+        params.flagged,
+    )?;
+    let flagged = data.iter().filter(|e| e.flagged).count();
+    Ok(Json(ReconciliationResponse { data, flagged }))
+}
+
+// ---------------------------------------------------------------------------
+// Experiments
+// ---------------------------------------------------------------------------
+
+/// GET /admin/experiments
+pub async fn list_experiments(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<ExperimentListResponse>, AppError> {
+    require_admin(&user)?;
+    Ok(Json(ExperimentListResponse {
+        experiments: state.config.experiments.clone(),
+    }))
+}
+
+/// GET /admin/experiments/:name
+///
+/// Compare the variants of an experiment on the requests they served.
+pub async fn experiment_stats(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(name): Path<String>,
+    Query(params): Query<ExperimentStatsQuery>,
+) -> Result<Json<ExperimentStatsResponse>, AppError> {
+    require_admin(&user)?;
+
+    let experiment = state
+        .config
+        .experiments
+        .iter()
+        .find(|e| e.name == name)
+        .cloned();
+    let variants = experiments::stats(
+        &state.db,
+        &name,
+        params.from.as_deref(),
+        params.to.as_deref(),
+    )?;
+    if experiment.is_none() && variants.iter().all(|v| v.requests == 0) {
+        return Err(AppError::NotFound(format!("Experiment '{name}' not found")));
+    }
+    Ok(Json(ExperimentStatsResponse {
+        name,
+        experiment,
+        variants,
+        currency: state.cost_calculator.currency().to_string(),
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Queue
+// ---------------------------------------------------------------------------
+
+/// GET /admin/queue
+///
+/// Upstream requests in flight, and how long each user's requests waited
+/// for a slot under `limits.max_in_flight`.
+pub async fn queue_status(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<QueueReport>, AppError> {
+    require_admin(&user)?;
+    Ok(Json(
+        state.scheduler.report(&state.settings.config().limits),
+    ))
+}
+
+/// GET /admin/metrics
+///
+/// Time-to-first-token and chunk-gap histograms of streamed responses per
+/// provider and model, in the Prometheus text format.
+pub async fn metrics(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Response, AppError> {
+    require_admin(&user)?;
+    let body = state.router.read().await.stream_latency().render();
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FailuresQuery {
+    /// Most failures returned, newest first.
+    #[serde(default = "default_failures_limit")]
+    pub limit: usize,
+}
+
+fn default_failures_limit() -> usize {
+    20
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailuresResponse {
+    pub failures: Vec<FailureRecord>,
+}
+
+/// GET /admin/debug/failures
+///
+/// The most recent requests every provider failed, with the sanitized
+/// request and each provider's error chain.
+pub async fn recent_failures(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<FailuresQuery>,
+) -> Result<Json<FailuresResponse>, AppError> {
+    require_admin(&user)?;
+    let failures = state.router.read().await.failures().recent(params.limit);
+    Ok(Json(FailuresResponse { failures }))
+}
+
+// ---------------------------------------------------------------------------
+// Reports
+// ---------------------------------------------------------------------------
+
+/// GET /admin/reports/chargeback
+///
+/// Per-team and per-user roll-up of a month's usage, as JSON or CSV.
+pub async fn chargeback_report(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<ChargebackQuery>,
+) -> Result<Response, AppError> {
+    require_admin(&user)?;
+
+    let period = params
+        .period
+        .unwrap_or_else(|| chrono::Utc::now().format("%Y-%m").to_string());
+    let report =
+        chargeback::chargeback_report(&state.db, &period, state.cost_calculator.currency())?;
+    match params.format.as_deref() {
+        None | Some("json") => Ok(Json(report).into_response()),
+        Some("csv") => Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"chargeback-{period}.csv\""),
+                ),
+            ],
+            report.to_csv(),
+        )
+            .into_response()),
+        Some(other) => Err(AppError::BadRequest(format!(
+            "Unsupported format '{other}', expected json or csv"
+        ))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Pricing
+// ---------------------------------------------------------------------------
+
+/// GET /admin/pricing
+pub async fn list_pricing(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<PricingResponse>, AppError> {
+    require_admin(&user)?;
+
+    let calculator = &state.cost_calculator;
+    let models = calculator
+        .all_pricing()
+        .into_iter()
+        .map(|pricing| PricingEntry {
+            overridden: calculator.is_overridden(&pricing.model),
+            pricing,
+        })
+        .collect();
+    Ok(Json(PricingResponse {
+        models,
+        images: calculator.all_image_pricing(),
+        audio: calculator.all_audio_pricing(),
+    }))
+}
+
+/// PUT /admin/pricing/:model
+///
+/// Override the token prices of a model. Overrides are stored in the
+/// database and survive refreshes and restarts.
+pub async fn set_pricing(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(model): Path<String>,
+    Json(body): Json<SetPricingRequest>,
+) -> Result<Json<ModelPricing>, AppError> {
+    require_admin(&user)?;
+
+    let prices = [
+        Some(body.input_cost_per_million),
+        Some(body.output_cost_per_million),
+        body.cached_input_cost_per_million,
+    ];
+    if prices
+        .into_iter()
+        .flatten()
+        .any(|p| !p.is_finite() || p < 0.0)
+    {
+        return Err(AppError::BadRequest(
+            "Prices must be non-negative numbers".to_string(),
+        ));
+    }
+
+    let provider = body
+        .provider
+        .or_else(|| {
+            state
+                .cost_calculator
+                .get_pricing(&model)
+                .map(|p| p.provider)
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    let before = state.cost_calculator.get_pricing(&model);
+    let pricing = ModelPricing {
+        model,
+        provider,
+        input_cost_per_million: body.input_cost_per_million,
+        output_cost_per_million: body.output_cost_per_million,
+        cached_input_cost_per_million: body.cached_input_cost_per_million,
+    };
+    pricing::save_override(&state.db, &pricing)?;
+    state.cost_calculator.set_override(pricing.clone());
+    audit_log::record(
+        &state.db,
+        &user,
+        "pricing.update",
+        &pricing.model,
+        before.as_ref().map(json_value),
+        Some(json_value(&pricing)),
+    );
+    Ok(Json(pricing))
+}
+
+/// DELETE /admin/pricing/:model
+///
+/// Remove a price override, restoring the configured price.
+pub async fn delete_pricing(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(model): Path<String>,
+) -> Result<Json<DeletedResponse>, AppError> {
+    require_admin(&user)?;
+
+    let before = state.cost_calculator.get_pricing(&model);
+    if !pricing::delete_override(&state.db, &model)? {
+        return Err(AppError::NotFound(format!(
+            "No price override for model '{model}'"
+        )));
+    }
+    state.cost_calculator.remove_override(&model);
+    audit_log::record(
+        &state.db,
+        &user,
+        "pricing.delete",
+        &model,
+        before.as_ref().map(json_value),
+        state
+            .cost_calculator
+            .get_pricing(&model)
+            .as_ref()
+            .map(json_value),
+    );
+    Ok(Json(DeletedResponse { deleted: true }))
+}
+
+/// POST /admin/pricing/refresh
+///
+/// Reload prices from `pricing.file` / `pricing.url` now.
+pub async fn refresh_pricing(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&user)?;
+
+    if !state.cost_calculator.has_source() {
+        return Err(AppError::BadRequest(
+            "No pricing.file or pricing.url configured".to_string(),
+        ));
+    }
+    let models = state
+        .cost_calculator
+        .refresh()
+        .await
+        .map_err(AppError::Provider)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "pricing.refresh",
+        "",
+        None,
+        Some(serde_json::json!({ "models": models })),
+    );
+    Ok(Json(
+        serde_json::json!({ "refreshed": true, "models": models }),
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Rate quotas
+// ---------------------------------------------------------------------------
+
+/// PUT /admin/quotas/users/:user_id
+pub async fn set_user_quota(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(user_id): Path<String>,
+    Json(body): Json<RateLimits>,
+) -> Result<Json<RateLimits>, AppError> {
+    require_admin(&user)?;
+
+    // Verify target user exists.
+    users::get_user(&state.db, &user_id)?;
+
+    let before = state.budget.get_rate_limits(QuotaScope::User, &user_id)?;
+    state
+        .budget
+        .set_rate_limits(QuotaScope::User, &user_id, body)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "quota.user",
+        &user_id,
+        Some(json_value(&before)),
+        Some(json_value(&body)),
+    );
+    Ok(Json(body))
+}
+
+/// GET /admin/quotas/users/:user_id
+pub async fn get_user_quota(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(user_id): Path<String>,
+) -> Result<Json<RateLimits>, AppError> {
+    require_admin(&user)?;
+    let limits = state.budget.get_rate_limits(QuotaScope::User, &user_id)?;
+    Ok(Json(limits))
+}
+
+/// PUT /admin/quotas/teams/:team
+pub async fn set_team_quota(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(team): Path<String>,
+    Json(body): Json<RateLimits>,
+) -> Result<Json<RateLimits>, AppError> {
+    require_admin(&user)?;
+    let before = state.budget.get_rate_limits(QuotaScope::Team, &team)?;
+    state
+        .budget
+        .set_rate_limits(QuotaScope::Team, &team, body)?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "quota.team",
+        &team,
+        Some(json_value(&before)),
+        Some(json_value(&body)),
+    );
+    Ok(Json(body))
+}
+
+/// GET /admin/quotas/teams/:team
+pub async fn get_team_quota(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(team): Path<String>,
+) -> Result<Json<RateLimits>, AppError> {
+    require_admin(&user)?;
+    let limits = state.budget.get_rate_limits(QuotaScope::Team, &team)?;
+    Ok(Json(limits))
 }
 
 // ---------------------------------------------------------------------------
-// Budget management
+// Cost markups
 // ---------------------------------------------------------------------------
 
-/// PUT /admin/budgets/:user_id
-pub async fn set_budget(
+/// PUT /admin/markups/users/:user_id
+///
+/// Set the markup charged on top of the user's provider costs. A body with
+/// neither field removes it.
+pub async fn set_user_markup(
     State(state): State<AppState>,
     Extension(user): Extension<AuthUser>,
     Path(user_id): Path<String>,
-    Json(body): Json<SetBudgetRequest>,
-) -> Result<Json<serde_json::Value>, AppError> {
+    Json(body): Json<CostMarkup>,
+) -> Result<Json<CostMarkup>, AppError> {
     require_admin(&user)?;
 
     // Verify target user exists.
     users::get_user(&state.db, &user_id)?;
 
+    let before = state.cost_calculator.get_markup(QuotaScope::User, &user_id);
+    cost::save_markup(&state.db, QuotaScope::User, &user_id, body)?;
     state
-        .budget
-        .set_budget(&user_id, body.monthly_limit, body.daily_limit)?;
-
-    let budget = state.budget.get_budget(&user_id)?;
-    Ok(Json(serde_json::to_value(budget).unwrap()))
+        .cost_calculator
+        .set_markup(QuotaScope::User, &user_id, body);
+    audit_log::record(
+        &state.db,
+        &user,
+        "markup.user",
+        &user_id,
+        before.as_ref().map(json_value),
+        Some(json_value(&body)),
+    );
+    Ok(Json(body))
 }
 
-/// GET /admin/budgets/:user_id
-pub async fn get_budget(
+/// GET /admin/markups/users/:user_id
+pub async fn get_user_markup(
     State(state): State<AppState>,
     Extension(user): Extension<AuthUser>,
     Path(user_id): Path<String>,
-) -> Result<Json<serde_json::Value>, AppError> {
+) -> Result<Json<CostMarkup>, AppError> {
     require_admin(&user)?;
+    let markup = state
+        .cost_calculator
+        .get_markup(QuotaScope::User, &user_id)
+        .unwrap_or_default();
+    Ok(Json(markup))
+}
 
-    let budget = state.budget.get_budget(&user_id)?;
-    match budget {
-        Some(b) => Ok(Json(serde_json::to_value(b).unwrap())),
-        None => Err(AppError::NotFound(format!(
-            "No budget found for user '{user_id}'"
-        ))),
-    }
+/// PUT /admin/markups/teams/:team
+pub async fn set_team_markup(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(team): Path<String>,
+    Json(body): Json<CostMarkup>,
+) -> Result<Json<CostMarkup>, AppError> {
+    require_admin(&user)?;
+    let before = state.cost_calculator.get_markup(QuotaScope::Team, &team);
+    cost::save_markup(&state.db, QuotaScope::Team, &team, body)?;
+    state
+        .cost_calculator
+        .set_markup(QuotaScope::Team, &team, body);
+    audit_log::record(
+        &state.db,
+        &user,
+        "markup.team",
+        &team,
+        before.as_ref().map(json_value),
+        Some(json_value(&body)),
+    );
+    Ok(Json(body))
+}
+
+/// GET /admin/markups/teams/:team
+pub async fn get_team_markup(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(team): Path<String>,
+) -> Result<Json<CostMarkup>, AppError> {
+    require_admin(&user)?;
+    let markup = state
+        .cost_calculator
+        .get_markup(QuotaScope::Team, &team)
+        .unwrap_or_default();
+    Ok(Json(markup))
 }
 
 // ---------------------------------------------------------------------------
@@ -234,48 +1354,258 @@ pub async fn query_usage(
     Query(params): Query<UsageQuery>,
 ) -> Result<Json<UsageResponse>, AppError> {
     require_admin(&user)?;
+    let usage = usage_page(&state.db, &params, state.cost_calculator.currency())?;
+    Ok(Json(usage))
+}
 
-    let page = params.page.max(1);
-    let per_page = params.per_page.clamp(1, 500);
-    let offset = (page - 1) * per_page;
+/// GET /admin/usage/summary
+///
+/// Usage totals grouped by user, provider, model, end user or a tag.
+pub async fn usage_summary(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<UsageSummaryQuery>,
+) -> Result<Json<UsageSummaryResponse>, AppError> {
+    require_admin(&user)?;
+    let summary = usage_summary_of(&state.db, &params, state.cost_calculator.currency())?;
+    Ok(Json(summary))
+}
 
-    // Build query dynamically based on provided filters.
-    let mut where_clauses: Vec<String> = Vec::new();
-    let mut bind_values: Vec<String> = Vec::new();
+/// Filters shared by the usage list and summary.
+#[derive(Default)]
+struct UsageFilter<'a> {
+    user_id: Option<&'a str>,
+    provider: Option<&'a str>,
+    end_user: Option<&'a str>,
+    tags: Option<&'a str>,
+    key_id: Option<&'a str>,
+    client_ip: Option<&'a str>,
+    user_agent: Option<&'a str>,
+    path: Option<&'a str>,
+    model: Option<&'a str>,
+    status: Option<&'a str>,
+    search: Option<&'a str>,
+    min_cost: Option<f64>,
+    max_cost: Option<f64>,
+    min_latency_ms: Option<u64>,
+    max_latency_ms: Option<u64>,
+    from: Option<&'a str>,
+    to: Option<&'a str>,
+}
 
-    if let Some(ref uid) = params.user_id {
-        bind_values.push(uid.clone());
-        where_clauses.push(format!("user_id = ?{}", bind_values.len()));
-    }
-    if let Some(ref provider) = params.provider {
-        bind_values.push(provider.clone());
-        where_clauses.push(format!("provider = ?{}", bind_values.len()));
-    }
-    if let Some(ref from) = params.from {
-        bind_values.push(from.clone());
-        where_clauses.push(format!("created_at >= ?{}", bind_values.len()));
-    }
-    if let Some(ref to) = params.to {
-        bind_values.push(to.clone());
-        where_clauses.push(format!("created_at <= ?{}", bind_values.len()));
+impl UsageFilter<'_> {
+    /// `WHERE` clause (empty without filters) and its bind values.
+    fn where_sql(&self) -> Result<(String, Vec<String>), AppError> {
+        let mut where_clauses: Vec<String> = Vec::new();
+        let mut bind_values: Vec<String> = Vec::new();
+
+        // A bare date covers the whole day.
+        let to = self.to.map(|to| {
+            if to.len() == 10 {
+                format!("{to} 23:59:59")
+            } else {
+                to.to_string()
+            }
+        });
+        let (status_condition, status) = match self.status {
+            Some("error") => ("status LIKE", Some("error%".to_string())),
+            Some("cache_hit") => ("status LIKE", Some("%cache_hit%".to_string())),
+            other => ("status =", other.map(String::from)),
+        };
+
+        for (condition, value) in [
+            ("user_id =", self.user_id.map(String::from)),
+            ("provider =", self.provider.map(String::from)),
+            ("end_user =", self.end_user.map(String::from)),
+            ("key_id =", self.key_id.map(String::from)),
+            ("client_ip =", self.client_ip.map(String::from)),
+            ("user_agent =", self.user_agent.map(String::from)),
+            ("path =", self.path.map(String::from)),
+            ("model =", self.model.map(String::from)),
+            (status_condition, status),
+            ("cost >=", self.min_cost.map(|v| v.to_string())),
+            ("cost <=", self.max_cost.map(|v| v.to_string())),
+            ("latency_ms >=", self.min_latency_ms.map(|v| v.to_string())),
+            ("latency_ms <=", self.max_latency_ms.map(|v| v.to_string())),
+            ("created_at >=", self.from.map(String::from)),
+            ("created_at <=", to),
+        ] {
+            if let Some(value) = value {
+                bind_values.push(value);
+                where_clauses.push(format!("{condition} ?{}", bind_values.len()));
+            }
+        }
+        if let Some(search) = self.search.filter(|s| !s.is_empty()) {
+            let escaped = search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            bind_values.push(format!("%{escaped}%"));
+            where_clauses.push(format!("tags LIKE ?{} ESCAPE '\\'", bind_values.len()));
+        }
+        let tags = parse_tags(self.tags.unwrap_or("")).map_err(AppError::BadRequest)?;
+        for (key, value) in tags {
+            bind_values.push(attribution::tag_path(&key));
+            bind_values.push(value);
+            where_clauses.push(format!(
+                "json_extract(tags, ?{}) = ?{}",
+                bind_values.len() - 1,
+                bind_values.len()
+            ));
+        }
+
+        let where_sql = if where_clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_clauses.join(" AND "))
+        };
+        Ok((where_sql, bind_values))
     }
+}
 
-    let where_sql = if where_clauses.is_empty() {
-        String::new()
-    } else {
-        format!("WHERE {}", where_clauses.join(" AND "))
+/// Usage totals matching `params`, grouped by `params.group_by`.
+pub(crate) fn usage_summary_of(
+    db: &crate::db::Database,
+    params: &UsageSummaryQuery,
+    currency: &str,
+) -> Result<UsageSummaryResponse, AppError> {
+    let group_expr = match params.group_by.as_str() {
+        "user" => "user_id".to_string(),
+        "provider" => "provider".to_string(),
+        "model" => "model".to_string(),
+        "end_user" => "end_user".to_string(),
+        "key" => "key_id".to_string(),
+        "client_ip" => "client_ip".to_string(),
+        "user_agent" => "user_agent".to_string(),
+        "path" => "path".to_string(),
+        other => match other.strip_prefix("tag:") {
+            // Valid keys contain no quotes, so the path can be inlined.
+            Some(key) if attribution::is_valid_key(key) => {
+                format!("json_extract(tags, '{}')", attribution::tag_path(key))
+            }
+            _ => {
+                return Err(AppError::BadRequest(format!(
+                    "Invalid group_by '{other}', expected user, provider, model, end_user, key, \
+                     client_ip, user_agent, path or tag:<key>"
+                )));
+            }
+        },
+    };
+    let filter = UsageFilter {
+        user_id: params.user_id.as_deref(),
+        provider: params.provider.as_deref(),
+        end_user: params.end_user.as_deref(),
+        tags: params.tags.as_deref(),
+        key_id: params.key_id.as_deref(),
+        client_ip: params.client_ip.as_deref(),
+        user_agent: params.user_agent.as_deref(),
+        path: params.path.as_deref(),
+        from: params.from.as_deref(),
+        to: params.to.as_deref(),
+        ..Default::default()
+    };
+    let (where_sql, bind_values) = filter.where_sql()?;
+    let sql = format!(
+        "SELECT {group_expr} AS grp, COUNT(*), COALESCE(SUM(input_tokens), 0), \
+         COALESCE(SUM(output_tokens), 0), COALESCE(SUM(premium_requests), 0.0), \
+         COALESCE(SUM(cost), 0.0) \
+         FROM usage_log {where_sql} GROUP BY grp ORDER BY 6 DESC, grp"
+    );
+
+    let data = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(bind_values.iter()), |row| {
+            Ok(UsageSummaryGroup {
+                key: row.get(0)?,
+                requests: row.get(1)?,
+                input_tokens: row.get(2)?,
+                output_tokens: row.get(3)?,
+                premium_requests: row.get(4)?,
+                cost: row.get(5)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+
+    Ok(UsageSummaryResponse {
+        group_by: params.group_by.clone(),
+        total_cost: data.iter().map(|g| g.cost).sum(),
+        data,
+        currency: currency.to_string(),
+    })
+}
+
+/// `ORDER BY` terms for a usage page. Ties are broken by insertion order so
+/// pages stay stable while entries share a sort value.
+fn usage_order_sql(sort: Option<&str>, order: Option<&str>) -> Result<String, AppError> {
+    let column = match sort.unwrap_or("created_at") {
+        column @ ("created_at" | "cost" | "latency_ms" | "input_tokens" | "output_tokens") => {
+            column
+        }
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "Invalid sort '{other}', expected created_at, cost, latency_ms, input_tokens \
+                 or output_tokens"
+            )));
+        }
+    };
+    let direction = match order.unwrap_or("desc") {
+        "desc" => "DESC",
+        "asc" => "ASC",
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "Invalid order '{other}', expected asc or desc"
+            )));
+        }
     };
+    Ok(format!("{column} {direction}, rowid {direction}"))
+}
+
+/// One page of usage log entries matching `params`, newest first unless
+/// `params.sort` says otherwise.
+pub(crate) fn usage_page(
+    db: &crate::db::Database,
+    params: &UsageQuery,
+    currency: &str,
+) -> Result<UsageResponse, AppError> {
+    let page = params.page.max(1);
+    let per_page = params.per_page.clamp(1, 500);
+    let offset = (page - 1) * per_page;
+
+    let filter = UsageFilter {
+        user_id: params.user_id.as_deref(),
+        provider: params.provider.as_deref(),
+        end_user: params.end_user.as_deref(),
+        tags: params.tags.as_deref(),
+        key_id: params.key_id.as_deref(),
+        client_ip: params.client_ip.as_deref(),
+        user_agent: params.user_agent.as_deref(),
+        path: params.path.as_deref(),
+        model: params.model.as_deref(),
+        status: params.status.as_deref(),
+        search: params.search.as_deref(),
+        min_cost: params.min_cost,
+        max_cost: params.max_cost,
+        min_latency_ms: params.min_latency_ms,
+        max_latency_ms: params.max_latency_ms,
+        from: params.from.as_deref(),
+        to: params.to.as_deref(),
+    };
+    let (where_sql, bind_values) = filter.where_sql()?;
+    let order_sql = usage_order_sql(params.sort.as_deref(), params.order.as_deref())?;
 
     let count_sql = format!("SELECT COUNT(*) FROM usage_log {where_sql}");
     let data_sql = format!(
         "SELECT id, user_id, request_id, provider, model, input_tokens, output_tokens, \
-         cost, latency_ms, status, created_at \
-         FROM usage_log {where_sql} ORDER BY created_at DESC LIMIT ?{} OFFSET ?{}",
+         audio_seconds, cost, latency_ms, status, created_at, premium_requests, raw_cost, \
+         end_user, tags, impersonated_by, finish_reason, experiment, experiment_variant, \
+         cost_saved, key_id, client_ip, user_agent, path \
+         FROM usage_log {where_sql} ORDER BY {order_sql} LIMIT ?{} OFFSET ?{}",
         bind_values.len() + 1,
         bind_values.len() + 2,
     );
 
-    let result = state.db.with_conn(|conn| {
+    let result = db.with_conn(|conn| {
         // Count total matching rows.
         let total: i64 = {
             let mut stmt = conn.prepare(&count_sql)?;
@@ -305,10 +1635,27 @@ pub async fn query_usage(
                 model: row.get(4)?,
                 input_tokens: row.get(5)?,
                 output_tokens: row.get(6)?,
-                cost: row.get(7)?,
-                latency_ms: row.get(8)?,
-                status: row.get(9)?,
-                created_at: row.get(10)?,
+                audio_seconds: row.get(7)?,
+                cost: row.get(8)?,
+                latency_ms: row.get(9)?,
+                status: row.get(10)?,
+                created_at: row.get(11)?,
+                premium_requests: row.get(12)?,
+                raw_cost: row.get(13)?,
+                end_user: row.get(14)?,
+                tags: row
+                    .get::<_, Option<String>>(15)?
+                    .and_then(|t| serde_json::from_str(&t).ok())
+                    .unwrap_or_default(),
+                impersonated_by: row.get(16)?,
+                finish_reason: row.get(17)?,
+                experiment: row.get(18)?,
+                experiment_variant: row.get(19)?,
+                cost_saved: row.get(20)?,
+                key_id: row.get(21)?,
+                client_ip: row.get(22)?,
+                user_agent: row.get(23)?,
+                path: row.get(24)?,
             })
         })?;
 
@@ -318,12 +1665,13 @@ pub async fn query_usage(
 
     let (data, total) = result;
 
-    Ok(Json(UsageResponse {
+    Ok(UsageResponse {
         data,
         page,
         per_page,
         total,
-    }))
+        currency: currency.to_string(),
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -366,10 +1714,19 @@ pub async fn flush_cache(
         .as_ref()
         .ok_or_else(|| AppError::NotFound("Cache is not enabled".to_string()))?;
 
+    let entries = cache.count().await.ok();
     cache
         .flush_all()
         .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "cache.flush",
+        "",
+        entries.map(|n| serde_json::json!({ "entries": n })),
+        None,
+    );
 
     Ok(Json(serde_json::json!({ "flushed": true })))
 }
@@ -391,6 +1748,7 @@ pub async fn flush_cache_model(
         .flush_model(&model)
         .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
+    audit_log::record(&state.db, &user, "cache.flush_model", &model, None, None);
 
     Ok(Json(serde_json::json!({ "flushed": true, "model": model })))
 }
@@ -406,16 +1764,26 @@ pub struct UpdateSettingRequest {
 }
 
 /// GET /admin/settings
+///
+/// Effective settings, including runtime changes.
 pub async fn get_settings(
     State(state): State<AppState>,
     Extension(user): Extension<AuthUser>,
 ) -> Result<Json<Vec<crate::config::SettingEntry>>, AppError> {
     require_admin(&user)?;
-    let report = state.config.settings_report();
+    let stored = settings::load(&state.db)?;
+    let mut report = state.settings.config().settings_report();
+    for entry in &mut report {
+        entry.stored = entry.runtime && !entry.overridden && stored.contains_key(&entry.key);
+    }
     Ok(Json(report))
 }
 
 /// PUT /admin/settings
+///
+/// Runtime settings (`config::RUNTIME_SETTINGS`) apply at once and are
+/// stored in the database; others are saved to the config file and apply
+/// after a restart.
 pub async fn update_settings(
     State(state): State<AppState>,
     Extension(user): Extension<AuthUser>,
@@ -436,21 +1804,475 @@ pub async fn update_settings(
         )));
     }
 
-    // Create a mutable copy, update, and save.
-    let mut config_copy = (*state.config).clone();
+    let before = setting_value(&state.settings.config(), &body.key);
+
+    // Runtime settings apply at once and are stored in the database.
+    if settings::is_runtime(&body.key) {
+        settings::update(&state, &body.key, &body.value, &user.name).await?;
+        audit_log::record(
+            &state.db,
+            &user,
+            "settings.update",
+            &body.key,
+            before,
+            setting_value(&state.settings.config(), &body.key),
+        );
+        return Ok(Json(serde_json::json!({
+            "message": "Setting applied.",
+            "key": body.key,
+            "restart_required": false
+        })));
+    }
+
+    // Others are saved to the config file as written and apply after a
+    // restart.
+    let mut config_copy = crate::config::Config::load_file(&state.config_path)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
     config_copy
         .update_setting(&body.key, &body.value)
         .map_err(|e| AppError::BadRequest(e))?;
     config_copy
         .save(&state.config_path)
         .map_err(|e| AppError::Internal(e.to_string()))?;
+    audit_log::record(
+        &state.db,
+        &user,
+        "settings.update",
+        &body.key,
+        before,
+        setting_value(&config_copy, &body.key),
+    );
 
     Ok(Json(serde_json::json!({
         "message": "Setting saved. Restart the server to apply changes.",
-        "key": body.key
+        "key": body.key,
+        "restart_required": true
+    })))
+}
+
+/// Current value of a setting as listed on the settings page. Sensitive
+/// values are masked so they stay out of the audit log.
+fn setting_value(config: &crate::config::Config, key: &str) -> Option<serde_json::Value> {
+    config
+        .settings_report()
+        .into_iter()
+        .find(|entry| entry.key == key)
+        .map(|entry| {
+            if entry.sensitive {
+                serde_json::Value::String("********".to_string())
+            } else {
+                entry.value
+            }
+        })
+}
+
+// ---------------------------------------------------------------------------
+// Provider configuration
+// ---------------------------------------------------------------------------
+
+/// Shown in place of secrets such as client secrets and API keys.
+const MASKED_SECRET: &str = "********";
+
+#[derive(Debug, Serialize)]
+pub struct ProviderSection {
+    pub name: String,
+    /// The section as written in the config file, secrets masked; `null`
+    /// for a provider that is not configured.
+    pub settings: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderConfigResponse {
+    pub sections: Vec<ProviderSection>,
+    /// Provider setting keys locked by environment variables, with the
+    /// variable.
+    pub overridden: BTreeMap<String, String>,
+}
+
+/// GET /admin/providers/config
+///
+/// Provider settings and virtual models as written in the config file.
+pub async fn get_provider_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<ProviderConfigResponse>, AppError> {
+    require_admin(&user)?;
+    let config = crate::config::Config::load_file(&state.config_path)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let sections = crate::config::PROVIDER_SECTIONS
+        .iter()
+        .map(|name| ProviderSection {
+            name: name.to_string(),
+            settings: masked(config.providers.section(name).unwrap_or_default()),
+        })
+        .collect();
+    let overridden = state
+        .config
+        .env_overrides
+        .all()
+        .iter()
+        .filter(|(key, _)| key.starts_with("providers."))
+        .map(|(key, var)| (key.clone(), var.clone()))
+        .collect();
+    Ok(Json(ProviderConfigResponse {
+        sections,
+        overridden,
+    }))
+}
+
+/// PUT /admin/providers/config/:section
+///
+/// Replace a provider's settings (`null` removes the provider) or the
+/// virtual models, and save them to the config file. Masked secrets keep
+/// their saved value. The section's providers are rebuilt and swapped into
+/// the router at once; only OAuth client settings and the `claude` section
+/// take effect after a restart.
+pub async fn update_provider_config(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(name): Path<String>,
+    Json(mut settings): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    require_admin(&user)?;
+
+    let mut config = crate::config::Config::load_file(&state.config_path)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let Some(before) = config.providers.section(&name) else {
+        return Err(AppError::NotFound(format!(
+            "Unknown provider section '{name}'"
+        )));
+    };
+    keep_masked_secrets(&mut settings, &before);
+
+    let prefix = format!("providers.{name}.");
+    for (key, var) in state.config.env_overrides.all() {
+        if let Some(field) = key.strip_prefix(&prefix)
+            && settings.get(field) != before.get(field)
+        {
+            return Err(AppError::BadRequest(format!(
+                "Setting '{key}' is overridden by environment variable '{var}'. Unset the variable and restart to edit."
+            )));
+        }
+    }
+
+    config
+        .providers
+        .set_section(&name, settings)
+        .map_err(AppError::BadRequest)?;
+    config.validate().map_err(AppError::BadRequest)?;
+    config
+        .save(&state.config_path)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let after = config.providers.section(&name).unwrap_or_default();
+
+    // A provider section's providers are rebuilt from the new settings, off
+    // the router lock, and swapped in; quotas and virtual models are the
+    // router's own.
+    let rebuilt = if factory::SECTIONS.contains(&name.as_str())
+        && without_quota(&before) != without_quota(&after)
+    {
+        Some(state.providers.build(&config.providers, &name).await)
+    } else {
+        None
+    };
+    let failed = rebuilt
+        .as_ref()
+        .is_some_and(|providers| providers.is_empty() && !after.is_null());
+    {
+        let mut router = state.router.write().await;
+        if let Some(providers) = rebuilt {
+            let ids: Vec<String> = providers.iter().map(|p| p.id().to_string()).collect();
+            router.replace_section(&name, providers);
+            for id in ids.iter().filter(|id| config.providers.dry_run_for(id)) {
+                router.set_dry_run(id);
+            }
+        }
+        for id in router.provider_ids().to_vec() {
+            router.set_quota(&id, config.providers.quota_for(&id));
+        }
+        router.set_virtual_models(config.providers.virtual_models.clone());
+    }
+    let restart_required = needs_restart(&name, &before, &after);
+
+    audit_log::record(
+        &state.db,
+        &user,
+        "providers.config.update",
+        &name,
+        Some(masked(before)),
+        Some(masked(after)),
+    );
+
+    let message = if restart_required {
+        "Provider settings saved. Restart the server to apply changes."
+    } else if failed {
+        "Provider settings saved, but the provider failed to start. See the server log."
+    } else {
+        "Provider settings saved and applied."
+    };
+    Ok(Json(serde_json::json!({
+        "message": message,
+        "section": name,
+        "restart_required": restart_required,
     })))
 }
 
+/// `value` with the values of secret keys masked.
+fn masked(mut value: serde_json::Value) -> serde_json::Value {
+    fn mask(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    if redact::is_secret_key(key) && v.as_str().is_some_and(|s| !s.is_empty()) {
+                        *v = serde_json::Value::String(MASKED_SECRET.to_string());
+                    } else {
+                        mask(v);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(mask),
+            _ => {}
+        }
+    }
+    mask(&mut value);
+    value
+}
+
+/// Put back the saved value of every secret the client sent masked.
+fn keep_masked_secrets(value: &mut serde_json::Value, saved: &serde_json::Value) {
+    let serde_json::Value::Object(map) = value else {
+        return;
+    };
+    for (key, v) in map.iter_mut() {
+        let old = saved.get(key).unwrap_or(&serde_json::Value::Null);
+        if v.as_str() == Some(MASKED_SECRET) {
+            *v = old.clone();
+        } else {
+            keep_masked_secrets(v, old);
+        }
+    }
+}
+
+/// OAuth client settings, which the OAuth manager reads once at startup.
+const OAUTH_CLIENT_KEYS: [&str; 5] = [
+    "client_id",
+    "client_secret",
+    "auth_url",
+    "token_url",
+    "callback_port",
+];
+
+/// Whether a change of provider section `name` only applies after a
+/// restart: OAuth client settings, and anything in `claude`, whose settings
+/// are all read at startup.
+fn needs_restart(name: &str, before: &serde_json::Value, after: &serde_json::Value) -> bool {
+    if name == "claude" {
+        return without_quota(before) != without_quota(after);
+    }
+    OAUTH_CLIENT_KEYS
+        .iter()
+        .any(|key| before.get(key) != after.get(key))
+}
+
+/// A provider section without its `quota`, which the router applies as is.
+fn without_quota(section: &serde_json::Value) -> serde_json::Value {
+    let mut section = section.clone();
+    if let Some(map) = section.as_object_mut() {
+        map.remove("quota");
+    }
+    section
+}
+
+// ---------------------------------------------------------------------------
+// Routing rules
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct RoutingRulesResponse {
+    /// Rules in evaluation order.
+    pub rules: Vec<RoutingRule>,
+    /// Registered providers, for the rules' provider lists.
+    pub providers: Vec<String>,
+    /// Virtual model names, with the `gaud:` prefix.
+    pub virtual_models: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoutingRulesRequest {
+    /// The complete rule list, in evaluation order.
+    pub rules: Vec<RoutingRule>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimulateRoutingRequest {
+    pub model: String,
+    /// User ID or name; the caller when left out. A registered user's team
+    /// is looked up.
+    pub user: Option<String>,
+    /// Team to match instead of the user's.
+    pub team: Option<String>,
+    /// Request size in characters of message text; the length of `prompt`
+    /// when left out.
+    pub input_chars: Option<usize>,
+    #[serde(default)]
+    pub prompt: String,
+    /// Hour of the day (UTC); the current hour when left out.
+    pub hour_utc: Option<u32>,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Rules to try instead of the saved ones.
+    pub rules: Option<Vec<RoutingRule>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulateRoutingResponse {
+    pub user: String,
+    pub team: Option<String>,
+    pub input_chars: usize,
+    pub hour_utc: u32,
+    #[serde(flatten)]
+    pub simulation: RoutingSimulation,
+}
+
+/// GET /admin/routing/rules
+pub async fn get_routing_rules(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<RoutingRulesResponse>, AppError> {
+    require_admin(&user)?;
+    let router = state.router.read().await;
+    Ok(Json(RoutingRulesResponse {
+        rules: router.rules().to_vec(),
+        providers: router.provider_ids().to_vec(),
+        virtual_models: router
+            .virtual_models()
+            .keys()
+            .map(|name| format!("{VIRTUAL_MODEL_PREFIX}{name}"))
+            .collect(),
+    }))
+}
+
+/// PUT /admin/routing/rules
+///
+/// Replace the routing rules, save them to the config file and apply them
+/// at once.
+pub async fn update_routing_rules(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<UpdateRoutingRulesRequest>,
+) -> Result<Json<RoutingRulesResponse>, AppError> {
+    require_admin(&user)?;
+
+    let mut config = crate::config::Config::load_file(&state.config_path)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let before = json_value(&config.providers.rules);
+    config.providers.rules = req.rules;
+    config.validate().map_err(AppError::BadRequest)?;
+    config
+        .save(&state.config_path)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    state
+        .router
+        .write()
+        .await
+        .set_rules(config.providers.rules.clone());
+
+    audit_log::record(
+        &state.db,
+        &user,
+        "routing.rules.update",
+        "providers.rules",
+        Some(before),
+        Some(json_value(&config.providers.rules)),
+    );
+    get_routing_rules(State(state), Extension(user)).await
+}
+
+/// POST /admin/routing/simulate
+///
+/// Which provider would serve a request: the model a virtual model
+/// resolves to, the matching rule and the provider order. Nothing is sent.
+pub async fn simulate_routing(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<SimulateRoutingRequest>,
+) -> Result<Json<SimulateRoutingResponse>, AppError> {
+    require_admin(&user)?;
+    if req.model.is_empty() {
+        return Err(AppError::BadRequest("model is required".to_string()));
+    }
+    if req.hour_utc.is_some_and(|h| h > 23) {
+        return Err(AppError::BadRequest(
+            "hour_utc must be between 0 and 23".to_string(),
+        ));
+    }
+    if let Some(rules) = &req.rules {
+        rules
+            .iter()
+            .try_for_each(RoutingRule::validate)
+            .map_err(AppError::BadRequest)?;
+    }
+
+    let (user_id, user_name, user_team) = match req.user.as_ref().filter(|u| !u.is_empty()) {
+        None => (user.user_id.clone(), user.name.clone(), user.team.clone()),
+        Some(who) => match users::get_user(&state.db, who)
+            .or_else(|_| users::get_user_by_name(&state.db, who))
+        {
+            Ok(found) => (found.id, found.name, found.team),
+            Err(_) => (who.clone(), who.clone(), None),
+        },
+    };
+    let team = req.team.filter(|t| !t.is_empty()).or(user_team);
+    let input_chars = req
+        .input_chars
+        .unwrap_or_else(|| req.prompt.chars().count());
+    let hour_utc = req.hour_utc.unwrap_or_else(|| chrono::Utc::now().hour());
+    let facts = RequestFacts {
+        model: &req.model,
+        user_id: &user_id,
+        user_name: &user_name,
+        team: team.as_deref(),
+        input_chars,
+        hour_utc,
+    };
+
+    let simulation =
+        state
+            .router
+            .write()
+            .await
+            .simulate(&facts, req.rules.as_deref(), req.priority)?;
+    Ok(Json(SimulateRoutingResponse {
+        user: user_name,
+        team,
+        input_chars,
+        hour_utc,
+        simulation,
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// Audit log
+// ---------------------------------------------------------------------------
+
+/// GET /admin/audit
+///
+/// Filters: `actor` (user ID or name), `action` (exact, or a prefix ending
+/// in `.`), `target`, `from`, `to`, plus `page`/`per_page`.
+pub async fn query_audit(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<audit_log::AuditLogQuery>,
+) -> Result<Json<audit_log::AuditLogPage>, AppError> {
+    require_admin(&user)?;
+    Ok(Json(audit_log::query(&state.db, &params)?))
+}
+
+/// Serialize a value for the audit log.
+fn json_value<T: Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -465,6 +2287,12 @@ mod tests {
             user_id: "u1".to_string(),
             name: "admin".to_string(),
             role: "admin".to_string(),
+            reasoning_mode: ReasoningMode::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin: Default::default(),
         };
         assert!(require_admin(&user).is_ok());
     }
@@ -475,16 +2303,125 @@ mod tests {
             user_id: "u1".to_string(),
             name: "member".to_string(),
             role: "member".to_string(),
+            reasoning_mode: ReasoningMode::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin: Default::default(),
         };
         assert!(require_admin(&user).is_err());
     }
 
+    #[test]
+    fn test_provider_config_secrets_round_trip() {
+        let saved = serde_json::json!({
+            "url": "http://localhost:4000",
+            "api_key": "sk-litellm-master",
+            "models": ["litellm:gpt-4o"]
+        });
+        let shown = masked(saved.clone());
+        assert_eq!(shown["api_key"], MASKED_SECRET);
+        assert_eq!(shown["url"], "http://localhost:4000");
+
+        // An edit that leaves the secret masked keeps the saved one.
+        let mut edited = shown.clone();
+        edited["url"] = serde_json::json!("http://litellm:4000");
+        keep_masked_secrets(&mut edited, &saved);
+        assert_eq!(edited["api_key"], "sk-litellm-master");
+        assert_eq!(edited["url"], "http://litellm:4000");
+    }
+
+    #[tokio::test]
+    async fn test_provider_config_update_rebuilds_the_provider() {
+        use crate::api::test_support::{litellm_router, test_state, user};
+        use serde_json::json;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let (old, new) = (MockServer::start().await, MockServer::start().await);
+        for server in [&old, &new] {
+            Mock::given(method("POST"))
+                .and(path("/v1/chat/completions"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "id": "c1", "object": "chat.completion", "created": 0, "model": "m",
+                    "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hi"},
+                                 "finish_reason": "stop"}],
+                    "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2},
+                })))
+                .mount(server)
+                .await;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = crate::config::Config::default();
+        let section = |url: String| json!({"url": url, "discover_models": false, "models": ["m"]});
+        config
+            .providers
+            .set_section("litellm", section(old.uri()))
+            .unwrap();
+        let mut state = test_state(config.clone(), litellm_router(&old.uri()).await);
+        state.config_path = dir.path().join("gaud.toml");
+        config.save(&state.config_path).unwrap();
+
+        let admin = AuthUser {
+            role: "admin".to_string(),
+            ..user()
+        };
+        let Json(response) = update_provider_config(
+            State(state.clone()),
+            Extension(admin),
+            Path("litellm".to_string()),
+            Json(section(new.uri())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response["restart_required"], false);
+
+        let request: crate::providers::types::ChatRequest = serde_json::from_value(
+            json!({"model": "m", "messages": [{"role": "user", "content": "Hello"}]}),
+        )
+        .unwrap();
+        state.router.write().await.chat(&request).await.unwrap();
+        assert_eq!(new.received_requests().await.unwrap().len(), 1);
+        assert!(old.received_requests().await.unwrap().is_empty());
+        assert_eq!(state.router.read().await.provider_ids(), ["litellm"]);
+    }
+
+    #[test]
+    fn test_needs_restart() {
+        let a = serde_json::json!({"client_id": "a", "default_model": "x"});
+        let b = serde_json::json!({"client_id": "a", "default_model": "y"});
+        assert!(!needs_restart("gemini", &a, &b));
+        assert!(needs_restart("claude", &a, &b));
+        let c = serde_json::json!({"client_id": "c", "default_model": "x"});
+        assert!(needs_restart("gemini", &a, &c));
+        assert!(!needs_restart("virtual_models", &a, &a));
+    }
+
+    #[test]
+    fn test_without_quota() {
+        let a = serde_json::json!({"url": "u", "quota": {"daily_requests": 10}});
+        let b = serde_json::json!({"url": "u", "quota": {"daily_requests": 20}});
+        assert_eq!(without_quota(&a), without_quota(&b));
+    }
+
     #[test]
     fn test_create_user_request_deserialization() {
         let json = r#"{"name": "alice", "role": "member"}"#;
         let req: CreateUserRequest = serde_json::from_str(json).unwrap();
         assert_eq!(req.name, "alice");
         assert_eq!(req.role, "member");
+        assert_eq!(req.team, None);
+    }
+
+    #[test]
+    fn test_rate_limits_request_deserialization() {
+        let json = r#"{"rpm": 60, "tpm": 100000}"#;
+        let req: RateLimits = serde_json::from_str(json).unwrap();
+        assert_eq!(req.rpm, Some(60));
+        assert_eq!(req.tpm, Some(100_000));
+        assert_eq!(req.rpd, None);
     }
 
     #[test]
@@ -492,6 +2429,14 @@ mod tests {
         let json = r#"{}"#;
         let req: CreateApiKeyRequest = serde_json::from_str(json).unwrap();
         assert_eq!(req.label, "");
+        assert!(req.reasoning_mode.is_none());
+    }
+
+    #[test]
+    fn test_create_api_key_request_reasoning_mode() {
+        let json = r#"{"label": "cli", "reasoning_mode": "inline"}"#;
+        let req: CreateApiKeyRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.reasoning_mode, Some(ReasoningMode::Inline));
     }
 
     #[test]
@@ -500,6 +2445,7 @@ mod tests {
         let req: SetBudgetRequest = serde_json::from_str(json).unwrap();
         assert_eq!(req.monthly_limit, Some(100.0));
         assert_eq!(req.daily_limit, Some(10.0));
+        assert_eq!(req.monthly_premium_limit, None);
     }
 
     #[test]
@@ -510,6 +2456,31 @@ mod tests {
         assert_eq!(req.daily_limit, None);
     }
 
+    #[test]
+    fn test_set_budget_request_premium_limits() {
+        let json = r#"{"monthly_premium_limit": 300, "daily_premium_limit": 20}"#;
+        let req: SetBudgetRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.monthly_limit, None);
+        assert_eq!(req.monthly_premium_limit, Some(300.0));
+        assert_eq!(req.daily_premium_limit, Some(20.0));
+    }
+
+    #[test]
+    fn test_set_pricing_request_deserialize() {
+        let json = r#"{"input_cost_per_million": 1.5, "output_cost_per_million": 6.0}"#;
+        let req: SetPricingRequest = serde_json::from_str(json).unwrap();
+        assert!(req.provider.is_none());
+        assert!(req.cached_input_cost_per_million.is_none());
+        assert_eq!(req.output_cost_per_million, 6.0);
+    }
+
+    #[test]
+    fn test_reconciliation_query_defaults() {
+        let query: ReconciliationQuery = serde_json::from_str("{}").unwrap();
+        assert!(query.from.is_none());
+        assert!(!query.flagged);
+    }
+
     #[test]
     fn test_usage_query_defaults() {
         let json = r#"{}"#;
@@ -530,15 +2501,216 @@ mod tests {
             model: "claude-3-sonnet".to_string(),
             input_tokens: 100,
             output_tokens: 50,
+            audio_seconds: 0.0,
+            premium_requests: 0.0,
             cost: 0.001,
+            raw_cost: Some(0.001),
+            cost_saved: 0.0,
             latency_ms: 250,
             status: "success".to_string(),
             created_at: "2025-01-01 00:00:00".to_string(),
+            end_user: None,
+            tags: BTreeMap::new(),
+            impersonated_by: None,
+            finish_reason: None,
+            experiment: None,
+            experiment_variant: None,
+            key_id: None,
+            client_ip: None,
+            user_agent: None,
+            path: None,
         };
 
         let json = serde_json::to_value(&entry).unwrap();
         assert_eq!(json["provider"], "claude");
         assert_eq!(json["input_tokens"], 100);
+        assert!(json.get("tags").is_none());
+    }
+
+    fn usage_db() -> crate::db::Database {
+        let db = crate::db::Database::open_in_memory().unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO users (id, name, role) VALUES ('u1', 'alice', 'member')",
+                [],
+            )?;
+            for (i, (model, end_user, tags, cost)) in [
+                (
+                    "m1",
+                    Some("cust-1"),
+                    Some(r#"{"project":"alpha","env":"prod"}"#),
+                    1.0,
+                ),
+                ("m1", None, Some(r#"{"project":"beta"}"#), 2.0),
+                ("m2", Some("cust-1"), Some(r#"{"project":"alpha"}"#), 4.0),
+                ("m2", None, None, 8.0),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                conn.execute(
+                    "INSERT INTO usage_log (id, user_id, request_id, provider, model, cost, \
+                     end_user, tags) VALUES (?1, 'u1', ?1, 'p', ?2, ?3, ?4, ?5)",
+                    rusqlite::params![format!("r{i}"), model, cost, end_user, tags],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        db
+    }
+
+    fn summary_query(group_by: &str, tags: Option<&str>) -> UsageSummaryQuery {
+        UsageSummaryQuery {
+            group_by: group_by.to_string(),
+            user_id: None,
+            provider: None,
+            end_user: None,
+            tags: tags.map(String::from),
+            key_id: None,
+            client_ip: None,
+            user_agent: None,
+            path: None,
+            from: None,
+            to: None,
+        }
+    }
+
+    #[test]
+    fn test_usage_filtered_by_tags_and_end_user() {
+        let db = usage_db();
+        let mut query: UsageQuery = serde_json::from_str(r#"{"tags": "project=alpha"}"#).unwrap();
+        let page = usage_page(&db, &query, "USD").unwrap();
+        assert_eq!(page.total, 2);
+        assert!(page.data.iter().all(|e| e.tags["project"] == "alpha"));
+
+        query.tags = Some("project=alpha,env=prod".into());
+        assert_eq!(usage_page(&db, &query, "USD").unwrap().total, 1);
+
+        query.tags = None;
+        query.end_user = Some("cust-1".into());
+        assert_eq!(usage_page(&db, &query, "USD").unwrap().total, 2);
+
+        query.tags = Some("bad tag".into());
+        assert!(matches!(
+            usage_page(&db, &query, "USD"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_usage_filtered_by_origin() {
+        let db = usage_db();
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE usage_log SET key_id = 'k1', client_ip = '203.0.113.7', \
+                 user_agent = 'sdk/1.0', path = '/v1/chat/completions' \
+                 WHERE id IN ('r0', 'r2')",
+                [],
+            )
+        })
+        .unwrap();
+
+        let mut query: UsageQuery = serde_json::from_str(r#"{"key_id": "k1"}"#).unwrap();
+        let page = usage_page(&db, &query, "USD").unwrap();
+        assert_eq!(page.total, 2);
+        assert!(page.data.iter().all(|e| {
+            e.client_ip.as_deref() == Some("203.0.113.7")
+                && e.user_agent.as_deref() == Some("sdk/1.0")
+                && e.path.as_deref() == Some("/v1/chat/completions")
+        }));
+
+        query.key_id = None;
+        query.client_ip = Some("198.51.100.1".into());
+        assert_eq!(usage_page(&db, &query, "USD").unwrap().total, 0);
+
+        let summary = usage_summary_of(&db, &summary_query("key", None), "USD").unwrap();
+        let groups: Vec<_> = summary
+            .data
+            .iter()
+            .map(|g| (g.key.as_deref(), g.requests))
+            .collect();
+        assert_eq!(groups, [(None, 2), (Some("k1"), 2)]);
+    }
+
+    #[test]
+    fn test_usage_search_and_sort() {
+        let db = usage_db();
+        db.with_conn(|conn| {
+            conn.execute_batch(
+                "UPDATE usage_log SET latency_ms = 100 * cost, created_at = '2025-01-16 09:00:00';
+                 UPDATE usage_log SET status = 'error: upstream timeout' WHERE id = 'r1';
+                 UPDATE usage_log SET status = 'cache_hit_exact' WHERE id = 'r3';
+                 UPDATE usage_log SET created_at = '2025-01-17 12:00:00' WHERE id = 'r3';",
+            )
+        })
+        .unwrap();
+        let ids = |json: &str| -> Vec<String> {
+            let query: UsageQuery = serde_json::from_str(json).unwrap();
+            let page = usage_page(&db, &query, "USD").unwrap();
+            page.data.into_iter().map(|e| e.id).collect()
+        };
+
+        assert_eq!(ids(r#"{"model": "m2"}"#), ["r3", "r2"]);
+        assert_eq!(ids(r#"{"status": "error"}"#), ["r1"]);
+        assert_eq!(ids(r#"{"status": "cache_hit"}"#), ["r3"]);
+        assert_eq!(
+            ids(r#"{"status": "success", "sort": "cost"}"#),
+            ["r2", "r0"]
+        );
+        assert_eq!(ids(r#"{"min_cost": 1.5, "max_cost": 4.0}"#), ["r2", "r1"]);
+        assert_eq!(ids(r#"{"min_latency_ms": 400}"#), ["r3", "r2"]);
+        assert_eq!(ids(r#"{"search": "ALP"}"#), ["r2", "r0"]);
+        assert_eq!(ids(r#"{"search": "%"}"#), Vec::<String>::new());
+        // A bare `to` date includes the whole day.
+        assert_eq!(ids(r#"{"to": "2025-01-16"}"#), ["r2", "r1", "r0"]);
+
+        // Equal sort values keep insertion order, so pages never overlap.
+        assert_eq!(
+            ids(r#"{"to": "2025-01-16", "order": "asc"}"#),
+            ["r0", "r1", "r2"]
+        );
+        assert_eq!(ids(r#"{"per_page": 2, "page": 2}"#), ["r1", "r0"]);
+        assert_eq!(
+            ids(r#"{"sort": "latency_ms", "order": "asc", "per_page": 3}"#),
+            ["r0", "r1", "r2"]
+        );
+
+        for bad in [r#"{"sort": "id"}"#, r#"{"order": "up"}"#] {
+            let query: UsageQuery = serde_json::from_str(bad).unwrap();
+            assert!(matches!(
+                usage_page(&db, &query, "USD"),
+                Err(AppError::BadRequest(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_usage_summary_by_tag() {
+        let db = usage_db();
+        let summary = usage_summary_of(&db, &summary_query("tag:project", None), "USD").unwrap();
+        let groups: Vec<_> = summary
+            .data
+            .iter()
+            .map(|g| (g.key.as_deref(), g.requests, g.cost))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (None, 1, 8.0),
+                (Some("alpha"), 2, 5.0),
+                (Some("beta"), 1, 2.0)
+            ]
+        );
+        assert_eq!(summary.total_cost, 15.0);
+
+        let summary =
+            usage_summary_of(&db, &summary_query("model", Some("project=alpha")), "USD").unwrap();
+        assert_eq!(summary.data.len(), 2);
+        assert_eq!(summary.total_cost, 5.0);
+
+        assert!(usage_summary_of(&db, &summary_query("tag:x'y", None), "USD").is_err());
+        assert!(usage_summary_of(&db, &summary_query("color", None), "USD").is_err());
     }
 
     #[test]
@@ -548,6 +2720,7 @@ mod tests {
             page: 1,
             per_page: 50,
             total: 0,
+            currency: "USD".to_string(),
         };
 
         let json = serde_json::to_value(&response).unwrap();
@@ -572,12 +2745,14 @@ mod tests {
             key_prefix: "sk-prx-abcd1234...".to_string(),
             label: "test".to_string(),
             created_at: "2025-01-01 00:00:00".to_string(),
+            reasoning_mode: ReasoningMode::Strip,
             plaintext: "sk-prx-full-key-here".to_string(),
         };
 
         let json = serde_json::to_value(&response).unwrap();
         assert_eq!(json["plaintext"], "sk-prx-full-key-here");
         assert_eq!(json["key_prefix"], "sk-prx-abcd1234...");
+        assert_eq!(json["reasoning_mode"], "strip");
     }
 
     #[test]
@@ -603,4 +2778,33 @@ mod tests {
         assert_eq!(req.key, "server.port");
         assert_eq!(req.value, serde_json::json!(9090));
     }
+
+    #[test]
+    fn test_simulate_routing_request_defaults() {
+        let json = r#"{"model": "gaud:smart", "prompt": "hello"}"#;
+        let req: SimulateRoutingRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.model, "gaud:smart");
+        assert!(req.user.is_none() && req.hour_utc.is_none() && req.rules.is_none());
+        assert_eq!(req.priority, Priority::Default);
+        assert_eq!(req.prompt.chars().count(), 5);
+    }
+
+    #[test]
+    fn test_simulate_routing_response_flattens_simulation() {
+        let response = SimulateRoutingResponse {
+            user: "alice".to_string(),
+            team: None,
+            input_chars: 5,
+            hour_utc: 9,
+            simulation: RoutingSimulation {
+                model: "claude-a".to_string(),
+                decision: None,
+                providers: vec!["kiro".to_string()],
+                unavailable: Vec::new(),
+            },
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["model"], "claude-a");
+        assert_eq!(json["providers"], serde_json::json!(["kiro"]));
+    }
 }
@@ -0,0 +1,795 @@
+//! Server-side agent runs (`/v1/agents/run`).
+//!
+//! A run gives a model a prompt and a set of tools and keeps calling it
+//! until it answers without asking for a tool. Tools are either callbacks
+//! (gaud POSTs the call to a URL the caller names) or tools of a configured
+//! MCP server (`[mcp.servers]`). Each model call goes through
+//! [`complete_chat`], so routing, caching, the usage log and token scopes
+//! apply per step. A run stops at its step limit or cost cap, and every
+//! model and tool call is returned as a trace, or streamed as it happens.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::time::{Duration, Instant};
+
+use axum::Extension;
+use axum::Json;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use url::Url;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::chat::complete_chat;
+use crate::auth::AuthUser;
+use crate::budget::Attribution;
+use crate::budget::tracker::BudgetStatus;
+use crate::error::AppError;
+use crate::mcp::client::TOOL_SEPARATOR;
+use crate::net;
+use crate::providers::redact;
+use crate::providers::types::{
+    ChatMessage, ChatRequest, FunctionDef, MessageContent, MessageRole, Tool, ToolCall, Usage,
+};
+
+/// Longest tool output passed back to the model, in bytes.
+const MAX_TOOL_OUTPUT_BYTES: usize = 64 * 1024;
+/// Longest tool name the providers accept.
+const MAX_TOOL_NAME: usize = 64;
+
+// ---------------------------------------------------------------------------
+// Request types
+// ---------------------------------------------------------------------------
+
+/// Body of `POST /v1/agents/run`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AgentRunRequest {
+    pub model: String,
+    /// User message the run starts from. Appended after `messages`.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Conversation the run continues.
+    #[serde(default)]
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub tools: Vec<AgentTool>,
+    /// Most model calls; defaults to, and may not exceed, `agents.max_steps`.
+    #[serde(default)]
+    pub max_steps: Option<u32>,
+    /// Cost in USD after which no further step is started.
+    #[serde(default)]
+    pub max_cost: Option<f64>,
+    /// Per model call.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Stream the trace as server-sent events.
+    #[serde(default)]
+    pub stream: bool,
+}
+
+/// A tool an agent run may call.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentTool {
+    /// Calls are POSTed to `url` as `{name, arguments, run_id, call_id}`.
+    Callback {
+        name: String,
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        parameters: Option<Value>,
+        url: String,
+    },
+    /// One tool of a configured MCP server, or all of them when `tool` is
+    /// omitted. The model sees them as `<server>__<tool>`.
+    Mcp {
+        server: String,
+        #[serde(default)]
+        tool: Option<String>,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// Handler
+// ---------------------------------------------------------------------------
+
+/// POST /v1/agents/run
+///
+/// Run the tool-use loop server-side. Returns the final answer with the
+/// trace of every step, or with `stream: true`, an SSE stream of
+/// `agent.step` events ending in `agent.completed` (or `error`).
+pub async fn run_agent(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Json(request): Json<AgentRunRequest>,
+) -> Result<Response, AppError> {
+    let attribution = Attribution::from_request(&headers, None)?;
+    let stream = request.stream;
+    let run = AgentRun::prepare(state, user, request, attribution).await?;
+    tracing::info!(
+        run_id = %run.id,
+        user_id = %run.user.user_id,
+        model = %run.model,
+        tools = run.tools.len(),
+        max_steps = run.max_steps,
+        "Agent run"
+    );
+
+    if !stream {
+        return Ok(Json(run.execute(None).await?).into_response());
+    }
+
+    let (tx, rx) = mpsc::channel::<Event>(32);
+    tokio::spawn(async move {
+        let last = match run.execute(Some(tx.clone())).await {
+            Ok(result) => Event::default()
+                .event("agent.completed")
+                .data(result.to_string()),
+            Err(e) => Event::default().event("error").data(e.to_json()),
+        };
+        let _ = tx.send(last).await;
+    });
+    let events = ReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    Ok(Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+// ---------------------------------------------------------------------------
+// Run
+// ---------------------------------------------------------------------------
+
+/// Where a tool's calls go.
+enum ToolTarget {
+    Callback(Url),
+    Mcp,
+}
+
+/// Why a run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunStatus {
+    Completed,
+    MaxSteps,
+    MaxCost,
+}
+
+impl RunStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::MaxSteps => "max_steps",
+            Self::MaxCost => "max_cost",
+        }
+    }
+}
+
+/// A validated agent run, ready to execute.
+struct AgentRun {
+    id: String,
+    state: AppState,
+    user: AuthUser,
+    attribution: Attribution,
+    model: String,
+    messages: Vec<ChatMessage>,
+    tools: Vec<Tool>,
+    targets: HashMap<String, ToolTarget>,
+    /// Client for callbacks, with their hosts pinned to the addresses
+    /// checked in [`AgentRun::prepare`].
+    http: reqwest::Client,
+    max_steps: u32,
+    max_cost: Option<f64>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+}
+
+impl AgentRun {
+    /// Validate `request` and resolve its tools.
+    async fn prepare(
+        state: AppState,
+        user: AuthUser,
+        request: AgentRunRequest,
+        attribution: Attribution,
+    ) -> Result<Self, AppError> {
+        let config = &state.config.agents;
+        if request.prompt.is_none() && request.messages.is_empty() {
+            return Err(invalid("prompt", "Either prompt or messages is required"));
+        }
+        let max_steps = request.max_steps.unwrap_or(config.max_steps);
+        if max_steps == 0 || max_steps > config.max_steps {
+            return Err(invalid(
+                "max_steps",
+                format!("max_steps must be between 1 and {}", config.max_steps),
+            ));
+        }
+        if let Some(cost) = request.max_cost
+            && !(cost.is_finite() && cost > 0.0)
+        {
+            return Err(invalid("max_cost", "max_cost must be a positive number"));
+        }
+
+        let mut tools = Vec::new();
+        let mut targets = HashMap::new();
+        let mut http = net::HttpClientFactory::new(&state.config.providers)
+            .builder("agents")
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_secs(config.callback_timeout_secs));
+        for (i, tool) in request.tools.into_iter().enumerate() {
+            let param = format!("tools[{i}]");
+            let resolved = match tool {
+                AgentTool::Callback {
+                    name,
+                    description,
+                    parameters,
+                    url,
+                } => {
+                    if !valid_tool_name(&name) {
+                        return Err(invalid(
+                            &format!("{param}.name"),
+                            "Tool names are 1-64 letters, digits, '_' or '-'",
+                        ));
+                    }
+                    let (url, pinned) = check_callback_url(&url, config.allow_private_callbacks)
+                        .await
+                        .map_err(|message| invalid(&format!("{param}.url"), message))?;
+                    if let Some((host, addr)) = pinned {
+                        http = http.resolve(&host, addr);
+                    }
+                    vec![(
+                        function_tool(name, description, parameters),
+                        ToolTarget::Callback(url),
+                    )]
+                }
+                AgentTool::Mcp { server, tool } => {
+                    let available =
+                        state.mcp_tools.server_tools(&server).await.ok_or_else(|| {
+                            invalid(
+                                &format!("{param}.server"),
+                                format!("Unknown MCP server '{server}'"),
+                            )
+                        })?;
+                    let selected: Vec<Tool> = match &tool {
+                        Some(tool) => {
+                            let name = format!("{server}{TOOL_SEPARATOR}{tool}");
+                            available
+                                .into_iter()
+                                .filter(|t| t.function.name == name)
+                                .collect()
+                        }
+                        None => available,
+                    };
+                    if selected.is_empty() {
+                        return Err(invalid(
+                            &format!("{param}.tool"),
+                            match tool {
+                                Some(tool) => format!("MCP server '{server}' has no tool '{tool}'"),
+                                None => format!("MCP server '{server}' has no tools available"),
+                            },
+                        ));
+                    }
+                    selected.into_iter().map(|t| (t, ToolTarget::Mcp)).collect()
+                }
+            };
+            for (tool, target) in resolved {
+                let name = tool.function.name.clone();
+                if targets.insert(name.clone(), target).is_some() {
+                    return Err(invalid(&param, format!("Duplicate tool name '{name}'")));
+                }
+                tools.push(tool);
+            }
+        }
+        let http = http
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to build callback client: {e}")))?;
+
+        let mut messages = Vec::new();
+        if let Some(system) = request.system.filter(|s| !s.is_empty()) {
+            messages.push(ChatMessage::text(MessageRole::System, system));
+        }
+        messages.extend(request.messages);
+        if let Some(prompt) = request.prompt {
+            messages.push(ChatMessage::text(MessageRole::User, prompt));
+        }
+
+        Ok(Self {
+            id: format!("agent-{}", Uuid::new_v4().simple()),
+            state,
+            user,
+            attribution,
+            model: request.model,
+            messages,
+            tools,
+            targets,
+            http,
+            max_steps,
+            max_cost: request.max_cost,
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+        })
+    }
+
+    /// Run the loop to the end. Each step is also sent to `trace` when
+    /// given; the run stops early if its receiver goes away.
+    async fn execute(mut self, trace: Option<mpsc::Sender<Event>>) -> Result<Value, AppError> {
+        let mut steps = Vec::new();
+        let mut usage = Usage::default();
+        let mut cost = 0.0;
+        let mut output = None;
+        let mut status = RunStatus::MaxSteps;
+
+        for step in 1..=self.max_steps {
+            self.check_budget()?;
+            let request = ChatRequest {
+                model: self.model.clone(),
+                messages: self.messages.clone(),
+                temperature: self.temperature,
+                max_tokens: self.max_tokens,
+                stream: false,
+                top_p: None,
+                stop: None,
+                tools: (!self.tools.is_empty()).then(|| self.tools.clone()),
+                tool_choice: None,
+                stream_options: None,
+                google_search: false,
+                extra_body: None,
+                user: None,
+            };
+            let (mut response, _) = complete_chat(
+                self.state.clone(),
+                self.user.clone(),
+                request,
+                format!("{}-{step}", self.id),
+                self.attribution.clone(),
+            )
+            .await?;
+
+            let step_cost = self
+                .state
+                .cost_calculator
+                .calculate_cost(&response.model, &response.usage);
+            cost += step_cost;
+            usage.prompt_tokens += response.usage.prompt_tokens;
+            usage.completion_tokens += response.usage.completion_tokens;
+            usage.total_tokens += response.usage.total_tokens;
+
+            let (content, calls) = match response.choices.first_mut() {
+                Some(choice) => (
+                    choice.message.content.take(),
+                    choice.message.tool_calls.take().unwrap_or_default(),
+                ),
+                None => (None, Vec::new()),
+            };
+            if !self
+                .record(
+                    &mut steps,
+                    &trace,
+                    json!({
+                        "type": "model_call",
+                        "step": step,
+                        "content": content,
+                        "tool_calls": calls,
+                        "usage": response.usage,
+                        "cost": step_cost,
+                    }),
+                )
+                .await
+            {
+                return Err(AppError::Internal("Agent run abandoned".to_string()));
+            }
+            output = content.clone();
+
+            if calls.is_empty() {
+                status = RunStatus::Completed;
+                break;
+            }
+            // Don't run tools whose results no later step would see.
+            if step == self.max_steps {
+                break;
+            }
+            if self.max_cost.is_some_and(|max| cost >= max) {
+                status = RunStatus::MaxCost;
+                break;
+            }
+
+            self.messages.push(ChatMessage {
+                role: MessageRole::Assistant,
+                content: content.map(MessageContent::Text),
+                name: None,
+                tool_calls: Some(calls.clone()),
+                tool_call_id: None,
+            });
+            for call in &calls {
+                let start = Instant::now();
+                let (result, is_error) = self.call_tool(call).await;
+                let record = json!({
+                    "type": "tool_call",
+                    "step": step,
+                    "call_id": call.id,
+                    "name": call.function.name,
+                    "arguments": call.function.arguments,
+                    "output": result,
+                    "is_error": is_error,
+                    "latency_ms": start.elapsed().as_millis() as u64,
+                });
+                if !self.record(&mut steps, &trace, record).await {
+                    return Err(AppError::Internal("Agent run abandoned".to_string()));
+                }
+                self.messages.push(ChatMessage {
+                    role: MessageRole::Tool,
+                    content: Some(MessageContent::Text(result)),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: Some(call.id.clone()),
+                });
+            }
+        }
+
+        tracing::info!(
+            run_id = %self.id,
+            status = status.as_str(),
+            steps = steps.len(),
+            cost,
+            "Agent run finished"
+        );
+        Ok(json!({
+            "id": self.id,
+            "object": "agent.run",
+            "created": chrono::Utc::now().timestamp(),
+            "model": self.model,
+            "status": status.as_str(),
+            "output": output,
+            "steps": steps,
+            "usage": usage,
+            "cost": cost,
+        }))
+    }
+
+    /// Keep `step` for the result and stream it. False when the stream's
+    /// client has gone.
+    async fn record(
+        &self,
+        steps: &mut Vec<Value>,
+        trace: &Option<mpsc::Sender<Event>>,
+        step: Value,
+    ) -> bool {
+        if let Some(tx) = trace {
+            let event = Event::default().event("agent.step").data(step.to_string());
+            if tx.send(event).await.is_err() {
+                tracing::debug!(run_id = %self.id, "Agent run stream closed; stopping");
+                return false;
+            }
+        }
+        steps.push(step);
+        true
+    }
+
+    /// A run can outlast the budget it started under; check before each
+    /// model call.
+    fn check_budget(&self) -> Result<(), AppError> {
+        let config = self.state.settings.config();
+        if config.budget.enabled
+            && self
+                .state
+                .budget
+                .check_budget(&self.user.user_id, config.budget.warning_threshold_percent)?
+                == BudgetStatus::Exceeded
+        {
+            return Err(AppError::BudgetExceeded(format!(
+                "Budget exceeded for user '{}'",
+                self.user.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Run one tool call, returning its output and whether it failed.
+    /// Failures are given to the model rather than ending the run.
+    async fn call_tool(&self, call: &ToolCall) -> (String, bool) {
+        let (output, is_error) = match self.targets.get(&call.function.name) {
+            Some(ToolTarget::Callback(url)) => match self.post_callback(url, call).await {
+                Ok(output) => (output, false),
+                Err(e) => (format!("Error: {e}"), true),
+            },
+            Some(ToolTarget::Mcp) => {
+                let output = self.state.mcp_tools.call(call).await;
+                let is_error = output.starts_with("Error:");
+                (output, is_error)
+            }
+            None => (format!("Error: unknown tool {}", call.function.name), true),
+        };
+        (redact::truncate(output, MAX_TOOL_OUTPUT_BYTES), is_error)
+    }
+
+    /// POST a call to a callback tool. A JSON reply's `output` field is
+    /// the result; any other reply body is taken as is.
+    async fn post_callback(&self, url: &Url, call: &ToolCall) -> Result<String, String> {
+        let arguments = match call.function.arguments.trim() {
+            "" => json!({}),
+            raw => serde_json::from_str::<Value>(raw)
+                .map_err(|e| format!("tool arguments are not valid JSON: {e}"))?,
+        };
+        let response = self
+            .http
+            .post(url.clone())
+            .json(&json!({
+                "name": call.function.name,
+                "arguments": arguments,
+                "run_id": self.id,
+                "call_id": call.id,
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("callback failed: {e}"))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("callback failed: {e}"))?;
+        if !status.is_success() {
+            return Err(format!(
+                "callback returned {status}: {}",
+                redact::summary(&body)
+            ));
+        }
+        Ok(match serde_json::from_str::<Value>(&body) {
+            Ok(Value::Object(mut reply)) if reply.contains_key("output") => {
+                match reply.remove("output") {
+                    Some(Value::String(text)) => text,
+                    Some(other) => other.to_string(),
+                    None => String::new(),
+                }
+            }
+            _ => body,
+        })
+    }
+}
+
+/// Check a callback URL. Unless `allow_private`, its host must resolve only
+/// to public addresses; the first is returned so the host can be pinned to
+/// it, keeping a later lookup from pointing the call elsewhere.
+async fn check_callback_url(
+    raw: &str,
+    allow_private: bool,
+) -> Result<(Url, Option<(String, std::net::SocketAddr)>), String> {
+    let url = Url::parse(raw).map_err(|e| format!("Invalid callback URL: {e}"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err("Callback URLs must be http or https".to_string());
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| "Callback URL has no host".to_string())?
+        .to_string();
+    if allow_private {
+        return Ok((url, None));
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<_> = tokio::net::lookup_host((lookup_host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve {host}: {e}"))?
+        .collect();
+    if addrs.is_empty() || addrs.iter().any(|a| !net::is_public_ip(&a.ip())) {
+        return Err(format!(
+            "Callback host {host} is not a public address (see agents.allow_private_callbacks)"
+        ));
+    }
+    let pinned = url.domain().map(|domain| (domain.to_string(), addrs[0]));
+    Ok((url, pinned))
+}
+
+fn valid_tool_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_TOOL_NAME
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn function_tool(name: String, description: Option<String>, parameters: Option<Value>) -> Tool {
+    Tool {
+        r#type: "function".to_string(),
+        function: FunctionDef {
+            name,
+            description,
+            parameters: Some(parameters.unwrap_or_else(|| json!({"type": "object"}))),
+        },
+    }
+}
+
+fn invalid(param: &str, message: impl Into<String>) -> AppError {
+    AppError::InvalidParam {
+        param: param.to_string(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::api::test_support::{litellm_router, test_state, user};
+    use crate::config::Config;
+
+    fn completion(message: Value, finish: &str) -> Value {
+        json!({
+            "id": "c1", "object": "chat.completion", "created": 0, "model": "m",
+            "choices": [{"index": 0, "message": message, "finish_reason": finish}],
+            "usage": {"prompt_tokens": 10, "completion_tokens": 2, "total_tokens": 12},
+        })
+    }
+
+    fn lookup_call() -> Value {
+        json!({"role": "assistant", "content": null, "tool_calls": [
+            {"id": "call_1", "type": "function",
+             "function": {"name": "lookup", "arguments": "{\"sku\":\"A1\"}"}}]})
+    }
+
+    async fn run(state: &AppState, body: Value) -> Result<Value, AppError> {
+        let request: AgentRunRequest = serde_json::from_value(body).unwrap();
+        AgentRun::prepare(state.clone(), user(), request, Attribution::default())
+            .await?
+            .execute(None)
+            .await
+    }
+
+    fn param_of(err: AppError) -> String {
+        match err {
+            AppError::InvalidParam { param, .. } => param,
+            other => panic!("expected InvalidParam, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_validation() {
+        let state = test_state(
+            Config::default(),
+            crate::providers::router::ProviderRouter::new(),
+        );
+        let cases = [
+            (json!({"model": "m"}), "prompt"),
+            (
+                json!({"model": "m", "prompt": "hi", "max_steps": 0}),
+                "max_steps",
+            ),
+            (
+                json!({"model": "m", "prompt": "hi", "max_steps": 11}),
+                "max_steps",
+            ),
+            (
+                json!({"model": "m", "prompt": "hi", "max_cost": -1.0}),
+                "max_cost",
+            ),
+            (
+                json!({"model": "m", "prompt": "hi", "tools": [
+                    {"type": "callback", "name": "bad name", "url": "https://example.com"}]}),
+                "tools[0].name",
+            ),
+            (
+                json!({"model": "m", "prompt": "hi", "tools": [
+                    {"type": "callback", "name": "t", "url": "ftp://example.com"}]}),
+                "tools[0].url",
+            ),
+            (
+                json!({"model": "m", "prompt": "hi", "tools": [
+                    {"type": "callback", "name": "t", "url": "http://127.0.0.1:9/hook"}]}),
+                "tools[0].url",
+            ),
+            (
+                json!({"model": "m", "prompt": "hi", "tools": [
+                    {"type": "callback", "name": "t", "url": "http://[::1]/hook"}]}),
+                "tools[0].url",
+            ),
+            (
+                json!({"model": "m", "prompt": "hi", "tools": [{"type": "mcp", "server": "nope"}]}),
+                "tools[0].server",
+            ),
+        ];
+        for (body, param) in cases {
+            let err = run(&state, body.clone()).await.unwrap_err();
+            assert_eq!(param_of(err), param, "{body}");
+        }
+
+        let mut config = Config::default();
+        config.agents.allow_private_callbacks = true;
+        let state = test_state(config, crate::providers::router::ProviderRouter::new());
+        let err = run(
+            &state,
+            json!({"model": "m", "prompt": "hi", "tools": [
+                {"type": "callback", "name": "t", "url": "http://127.0.0.1:9/a"},
+                {"type": "callback", "name": "t", "url": "http://127.0.0.1:9/b"}]}),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(param_of(err), "tools[1]");
+    }
+
+    #[tokio::test]
+    async fn test_run_calls_back_and_traces_steps() {
+        let llm = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({"messages": [{}, {}, {"role": "tool", "content": "3 in stock", "tool_call_id": "call_1"}]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion(
+                json!({"role": "assistant", "content": "There are 3 left."}),
+                "stop",
+            )))
+            .mount(&llm)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(
+                json!({"tools": [{"function": {"name": "lookup"}}]}),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(completion(lookup_call(), "tool_calls")),
+            )
+            .mount(&llm)
+            .await;
+
+        let hook = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/stock"))
+            .and(body_partial_json(
+                json!({"name": "lookup", "arguments": {"sku": "A1"}, "call_id": "call_1"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"output": "3 in stock"})))
+            .expect(1)
+            .mount(&hook)
+            .await;
+
+        let mut config = Config::default();
+        config.agents.allow_private_callbacks = true;
+        let state = test_state(config, litellm_router(&llm.uri()).await);
+        let tools = json!([{"type": "callback", "name": "lookup",
+            "parameters": {"type": "object"}, "url": format!("{}/stock", hook.uri())}]);
+
+        let result = run(
+            &state,
+            json!({"model": "m", "prompt": "How many A1?", "tools": tools}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result["object"], "agent.run");
+        assert_eq!(result["status"], "completed");
+        assert_eq!(result["output"], "There are 3 left.");
+        assert_eq!(result["usage"]["total_tokens"], 24);
+        let steps = result["steps"].as_array().unwrap();
+        let kinds: Vec<_> = steps.iter().map(|s| s["type"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["model_call", "tool_call", "model_call"]);
+        assert_eq!(steps[1]["output"], "3 in stock");
+        assert_eq!(steps[1]["is_error"], false);
+        assert_eq!(steps[2]["step"], 2);
+
+        // At the step limit the requested tool is not run.
+        let result = run(
+            &state,
+            json!({"model": "m", "prompt": "How many A1?", "tools": tools, "max_steps": 1}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result["status"], "max_steps");
+        assert_eq!(result["steps"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_tool_names() {
+        assert!(valid_tool_name("get_weather-2"));
+        assert!(!valid_tool_name(""));
+        assert!(!valid_tool_name("a.b"));
+        assert!(!valid_tool_name(&"x".repeat(65)));
+        assert_eq!(redact::truncate("héllo".to_string(), 2), "h...");
+    }
+}
@@ -0,0 +1,347 @@
+use std::time::Instant;
+
+use axum::Extension;
+use axum::Json;
+use axum::body::Body;
+use axum::extract::{Multipart, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::auth::AuthUser;
+use crate::budget::tracker::BudgetStatus;
+use crate::budget::{Attribution, AuditEntry};
+use crate::error::AppError;
+use crate::providers::types::{SpeechRequest, TranscriptionRequest, TranscriptionResponse};
+
+/// Largest audio upload accepted for transcription (matches the OpenAI limit).
+pub const MAX_AUDIO_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+/// Longest text accepted for speech synthesis (matches the OpenAI limit).
+const MAX_SPEECH_INPUT_CHARS: usize = 4096;
+
+const TRANSCRIPTION_FORMATS: &[&str] = &["json", "text", "srt", "verbose_json", "vtt"];
+
+// ---------------------------------------------------------------------------
+// Transcription
+// ---------------------------------------------------------------------------
+
+/// POST /v1/audio/transcriptions
+///
+/// OpenAI-compatible speech-to-text. Accepts a multipart upload with `file`
+/// and `model` fields. The audio duration reported by the provider is
+/// recorded as `audio_seconds` and billed per minute.
+pub async fn create_transcription(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    multipart: Multipart,
+) -> Result<Response, AppError> {
+    let request_id = Uuid::new_v4().to_string();
+    let request = read_transcription_form(multipart).await?;
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %request.model,
+        bytes = request.file.len(),
+        "Transcription request"
+    );
+
+    // The duration is only known once the provider has processed the file,
+    // so only users who are already over budget are turned away.
+    if state.config.budget.enabled
+        && state.budget.check_budget(
+            &user.user_id,
+            state.settings.config().budget.warning_threshold_percent,
+        )? == BudgetStatus::Exceeded
+    {
+        return Err(AppError::BudgetExceeded(format!(
+            "Budget exceeded for user '{}'",
+            user.name
+        )));
+    }
+
+    let attribution = Attribution::default().with_user(&user);
+    let start = Instant::now();
+    let mut router = state.router.write().await;
+    let result = router.transcribe(&request).await;
+    drop(router);
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(response) => {
+            let audio_seconds = response.duration.unwrap_or(0.0);
+            let cost = state
+                .cost_calculator
+                .calculate_transcription_cost(&request.model, audio_seconds);
+
+            let _ = state.audit_tx.send(AuditEntry {
+                user_id: user.user_id,
+                request_id,
+                provider: request.model.clone(),
+                model: request.model.clone(),
+                input_tokens: 0,
+                output_tokens: 0,
+                audio_seconds,
+                premium_requests: 0.0,
+                cost,
+                cost_saved: 0.0,
+                latency_ms,
+                status: "success".to_string(),
+                finish_reason: None,
+                attribution,
+            });
+
+            Ok(render_transcription(
+                request.response_format.as_deref(),
+                response,
+            ))
+        }
+        Err(e) => {
+            let _ = state.audit_tx.send(AuditEntry {
+                user_id: user.user_id,
+                request_id,
+                provider: String::new(),
+                model: request.model,
+                input_tokens: 0,
+                output_tokens: 0,
+                audio_seconds: 0.0,
+                premium_requests: 0.0,
+                cost: 0.0,
+                cost_saved: 0.0,
+                latency_ms,
+                status: format!("error: {e}"),
+                finish_reason: None,
+                attribution,
+            });
+
+            Err(AppError::from(e))
+        }
+    }
+}
+
+/// Collect the multipart fields of a transcription upload.
+async fn read_transcription_form(
+    mut multipart: Multipart,
+) -> Result<TranscriptionRequest, AppError> {
+    let bad_form = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("Invalid multipart body: {e}"))
+    };
+
+    let mut model = None;
+    let mut file = None;
+    let mut request = TranscriptionRequest {
+        model: String::new(),
+        file: Default::default(),
+        filename: String::new(),
+        content_type: None,
+        language: None,
+        prompt: None,
+        response_format: None,
+        temperature: None,
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(bad_form)? {
+        match field.name().unwrap_or_default() {
+            "file" => {
+                request.filename = field.file_name().unwrap_or("audio").to_string();
+                request.content_type = field.content_type().map(String::from);
+                file = Some(field.bytes().await.map_err(bad_form)?);
+            }
+            "model" => model = Some(field.text().await.map_err(bad_form)?),
+            "language" => request.language = Some(field.text().await.map_err(bad_form)?),
+            "prompt" => request.prompt = Some(field.text().await.map_err(bad_form)?),
+            "response_format" => {
+                request.response_format = Some(field.text().await.map_err(bad_form)?)
+            }
+            "temperature" => {
+                let value = field.text().await.map_err(bad_form)?;
+                request.temperature =
+                    Some(value.trim().parse().map_err(|_| AppError::InvalidParam {
+                        param: "temperature".into(),
+                        message: format!("'{value}' is not a number"),
+                    })?);
+            }
+            // Ignore fields we don't forward (e.g. timestamp_granularities[]).
+            _ => {}
+        }
+    }
+
+    request.model = model
+        .filter(|m| !m.trim().is_empty())
+        .ok_or_else(|| AppError::BadRequest("Missing 'model' field".into()))?;
+    request.file = file
+        .filter(|f| !f.is_empty())
+        .ok_or_else(|| AppError::BadRequest("Missing 'file' field".into()))?;
+
+    let unsupported = request
+        .response_format
+        .as_deref()
+        .filter(|f| !TRANSCRIPTION_FORMATS.contains(f));
+    if let Some(format) = unsupported {
+        return Err(AppError::InvalidParam {
+            param: "response_format".into(),
+            message: format!("unsupported format '{format}'"),
+        });
+    }
+
+    Ok(request)
+}
+
+/// Shape a provider transcription into the format the client asked for.
+fn render_transcription(format: Option<&str>, response: TranscriptionResponse) -> Response {
+    match format {
+        Some("text") | Some("srt") => (
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            response.text,
+        )
+            .into_response(),
+        Some("vtt") => ([(header::CONTENT_TYPE, "text/vtt")], response.text).into_response(),
+        Some("verbose_json") => Json(response).into_response(),
+        _ => Json(serde_json::json!({ "text": response.text })).into_response(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Speech
+// ---------------------------------------------------------------------------
+
+/// POST /v1/audio/speech
+///
+/// OpenAI-compatible text-to-speech. Audio is streamed back to the client as
+/// the provider produces it. Speech is billed per input character, so the
+/// cost is checked against the user's budget before the provider is called.
+pub async fn create_speech(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(request): Json<SpeechRequest>,
+) -> Result<Response, AppError> {
+    let request_id = Uuid::new_v4().to_string();
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %request.model,
+        voice = %request.voice,
+        "Speech request"
+    );
+
+    let characters = request.input.chars().count();
+    if characters == 0 {
+        return Err(AppError::InvalidParam {
+            param: "input".into(),
+            message: "must not be empty".into(),
+        });
+    }
+    if characters > MAX_SPEECH_INPUT_CHARS {
+        return Err(AppError::InvalidParam {
+            param: "input".into(),
+            message: format!("must be at most {MAX_SPEECH_INPUT_CHARS} characters"),
+        });
+    }
+
+    let cost = state
+        .cost_calculator
+        .calculate_speech_cost(&request.model, characters);
+    if state.config.budget.enabled
+        && state.budget.check_projected_cost(&user.user_id, cost)? == BudgetStatus::Exceeded
+    {
+        return Err(AppError::BudgetExceeded(format!(
+            "Synthesizing {characters} characters (${cost:.4}) would exceed your budget"
+        )));
+    }
+
+    let attribution = Attribution::default().with_user(&user);
+    let start = Instant::now();
+    let mut router = state.router.write().await;
+    let result = router.speech(&request).await;
+    drop(router);
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(speech) => {
+            let _ = state.audit_tx.send(AuditEntry {
+                user_id: user.user_id,
+                request_id,
+                provider: request.model.clone(),
+                model: request.model,
+                input_tokens: 0,
+                output_tokens: 0,
+                audio_seconds: 0.0,
+                premium_requests: 0.0,
+                cost,
+                cost_saved: 0.0,
+                latency_ms,
+                status: "success".to_string(),
+                finish_reason: None,
+                attribution,
+            });
+
+            Ok((
+                [(header::CONTENT_TYPE, speech.content_type)],
+                Body::from_stream(speech.audio),
+            )
+                .into_response())
+        }
+        Err(e) => {
+            let _ = state.audit_tx.send(AuditEntry {
+                user_id: user.user_id,
+                request_id,
+                provider: String::new(),
+                model: request.model,
+                input_tokens: 0,
+                output_tokens: 0,
+                audio_seconds: 0.0,
+                premium_requests: 0.0,
+                cost: 0.0,
+                cost_saved: 0.0,
+                latency_ms,
+                status: format!("error: {e}"),
+                finish_reason: None,
+                attribution,
+            });
+
+            Err(AppError::from(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcription() -> TranscriptionResponse {
+        TranscriptionResponse {
+            text: "hello world".to_string(),
+            language: Some("english".to_string()),
+            duration: Some(1.5),
+            ..Default::default()
+        }
+    }
+
+    async fn body_string(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_render_transcription_formats() {
+        let json = render_transcription(None, transcription());
+        assert_eq!(body_string(json).await, r#"{"text":"hello world"}"#);
+
+        let text = render_transcription(Some("text"), transcription());
+        assert_eq!(
+            text.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(body_string(text).await, "hello world");
+
+        let verbose = render_transcription(Some("verbose_json"), transcription());
+        let value: serde_json::Value = serde_json::from_str(&body_string(verbose).await).unwrap();
+        assert_eq!(value["duration"], 1.5);
+        assert_eq!(value["language"], "english");
+    }
+}
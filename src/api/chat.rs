@@ -6,29 +6,62 @@ use std::time::Instant;
 use axum::Extension;
 use axum::Json;
 use axum::extract::State;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
-use tokio_stream::Stream;
+use tokio_stream::{Stream, StreamExt};
+use tokio_util::task::TaskTracker;
 use uuid::Uuid;
 
 use crate::AppState;
 use crate::auth::AuthUser;
-use crate::budget::AuditEntry;
-use crate::cache::StreamCacheOps;
+use crate::auth::{tokens, users};
+use crate::budget::middleware::preflight;
+use crate::budget::scheduler::{self, Priority};
+use crate::budget::streams::PermittedStream;
+use crate::budget::tracker::BudgetStatus;
+use crate::budget::{Attribution, AuditEntry};
+use crate::cache::{SemanticCacheService, StreamCacheOps};
+use crate::config::LimitsConfig;
 use crate::error::AppError;
+use crate::experiments::{self, Assignment};
+use crate::providers::ProviderError;
+use crate::providers::capabilities;
 use crate::providers::cost::CostCalculator;
-use crate::providers::types::{ChatChunk, ChatRequest, Usage, UsageTokenDetails};
+use crate::providers::params;
+use crate::providers::ratelimit::UpstreamRateLimit;
+use crate::providers::reasoning::{self, ReasoningFilter};
+use crate::providers::router::VIRTUAL_MODEL_PREFIX;
+use crate::providers::rules::{RequestFacts, RoutingDecision};
+use crate::providers::tokens as token_count;
+use crate::providers::tool_emulation;
+use crate::providers::types::{
+    ChatChunk, ChatMessage, ChatRequest, ChatResponse, ContentPart, MessageContent, MessageRole,
+    Usage, UsageTokenDetails,
+};
+use crate::uploads;
 
 /// POST /v1/chat/completions
 ///
 /// OpenAI-compatible chat completion endpoint supporting both streaming
-/// (SSE) and non-streaming JSON responses.
+/// (SSE) and non-streaming JSON responses. Streaming requests may carry a
+/// `Last-Event-ID` header to resume an interrupted stream (see
+/// [`crate::api::resume`]).
 pub async fn chat_completions(
     State(state): State<AppState>,
     Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
     Json(request): Json<ChatRequest>,
 ) -> Result<Response, AppError> {
-    let request_id = Uuid::new_v4().to_string();
+    // Log under the `X-Request-ID` returned to the client, so a response can
+    // be matched to its usage entry.
+    let request_id = headers
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map_or_else(|| Uuid::new_v4().to_string(), String::from);
+
+    let attribution = Attribution::from_request(&headers, request.user.as_deref())?;
 
     tracing::info!(
         request_id = %request_id,
@@ -39,9 +72,13 @@ pub async fn chat_completions(
     );
 
     if request.stream {
-        handle_streaming(state, user, request, request_id).await
+        let last_event_id = headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        handle_streaming(state, user, request, request_id, attribution, last_event_id).await
     } else {
-        handle_non_streaming(state, user, request, request_id).await
+        handle_non_streaming(state, user, request, request_id, attribution).await
     }
 }
 
@@ -51,23 +88,53 @@ async fn handle_non_streaming(
     user: AuthUser,
     request: ChatRequest,
     request_id: String,
+    attribution: Attribution,
 ) -> Result<Response, AppError> {
+    let (response, upstream) = complete_chat(state, user, request, request_id, attribution).await?;
+    let mut response = Json(response).into_response();
+    insert_upstream_rate_limit_headers(response.headers_mut(), upstream.as_ref());
+    Ok(response)
+}
+
+/// Run a non-streaming completion through the cache, router, and audit log.
+///
+/// Shared by every endpoint that is translated onto [`ChatRequest`]. The
+/// caller's reasoning exposure policy is applied to the returned response.
+/// Also returns the upstream rate-limit state of the providers serving the
+/// model, if it was forwarded to one.
+pub(crate) async fn complete_chat(
+    state: AppState,
+    user: AuthUser,
+    mut request: ChatRequest,
+    request_id: String,
+    attribution: Attribution,
+) -> Result<(ChatResponse, Option<UpstreamRateLimit>), AppError> {
+    check_request_size(&state.config.limits, &request)?;
+    uploads::resolve(&state.db, &user.user_id, &mut request.messages)?;
+    let mut attribution = attribution.with_user(&user);
+    apply_token_scope(&state, &user, &mut request)?;
+    attribution.experiment = apply_experiments(&state, &user, &mut request, &attribution);
+    resolve_virtual_model(&state, &mut request).await?;
+    let route = apply_routing_rules(&state, &user, &mut request).await;
+    apply_params(&state, &mut request);
+    let emulated_tools = check_capabilities(&state, &mut request)?;
     let start = Instant::now();
     let model = request.model.clone();
+    let cache = response_cache(&state, route.as_ref());
 
     // -- Cache lookup --
-    if let Some(ref cache) = state.cache {
+    if let Some(cache) = cache {
         if cache.should_check(&request) {
             match cache.lookup(&request).await {
                 Ok(hit) if hit.is_hit() => {
                     let kind = hit.hit_kind_str().unwrap_or("unknown");
                     let entry = hit.into_entry().unwrap();
 
-                    match serde_json::from_str::<crate::providers::types::ChatResponse>(
-                        &entry.response_json,
-                    ) {
-                        Ok(cached_response) => {
+                    match serde_json::from_str::<ChatResponse>(&entry.response_json) {
+                        Ok(mut cached_response) => {
                             let latency_ms = start.elapsed().as_millis() as u64;
+                            let cost_saved =
+                                cache_saving(&state.cost_calculator, &request, &cached_response);
                             let _ = state.audit_tx.send(AuditEntry {
                                 user_id: user.user_id,
                                 request_id,
@@ -75,16 +142,22 @@ async fn handle_non_streaming(
                                 model,
                                 input_tokens: 0,
                                 output_tokens: 0,
+                                audio_seconds: 0.0,
+                                premium_requests: 0.0,
                                 cost: 0.0,
+                                cost_saved,
                                 latency_ms,
                                 status: format!("cache_hit_{kind}"),
+                                finish_reason: first_finish_reason(&cached_response),
+                                attribution,
                             });
                             tracing::info!(
                                 cache_hit = kind,
                                 latency_ms = latency_ms,
                                 "Served from cache"
                             );
-                            return Ok(Json(cached_response).into_response());
+                            reasoning::apply_to_response(user.reasoning_mode, &mut cached_response);
+                            return Ok((cached_response, None));
                         }
                         Err(e) => {
                             tracing::warn!(error = %e, "Failed to deserialize cached response");
@@ -100,12 +173,38 @@ async fn handle_non_streaming(
     }
 
     // -- Forward to provider --
-    let mut router = state.router.write().await;
-    let result = router.chat(&request).await;
-    drop(router);
+    let input_tokens = preflight(&state, &user, &request)?;
+    let premium_requests = state.router.read().await.premium_requests(&model);
+    check_premium_budget(&state, &user, premium_requests)?;
+    let priority = request_priority(&state, &user);
+    let _slot = state
+        .scheduler
+        .acquire(
+            &user.user_id,
+            priority,
+            scheduler::cost(input_tokens),
+            &state.settings.config().limits,
+        )
+        .await?;
+
+    let result = forward_with_mcp_tools(
+        &state,
+        &request,
+        preferred_providers(route.as_ref()),
+        priority,
+    )
+    .await;
+    let upstream = state
+        .router
+        .read()
+        .await
+        .upstream_rate_limit(&request.model);
 
     match result {
-        Ok(response) => {
+        Ok(mut response) => {
+            if let Some(tools) = &emulated_tools {
+                tool_emulation::apply_to_response(&mut response, tools);
+            }
             let latency_ms = start.elapsed().as_millis() as u64;
             let cost = state
                 .cost_calculator
@@ -118,26 +217,33 @@ async fn handle_non_streaming(
                 model,
                 input_tokens: response.usage.prompt_tokens,
                 output_tokens: response.usage.completion_tokens,
+                audio_seconds: 0.0,
+                premium_requests,
                 cost,
+                cost_saved: 0.0,
                 latency_ms,
                 status: "success".to_string(),
+                finish_reason: first_finish_reason(&response),
+                attribution,
             });
 
             // -- Cache store (background, non-blocking) --
-            if let Some(ref cache) = state.cache {
+            if let Some(cache) = cache {
                 if cache.should_check(&request) {
                     let cache = Arc::clone(cache);
                     let req = request.clone();
                     let resp = response.clone();
-                    tokio::spawn(async move {
-                        if let Err(e) = cache.store(&req, &resp).await {
+                    let user_id = user.user_id.clone();
+                    state.background.spawn(async move {
+                        if let Err(e) = cache.store(&req, &resp, &user_id).await {
                             tracing::warn!(error = %e, "Failed to store in cache");
                         }
                     });
                 }
             }
 
-            Ok(Json(response).into_response())
+            reasoning::apply_to_response(user.reasoning_mode, &mut response);
+            Ok((response, upstream))
         }
         Err(e) => {
             let latency_ms = start.elapsed().as_millis() as u64;
@@ -149,32 +255,217 @@ async fn handle_non_streaming(
                 model,
                 input_tokens: 0,
                 output_tokens: 0,
+                audio_seconds: 0.0,
+                premium_requests: 0.0,
                 cost: 0.0,
+                cost_saved: 0.0,
                 latency_ms,
                 status: format!("error: {e}"),
+                finish_reason: None,
+                attribution,
             });
 
             Err(AppError::from(e))
         }
     }
 }
+
+/// Forward a non-streaming request to its provider, with the tools of the
+/// MCP servers attached to its model (`[mcp.servers]`).
+///
+/// While the model calls only those tools, gaud runs the calls, appends the
+/// results and asks again, up to `mcp.max_tool_rounds` times. The caller
+/// gets the final response, with usage summed over every round. Calls to
+/// the caller's own tools are returned to it as usual; MCP calls made in
+/// the same turn, or left over when the rounds run out, are dropped from
+/// the response. A tool the request declares itself is always the
+/// caller's, even if an attached server has one of the same name.
+async fn forward_with_mcp_tools(
+    state: &AppState,
+    request: &ChatRequest,
+    preferred: &[String],
+    priority: Priority,
+) -> Result<ChatResponse, ProviderError> {
+    let declared: Vec<String> = request
+        .tools
+        .iter()
+        .flatten()
+        .map(|t| t.function.name.clone())
+        .collect();
+    let mut tools = if state.mcp_tools.is_empty() {
+        Vec::new()
+    } else {
+        state.mcp_tools.tools_for(&request.model).await
+    };
+    tools.retain(|t| !declared.contains(&t.function.name));
+    if tools.is_empty() {
+        let mut router = state.router.write().await;
+        return router.chat_with_order(request, preferred, priority).await;
+    }
+
+    let mut request = request.clone();
+    request.tools.get_or_insert_with(Vec::new).extend(tools);
+    let mut usage = Usage::default();
+    let mut round = 0;
+    loop {
+        let mut response = state
+            .router
+            .write()
+            .await
+            .chat_with_order(&request, preferred, priority)
+            .await?;
+        usage.prompt_tokens += response.usage.prompt_tokens;
+        usage.completion_tokens += response.usage.completion_tokens;
+        usage.total_tokens += response.usage.total_tokens;
+
+        let Some(choice) = response.choices.first_mut() else {
+            response.usage = usage;
+            return Ok(response);
+        };
+        let (mcp_calls, own_calls): (Vec<_>, Vec<_>) = choice
+            .message
+            .tool_calls
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|c| {
+                state.mcp_tools.owns(&c.function.name) && !declared.contains(&c.function.name)
+            });
+        if mcp_calls.is_empty() || !own_calls.is_empty() || round == state.mcp_tools.max_rounds() {
+            if own_calls.is_empty() && !mcp_calls.is_empty() {
+                // Out of rounds with the model still asking for tools.
+                choice.finish_reason = Some("length".to_string());
+            }
+            choice.message.tool_calls = (!own_calls.is_empty()).then_some(own_calls);
+            response.usage = usage;
+            return Ok(response);
+        }
+
+        tracing::debug!(round, calls = mcp_calls.len(), "Running MCP tool calls");
+        request.messages.push(ChatMessage {
+            role: MessageRole::Assistant,
+            content: choice.message.content.take().map(MessageContent::Text),
+            name: None,
+            tool_calls: Some(mcp_calls.clone()),
+            tool_call_id: None,
+        });
+        for call in &mcp_calls {
+            let output = state.mcp_tools.call(call).await;
+            request.messages.push(ChatMessage {
+                role: MessageRole::Tool,
+                content: Some(MessageContent::Text(output)),
+                name: None,
+                tool_calls: None,
+                tool_call_id: Some(call.id.clone()),
+            });
+        }
+        round += 1;
+    }
+}
+
 /// Handle a streaming chat completion request via SSE.
+///
+/// With stream resume enabled, events are served from the stream registry
+/// and carry IDs; a `Last-Event-ID` replays the rest of that stream instead
+/// of starting a new generation.
 async fn handle_streaming(
     state: AppState,
     user: AuthUser,
     request: ChatRequest,
     request_id: String,
+    attribution: Attribution,
+    last_event_id: Option<String>,
 ) -> Result<Response, AppError> {
+    if state.config.cache.stream_resume_enabled {
+        let (events, upstream) = match last_event_id {
+            Some(ref id) => {
+                tracing::info!(request_id = %request_id, last_event_id = %id, "Resuming stream");
+                (state.stream_registry.resume(&user.user_id, id)?, None)
+            }
+            None => {
+                let registry = Arc::clone(&state.stream_registry);
+                let background = state.background.clone();
+                let user_id = user.user_id.clone();
+                let (stream, upstream) =
+                    open_chat_stream(state, user, request, request_id, attribution).await?;
+                (registry.start(&background, &user_id, stream), upstream)
+            }
+        };
+        let events =
+            events.map(|(id, msg)| Ok::<_, std::convert::Infallible>(sse_event(msg).id(id)));
+        let mut response = Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response();
+        insert_upstream_rate_limit_headers(response.headers_mut(), upstream.as_ref());
+        return Ok(response);
+    }
+
+    let (stream, upstream) =
+        open_chat_stream(state, user, request, request_id, attribution).await?;
+    let mut response = Sse::new(SseAdapter::new(stream))
+        .keep_alive(KeepAlive::default())
+        .into_response();
+    insert_upstream_rate_limit_headers(response.headers_mut(), upstream.as_ref());
+    Ok(response)
+}
+
+/// Add `x-upstream-ratelimit-*` headers describing the providers' own rate
+/// limits.
+pub(crate) fn insert_upstream_rate_limit_headers(
+    headers: &mut HeaderMap,
+    upstream: Option<&UpstreamRateLimit>,
+) {
+    let Some(upstream) = upstream else { return };
+    for (name, value) in upstream.client_headers() {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+/// Stream of [`SseMsg`] values carrying serialized [`ChatChunk`] payloads.
+pub(crate) type ChatEventStream = Pin<Box<dyn Stream<Item = SseMsg> + Send>>;
+
+/// Open a streaming completion through the stream cache, router, and audit
+/// log.
+///
+/// Shared by every endpoint that is translated onto [`ChatRequest`]. The
+/// returned stream applies the caller's reasoning policy, emits an audit
+/// entry when it finishes, and ends with [`SseMsg::Done`]. Like
+/// [`complete_chat`], also returns the upstream rate-limit state.
+pub(crate) async fn open_chat_stream(
+    state: AppState,
+    user: AuthUser,
+    mut request: ChatRequest,
+    request_id: String,
+    attribution: Attribution,
+) -> Result<(ChatEventStream, Option<UpstreamRateLimit>), AppError> {
+    check_request_size(&state.config.limits, &request)?;
+    uploads::resolve(&state.db, &user.user_id, &mut request.messages)?;
+    let mut attribution = attribution.with_user(&user);
+    apply_token_scope(&state, &user, &mut request)?;
+    attribution.experiment = apply_experiments(&state, &user, &mut request, &attribution);
+    resolve_virtual_model(&state, &mut request).await?;
+    let route = apply_routing_rules(&state, &user, &mut request).await;
+    apply_params(&state, &mut request);
+    let emulated_tools = check_capabilities(&state, &mut request)?;
+    let permit = state.stream_limiter.acquire(&user, &state.config.limits)?;
     let start = Instant::now();
     let model = request.model.clone();
+    let cache = response_cache(&state, route.as_ref());
+    let reasoning_mode = user.reasoning_mode;
 
     // -- Stream cache lookup ------------------------------------------------
-    if let Some(ref cache) = state.cache {
+    if let Some(cache) = cache {
         let cache_ops: &dyn StreamCacheOps = cache.as_ref();
         if cache_ops.check_stream(&request) {
             match cache_ops.get_cached_events(&request).await {
                 Ok(Some((events, kind))) => {
                     let latency_ms = start.elapsed().as_millis() as u64;
+                    let cost_saved = SemanticCacheService::reconstruct_response(&request, &events)
+                        .map_or(0.0, |cached| {
+                            cache_saving(&state.cost_calculator, &request, &cached)
+                        });
                     let _ = state.audit_tx.send(AuditEntry {
                         user_id: user.user_id,
                         request_id,
@@ -182,9 +473,14 @@ async fn handle_streaming(
                         model,
                         input_tokens: 0,
                         output_tokens: 0,
+                        audio_seconds: 0.0,
+                        premium_requests: 0.0,
                         cost: 0.0,
+                        cost_saved,
                         latency_ms,
                         status: format!("stream_cache_hit_{kind}"),
+                        finish_reason: None,
+                        attribution,
                     });
                     tracing::info!(
                         cache_hit = kind,
@@ -192,10 +488,9 @@ async fn handle_streaming(
                         latency_ms = latency_ms,
                         "Serving stream from cache"
                     );
-                    let replay = ReplayStream::new(events);
-                    return Ok(Sse::new(SseAdapter::new(replay))
-                        .keep_alive(KeepAlive::default())
-                        .into_response());
+                    let replay = ReplayStream::new(events)
+                        .with_reasoning(ReasoningFilter::new(reasoning_mode));
+                    return Ok((hold_permit(Box::pin(replay), permit), None));
                 }
                 Ok(None) => {} // Miss, proceed to provider
                 Err(e) => {
@@ -206,12 +501,32 @@ async fn handle_streaming(
     }
 
     // -- Forward to provider ------------------------------------------------
+    let input_tokens = preflight(&state, &user, &request)?;
+    let premium_requests = state.router.read().await.premium_requests(&model);
+    check_premium_budget(&state, &user, premium_requests)?;
+    let priority = request_priority(&state, &user);
+    let slot = state
+        .scheduler
+        .acquire(
+            &user.user_id,
+            priority,
+            scheduler::cost(input_tokens),
+            &state.settings.config().limits,
+        )
+        .await?;
+
     let mut router = state.router.write().await;
-    let stream_result = router.stream_chat(&request).await;
+    let stream_result = router
+        .stream_chat_with_order(&request, preferred_providers(route.as_ref()), priority)
+        .await;
+    let upstream = router.upstream_rate_limit(&request.model);
     drop(router);
 
     let chunk_stream = match stream_result {
-        Ok(s) => s,
+        Ok(s) => match emulated_tools {
+            Some(tools) => tool_emulation::emulate_stream(s, tools),
+            None => s,
+        },
         Err(e) => {
             let latency_ms = start.elapsed().as_millis() as u64;
             let _ = state.audit_tx.send(AuditEntry {
@@ -221,16 +536,21 @@ async fn handle_streaming(
                 model,
                 input_tokens: 0,
                 output_tokens: 0,
+                audio_seconds: 0.0,
+                premium_requests: 0.0,
                 cost: 0.0,
+                cost_saved: 0.0,
                 latency_ms,
                 status: format!("error: {e}"),
+                finish_reason: None,
+                attribution,
             });
             return Err(AppError::from(e));
         }
     };
 
     // Resolve cache tee parameters.
-    let cache_ops: Option<Arc<dyn StreamCacheOps>> = if let Some(ref cache) = state.cache {
+    let cache_ops: Option<Arc<dyn StreamCacheOps>> = if let Some(cache) = cache {
         let ops: &dyn StreamCacheOps = cache.as_ref();
         if ops.check_stream(&request) {
             Some(Arc::clone(cache) as Arc<dyn StreamCacheOps>)
@@ -258,11 +578,290 @@ async fn handle_streaming(
         Some(request),
         max_events,
         max_bytes,
+    )
+    .with_reasoning(ReasoningFilter::new(reasoning_mode))
+    .with_background(state.background.clone())
+    .with_premium_requests(premium_requests)
+    .with_estimated_input(input_tokens)
+    .with_attribution(attribution);
+
+    let sse_stream = hold_permit(Box::pin(sse_stream), slot);
+    Ok((hold_permit(sse_stream, permit), upstream))
+}
+
+/// Keep a stream's `[limits]` slot until the stream is dropped.
+fn hold_permit<P: Unpin + Send + 'static>(
+    stream: ChatEventStream,
+    permit: Option<P>,
+) -> ChatEventStream {
+    match permit {
+        Some(permit) => Box::pin(PermittedStream::new(stream, permit)),
+        None => stream,
+    }
+}
+
+/// Hold a request made with a scoped token to the token's models, and cap
+/// its `max_tokens` at what is left of the token budget. Usage is counted
+/// after the response, so a token can overrun its budget by one request's
+/// input.
+fn apply_token_scope(
+    state: &AppState,
+    user: &AuthUser,
+    request: &mut ChatRequest,
+) -> Result<(), AppError> {
+    let Some(scope) = &user.scope else {
+        return Ok(());
+    };
+    if !scope.allows_model(&request.model) {
+        return Err(AppError::Forbidden(format!(
+            "This token may not use model '{}'",
+            request.model
+        )));
+    }
+    if let Some(remaining) = tokens::remaining_tokens(&state.db, scope)? {
+        if remaining == 0 {
+            return Err(AppError::BudgetExceeded(
+                "This token has used up its token budget".to_string(),
+            ));
+        }
+        let cap = u32::try_from(remaining).unwrap_or(u32::MAX);
+        request.max_tokens = Some(request.max_tokens.map_or(cap, |t| t.min(cap)));
+    }
+    Ok(())
+}
+
+/// Put the request in the first `[[experiments]]` entry that enrols it and
+/// apply the variant. Sticky experiments assign by the end user, or the
+/// gaud user without one. A variant whose model the request's scoped token
+/// may not use leaves the request as it was.
+fn apply_experiments(
+    state: &AppState,
+    user: &AuthUser,
+    request: &mut ChatRequest,
+    attribution: &Attribution,
+) -> Option<Assignment> {
+    if state.config.experiments.is_empty() {
+        return None;
+    }
+    let mut candidate = request.clone();
+    let key = attribution.end_user.as_deref().unwrap_or(&user.user_id);
+    let assignment = experiments::enrol(
+        &state.config.experiments,
+        &mut candidate,
+        &user.user_id,
+        &user.name,
+        user.team.as_deref(),
+        key,
+    )?;
+    if user
+        .scope
+        .as_ref()
+        .is_some_and(|scope| !scope.allows_model(&candidate.model))
+    {
+        return None;
+    }
+    *request = candidate;
+    Some(assignment)
+}
+
+/// Replace a virtual model (`gaud:smart`) with the concrete model the router
+/// picks for it, so caching, pricing, the audit log and the response all see
+/// the model that actually served the request.
+async fn resolve_virtual_model(
+    state: &AppState,
+    request: &mut ChatRequest,
+) -> Result<(), AppError> {
+    if request.model.starts_with(VIRTUAL_MODEL_PREFIX) {
+        request.model = state.router.write().await.resolve_model(&request.model)?;
+    }
+    Ok(())
+}
+
+/// Evaluate the routing rules for `request`, clamping its `max_tokens` if
+/// the matching rule says so.
+async fn apply_routing_rules(
+    state: &AppState,
+    user: &AuthUser,
+    request: &mut ChatRequest,
+) -> Option<RoutingDecision> {
+    let facts = RequestFacts::new(
+        request,
+        &user.user_id,
+        &user.name,
+        user.team.as_deref(),
+        chrono::Utc::now(),
     );
+    let decision = state.router.read().await.route(&facts)?;
+    decision.apply(request);
+    Some(decision)
+}
 
-    Ok(Sse::new(SseAdapter::new(sse_stream))
-        .keep_alive(KeepAlive::default())
-        .into_response())
+/// Apply the model's `[[providers.model_params]]`, then
+/// `providers.default_max_tokens` if the request still sets no `max_tokens`.
+fn apply_params(state: &AppState, request: &mut ChatRequest) {
+    let config = state.settings.config();
+    params::apply(&config.providers.model_params, request);
+    if request.max_tokens.is_none() && config.providers.default_max_tokens > 0 {
+        request.max_tokens = Some(config.providers.default_max_tokens);
+    }
+}
+
+/// The response cache, unless disabled for this request by a routing rule.
+fn response_cache<'a>(
+    state: &'a AppState,
+    route: Option<&RoutingDecision>,
+) -> Option<&'a Arc<SemanticCacheService>> {
+    state
+        .cache
+        .as_ref()
+        .filter(|_| route.is_none_or(RoutingDecision::allows_cache))
+}
+
+/// Estimated cost of `request` had it gone upstream instead of being
+/// answered with the cached `response`: the usage the response was cached
+/// with, estimating input from the request and output from the reply text
+/// where it reports none. Zero for models without pricing.
+fn cache_saving(
+    calculator: &CostCalculator,
+    request: &ChatRequest,
+    response: &ChatResponse,
+) -> f64 {
+    let mut usage = response.usage.clone();
+    if usage.prompt_tokens == 0 {
+        usage.prompt_tokens = u32::try_from(token_count::estimate(request)).unwrap_or(u32::MAX);
+    }
+    if usage.completion_tokens == 0 {
+        let reply: String = response
+            .choices
+            .iter()
+            .filter_map(|c| c.message.content.as_deref())
+            .collect();
+        usage.completion_tokens =
+            u32::try_from(token_count::count_text(&request.model, &reply)).unwrap_or(u32::MAX);
+    }
+    calculator
+        .try_calculate_cost(&request.model, &usage)
+        .unwrap_or(0.0)
+}
+
+/// Finish reason of a response's first choice, for the audit log.
+fn first_finish_reason(response: &ChatResponse) -> Option<String> {
+    response
+        .choices
+        .first()
+        .and_then(|c| c.finish_reason.clone())
+}
+
+/// Scheduling priority of a request: its key's, or else its team's
+/// (`limits.team_priority`).
+fn request_priority(state: &AppState, user: &AuthUser) -> Priority {
+    let key_priority = user
+        .key_id
+        .as_deref()
+        .and_then(|id| users::api_key_priority(&state.db, id).ok())
+        .unwrap_or_default();
+    state
+        .config
+        .limits
+        .priority(key_priority, user.team.as_deref())
+}
+
+/// Providers a routing rule wants tried first.
+fn preferred_providers(route: Option<&RoutingDecision>) -> &[String] {
+    route.map_or(&[], |r| r.providers.as_slice())
+}
+
+/// Refuse a request with more messages than `limits.max_messages`, or an
+/// inline image over `limits.max_image_mb`, before any of it is transformed
+/// or sent upstream.
+fn check_request_size(limits: &LimitsConfig, request: &ChatRequest) -> Result<(), AppError> {
+    if limits.max_messages > 0 && request.messages.len() > limits.max_messages as usize {
+        return Err(AppError::InvalidParam {
+            param: "messages".to_string(),
+            message: format!(
+                "{} messages is over the limit of {}",
+                request.messages.len(),
+                limits.max_messages
+            ),
+        });
+    }
+    if limits.max_image_mb > 0 {
+        let max_bytes = limits.max_image_mb as usize * 1024 * 1024;
+        let images = request
+            .messages
+            .iter()
+            .filter_map(|m| match &m.content {
+                Some(MessageContent::Parts(parts)) => Some(parts),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|p| match p {
+                ContentPart::ImageUrl { image_url } => image_url.url.strip_prefix("data:"),
+                _ => None,
+            });
+        for data in images {
+            // Base64 takes four characters for every three bytes.
+            let bytes = data.split_once(',').map_or(0, |(_, b64)| b64.len() / 4 * 3);
+            if bytes > max_bytes {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "An inline image of {:.1} MiB is over the limit of {} MiB",
+                    bytes as f64 / (1024.0 * 1024.0),
+                    limits.max_image_mb
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Refuse a request needing a feature (such as image input) that its model
+/// is known to lack. With `providers.tool_emulation`, tools for a model
+/// without tool calling are emulated instead; returns their names then.
+fn check_capabilities(
+    state: &AppState,
+    request: &mut ChatRequest,
+) -> Result<Option<Vec<String>>, AppError> {
+    let model = state.cost_calculator.get_capabilities(&request.model);
+    let emulated =
+        if state.config.providers.tool_emulation && model.as_ref().is_some_and(|m| !m.tools) {
+            tool_emulation::prepare(request)
+        } else {
+            None
+        };
+    capabilities::check(request, model.as_ref()).map_err(AppError::BadRequest)?;
+    Ok(emulated)
+}
+
+/// Reject a request whose upstream premium requests (GitHub Copilot) would
+/// exceed the user's premium request limits.
+fn check_premium_budget(
+    state: &AppState,
+    user: &AuthUser,
+    premium_requests: f64,
+) -> Result<(), AppError> {
+    if state.config.budget.enabled
+        && state
+            .budget
+            .check_premium_requests(&user.user_id, premium_requests)?
+            == BudgetStatus::Exceeded
+    {
+        tracing::warn!(
+            user_id = %user.user_id,
+            premium_requests,
+            "Premium request budget exceeded, rejecting request"
+        );
+        state
+            .notifier
+            .notify(crate::notify::Notification::BudgetExhausted {
+                user: user.name.clone(),
+                limit: "premium request".to_string(),
+            });
+        return Err(AppError::BudgetExceeded(format!(
+            "Premium request budget exceeded for user '{}'",
+            user.name
+        )));
+    }
+    Ok(())
 }
 
 // ---------------------------------------------------------------------------
@@ -305,26 +904,33 @@ impl<S: Stream<Item = SseMsg>> Stream for SseAdapter<S> {
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.get_mut();
         match this.inner.as_mut().poll_next(cx) {
-            Poll::Ready(Some(SseMsg::Data(payload))) => {
-                Poll::Ready(Some(Ok(Event::default().data(payload))))
-            }
-            Poll::Ready(Some(SseMsg::Done)) => {
-                Poll::Ready(Some(Ok(Event::default().data("[DONE]"))))
-            }
+            Poll::Ready(Some(msg)) => Poll::Ready(Some(Ok(sse_event(msg)))),
             Poll::Ready(None) => Poll::Ready(None),
             Poll::Pending => Poll::Pending,
         }
     }
 }
 
+/// Convert an [`SseMsg`] into an SSE event.
+pub(crate) fn sse_event(msg: SseMsg) -> Event {
+    match msg {
+        SseMsg::Data(payload) => Event::default().data(payload),
+        SseMsg::Done => Event::default().data("[DONE]"),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ReplayStream — serves cached events as SseMsg
 // ---------------------------------------------------------------------------
 
 /// Replays cached SSE event payloads as [`SseMsg`], then emits [`SseMsg::Done`].
+///
+/// Cached payloads are stored exactly as the provider produced them; the
+/// per-key reasoning policy is re-applied on the way out.
 struct ReplayStream {
     events: std::vec::IntoIter<String>,
     done_sent: bool,
+    reasoning: ReasoningFilter,
 }
 
 impl ReplayStream {
@@ -332,8 +938,14 @@ impl ReplayStream {
         Self {
             events: events.into_iter(),
             done_sent: false,
+            reasoning: ReasoningFilter::default(),
         }
     }
+
+    fn with_reasoning(mut self, reasoning: ReasoningFilter) -> Self {
+        self.reasoning = reasoning;
+        self
+    }
 }
 
 impl Stream for ReplayStream {
@@ -342,6 +954,16 @@ impl Stream for ReplayStream {
     fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<SseMsg>> {
         let this = self.get_mut();
         if let Some(payload) = this.events.next() {
+            if this.reasoning.is_passthrough() {
+                return Poll::Ready(Some(SseMsg::Data(payload)));
+            }
+            let payload = match serde_json::from_str::<ChatChunk>(&payload) {
+                Ok(mut chunk) => {
+                    this.reasoning.apply_chunk(&mut chunk);
+                    serde_json::to_string(&chunk).unwrap_or(payload)
+                }
+                Err(_) => payload,
+            };
             return Poll::Ready(Some(SseMsg::Data(payload)));
         }
         if !this.done_sent {
@@ -358,8 +980,12 @@ impl Stream for ReplayStream {
 
 /// Wraps a `ChatChunk` stream, converting to [`SseMsg`] while accumulating
 /// token usage. Emits an `AuditEntry` with computed cost when the stream ends.
-/// Optionally tees event payloads into a bounded buffer for stream cache
-/// write-behind via the [`StreamCacheOps`] trait.
+/// Usage the provider does not report is estimated: input from the request,
+/// output from the text the stream emitted.
+/// A failed chunk ends the stream with an OpenAI error object and `[DONE]`,
+/// so clients see the error instead of a dropped connection. Optionally tees
+/// event payloads into a bounded buffer for stream cache write-behind via the
+/// [`StreamCacheOps`] trait.
 struct AuditingStream {
     /// The underlying provider chunk stream.
     inner: Pin<
@@ -382,7 +1008,16 @@ struct AuditingStream {
     input_tokens: u32,
     output_tokens: u32,
     cached_tokens: Option<u32>,
+    /// Estimated input tokens, used if no chunk reports any.
+    estimated_input_tokens: u32,
+    /// Text, reasoning and tool calls emitted, counted for the output tokens
+    /// if no chunk reports any.
+    emitted_text: String,
+    finish_reason: Option<String>,
     errored: bool,
+    /// Upstream premium requests charged when the stream succeeds.
+    premium_requests: f64,
+    attribution: Attribution,
 
     // Streaming cache tee (optional, trait-based for testability)
     cache: Option<Arc<dyn StreamCacheOps>>,
@@ -392,6 +1027,11 @@ struct AuditingStream {
     event_log_enabled: bool,
     stream_cache_max_events: usize,
     stream_cache_max_bytes: usize,
+    /// Tracks the write-behind task so shutdown can wait for it.
+    background: TaskTracker,
+
+    // Per-key reasoning exposure policy (applied after the cache tee).
+    reasoning: ReasoningFilter,
 }
 
 impl AuditingStream {
@@ -427,7 +1067,12 @@ impl AuditingStream {
             input_tokens: 0,
             output_tokens: 0,
             cached_tokens: None,
+            estimated_input_tokens: 0,
+            emitted_text: String::new(),
+            finish_reason: None,
             errored: false,
+            premium_requests: 0.0,
+            attribution: Attribution::default(),
             cache,
             cache_request,
             event_log: Vec::new(),
@@ -435,11 +1080,69 @@ impl AuditingStream {
             event_log_enabled,
             stream_cache_max_events,
             stream_cache_max_bytes,
+            background: TaskTracker::new(),
+            reasoning: ReasoningFilter::default(),
         }
     }
 
-    /// Extract and accumulate token usage from a chunk.
+    /// Apply a reasoning exposure policy to emitted chunks.
+    ///
+    /// The stream cache still records the unfiltered payloads so a cached
+    /// stream can be replayed under a different key's policy.
+    fn with_reasoning(mut self, reasoning: ReasoningFilter) -> Self {
+        self.reasoning = reasoning;
+        self
+    }
+
+    /// Record the upstream premium requests of the request in the audit entry.
+    fn with_premium_requests(mut self, premium_requests: f64) -> Self {
+        self.premium_requests = premium_requests;
+        self
+    }
+
+    /// Estimated input tokens of the request, logged if the provider does
+    /// not report them.
+    fn with_estimated_input(mut self, input_tokens: u64) -> Self {
+        self.estimated_input_tokens = u32::try_from(input_tokens).unwrap_or(u32::MAX);
+        self
+    }
+
+    /// Record the client's end user and tags in the audit entry.
+    fn with_attribution(mut self, attribution: Attribution) -> Self {
+        self.attribution = attribution;
+        self
+    }
+
+    /// Spawn cache writes on the application's shutdown-tracked tasks.
+    fn with_background(mut self, background: TaskTracker) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Extract and accumulate token usage, emitted text and the finish
+    /// reason from a chunk.
     fn accumulate_usage(&mut self, chunk: &ChatChunk) {
+        if let Some(reason) = chunk
+            .choices
+            .iter()
+            .find(|c| c.index == 0)
+            .and_then(|c| c.finish_reason.as_ref())
+        {
+            self.finish_reason = Some(reason.clone());
+        }
+        for choice in &chunk.choices {
+            let delta = &choice.delta;
+            for text in [&delta.content, &delta.reasoning_content]
+                .into_iter()
+                .flatten()
+            {
+                self.emitted_text.push_str(text);
+            }
+            for call in delta.tool_calls.iter().flatten() {
+                self.emitted_text.push_str(&call.function.name);
+                self.emitted_text.push_str(&call.function.arguments);
+            }
+        }
         if let Some(ref usage) = chunk.usage {
             // Take the maximum of seen tokens (providers report cumulative or
             // final usage in different chunks).
@@ -482,6 +1185,29 @@ impl AuditingStream {
         self.event_log.push(payload.to_owned());
     }
 
+    /// Input and output tokens of the stream: as reported by the provider,
+    /// or estimated where it reported none. A successful stream generated at
+    /// least its stop token, so its output is never zero.
+    fn final_tokens(&self) -> (u32, u32) {
+        let input_tokens = if self.input_tokens > 0 {
+            self.input_tokens
+        } else {
+            self.estimated_input_tokens
+        };
+        let output_tokens = if self.output_tokens > 0 {
+            self.output_tokens
+        } else {
+            let estimated = token_count::count_text(&self.model, &self.emitted_text);
+            let estimated = u32::try_from(estimated).unwrap_or(u32::MAX);
+            if self.errored {
+                estimated
+            } else {
+                estimated.max(1)
+            }
+        };
+        (input_tokens, output_tokens)
+    }
+
     /// Send the audit entry with accumulated tokens and computed cost.
     fn emit_audit(&mut self) {
         if let Some(tx) = self.audit_tx.take() {
@@ -492,10 +1218,18 @@ impl AuditingStream {
                 "success".to_string()
             };
 
+            let (input_tokens, output_tokens) = self.final_tokens();
+            if self.output_tokens == 0 {
+                tracing::debug!(
+                    request_id = %self.request_id,
+                    output_tokens,
+                    "Stream reported no output usage, logging an estimate"
+                );
+            }
             let usage = Usage {
-                prompt_tokens: self.input_tokens,
-                completion_tokens: self.output_tokens,
-                total_tokens: self.input_tokens + self.output_tokens,
+                prompt_tokens: input_tokens,
+                completion_tokens: output_tokens,
+                total_tokens: input_tokens.saturating_add(output_tokens),
                 prompt_tokens_details: self.cached_tokens.map(|cached| UsageTokenDetails {
                     cached_tokens: Some(cached),
                     reasoning_tokens: None,
@@ -503,17 +1237,27 @@ impl AuditingStream {
                 completion_tokens_details: None,
             };
             let cost = self.cost_calculator.calculate_cost(&self.model, &usage);
+            let premium_requests = if self.errored {
+                0.0
+            } else {
+                self.premium_requests
+            };
 
             let _ = tx.send(AuditEntry {
                 user_id: self.user_id.clone(),
                 request_id: self.request_id.clone(),
                 provider: String::new(),
                 model: self.model.clone(),
-                input_tokens: self.input_tokens,
-                output_tokens: self.output_tokens,
+                input_tokens,
+                output_tokens,
+                audio_seconds: 0.0,
+                premium_requests,
                 cost,
+                cost_saved: 0.0,
                 latency_ms,
                 status,
+                finish_reason: self.finish_reason.take(),
+                attribution: self.attribution.clone(),
             });
         }
     }
@@ -525,8 +1269,9 @@ impl AuditingStream {
         }
         if let (Some(cache), Some(request)) = (self.cache.take(), self.cache_request.take()) {
             let events = std::mem::take(&mut self.event_log);
-            tokio::spawn(async move {
-                if let Err(e) = cache.put_stream_events(&request, events).await {
+            let user_id = self.user_id.clone();
+            self.background.spawn(async move {
+                if let Err(e) = cache.put_stream_events(&request, &user_id, events).await {
                     tracing::warn!(error = %e, "Failed to store stream events in cache");
                 }
             });
@@ -543,22 +1288,24 @@ impl Stream for AuditingStream {
         // Phase 1: drain the inner chunk stream.
         if !this.inner_done {
             match this.inner.as_mut().poll_next(cx) {
-                Poll::Ready(Some(Ok(chunk))) => {
+                Poll::Ready(Some(Ok(mut chunk))) => {
                     this.accumulate_usage(&chunk);
                     let json = serde_json::to_string(&chunk).unwrap_or_default();
                     this.maybe_push_event(&json);
-                    return Poll::Ready(Some(SseMsg::Data(json)));
+                    if this.reasoning.is_passthrough() {
+                        return Poll::Ready(Some(SseMsg::Data(json)));
+                    }
+                    this.reasoning.apply_chunk(&mut chunk);
+                    let filtered = serde_json::to_string(&chunk).unwrap_or_default();
+                    return Poll::Ready(Some(SseMsg::Data(filtered)));
                 }
                 Poll::Ready(Some(Err(e))) => {
                     this.errored = true;
+                    // Nothing the provider sends after a failure can be
+                    // trusted, so stop reading and finish with Done.
+                    this.inner_done = true;
                     tracing::error!(error = %e, "Stream chunk error");
-                    let error_json = serde_json::json!({
-                        "error": {
-                            "message": e.to_string(),
-                            "type": "stream_error",
-                        }
-                    });
-                    return Poll::Ready(Some(SseMsg::Data(error_json.to_string())));
+                    return Poll::Ready(Some(SseMsg::Data(AppError::from(e).to_json())));
                 }
                 Poll::Ready(None) => {
                     this.inner_done = true;
@@ -588,6 +1335,7 @@ impl Stream for AuditingStream {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::test_support::{litellm_router, test_state};
     use crate::cache::types::CacheError;
     use crate::providers::types::{ChunkChoice, Delta};
     use futures::StreamExt;
@@ -612,10 +1360,12 @@ mod tests {
                     content: Some(content.to_string()),
                     reasoning_content: None,
                     tool_calls: None,
+                    grounding: None,
                 },
                 finish_reason: None,
             }],
             usage: None,
+            tool_calls_emulated: false,
         }
     }
 
@@ -634,6 +1384,7 @@ mod tests {
                 prompt_tokens_details: None,
                 completion_tokens_details: None,
             }),
+            tool_calls_emulated: false,
         }
     }
 
@@ -741,6 +1492,7 @@ mod tests {
         fn put_stream_events<'a>(
             &'a self,
             _request: &'a ChatRequest,
+            _user_id: &'a str,
             events: Vec<String>,
         ) -> Pin<Box<dyn std::future::Future<Output = Result<(), CacheError>> + Send + 'a>>
         {
@@ -776,6 +1528,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         }
     }
 
@@ -808,6 +1563,59 @@ mod tests {
         assert_eq!(msgs, vec![SseMsg::Done]);
     }
 
+    /// Build a chunk carrying only a reasoning delta.
+    fn reasoning_chunk(reasoning: &str) -> ChatChunk {
+        let mut c = chunk("");
+        c.choices[0].delta.content = None;
+        c.choices[0].delta.reasoning_content = Some(reasoning.to_string());
+        c
+    }
+
+    #[tokio::test]
+    async fn test_replay_applies_reasoning_mode() {
+        let events = vec![serde_json::to_string(&reasoning_chunk("hmm")).unwrap()];
+        let stream = ReplayStream::new(events).with_reasoning(ReasoningFilter::new(
+            crate::providers::reasoning::ReasoningMode::Strip,
+        ));
+        let msgs = collect_msgs(stream).await;
+
+        match &msgs[0] {
+            SseMsg::Data(s) => assert!(!s.contains("reasoning_content")),
+            other => panic!("expected data, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wrapper_reasoning_filter_after_cache_tee() {
+        let fake = Arc::new(FakeCache::new());
+        let store_rx = fake.on_store();
+
+        let (stream, _) = auditing_with_cache(
+            vec![Ok(reasoning_chunk("thinking...")), Ok(chunk("answer"))],
+            Arc::clone(&fake),
+        );
+        let stream = stream.with_reasoning(ReasoningFilter::new(
+            crate::providers::reasoning::ReasoningMode::Strip,
+        ));
+
+        let msgs = collect_msgs(stream).await;
+        if let SseMsg::Data(ref s) = msgs[0] {
+            assert!(!s.contains("thinking..."), "emitted chunk must be filtered");
+        } else {
+            panic!("expected data payload");
+        }
+
+        tokio::time::timeout(std::time::Duration::from_secs(2), store_rx)
+            .await
+            .unwrap()
+            .unwrap();
+        let calls = fake.store_calls.lock().unwrap();
+        assert!(
+            calls[0][0].contains("thinking..."),
+            "cached payload must stay unfiltered"
+        );
+    }
+
     // =======================================================================
     // Layer 1: Bounded buffer unit tests
     // =======================================================================
@@ -960,19 +1768,24 @@ mod tests {
             vec![
                 Ok(chunk("good")),
                 Err(crate::providers::ProviderError::Stream("boom".into())),
+                Ok(chunk("after the failure")),
             ],
             Arc::clone(&fake),
         );
 
         let msgs = collect_msgs(stream).await;
 
-        // Should have: Data(good), Data(error_json), Done.
+        // Should have: Data(good), Data(error_json), Done; nothing after the
+        // error is forwarded.
         assert_eq!(msgs.len(), 3);
         assert!(matches!(&msgs[2], SseMsg::Done));
 
-        // Error JSON should be present.
+        // The error is an OpenAI error object.
         if let SseMsg::Data(ref s) = msgs[1] {
-            assert!(s.contains("stream_error"), "should contain error type");
+            let error: serde_json::Value = serde_json::from_str(s).unwrap();
+            assert_eq!(error["error"]["type"], "api_error");
+            assert_eq!(error["error"]["code"], "provider_error");
+            assert!(error["error"]["message"].as_str().unwrap().contains("boom"));
         } else {
             panic!("expected Data with error JSON");
         }
@@ -1041,6 +1854,7 @@ mod tests {
                 prompt_tokens_details: None,
                 completion_tokens_details: None,
             }),
+            tool_calls_emulated: false,
         };
         s.accumulate_usage(&c1);
         assert_eq!(s.input_tokens, 100);
@@ -1060,6 +1874,7 @@ mod tests {
                 prompt_tokens_details: None,
                 completion_tokens_details: None,
             }),
+            tool_calls_emulated: false,
         };
         s.accumulate_usage(&c2);
         assert_eq!(s.input_tokens, 100);
@@ -1073,6 +1888,7 @@ mod tests {
             model: "test".into(),
             choices: vec![],
             usage: None,
+            tool_calls_emulated: false,
         };
         s.accumulate_usage(&c3);
         assert_eq!(s.input_tokens, 100);
@@ -1099,6 +1915,7 @@ mod tests {
                 }),
                 completion_tokens_details: None,
             }),
+            tool_calls_emulated: false,
         };
         s.accumulate_usage(&c);
         assert_eq!(s.cached_tokens, Some(800));
@@ -1119,6 +1936,7 @@ mod tests {
                 }),
                 completion_tokens_details: None,
             }),
+            tool_calls_emulated: false,
         };
         s.accumulate_usage(&c2);
         assert_eq!(s.cached_tokens, Some(900));
@@ -1139,6 +1957,29 @@ mod tests {
         assert_eq!(audit.output_tokens, 10);
     }
 
+    #[tokio::test]
+    async fn test_audit_estimates_unreported_usage() {
+        let (stream, mut rx) = auditing_no_cache(vec![Ok(chunk("hello")), Ok(chunk(" world"))]);
+        let _msgs = collect_msgs(stream.with_estimated_input(42)).await;
+        let audit = rx.try_recv().unwrap();
+        assert_eq!(audit.input_tokens, 42);
+        assert_eq!(
+            u64::from(audit.output_tokens),
+            token_count::count_text("test-model", "hello world")
+        );
+
+        // A final usage frame replaces the estimates.
+        let (stream, mut rx) = auditing_no_cache(vec![Ok(chunk("hello")), Ok(usage_chunk(50, 10))]);
+        let _msgs = collect_msgs(stream.with_estimated_input(42)).await;
+        let audit = rx.try_recv().unwrap();
+        assert_eq!((audit.input_tokens, audit.output_tokens), (50, 10));
+
+        // A successful stream that emitted nothing still counts its stop token.
+        let (stream, mut rx) = auditing_no_cache(vec![]);
+        let _msgs = collect_msgs(stream).await;
+        assert_eq!(rx.try_recv().unwrap().output_tokens, 1);
+    }
+
     #[tokio::test]
     async fn test_audit_emitted_on_error() {
         let (stream, mut rx) = auditing_no_cache(vec![
@@ -1151,6 +1992,19 @@ mod tests {
         assert_eq!(audit.status, "error");
     }
 
+    #[tokio::test]
+    async fn test_audit_premium_requests_only_on_success() {
+        let (stream, mut rx) = auditing_no_cache(vec![Ok(chunk("hi"))]);
+        let _msgs = collect_msgs(stream.with_premium_requests(10.0)).await;
+        assert_eq!(rx.try_recv().unwrap().premium_requests, 10.0);
+
+        let (stream, mut rx) = auditing_no_cache(vec![Err(
+            crate::providers::ProviderError::Stream("fail".into()),
+        )]);
+        let _msgs = collect_msgs(stream.with_premium_requests(10.0)).await;
+        assert_eq!(rx.try_recv().unwrap().premium_requests, 0.0);
+    }
+
     // =======================================================================
     // Layer 1: Existing format tests (preserved)
     // =======================================================================
@@ -1164,9 +2018,14 @@ mod tests {
             model: "claude-3-sonnet".to_string(),
             input_tokens: 100,
             output_tokens: 50,
+            audio_seconds: 0.0,
+            premium_requests: 0.0,
             cost: 0.001,
+            cost_saved: 0.0,
             latency_ms: 250,
             status: "success".to_string(),
+            finish_reason: None,
+            attribution: Attribution::default(),
         };
 
         assert_eq!(entry.user_id, "user1");
@@ -1174,6 +2033,38 @@ mod tests {
         assert_eq!(entry.output_tokens, 50);
     }
 
+    #[test]
+    fn test_cache_saving() {
+        let calculator = CostCalculator::new();
+        let request: ChatRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Say hello"}]
+        }))
+        .unwrap();
+        let mut response: ChatResponse = serde_json::from_value(serde_json::json!({
+            "id": "c1", "object": "chat.completion", "created": 0, "model": "gpt-4o",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello!"},
+                         "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 1000, "completion_tokens": 500, "total_tokens": 1500}
+        }))
+        .unwrap();
+        let reported = cache_saving(&calculator, &request, &response);
+        assert_eq!(
+            reported,
+            calculator.calculate_cost("gpt-4o", &response.usage)
+        );
+
+        // Without usage in the cached response, both sides are estimated.
+        response.usage = Usage::default();
+        let estimated = cache_saving(&calculator, &request, &response);
+        assert!(estimated > 0.0 && estimated < reported);
+
+        // Models without pricing save nothing that can be counted.
+        let mut request = request;
+        request.model = "my-local-model".to_string();
+        assert_eq!(cache_saving(&calculator, &request, &response), 0.0);
+    }
+
     #[test]
     fn test_chat_chunk_sse_format() {
         let c = chunk("Hello");
@@ -1375,4 +2266,164 @@ mod tests {
             "Stored events must exactly match emitted data payloads"
         );
     }
+
+    // -----------------------------------------------------------------------
+    // MCP tool loop
+    // -----------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn test_mcp_tool_calls_run_server_side() {
+        use serde_json::json;
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let completion = |message: serde_json::Value, finish: &str, tokens: u32| {
+            json!({
+                "id": "c1", "object": "chat.completion", "created": 0, "model": "m",
+                "choices": [{"index": 0, "message": message, "finish_reason": finish}],
+                "usage": {"prompt_tokens": tokens, "completion_tokens": 1, "total_tokens": tokens + 1},
+            })
+        };
+        let weather_call = json!({"role": "assistant", "content": null, "tool_calls": [
+            {"id": "call_1", "type": "function",
+             "function": {"name": "weather__forecast", "arguments": "{\"city\":\"Oslo\"}"}}]});
+
+        // The model: asks for the forecast until it sees the tool result.
+        let llm = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({"messages": [{}, {}, {"role": "tool", "content": "Rain", "tool_call_id": "call_1"}]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion(
+                json!({"role": "assistant", "content": "It will rain in Oslo."}),
+                "stop",
+                20,
+            )))
+            .mount(&llm)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(
+                json!({"tools": [{"function": {"name": "weather__forecast"}}]}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion(
+                weather_call,
+                "tool_calls",
+                10,
+            )))
+            .mount(&llm)
+            .await;
+
+        let tools = MockServer::start().await;
+        for (rpc, result) in [
+            ("initialize", json!({})),
+            (
+                "tools/list",
+                json!({"tools": [{"name": "forecast", "inputSchema": {"type": "object"}}]}),
+            ),
+            (
+                "tools/call",
+                json!({"content": [{"type": "text", "text": "Rain"}]}),
+            ),
+        ] {
+            Mock::given(method("POST"))
+                .and(body_partial_json(json!({"method": rpc})))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(json!({"jsonrpc": "2.0", "id": null, "result": result})),
+                )
+                .mount(&tools)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({"method": "notifications/initialized"}),
+            ))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&tools)
+            .await;
+
+        let mut mcp = crate::config::McpConfig::default();
+        mcp.servers.insert(
+            "weather".to_string(),
+            crate::config::McpServerConfig {
+                url: tools.uri(),
+                api_key: None,
+                models: vec!["*".to_string()],
+                timeout_secs: 5,
+            },
+        );
+        let state = test_state(
+            crate::config::Config {
+                mcp: mcp.clone(),
+                ..Default::default()
+            },
+            litellm_router(&llm.uri()).await,
+        );
+
+        let request: ChatRequest = serde_json::from_value(json!({
+            "model": "m",
+            "messages": [{"role": "user", "content": "Weather in Oslo?"}],
+        }))
+        .unwrap();
+        let response = forward_with_mcp_tools(&state, &request, &[], Priority::Default)
+            .await
+            .unwrap();
+        let message = &response.choices[0].message;
+        assert_eq!(message.content.as_deref(), Some("It will rain in Oslo."));
+        assert!(message.tool_calls.is_none());
+        assert_eq!(response.usage.prompt_tokens, 30);
+        assert_eq!(response.usage.total_tokens, 32);
+
+        // Out of rounds, the unanswered MCP call is dropped.
+        mcp.max_tool_rounds = 0;
+        let state = test_state(
+            crate::config::Config {
+                mcp: mcp.clone(),
+                ..Default::default()
+            },
+            litellm_router(&llm.uri()).await,
+        );
+        let response = forward_with_mcp_tools(&state, &request, &[], Priority::Default)
+            .await
+            .unwrap();
+        assert!(response.choices[0].message.tool_calls.is_none());
+        assert_eq!(response.choices[0].finish_reason.as_deref(), Some("length"));
+    }
+
+    #[test]
+    fn test_check_request_size() {
+        let mut request: ChatRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "What is this?"},
+                    {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+                ]}
+            ],
+        }))
+        .unwrap();
+        assert!(check_request_size(&LimitsConfig::default(), &request).is_ok());
+
+        let limits = LimitsConfig {
+            max_messages: 1,
+            ..Default::default()
+        };
+        let err = check_request_size(&limits, &request).unwrap_err();
+        assert!(matches!(err, AppError::InvalidParam { ref param, .. } if param == "messages"));
+
+        let limits = LimitsConfig {
+            max_image_mb: 1,
+            ..Default::default()
+        };
+        assert!(check_request_size(&limits, &request).is_ok());
+        let big = format!("data:image/png;base64,{}", "A".repeat(2 * 1024 * 1024));
+        if let Some(MessageContent::Parts(parts)) = &mut request.messages[1].content
+            && let ContentPart::ImageUrl { image_url } = &mut parts[1]
+        {
+            image_url.url = big;
+        }
+        let err = check_request_size(&limits, &request).unwrap_err();
+        assert!(matches!(err, AppError::PayloadTooLarge(_)));
+    }
 }
@@ -0,0 +1,886 @@
+//! Server-side conversations (`/v1/conversations`).
+//!
+//! A conversation keeps its messages in the database, so a client sends
+//! only its new turn to `POST /v1/conversations/{id}/completions` and gaud
+//! adds the history. Completions go through the same pipeline as
+//! `/v1/chat/completions`. The history is held to the `[conversations]`
+//! limits centrally: once a conversation outgrows them, its oldest turns
+//! are dropped or, with `compaction = "summarize"`, folded into a summary
+//! that is sent to the model as a system message.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::Extension;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use rusqlite::{OptionalExtension, params};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::chat::{self, ChatEventStream, SseMsg};
+use crate::auth::AuthUser;
+use crate::budget::Attribution;
+use crate::config::{ConversationCompaction, ConversationsConfig};
+use crate::db::Database;
+use crate::error::AppError;
+use crate::providers::types::{
+    ChatChunk, ChatMessage, ChatRequest, MessageContent, MessageRole, ResponseMessage, ToolCall,
+};
+use crate::uploads;
+
+/// Most conversations returned by `GET /v1/conversations`.
+const LIST_LIMIT: usize = 100;
+/// Longest summary asked of the summary model, in tokens.
+const SUMMARY_MAX_TOKENS: u32 = 1024;
+
+const SUMMARY_PROMPT: &str = "Summarize the conversation below so it can be continued \
+    without it. Keep facts, decisions, names, numbers and open questions. Reply with the \
+    summary only.";
+
+// ---------------------------------------------------------------------------
+// Request types
+// ---------------------------------------------------------------------------
+
+/// Body of `POST /v1/conversations`.
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateConversationRequest {
+    /// Messages to start with, such as a system prompt.
+    #[serde(default)]
+    pub messages: Vec<ChatMessage>,
+    /// Free-form JSON object kept with the conversation.
+    #[serde(default)]
+    pub metadata: Option<Value>,
+}
+
+// ---------------------------------------------------------------------------
+// Storage
+// ---------------------------------------------------------------------------
+
+/// A stored conversation.
+#[derive(Debug)]
+struct Conversation {
+    id: String,
+    messages: Vec<ChatMessage>,
+    summary: Option<String>,
+    metadata: Option<Value>,
+    /// Bumped on every update, so concurrent turns cannot overwrite each
+    /// other.
+    version: i64,
+    created_at: i64,
+    updated_at: i64,
+}
+
+impl Conversation {
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "object": "conversation",
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+            "metadata": self.metadata,
+            "summary": self.summary,
+            "messages": self.messages,
+        })
+    }
+}
+
+/// Load a conversation owned by `user_id`.
+fn load(db: &Database, id: &str, user_id: &str) -> Result<Conversation, AppError> {
+    let row = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT messages, summary, metadata, version, created_at, updated_at
+             FROM conversations WHERE id = ?1 AND user_id = ?2",
+            params![id, user_id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, i64>(5)?,
+                ))
+            },
+        )
+        .optional()
+    })?;
+    let (messages, summary, metadata, version, created_at, updated_at) =
+        row.ok_or_else(|| AppError::NotFound(format!("Conversation '{id}' not found")))?;
+    let corrupt =
+        |e: serde_json::Error| AppError::Internal(format!("Corrupt conversation '{id}': {e}"));
+    Ok(Conversation {
+        id: id.to_string(),
+        messages: serde_json::from_str(&messages).map_err(corrupt)?,
+        summary,
+        metadata: metadata
+            .map(|m| serde_json::from_str(&m))
+            .transpose()
+            .map_err(corrupt)?,
+        version,
+        created_at,
+        updated_at,
+    })
+}
+
+/// The state of a conversation after a turn, written once the turn's reply
+/// is known.
+struct Update {
+    db: Database,
+    id: String,
+    user_id: String,
+    version: i64,
+    messages: Vec<ChatMessage>,
+    summary: Option<String>,
+}
+
+impl Update {
+    /// Store the conversation with `reply` appended. Fails with a conflict
+    /// if another turn was stored since the conversation was loaded.
+    fn save(mut self, reply: Option<ChatMessage>) -> Result<(), AppError> {
+        self.messages.extend(reply);
+        let messages = serde_json::to_string(&self.messages)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize conversation: {e}")))?;
+        let updated = self.db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE conversations
+                 SET messages = ?1, summary = ?2, version = version + 1, updated_at = ?3
+                 WHERE id = ?4 AND user_id = ?5 AND version = ?6",
+                params![
+                    messages,
+                    self.summary,
+                    chrono::Utc::now().timestamp(),
+                    self.id,
+                    self.user_id,
+                    self.version
+                ],
+            )
+        })?;
+        if updated == 0 {
+            return Err(AppError::Conflict(format!(
+                "Conversation '{}' was changed or deleted by another request",
+                self.id
+            )));
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+/// POST /v1/conversations
+pub async fn create_conversation(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(request): Json<CreateConversationRequest>,
+) -> Result<Json<Value>, AppError> {
+    ensure_enabled(&state)?;
+    if request.metadata.as_ref().is_some_and(|m| !m.is_object()) {
+        return Err(AppError::InvalidParam {
+            param: "metadata".to_string(),
+            message: "metadata must be an object".to_string(),
+        });
+    }
+    let now = chrono::Utc::now().timestamp();
+    let conversation = Conversation {
+        id: format!("conv_{}", Uuid::new_v4().simple()),
+        messages: request.messages,
+        summary: None,
+        metadata: request.metadata,
+        version: 0,
+        created_at: now,
+        updated_at: now,
+    };
+    let messages = serde_json::to_string(&conversation.messages)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize conversation: {e}")))?;
+    let metadata = conversation.metadata.as_ref().map(Value::to_string);
+    state.db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO conversations (id, user_id, messages, metadata, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![conversation.id, user.user_id, messages, metadata, now],
+        )
+    })?;
+    tracing::info!(conversation_id = %conversation.id, user_id = %user.user_id, "Conversation created");
+    Ok(Json(conversation.to_json()))
+}
+
+/// GET /v1/conversations
+///
+/// The caller's conversations, most recently updated first, without their
+/// messages.
+pub async fn list_conversations(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Value>, AppError> {
+    ensure_enabled(&state)?;
+    let data = state.db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, metadata, created_at, updated_at, json_array_length(messages)
+             FROM conversations WHERE user_id = ?1
+             ORDER BY updated_at DESC, id LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![user.user_id, LIST_LIMIT as i64], |row| {
+            let metadata: Option<String> = row.get(1)?;
+            Ok(json!({
+                "id": row.get::<_, String>(0)?,
+                "object": "conversation",
+                "created_at": row.get::<_, i64>(2)?,
+                "updated_at": row.get::<_, i64>(3)?,
+                "metadata": metadata.and_then(|m| serde_json::from_str::<Value>(&m).ok()),
+                "message_count": row.get::<_, i64>(4)?,
+            }))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(Json(json!({"object": "list", "data": data})))
+}
+
+/// GET /v1/conversations/{id}
+pub async fn get_conversation(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    ensure_enabled(&state)?;
+    Ok(Json(load(&state.db, &id, &user.user_id)?.to_json()))
+}
+
+/// DELETE /v1/conversations/{id}
+pub async fn delete_conversation(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    ensure_enabled(&state)?;
+    let deleted = state.db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM conversations WHERE id = ?1 AND user_id = ?2",
+            params![id, user.user_id],
+        )
+    })?;
+    if deleted == 0 {
+        return Err(AppError::NotFound(format!("Conversation '{id}' not found")));
+    }
+    Ok(Json(
+        json!({"id": id, "object": "conversation.deleted", "deleted": true}),
+    ))
+}
+
+/// POST /v1/conversations/{id}/completions
+///
+/// A chat completion whose `messages` are only the new turn. The stored
+/// history is put in front of them, and the turn and its reply are stored
+/// when the completion finishes. Streams like `/v1/chat/completions`.
+pub async fn create_completion(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatRequest>,
+) -> Result<Response, AppError> {
+    ensure_enabled(&state)?;
+    if request.messages.is_empty() {
+        return Err(AppError::InvalidParam {
+            param: "messages".to_string(),
+            message: "At least one new message is required".to_string(),
+        });
+    }
+    let request_id = headers
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map_or_else(|| Uuid::new_v4().to_string(), String::from);
+    let attribution = Attribution::from_request(&headers, request.user.as_deref())?;
+
+    // Stored history outlives uploads, so keep their data, not references.
+    uploads::resolve(&state.db, &user.user_id, &mut request.messages)?;
+    let conversation = load(&state.db, &id, &user.user_id)?;
+    let mut history = conversation.messages;
+    history.append(&mut request.messages);
+    let config = &state.config.conversations;
+    let (kept, dropped) = compact(history, config);
+    let summary = if !dropped.is_empty() && config.compaction == ConversationCompaction::Summarize {
+        let model = config.summary_model.as_deref().unwrap_or(&request.model);
+        summarize(
+            &state,
+            &user,
+            model,
+            conversation.summary.as_deref(),
+            &dropped,
+            &attribution,
+        )
+        .await
+        .or(conversation.summary)
+    } else {
+        conversation.summary
+    };
+    request.messages = with_summary(&kept, summary.as_deref());
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        conversation_id = %id,
+        model = %request.model,
+        messages = request.messages.len(),
+        dropped = dropped.len(),
+        stream = request.stream,
+        "Conversation completion request"
+    );
+
+    let update = Update {
+        db: state.db.clone(),
+        id,
+        user_id: user.user_id.clone(),
+        version: conversation.version,
+        messages: kept,
+        summary,
+    };
+
+    if request.stream {
+        let (inner, upstream) =
+            chat::open_chat_stream(state, user, request, request_id, attribution).await?;
+        let events = ConversationStream::new(inner, update)
+            .map(|msg| Ok::<_, std::convert::Infallible>(chat::sse_event(msg)));
+        let mut response = Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response();
+        chat::insert_upstream_rate_limit_headers(response.headers_mut(), upstream.as_ref());
+        return Ok(response);
+    }
+
+    let (response, upstream) =
+        chat::complete_chat(state, user, request, request_id, attribution).await?;
+    update.save(response.choices.first().map(|c| reply_message(&c.message)))?;
+    let mut http_response = Json(response).into_response();
+    chat::insert_upstream_rate_limit_headers(http_response.headers_mut(), upstream.as_ref());
+    Ok(http_response)
+}
+
+fn ensure_enabled(state: &AppState) -> Result<(), AppError> {
+    if state.config.conversations.enabled {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(
+            "Conversation storage is not enabled".to_string(),
+        ))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Compaction
+// ---------------------------------------------------------------------------
+
+/// Split `messages` into those to keep and the oldest ones over the limits.
+///
+/// Leading system messages are always kept. Turns are dropped whole, from
+/// the oldest, so a reply or tool result is never kept without the user
+/// message that led to it; the newest turn is kept even if it alone is
+/// over the limits.
+fn compact(
+    messages: Vec<ChatMessage>,
+    config: &ConversationsConfig,
+) -> (Vec<ChatMessage>, Vec<ChatMessage>) {
+    let pinned = messages
+        .iter()
+        .take_while(|m| m.role == MessageRole::System)
+        .count();
+    let over = |rest: &[ChatMessage]| {
+        rest.len() > config.max_messages
+            || (config.max_history_tokens > 0
+                && estimate_tokens(rest) > config.max_history_tokens as usize)
+    };
+    let mut start = pinned;
+    while start < messages.len() && over(&messages[start..]) {
+        let Some(next) = messages[start + 1..]
+            .iter()
+            .position(|m| m.role == MessageRole::User)
+        else {
+            break;
+        };
+        start += 1 + next;
+    }
+    let mut kept = messages;
+    let dropped = kept.drain(pinned..start).collect();
+    (kept, dropped)
+}
+
+/// Rough token count of `messages`: four characters a token, plus a few
+/// for each message's framing.
+fn estimate_tokens(messages: &[ChatMessage]) -> usize {
+    messages
+        .iter()
+        .map(|m| {
+            let text = m.content.as_ref().map_or(0, |c| c.as_text().len());
+            let calls: usize = m
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|c| c.function.name.len() + c.function.arguments.len())
+                .sum();
+            (text + calls) / 4 + 4
+        })
+        .sum()
+}
+
+/// `messages` with the summary of dropped turns after the leading system
+/// messages.
+fn with_summary(messages: &[ChatMessage], summary: Option<&str>) -> Vec<ChatMessage> {
+    let mut out = messages.to_vec();
+    if let Some(summary) = summary {
+        let at = out
+            .iter()
+            .take_while(|m| m.role == MessageRole::System)
+            .count();
+        out.insert(
+            at,
+            ChatMessage::text(
+                MessageRole::System,
+                format!("Summary of the earlier conversation:\n{summary}"),
+            ),
+        );
+    }
+    out
+}
+
+/// Ask `model` to fold `dropped` into the running summary. `None` if the
+/// call fails, in which case the dropped turns are lost as with truncation.
+async fn summarize(
+    state: &AppState,
+    user: &AuthUser,
+    model: &str,
+    previous: Option<&str>,
+    dropped: &[ChatMessage],
+    attribution: &Attribution,
+) -> Option<String> {
+    let mut transcript = String::new();
+    if let Some(previous) = previous {
+        transcript.push_str(&format!("Earlier summary:\n{previous}\n\n"));
+    }
+    for message in dropped {
+        let role = serde_json::to_value(&message.role).unwrap_or_default();
+        let role = role.as_str().unwrap_or("message");
+        if let Some(content) = &message.content {
+            transcript.push_str(&format!("{role}: {}\n", content.as_text()));
+        }
+        for call in message.tool_calls.iter().flatten() {
+            transcript.push_str(&format!(
+                "{role} called {}({})\n",
+                call.function.name, call.function.arguments
+            ));
+        }
+    }
+    let request = ChatRequest {
+        model: model.to_string(),
+        messages: vec![
+            ChatMessage::text(MessageRole::System, SUMMARY_PROMPT),
+            ChatMessage::text(MessageRole::User, transcript),
+        ],
+        temperature: None,
+        max_tokens: Some(SUMMARY_MAX_TOKENS),
+        stream: false,
+        top_p: None,
+        stop: None,
+        tools: None,
+        tool_choice: None,
+        stream_options: None,
+        google_search: false,
+        extra_body: None,
+        user: None,
+    };
+    match chat::complete_chat(
+        state.clone(),
+        user.clone(),
+        request,
+        Uuid::new_v4().to_string(),
+        attribution.clone(),
+    )
+    .await
+    {
+        Ok((response, _)) => response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|c| c.message.content)
+            .filter(|s| !s.trim().is_empty()),
+        Err(e) => {
+            tracing::warn!(error = %e, model, "Conversation summary failed; truncating instead");
+            None
+        }
+    }
+}
+
+/// The assistant message to store for a reply.
+fn reply_message(message: &ResponseMessage) -> ChatMessage {
+    ChatMessage {
+        role: MessageRole::Assistant,
+        content: message.content.clone().map(MessageContent::Text),
+        name: None,
+        tool_calls: message.tool_calls.clone().map(|calls| {
+            calls
+                .into_iter()
+                .map(|c| ToolCall { index: None, ..c })
+                .collect()
+        }),
+        tool_call_id: None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ConversationStream — stores the streamed reply
+// ---------------------------------------------------------------------------
+
+/// Passes a chat stream through, assembling the reply from its chunks, and
+/// stores the turn when the stream completes. Nothing is stored if the
+/// stream fails or the client goes away first.
+struct ConversationStream {
+    inner: ChatEventStream,
+    update: Option<Update>,
+    content: String,
+    tool_calls: Vec<(u32, ToolCall)>,
+}
+
+impl ConversationStream {
+    fn new(inner: ChatEventStream, update: Update) -> Self {
+        Self {
+            inner,
+            update: Some(update),
+            content: String::new(),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    fn on_chunk(&mut self, chunk: ChatChunk) {
+        let Some(choice) = chunk.choices.into_iter().find(|c| c.index == 0) else {
+            return;
+        };
+        if let Some(content) = choice.delta.content {
+            self.content.push_str(&content);
+        }
+        for call in choice.delta.tool_calls.into_iter().flatten() {
+            let index = call.index.unwrap_or(0);
+            match self.tool_calls.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, existing)) => existing
+                    .function
+                    .arguments
+                    .push_str(&call.function.arguments),
+                None => self.tool_calls.push((
+                    index,
+                    ToolCall {
+                        index: None,
+                        ..call
+                    },
+                )),
+            }
+        }
+    }
+
+    fn on_done(&mut self) {
+        let Some(update) = self.update.take() else {
+            return;
+        };
+        let tool_calls: Vec<ToolCall> = self.tool_calls.drain(..).map(|(_, c)| c).collect();
+        let reply = ChatMessage {
+            role: MessageRole::Assistant,
+            content: (!self.content.is_empty())
+                .then(|| MessageContent::Text(std::mem::take(&mut self.content))),
+            name: None,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            tool_call_id: None,
+        };
+        let id = update.id.clone();
+        if let Err(e) = update.save(Some(reply)) {
+            tracing::warn!(conversation_id = %id, error = %e, "Failed to store conversation turn");
+        }
+    }
+}
+
+impl Stream for ConversationStream {
+    type Item = SseMsg;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SseMsg>> {
+        let this = self.get_mut();
+        let msg = std::task::ready!(this.inner.as_mut().poll_next(cx));
+        match &msg {
+            Some(SseMsg::Data(payload)) if this.update.is_some() => {
+                match serde_json::from_str::<ChatChunk>(payload) {
+                    Ok(chunk) => this.on_chunk(chunk),
+                    // An error payload: the turn is not stored.
+                    Err(_) => this.update = None,
+                }
+            }
+            Some(SseMsg::Done) => this.on_done(),
+            _ => {}
+        }
+        Poll::Ready(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::api::test_support::{litellm_router, test_state, user};
+    use crate::config::Config;
+    use crate::providers::types::{ChunkChoice, Delta, FunctionCall};
+
+    fn msg(role: MessageRole, text: &str) -> ChatMessage {
+        ChatMessage::text(role, text)
+    }
+
+    fn texts(messages: &[ChatMessage]) -> Vec<&str> {
+        messages
+            .iter()
+            .map(|m| m.content.as_ref().map_or("", |c| c.as_text()))
+            .collect()
+    }
+
+    #[test]
+    fn test_compact_drops_whole_turns() {
+        let config = ConversationsConfig {
+            max_messages: 3,
+            ..Default::default()
+        };
+        let mut tool_reply = msg(MessageRole::Tool, "t1");
+        tool_reply.tool_call_id = Some("c1".to_string());
+        let messages = vec![
+            msg(MessageRole::System, "sys"),
+            msg(MessageRole::User, "u1"),
+            msg(MessageRole::Assistant, "a1"),
+            tool_reply,
+            msg(MessageRole::Assistant, "a1b"),
+            msg(MessageRole::User, "u2"),
+            msg(MessageRole::Assistant, "a2"),
+            msg(MessageRole::User, "u3"),
+        ];
+        let (kept, dropped) = compact(messages.clone(), &config);
+        assert_eq!(texts(&kept), ["sys", "u2", "a2", "u3"]);
+        assert_eq!(texts(&dropped), ["u1", "a1", "t1", "a1b"]);
+
+        // The newest turn is kept even when it alone is over the limit.
+        let config = ConversationsConfig {
+            max_messages: 100,
+            max_history_tokens: 1,
+            ..Default::default()
+        };
+        let (kept, _) = compact(messages, &config);
+        assert_eq!(texts(&kept), ["sys", "u3"]);
+
+        // Within the limits nothing is dropped.
+        let (kept, dropped) = compact(
+            vec![msg(MessageRole::User, "hi")],
+            &ConversationsConfig::default(),
+        );
+        assert_eq!(kept.len(), 1);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn test_summary_follows_system_messages() {
+        let messages = vec![msg(MessageRole::System, "sys"), msg(MessageRole::User, "u")];
+        let out = with_summary(&messages, Some("they met"));
+        assert_eq!(
+            texts(&out),
+            ["sys", "Summary of the earlier conversation:\nthey met", "u"]
+        );
+        assert_eq!(with_summary(&messages, None).len(), 2);
+    }
+
+    async fn body_json(response: Response) -> Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_completion_appends_to_history() {
+        let llm = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({"messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello!"},
+                {"role": "user", "content": "Again"},
+            ]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "c2", "object": "chat.completion", "created": 0, "model": "m",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello again!"}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 9, "completion_tokens": 3, "total_tokens": 12},
+            })))
+            .mount(&llm)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "c1", "object": "chat.completion", "created": 0, "model": "m",
+                "choices": [{"index": 0, "message": {"role": "assistant", "content": "Hello!"}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 2, "total_tokens": 7},
+            })))
+            .mount(&llm)
+            .await;
+
+        let router = litellm_router(&llm.uri()).await;
+        let mut config = Config::default();
+        config.conversations.enabled = true;
+        let state = test_state(config, router);
+
+        let created = create_conversation(
+            State(state.clone()),
+            Extension(user()),
+            Json(CreateConversationRequest {
+                messages: vec![msg(MessageRole::System, "Be brief.")],
+                metadata: Some(json!({"topic": "greetings"})),
+            }),
+        )
+        .await
+        .unwrap()
+        .0;
+        let id = created["id"].as_str().unwrap().to_string();
+        assert!(id.starts_with("conv_"));
+
+        for (turn, reply) in [("Hi", "Hello!"), ("Again", "Hello again!")] {
+            let request: ChatRequest = serde_json::from_value(json!({
+                "model": "m",
+                "messages": [{"role": "user", "content": turn}],
+            }))
+            .unwrap();
+            let response = create_completion(
+                State(state.clone()),
+                Extension(user()),
+                Path(id.clone()),
+                HeaderMap::new(),
+                Json(request),
+            )
+            .await
+            .unwrap();
+            let body = body_json(response).await;
+            assert_eq!(body["choices"][0]["message"]["content"], reply);
+        }
+
+        let stored = load(&state.db, &id, "u1").unwrap();
+        assert_eq!(
+            texts(&stored.messages),
+            ["Be brief.", "Hi", "Hello!", "Again", "Hello again!"]
+        );
+        assert_eq!(stored.version, 2);
+
+        // Other users cannot see it.
+        assert!(matches!(
+            load(&state.db, &id, "u2"),
+            Err(AppError::NotFound(_))
+        ));
+
+        let list = list_conversations(State(state.clone()), Extension(user()))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(list["data"][0]["id"], id.as_str());
+        assert_eq!(list["data"][0]["message_count"], 5);
+        assert_eq!(list["data"][0]["metadata"]["topic"], "greetings");
+
+        // A turn saved against an outdated version is refused.
+        let stale = Update {
+            db: state.db.clone(),
+            id: id.clone(),
+            user_id: "u1".to_string(),
+            version: 1,
+            messages: Vec::new(),
+            summary: None,
+        };
+        assert!(matches!(stale.save(None), Err(AppError::Conflict(_))));
+
+        let deleted =
+            delete_conversation(State(state.clone()), Extension(user()), Path(id.clone()))
+                .await
+                .unwrap();
+        assert_eq!(deleted.0["deleted"], true);
+        let err = get_conversation(State(state), Extension(user()), Path(id))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_stream_stores_assembled_reply() {
+        let state = test_state(
+            Config::default(),
+            crate::providers::router::ProviderRouter::new(),
+        );
+        state
+            .db
+            .with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO conversations (id, user_id, messages, created_at, updated_at)
+                     VALUES ('conv_1', 'u1', '[]', 0, 0)",
+                    [],
+                )
+            })
+            .unwrap();
+        let chunk = |content: Option<&str>, call: Option<ToolCall>| {
+            let chunk = ChatChunk {
+                id: "c".to_string(),
+                object: "chat.completion.chunk".to_string(),
+                created: 0,
+                model: "m".to_string(),
+                choices: vec![ChunkChoice {
+                    index: 0,
+                    delta: Delta {
+                        role: None,
+                        content: content.map(String::from),
+                        reasoning_content: None,
+                        tool_calls: call.map(|c| vec![c]),
+                        grounding: None,
+                    },
+                    finish_reason: None,
+                }],
+                usage: None,
+                tool_calls_emulated: false,
+            };
+            SseMsg::Data(serde_json::to_string(&chunk).unwrap())
+        };
+        let call = |id: &str, name: &str, args: &str| ToolCall {
+            index: Some(0),
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: args.to_string(),
+            },
+        };
+        let inner: ChatEventStream = Box::pin(tokio_stream::iter(vec![
+            chunk(Some("Let me "), None),
+            chunk(Some("check."), None),
+            chunk(None, Some(call("call_1", "lookup", "{\"q\":"))),
+            chunk(None, Some(call("", "", "\"x\"}"))),
+            SseMsg::Done,
+        ]));
+        let update = Update {
+            db: state.db.clone(),
+            id: "conv_1".to_string(),
+            user_id: "u1".to_string(),
+            version: 0,
+            messages: vec![msg(MessageRole::User, "q")],
+            summary: None,
+        };
+        let passed: Vec<SseMsg> = ConversationStream::new(inner, update).collect().await;
+        assert_eq!(passed.len(), 5);
+
+        let stored = load(&state.db, "conv_1", "u1").unwrap();
+        assert_eq!(texts(&stored.messages), ["q", "Let me check."]);
+        let calls = stored.messages[1].tool_calls.as_ref().unwrap();
+        assert_eq!(calls[0].id, "call_1");
+        assert_eq!(calls[0].function.arguments, "{\"q\":\"x\"}");
+    }
+}
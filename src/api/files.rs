@@ -0,0 +1,150 @@
+use axum::Extension;
+use axum::Json;
+use axum::extract::{Multipart, Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::files::{FileObject, FileStore, NewFile, PURPOSES};
+
+#[derive(Debug, Deserialize)]
+pub struct ListFilesQuery {
+    pub purpose: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileList {
+    pub object: &'static str,
+    pub data: Vec<FileObject>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeletedFile {
+    pub id: String,
+    pub object: &'static str,
+    pub deleted: bool,
+}
+
+fn file_store(state: &AppState) -> FileStore {
+    FileStore::new(state.db.clone(), state.config.files.storage_dir.as_deref())
+}
+
+/// POST /v1/files
+///
+/// Upload a file as `multipart/form-data` with `file` and `purpose` fields.
+/// The size limit comes from `files.max_upload_mb`.
+pub async fn upload_file(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    mut multipart: Multipart,
+) -> Result<Json<FileObject>, AppError> {
+    let bad_form = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("Invalid multipart body: {e}"))
+    };
+    let max_bytes = state.config.files.max_upload_mb as usize * 1024 * 1024;
+
+    let mut purpose = None;
+    let mut upload: Option<(String, Option<String>, Vec<u8>)> = None;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(bad_form)? {
+        match field.name().unwrap_or_default() {
+            "purpose" => purpose = Some(field.text().await.map_err(bad_form)?),
+            "file" => {
+                let filename = field.file_name().unwrap_or("upload").to_string();
+                let content_type = field.content_type().map(String::from);
+                let mut data = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(bad_form)? {
+                    if data.len() + chunk.len() > max_bytes {
+                        return Err(AppError::BadRequest(format!(
+                            "File exceeds the {} MB upload limit",
+                            state.config.files.max_upload_mb
+                        )));
+                    }
+                    data.extend_from_slice(&chunk);
+                }
+                upload = Some((filename, content_type, data));
+            }
+            _ => {}
+        }
+    }
+
+    let purpose = purpose.ok_or_else(|| AppError::BadRequest("Missing 'purpose' field".into()))?;
+    if !PURPOSES.contains(&purpose.as_str()) {
+        return Err(AppError::BadRequest(format!(
+            "Invalid purpose '{purpose}', expected one of: {}",
+            PURPOSES.join(", ")
+        )));
+    }
+    let (filename, content_type, data) =
+        upload.ok_or_else(|| AppError::BadRequest("Missing 'file' field".into()))?;
+
+    let file = file_store(&state).create(
+        &user.user_id,
+        NewFile {
+            filename: &filename,
+            purpose: &purpose,
+            content_type: content_type.as_deref(),
+            data: &data,
+        },
+    )?;
+
+    tracing::info!(
+        user_id = %user.user_id,
+        file_id = %file.id,
+        bytes = file.bytes,
+        purpose = %file.purpose,
+        "File uploaded"
+    );
+
+    Ok(Json(file))
+}
+
+/// GET /v1/files
+pub async fn list_files(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<ListFilesQuery>,
+) -> Result<Json<FileList>, AppError> {
+    let data = file_store(&state).list(&user.user_id, query.purpose.as_deref())?;
+    Ok(Json(FileList {
+        object: "list",
+        data,
+    }))
+}
+
+/// GET /v1/files/{id}
+pub async fn get_file(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<FileObject>, AppError> {
+    Ok(Json(file_store(&state).get(&user.user_id, &id)?))
+}
+
+/// GET /v1/files/{id}/content
+pub async fn get_file_content(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    let (data, content_type) = file_store(&state).content(&user.user_id, &id)?;
+    let content_type = content_type.unwrap_or_else(|| "application/octet-stream".to_string());
+    Ok(([(header::CONTENT_TYPE, content_type)], data).into_response())
+}
+
+/// DELETE /v1/files/{id}
+pub async fn delete_file(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<DeletedFile>, AppError> {
+    file_store(&state).delete(&user.user_id, &id)?;
+    Ok(Json(DeletedFile {
+        id,
+        object: "file",
+        deleted: true,
+    }))
+}
@@ -0,0 +1,378 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::Extension;
+use axum::Json;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use bytes::Bytes;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::chat::insert_upstream_rate_limit_headers;
+use crate::auth::AuthUser;
+use crate::budget::tracker::BudgetStatus;
+use crate::budget::{Attribution, AuditEntry};
+use crate::error::AppError;
+use crate::providers::ProviderError;
+use crate::providers::cost::CostCalculator;
+use crate::providers::gemini::models::google::GoogleRequest;
+use crate::providers::types::Usage;
+
+/// Gemini API method named after the colon in the request path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    Generate,
+    Stream,
+}
+
+/// Query parameters of the Gemini API. `key` is read by the auth middleware.
+#[derive(Debug, Default, Deserialize)]
+pub struct GeminiParams {
+    /// `sse` streams Server-Sent Events; otherwise a JSON array is streamed.
+    alt: Option<String>,
+}
+
+/// POST /v1beta/models/{model}:generateContent
+/// POST /v1beta/models/{model}:streamGenerateContent
+///
+/// Native Gemini API. Request and response bodies use the Google GenAI
+/// format, so tools built for the Gemini SDKs can use gaud as their base
+/// URL. Routed to whichever provider claims the model via
+/// `supports_gemini_native`.
+pub async fn generate_content(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(target): Path<String>,
+    Query(params): Query<GeminiParams>,
+    Json(request): Json<Value>,
+) -> Result<Response, AppError> {
+    let request_id = Uuid::new_v4().to_string();
+    let (model, method) = parse_target(&target)?;
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %model,
+        stream = method == Method::Stream,
+        "Gemini API request"
+    );
+
+    validate_request(&request)?;
+
+    // Token usage is only known from the response, so only users who are
+    // already over budget are turned away.
+    if state.config.budget.enabled
+        && state.budget.check_budget(
+            &user.user_id,
+            state.settings.config().budget.warning_threshold_percent,
+        )? == BudgetStatus::Exceeded
+    {
+        return Err(AppError::BudgetExceeded(format!(
+            "Budget exceeded for user '{}'",
+            user.name
+        )));
+    }
+
+    let audit = NativeAudit {
+        audit_tx: state.audit_tx.clone(),
+        cost_calculator: Arc::clone(&state.cost_calculator),
+        attribution: Attribution::default().with_user(&user),
+        user_id: user.user_id,
+        request_id,
+        model: model.to_string(),
+        start: Instant::now(),
+        usage: None,
+        error: None,
+    };
+
+    match method {
+        Method::Generate => generate(state, audit, request).await,
+        Method::Stream => stream(state, audit, request, params.alt.as_deref() == Some("sse")).await,
+    }
+}
+
+async fn generate(
+    state: AppState,
+    mut audit: NativeAudit,
+    request: Value,
+) -> Result<Response, AppError> {
+    let mut router = state.router.write().await;
+    let result = router.generate_content(&audit.model, &request).await;
+    let upstream = router.upstream_rate_limit(&audit.model);
+    drop(router);
+
+    match result {
+        Ok(response) => {
+            audit.observe(&response);
+            let mut response = Json(response).into_response();
+            insert_upstream_rate_limit_headers(response.headers_mut(), upstream.as_ref());
+            Ok(response)
+        }
+        Err(e) => {
+            audit.error = Some(e.to_string());
+            Err(AppError::from(e))
+        }
+    }
+}
+
+async fn stream(
+    state: AppState,
+    mut audit: NativeAudit,
+    request: Value,
+    sse: bool,
+) -> Result<Response, AppError> {
+    let mut router = state.router.write().await;
+    let result = router.stream_generate_content(&audit.model, &request).await;
+    let upstream = router.upstream_rate_limit(&audit.model);
+    drop(router);
+
+    let chunks = match result {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            audit.error = Some(e.to_string());
+            return Err(AppError::from(e));
+        }
+    };
+
+    // The audit entry is sent when the stream is dropped, whether it ran to
+    // the end or the client went away.
+    let chunks = chunks.map(move |chunk| {
+        let value = match chunk {
+            Ok(value) => value,
+            Err(e) => {
+                audit.error.get_or_insert_with(|| e.to_string());
+                stream_error(&e)
+            }
+        };
+        audit.observe(&value);
+        value
+    });
+
+    let mut response = if sse {
+        let events =
+            chunks.map(|value| Ok::<_, Infallible>(Event::default().data(value.to_string())));
+        Sse::new(events).into_response()
+    } else {
+        // Without `alt=sse` the Gemini API streams a single JSON array.
+        let body = async_stream::stream! {
+            yield Ok::<_, Infallible>(Bytes::from_static(b"["));
+            let mut chunks = Box::pin(chunks);
+            let mut first = true;
+            while let Some(value) = chunks.next().await {
+                let separator = if first { "" } else { ",\r\n" };
+                first = false;
+                yield Ok(Bytes::from(format!("{separator}{value}")));
+            }
+            yield Ok(Bytes::from_static(b"]"));
+        };
+        (
+            [(header::CONTENT_TYPE, "application/json")],
+            Body::from_stream(body),
+        )
+            .into_response()
+    };
+    insert_upstream_rate_limit_headers(response.headers_mut(), upstream.as_ref());
+    Ok(response)
+}
+
+/// Split `gemini-2.5-flash:generateContent` into model and method.
+fn parse_target(target: &str) -> Result<(&str, Method), AppError> {
+    let (model, method) = target
+        .rsplit_once(':')
+        .ok_or_else(|| AppError::NotFound(format!("No Gemini API method in '{target}'")))?;
+    let method = match method {
+        "generateContent" => Method::Generate,
+        "streamGenerateContent" => Method::Stream,
+        other => {
+            return Err(AppError::NotFound(format!(
+                "Unsupported Gemini API method: {other}"
+            )));
+        }
+    };
+    if model.is_empty() {
+        return Err(AppError::BadRequest("model must not be empty".into()));
+    }
+    Ok((model, method))
+}
+
+/// Reject bodies that are not Gemini API requests.
+fn validate_request(request: &Value) -> Result<(), AppError> {
+    let parsed: GoogleRequest = serde_json::from_value(request.clone())
+        .map_err(|e| AppError::BadRequest(format!("Invalid Gemini request: {e}")))?;
+    if parsed.contents.is_empty() {
+        return Err(AppError::BadRequest("contents must not be empty".into()));
+    }
+    Ok(())
+}
+
+/// Error chunk in the Gemini API error format, for failures mid-stream.
+fn stream_error(err: &ProviderError) -> Value {
+    serde_json::json!({
+        "error": {
+            "code": 500,
+            "message": err.to_string(),
+            "status": "INTERNAL",
+        }
+    })
+}
+
+/// Token usage reported in a response's `usageMetadata`.
+fn usage_from(response: &Value) -> Option<Usage> {
+    let meta = response.get("usageMetadata")?;
+    let count = |key: &str| meta.get(key).and_then(Value::as_u64).unwrap_or(0) as u32;
+    let prompt_tokens = count("promptTokenCount");
+    let completion_tokens = count("candidatesTokenCount") + count("thoughtsTokenCount");
+    Some(Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+        prompt_tokens_details: None,
+        completion_tokens_details: None,
+    })
+}
+
+/// Audit log entry of one native request, sent when dropped.
+struct NativeAudit {
+    audit_tx: UnboundedSender<AuditEntry>,
+    cost_calculator: Arc<CostCalculator>,
+    /// Records an admin impersonating the user.
+    attribution: Attribution,
+    user_id: String,
+    request_id: String,
+    model: String,
+    start: Instant,
+    /// Latest usage seen; streamed chunks carry running totals.
+    usage: Option<Usage>,
+    error: Option<String>,
+}
+
+impl NativeAudit {
+    fn observe(&mut self, response: &Value) {
+        if let Some(usage) = usage_from(response) {
+            self.usage = Some(usage);
+        }
+    }
+}
+
+impl Drop for NativeAudit {
+    fn drop(&mut self) {
+        let usage = self.usage.take().unwrap_or_default();
+        let (status, cost) = match self.error.take() {
+            Some(e) => (format!("error: {e}"), 0.0),
+            None => (
+                "success".to_string(),
+                self.cost_calculator.calculate_cost(&self.model, &usage),
+            ),
+        };
+        let _ = self.audit_tx.send(AuditEntry {
+            user_id: std::mem::take(&mut self.user_id),
+            request_id: std::mem::take(&mut self.request_id),
+            provider: self.model.clone(),
+            model: std::mem::take(&mut self.model),
+            input_tokens: usage.prompt_tokens,
+            output_tokens: usage.completion_tokens,
+            audio_seconds: 0.0,
+            premium_requests: 0.0,
+            cost,
+            cost_saved: 0.0,
+            latency_ms: self.start.elapsed().as_millis() as u64,
+            status,
+            finish_reason: None,
+            attribution: std::mem::take(&mut self.attribution),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            parse_target("gemini-2.5-flash:generateContent").unwrap(),
+            ("gemini-2.5-flash", Method::Generate)
+        );
+        assert_eq!(
+            parse_target("gemini-2.5-pro:streamGenerateContent").unwrap(),
+            ("gemini-2.5-pro", Method::Stream)
+        );
+        assert!(matches!(
+            parse_target("gemini-2.5-pro:countTokens"),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            parse_target("gemini-2.5-pro"),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_request() {
+        let ok = serde_json::json!({
+            "contents": [{"role": "user", "parts": [{"text": "Hi"}]}],
+            "safetySettings": [{"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_NONE"}],
+        });
+        assert!(validate_request(&ok).is_ok());
+        assert!(matches!(
+            validate_request(&serde_json::json!({"contents": []})),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            validate_request(&serde_json::json!({"contents": "Hi"})),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_usage_from() {
+        let response = serde_json::json!({
+            "candidates": [],
+            "usageMetadata": {
+                "promptTokenCount": 12,
+                "candidatesTokenCount": 30,
+                "thoughtsTokenCount": 8,
+                "totalTokenCount": 50,
+            }
+        });
+        let usage = usage_from(&response).unwrap();
+        assert_eq!(usage.prompt_tokens, 12);
+        assert_eq!(usage.completion_tokens, 38);
+        assert_eq!(usage.total_tokens, 50);
+        assert!(usage_from(&serde_json::json!({"candidates": []})).is_none());
+    }
+
+    #[test]
+    fn test_audit_sent_on_drop() {
+        let (audit_tx, mut audit_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut audit = NativeAudit {
+            audit_tx,
+            cost_calculator: Arc::new(CostCalculator::new()),
+            attribution: Attribution::default(),
+            user_id: "u1".into(),
+            request_id: "r1".into(),
+            model: "gemini-2.5-flash".into(),
+            start: Instant::now(),
+            usage: None,
+            error: None,
+        };
+        audit.observe(&serde_json::json!({
+            "usageMetadata": {"promptTokenCount": 5, "candidatesTokenCount": 7}
+        }));
+        drop(audit);
+
+        let entry = audit_rx.try_recv().unwrap();
+        assert_eq!(entry.status, "success");
+        assert_eq!(entry.input_tokens, 5);
+        assert_eq!(entry.output_tokens, 7);
+    }
+}
@@ -1,21 +1,109 @@
-use axum::Json;
-use axum::extract::State;
-use serde::Serialize;
+use std::time::Instant;
+
+use axum::extract::{Query, State};
+use axum::http::Uri;
+use axum::{Extension, Json};
+use serde::{Deserialize, Serialize};
 
 use crate::AppState;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::providers::health::{CircuitState, HealthProbe};
 use crate::providers::types::ProviderStatus;
 
+/// Providers whose credentials are OAuth tokens managed by gaud.
+const OAUTH_PROVIDERS: [&str; 4] = ["claude", "gemini", "copilot", "kiro"];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HealthQuery {
+    /// Include the detailed report (admin only).
+    #[serde(default)]
+    pub detail: bool,
+}
+
+/// Whether a `/health` request asks for the detailed report, which needs
+/// authentication unlike the plain one.
+pub fn wants_detail(uri: &Uri) -> bool {
+    Query::<HealthQuery>::try_from_uri(uri).is_ok_and(|q| q.detail)
+}
+
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
     pub status: String,
     pub providers: Vec<ProviderStatus>,
+    /// Only with `?detail=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<HealthDetail>,
+}
+
+/// Detailed health of the providers and the services gaud depends on.
+#[derive(Debug, Serialize)]
+pub struct HealthDetail {
+    pub providers: Vec<ProviderHealth>,
+    pub database: ComponentHealth,
+    pub cache: CacheHealth,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProviderHealth {
+    pub provider: String,
+    /// `closed`, `open` or `half-open`.
+    pub circuit: String,
+    pub consecutive_failures: u32,
+    pub secs_since_success: Option<u64>,
+    pub secs_since_failure: Option<u64>,
+    /// Last active health check, `null` before the first one.
+    pub last_probe: Option<HealthProbe>,
+    /// OAuth token state; `null` for providers without OAuth.
+    pub token: Option<TokenHealth>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenHealth {
+    pub authenticated: bool,
+    pub expired: bool,
+    pub needs_refresh: bool,
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Result of a round trip to a dependency.
+#[derive(Debug, Serialize)]
+pub struct ComponentHealth {
+    pub ok: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheHealth {
+    pub enabled: bool,
+    /// `embedded` or `remote`, when enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// Stored entries, when the backend answered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entries: Option<u64>,
+    #[serde(flatten)]
+    pub check: Option<ComponentHealth>,
 }
 
 /// GET /health
 ///
 /// Returns overall system health and per-provider status.
-/// No authentication required.
-pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
+/// No authentication required, except for `?detail=true`, which adds
+/// circuit breaker, probe, token, database and cache status for admins.
+pub async fn health_check(
+    State(state): State<AppState>,
+    Query(params): Query<HealthQuery>,
+    user: Option<Extension<AuthUser>>,
+) -> Result<Json<HealthResponse>, AppError> {
+    if params.detail && !user.is_some_and(|Extension(u)| u.is_admin()) {
+        return Err(AppError::Forbidden(
+            "Detailed health requires the admin role".to_string(),
+        ));
+    }
+
     let router = state.router.read().await;
 
     // Build provider status from the router's available data.
@@ -33,26 +121,126 @@ pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse>
 
             let healthy = router
                 .circuit_state(id)
-                .map(|s| s != crate::providers::health::CircuitState::Open)
+                .map(|s| s != CircuitState::Open)
                 .unwrap_or(false);
 
             let latency_ms = router.stats(id).map(|s| s.avg_latency_ms());
+            let quota = router.quota_status(id).filter(|q| {
+                q.daily_limit.is_some() || q.monthly_limit.is_some() || q.exhausted_until.is_some()
+            });
 
             ProviderStatus {
                 provider: id.clone(),
                 healthy,
                 models,
                 latency_ms,
+                quota,
             }
         })
         .collect();
 
-    Json(HealthResponse {
-        status: "ok".to_string(),
+    if !params.detail {
+        return Ok(Json(HealthResponse {
+            status: "ok".to_string(),
+            providers,
+            detail: None,
+        }));
+    }
+
+    let provider_health: Vec<ProviderHealth> = provider_ids
+        .iter()
+        .map(|id| {
+            let circuit = router.circuit(id);
+            ProviderHealth {
+                provider: id.clone(),
+                circuit: circuit.map(|c| c.state()).unwrap_or_default().to_string(),
+                consecutive_failures: circuit.map_or(0, |c| c.failure_count()),
+                secs_since_success: circuit
+                    .and_then(|c| c.time_since_success())
+                    .map(|d| d.as_secs()),
+                secs_since_failure: circuit
+                    .and_then(|c| c.time_since_failure())
+                    .map(|d| d.as_secs()),
+                last_probe: router.last_probe(id),
+                token: token_health(&state, id),
+            }
+        })
+        .collect();
+    drop(router);
+
+    let database = database_health(&state);
+    let cache = cache_health(&state).await;
+    let degraded = !database.ok || cache.check.as_ref().is_some_and(|c| !c.ok);
+
+    Ok(Json(HealthResponse {
+        status: if degraded { "degraded" } else { "ok" }.to_string(),
         providers,
+        detail: Some(HealthDetail {
+            providers: provider_health,
+            database,
+            cache,
+        }),
+    }))
+}
+
+fn token_health(state: &AppState, provider: &str) -> Option<TokenHealth> {
+    if !OAUTH_PROVIDERS.contains(&provider) {
+        return None;
+    }
+    let status = state.oauth_manager.get_status(provider).ok()?;
+    Some(TokenHealth {
+        authenticated: status.authenticated,
+        expired: status.expired,
+        needs_refresh: status.needs_refresh,
+        expires_in_secs: status.expires_in_secs,
     })
 }
 
+fn database_health(state: &AppState) -> ComponentHealth {
+    let started = Instant::now();
+    let result = state
+        .db
+        .with_conn(|conn| conn.query_row("SELECT 1", [], |row| row.get::<_, i64>(0)));
+    ComponentHealth {
+        ok: result.is_ok(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+async fn cache_health(state: &AppState) -> CacheHealth {
+    let Some(cache) = &state.cache else {
+        return CacheHealth {
+            enabled: false,
+            backend: None,
+            entries: None,
+            check: None,
+        };
+    };
+    let backend = if state.config.cache.backend_url.is_some() {
+        "remote"
+    } else {
+        "embedded"
+    };
+    let started = Instant::now();
+    let result = cache.count().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let (entries, error) = match result {
+        Ok(n) => (Some(n), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    CacheHealth {
+        enabled: true,
+        backend: Some(backend.to_string()),
+        entries,
+        check: Some(ComponentHealth {
+            ok: error.is_none(),
+            latency_ms,
+            error,
+        }),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,13 +254,16 @@ mod tests {
                 healthy: true,
                 models: vec!["model-1".to_string()],
                 latency_ms: Some(42),
+                quota: None,
             }],
+            detail: None,
         };
 
         let json = serde_json::to_value(&response).unwrap();
         assert_eq!(json["status"], "ok");
         assert_eq!(json["providers"][0]["provider"], "test");
         assert_eq!(json["providers"][0]["healthy"], true);
+        assert!(json.get("detail").is_none());
     }
 
     #[test]
@@ -80,10 +271,37 @@ mod tests {
         let response = HealthResponse {
             status: "ok".to_string(),
             providers: vec![],
+            detail: None,
         };
 
         let json = serde_json::to_value(&response).unwrap();
         assert_eq!(json["status"], "ok");
         assert!(json["providers"].as_array().unwrap().is_empty());
     }
+
+    #[test]
+    fn test_wants_detail() {
+        assert!(wants_detail(&"/health?detail=true".parse().unwrap()));
+        assert!(!wants_detail(&"/health?detail=false".parse().unwrap()));
+        assert!(!wants_detail(&"/health".parse().unwrap()));
+        assert!(!wants_detail(&"/health?detail=yes".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_cache_health_flattens_check() {
+        let cache = CacheHealth {
+            enabled: true,
+            backend: Some("embedded".to_string()),
+            entries: Some(12),
+            check: Some(ComponentHealth {
+                ok: true,
+                latency_ms: 1,
+                error: None,
+            }),
+        };
+        let json = serde_json::to_value(&cache).unwrap();
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["entries"], 12);
+        assert!(json.get("error").is_none());
+    }
 }
@@ -0,0 +1,164 @@
+use std::time::Instant;
+
+use axum::Extension;
+use axum::Json;
+use axum::extract::State;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::auth::AuthUser;
+use crate::budget::tracker::BudgetStatus;
+use crate::budget::{Attribution, AuditEntry};
+use crate::error::AppError;
+use crate::providers::types::{ImageGenerationRequest, ImageGenerationResponse};
+
+/// Largest `n` accepted in a single request (matches the OpenAI limit).
+const MAX_IMAGES_PER_REQUEST: u32 = 10;
+
+/// POST /v1/images/generations
+///
+/// OpenAI-compatible image generation. Routed to whichever provider claims
+/// the model via `supports_image_model`. Images are priced per image, so the
+/// full cost is checked against the user's budget before the provider is
+/// called.
+pub async fn create_image(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(request): Json<ImageGenerationRequest>,
+) -> Result<Json<ImageGenerationResponse>, AppError> {
+    let request_id = Uuid::new_v4().to_string();
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %request.model,
+        n = request.count(),
+        "Image generation request"
+    );
+
+    validate_request(&request)?;
+
+    if state.config.budget.enabled {
+        let estimated = state.cost_calculator.calculate_image_cost(
+            &request.model,
+            request.size.as_deref(),
+            request.count(),
+        );
+        if state
+            .budget
+            .check_projected_cost(&user.user_id, estimated)?
+            == BudgetStatus::Exceeded
+        {
+            return Err(AppError::BudgetExceeded(format!(
+                "Generating {} image(s) (${estimated:.4}) would exceed your budget",
+                request.count()
+            )));
+        }
+    }
+
+    let attribution = Attribution::default().with_user(&user);
+    let start = Instant::now();
+    let mut router = state.router.write().await;
+    let result = router.generate_images(&request).await;
+    drop(router);
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(response) => {
+            let cost = state.cost_calculator.calculate_image_cost(
+                &request.model,
+                request.size.as_deref(),
+                response.data.len() as u32,
+            );
+
+            let _ = state.audit_tx.send(AuditEntry {
+                user_id: user.user_id,
+                request_id,
+                provider: request.model.clone(),
+                model: request.model,
+                input_tokens: 0,
+                output_tokens: 0,
+                audio_seconds: 0.0,
+                premium_requests: 0.0,
+                cost,
+                cost_saved: 0.0,
+                latency_ms,
+                status: "success".to_string(),
+                finish_reason: None,
+                attribution,
+            });
+
+            Ok(Json(response))
+        }
+        Err(e) => {
+            let _ = state.audit_tx.send(AuditEntry {
+                user_id: user.user_id,
+                request_id,
+                provider: String::new(),
+                model: request.model,
+                input_tokens: 0,
+                output_tokens: 0,
+                audio_seconds: 0.0,
+                premium_requests: 0.0,
+                cost: 0.0,
+                cost_saved: 0.0,
+                latency_ms,
+                status: format!("error: {e}"),
+                finish_reason: None,
+                attribution,
+            });
+
+            Err(AppError::from(e))
+        }
+    }
+}
+
+/// Reject requests the providers would refuse anyway.
+fn validate_request(request: &ImageGenerationRequest) -> Result<(), AppError> {
+    if request.prompt.trim().is_empty() {
+        return Err(AppError::InvalidParam {
+            param: "prompt".into(),
+            message: "must not be empty".into(),
+        });
+    }
+    let n = request.count();
+    if n == 0 || n > MAX_IMAGES_PER_REQUEST {
+        return Err(AppError::InvalidParam {
+            param: "n".into(),
+            message: format!("must be between 1 and {MAX_IMAGES_PER_REQUEST}"),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(prompt: &str, n: Option<u32>) -> ImageGenerationRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "dall-e-3",
+            "prompt": prompt,
+            "n": n,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_request() {
+        assert!(validate_request(&request("a cat", None)).is_ok());
+        assert!(validate_request(&request("a cat", Some(10))).is_ok());
+        assert!(matches!(
+            validate_request(&request("  ", None)),
+            Err(AppError::InvalidParam { param, .. }) if param == "prompt"
+        ));
+        assert!(matches!(
+            validate_request(&request("a cat", Some(0))),
+            Err(AppError::InvalidParam { param, .. }) if param == "n"
+        ));
+        assert!(matches!(
+            validate_request(&request("a cat", Some(11))),
+            Err(AppError::InvalidParam { .. })
+        ));
+    }
+}
@@ -0,0 +1,636 @@
+//! Model Context Protocol (MCP) server facade.
+//!
+//! MCP clients (Claude Desktop, IDE agents) speak JSON-RPC 2.0 to gaud and
+//! see its models as two tools: `chat`, which sends a prompt to a model and
+//! returns the reply, and `list_models`. A `chat` call goes through the
+//! same quota, budget, routing, cache and usage log as
+//! `/v1/chat/completions`.
+//!
+//! Transports:
+//! - `POST /mcp`: one JSON-RPC message per request, answered in the
+//!   response body (202 for notifications).
+//! - `GET /mcp/sse` + `POST /mcp/messages?session_id=...`: the HTTP+SSE
+//!   transport. The stream's first `endpoint` event names the URL to post
+//!   messages to; answers arrive as `message` events.
+//! - stdio: `gaud mcp` relays stdin/stdout to `POST /mcp` (see
+//!   [`crate::mcp::stdio`]).
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use axum::Extension;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Json, Response};
+use futures::Stream;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::chat::complete_chat;
+use crate::auth::AuthUser;
+use crate::budget::Attribution;
+use crate::budget::quota::QuotaDecision;
+use crate::budget::tracker::BudgetStatus;
+use crate::error::AppError;
+use crate::providers::router::VIRTUAL_MODEL_PREFIX;
+use crate::providers::types::{ChatMessage, ChatRequest, ChatResponse, MessageRole};
+
+/// Protocol revisions gaud accepts, newest first. A client asking for any
+/// other revision is offered the newest.
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Messages buffered per SSE session before a slow client holds up replies.
+const SESSION_BUFFER: usize = 32;
+
+/// Open HTTP+SSE sessions, by session ID.
+#[derive(Default)]
+pub struct McpSessions {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+struct Session {
+    user_id: String,
+    tx: mpsc::Sender<String>,
+}
+
+impl McpSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a session for `user_id`; replies are read from the receiver.
+    fn open(&self, user_id: &str) -> (String, mpsc::Receiver<String>) {
+        let id = Uuid::new_v4().simple().to_string();
+        let (tx, rx) = mpsc::channel(SESSION_BUFFER);
+        let session = Session {
+            user_id: user_id.to_string(),
+            tx,
+        };
+        self.sessions.lock().unwrap().insert(id.clone(), session);
+        (id, rx)
+    }
+
+    /// The reply channel of session `id`, if it is open and belongs to
+    /// `user_id`.
+    fn sender(&self, id: &str, user_id: &str) -> Option<mpsc::Sender<String>> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(id)
+            .filter(|s| s.user_id == user_id)
+            .map(|s| s.tx.clone())
+    }
+
+    fn close(&self, id: &str) {
+        self.sessions.lock().unwrap().remove(id);
+    }
+
+    /// Number of open sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Removes a session when its event stream is dropped.
+struct SessionGuard {
+    sessions: Arc<McpSessions>,
+    id: String,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        self.sessions.close(&self.id);
+    }
+}
+
+/// POST /mcp
+///
+/// One JSON-RPC message in, its response out. Notifications and client
+/// responses get 202 with no body.
+pub async fn post_message(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    body: String,
+) -> Result<Response, AppError> {
+    ensure_enabled(&state)?;
+    Ok(match handle_message(&state, &user, &body).await {
+        Some(reply) => Json(reply).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    })
+}
+
+/// GET /mcp/sse
+///
+/// Open an HTTP+SSE session. The session lives as long as the stream.
+pub async fn open_sse(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    ensure_enabled(&state)?;
+    let (id, rx) = state.mcp_sessions.open(&user.user_id);
+    tracing::debug!(session_id = %id, user_id = %user.user_id, "MCP SSE session opened");
+
+    let endpoint = Event::default()
+        .event("endpoint")
+        .data(format!("/mcp/messages?session_id={id}"));
+    let guard = SessionGuard {
+        sessions: state.mcp_sessions.clone(),
+        id,
+    };
+    let messages = ReceiverStream::new(rx).map(move |reply| {
+        let _guard = &guard;
+        Ok(Event::default().event("message").data(reply))
+    });
+    let events = tokio_stream::once(Ok(endpoint)).chain(messages);
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionQuery {
+    session_id: String,
+}
+
+/// POST /mcp/messages?session_id=...
+///
+/// A message for an SSE session. It is answered on the session's stream,
+/// so this returns 202 as soon as the message is accepted.
+pub async fn post_session_message(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<SessionQuery>,
+    body: String,
+) -> Result<StatusCode, AppError> {
+    ensure_enabled(&state)?;
+    let tx = state
+        .mcp_sessions
+        .sender(&query.session_id, &user.user_id)
+        .ok_or_else(|| AppError::NotFound("Unknown MCP session".to_string()))?;
+
+    // A tool call can take as long as a completion; answer it in the
+    // background so the client can keep posting (pings, cancellations).
+    tokio::spawn(async move {
+        if let Some(reply) = handle_message(&state, &user, &body).await {
+            // The client closed the stream; nobody is left to tell.
+            let _ = tx.send(reply.to_string()).await;
+        }
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+fn ensure_enabled(state: &AppState) -> Result<(), AppError> {
+    if state.config.mcp.enabled {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(
+            "The MCP server is disabled (mcp.enabled)".to_string(),
+        ))
+    }
+}
+
+/// A JSON-RPC error.
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Handle one JSON-RPC message, returning the response to send, if any.
+pub async fn handle_message(state: &AppState, user: &AuthUser, body: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(body) {
+        Ok(v) => v,
+        Err(e) => {
+            let err = RpcError::new(PARSE_ERROR, format!("Parse error: {e}"));
+            return Some(error_response(Value::Null, err));
+        }
+    };
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        // gaud sends no requests, so a response from the client needs no
+        // answer.
+        if message.get("result").is_some() || message.get("error").is_some() {
+            return None;
+        }
+        let err = RpcError::new(
+            INVALID_REQUEST,
+            "Invalid request: expected a JSON-RPC message",
+        );
+        return Some(error_response(id.unwrap_or(Value::Null), err));
+    };
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = dispatch(state, user, method, &params).await;
+    // Notifications are never answered, not even with an error.
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(err) => error_response(id, err),
+    })
+}
+
+fn error_response(id: Value, err: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": err.code, "message": err.message },
+    })
+}
+
+async fn dispatch(
+    state: &AppState,
+    user: &AuthUser,
+    method: &str,
+    params: &Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "initialize" => Ok(initialize(params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => {
+            Ok(json!({ "tools": tool_definitions(&visible_models(state, user).await) }))
+        }
+        "tools/call" => call_tool(state, user, params).await,
+        m if m.starts_with("notifications/") => Ok(Value::Null),
+        m => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method not found: {m}"),
+        )),
+    }
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or_default();
+    let version = PROTOCOL_VERSIONS
+        .iter()
+        .find(|v| **v == requested)
+        .unwrap_or(&PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "gaud", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Use the `chat` tool to ask one of the listed models; \
+                         `list_models` shows which models are available.",
+    })
+}
+
+/// Models the caller may use, sorted: every routed model, then virtual
+/// models, less those the caller's token scope excludes.
+async fn visible_models(state: &AppState, user: &AuthUser) -> Vec<String> {
+    let router = state.router.read().await;
+    let mut models: Vec<String> = router
+        .available_models()
+        .into_iter()
+        .map(|(model, _)| model)
+        .collect();
+    models.sort();
+    models.dedup();
+    models.extend(
+        router
+            .virtual_models()
+            .keys()
+            .map(|name| format!("{VIRTUAL_MODEL_PREFIX}{name}")),
+    );
+    models.retain(|m| user.scope.as_ref().is_none_or(|s| s.allows_model(m)));
+    models
+}
+
+fn tool_definitions(models: &[String]) -> Value {
+    let mut model = json!({ "type": "string", "description": "Model to ask" });
+    if !models.is_empty() {
+        model["enum"] = json!(models);
+    }
+    json!([
+        {
+            "name": "chat",
+            "description": "Send a prompt to a model through gaud and return its reply.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "model": model,
+                    "prompt": { "type": "string", "description": "The user message" },
+                    "system": { "type": "string", "description": "Optional system prompt" },
+                    "max_tokens": { "type": "integer", "minimum": 1 },
+                    "temperature": { "type": "number", "minimum": 0, "maximum": 2 },
+                },
+                "required": ["model", "prompt"],
+            },
+        },
+        {
+            "name": "list_models",
+            "description": "List the models available through gaud.",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+    ])
+}
+
+/// Arguments of the `chat` tool.
+#[derive(Debug, Deserialize)]
+struct ChatArgs {
+    model: String,
+    prompt: String,
+    #[serde(default)]
+    system: Option<String>,
+    #[serde(default)]
+    max_tokens: Option<u32>,
+    #[serde(default)]
+    temperature: Option<f32>,
+}
+
+async fn call_tool(state: &AppState, user: &AuthUser, params: &Value) -> Result<Value, RpcError> {
+    let name = params["name"]
+        .as_str()
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing tool name"))?;
+    let arguments = match &params["arguments"] {
+        Value::Null => json!({}),
+        args => args.clone(),
+    };
+
+    match name {
+        "list_models" => Ok(tool_result(
+            visible_models(state, user).await.join("\n"),
+            false,
+        )),
+        "chat" => {
+            let args: ChatArgs = serde_json::from_value(arguments)
+                .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid arguments: {e}")))?;
+            // Failures are tool results, so the calling model can see them.
+            Ok(match chat(state, user, args).await {
+                Ok(response) => {
+                    let text = response
+                        .choices
+                        .into_iter()
+                        .next()
+                        .and_then(|c| c.message.content)
+                        .unwrap_or_default();
+                    let mut result = tool_result(text, false);
+                    result["_meta"] = json!({ "model": response.model, "usage": response.usage });
+                    result
+                }
+                Err(e) => tool_result(e.to_string(), true),
+            })
+        }
+        other => Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("Unknown tool: {other}"),
+        )),
+    }
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+/// Run a `chat` call. `/mcp` is outside `/v1/`, so the rate quota and
+/// budget are checked here rather than by middleware.
+async fn chat(state: &AppState, user: &AuthUser, args: ChatArgs) -> Result<ChatResponse, AppError> {
+    if let QuotaDecision::Limited { message, .. } = state
+        .budget
+        .check_rate_limits(&user.user_id, user.team.as_deref())?
+    {
+        return Err(AppError::RateLimited(message));
+    }
+    if state.config.budget.enabled
+        && state.budget.check_budget(
+            &user.user_id,
+            state.settings.config().budget.warning_threshold_percent,
+        )? == BudgetStatus::Exceeded
+    {
+        return Err(AppError::BudgetExceeded(format!(
+            "Budget exceeded for user '{}'",
+            user.name
+        )));
+    }
+
+    let mut messages = Vec::new();
+    if let Some(system) = args.system.filter(|s| !s.is_empty()) {
+        messages.push(ChatMessage::text(MessageRole::System, system));
+    }
+    messages.push(ChatMessage::text(MessageRole::User, args.prompt));
+    let request = ChatRequest {
+        model: args.model,
+        messages,
+        temperature: args.temperature,
+        max_tokens: args.max_tokens,
+        stream: false,
+        top_p: None,
+        stop: None,
+        tools: None,
+        tool_choice: None,
+        stream_options: None,
+        google_search: false,
+        extra_body: None,
+        user: None,
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %request.model,
+        "MCP chat request"
+    );
+    let (response, _) = complete_chat(
+        state.clone(),
+        user.clone(),
+        request,
+        request_id,
+        Attribution::default(),
+    )
+    .await?;
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_support::{test_state, user};
+    use crate::auth::tokens::TokenScope;
+    use crate::providers::router::ProviderRouter;
+
+    async fn call(state: &AppState, message: Value) -> Option<Value> {
+        handle_message(state, &user(), &message.to_string()).await
+    }
+
+    #[tokio::test]
+    async fn test_initialize_negotiates_version() {
+        let state = test_state(Default::default(), ProviderRouter::new());
+        let reply = call(
+            &state,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+                   "params": {"protocolVersion": "2024-11-05", "capabilities": {}}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(reply["result"]["serverInfo"]["name"], "gaud");
+        assert!(reply["result"]["capabilities"]["tools"].is_object());
+
+        let reply = call(
+            &state,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "initialize",
+                   "params": {"protocolVersion": "1999-01-01"}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors_and_notifications() {
+        let state = test_state(Default::default(), ProviderRouter::new());
+        let reply = handle_message(&state, &user(), "{not json").await.unwrap();
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+        assert_eq!(reply["id"], Value::Null);
+
+        let reply = call(
+            &state,
+            json!({"jsonrpc": "2.0", "id": "a", "method": "nope"}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["id"], "a");
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+
+        let reply = call(&state, json!([1, 2])).await.unwrap();
+        assert_eq!(reply["error"]["code"], INVALID_REQUEST);
+
+        let ping = call(&state, json!({"jsonrpc": "2.0", "id": 3, "method": "ping"})).await;
+        assert_eq!(ping.unwrap()["result"], json!({}));
+
+        // Notifications and client responses are never answered.
+        let note = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(call(&state, note).await.is_none());
+        assert!(
+            call(&state, json!({"jsonrpc": "2.0", "method": "nope"}))
+                .await
+                .is_none()
+        );
+        assert!(
+            call(&state, json!({"jsonrpc": "2.0", "id": 9, "result": {}}))
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_and_call() {
+        let state = test_state(Default::default(), ProviderRouter::new());
+        let reply = call(
+            &state,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+        )
+        .await
+        .unwrap();
+        let names: Vec<&str> = reply["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["chat", "list_models"]);
+
+        let reply = call(
+            &state,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                   "params": {"name": "chat", "arguments": {"model": "m"}}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+
+        let reply = call(
+            &state,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                   "params": {"name": "teleport", "arguments": {}}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+
+        // A failed completion is a tool error the calling model can read.
+        let reply = call(
+            &state,
+            json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                   "params": {"name": "chat", "arguments": {"model": "nowhere", "prompt": "hi"}}}),
+        )
+        .await
+        .unwrap();
+        assert_eq!(reply["result"]["isError"], true);
+        assert!(reply["result"]["content"][0]["text"].as_str().unwrap() != "");
+    }
+
+    #[test]
+    fn test_tool_schema_lists_models() {
+        let tools = tool_definitions(&["a".to_string(), "b".to_string()]);
+        assert_eq!(
+            tools[0]["inputSchema"]["properties"]["model"]["enum"],
+            json!(["a", "b"])
+        );
+        let tools = tool_definitions(&[]);
+        assert!(tools[0]["inputSchema"]["properties"]["model"]["enum"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_visible_models_respect_token_scope() {
+        let state = test_state(Default::default(), ProviderRouter::new());
+        let mut virtual_models = std::collections::BTreeMap::new();
+        virtual_models.insert("fast".to_string(), Default::default());
+        state
+            .router
+            .write()
+            .await
+            .set_virtual_models(virtual_models);
+
+        let mut scoped = user();
+        assert_eq!(visible_models(&state, &scoped).await, ["gaud:fast"]);
+        scoped.scope = Some(TokenScope {
+            id: "t1".to_string(),
+            models: vec!["claude-*".to_string()],
+            max_tokens: None,
+            exp: i64::MAX,
+        });
+        assert!(visible_models(&state, &scoped).await.is_empty());
+    }
+
+    #[test]
+    fn test_sessions_are_per_user_and_closed_on_drop() {
+        let sessions = Arc::new(McpSessions::new());
+        let (id, _rx) = sessions.open("u1");
+        assert!(sessions.sender(&id, "u1").is_some());
+        assert!(sessions.sender(&id, "u2").is_none());
+        assert!(sessions.sender("other", "u1").is_none());
+
+        drop(SessionGuard {
+            sessions: sessions.clone(),
+            id: id.clone(),
+        });
+        assert!(sessions.is_empty());
+        assert!(sessions.sender(&id, "u1").is_none());
+    }
+}
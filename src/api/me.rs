@@ -0,0 +1,297 @@
+//! Self-service endpoints for the authenticated user.
+//!
+//! Any user, admin or member, can see their own profile, budget and usage
+//! and manage their own API keys here, up to `auth.self_service_max_keys`
+//! keys. Nothing under `/me` touches another user's data.
+
+use axum::Extension;
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::api::admin::{
+    self, CreateApiKeyRequest, CreatedApiKeyResponse, DeletedResponse, UsageQuery, UsageResponse,
+    UsageSummaryQuery, UsageSummaryResponse,
+};
+use crate::auth::AuthUser;
+use crate::auth::throttle::ClientIp;
+use crate::auth::tokens::{self, MintTokenRequest, MintedToken};
+use crate::auth::totp::{self, TotpEnrollment, TotpStatus};
+use crate::auth::users::{self, ApiKeyInfo, User};
+use crate::budget::tracker::Budget;
+use crate::error::AppError;
+
+// ---------------------------------------------------------------------------
+// Request / response types
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct ProfileResponse {
+    pub user: User,
+    /// Spending limits, or `null` when none are set.
+    pub budget: Option<Budget>,
+    /// Remaining monthly spend, or `null` without a monthly limit.
+    pub monthly_remaining: Option<f64>,
+    /// Remaining daily spend, or `null` without a daily limit.
+    pub daily_remaining: Option<f64>,
+    /// Currency of the budget figures (`pricing.currency`).
+    pub currency: String,
+    /// Most API keys the user may hold when creating their own.
+    pub max_keys: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MyUsageQuery {
+    pub provider: Option<String>,
+    pub end_user: Option<String>,
+    pub tags: Option<String>,
+    /// Only entries made with one of the user's API keys.
+    pub key_id: Option<String>,
+    pub model: Option<String>,
+    pub status: Option<String>,
+    pub search: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    #[serde(default)]
+    pub page: Option<u32>,
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmTotpRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    /// Single-use recovery codes. Shown exactly once.
+    pub recovery_codes: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+/// GET /me
+pub async fn profile(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<ProfileResponse>, AppError> {
+    let record = users::get_user(&state.db, &user.user_id)?;
+    let budget = state.budget.get_budget(&user.user_id)?;
+    let remaining = |limit: Option<f64>, used: f64| limit.map(|l| (l - used).max(0.0));
+    Ok(Json(ProfileResponse {
+        user: record,
+        monthly_remaining: budget
+            .as_ref()
+            .and_then(|b| remaining(b.monthly_limit, b.monthly_used)),
+        daily_remaining: budget
+            .as_ref()
+            .and_then(|b| remaining(b.daily_limit, b.daily_used)),
+        budget,
+        currency: state.cost_calculator.currency().to_string(),
+        max_keys: state.config.auth.self_service_max_keys,
+    }))
+}
+
+/// GET /me/usage
+pub async fn usage(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(params): Query<MyUsageQuery>,
+) -> Result<Json<UsageResponse>, AppError> {
+    let query = UsageQuery {
+        user_id: Some(user.user_id),
+        provider: params.provider,
+        end_user: params.end_user,
+        tags: params.tags,
+        key_id: params.key_id,
+        model: params.model,
+        status: params.status,
+        search: params.search,
+        from: params.from,
+        to: params.to,
+        sort: params.sort,
+        order: params.order,
+        page: params.page.unwrap_or(1),
+        per_page: params.per_page.unwrap_or(50),
+        ..Default::default()
+    };
+    let usage = admin::usage_page(&state.db, &query, state.cost_calculator.currency())?;
+    Ok(Json(usage))
+}
+
+/// GET /me/usage/summary
+///
+/// The caller's usage totals, grouped like `GET /admin/usage/summary`.
+pub async fn usage_summary(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(mut params): Query<UsageSummaryQuery>,
+) -> Result<Json<UsageSummaryResponse>, AppError> {
+    params.user_id = Some(user.user_id);
+    let summary = admin::usage_summary_of(&state.db, &params, state.cost_calculator.currency())?;
+    Ok(Json(summary))
+}
+
+/// GET /me/keys
+pub async fn list_keys(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<ApiKeyInfo>>, AppError> {
+    let keys = users::list_api_keys(&state.db, &user.user_id)?;
+    Ok(Json(keys))
+}
+
+/// POST /me/keys
+///
+/// Users with two-factor enabled must send a current code in `X-TOTP-Code`.
+pub async fn create_key(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Json(body): Json<CreateApiKeyRequest>,
+) -> Result<Json<CreatedApiKeyResponse>, AppError> {
+    state.login_throttle.require_second_factor(
+        ip,
+        &user,
+        totp::header_code(&headers),
+        "key_create_totp",
+    )?;
+    let existing = users::list_api_keys(&state.db, &user.user_id)?.len();
+    check_key_limit(existing, state.config.auth.self_service_max_keys)?;
+
+    let created = users::create_api_key(&state.db, &user.user_id, &body.label)?;
+    let reasoning_mode = body.reasoning_mode.unwrap_or_default();
+    if reasoning_mode != created.info.reasoning_mode {
+        users::set_api_key_reasoning_mode(&state.db, &created.info.id, reasoning_mode)?;
+    }
+
+    Ok(Json(CreatedApiKeyResponse {
+        id: created.info.id,
+        user_id: created.info.user_id,
+        key_prefix: created.info.key_prefix,
+        label: created.info.label,
+        created_at: created.info.created_at,
+        reasoning_mode,
+        plaintext: created.plaintext,
+    }))
+}
+
+/// DELETE /me/keys/:id
+pub async fn revoke_key(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<DeletedResponse>, AppError> {
+    users::revoke_user_api_key(&state.db, &user.user_id, &id)?;
+    Ok(Json(DeletedResponse { deleted: true }))
+}
+
+/// POST /me/tokens
+///
+/// Mint a short-lived token restricted to some models and a token budget,
+/// for handing to browser clients. Revoking the key it was minted with
+/// revokes the token.
+pub async fn mint_token(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<MintTokenRequest>,
+) -> Result<Json<MintedToken>, AppError> {
+    let key_id = user.key_id.as_deref().ok_or_else(|| {
+        AppError::BadRequest("Scoped tokens can only be minted with an API key".to_string())
+    })?;
+    let minted = tokens::mint(&state.db, &user.user_id, key_id, &body)?;
+    Ok(Json(minted))
+}
+
+/// GET /me/totp
+pub async fn totp_status(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<TotpStatus>, AppError> {
+    Ok(Json(totp::status(&state.db, &user.user_id)?))
+}
+
+/// POST /me/totp/enroll
+///
+/// Two-factor authentication is offered to admins only.
+pub async fn totp_enroll(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<TotpEnrollment>, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden(
+            "Two-factor authentication is available to admins only".to_string(),
+        ));
+    }
+    let enrollment = totp::begin_enrollment(&state.db, &user.user_id, &user.name)?;
+    Ok(Json(enrollment))
+}
+
+/// POST /me/totp/confirm
+pub async fn totp_confirm(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<ConfirmTotpRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let recovery_codes = totp::confirm_enrollment(&state.db, &user.user_id, &body.code)?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// DELETE /me/totp
+///
+/// Requires a current code (or recovery code) in `X-TOTP-Code`.
+pub async fn totp_disable(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+) -> Result<Json<DeletedResponse>, AppError> {
+    state.login_throttle.require_second_factor(
+        ip,
+        &user,
+        totp::header_code(&headers),
+        "totp_disable",
+    )?;
+    totp::disable(&state.db, &user.user_id)?;
+    Ok(Json(DeletedResponse { deleted: true }))
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Refuse a new self-service key once the user holds `max_keys` keys.
+fn check_key_limit(existing: usize, max_keys: u32) -> Result<(), AppError> {
+    if max_keys == 0 {
+        return Err(AppError::Forbidden(
+            "Self-service API key creation is disabled".to_string(),
+        ));
+    }
+    if existing >= max_keys as usize {
+        return Err(AppError::Forbidden(format!(
+            "API key limit reached ({max_keys}); revoke a key first"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_key_limit() {
+        assert!(check_key_limit(0, 5).is_ok());
+        assert!(check_key_limit(4, 5).is_ok());
+        assert!(matches!(check_key_limit(5, 5), Err(AppError::Forbidden(_))));
+        assert!(matches!(check_key_limit(0, 0), Err(AppError::Forbidden(_))));
+    }
+}
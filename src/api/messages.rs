@@ -0,0 +1,884 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::Extension;
+use axum::Json;
+use axum::extract::State;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio_stream::Stream;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::chat::{self, ChatEventStream, SseMsg};
+use crate::auth::AuthUser;
+use crate::budget::Attribution;
+use crate::error::AppError;
+use crate::providers::ProviderError;
+use crate::providers::router::VIRTUAL_MODEL_PREFIX;
+use crate::providers::transform::util::map_finish_reason_to_anthropic;
+use crate::providers::types::{
+    ChatChunk, ChatMessage, ChatRequest, ChatResponse, ContentPart, DocumentSource, FunctionCall,
+    FunctionDef, ImageUrl, MessageContent, MessageRole, StopSequence, Tool, ToolCall, Usage,
+};
+
+/// Response of `count_tokens`, as the Anthropic API returns it.
+#[derive(Debug, Serialize)]
+pub struct TokenCount {
+    pub input_tokens: u64,
+}
+
+/// POST /v1/messages
+///
+/// Anthropic Messages API, so Anthropic SDK clients can use any model gaud
+/// serves. The request is translated onto the chat pipeline (cache, routing,
+/// budgets and audit log as for `/v1/chat/completions`); the answer comes
+/// back as an Anthropic message, or as Anthropic stream events when
+/// `stream` is set.
+pub async fn create_message(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Result<Response, AppError> {
+    let request_id = Uuid::new_v4().to_string();
+    let request = to_chat_request(&body)?;
+    let attribution = Attribution::from_request(&headers, request.user.as_deref())?;
+    let model = request.model.clone();
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %model,
+        stream = request.stream,
+        "Messages request"
+    );
+
+    if request.stream {
+        let (inner, upstream) =
+            chat::open_chat_stream(state, user, request, request_id, attribution).await?;
+        let mut sse = Sse::new(MessageEventStream::new(inner, model))
+            .keep_alive(KeepAlive::default())
+            .into_response();
+        chat::insert_upstream_rate_limit_headers(sse.headers_mut(), upstream.as_ref());
+        return Ok(sse);
+    }
+
+    let (response, upstream) =
+        chat::complete_chat(state, user, request, request_id, attribution).await?;
+    let mut http_response = Json(message_from_chat(&response, &model)).into_response();
+    chat::insert_upstream_rate_limit_headers(http_response.headers_mut(), upstream.as_ref());
+    Ok(http_response)
+}
+
+/// POST /v1/messages/count_tokens
+///
+/// Anthropic API token counting, so Anthropic SDK clients pointed at gaud
+/// can size their requests. Takes an Anthropic Messages request body and
+/// returns the input tokens estimated by the provider that would serve the
+/// model; nothing is sent upstream.
+pub async fn count_tokens(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(body): Json<Value>,
+) -> Result<Json<TokenCount>, AppError> {
+    let mut request = to_chat_request(&body)?;
+    if let Some(scope) = &user.scope
+        && !scope.allows_model(&request.model)
+    {
+        return Err(AppError::Forbidden(format!(
+            "This token may not use model '{}'",
+            request.model
+        )));
+    }
+    if request.model.starts_with(VIRTUAL_MODEL_PREFIX) {
+        request.model = state.router.write().await.resolve_model(&request.model)?;
+    }
+
+    let input_tokens = state
+        .router
+        .read()
+        .await
+        .count_tokens(&request)
+        .ok_or_else(|| ProviderError::NoProvider(request.model.clone()))?;
+    tracing::debug!(
+        user_id = %user.user_id,
+        model = %request.model,
+        input_tokens,
+        "Counted tokens"
+    );
+    Ok(Json(TokenCount { input_tokens }))
+}
+
+/// Convert an Anthropic Messages request body to a chat request: the system
+/// prompt, messages, tools and sampling parameters. Thinking blocks from
+/// earlier turns and unknown parameters are dropped.
+fn to_chat_request(body: &Value) -> Result<ChatRequest, AppError> {
+    let model = body
+        .get("model")
+        .and_then(Value::as_str)
+        .filter(|m| !m.is_empty())
+        .ok_or_else(|| invalid("model", "Expected a model name"))?;
+    let input = body
+        .get("messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("messages", "Expected an array of messages"))?;
+
+    let mut messages = Vec::new();
+    if let Some(system) = body.get("system") {
+        messages.push(message(MessageRole::System, text_of(system)));
+    }
+    for m in input {
+        let role = match m.get("role").and_then(Value::as_str) {
+            Some("user") => MessageRole::User,
+            Some("assistant") => MessageRole::Assistant,
+            _ => return Err(invalid("messages", "Expected role 'user' or 'assistant'")),
+        };
+        let blocks = match m.get("content") {
+            Some(Value::String(text)) => {
+                messages.push(message(role, text.clone()));
+                continue;
+            }
+            Some(Value::Array(blocks)) => blocks,
+            _ => return Err(invalid("messages", "Expected string or array content")),
+        };
+
+        let mut parts = Vec::new();
+        let mut tool_calls = Vec::new();
+        for block in blocks {
+            match block.get("type").and_then(Value::as_str) {
+                Some("text") => parts.push(ContentPart::Text {
+                    text: text_of(block),
+                }),
+                Some("image") => parts.push(ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: source_data(block),
+                        detail: None,
+                    },
+                }),
+                Some("document") => parts.push(ContentPart::Document {
+                    document: DocumentSource {
+                        data: source_data(block),
+                        media_type: "application/pdf".to_string(),
+                        filename: None,
+                    },
+                }),
+                Some("tool_use") => tool_calls.push(ToolCall {
+                    index: None,
+                    id: string_at(block, "id"),
+                    r#type: "function".to_string(),
+                    function: FunctionCall {
+                        name: string_at(block, "name"),
+                        arguments: block
+                            .get("input")
+                            .map_or_else(String::new, Value::to_string),
+                    },
+                }),
+                Some("tool_result") => messages.push(ChatMessage {
+                    tool_call_id: Some(string_at(block, "tool_use_id")),
+                    ..message(
+                        MessageRole::Tool,
+                        block.get("content").map(text_of).unwrap_or_default(),
+                    )
+                }),
+                // Thinking from earlier turns is not sent to the model again.
+                _ => {}
+            }
+        }
+        if parts.is_empty() && tool_calls.is_empty() {
+            continue;
+        }
+        messages.push(ChatMessage {
+            content: Some(MessageContent::Parts(parts)),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            ..message(role, String::new())
+        });
+    }
+
+    let tools: Vec<Tool> = body
+        .get("tools")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|t| Tool {
+            r#type: "function".to_string(),
+            function: FunctionDef {
+                name: string_at(t, "name"),
+                description: t
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                parameters: t.get("input_schema").cloned(),
+            },
+        })
+        .collect();
+
+    let stop_sequences: Vec<String> = body
+        .get("stop_sequences")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+
+    let mut request: ChatRequest = serde_json::from_value(json!({"model": model, "messages": []}))
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+    request.messages = messages;
+    request.tools = (!tools.is_empty()).then_some(tools);
+    request.tool_choice = body.get("tool_choice").map(convert_tool_choice);
+    request.max_tokens = number_at(body, "max_tokens")?.map(|n| n as u32);
+    request.temperature = number_at(body, "temperature")?.map(|n| n as f32);
+    request.top_p = number_at(body, "top_p")?.map(|n| n as f32);
+    request.stop = (!stop_sequences.is_empty()).then_some(StopSequence::Multiple(stop_sequences));
+    request.stream = body.get("stream").and_then(Value::as_bool).unwrap_or(false);
+    request.user = body
+        .pointer("/metadata/user_id")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    Ok(request)
+}
+
+/// Anthropic `tool_choice` (`auto`, `any`, `tool` or `none`) as a chat
+/// `tool_choice`.
+fn convert_tool_choice(choice: &Value) -> Value {
+    match choice.get("type").and_then(Value::as_str) {
+        Some("any") => json!("required"),
+        Some("none") => json!("none"),
+        Some("tool") => json!({
+            "type": "function",
+            "function": {"name": string_at(choice, "name")},
+        }),
+        _ => json!("auto"),
+    }
+}
+
+/// A non-negative number at `key`, if set.
+fn number_at(body: &Value, key: &str) -> Result<Option<f64>, AppError> {
+    match body.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_f64()
+            .filter(|n| *n >= 0.0)
+            .map(Some)
+            .ok_or_else(|| invalid(key, "Expected a non-negative number")),
+    }
+}
+
+/// A chat completion as an Anthropic message, with content blocks in the
+/// order Anthropic uses: thinking, text, then tool use.
+fn message_from_chat(response: &ChatResponse, model: &str) -> Value {
+    let mut content = Vec::new();
+    let mut finish_reason = None;
+    if let Some(choice) = response.choices.first() {
+        let msg = &choice.message;
+        if let Some(thinking) = msg.reasoning_content.as_ref().filter(|r| !r.is_empty()) {
+            content.push(json!({"type": "thinking", "thinking": thinking}));
+        }
+        if let Some(text) = msg.content.as_ref().filter(|c| !c.is_empty()) {
+            content.push(json!({"type": "text", "text": text}));
+        }
+        for call in msg.tool_calls.iter().flatten() {
+            let input = serde_json::from_str::<Value>(&call.function.arguments)
+                .unwrap_or_else(|_| json!({}));
+            content.push(json!({
+                "type": "tool_use",
+                "id": call.id,
+                "name": call.function.name,
+                "input": input,
+            }));
+        }
+        finish_reason = choice.finish_reason.as_deref();
+    }
+    json!({
+        "id": format!("msg_{}", Uuid::new_v4().simple()),
+        "type": "message",
+        "role": "assistant",
+        "model": model,
+        "content": content,
+        "stop_reason": map_finish_reason_to_anthropic(finish_reason.unwrap_or("")),
+        "stop_sequence": null,
+        "usage": {
+            "input_tokens": response.usage.prompt_tokens,
+            "output_tokens": response.usage.completion_tokens,
+        },
+    })
+}
+
+fn message(role: MessageRole, text: String) -> ChatMessage {
+    ChatMessage {
+        role,
+        content: Some(MessageContent::Text(text)),
+        name: None,
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+/// Text of a string, a text block, or an array of text blocks.
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks.iter().map(text_of).collect::<Vec<_>>().join("\n"),
+        Value::Object(_) => string_at(value, "text"),
+        _ => String::new(),
+    }
+}
+
+fn string_at(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// The data of an image or document block's `source`: a data URL for
+/// base64 sources, otherwise the URL.
+fn source_data(block: &Value) -> String {
+    let source = block.get("source").unwrap_or(&Value::Null);
+    match source.get("type").and_then(Value::as_str) {
+        Some("base64") => format!(
+            "data:{};base64,{}",
+            string_at(source, "media_type"),
+            string_at(source, "data")
+        ),
+        _ => string_at(source, "url"),
+    }
+}
+
+fn invalid(param: &str, message: &str) -> AppError {
+    AppError::InvalidParam {
+        param: param.to_string(),
+        message: message.to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Anthropic stream events
+// ---------------------------------------------------------------------------
+
+/// Content block a [`MessageEventStream`] has open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenBlock {
+    Thinking,
+    Text,
+    /// Tool call with this chat tool-call index.
+    ToolUse(u32),
+}
+
+/// Translates the chat pipeline's [`SseMsg`] stream into Anthropic Messages
+/// events (`message_start`, `content_block_start`, `content_block_delta`,
+/// ..., `message_stop`).
+///
+/// The stream cache stores chat chunks (`openai_sse_v1`), and streams served
+/// from it arrive here like any other, so cached entries replay in Anthropic
+/// format without a cache of their own. Anthropic streams have one content
+/// block open at a time: a block is closed when output of another kind
+/// starts.
+pub(crate) struct MessageEventStream {
+    inner: ChatEventStream,
+    id: String,
+    model: String,
+    pending: VecDeque<(&'static str, Value)>,
+    started: bool,
+    finished: bool,
+    /// Set once an `error` event has been emitted; the inner stream is still
+    /// drained so the chat pipeline records its audit entry.
+    failed: bool,
+    open: Option<OpenBlock>,
+    /// Index of the next content block.
+    next_index: usize,
+    finish_reason: Option<String>,
+    usage: Usage,
+}
+
+impl MessageEventStream {
+    pub(crate) fn new(inner: ChatEventStream, model: String) -> Self {
+        Self {
+            inner,
+            id: format!("msg_{}", Uuid::new_v4().simple()),
+            model,
+            pending: VecDeque::new(),
+            started: false,
+            finished: false,
+            failed: false,
+            open: None,
+            next_index: 0,
+            finish_reason: None,
+            usage: Usage::default(),
+        }
+    }
+
+    fn push(&mut self, kind: &'static str, mut data: Value) {
+        data["type"] = Value::String(kind.to_string());
+        self.pending.push_back((kind, data));
+    }
+
+    /// Make `block` the open content block, closing any other.
+    fn open_block(&mut self, block: OpenBlock, content_block: impl FnOnce() -> Value) {
+        if self.open == Some(block) {
+            return;
+        }
+        self.close_block();
+        self.open = Some(block);
+        self.push(
+            "content_block_start",
+            json!({"index": self.next_index, "content_block": content_block()}),
+        );
+    }
+
+    fn close_block(&mut self) {
+        if self.open.take().is_some() {
+            self.push("content_block_stop", json!({"index": self.next_index}));
+            self.next_index += 1;
+        }
+    }
+
+    fn push_delta(&mut self, delta: Value) {
+        self.push(
+            "content_block_delta",
+            json!({"index": self.next_index, "delta": delta}),
+        );
+    }
+
+    fn on_chunk(&mut self, chunk: ChatChunk) {
+        if let Some(usage) = chunk.usage {
+            self.usage = usage;
+        }
+        let Some(choice) = chunk.choices.into_iter().find(|c| c.index == 0) else {
+            return;
+        };
+        if choice.finish_reason.is_some() {
+            self.finish_reason = choice.finish_reason;
+        }
+        let delta = choice.delta;
+
+        if let Some(thinking) = delta.reasoning_content.filter(|r| !r.is_empty()) {
+            self.open_block(
+                OpenBlock::Thinking,
+                || json!({"type": "thinking", "thinking": ""}),
+            );
+            self.push_delta(json!({"type": "thinking_delta", "thinking": thinking}));
+        }
+
+        if let Some(text) = delta.content.filter(|c| !c.is_empty()) {
+            self.open_block(OpenBlock::Text, || json!({"type": "text", "text": ""}));
+            self.push_delta(json!({"type": "text_delta", "text": text}));
+        }
+
+        for call in delta.tool_calls.into_iter().flatten() {
+            let block = OpenBlock::ToolUse(call.index.unwrap_or(0));
+            self.open_block(block, || {
+                json!({
+                    "type": "tool_use",
+                    "id": call.id,
+                    "name": call.function.name,
+                    "input": {},
+                })
+            });
+            if !call.function.arguments.is_empty() {
+                self.push_delta(json!({
+                    "type": "input_json_delta",
+                    "partial_json": call.function.arguments,
+                }));
+            }
+        }
+    }
+
+    fn on_done(&mut self) {
+        self.close_block();
+        let stop_reason =
+            map_finish_reason_to_anthropic(self.finish_reason.as_deref().unwrap_or(""));
+        let usage = json!({
+            "input_tokens": self.usage.prompt_tokens,
+            "output_tokens": self.usage.completion_tokens,
+        });
+        self.push(
+            "message_delta",
+            json!({
+                "delta": {"stop_reason": stop_reason, "stop_sequence": null},
+                "usage": usage,
+            }),
+        );
+        self.push("message_stop", json!({}));
+    }
+
+    fn on_error(&mut self, payload: &str) {
+        let error = serde_json::from_str::<Value>(payload).unwrap_or_default();
+        let message = error["error"]["message"].as_str().unwrap_or(payload);
+        self.push(
+            "error",
+            json!({"error": {"type": "api_error", "message": message}}),
+        );
+        self.failed = true;
+    }
+}
+
+impl MessageEventStream {
+    /// Next event name and data, translated from the inner stream.
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<(&'static str, Value)>> {
+        if !self.started {
+            self.started = true;
+            // Usage is only known once the stream ends; see `message_delta`.
+            let message = json!({
+                "id": self.id,
+                "type": "message",
+                "role": "assistant",
+                "model": self.model,
+                "content": [],
+                "stop_reason": null,
+                "stop_sequence": null,
+                "usage": {"input_tokens": 0, "output_tokens": 0},
+            });
+            self.push("message_start", json!({"message": message}));
+        }
+
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+            if self.finished {
+                return Poll::Ready(None);
+            }
+            match self.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(SseMsg::Data(_))) if self.failed => {}
+                Poll::Ready(Some(SseMsg::Data(payload))) => {
+                    match serde_json::from_str::<ChatChunk>(&payload) {
+                        Ok(chunk) => self.on_chunk(chunk),
+                        Err(_) => self.on_error(&payload),
+                    }
+                }
+                Poll::Ready(Some(SseMsg::Done)) | Poll::Ready(None) => {
+                    self.finished = true;
+                    if !self.failed {
+                        self.on_done();
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Stream for MessageEventStream {
+    type Item = Result<Event, std::convert::Infallible>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_event(cx).map(|event| {
+            event.map(|(kind, data)| Ok(Event::default().event(kind).data(data.to_string())))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_chat_request() {
+        let body = json!({
+            "model": "claude-sonnet-4",
+            "system": [{"type": "text", "text": "Be brief."}],
+            "tools": [{"name": "lookup", "input_schema": {"type": "object"}}],
+            "messages": [
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": [
+                    {"type": "text", "text": "Looking it up."},
+                    {"type": "tool_use", "id": "t1", "name": "lookup", "input": {"q": "x"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "found"},
+                    {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}}
+                ]}
+            ]
+        });
+        let request = to_chat_request(&body).unwrap();
+        let roles: Vec<_> = request.messages.iter().map(|m| m.role.clone()).collect();
+        assert_eq!(
+            roles,
+            [
+                MessageRole::System,
+                MessageRole::User,
+                MessageRole::Assistant,
+                MessageRole::Tool,
+                MessageRole::User,
+            ]
+        );
+        let call = &request.messages[2].tool_calls.as_ref().unwrap()[0];
+        assert_eq!(call.function.arguments, r#"{"q":"x"}"#);
+        assert_eq!(request.messages[3].tool_call_id.as_deref(), Some("t1"));
+        assert_eq!(request.tools.as_ref().unwrap()[0].function.name, "lookup");
+        assert!(!request.stream);
+        assert_eq!(request.max_tokens, None);
+    }
+
+    #[test]
+    fn test_to_chat_request_parameters() {
+        let body = json!({
+            "model": "m",
+            "max_tokens": 256,
+            "temperature": 0.5,
+            "stop_sequences": ["END"],
+            "stream": true,
+            "tool_choice": {"type": "tool", "name": "lookup"},
+            "metadata": {"user_id": "end-user-7"},
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+        let request = to_chat_request(&body).unwrap();
+        assert_eq!(request.max_tokens, Some(256));
+        assert_eq!(request.temperature, Some(0.5));
+        assert!(matches!(request.stop, Some(StopSequence::Multiple(ref s)) if s == &["END"]));
+        assert!(request.stream);
+        assert_eq!(
+            request.tool_choice,
+            Some(json!({"type": "function", "function": {"name": "lookup"}}))
+        );
+        assert_eq!(request.user.as_deref(), Some("end-user-7"));
+
+        let body = json!({"model": "m", "max_tokens": -1, "messages": []});
+        assert!(matches!(
+            to_chat_request(&body).unwrap_err(),
+            AppError::InvalidParam { param, .. } if param == "max_tokens"
+        ));
+    }
+
+    #[test]
+    fn test_message_from_chat() {
+        let response: ChatResponse = serde_json::from_value(json!({
+            "id": "c1", "object": "chat.completion", "created": 0, "model": "m",
+            "choices": [{"index": 0, "finish_reason": "tool_calls", "message": {
+                "role": "assistant",
+                "content": "Looking it up.",
+                "tool_calls": [{"id": "call_1", "type": "function",
+                    "function": {"name": "lookup", "arguments": "{\"q\":1}"}}]
+            }}],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 5, "total_tokens": 17}
+        }))
+        .unwrap();
+        let message = message_from_chat(&response, "claude-sonnet-4");
+        assert_eq!(message["model"], "claude-sonnet-4");
+        assert_eq!(
+            message["content"],
+            json!([
+                {"type": "text", "text": "Looking it up."},
+                {"type": "tool_use", "id": "call_1", "name": "lookup", "input": {"q": 1}}
+            ])
+        );
+        assert_eq!(message["stop_reason"], "tool_use");
+        assert_eq!(
+            message["usage"],
+            json!({"input_tokens": 12, "output_tokens": 5})
+        );
+    }
+
+    #[cfg(feature = "cache-ephemeral")]
+    #[tokio::test]
+    async fn test_create_message_replays_cached_stream() {
+        use crate::api::test_support::{litellm_router, test_state, user};
+        use crate::cache::SemanticCacheService;
+        use crate::cache::store::CacheStore;
+        use crate::config::{CacheConfig, Config};
+
+        let config = Config {
+            cache: CacheConfig {
+                enabled: true,
+                stream_cache_enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        // Nothing listens on the provider's port: the answer has to come
+        // from the cache.
+        let mut state = test_state(config.clone(), litellm_router("http://127.0.0.1:9").await);
+        let body = json!({
+            "model": "m",
+            "max_tokens": 64,
+            "stream": true,
+            "messages": [{"role": "user", "content": "Hi"}]
+        });
+        let cache = SemanticCacheService::new_with_store(
+            CacheStore::ephemeral(3).await.unwrap(),
+            config.cache,
+        );
+        let events = [
+            chunk(
+                json!({"role": "assistant", "content": "Hello!"}),
+                None,
+                None,
+            ),
+            chunk(
+                json!({}),
+                Some("stop"),
+                Some(json!({"prompt_tokens": 8, "completion_tokens": 2, "total_tokens": 10})),
+            ),
+        ];
+        cache
+            .store_stream(&to_chat_request(&body).unwrap(), "u1", &events)
+            .await
+            .unwrap();
+        state.cache = Some(std::sync::Arc::new(cache));
+
+        let response = create_message(
+            State(state),
+            Extension(user()),
+            HeaderMap::new(),
+            Json(body),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        let kinds: Vec<_> = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("event: "))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert!(text.contains(r#""text":"Hello!""#));
+        assert!(text.contains(r#""stop_reason":"end_turn""#));
+        assert!(text.contains(r#""output_tokens":2"#));
+    }
+
+    fn chunk(delta: Value, finish_reason: Option<&str>, usage: Option<Value>) -> String {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "m",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+            "usage": usage,
+        })
+        .to_string()
+    }
+
+    /// Event names and data of `msgs` translated to Anthropic events.
+    async fn translate(msgs: Vec<SseMsg>) -> Vec<(&'static str, Value)> {
+        let inner: ChatEventStream = Box::pin(tokio_stream::iter(msgs));
+        let mut stream = MessageEventStream::new(inner, "claude-sonnet-4".into());
+        let mut events = Vec::new();
+        while let Some(event) = std::future::poll_fn(|cx| stream.poll_event(cx)).await {
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn test_message_events_from_cached_stream() {
+        // A cached `openai_sse_v1` entry as the stream cache replays it.
+        let msgs = vec![
+            SseMsg::Data(chunk(json!({"role": "assistant"}), None, None)),
+            SseMsg::Data(chunk(json!({"reasoning_content": "Hmm."}), None, None)),
+            SseMsg::Data(chunk(json!({"content": "Let me "}), None, None)),
+            SseMsg::Data(chunk(json!({"content": "check."}), None, None)),
+            SseMsg::Data(chunk(
+                json!({"tool_calls": [{"index": 0, "id": "call_1", "type": "function",
+                    "function": {"name": "lookup", "arguments": ""}}]}),
+                None,
+                None,
+            )),
+            SseMsg::Data(chunk(
+                json!({"tool_calls": [{"index": 0, "id": "", "type": "function",
+                    "function": {"name": "", "arguments": "{\"q\":1}"}}]}),
+                Some("tool_calls"),
+                Some(json!({"prompt_tokens": 12, "completion_tokens": 5, "total_tokens": 17})),
+            )),
+            SseMsg::Done,
+        ];
+        let (kinds, events): (Vec<_>, Vec<_>) = translate(msgs).await.into_iter().unzip();
+
+        assert_eq!(
+            kinds,
+            [
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert_eq!(events[0]["message"]["model"], "claude-sonnet-4");
+        assert!(
+            events[0]["message"]["id"]
+                .as_str()
+                .unwrap()
+                .starts_with("msg_")
+        );
+        assert_eq!(events[1]["content_block"]["type"], "thinking");
+        assert_eq!(events[2]["delta"]["thinking"], "Hmm.");
+        assert_eq!(events[4]["index"], 1);
+        assert_eq!(
+            events[6]["delta"],
+            json!({"type": "text_delta", "text": "check."})
+        );
+        assert_eq!(
+            events[8]["content_block"],
+            json!({"type": "tool_use", "id": "call_1", "name": "lookup", "input": {}})
+        );
+        assert_eq!(events[9]["delta"]["partial_json"], r#"{"q":1}"#);
+        assert_eq!(events[10]["index"], 2);
+        assert_eq!(events[11]["delta"]["stop_reason"], "tool_use");
+        assert_eq!(
+            events[11]["usage"],
+            json!({"input_tokens": 12, "output_tokens": 5})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_message_events_error() {
+        let msgs = vec![
+            SseMsg::Data(chunk(json!({"content": "Hi"}), None, None)),
+            SseMsg::Data(r#"{"error":{"message":"boom","type":"api_error"}}"#.into()),
+            SseMsg::Done,
+        ];
+        let events = translate(msgs).await;
+        let kinds: Vec<_> = events.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "error"
+            ]
+        );
+        assert_eq!(
+            events[3].1,
+            json!({"type": "error", "error": {"type": "api_error", "message": "boom"}})
+        );
+    }
+
+    #[test]
+    fn test_to_chat_request_rejects_bad_role() {
+        let body = json!({"model": "m", "messages": [{"role": "system", "content": "x"}]});
+        assert!(matches!(
+            to_chat_request(&body).unwrap_err(),
+            AppError::InvalidParam { .. }
+        ));
+        assert!(to_chat_request(&json!({"messages": []})).is_err());
+    }
+}
@@ -1,60 +1,270 @@
 pub mod admin;
+pub mod agents;
+pub mod audio;
 pub mod chat;
+pub mod conversations;
 pub mod embeddings;
+pub mod files;
+pub mod gemini;
 pub mod health;
+pub mod images;
+pub mod mcp;
+pub mod me;
+pub mod messages;
 pub mod models;
+pub mod responses;
+pub mod resume;
+#[cfg(test)]
+mod test_support;
+pub mod uploads;
+pub mod ws;
 
 use axum::Router;
+use axum::extract::DefaultBodyLimit;
+use axum::http::{Method, Uri};
 use axum::routing::{delete, get, post, put};
 
 use crate::AppState;
+use crate::error::AppError;
+
+/// Fallback for requests matching no route.
+pub async fn route_not_found(method: Method, uri: Uri) -> AppError {
+    AppError::NotFound(format!("No route for {method} {}", uri.path()))
+}
 
 /// Build the full API router with all endpoint groups.
 ///
 /// Route layout:
 /// ```text
-/// /health                        GET    (no auth)
+/// /health                        GET    (no auth; admin for ?detail=true)
 /// /v1/chat/completions           POST   (auth required)
+/// /v1/chat/completions/ws        GET    (auth required, WebSocket)
+/// /v1/responses                  POST   (auth required)
+/// /v1/responses/:id              GET    (auth required)
 /// /v1/models                     GET    (auth required)
+/// /v1/agents/run                 POST   (auth required)
+/// /v1/conversations              POST   (auth required; conversations.enabled)
+/// /v1/conversations              GET    (auth required; conversations.enabled)
+/// /v1/conversations/:id          GET    (auth required; conversations.enabled)
+/// /v1/conversations/:id          DELETE (auth required; conversations.enabled)
+/// /v1/conversations/:id/completions POST (auth required; conversations.enabled)
 /// /v1/embeddings                 POST   (auth required)
+/// /v1/images/generations         POST   (auth required)
+/// /v1/audio/transcriptions        POST   (auth required)
+/// /v1/audio/speech                POST   (auth required)
+/// /v1/files                       POST   (auth required)
+/// /v1/files                       GET    (auth required)
+/// /v1/files/:id                   GET    (auth required)
+/// /v1/files/:id                   DELETE (auth required)
+/// /v1/files/:id/content           GET    (auth required)
+/// /v1/uploads/images              POST   (auth required)
+/// /v1/messages                    POST   (auth required)
+/// /v1/messages/count_tokens       POST   (auth required)
+/// /v1beta/models/:model:generateContent        POST (auth required)
+/// /v1beta/models/:model:streamGenerateContent  POST (auth required)
+/// /mcp                           POST   (auth required; mcp.enabled)
+/// /mcp/sse                       GET    (auth required; mcp.enabled)
+/// /mcp/messages                  POST   (auth required; mcp.enabled)
+/// /me                            GET    (auth required)
+/// /me/usage                      GET    (auth required)
+/// /me/usage/summary              GET    (auth required)
+/// /me/keys                       GET    (auth required)
+/// /me/keys                       POST   (auth required)
+/// /me/keys/:id                   DELETE (auth required)
+/// /me/tokens                     POST   (auth required)
+/// /me/totp                       GET    (auth required)
+/// /me/totp                       DELETE (auth required)
+/// /me/totp/enroll                POST   (admin)
+/// /me/totp/confirm               POST   (auth required)
 /// /admin/users                   POST   (admin)
 /// /admin/users                   GET    (admin)
+/// /admin/users/export            GET    (admin)
+/// /admin/users/import            POST   (admin)
 /// /admin/users/:id               DELETE (admin)
+/// /admin/users/:id/team          PUT    (admin)
+/// /admin/users/:id/totp          DELETE (admin)
+/// /admin/users/:id/purge         POST   (admin)
 /// /admin/users/:id/keys          POST   (admin)
 /// /admin/users/:id/keys          GET    (admin)
+/// /admin/service-accounts        POST   (admin)
+/// /admin/service-accounts        GET    (admin)
+/// /admin/keys/stale              GET    (admin)
+/// /admin/keys/stale/disable      POST   (admin)
 /// /admin/keys/:id                DELETE (admin)
+/// /admin/keys/:id/enable         POST   (admin)
 /// /admin/budgets/:user_id        PUT    (admin)
 /// /admin/budgets/:user_id        GET    (admin)
+/// /admin/quotas/users/:user_id   PUT    (admin)
+/// /admin/quotas/users/:user_id   GET    (admin)
+/// /admin/quotas/teams/:team      PUT    (admin)
+/// /admin/quotas/teams/:team      GET    (admin)
+/// /admin/markups/users/:user_id  PUT    (admin)
+/// /admin/markups/users/:user_id  GET    (admin)
+/// /admin/markups/teams/:team     PUT    (admin)
+/// /admin/markups/teams/:team     GET    (admin)
 /// /admin/usage                   GET    (admin)
+/// /admin/usage/summary           GET    (admin)
+/// /admin/usage/timeseries        GET    (admin)
+/// /admin/reconciliation/litellm  GET    (admin)
+/// /admin/reports/chargeback      GET    (admin)
+/// /admin/experiments             GET    (admin)
+/// /admin/experiments/:name       GET    (admin)
+/// /admin/queue                   GET    (admin)
+/// /admin/metrics                 GET    (admin)
+/// /admin/debug/failures          GET    (admin)
+/// /admin/pricing                 GET    (admin)
+/// /admin/pricing/refresh         POST   (admin)
+/// /admin/pricing/:model          PUT    (admin)
+/// /admin/pricing/:model          DELETE (admin)
+/// /admin/providers/config        GET    (admin)
+/// /admin/providers/config/:name  PUT    (admin)
+/// /admin/routing/rules           GET    (admin)
+/// /admin/routing/rules           PUT    (admin)
+/// /admin/routing/simulate        POST   (admin)
 /// /admin/settings                GET    (admin)
 /// /admin/settings                PUT    (admin)
 /// /admin/cache/stats             GET    (admin)
 /// /admin/cache                   DELETE (admin)
 /// /admin/cache/:model            DELETE (admin)
+/// /admin/audit                   GET    (admin)
 /// ```
 pub fn build_api_router() -> Router<AppState> {
+    build_proxy_router().merge(build_admin_router())
+}
+
+/// `/admin/*` and self-service `/me/*` routes only, for a dedicated admin
+/// listener (`[server.admin]`).
+pub fn build_admin_router() -> Router<AppState> {
     let admin_routes = Router::new()
         .route("/users", post(admin::create_user))
         .route("/users", get(admin::list_users))
+        .route("/users/export", get(admin::export_users))
+        .route("/users/import", post(admin::import_users))
         .route("/users/{id}", delete(admin::delete_user))
+        .route("/service-accounts", post(admin::create_service_account))
+        .route("/service-accounts", get(admin::list_service_accounts))
+        .route("/users/{id}/team", put(admin::set_user_team))
+        .route("/users/{id}/totp", delete(admin::reset_totp))
+        .route("/users/{id}/purge", post(admin::purge_user))
         .route("/users/{id}/keys", post(admin::create_api_key))
         .route("/users/{id}/keys", get(admin::list_api_keys))
+        .route("/keys/stale", get(admin::stale_api_keys))
+        .route("/keys/stale/disable", post(admin::disable_stale_api_keys))
+        .route("/keys/{id}", put(admin::update_api_key))
         .route("/keys/{id}", delete(admin::revoke_api_key))
+        .route("/keys/{id}/enable", post(admin::enable_api_key))
         .route("/budgets/{user_id}", put(admin::set_budget))
         .route("/budgets/{user_id}", get(admin::get_budget))
+        .route("/quotas/users/{user_id}", put(admin::set_user_quota))
+        .route("/quotas/users/{user_id}", get(admin::get_user_quota))
+        .route("/quotas/teams/{team}", put(admin::set_team_quota))
+        .route("/quotas/teams/{team}", get(admin::get_team_quota))
+        .route("/markups/users/{user_id}", put(admin::set_user_markup))
+        .route("/markups/users/{user_id}", get(admin::get_user_markup))
+        .route("/markups/teams/{team}", put(admin::set_team_markup))
+        .route("/markups/teams/{team}", get(admin::get_team_markup))
         .route("/usage", get(admin::query_usage))
+        .route("/usage/summary", get(admin::usage_summary))
+        .route("/usage/timeseries", get(admin::usage_timeseries))
+        .route(
+            "/reconciliation/litellm",
+            get(admin::litellm_reconciliation),
+        )
+        .route("/reports/chargeback", get(admin::chargeback_report))
+        .route("/experiments", get(admin::list_experiments))
+        .route("/experiments/{name}", get(admin::experiment_stats))
+        .route("/queue", get(admin::queue_status))
+        .route("/metrics", get(admin::metrics))
+        .route("/debug/failures", get(admin::recent_failures))
+        .route("/pricing", get(admin::list_pricing))
+        .route("/pricing/refresh", post(admin::refresh_pricing))
+        .route("/pricing/{model}", put(admin::set_pricing))
+        .route("/pricing/{model}", delete(admin::delete_pricing))
+        .route("/providers/config", get(admin::get_provider_config))
+        .route(
+            "/providers/config/{section}",
+            put(admin::update_provider_config),
+        )
+        .route("/routing/rules", get(admin::get_routing_rules))
+        .route("/routing/rules", put(admin::update_routing_rules))
+        .route("/routing/simulate", post(admin::simulate_routing))
         .route("/settings", get(admin::get_settings))
         .route("/settings", put(admin::update_settings))
         .route("/cache/stats", get(admin::cache_stats))
         .route("/cache", delete(admin::flush_cache))
-        .route("/cache/{model}", delete(admin::flush_cache_model));
+        .route("/cache/{model}", delete(admin::flush_cache_model))
+        .route("/audit", get(admin::query_audit));
+
+    let me_routes = Router::new()
+        .route("/", get(me::profile))
+        .route("/usage", get(me::usage))
+        .route("/usage/summary", get(me::usage_summary))
+        .route("/keys", get(me::list_keys))
+        .route("/keys", post(me::create_key))
+        .route("/keys/{id}", delete(me::revoke_key))
+        .route("/tokens", post(me::mint_token))
+        .route("/totp", get(me::totp_status))
+        .route("/totp", delete(me::totp_disable))
+        .route("/totp/enroll", post(me::totp_enroll))
+        .route("/totp/confirm", post(me::totp_confirm));
 
+    Router::new()
+        .nest("/admin", admin_routes)
+        .nest("/me", me_routes)
+}
+
+/// `/health` and `/v1/*` routes, without the admin API.
+pub fn build_proxy_router() -> Router<AppState> {
     Router::new()
         .route("/health", get(health::health_check))
         .route("/v1/chat/completions", post(chat::chat_completions))
+        .route("/v1/chat/completions/ws", get(ws::chat_completions_ws))
+        .route("/v1/responses", post(responses::create_response))
+        .route("/v1/responses/{id}", get(responses::get_response))
         .route("/v1/models", get(models::list_models))
+        .route("/v1/agents/run", post(agents::run_agent))
+        .route(
+            "/v1/conversations",
+            post(conversations::create_conversation),
+        )
+        .route("/v1/conversations", get(conversations::list_conversations))
+        .route(
+            "/v1/conversations/{id}",
+            get(conversations::get_conversation),
+        )
+        .route(
+            "/v1/conversations/{id}",
+            delete(conversations::delete_conversation),
+        )
+        .route(
+            "/v1/conversations/{id}/completions",
+            post(conversations::create_completion),
+        )
         .route("/v1/embeddings", post(embeddings::create_embedding))
-        .nest("/admin", admin_routes)
+        .route("/v1/images/generations", post(images::create_image))
+        .route(
+            "/v1/audio/transcriptions",
+            post(audio::create_transcription)
+                .layer(DefaultBodyLimit::max(audio::MAX_AUDIO_UPLOAD_BYTES)),
+        )
+        .route("/v1/audio/speech", post(audio::create_speech))
+        // Uploads are capped by `files.max_upload_mb` inside the handler.
+        .route(
+            "/v1/files",
+            post(files::upload_file).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/v1/files", get(files::list_files))
+        .route("/v1/files/{id}", get(files::get_file))
+        .route("/v1/files/{id}", delete(files::delete_file))
+        .route("/v1/files/{id}/content", get(files::get_file_content))
+        .route("/v1/uploads/images", post(uploads::upload_image))
+        .route("/v1/messages", post(messages::create_message))
+        .route("/v1/messages/count_tokens", post(messages::count_tokens))
+        // `{model}:generateContent` and `{model}:streamGenerateContent`
+        .route("/v1beta/models/{target}", post(gemini::generate_content))
+        .route("/mcp", post(mcp::post_message))
+        .route("/mcp/sse", get(mcp::open_sse))
+        .route("/mcp/messages", post(mcp::post_session_message))
 }
 
 #[cfg(test)]
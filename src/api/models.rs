@@ -1,29 +1,87 @@
 use axum::Json;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use chrono::Utc;
+use serde::Deserialize;
 
 use crate::AppState;
 use crate::error::AppError;
-use crate::providers::types::{ModelInfo, ModelsResponse};
+use crate::providers::CostCalculator;
+use crate::providers::capabilities::{self, Capability};
+use crate::providers::router::VIRTUAL_MODEL_PREFIX;
+use crate::providers::types::{ModelFeatures, ModelInfo, ModelPrice, ModelsResponse};
+
+/// Filters for `GET /v1/models`.
+#[derive(Debug, Default, Deserialize)]
+pub struct ModelsQuery {
+    /// Comma-separated features every listed model must have.
+    pub capability: Option<String>,
+    /// Smallest acceptable context window, in tokens.
+    pub min_context: Option<u32>,
+}
 
 /// GET /v1/models
 ///
 /// Returns the list of all available models across all configured providers.
-/// Compatible with the OpenAI `GET /v1/models` response format.
-pub async fn list_models(State(state): State<AppState>) -> Result<Json<ModelsResponse>, AppError> {
+/// Compatible with the OpenAI `GET /v1/models` response format, with each
+/// model's providers, limits, features and prices added where known. With
+/// `capability` or `min_context` only models known to qualify are listed.
+pub async fn list_models(
+    State(state): State<AppState>,
+    Query(query): Query<ModelsQuery>,
+) -> Result<Json<ModelsResponse>, AppError> {
+    let wanted = query
+        .capability
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(str::parse::<Capability>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(AppError::BadRequest)?;
+
     let router = state.router.read().await;
-    let available = router.available_models();
+    let mut available = router.available_models();
+    // A model served by several providers (e.g. Kiro profiles) is listed once.
+    available.sort();
 
     let now = Utc::now().timestamp();
-    let models: Vec<ModelInfo> = available
-        .into_iter()
-        .map(|(model_id, provider_id)| ModelInfo {
-            id: model_id,
+    let mut models: Vec<ModelInfo> = Vec::new();
+    for (model_id, provider_id) in available {
+        let qualifies = || {
+            let known = state.cost_calculator.get_capabilities(&model_id);
+            capabilities::matches(known.as_ref(), &wanted, query.min_context)
+        };
+        match models.last_mut() {
+            Some(last) if last.id == model_id => {
+                if !last.providers.contains(&provider_id) {
+                    last.providers.push(provider_id);
+                }
+            }
+            _ if !qualifies() => {}
+            _ => models.push(model_info(
+                model_id,
+                provider_id,
+                now,
+                &state.cost_calculator,
+            )),
+        }
+    }
+
+    // Virtual models have no capabilities of their own, so filters skip them.
+    if wanted.is_empty() && query.min_context.is_none() {
+        models.extend(router.virtual_models().keys().map(|name| ModelInfo {
+            id: format!("{VIRTUAL_MODEL_PREFIX}{name}"),
             object: "model".to_string(),
             created: now,
-            owned_by: provider_id,
-        })
-        .collect();
+            owned_by: "gaud".to_string(),
+            providers: Vec::new(),
+            context_window: None,
+            max_output_tokens: None,
+            capabilities: None,
+            pricing: None,
+        }));
+    }
 
     Ok(Json(ModelsResponse {
         object: "list".to_string(),
@@ -31,27 +89,83 @@ pub async fn list_models(State(state): State<AppState>) -> Result<Json<ModelsRes
     }))
 }
 
+/// The listing of `model`, with metadata from the pricing database.
+fn model_info(
+    model: String,
+    provider: String,
+    created: i64,
+    calculator: &CostCalculator,
+) -> ModelInfo {
+    let capabilities = calculator.get_capabilities(&model);
+    let pricing = calculator.get_pricing(&model).map(|p| ModelPrice {
+        input_per_million: calculator.convert_usd(p.input_cost_per_million),
+        output_per_million: calculator.convert_usd(p.output_cost_per_million),
+        cached_input_per_million: p
+            .cached_input_cost_per_million
+            .map(|c| calculator.convert_usd(c)),
+        currency: calculator.currency().to_string(),
+    });
+    ModelInfo {
+        id: model,
+        object: "model".to_string(),
+        created,
+        owned_by: provider.clone(),
+        providers: vec![provider],
+        context_window: capabilities.as_ref().and_then(|c| c.context_window),
+        max_output_tokens: capabilities.as_ref().and_then(|c| c.max_output_tokens),
+        capabilities: capabilities.map(|c| ModelFeatures {
+            vision: c.vision,
+            tools: c.tools,
+            thinking: c.thinking,
+        }),
+        pricing,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::providers::types::{ModelInfo, ModelsResponse};
+    use super::*;
 
     #[test]
     fn test_models_response_format() {
         let response = ModelsResponse {
             object: "list".to_string(),
-            data: vec![ModelInfo {
-                id: "claude-3-sonnet".to_string(),
-                object: "model".to_string(),
-                created: 1700000000,
-                owned_by: "anthropic".to_string(),
-            }],
+            data: vec![model_info(
+                "claude-sonnet-4-20250514".to_string(),
+                "claude".to_string(),
+                1700000000,
+                &CostCalculator::new(),
+            )],
         };
 
         let json = serde_json::to_value(&response).unwrap();
         assert_eq!(json["object"], "list");
-        assert_eq!(json["data"][0]["id"], "claude-3-sonnet");
-        assert_eq!(json["data"][0]["object"], "model");
-        assert_eq!(json["data"][0]["owned_by"], "anthropic");
+        let model = &json["data"][0];
+        assert_eq!(model["id"], "claude-sonnet-4-20250514");
+        assert_eq!(model["object"], "model");
+        assert_eq!(model["owned_by"], "claude");
+        assert_eq!(model["providers"], serde_json::json!(["claude"]));
+        assert_eq!(model["context_window"], 200_000);
+        assert_eq!(model["max_output_tokens"], 64_000);
+        assert_eq!(model["capabilities"]["vision"], true);
+        assert_eq!(model["capabilities"]["thinking"], true);
+        assert_eq!(model["pricing"]["input_per_million"], 3.0);
+        assert_eq!(model["pricing"]["currency"], "USD");
+    }
+
+    #[test]
+    fn test_unknown_model_has_no_metadata() {
+        let calculator = CostCalculator::new().with_currency("EUR", 0.5);
+        let info = model_info("mystery".into(), "litellm".into(), 0, &calculator);
+        let json = serde_json::to_value(&info).unwrap();
+        assert!(json.get("capabilities").is_none());
+        assert!(json.get("pricing").is_none());
+        assert!(json.get("context_window").is_none());
+
+        let info = model_info("gpt-4o".into(), "copilot".into(), 0, &calculator);
+        let price = info.pricing.unwrap();
+        assert_eq!(price.input_per_million, 1.25);
+        assert_eq!(price.currency, "EUR");
     }
 
     #[test]
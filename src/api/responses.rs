@@ -0,0 +1,1268 @@
+//! OpenAI Responses API (`/v1/responses`).
+//!
+//! Requests are translated onto the existing [`ChatRequest`] pipeline, so
+//! caching, routing, auditing, and the per-key reasoning policy behave
+//! exactly as they do for `/v1/chat/completions`. Completed responses are
+//! stored so later requests can continue the conversation with
+//! `previous_response_id`.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::Extension;
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use rusqlite::{OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use tokio_stream::Stream;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::chat::{self, ChatEventStream, SseMsg};
+use crate::auth::AuthUser;
+use crate::budget::Attribution;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::providers::types::{
+    ChatChunk, ChatMessage, ChatRequest, ChatResponse, ContentPart, DocumentSource, FunctionCall,
+    FunctionDef, ImageUrl, MessageContent, MessageRole, Tool, ToolCall, Usage,
+};
+
+// ---------------------------------------------------------------------------
+// Request types
+// ---------------------------------------------------------------------------
+
+/// Body of `POST /v1/responses`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateResponseRequest {
+    pub model: String,
+    pub input: ResponseInput,
+    #[serde(default)]
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub previous_response_id: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+    #[serde(default)]
+    pub tools: Option<Vec<ResponseTool>>,
+    #[serde(default)]
+    pub tool_choice: Option<serde_json::Value>,
+    #[serde(default)]
+    pub stream: bool,
+    /// Whether the response is kept for `previous_response_id` chaining.
+    #[serde(default = "default_store")]
+    pub store: bool,
+    /// The client's end user, recorded for cost attribution.
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+fn default_store() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ResponseInput {
+    Text(String),
+    Items(Vec<InputItem>),
+}
+
+/// An input item. Message items may omit `type`, so the typed form is tried
+/// first and a bare `{role, content}` object is accepted as a fallback.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum InputItem {
+    Typed(TypedInputItem),
+    Message(InputMessage),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TypedInputItem {
+    Message(InputMessage),
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+    FunctionCallOutput {
+        call_id: String,
+        output: String,
+    },
+    /// Items gaud cannot forward (e.g. `reasoning`, `item_reference`) are
+    /// ignored.
+    #[serde(other)]
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InputMessage {
+    pub role: String,
+    pub content: InputContent,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum InputContent {
+    Text(String),
+    Parts(Vec<InputPart>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputPart {
+    InputText {
+        text: String,
+    },
+    OutputText {
+        text: String,
+    },
+    Refusal {
+        refusal: String,
+    },
+    InputImage {
+        #[serde(default)]
+        image_url: Option<String>,
+        #[serde(default)]
+        detail: Option<String>,
+    },
+    InputFile {
+        #[serde(default)]
+        file_data: Option<String>,
+        #[serde(default)]
+        filename: Option<String>,
+    },
+}
+
+/// Responses-style tool definition (flat, unlike Chat Completions).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResponseTool {
+    pub r#type: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub parameters: Option<serde_json::Value>,
+}
+
+// ---------------------------------------------------------------------------
+// Response types
+// ---------------------------------------------------------------------------
+
+/// A Responses API `response` object.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseObject {
+    pub id: String,
+    pub object: String,
+    pub created_at: i64,
+    pub status: String,
+    pub model: String,
+    pub output: Vec<OutputItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub incomplete_details: Option<IncompleteDetails>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ResponseError>,
+    pub previous_response_id: Option<String>,
+    pub instructions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResponseUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputItem {
+    Reasoning {
+        id: String,
+        summary: Vec<SummaryPart>,
+    },
+    Message {
+        id: String,
+        status: String,
+        role: String,
+        content: Vec<OutputContent>,
+    },
+    FunctionCall {
+        id: String,
+        call_id: String,
+        name: String,
+        arguments: String,
+        status: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SummaryPart {
+    SummaryText { text: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputContent {
+    OutputText {
+        text: String,
+        annotations: Vec<serde_json::Value>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IncompleteDetails {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseError {
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseUsage {
+    pub input_tokens: u32,
+    pub input_tokens_details: InputTokensDetails,
+    pub output_tokens: u32,
+    pub output_tokens_details: OutputTokensDetails,
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InputTokensDetails {
+    pub cached_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputTokensDetails {
+    pub reasoning_tokens: u32,
+}
+
+impl From<&Usage> for ResponseUsage {
+    fn from(usage: &Usage) -> Self {
+        Self {
+            input_tokens: usage.prompt_tokens,
+            input_tokens_details: InputTokensDetails {
+                cached_tokens: usage
+                    .prompt_tokens_details
+                    .as_ref()
+                    .and_then(|d| d.cached_tokens)
+                    .unwrap_or(0),
+            },
+            output_tokens: usage.completion_tokens,
+            output_tokens_details: OutputTokensDetails {
+                reasoning_tokens: usage
+                    .completion_tokens_details
+                    .as_ref()
+                    .and_then(|d| d.reasoning_tokens)
+                    .unwrap_or(0),
+            },
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+impl ResponseObject {
+    fn new(id: String, model: String, request: &CreateResponseRequest) -> Self {
+        Self {
+            id,
+            object: "response".to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+            status: "in_progress".to_string(),
+            model,
+            output: Vec::new(),
+            incomplete_details: None,
+            error: None,
+            previous_response_id: request.previous_response_id.clone(),
+            instructions: request.instructions.clone(),
+            usage: None,
+        }
+    }
+
+    /// Mark the response finished, deriving the status from the finish reason.
+    fn finish(&mut self, finish_reason: Option<&str>, usage: &Usage) {
+        for item in &mut self.output {
+            match item {
+                OutputItem::Message { status, .. } | OutputItem::FunctionCall { status, .. } => {
+                    *status = "completed".to_string();
+                }
+                OutputItem::Reasoning { .. } => {}
+            }
+        }
+        if finish_reason == Some("length") {
+            self.status = "incomplete".to_string();
+            self.incomplete_details = Some(IncompleteDetails {
+                reason: "max_output_tokens".to_string(),
+            });
+        } else {
+            self.status = "completed".to_string();
+        }
+        self.usage = Some(ResponseUsage::from(usage));
+    }
+
+    /// The assistant turn represented by this response, for chaining.
+    fn assistant_message(&self) -> ChatMessage {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for item in &self.output {
+            match item {
+                OutputItem::Message { content, .. } => {
+                    for OutputContent::OutputText { text: t, .. } in content {
+                        text.push_str(t);
+                    }
+                }
+                OutputItem::FunctionCall {
+                    call_id,
+                    name,
+                    arguments,
+                    ..
+                } => tool_calls.push(ToolCall {
+                    index: None,
+                    id: call_id.clone(),
+                    r#type: "function".to_string(),
+                    function: FunctionCall {
+                        name: name.clone(),
+                        arguments: arguments.clone(),
+                    },
+                }),
+                OutputItem::Reasoning { .. } => {}
+            }
+        }
+        ChatMessage {
+            role: MessageRole::Assistant,
+            content: (!text.is_empty()).then_some(MessageContent::Text(text)),
+            name: None,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            tool_call_id: None,
+        }
+    }
+}
+
+fn item_id(prefix: &str) -> String {
+    format!("{prefix}_{}", Uuid::new_v4().simple())
+}
+
+fn message_item(text: String, status: &str) -> OutputItem {
+    OutputItem::Message {
+        id: item_id("msg"),
+        status: status.to_string(),
+        role: "assistant".to_string(),
+        content: vec![OutputContent::OutputText {
+            text,
+            annotations: Vec::new(),
+        }],
+    }
+}
+
+fn reasoning_item(text: String) -> OutputItem {
+    OutputItem::Reasoning {
+        id: item_id("rs"),
+        summary: vec![SummaryPart::SummaryText { text }],
+    }
+}
+
+fn function_call_item(call: &ToolCall, status: &str) -> OutputItem {
+    OutputItem::FunctionCall {
+        id: item_id("fc"),
+        call_id: call.id.clone(),
+        name: call.function.name.clone(),
+        arguments: call.function.arguments.clone(),
+        status: status.to_string(),
+    }
+}
+
+/// Build output items from a complete chat response (first choice only).
+fn output_from_chat(response: &ChatResponse) -> (Vec<OutputItem>, Option<String>) {
+    let Some(choice) = response.choices.first() else {
+        return (Vec::new(), None);
+    };
+    let msg = &choice.message;
+    let mut output = Vec::new();
+    if let Some(reasoning) = msg.reasoning_content.as_ref().filter(|r| !r.is_empty()) {
+        output.push(reasoning_item(reasoning.clone()));
+    }
+    if let Some(content) = msg.content.as_ref().filter(|c| !c.is_empty()) {
+        output.push(message_item(content.clone(), "in_progress"));
+    }
+    for call in msg.tool_calls.iter().flatten() {
+        output.push(function_call_item(call, "in_progress"));
+    }
+    (output, choice.finish_reason.clone())
+}
+
+// ---------------------------------------------------------------------------
+// Translation onto ChatRequest
+// ---------------------------------------------------------------------------
+
+fn map_role(role: &str) -> Result<MessageRole, AppError> {
+    match role {
+        "user" => Ok(MessageRole::User),
+        "assistant" => Ok(MessageRole::Assistant),
+        "system" | "developer" => Ok(MessageRole::System),
+        other => Err(AppError::BadRequest(format!(
+            "Unsupported input message role: {other}"
+        ))),
+    }
+}
+
+fn convert_content(content: &InputContent) -> MessageContent {
+    let parts = match content {
+        InputContent::Text(text) => return MessageContent::Text(text.clone()),
+        InputContent::Parts(parts) => parts,
+    };
+    let converted = parts
+        .iter()
+        .filter_map(|part| match part {
+            InputPart::InputText { text } | InputPart::OutputText { text } => {
+                Some(ContentPart::Text { text: text.clone() })
+            }
+            InputPart::Refusal { refusal } => Some(ContentPart::Text {
+                text: refusal.clone(),
+            }),
+            InputPart::InputImage { image_url, detail } => {
+                image_url.as_ref().map(|url| ContentPart::ImageUrl {
+                    image_url: ImageUrl {
+                        url: url.clone(),
+                        detail: detail.clone(),
+                    },
+                })
+            }
+            InputPart::InputFile {
+                file_data,
+                filename,
+            } => file_data.as_ref().map(|data| ContentPart::Document {
+                document: DocumentSource {
+                    data: data.clone(),
+                    media_type: "application/pdf".to_string(),
+                    filename: filename.clone(),
+                },
+            }),
+        })
+        .collect();
+    MessageContent::Parts(converted)
+}
+
+/// Convert input items into chat messages, appending to `messages`.
+///
+/// Consecutive `function_call` items are folded into a single assistant
+/// message, matching how Chat Completions represents parallel tool calls.
+fn convert_input(input: &ResponseInput, messages: &mut Vec<ChatMessage>) -> Result<(), AppError> {
+    let items = match input {
+        ResponseInput::Text(text) => {
+            messages.push(ChatMessage {
+                role: MessageRole::User,
+                content: Some(MessageContent::Text(text.clone())),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            });
+            return Ok(());
+        }
+        ResponseInput::Items(items) => items,
+    };
+
+    for item in items {
+        match item {
+            InputItem::Message(msg) | InputItem::Typed(TypedInputItem::Message(msg)) => {
+                messages.push(ChatMessage {
+                    role: map_role(&msg.role)?,
+                    content: Some(convert_content(&msg.content)),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                });
+            }
+            InputItem::Typed(TypedInputItem::FunctionCall {
+                call_id,
+                name,
+                arguments,
+            }) => {
+                let call = ToolCall {
+                    index: None,
+                    id: call_id.clone(),
+                    r#type: "function".to_string(),
+                    function: FunctionCall {
+                        name: name.clone(),
+                        arguments: arguments.clone(),
+                    },
+                };
+                match messages.last_mut() {
+                    Some(last) if last.role == MessageRole::Assistant => {
+                        last.tool_calls.get_or_insert_with(Vec::new).push(call);
+                    }
+                    _ => messages.push(ChatMessage {
+                        role: MessageRole::Assistant,
+                        content: None,
+                        name: None,
+                        tool_calls: Some(vec![call]),
+                        tool_call_id: None,
+                    }),
+                }
+            }
+            InputItem::Typed(TypedInputItem::FunctionCallOutput { call_id, output }) => {
+                messages.push(ChatMessage {
+                    role: MessageRole::Tool,
+                    content: Some(MessageContent::Text(output.clone())),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: Some(call_id.clone()),
+                });
+            }
+            InputItem::Typed(TypedInputItem::Unsupported) => {}
+        }
+    }
+    Ok(())
+}
+
+fn convert_tools(tools: &[ResponseTool]) -> Result<Vec<Tool>, AppError> {
+    tools
+        .iter()
+        .map(|tool| {
+            if tool.r#type != "function" {
+                return Err(AppError::BadRequest(format!(
+                    "Unsupported tool type: {}",
+                    tool.r#type
+                )));
+            }
+            let name = tool
+                .name
+                .clone()
+                .ok_or_else(|| AppError::BadRequest("Function tool is missing a name".into()))?;
+            Ok(Tool {
+                r#type: "function".to_string(),
+                function: FunctionDef {
+                    name,
+                    description: tool.description.clone(),
+                    parameters: tool.parameters.clone(),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Responses uses `{"type":"function","name":..}`; Chat Completions nests the
+/// name under `function`. String choices are shared by both APIs.
+fn convert_tool_choice(choice: &serde_json::Value) -> serde_json::Value {
+    match choice.get("name") {
+        Some(name) if choice.get("type").and_then(|t| t.as_str()) == Some("function") => {
+            serde_json::json!({"type": "function", "function": {"name": name}})
+        }
+        _ => choice.clone(),
+    }
+}
+
+/// Build the chat request for a Responses request on top of prior history.
+///
+/// Returns the chat request together with the conversation (without
+/// `instructions`, which do not carry over between chained responses).
+fn build_chat_request(
+    request: &CreateResponseRequest,
+    history: Vec<ChatMessage>,
+) -> Result<(ChatRequest, Vec<ChatMessage>), AppError> {
+    let mut conversation = history;
+    convert_input(&request.input, &mut conversation)?;
+
+    let mut messages = Vec::with_capacity(conversation.len() + 1);
+    if let Some(instructions) = &request.instructions {
+        messages.push(ChatMessage {
+            role: MessageRole::System,
+            content: Some(MessageContent::Text(instructions.clone())),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        });
+    }
+    messages.extend(conversation.iter().cloned());
+
+    let tools = request.tools.as_deref().map(convert_tools).transpose()?;
+
+    let chat_request = ChatRequest {
+        model: request.model.clone(),
+        messages,
+        temperature: request.temperature,
+        max_tokens: request.max_output_tokens,
+        stream: request.stream,
+        top_p: request.top_p,
+        stop: None,
+        tools,
+        tool_choice: request.tool_choice.as_ref().map(convert_tool_choice),
+        stream_options: None,
+        google_search: false,
+        extra_body: None,
+        user: request.user.clone(),
+    };
+    Ok((chat_request, conversation))
+}
+
+// ---------------------------------------------------------------------------
+// Storage
+// ---------------------------------------------------------------------------
+
+/// Load the stored conversation for a previous response owned by `user_id`.
+fn load_history(db: &Database, id: &str, user_id: &str) -> Result<Vec<ChatMessage>, AppError> {
+    let messages: Option<String> = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT messages FROM responses WHERE id = ?1 AND user_id = ?2",
+            params![id, user_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    let messages =
+        messages.ok_or_else(|| AppError::NotFound(format!("Response '{id}' not found")))?;
+    serde_json::from_str(&messages)
+        .map_err(|e| AppError::Internal(format!("Corrupt stored conversation for '{id}': {e}")))
+}
+
+/// Persist a completed response and the conversation that produced it.
+fn store_response(
+    db: &Database,
+    user_id: &str,
+    response: &ResponseObject,
+    mut conversation: Vec<ChatMessage>,
+) -> Result<(), AppError> {
+    conversation.push(response.assistant_message());
+    let messages = serde_json::to_string(&conversation)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize conversation: {e}")))?;
+    let body = serde_json::to_string(response)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize response: {e}")))?;
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO responses (id, user_id, model, messages, response)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![response.id, user_id, response.model, messages, body],
+        )
+    })?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+/// POST /v1/responses
+///
+/// OpenAI Responses API endpoint supporting both streaming (typed SSE
+/// events) and non-streaming JSON responses.
+pub async fn create_response(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Json(request): Json<CreateResponseRequest>,
+) -> Result<Response, AppError> {
+    let request_id = Uuid::new_v4().to_string();
+    let attribution = Attribution::from_request(&headers, request.user.as_deref())?;
+
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %request.model,
+        stream = request.stream,
+        previous_response_id = ?request.previous_response_id,
+        "Responses request"
+    );
+
+    let history = match &request.previous_response_id {
+        Some(id) => load_history(&state.db, id, &user.user_id)?,
+        None => Vec::new(),
+    };
+    let (chat_request, conversation) = build_chat_request(&request, history)?;
+    let mut response = ResponseObject::new(item_id("resp"), request.model.clone(), &request);
+
+    if request.stream {
+        let db = request.store.then(|| state.db.clone());
+        let user_id = user.user_id.clone();
+        let (inner, upstream) =
+            chat::open_chat_stream(state, user, chat_request, request_id, attribution).await?;
+        let events = ResponseEventStream::new(inner, response).on_complete(move |response| {
+            let Some(db) = db else { return };
+            if let Err(e) = store_response(&db, &user_id, response, conversation) {
+                tracing::warn!(error = %e, "Failed to store response");
+            }
+        });
+        let mut sse = Sse::new(events)
+            .keep_alive(KeepAlive::default())
+            .into_response();
+        chat::insert_upstream_rate_limit_headers(sse.headers_mut(), upstream.as_ref());
+        return Ok(sse);
+    }
+
+    let db = state.db.clone();
+    let user_id = user.user_id.clone();
+    let (chat_response, upstream) =
+        chat::complete_chat(state, user, chat_request, request_id, attribution).await?;
+    let (output, finish_reason) = output_from_chat(&chat_response);
+    response.output = output;
+    response.finish(finish_reason.as_deref(), &chat_response.usage);
+
+    if request.store {
+        store_response(&db, &user_id, &response, conversation)?;
+    }
+    let mut http_response = Json(response).into_response();
+    chat::insert_upstream_rate_limit_headers(http_response.headers_mut(), upstream.as_ref());
+    Ok(http_response)
+}
+
+/// GET /v1/responses/{id}
+///
+/// Retrieve a stored response owned by the caller.
+pub async fn get_response(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    let body: Option<String> = state.db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT response FROM responses WHERE id = ?1 AND user_id = ?2",
+            params![id, user.user_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    let body = body.ok_or_else(|| AppError::NotFound(format!("Response '{id}' not found")))?;
+    let value = serde_json::from_str(&body)
+        .map_err(|e| AppError::Internal(format!("Corrupt stored response for '{id}': {e}")))?;
+    Ok(Json(value))
+}
+
+// ---------------------------------------------------------------------------
+// ResponseEventStream — chat chunks → Responses SSE events
+// ---------------------------------------------------------------------------
+
+type CompletionHook = Box<dyn FnOnce(&ResponseObject) + Send>;
+
+/// Translates the chat pipeline's [`SseMsg`] stream into typed Responses
+/// events (`response.created`, `response.output_text.delta`, ...,
+/// `response.completed`).
+struct ResponseEventStream {
+    inner: ChatEventStream,
+    response: ResponseObject,
+    pending: VecDeque<(&'static str, serde_json::Value)>,
+    sequence_number: u64,
+    started: bool,
+    finished: bool,
+    /// Set once `response.failed` has been emitted; the inner stream is still
+    /// drained so the chat pipeline records its audit entry.
+    failed: bool,
+    /// Output index of the currently open message / reasoning item.
+    text_index: Option<usize>,
+    reasoning_index: Option<usize>,
+    /// Chat tool-call index → output index.
+    tool_indices: Vec<(u32, usize)>,
+    finish_reason: Option<String>,
+    usage: Usage,
+    on_complete: Option<CompletionHook>,
+}
+
+impl ResponseEventStream {
+    fn new(inner: ChatEventStream, response: ResponseObject) -> Self {
+        Self {
+            inner,
+            response,
+            pending: VecDeque::new(),
+            sequence_number: 0,
+            started: false,
+            finished: false,
+            failed: false,
+            text_index: None,
+            reasoning_index: None,
+            tool_indices: Vec::new(),
+            finish_reason: None,
+            usage: Usage::default(),
+            on_complete: None,
+        }
+    }
+
+    /// Run `hook` with the final response once the stream completes
+    /// successfully.
+    fn on_complete(mut self, hook: impl FnOnce(&ResponseObject) + Send + 'static) -> Self {
+        self.on_complete = Some(Box::new(hook));
+        self
+    }
+
+    fn push(&mut self, kind: &'static str, mut data: serde_json::Value) {
+        data["type"] = serde_json::Value::String(kind.to_string());
+        data["sequence_number"] = serde_json::json!(self.sequence_number);
+        self.sequence_number += 1;
+        self.pending.push_back((kind, data));
+    }
+
+    fn push_item_added(&mut self, index: usize) {
+        let item = serde_json::to_value(&self.response.output[index]).unwrap_or_default();
+        self.push(
+            "response.output_item.added",
+            serde_json::json!({"output_index": index, "item": item}),
+        );
+    }
+
+    fn item_id(&self, index: usize) -> String {
+        match &self.response.output[index] {
+            OutputItem::Reasoning { id, .. }
+            | OutputItem::Message { id, .. }
+            | OutputItem::FunctionCall { id, .. } => id.clone(),
+        }
+    }
+
+    fn on_chunk(&mut self, chunk: ChatChunk) {
+        if let Some(usage) = chunk.usage {
+            self.usage = usage;
+        }
+        let Some(choice) = chunk.choices.into_iter().find(|c| c.index == 0) else {
+            return;
+        };
+        if choice.finish_reason.is_some() {
+            self.finish_reason = choice.finish_reason;
+        }
+        let delta = choice.delta;
+
+        if let Some(reasoning) = delta.reasoning_content.filter(|r| !r.is_empty()) {
+            let index = match self.reasoning_index {
+                Some(index) => index,
+                None => {
+                    self.response.output.push(reasoning_item(String::new()));
+                    let index = self.response.output.len() - 1;
+                    self.reasoning_index = Some(index);
+                    self.push_item_added(index);
+                    index
+                }
+            };
+            if let OutputItem::Reasoning { summary, .. } = &mut self.response.output[index] {
+                let SummaryPart::SummaryText { text } = &mut summary[0];
+                text.push_str(&reasoning);
+            }
+            let item_id = self.item_id(index);
+            self.push(
+                "response.reasoning_summary_text.delta",
+                serde_json::json!({
+                    "item_id": item_id,
+                    "output_index": index,
+                    "summary_index": 0,
+                    "delta": reasoning,
+                }),
+            );
+        }
+
+        if let Some(content) = delta.content.filter(|c| !c.is_empty()) {
+            let index = match self.text_index {
+                Some(index) => index,
+                None => {
+                    self.response
+                        .output
+                        .push(message_item(String::new(), "in_progress"));
+                    let index = self.response.output.len() - 1;
+                    self.text_index = Some(index);
+                    self.push_item_added(index);
+                    let item_id = self.item_id(index);
+                    self.push(
+                        "response.content_part.added",
+                        serde_json::json!({
+                            "item_id": item_id,
+                            "output_index": index,
+                            "content_index": 0,
+                            "part": {"type": "output_text", "text": "", "annotations": []},
+                        }),
+                    );
+                    index
+                }
+            };
+            if let OutputItem::Message { content: parts, .. } = &mut self.response.output[index] {
+                let OutputContent::OutputText { text, .. } = &mut parts[0];
+                text.push_str(&content);
+            }
+            let item_id = self.item_id(index);
+            self.push(
+                "response.output_text.delta",
+                serde_json::json!({
+                    "item_id": item_id,
+                    "output_index": index,
+                    "content_index": 0,
+                    "delta": content,
+                }),
+            );
+        }
+
+        for call in delta.tool_calls.into_iter().flatten() {
+            let call_index = call.index.unwrap_or(0);
+            let existing = self
+                .tool_indices
+                .iter()
+                .find(|(i, _)| *i == call_index)
+                .map(|(_, o)| *o);
+            let index = match existing {
+                Some(index) => index,
+                None => {
+                    let mut start = call.clone();
+                    start.function.arguments = String::new();
+                    self.response
+                        .output
+                        .push(function_call_item(&start, "in_progress"));
+                    let index = self.response.output.len() - 1;
+                    self.tool_indices.push((call_index, index));
+                    self.push_item_added(index);
+                    index
+                }
+            };
+            if call.function.arguments.is_empty() {
+                continue;
+            }
+            if let OutputItem::FunctionCall { arguments, .. } = &mut self.response.output[index] {
+                arguments.push_str(&call.function.arguments);
+            }
+            let item_id = self.item_id(index);
+            self.push(
+                "response.function_call_arguments.delta",
+                serde_json::json!({
+                    "item_id": item_id,
+                    "output_index": index,
+                    "delta": call.function.arguments,
+                }),
+            );
+        }
+    }
+
+    fn on_done(&mut self) {
+        let finish_reason = self.finish_reason.take();
+        let usage = std::mem::take(&mut self.usage);
+        self.response.finish(finish_reason.as_deref(), &usage);
+
+        for index in 0..self.response.output.len() {
+            let item_id = self.item_id(index);
+            match self.response.output[index].clone() {
+                OutputItem::Message { content, .. } => {
+                    let OutputContent::OutputText { text, .. } = &content[0];
+                    self.push(
+                        "response.output_text.done",
+                        serde_json::json!({
+                            "item_id": item_id,
+                            "output_index": index,
+                            "content_index": 0,
+                            "text": text,
+                        }),
+                    );
+                    self.push(
+                        "response.content_part.done",
+                        serde_json::json!({
+                            "item_id": item_id,
+                            "output_index": index,
+                            "content_index": 0,
+                            "part": serde_json::to_value(&content[0]).unwrap_or_default(),
+                        }),
+                    );
+                }
+                OutputItem::FunctionCall { arguments, .. } => {
+                    self.push(
+                        "response.function_call_arguments.done",
+                        serde_json::json!({
+                            "item_id": item_id,
+                            "output_index": index,
+                            "arguments": arguments,
+                        }),
+                    );
+                }
+                OutputItem::Reasoning { .. } => {}
+            }
+            let item = serde_json::to_value(&self.response.output[index]).unwrap_or_default();
+            self.push(
+                "response.output_item.done",
+                serde_json::json!({"output_index": index, "item": item}),
+            );
+        }
+
+        let kind = if self.response.status == "incomplete" {
+            "response.incomplete"
+        } else {
+            "response.completed"
+        };
+        let response = serde_json::to_value(&self.response).unwrap_or_default();
+        self.push(kind, serde_json::json!({"response": response}));
+
+        if let Some(hook) = self.on_complete.take() {
+            hook(&self.response);
+        }
+    }
+
+    fn on_error(&mut self, payload: &str) {
+        let error = serde_json::from_str::<serde_json::Value>(payload).unwrap_or_default();
+        let field = |key: &str| error["error"][key].as_str().map(String::from);
+        self.response.status = "failed".to_string();
+        self.response.error = Some(ResponseError {
+            code: field("code").unwrap_or_else(|| "server_error".to_string()),
+            message: field("message").unwrap_or_else(|| payload.to_string()),
+        });
+        let response = serde_json::to_value(&self.response).unwrap_or_default();
+        self.push("response.failed", serde_json::json!({"response": response}));
+        self.failed = true;
+        self.on_complete = None;
+    }
+}
+
+impl Stream for ResponseEventStream {
+    type Item = Result<Event, std::convert::Infallible>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if !this.started {
+            this.started = true;
+            let response = serde_json::to_value(&this.response).unwrap_or_default();
+            this.push(
+                "response.created",
+                serde_json::json!({"response": response}),
+            );
+        }
+
+        loop {
+            if let Some((kind, data)) = this.pending.pop_front() {
+                let event = Event::default().event(kind).data(data.to_string());
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+            match this.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(SseMsg::Data(_))) if this.failed => {}
+                Poll::Ready(Some(SseMsg::Data(payload))) => {
+                    match serde_json::from_str::<ChatChunk>(&payload) {
+                        Ok(chunk) => this.on_chunk(chunk),
+                        Err(_) => this.on_error(&payload),
+                    }
+                }
+                Poll::Ready(Some(SseMsg::Done)) | Poll::Ready(None) => {
+                    this.finished = true;
+                    if !this.failed {
+                        this.on_done();
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+// ===========================================================================
+// Tests
+// ===========================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::types::{ChunkChoice, Delta};
+    use tokio_stream::StreamExt;
+
+    fn parse_request(body: serde_json::Value) -> CreateResponseRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    fn text_chunk(content: &str) -> ChatChunk {
+        ChatChunk {
+            id: "c1".into(),
+            object: "chat.completion.chunk".into(),
+            created: 0,
+            model: "m".into(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta: Delta {
+                    role: None,
+                    content: Some(content.into()),
+                    reasoning_content: None,
+                    tool_calls: None,
+                    grounding: None,
+                },
+                finish_reason: None,
+            }],
+            usage: None,
+            tool_calls_emulated: false,
+        }
+    }
+
+    #[test]
+    fn test_string_input_with_instructions() {
+        let request = parse_request(serde_json::json!({
+            "model": "gpt-4o",
+            "input": "Hello",
+            "instructions": "Be brief.",
+            "max_output_tokens": 64,
+        }));
+        let (chat, conversation) = build_chat_request(&request, Vec::new()).unwrap();
+
+        assert_eq!(chat.messages.len(), 2);
+        assert_eq!(chat.messages[0].role, MessageRole::System);
+        assert_eq!(
+            chat.messages[1].content.as_ref().unwrap().as_text(),
+            "Hello"
+        );
+        assert_eq!(chat.max_tokens, Some(64));
+        // Instructions are not part of the stored conversation.
+        assert_eq!(conversation.len(), 1);
+        assert!(request.store);
+    }
+
+    #[test]
+    fn test_item_input_with_function_calls() {
+        let request = parse_request(serde_json::json!({
+            "model": "gpt-4o",
+            "input": [
+                {"role": "developer", "content": "sys"},
+                {"type": "message", "role": "user", "content": [
+                    {"type": "input_text", "text": "Weather?"},
+                    {"type": "input_image", "image_url": "data:image/png;base64,AAAA"},
+                ]},
+                {"type": "function_call", "call_id": "c1", "name": "a", "arguments": "{}"},
+                {"type": "function_call", "call_id": "c2", "name": "b", "arguments": "{}"},
+                {"type": "function_call_output", "call_id": "c1", "output": "sunny"},
+                {"type": "reasoning", "id": "rs_1", "summary": []},
+            ],
+            "tools": [{"type": "function", "name": "a", "parameters": {"type": "object"}}],
+            "tool_choice": {"type": "function", "name": "a"},
+        }));
+        let (chat, _) = build_chat_request(&request, Vec::new()).unwrap();
+
+        assert_eq!(chat.messages.len(), 4);
+        assert_eq!(chat.messages[0].role, MessageRole::System);
+        match chat.messages[1].content.as_ref().unwrap() {
+            MessageContent::Parts(parts) => assert_eq!(parts.len(), 2),
+            other => panic!("expected parts, got {other:?}"),
+        }
+        assert_eq!(chat.messages[2].tool_calls.as_ref().unwrap().len(), 2);
+        assert_eq!(chat.messages[3].tool_call_id.as_deref(), Some("c1"));
+        assert_eq!(chat.tools.as_ref().unwrap()[0].function.name, "a");
+        assert_eq!(chat.tool_choice.unwrap()["function"]["name"], "a");
+    }
+
+    #[test]
+    fn test_non_function_tool_rejected() {
+        let request = parse_request(serde_json::json!({
+            "model": "gpt-4o",
+            "input": "hi",
+            "tools": [{"type": "web_search"}],
+        }));
+        assert!(matches!(
+            build_chat_request(&request, Vec::new()),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_chaining_roundtrip() {
+        let db = Database::open_in_memory().unwrap();
+        let request = parse_request(serde_json::json!({"model": "m", "input": "first"}));
+        let (_, conversation) = build_chat_request(&request, Vec::new()).unwrap();
+
+        let mut response = ResponseObject::new("resp_1".into(), "m".into(), &request);
+        response.output = vec![message_item("answer".into(), "in_progress")];
+        response.finish(Some("stop"), &Usage::default());
+        store_response(&db, "u1", &response, conversation).unwrap();
+
+        let history = load_history(&db, "resp_1", "u1").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].role, MessageRole::Assistant);
+        assert_eq!(history[1].content.as_ref().unwrap().as_text(), "answer");
+
+        // Other users cannot chain onto the response.
+        assert!(matches!(
+            load_history(&db, "resp_1", "u2"),
+            Err(AppError::NotFound(_))
+        ));
+
+        let next = parse_request(serde_json::json!({
+            "model": "m",
+            "input": "second",
+            "previous_response_id": "resp_1",
+        }));
+        let (chat, _) = build_chat_request(&next, history).unwrap();
+        assert_eq!(chat.messages.len(), 3);
+    }
+
+    #[test]
+    fn test_output_from_chat_and_status() {
+        let chat: ChatResponse = serde_json::from_value(serde_json::json!({
+            "id": "x", "object": "chat.completion", "created": 0, "model": "m",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "partial",
+                    "reasoning_content": "hmm",
+                    "tool_calls": [{"id": "c1", "type": "function",
+                        "function": {"name": "f", "arguments": "{}"}}],
+                },
+                "finish_reason": "length",
+            }],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 4, "total_tokens": 7},
+        }))
+        .unwrap();
+        let request = parse_request(serde_json::json!({"model": "m", "input": "q"}));
+        let mut response = ResponseObject::new("resp_1".into(), "m".into(), &request);
+        let (output, finish) = output_from_chat(&chat);
+        response.output = output;
+        response.finish(finish.as_deref(), &chat.usage);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["status"], "incomplete");
+        assert_eq!(json["incomplete_details"]["reason"], "max_output_tokens");
+        assert_eq!(json["output"][0]["type"], "reasoning");
+        assert_eq!(json["output"][1]["type"], "message");
+        assert_eq!(json["output"][1]["content"][0]["text"], "partial");
+        assert_eq!(json["output"][2]["type"], "function_call");
+        assert_eq!(json["output"][2]["status"], "completed");
+        assert_eq!(json["usage"]["total_tokens"], 7);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_text() {
+        let msgs = vec![
+            SseMsg::Data(serde_json::to_string(&text_chunk("Hel")).unwrap()),
+            SseMsg::Data(serde_json::to_string(&text_chunk("lo")).unwrap()),
+            SseMsg::Done,
+        ];
+        let inner: ChatEventStream = Box::pin(tokio_stream::iter(msgs));
+        let request = parse_request(serde_json::json!({"model": "m", "input": "q"}));
+        let response = ResponseObject::new("resp_1".into(), "m".into(), &request);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut stream = ResponseEventStream::new(inner, response).on_complete(move |r| {
+            tx.send(r.assistant_message()).unwrap();
+        });
+        while stream.next().await.is_some() {}
+
+        assert_eq!(stream.response.status, "completed");
+        let message = rx.try_recv().unwrap();
+        assert_eq!(message.content.unwrap().as_text(), "Hello");
+        // created, item.added, part.added, 2 deltas, text.done, part.done,
+        // item.done, completed
+        assert_eq!(stream.sequence_number, 9);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_error_skips_completion_hook() {
+        let msgs = vec![
+            SseMsg::Data(
+                r#"{"error":{"message":"boom","type":"api_error","code":"upstream_timeout","param":null}}"#
+                    .into(),
+            ),
+            SseMsg::Done,
+        ];
+        let inner: ChatEventStream = Box::pin(tokio_stream::iter(msgs));
+        let request = parse_request(serde_json::json!({"model": "m", "input": "q"}));
+        let response = ResponseObject::new("resp_1".into(), "m".into(), &request);
+
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let mut stream = ResponseEventStream::new(inner, response).on_complete(move |_| {
+            tx.send(()).unwrap();
+        });
+        while stream.next().await.is_some() {}
+
+        assert_eq!(stream.response.status, "failed");
+        let error = stream.response.error.as_ref().unwrap();
+        assert_eq!(error.message, "boom");
+        assert_eq!(error.code, "upstream_timeout");
+        assert!(rx.try_recv().is_err());
+    }
+}
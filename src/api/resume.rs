@@ -0,0 +1,384 @@
+//! Resumable SSE streams (`Last-Event-ID`).
+//!
+//! When `cache.stream_resume_enabled` is set, a streaming completion is no
+//! longer driven by the HTTP response. A background task pumps the
+//! [`ChatEventStream`] into a [`StreamRegistry`] buffer and the response
+//! subscribes to that buffer. Every event carries an id of the form
+//! `{stream_id}:{seq}`; a client that loses its connection re-sends the
+//! request with `Last-Event-ID` and picks up after that event instead of
+//! paying for a second generation.
+//!
+//! Buffers are bounded by the stream-cache limits
+//! (`stream_cache_max_events` / `stream_cache_max_bytes`): once exceeded, the
+//! oldest events are dropped and can no longer be resumed from. Events a
+//! connected subscriber has not read yet are never dropped, so a slow client
+//! only delays trimming. Finished streams are kept for
+//! `stream_resume_ttl_secs`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::Stream;
+use tokio::sync::watch;
+use tokio_stream::StreamExt;
+use tokio_util::task::TaskTracker;
+use uuid::Uuid;
+
+use crate::api::chat::{ChatEventStream, SseMsg};
+use crate::config::CacheConfig;
+use crate::error::AppError;
+
+/// Stream of `(event_id, message)` pairs read from a resumable buffer.
+pub(crate) type ResumableEvents = std::pin::Pin<Box<dyn Stream<Item = (String, SseMsg)> + Send>>;
+
+/// Registry of in-flight and recently finished resumable streams.
+pub struct StreamRegistry {
+    streams: Mutex<HashMap<String, Arc<ResumableStream>>>,
+    ttl: Duration,
+    max_events: usize,
+    max_bytes: usize,
+}
+
+struct ResumableStream {
+    user_id: String,
+    buffer: Mutex<Buffer>,
+    /// Total number of events produced so far; bumped on every push so
+    /// subscribers wake up.
+    produced: watch::Sender<u64>,
+}
+
+#[derive(Default)]
+struct Buffer {
+    events: VecDeque<SseMsg>,
+    /// Sequence number of `events[0]`.
+    first_seq: u64,
+    bytes: usize,
+    finished_at: Option<Instant>,
+    /// Number of connected subscribers.
+    readers: usize,
+    /// Highest sequence number every connected subscriber has read up to.
+    acked: u64,
+}
+
+impl StreamRegistry {
+    pub fn new(ttl: Duration, max_events: usize, max_bytes: usize) -> Self {
+        Self {
+            streams: Mutex::new(HashMap::new()),
+            ttl,
+            max_events: max_events.max(1),
+            max_bytes,
+        }
+    }
+
+    pub fn from_config(config: &CacheConfig) -> Self {
+        Self::new(
+            Duration::from_secs(config.stream_resume_ttl_secs),
+            config.stream_cache_max_events,
+            config.stream_cache_max_bytes,
+        )
+    }
+
+    /// Start buffering `stream` for `user_id` and return a subscription from
+    /// the first event.
+    ///
+    /// The stream is driven to completion by a task on `tasks` even if the
+    /// subscriber goes away.
+    pub(crate) fn start(
+        &self,
+        tasks: &TaskTracker,
+        user_id: &str,
+        mut stream: ChatEventStream,
+    ) -> ResumableEvents {
+        self.purge_expired();
+
+        let stream_id = Uuid::new_v4().simple().to_string();
+        let (produced, _) = watch::channel(0);
+        let entry = Arc::new(ResumableStream {
+            user_id: user_id.to_string(),
+            buffer: Mutex::new(Buffer::default()),
+            produced,
+        });
+        self.streams
+            .lock()
+            .expect("stream registry mutex poisoned")
+            .insert(stream_id.clone(), Arc::clone(&entry));
+
+        let pump = Arc::clone(&entry);
+        let (max_events, max_bytes) = (self.max_events, self.max_bytes);
+        tasks.spawn(async move {
+            while let Some(msg) = stream.next().await {
+                pump.push(msg, max_events, max_bytes);
+            }
+            pump.finish();
+        });
+
+        subscribe(stream_id, entry, 0)
+    }
+
+    /// Resume a stream after the event with id `last_event_id`.
+    pub(crate) fn resume(
+        &self,
+        user_id: &str,
+        last_event_id: &str,
+    ) -> Result<ResumableEvents, AppError> {
+        let expired = || {
+            AppError::NotFound(format!(
+                "Stream '{last_event_id}' is no longer available to resume"
+            ))
+        };
+        let (stream_id, seq) = parse_event_id(last_event_id).ok_or_else(|| {
+            AppError::BadRequest(format!("Invalid Last-Event-ID '{last_event_id}'"))
+        })?;
+
+        self.purge_expired();
+        let entry = self
+            .streams
+            .lock()
+            .expect("stream registry mutex poisoned")
+            .get(stream_id)
+            .cloned()
+            .ok_or_else(expired)?;
+        // Don't reveal other users' streams.
+        if entry.user_id != user_id {
+            return Err(expired());
+        }
+
+        let next = seq + 1;
+        let first_seq = entry
+            .buffer
+            .lock()
+            .expect("stream buffer mutex poisoned")
+            .first_seq;
+        if next < first_seq {
+            return Err(expired());
+        }
+        Ok(subscribe(stream_id.to_string(), entry, next))
+    }
+
+    fn purge_expired(&self) {
+        let ttl = self.ttl;
+        self.streams
+            .lock()
+            .expect("stream registry mutex poisoned")
+            .retain(|_, s| {
+                let buffer = s.buffer.lock().expect("stream buffer mutex poisoned");
+                buffer.finished_at.is_none_or(|t| t.elapsed() < ttl)
+            });
+    }
+}
+
+impl ResumableStream {
+    fn push(&self, msg: SseMsg, max_events: usize, max_bytes: usize) {
+        let mut buffer = self.buffer.lock().expect("stream buffer mutex poisoned");
+        buffer.bytes += msg_len(&msg);
+        buffer.events.push_back(msg);
+        while buffer.events.len() > 1
+            && (buffer.events.len() > max_events || buffer.bytes > max_bytes)
+            && (buffer.readers == 0 || buffer.first_seq < buffer.acked)
+        {
+            if let Some(dropped) = buffer.events.pop_front() {
+                buffer.bytes -= msg_len(&dropped);
+                buffer.first_seq += 1;
+            }
+        }
+        let produced = buffer.first_seq + buffer.events.len() as u64;
+        drop(buffer);
+        self.produced.send_replace(produced);
+    }
+
+    fn finish(&self) {
+        let mut buffer = self.buffer.lock().expect("stream buffer mutex poisoned");
+        buffer.finished_at = Some(Instant::now());
+        let produced = buffer.first_seq + buffer.events.len() as u64;
+        drop(buffer);
+        self.produced.send_replace(produced);
+    }
+}
+
+/// Keeps a subscriber counted in [`Buffer::readers`] while it is alive.
+struct ReaderGuard(Arc<ResumableStream>);
+
+impl ReaderGuard {
+    fn new(entry: Arc<ResumableStream>, from: u64) -> Self {
+        let mut buffer = entry.buffer.lock().expect("stream buffer mutex poisoned");
+        // With several readers, only trim what the slowest one has seen.
+        buffer.acked = if buffer.readers == 0 {
+            from
+        } else {
+            buffer.acked.min(from)
+        };
+        buffer.readers += 1;
+        drop(buffer);
+        Self(entry)
+    }
+}
+
+impl Drop for ReaderGuard {
+    fn drop(&mut self) {
+        if let Ok(mut buffer) = self.0.buffer.lock() {
+            buffer.readers -= 1;
+        }
+    }
+}
+
+/// Read events from `entry` starting at sequence number `from`.
+fn subscribe(stream_id: String, entry: Arc<ResumableStream>, from: u64) -> ResumableEvents {
+    let mut produced = entry.produced.subscribe();
+    let guard = ReaderGuard::new(Arc::clone(&entry), from);
+    Box::pin(async_stream::stream! {
+        let _guard = guard;
+        let mut next = from;
+        loop {
+            // Mark the current value seen before reading the buffer so a push
+            // racing with the read still wakes us up below.
+            produced.borrow_and_update();
+            let (batch, finished) = {
+                let mut buffer = entry.buffer.lock().expect("stream buffer mutex poisoned");
+                if buffer.readers == 1 {
+                    buffer.acked = next;
+                }
+                if next < buffer.first_seq {
+                    // Fell behind the buffer window; nothing sensible to send.
+                    tracing::warn!(stream_id = %stream_id, "Resumable stream subscriber fell behind");
+                    break;
+                }
+                let skip = (next - buffer.first_seq) as usize;
+                let batch: Vec<SseMsg> = buffer.events.iter().skip(skip).cloned().collect();
+                (batch, buffer.finished_at.is_some())
+            };
+
+            for msg in batch {
+                yield (format!("{stream_id}:{next}"), msg);
+                next += 1;
+            }
+
+            if finished {
+                break;
+            }
+            if produced.changed().await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+fn parse_event_id(id: &str) -> Option<(&str, u64)> {
+    let (stream_id, seq) = id.trim().rsplit_once(':')?;
+    Some((stream_id, seq.parse().ok()?))
+}
+
+fn msg_len(msg: &SseMsg) -> usize {
+    match msg {
+        SseMsg::Data(payload) => payload.len(),
+        SseMsg::Done => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(payloads: &[&str]) -> ChatEventStream {
+        let mut msgs: Vec<SseMsg> = payloads
+            .iter()
+            .map(|p| SseMsg::Data(p.to_string()))
+            .collect();
+        msgs.push(SseMsg::Done);
+        Box::pin(tokio_stream::iter(msgs))
+    }
+
+    async fn collect(events: ResumableEvents) -> Vec<(String, SseMsg)> {
+        events.collect().await
+    }
+
+    #[tokio::test]
+    async fn test_start_assigns_sequential_ids() {
+        let registry = StreamRegistry::new(Duration::from_secs(60), 100, 1 << 20);
+        let events =
+            collect(registry.start(&TaskTracker::new(), "user1", source(&["a", "b"]))).await;
+
+        assert_eq!(events.len(), 3);
+        let stream_id = events[0].0.split(':').next().unwrap().to_string();
+        assert_eq!(
+            events[0],
+            (format!("{stream_id}:0"), SseMsg::Data("a".into()))
+        );
+        assert_eq!(events[1].0, format!("{stream_id}:1"));
+        assert_eq!(events[2], (format!("{stream_id}:2"), SseMsg::Done));
+    }
+
+    #[tokio::test]
+    async fn test_resume_after_last_event_id() {
+        let registry = StreamRegistry::new(Duration::from_secs(60), 100, 1 << 20);
+        let first =
+            collect(registry.start(&TaskTracker::new(), "user1", source(&["a", "b", "c"]))).await;
+
+        let resumed = collect(registry.resume("user1", &first[0].0).unwrap()).await;
+        let msgs: Vec<SseMsg> = resumed.into_iter().map(|(_, m)| m).collect();
+        assert_eq!(
+            msgs,
+            vec![
+                SseMsg::Data("b".into()),
+                SseMsg::Data("c".into()),
+                SseMsg::Done
+            ]
+        );
+
+        // Other users cannot resume the stream.
+        assert!(matches!(
+            registry.resume("user2", &first[0].0),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            registry.resume("user1", "garbage"),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_slow_reader_is_not_trimmed() {
+        let registry = StreamRegistry::new(Duration::from_secs(60), 1, 1 << 20);
+        let events =
+            collect(registry.start(&TaskTracker::new(), "user1", source(&["a", "b", "c"]))).await;
+        assert_eq!(events.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_resume_outside_window_fails() {
+        let registry = StreamRegistry::new(Duration::from_secs(60), 2, 1 << 20);
+        // Disconnect immediately and let the pump run to completion.
+        let tasks = TaskTracker::new();
+        drop(registry.start(&tasks, "user1", source(&["a", "b", "c"])));
+        tasks.close();
+        tasks.wait().await;
+        let stream_id = registry
+            .streams
+            .lock()
+            .unwrap()
+            .keys()
+            .next()
+            .unwrap()
+            .clone();
+
+        // Only the last two events ("c", Done) are still buffered.
+        assert!(matches!(
+            registry.resume("user1", &format!("{stream_id}:0")),
+            Err(AppError::NotFound(_))
+        ));
+        let resumed = collect(registry.resume("user1", &format!("{stream_id}:1")).unwrap()).await;
+        assert_eq!(resumed.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_finished_streams_expire() {
+        let registry = StreamRegistry::new(Duration::ZERO, 100, 1 << 20);
+        let first = collect(registry.start(&TaskTracker::new(), "user1", source(&["a"]))).await;
+        // Wait for the pump task to record completion.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(matches!(
+            registry.resume("user1", &first[0].0),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+//! Fixtures shared by the API handler tests.
+
+use std::sync::Arc;
+
+use tokio_util::task::TaskTracker;
+
+use crate::AppState;
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::leader::Leader;
+use crate::providers::cost::CostCalculator;
+use crate::providers::factory::ProviderFactory;
+use crate::providers::reasoning::ReasoningMode;
+use crate::providers::router::ProviderRouter;
+
+/// Application state over an in-memory database, serving `router`. MCP
+/// servers are taken from `config.mcp`; audit entries are dropped.
+pub(crate) fn test_state(config: Config, router: ProviderRouter) -> AppState {
+    let db = crate::db::Database::open_in_memory().unwrap();
+    let config = Arc::new(config);
+    let (audit_tx, _audit_rx) = tokio::sync::mpsc::unbounded_channel();
+    let oauth_manager = Arc::new(crate::oauth::OAuthManager::from_config(
+        config.clone(),
+        db.clone(),
+    ));
+    let cost_calculator = Arc::new(CostCalculator::new());
+    AppState {
+        mcp_tools: Arc::new(crate::mcp::client::McpToolServers::new(
+            &config.mcp,
+            reqwest::Client::new(),
+        )),
+        config: config.clone(),
+        config_path: std::path::PathBuf::from("test.toml"),
+        db: db.clone(),
+        router: Arc::new(tokio::sync::RwLock::new(router)),
+        budget: Arc::new(crate::budget::BudgetTracker::new(db.clone())),
+        audit_tx,
+        providers: Arc::new(ProviderFactory::new(
+            db.clone(),
+            oauth_manager.clone(),
+            cost_calculator.clone(),
+            Arc::new(Leader::always()),
+        )),
+        cost_calculator,
+        cache: None,
+        oauth_manager,
+        mcp_sessions: Arc::new(crate::api::mcp::McpSessions::new()),
+        stream_registry: Arc::new(crate::api::resume::StreamRegistry::from_config(
+            &crate::config::CacheConfig::default(),
+        )),
+        request_tail: Arc::new(crate::budget::tail::RequestTail::new()),
+        stream_limiter: Arc::new(crate::budget::streams::StreamLimiter::new()),
+        scheduler: Arc::new(crate::budget::scheduler::FairScheduler::new()),
+        settings: Arc::new(crate::settings::RuntimeSettings::new(config)),
+        login_throttle: Arc::new(crate::auth::throttle::LoginThrottle::new(
+            Default::default(),
+            db,
+        )),
+        notifier: Arc::new(crate::notify::Notifier::disabled()),
+        background: TaskTracker::new(),
+    }
+}
+
+/// A router with one OpenAI-compatible provider at `url` serving `m`.
+pub(crate) async fn litellm_router(url: &str) -> ProviderRouter {
+    use crate::providers::litellm::{LitellmConfig, LitellmProvider};
+    let provider = LitellmProvider::new(LitellmConfig {
+        url: url.to_string(),
+        api_key: None,
+        discover_models: false,
+        models: vec!["m".to_string()],
+        timeout_secs: 30,
+        http: crate::net::HttpClientFactory::default(),
+    })
+    .await
+    .unwrap();
+    let mut router = ProviderRouter::new();
+    router.register(Arc::new(provider));
+    router
+}
+
+/// A member with no team or token scope.
+pub(crate) fn user() -> AuthUser {
+    AuthUser {
+        user_id: "u1".to_string(),
+        name: "alice".to_string(),
+        role: "member".to_string(),
+        reasoning_mode: ReasoningMode::default(),
+        team: None,
+        impersonator: None,
+        key_id: None,
+        scope: None,
+        origin: Default::default(),
+    }
+}
@@ -0,0 +1,85 @@
+use axum::Extension;
+use axum::Json;
+use axum::extract::{Multipart, State};
+
+use crate::AppState;
+use crate::auth::AuthUser;
+use crate::error::AppError;
+use crate::uploads::{self, ImageUpload};
+
+/// POST /v1/uploads/images
+///
+/// Upload an image as `multipart/form-data` with a `file` field. Returns a
+/// `gaud-image://` reference valid for `files.image_ttl_secs`, or with
+/// `format=data_url` the image's data URL without storing it.
+pub async fn upload_image(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    mut multipart: Multipart,
+) -> Result<Json<ImageUpload>, AppError> {
+    let bad_form = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("Invalid multipart body: {e}"))
+    };
+    let max_bytes = match state.config.limits.max_image_mb {
+        0 => uploads::MAX_IMAGE_BYTES,
+        mb => mb as usize * 1024 * 1024,
+    };
+
+    let mut format = None;
+    let mut image = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(bad_form)? {
+        match field.name().unwrap_or_default() {
+            "format" => format = Some(field.text().await.map_err(bad_form)?),
+            "file" => {
+                let mut data = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(bad_form)? {
+                    data.extend_from_slice(&chunk);
+                    // Let prepare() report the size once it is over.
+                    if data.len() > max_bytes {
+                        break;
+                    }
+                }
+                image = Some(data);
+            }
+            _ => {}
+        }
+    }
+
+    let data = image.ok_or_else(|| AppError::BadRequest("Missing 'file' field".into()))?;
+    // Decoding and resizing are CPU-bound; keep them off the runtime.
+    let (mime_type, data) = tokio::task::spawn_blocking(move || uploads::prepare(data, max_bytes))
+        .await
+        .map_err(|e| AppError::Internal(format!("Image processing failed: {e}")))??;
+    let upload = match format.as_deref().unwrap_or("reference") {
+        "reference" => uploads::store(
+            &state.db,
+            &user.user_id,
+            mime_type,
+            &data,
+            state.config.files.image_ttl_secs,
+        )?,
+        "data_url" => ImageUpload {
+            id: None,
+            object: "image.upload",
+            bytes: data.len() as u64,
+            mime_type,
+            url: uploads::data_url(mime_type, &data),
+            expires_at: None,
+        },
+        other => {
+            return Err(AppError::InvalidParam {
+                param: "format".to_string(),
+                message: format!("Expected 'reference' or 'data_url', got '{other}'"),
+            });
+        }
+    };
+
+    tracing::info!(
+        user_id = %user.user_id,
+        image_id = upload.id.as_deref().unwrap_or("-"),
+        bytes = upload.bytes,
+        mime_type,
+        "Image uploaded"
+    );
+    Ok(Json(upload))
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use axum::Extension;
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::chat::{SseMsg, open_chat_stream};
+use crate::auth::AuthUser;
+use crate::budget::Attribution;
+use crate::budget::attribution::tags_from_headers;
+use crate::error::AppError;
+use crate::providers::types::ChatRequest;
+
+/// GET /v1/chat/completions/ws
+///
+/// WebSocket variant of streaming chat completions for clients whose
+/// proxies buffer or mangle SSE. Each text frame from the client is a
+/// [`ChatRequest`]; the server answers with one text frame per SSE event
+/// (the same `chat.completion.chunk` JSON, then `[DONE]`). Requests on one
+/// connection are handled one after another. Errors are sent as an OpenAI
+/// error object and leave the connection open. `X-Gaud-Tags` on the
+/// upgrade request applies to every request on the connection.
+pub async fn chat_completions_ws(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let tags = match tags_from_headers(&headers) {
+        Ok(tags) => tags,
+        Err(e) => return e.into_response(),
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, state, user, tags))
+}
+
+async fn handle_socket(
+    mut socket: WebSocket,
+    state: AppState,
+    user: AuthUser,
+    tags: BTreeMap<String, String>,
+) {
+    while let Some(message) = socket.recv().await {
+        let text = match message {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => break,
+            // Pings are answered by axum; pongs need no reply.
+            Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => continue,
+            Ok(Message::Binary(_)) => {
+                let err = AppError::BadRequest("Expected a text frame".into());
+                if send(&mut socket, err.to_json()).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, "WebSocket receive failed");
+                break;
+            }
+        };
+
+        if let Err(e) = run_request(&mut socket, &state, &user, &tags, text.as_str()).await {
+            tracing::debug!(error = %e, "WebSocket send failed, closing");
+            break;
+        }
+    }
+}
+
+/// Stream one completion over the socket.
+///
+/// Only a failure to write to the socket is returned; request errors are
+/// reported to the client as frames.
+async fn run_request(
+    socket: &mut WebSocket,
+    state: &AppState,
+    user: &AuthUser,
+    tags: &BTreeMap<String, String>,
+    text: &str,
+) -> Result<(), axum::Error> {
+    let request = match parse_request(text) {
+        Ok(r) => r,
+        Err(e) => return send(socket, e.to_json()).await,
+    };
+    let attribution = match Attribution::new(request.user.as_deref(), tags.clone()) {
+        Ok(a) => a,
+        Err(e) => return send(socket, e.to_json()).await,
+    };
+
+    let request_id = Uuid::new_v4().to_string();
+    tracing::info!(
+        request_id = %request_id,
+        user_id = %user.user_id,
+        model = %request.model,
+        "WebSocket chat completion request"
+    );
+
+    let mut stream = match open_chat_stream(
+        state.clone(),
+        user.clone(),
+        request,
+        request_id,
+        attribution,
+    )
+    .await
+    {
+        Ok((s, _)) => s,
+        Err(e) => return send(socket, e.to_json()).await,
+    };
+
+    while let Some(msg) = stream.next().await {
+        send(socket, frame_text(msg)).await?;
+    }
+    Ok(())
+}
+
+/// Parse a client frame. Streaming is implied by the transport.
+fn parse_request(text: &str) -> Result<ChatRequest, AppError> {
+    let mut request: ChatRequest = serde_json::from_str(text)?;
+    request.stream = true;
+    Ok(request)
+}
+
+/// Frame payload for a stream message, identical to the SSE `data:` field.
+fn frame_text(msg: SseMsg) -> String {
+    match msg {
+        SseMsg::Data(payload) => payload,
+        SseMsg::Done => "[DONE]".to_string(),
+    }
+}
+
+async fn send(socket: &mut WebSocket, text: String) -> Result<(), axum::Error> {
+    socket.send(Message::Text(text.into())).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_forces_stream() {
+        let request = parse_request(r#"{"model": "m", "messages": [], "stream": false}"#).unwrap();
+        assert!(request.stream);
+
+        let err = parse_request("not json").unwrap_err();
+        let body: serde_json::Value = serde_json::from_str(&err.to_json()).unwrap();
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+    }
+
+    #[test]
+    fn test_frame_text_matches_sse_payload() {
+        assert_eq!(frame_text(SseMsg::Data("{\"a\":1}".into())), "{\"a\":1}");
+        assert_eq!(frame_text(SseMsg::Done), "[DONE]");
+    }
+}
@@ -0,0 +1,258 @@
+//! Append-only security log of admin actions.
+//!
+//! Every admin mutation (users, keys, budgets, quotas, markups, pricing,
+//! settings, cache flushes) is recorded with the acting user, the target,
+//! and the state before and after the change. The
+//! `admin_audit_log` table rejects updates and deletes through triggers, so
+//! entries can only be added.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthUser;
+use crate::db::Database;
+use crate::error::AppError;
+
+/// One recorded admin action.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub created_at: String,
+    pub actor_id: String,
+    pub actor_name: String,
+    /// What was done, e.g. `user.create` or `settings.update`.
+    pub action: String,
+    /// What it was done to: a user, key, team or model ID, or a setting key.
+    pub target: String,
+    /// State before the change, when there was one.
+    pub before: Option<serde_json::Value>,
+    /// State after the change, when there is one.
+    pub after: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditLogQuery {
+    pub actor: Option<String>,
+    /// Exact action, or a prefix ending in `.` (e.g. `user.`).
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(default)]
+    pub page: Option<u32>,
+    #[serde(default)]
+    pub per_page: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditLogPage {
+    pub data: Vec<AuditLogEntry>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+}
+
+/// Record an admin action. A failure to write is logged rather than
+/// returned: the action itself has already happened.
+pub fn record(
+    db: &Database,
+    actor: &AuthUser,
+    action: &str,
+    target: &str,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+) {
+    let result = db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO admin_audit_log (actor_id, actor_name, action, target, before_json, after_json) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                actor.user_id,
+                actor.name,
+                action,
+                target,
+                before.map(|v| v.to_string()),
+                after.map(|v| v.to_string()),
+            ],
+        )
+    });
+    match result {
+        Ok(_) => tracing::info!(actor = %actor.name, action, target, "Admin action"),
+        Err(e) => tracing::error!(error = %e, action, target, "Failed to record admin action"),
+    }
+}
+
+/// A page of the log, newest first.
+pub fn query(db: &Database, params: &AuditLogQuery) -> Result<AuditLogPage, AppError> {
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 500);
+    let offset = (page - 1) * per_page;
+
+    let mut where_clauses: Vec<String> = Vec::new();
+    let mut bind_values: Vec<String> = Vec::new();
+    if let Some(actor) = params.actor.as_deref().filter(|v| !v.is_empty()) {
+        bind_values.push(actor.to_string());
+        let n = bind_values.len();
+        where_clauses.push(format!("(actor_id = ?{n} OR actor_name = ?{n})"));
+    }
+    if let Some(action) = params.action.as_deref().filter(|v| !v.is_empty()) {
+        if let Some(prefix) = action.strip_suffix('.') {
+            bind_values.push(format!("{prefix}.%"));
+            where_clauses.push(format!("action LIKE ?{}", bind_values.len()));
+        } else {
+            bind_values.push(action.to_string());
+            where_clauses.push(format!("action = ?{}", bind_values.len()));
+        }
+    }
+    if let Some(target) = params.target.as_deref().filter(|v| !v.is_empty()) {
+        bind_values.push(target.to_string());
+        where_clauses.push(format!("target = ?{}", bind_values.len()));
+    }
+    if let Some(from) = params.from.as_deref().filter(|v| !v.is_empty()) {
+        bind_values.push(from.to_string());
+        where_clauses.push(format!("created_at >= ?{}", bind_values.len()));
+    }
+    if let Some(to) = params.to.as_deref().filter(|v| !v.is_empty()) {
+        // A bare date covers the whole day.
+        let to = if to.len() == 10 {
+            format!("{to} 23:59:59")
+        } else {
+            to.to_string()
+        };
+        bind_values.push(to);
+        where_clauses.push(format!("created_at <= ?{}", bind_values.len()));
+    }
+    let where_sql = if where_clauses.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", where_clauses.join(" AND "))
+    };
+
+    let count_sql = format!("SELECT COUNT(*) FROM admin_audit_log {where_sql}");
+    let data_sql = format!(
+        "SELECT id, created_at, actor_id, actor_name, action, target, before_json, after_json \
+         FROM admin_audit_log {where_sql} ORDER BY id DESC LIMIT ?{} OFFSET ?{}",
+        bind_values.len() + 1,
+        bind_values.len() + 2,
+    );
+
+    let (total, data) = db.with_conn(|conn| {
+        let binds: Vec<&dyn rusqlite::ToSql> = bind_values
+            .iter()
+            .map(|v| v as &dyn rusqlite::ToSql)
+            .collect();
+        let total: i64 = conn.query_row(&count_sql, binds.as_slice(), |row| row.get(0))?;
+
+        let limit = per_page as i64;
+        let offset = offset as i64;
+        let mut data_binds = binds;
+        data_binds.push(&limit);
+        data_binds.push(&offset);
+        let mut stmt = conn.prepare(&data_sql)?;
+        let rows = stmt.query_map(data_binds.as_slice(), |row| {
+            Ok(AuditLogEntry {
+                id: row.get(0)?,
+                created_at: row.get(1)?,
+                actor_id: row.get(2)?,
+                actor_name: row.get(3)?,
+                action: row.get(4)?,
+                target: row.get(5)?,
+                before: parse_json(row.get(6)?),
+                after: parse_json(row.get(7)?),
+            })
+        })?;
+        Ok((total, rows.collect::<Result<Vec<_>, _>>()?))
+    })?;
+
+    Ok(AuditLogPage {
+        data,
+        page,
+        per_page,
+        total,
+    })
+}
+
+fn parse_json(raw: Option<String>) -> Option<serde_json::Value> {
+    raw.and_then(|s| serde_json::from_str(&s).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin() -> AuthUser {
+        AuthUser {
+            user_id: "usr_admin".to_string(),
+            name: "root".to_string(),
+            role: "admin".to_string(),
+            reasoning_mode: Default::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let db = Database::open_in_memory().unwrap();
+        record(
+            &db,
+            &admin(),
+            "user.create",
+            "usr_1",
+            None,
+            Some(serde_json::json!({"name": "alice"})),
+        );
+        record(
+            &db,
+            &admin(),
+            "budget.update",
+            "usr_1",
+            Some(serde_json::json!({"monthly_limit": 10.0})),
+            Some(serde_json::json!({"monthly_limit": 20.0})),
+        );
+        record(&db, &admin(), "cache.flush", "*", None, None);
+
+        let all = query(&db, &AuditLogQuery::default()).unwrap();
+        assert_eq!(all.total, 3);
+        // Newest first.
+        assert_eq!(all.data[0].action, "cache.flush");
+        assert_eq!(all.data[1].before.as_ref().unwrap()["monthly_limit"], 10.0);
+
+        let users = query(
+            &db,
+            &AuditLogQuery {
+                action: Some("user.".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(users.total, 1);
+        assert_eq!(users.data[0].after.as_ref().unwrap()["name"], "alice");
+
+        let by_actor = query(
+            &db,
+            &AuditLogQuery {
+                actor: Some("root".to_string()),
+                target: Some("usr_1".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(by_actor.total, 2);
+    }
+
+    #[test]
+    fn test_log_is_append_only() {
+        let db = Database::open_in_memory().unwrap();
+        record(&db, &admin(), "cache.flush", "*", None, None);
+        let update =
+            db.with_conn(|conn| conn.execute("UPDATE admin_audit_log SET action = 'nothing'", []));
+        assert!(update.is_err());
+        let delete = db.with_conn(|conn| conn.execute("DELETE FROM admin_audit_log", []));
+        assert!(delete.is_err());
+        assert_eq!(query(&db, &AuditLogQuery::default()).unwrap().total, 1);
+    }
+}
@@ -0,0 +1,336 @@
+//! Export and import of users, roles, teams, budgets and API key metadata.
+//!
+//! An export is a JSON document that can be kept in version control or
+//! carried to another deployment. It never contains key secrets or hashes,
+//! so keys cannot be recreated from it: an import updates the metadata of
+//! keys that already exist and reports the rest, which must be reissued.
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth::users;
+use crate::budget::tracker::BudgetTracker;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::providers::reasoning::ReasoningMode;
+
+/// Format version written by [`export`] and accepted by [`import`].
+pub const EXPORT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserExport {
+    pub version: u32,
+    pub users: Vec<ExportedUser>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedUser {
+    /// Informational; users are matched by name on import.
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub role: String,
+    #[serde(default)]
+    pub team: Option<String>,
+    /// Service account owned by `team`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub service_account: bool,
+    #[serde(default)]
+    pub budget: Option<ExportedBudget>,
+    #[serde(default)]
+    pub keys: Vec<ExportedKey>,
+}
+
+/// Budget limits. Usage counters are not exported.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ExportedBudget {
+    #[serde(default)]
+    pub monthly_limit: Option<f64>,
+    #[serde(default)]
+    pub daily_limit: Option<f64>,
+    #[serde(default)]
+    pub monthly_premium_limit: Option<f64>,
+    #[serde(default)]
+    pub daily_premium_limit: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedKey {
+    pub id: String,
+    #[serde(default)]
+    pub key_prefix: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub reasoning_mode: ReasoningMode,
+}
+
+/// What an import changed.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub users_created: usize,
+    pub users_updated: usize,
+    pub budgets_set: usize,
+    pub keys_updated: usize,
+    /// IDs of exported keys with no matching key for the user here. Their
+    /// secrets are not part of an export, so they must be reissued.
+    pub keys_not_found: Vec<String>,
+}
+
+/// Export every user with their budget limits and key metadata.
+pub fn export(db: &Database, budget: &BudgetTracker) -> Result<UserExport, AppError> {
+    let mut exported = Vec::new();
+    for user in users::list_users(db)? {
+        let budget = budget.get_budget(&user.id)?.map(|b| ExportedBudget {
+            monthly_limit: b.monthly_limit,
+            daily_limit: b.daily_limit,
+            monthly_premium_limit: b.monthly_premium_limit,
+            daily_premium_limit: b.daily_premium_limit,
+        });
+        let keys = users::list_api_keys(db, &user.id)?
+            .into_iter()
+            .map(|k| ExportedKey {
+                id: k.id,
+                key_prefix: k.key_prefix,
+                label: k.label,
+                reasoning_mode: k.reasoning_mode,
+            })
+            .collect();
+        exported.push(ExportedUser {
+            id: Some(user.id),
+            name: user.name,
+            role: user.role,
+            team: user.team,
+            service_account: user.service_account,
+            budget,
+            keys,
+        });
+    }
+    Ok(UserExport {
+        version: EXPORT_VERSION,
+        users: exported,
+    })
+}
+
+/// Apply an export: create missing users, update the role and team of
+/// existing ones (matched by name), set budget limits, and update the
+/// reasoning mode of keys that exist. Users and keys not in the document
+/// are left alone.
+pub fn import(
+    db: &Database,
+    budget: &BudgetTracker,
+    doc: &UserExport,
+) -> Result<ImportSummary, AppError> {
+    validate(doc)?;
+
+    let mut summary = ImportSummary::default();
+    for entry in &doc.users {
+        let team = entry.team.as_deref().filter(|t| !t.is_empty());
+        let user = match users::get_user_by_name(db, &entry.name) {
+            Ok(existing) => {
+                if existing.role != entry.role {
+                    users::set_user_role(db, &existing.id, &entry.role)?;
+                }
+                if existing.team.as_deref() != team {
+                    users::set_user_team(db, &existing.id, team)?;
+                }
+                summary.users_updated += 1;
+                existing
+            }
+            Err(AppError::NotFound(_)) => {
+                let created = match team {
+                    Some(team) if entry.service_account => {
+                        users::create_service_account(db, &entry.name, team)?
+                    }
+                    _ => {
+                        let created = users::create_user(db, &entry.name, &entry.role)?;
+                        if team.is_some() {
+                            users::set_user_team(db, &created.id, team)?;
+                        }
+                        created
+                    }
+                };
+                summary.users_created += 1;
+                created
+            }
+            Err(e) => return Err(e),
+        };
+
+        if let Some(b) = entry.budget {
+            budget.set_budget(&user.id, b.monthly_limit, b.daily_limit)?;
+            budget.set_premium_limits(&user.id, b.monthly_premium_limit, b.daily_premium_limit)?;
+            summary.budgets_set += 1;
+        }
+
+        let existing_keys = users::list_api_keys(db, &user.id)?;
+        for key in &entry.keys {
+            match existing_keys.iter().find(|k| k.id == key.id) {
+                Some(found) => {
+                    if found.reasoning_mode != key.reasoning_mode {
+                        users::set_api_key_reasoning_mode(db, &key.id, key.reasoning_mode)?;
+                    }
+                    summary.keys_updated += 1;
+                }
+                None => summary.keys_not_found.push(key.id.clone()),
+            }
+        }
+    }
+
+    tracing::info!(
+        created = summary.users_created,
+        updated = summary.users_updated,
+        "Users imported"
+    );
+    Ok(summary)
+}
+
+/// Reject the whole document before anything is written.
+fn validate(doc: &UserExport) -> Result<(), AppError> {
+    if doc.version != EXPORT_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "Unsupported export version {} (expected {EXPORT_VERSION})",
+            doc.version
+        )));
+    }
+    let mut names = std::collections::HashSet::new();
+    for user in &doc.users {
+        if user.name.trim().is_empty() {
+            return Err(AppError::BadRequest(
+                "User name must not be empty".to_string(),
+            ));
+        }
+        if user.role != "admin" && user.role != "member" {
+            return Err(AppError::BadRequest(format!(
+                "Invalid role '{}' for user '{}': must be 'admin' or 'member'",
+                user.role, user.name
+            )));
+        }
+        if user.service_account
+            && (user.role != "member" || user.team.as_deref().is_none_or(str::is_empty))
+        {
+            return Err(AppError::BadRequest(format!(
+                "Service account '{}' must be a member with a team",
+                user.name
+            )));
+        }
+        if !names.insert(user.name.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "User '{}' appears more than once",
+                user.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Database, BudgetTracker) {
+        let db = Database::open_in_memory().unwrap();
+        let budget = BudgetTracker::new(db.clone());
+        (db, budget)
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let (db, budget) = setup();
+        let alice = users::create_user(&db, "alice", "member").unwrap();
+        users::set_user_team(&db, &alice.id, Some("research")).unwrap();
+        budget.set_budget(&alice.id, Some(100.0), None).unwrap();
+        let key = users::create_api_key(&db, &alice.id, "ci").unwrap();
+        users::create_service_account(&db, "deploy-bot", "research").unwrap();
+
+        let doc = export(&db, &budget).unwrap();
+        let json = serde_json::to_string(&doc).unwrap();
+        assert!(!json.contains(&key.plaintext));
+        assert!(!json.contains("key_hash"));
+
+        let (db2, budget2) = setup();
+        let summary = import(&db2, &budget2, &serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(summary.users_created, 2);
+        assert_eq!(summary.budgets_set, 1);
+        assert_eq!(summary.keys_not_found, vec![key.info.id]);
+
+        let imported = users::get_user_by_name(&db2, "alice").unwrap();
+        assert_eq!(imported.team.as_deref(), Some("research"));
+        let b = budget2.get_budget(&imported.id).unwrap().unwrap();
+        assert_eq!(b.monthly_limit, Some(100.0));
+        assert!(!imported.service_account);
+        let bot = users::get_user_by_name(&db2, "deploy-bot").unwrap();
+        assert!(bot.service_account);
+        assert_eq!(bot.team.as_deref(), Some("research"));
+    }
+
+    #[test]
+    fn test_import_updates_existing_users_and_keys() {
+        let (db, budget) = setup();
+        let bob = users::create_user(&db, "bob", "member").unwrap();
+        let key = users::create_api_key(&db, &bob.id, "ci").unwrap();
+
+        let doc = UserExport {
+            version: EXPORT_VERSION,
+            users: vec![ExportedUser {
+                id: None,
+                name: "bob".to_string(),
+                role: "admin".to_string(),
+                team: None,
+                budget: None,
+                keys: vec![ExportedKey {
+                    id: key.info.id.clone(),
+                    key_prefix: String::new(),
+                    label: "ci".to_string(),
+                    reasoning_mode: ReasoningMode::Strip,
+                }],
+                service_account: false,
+            }],
+        };
+        let summary = import(&db, &budget, &doc).unwrap();
+        assert_eq!(summary.users_updated, 1);
+        assert_eq!(summary.keys_updated, 1);
+        assert!(summary.keys_not_found.is_empty());
+        assert_eq!(users::get_user(&db, &bob.id).unwrap().role, "admin");
+        assert_eq!(
+            users::get_api_key(&db, &key.info.id)
+                .unwrap()
+                .reasoning_mode,
+            ReasoningMode::Strip
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_documents() {
+        let (db, budget) = setup();
+        let user = |name: &str, role: &str| ExportedUser {
+            id: None,
+            name: name.to_string(),
+            role: role.to_string(),
+            team: None,
+            budget: None,
+            keys: Vec::new(),
+            service_account: false,
+        };
+        let bad_role = UserExport {
+            version: EXPORT_VERSION,
+            users: vec![user("carol", "member"), user("dave", "owner")],
+        };
+        assert!(matches!(
+            import(&db, &budget, &bad_role),
+            Err(AppError::BadRequest(_))
+        ));
+        // Nothing was written.
+        assert!(users::list_users(&db).unwrap().is_empty());
+
+        let duplicate = UserExport {
+            version: EXPORT_VERSION,
+            users: vec![user("carol", "member"), user("carol", "admin")],
+        };
+        assert!(import(&db, &budget, &duplicate).is_err());
+
+        let wrong_version = UserExport {
+            version: 99,
+            users: Vec::new(),
+        };
+        assert!(import(&db, &budget, &wrong_version).is_err());
+    }
+}
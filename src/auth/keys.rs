@@ -1,13 +1,65 @@
+//! API key, session token and recovery code generation and hashing.
+//!
+//! API keys are stored as argon2id hashes in a versioned format:
+//!
+//! - v1: a bare PHC string (`$argon2id$...`) of the key's SHA-256 hex digest,
+//!   with the argon2 crate's default cost.
+//! - v2: `$gaud2` followed by a PHC string of the key itself, with the cost in
+//!   [`KEY_HASH_PARAMS`].
+//!
+//! New keys get the current format. Older hashes, or hashes with a lower
+//! cost, are replaced the next time their key is used ([`needs_rehash`]).
+
 use argon2::password_hash::SaltString;
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use rand::Rng;
 use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 
 const KEY_PREFIX: &str = "sk-prx-";
 const KEY_RANDOM_LEN: usize = 32;
+const SESSION_TOKEN_LEN: usize = 48;
+const RECOVERY_CODE_LEN: usize = 10;
 /// Salt length in bytes (16 bytes = 22 base64 chars, well within argon2 limits).
 const SALT_LEN: usize = 16;
 
+/// Tag in front of the PHC string of v2 hashes.
+const V2_TAG: &str = "$gaud2";
+
+/// Argon2id cost of new key hashes: 46 MiB, one pass, one lane (the OWASP
+/// recommendation of the same strength as the crate default).
+const KEY_HASH_PARAMS: (u32, u32, u32) = (46 * 1024, 1, 1);
+
+/// Format of a stored key hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HashVersion {
+    /// Bare PHC string over the key's SHA-256 hex digest.
+    V1,
+    /// `$gaud2` + PHC string over the key.
+    V2,
+}
+
+impl HashVersion {
+    /// Format used for new hashes.
+    pub const CURRENT: HashVersion = HashVersion::V2;
+
+    /// Split a stored hash into its format and PHC string.
+    fn split(hash: &str) -> (Self, &str) {
+        match hash.strip_prefix(V2_TAG) {
+            Some(phc) => (Self::V2, phc),
+            None => (Self::V1, hash),
+        }
+    }
+
+    /// The bytes hashed for `plaintext` in this format.
+    fn input(self, plaintext: &str) -> Vec<u8> {
+        match self {
+            Self::V1 => sha256_key(plaintext).into_bytes(),
+            Self::V2 => plaintext.as_bytes().to_vec(),
+        }
+    }
+}
+
 /// A newly generated API key containing the raw plaintext and its argon2 hash.
 #[derive(Debug)]
 pub struct GeneratedKey {
@@ -26,6 +78,7 @@ pub fn generate_api_key() -> Result<GeneratedKey, argon2::password_hash::Error>
     let random_part = generate_random_alphanumeric(KEY_RANDOM_LEN);
     let plaintext = format!("{KEY_PREFIX}{random_part}");
     let prefix = format!("{KEY_PREFIX}{}...", &random_part[..8]);
+    debug_assert_eq!(display_prefix(&plaintext).as_deref(), Some(prefix.as_str()));
     let hash = hash_key(&plaintext)?;
 
     Ok(GeneratedKey {
@@ -35,17 +88,27 @@ pub fn generate_api_key() -> Result<GeneratedKey, argon2::password_hash::Error>
     })
 }
 
-/// Hash a plaintext API key using argon2id.
-///
-/// We first SHA-256 the key to produce a fixed-length input for argon2,
-/// which avoids issues with variable-length passwords and keeps the argon2
-/// input consistent.
+/// The display prefix of a plaintext key, in the form stored with it
+/// (`sk-prx-a1b2c3d4...`), or `None` for strings that are not gaud keys.
+pub fn display_prefix(plaintext: &str) -> Option<String> {
+    let random_part = plaintext.strip_prefix(KEY_PREFIX)?;
+    let head = random_part.get(..8)?;
+    Some(format!("{KEY_PREFIX}{head}..."))
+}
+
+/// Hash a plaintext API key with argon2id, in the current format.
 pub fn hash_key(plaintext: &str) -> Result<String, argon2::password_hash::Error> {
-    let sha_digest = sha256_key(plaintext);
     let salt = generate_salt()?;
-    let argon2 = Argon2::default();
-    let hash = argon2.hash_password(sha_digest.as_bytes(), &salt)?;
-    Ok(hash.to_string())
+    let input = HashVersion::CURRENT.input(plaintext);
+    let hash = key_hasher()?.hash_password(&input, &salt)?;
+    Ok(format!("{V2_TAG}{hash}"))
+}
+
+/// Argon2id with the cost of new key hashes.
+fn key_hasher() -> Result<Argon2<'static>, argon2::password_hash::Error> {
+    let (m_cost, t_cost, p_cost) = KEY_HASH_PARAMS;
+    let params = Params::new(m_cost, t_cost, p_cost, None)?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
 }
 
 /// Generate a random salt string for argon2 using `rand`.
@@ -57,15 +120,69 @@ fn generate_salt() -> Result<SaltString, argon2::password_hash::Error> {
     SaltString::encode_b64(&salt_bytes)
 }
 
-/// Verify a plaintext API key against an argon2 hash.
+/// Verify a plaintext API key against a stored hash of any format. The
+/// cost is read from the hash and the digests are compared in constant time.
 pub fn verify_key(plaintext: &str, hash: &str) -> Result<bool, argon2::password_hash::Error> {
-    let sha_digest = sha256_key(plaintext);
-    let parsed_hash = PasswordHash::new(hash)?;
+    let (version, phc) = HashVersion::split(hash);
+    let parsed_hash = PasswordHash::new(phc)?;
     Ok(Argon2::default()
-        .verify_password(sha_digest.as_bytes(), &parsed_hash)
+        .verify_password(&version.input(plaintext), &parsed_hash)
         .is_ok())
 }
 
+/// Whether `hash` is a stored key hash that [`verify_key`] can check.
+pub fn is_key_hash(hash: &str) -> bool {
+    PasswordHash::new(HashVersion::split(hash).1).is_ok()
+}
+
+/// Whether `hash` should be replaced by a fresh [`hash_key`]: it is in an
+/// older format, or was made with a lower cost than new hashes get.
+pub fn needs_rehash(hash: &str) -> bool {
+    let (version, phc) = HashVersion::split(hash);
+    if version < HashVersion::CURRENT {
+        return true;
+    }
+    let Ok(params) = PasswordHash::new(phc).and_then(|h| Params::try_from(&h)) else {
+        return false;
+    };
+    let (m_cost, t_cost, p_cost) = KEY_HASH_PARAMS;
+    params.m_cost() < m_cost || params.t_cost() < t_cost || params.p_cost() < p_cost
+}
+
+/// Compare two secrets without leaking where they differ through timing.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Generate a web UI session token. Session tokens are random enough that
+/// they are stored as a plain SHA-256 digest ([`hash_session_token`]).
+pub fn generate_session_token() -> String {
+    generate_random_alphanumeric(SESSION_TOKEN_LEN)
+}
+
+/// Digest under which a session token is stored.
+pub fn hash_session_token(token: &str) -> String {
+    sha256_key(token)
+}
+
+/// Generate a two-factor recovery code, `xxxxx-xxxxx` in lowercase
+/// alphanumerics. Stored as a plain SHA-256 digest ([`hash_recovery_code`]).
+pub fn generate_recovery_code() -> String {
+    let raw = generate_random_alphanumeric(RECOVERY_CODE_LEN).to_ascii_lowercase();
+    format!("{}-{}", &raw[..5], &raw[5..])
+}
+
+/// Digest under which a recovery code is stored. Case and dashes are
+/// ignored, so codes can be typed loosely.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    sha256_key(&normalized)
+}
+
 /// SHA-256 digest of a key, returned as a hex string.
 fn sha256_key(key: &str) -> String {
     let mut hasher = Sha256::new();
@@ -73,6 +190,15 @@ fn sha256_key(key: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
+/// A v1 hash of `plaintext`, as stored before versioned formats.
+#[cfg(test)]
+pub(crate) fn v1_hash(plaintext: &str) -> String {
+    Argon2::default()
+        .hash_password(sha256_key(plaintext).as_bytes(), &generate_salt().unwrap())
+        .unwrap()
+        .to_string()
+}
+
 /// Generate a random alphanumeric string of the given length.
 fn generate_random_alphanumeric(len: usize) -> String {
     const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
@@ -120,10 +246,57 @@ mod tests {
         assert!(!verify_key("sk-prx-wrongkey1234567890123456789", &hash).unwrap());
     }
 
+    #[test]
+    fn test_v1_hashes_verify_and_need_rehash() {
+        let plaintext = "sk-prx-testkey12345678901234567890ab";
+        let v1 = v1_hash(plaintext);
+        assert!(v1.starts_with("$argon2id$"));
+        assert!(is_key_hash(&v1));
+        assert!(verify_key(plaintext, &v1).unwrap());
+        assert!(!verify_key("sk-prx-wrongkey1234567890123456789", &v1).unwrap());
+        assert!(needs_rehash(&v1));
+
+        let v2 = hash_key(plaintext).unwrap();
+        assert!(v2.starts_with("$gaud2$argon2id$"));
+        assert!(is_key_hash(&v2));
+        assert!(!needs_rehash(&v2));
+        // A v2 hash is not a v1 hash of the same key.
+        assert!(!verify_key(plaintext, v2.strip_prefix(V2_TAG).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_weaker_v2_hash_needs_rehash() {
+        let params = Params::new(8 * 1024, 1, 1, None).unwrap();
+        let weak = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(b"sk-prx-k", &generate_salt().unwrap())
+            .unwrap()
+            .to_string();
+        let weak = format!("{V2_TAG}{weak}");
+        assert!(verify_key("sk-prx-k", &weak).unwrap());
+        assert!(needs_rehash(&weak));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+
     #[test]
     fn test_alphanumeric_only() {
         let key = generate_api_key().unwrap();
         let random_part = &key.plaintext[KEY_PREFIX.len()..];
         assert!(random_part.chars().all(|c| c.is_ascii_alphanumeric()));
     }
+
+    #[test]
+    fn test_recovery_code_hash_ignores_case_and_dashes() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), RECOVERY_CODE_LEN + 1);
+        assert_eq!(
+            hash_recovery_code(&code),
+            hash_recovery_code(&code.to_uppercase().replace('-', ""))
+        );
+    }
 }
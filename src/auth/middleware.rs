@@ -1,54 +1,86 @@
-use axum::extract::{Request, State};
+use axum::extract::{ConnectInfo, Request, State};
 use axum::middleware::Next;
 use axum::response::Response;
 
 use crate::AppState;
-use crate::auth::AuthUser;
+use crate::api::health;
+use crate::auth::sessions;
+use crate::auth::throttle;
+use crate::auth::tokens;
 use crate::auth::users;
-use crate::error::AppError;
+use crate::auth::{AuthUser, RequestOrigin};
+use crate::error::{self, AppError};
+use crate::providers::reasoning::ReasoningMode;
+use crate::tls::TlsPeer;
+
+/// Request header an admin sets to act as another user.
+pub const ON_BEHALF_OF_HEADER: &str = "x-gaud-on-behalf-of";
+
+/// Request header asking for the upstream detail of provider errors.
+pub const DEBUG_ERRORS_HEADER: &str = "x-gaud-debug-errors";
 
 /// Axum middleware that extracts a Bearer token from the Authorization header,
 /// validates it against the database, and injects an `AuthUser` into request
-/// extensions.
+/// extensions. Without an Authorization header, the key may be passed the
+/// way Gemini SDKs do, in `x-goog-api-key` or the `key` query parameter, or
+/// the way Anthropic SDKs do, in `x-api-key`.
+///
+/// An admin may send `X-Gaud-On-Behalf-Of: <user_id>` to be treated as that
+/// user, with their budget, quotas and routing; see [`on_behalf_of`].
 ///
-/// Supports three modes:
+/// Supports four modes:
 /// 1. Auth disabled: injects a synthetic anonymous admin user.
-/// 2. TLS client cert auth: checks a header set by a TLS-terminating proxy.
-/// 3. Bearer token auth: validates an API key from the Authorization header.
+/// 2. TLS client cert auth: uses the certificate verified by the built-in TLS
+///    listener, or a header set by a TLS-terminating proxy.
+/// 3. Web UI session: a session cookie, when no API key is sent.
+/// 4. Bearer token auth: validates an API key from the Authorization header,
+///    or a scoped token (`sk-tmp-...`), which only works on the endpoints
+///    [`tokens::allows_path`] lists.
 pub async fn require_auth(
     State(state): State<AppState>,
     mut request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
+    // The plain health check is public; only its detailed report needs a user.
+    if request.uri().path() == "/health" && !health::wants_detail(request.uri()) {
+        return Ok(next.run(request).await);
+    }
+
     // 1. Auth disabled -> anonymous admin
     if !state.config.auth.enabled {
         let anon = AuthUser {
             user_id: "anonymous".to_string(),
             name: "anonymous".to_string(),
             role: "admin".to_string(),
+            reasoning_mode: ReasoningMode::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin: Default::default(),
         };
+        let anon = with_origin(&state, &request, anon);
         request.extensions_mut().insert(anon);
         return Ok(next.run(request).await);
     }
 
     // 2. TLS client cert auth
-    if state.config.auth.tls_client_cert.enabled {
-        let header_name = state.config.auth.tls_client_cert.effective_header();
-        let cert_cn = request
-            .headers()
-            .get(header_name)
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty());
-
+    if let Some(cert_cn) = client_cert_cn(&state, &request) {
+        let tls = &state.config.auth.tls_client_cert;
         if let Some(cn) = cert_cn {
             // Look up user by name
-            match users::get_user_by_name(&state.db, &cn) {
+            match users::get_user_by_name(&state.db, tls.user_for_cn(&cn)) {
                 Ok(user) => {
                     let auth_user = AuthUser {
                         user_id: user.id.clone(),
                         name: user.name.clone(),
                         role: user.role.clone(),
+                        reasoning_mode: ReasoningMode::default(),
+                        team: user.team.clone(),
+                        impersonator: None,
+                        key_id: None,
+                        scope: None,
+                        origin: Default::default(),
                     };
                     tracing::debug!(
                         user_id = %auth_user.user_id,
@@ -56,11 +88,13 @@ pub async fn require_auth(
                         role = %auth_user.role,
                         "Authenticated via TLS client cert"
                     );
+                    let auth_user = on_behalf_of(&state, &request, auth_user)?;
+                    let auth_user = with_origin(&state, &request, auth_user);
                     request.extensions_mut().insert(auth_user);
                     return Ok(next.run(request).await);
                 }
                 Err(_) => {
-                    if state.config.auth.tls_client_cert.require_cert {
+                    if tls.require_cert {
                         return Err(AppError::Unauthorized(format!(
                             "Unknown client certificate CN: {cn}"
                         )));
@@ -68,16 +102,52 @@ pub async fn require_auth(
                     // Fall through to bearer token auth
                 }
             }
-        } else if state.config.auth.tls_client_cert.require_cert {
-            return Err(AppError::Unauthorized(format!(
-                "Client certificate required (header: {header_name})"
-            )));
+        } else if tls.require_cert {
+            return Err(AppError::Unauthorized(
+                "Client certificate required".to_string(),
+            ));
         }
     }
 
-    // 3. Bearer token auth (existing logic)
-    let token = extract_bearer_token(&request)?;
-    let auth_user = users::validate_api_key(&state.db, &token)?;
+    // 3. Web UI session cookie, only when no API key was sent
+    let has_api_key = request
+        .headers()
+        .contains_key(axum::http::header::AUTHORIZATION)
+        || sdk_api_key(&request).is_some();
+    let session = if has_api_key {
+        None
+    } else {
+        sessions::session_token(request.headers())
+    };
+
+    // 4. Bearer token auth, throttled against brute force
+    let auth_user = match session {
+        Some(token) => sessions::validate_session(&state.db, &token)?,
+        None => {
+            let token = extract_bearer_token(&request)?;
+            if tokens::is_scoped_token(&token) {
+                let auth_user = tokens::validate(&state.db, &token)?;
+                if !tokens::allows_path(request.uri().path()) {
+                    return Err(AppError::Forbidden(
+                        "Scoped tokens can only be used for chat completions, responses, messages and models"
+                            .to_string(),
+                    ));
+                }
+                let auth_user = with_origin(&state, &request, auth_user);
+                request.extensions_mut().insert(auth_user);
+                return Ok(next.run(request).await);
+            }
+            let ip = throttle::client_ip(
+                request.headers(),
+                request.extensions(),
+                &state.config.auth.login_throttle,
+            );
+            let (_, user) = state
+                .login_throttle
+                .validate_api_key(ip, &token, "api_key")?;
+            user
+        }
+    };
 
     tracing::debug!(
         user_id = %auth_user.user_id,
@@ -86,10 +156,115 @@ pub async fn require_auth(
         "Authenticated request"
     );
 
+    let auth_user = on_behalf_of(&state, &request, auth_user)?;
+    let auth_user = with_origin(&state, &request, auth_user);
     request.extensions_mut().insert(auth_user);
     Ok(next.run(request).await)
 }
 
+/// Longest user agent recorded; longer ones are cut.
+const MAX_USER_AGENT_LEN: usize = 512;
+
+/// Record where `request` came from on `auth_user`, for its usage log
+/// entries. The client IP is taken as for login throttling
+/// ([`throttle::client_ip`]).
+fn with_origin(state: &AppState, request: &Request, mut auth_user: AuthUser) -> AuthUser {
+    let client_ip = throttle::client_ip(
+        request.headers(),
+        request.extensions(),
+        &state.config.auth.login_throttle,
+    );
+    let user_agent = request
+        .headers()
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| v.chars().take(MAX_USER_AGENT_LEN).collect());
+    auth_user.origin = RequestOrigin {
+        client_ip: client_ip.map(|ip| ip.to_string()),
+        user_agent,
+        path: Some(request.uri().path().to_string()),
+    };
+    auth_user
+}
+
+/// Apply `X-Gaud-On-Behalf-Of`: an admin's request is served as the named
+/// user, so support staff can reproduce that user's routing and budget
+/// behavior. The admin is kept as `impersonator`, which flags the request's
+/// usage log entries. Non-admins sending the header are refused.
+fn on_behalf_of(
+    state: &AppState,
+    request: &Request,
+    auth_user: AuthUser,
+) -> Result<AuthUser, AppError> {
+    let Some(value) = request.headers().get(ON_BEHALF_OF_HEADER) else {
+        return Ok(auth_user);
+    };
+    if !auth_user.is_admin() {
+        return Err(AppError::Forbidden(
+            "X-Gaud-On-Behalf-Of requires the admin role".to_string(),
+        ));
+    }
+    let target_id = value
+        .to_str()
+        .map(str::trim)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::BadRequest("X-Gaud-On-Behalf-Of must be a user ID".to_string()))?;
+    let target = users::get_user(&state.db, target_id).map_err(|_| {
+        AppError::BadRequest(format!(
+            "X-Gaud-On-Behalf-Of names unknown user '{target_id}'"
+        ))
+    })?;
+
+    tracing::info!(
+        admin_id = %auth_user.user_id,
+        admin = %auth_user.name,
+        user_id = %target.id,
+        user = %target.name,
+        path = %request.uri().path(),
+        "Admin request on behalf of user"
+    );
+    Ok(AuthUser {
+        user_id: target.id,
+        name: target.name,
+        role: target.role,
+        // The policy belongs to the credential, which is the admin's.
+        reasoning_mode: auth_user.reasoning_mode,
+        team: target.team,
+        impersonator: Some(auth_user.user_id),
+        key_id: auth_user.key_id,
+        scope: None,
+        origin: Default::default(),
+    })
+}
+
+/// Common name of the client certificate for this request.
+///
+/// Returns `None` when client cert auth does not apply to the connection,
+/// and `Some(None)` when it applies but no certificate was presented. With
+/// built-in TLS the listener has already verified the certificate and the
+/// header is ignored, since clients could set it themselves.
+fn client_cert_cn(state: &AppState, request: &Request) -> Option<Option<String>> {
+    let tls = &state.config.auth.tls_client_cert;
+    if !tls.enabled {
+        return None;
+    }
+
+    if let Some(ConnectInfo(peer)) = request.extensions().get::<ConnectInfo<TlsPeer>>() {
+        return peer.client_auth.then(|| peer.client_cn.clone());
+    }
+
+    let cn = request
+        .headers()
+        .get(tls.effective_header())
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    Some(cn)
+}
+
 /// Axum middleware that requires the authenticated user to have the admin role.
 ///
 /// Must be applied _after_ `require_auth` so that `AuthUser` is present in
@@ -111,12 +286,54 @@ pub async fn require_admin(request: Request, next: Next) -> Result<Response, App
     Ok(next.run(request).await)
 }
 
-/// Extract the Bearer token from the Authorization header.
-fn extract_bearer_token(request: &Request) -> Result<String, AppError> {
-    let header = request
+/// Axum middleware adding the upstream status and body to provider errors
+/// for callers debugging provider rejections: admins sending
+/// `X-Gaud-Debug-Errors: true`, and requests made with a key that has
+/// `debug_errors` turned on. The body is sanitized of credentials; see
+/// [`error::with_upstream_detail`].
+///
+/// Must be applied _after_ `require_auth` so that `AuthUser` is present in
+/// request extensions.
+pub async fn debug_errors(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let user = request.extensions().get::<AuthUser>().cloned();
+    let requested = request
         .headers()
-        .get(axum::http::header::AUTHORIZATION)
-        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
+        .get(DEBUG_ERRORS_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| matches!(v.trim(), "1" | "true"));
+
+    let response = next.run(request).await;
+    if response
+        .extensions()
+        .get::<error::UpstreamError>()
+        .is_none()
+    {
+        return response;
+    }
+    let Some(user) = user else {
+        return response;
+    };
+    // Impersonating admins keep their own privileges here.
+    let admin = user.is_admin() || user.impersonator.is_some();
+    let allowed = (requested && admin)
+        || user
+            .key_id
+            .as_deref()
+            .is_some_and(|id| users::api_key_debug_errors(&state.db, id).unwrap_or(false));
+    if allowed {
+        error::with_upstream_detail(response).await
+    } else {
+        response
+    }
+}
+
+/// Extract the Bearer token from the Authorization header, falling back to
+/// the API key headers and query parameter of the Gemini and Anthropic SDKs.
+fn extract_bearer_token(request: &Request) -> Result<String, AppError> {
+    let Some(header) = request.headers().get(axum::http::header::AUTHORIZATION) else {
+        return sdk_api_key(request)
+            .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()));
+    };
 
     let value = header
         .to_str()
@@ -136,6 +353,21 @@ fn extract_bearer_token(request: &Request) -> Result<String, AppError> {
     Ok(token.to_string())
 }
 
+/// API key sent in `x-goog-api-key`, `x-api-key` or `?key=`.
+fn sdk_api_key(request: &Request) -> Option<String> {
+    let from_header = ["x-goog-api-key", "x-api-key"]
+        .iter()
+        .find_map(|name| request.headers().get(*name))
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string());
+    let from_query = || {
+        url::form_urlencoded::parse(request.uri().query()?.as_bytes())
+            .find(|(k, _)| k == "key")
+            .map(|(_, v)| v.into_owned())
+    };
+    from_header.or_else(from_query).filter(|k| !k.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,18 +389,46 @@ mod tests {
         let (audit_tx, _audit_rx) = tokio::sync::mpsc::unbounded_channel();
         let budget = crate::budget::BudgetTracker::new(db.clone());
         let router = crate::providers::router::ProviderRouter::new();
-        let oauth_manager = crate::oauth::OAuthManager::from_config(config_arc.clone(), db.clone());
+        let oauth_manager = std::sync::Arc::new(crate::oauth::OAuthManager::from_config(
+            config_arc.clone(),
+            db.clone(),
+        ));
+        let cost_calculator = std::sync::Arc::new(crate::providers::cost::CostCalculator::new());
 
         AppState {
-            config: config_arc,
+            config: config_arc.clone(),
             config_path: std::path::PathBuf::from("test.toml"),
-            db,
+            db: db.clone(),
             router: std::sync::Arc::new(tokio::sync::RwLock::new(router)),
             budget: std::sync::Arc::new(budget),
             audit_tx,
-            cost_calculator: std::sync::Arc::new(crate::providers::cost::CostCalculator::new()),
+            providers: std::sync::Arc::new(crate::providers::factory::ProviderFactory::new(
+                db.clone(),
+                oauth_manager.clone(),
+                cost_calculator.clone(),
+                std::sync::Arc::new(crate::leader::Leader::always()),
+            )),
+            cost_calculator,
             cache: None,
-            oauth_manager: std::sync::Arc::new(oauth_manager),
+            oauth_manager,
+            mcp_sessions: std::sync::Arc::new(crate::api::mcp::McpSessions::new()),
+            mcp_tools: std::sync::Arc::new(crate::mcp::client::McpToolServers::new(
+                &Default::default(),
+                reqwest::Client::new(),
+            )),
+            stream_registry: std::sync::Arc::new(crate::api::resume::StreamRegistry::from_config(
+                &crate::config::CacheConfig::default(),
+            )),
+            request_tail: std::sync::Arc::new(crate::budget::tail::RequestTail::new()),
+            stream_limiter: std::sync::Arc::new(crate::budget::streams::StreamLimiter::new()),
+            scheduler: std::sync::Arc::new(crate::budget::scheduler::FairScheduler::new()),
+            settings: std::sync::Arc::new(crate::settings::RuntimeSettings::new(config_arc)),
+            login_throttle: std::sync::Arc::new(crate::auth::throttle::LoginThrottle::new(
+                Default::default(),
+                db,
+            )),
+            notifier: std::sync::Arc::new(crate::notify::Notifier::disabled()),
+            background: tokio_util::task::TaskTracker::new(),
         }
     }
 
@@ -250,6 +510,73 @@ mod tests {
         assert_eq!(token, "sk-prx-abc123");
     }
 
+    #[test]
+    fn test_extract_sdk_api_key() {
+        let req = HttpRequest::builder()
+            .header("x-goog-api-key", "sk-prx-goog")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(extract_bearer_token(&req).unwrap(), "sk-prx-goog");
+
+        let req = HttpRequest::builder()
+            .header("x-api-key", "sk-prx-anthropic")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(extract_bearer_token(&req).unwrap(), "sk-prx-anthropic");
+
+        let req = HttpRequest::builder()
+            .uri("/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse&key=sk-prx-q")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(extract_bearer_token(&req).unwrap(), "sk-prx-q");
+
+        // The Authorization header wins.
+        let req = HttpRequest::builder()
+            .uri("/?key=sk-prx-q")
+            .header(header::AUTHORIZATION, "Bearer sk-prx-abc123")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(extract_bearer_token(&req).unwrap(), "sk-prx-abc123");
+    }
+
+    #[test]
+    fn test_with_origin() {
+        let state = test_state();
+        let user = |origin| AuthUser {
+            user_id: "u1".to_string(),
+            name: "alice".to_string(),
+            role: "member".to_string(),
+            reasoning_mode: ReasoningMode::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin,
+        };
+        let req = HttpRequest::builder()
+            .uri("/v1/chat/completions?x=1")
+            .header(header::USER_AGENT, "openai-python/1.40")
+            .header("x-forwarded-for", "192.0.2.66, 203.0.113.7")
+            .body(Body::empty())
+            .unwrap();
+        let origin = with_origin(&state, &req, user(RequestOrigin::default())).origin;
+        assert_eq!(origin.path.as_deref(), Some("/v1/chat/completions"));
+        assert_eq!(origin.user_agent.as_deref(), Some("openai-python/1.40"));
+        // X-Forwarded-For is only trusted behind a proxy, and without a
+        // connection there is no peer address.
+        assert_eq!(origin.client_ip, None);
+
+        let mut config = (*state.config).clone();
+        config.auth.login_throttle.trust_forwarded_for = true;
+        let state = AppState {
+            config: std::sync::Arc::new(config),
+            ..state
+        };
+        // The client-supplied first hop is ignored, as for login throttling.
+        let origin = with_origin(&state, &req, user(RequestOrigin::default())).origin;
+        assert_eq!(origin.client_ip.as_deref(), Some("203.0.113.7"));
+    }
+
     // -----------------------------------------------------------------------
     // Integration tests for full middleware stack (require AppState)
     // -----------------------------------------------------------------------
@@ -324,6 +651,64 @@ mod tests {
         assert_eq!(String::from_utf8(body.to_vec()).unwrap(), "alice");
     }
 
+    #[tokio::test]
+    async fn test_valid_session_cookie() {
+        let state = test_state();
+        let user = create_user(&state.db, "alice", "member").unwrap();
+        let key = create_api_key(&state.db, &user.id, "web").unwrap();
+        let token = sessions::create_session(&state.db, &key.info.id, 1).unwrap();
+
+        let app = auth_router(state);
+
+        let req = HttpRequest::builder()
+            .uri("/whoami")
+            .header(header::COOKIE, format!("gaud_session={token}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = HttpRequest::builder()
+            .uri("/whoami")
+            .header(header::COOKIE, "gaud_session=bogus")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_bad_keys_lock_out_the_account() {
+        let mut state = test_state();
+        state.login_throttle = std::sync::Arc::new(crate::auth::throttle::LoginThrottle::new(
+            crate::config::LoginThrottleConfig {
+                max_failures: 2,
+                ..Default::default()
+            },
+            state.db.clone(),
+        ));
+        let user = create_user(&state.db, "alice", "member").unwrap();
+        let key = create_api_key(&state.db, &user.id, "k").unwrap();
+        // Right prefix, wrong key: the failures count against alice.
+        let guess = format!("{}{}", &key.plaintext[..15], "x".repeat(17));
+
+        let app = auth_router(state);
+        let request = |token: &str| {
+            HttpRequest::builder()
+                .uri("/whoami")
+                .header(header::AUTHORIZATION, format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+        for _ in 0..2 {
+            let resp = app.clone().oneshot(request(&guess)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+        // Locked out, even with the right key.
+        let resp = app.oneshot(request(&key.plaintext)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
     #[tokio::test]
     async fn test_admin_middleware_allows_admin() {
         let state = test_state();
@@ -360,6 +745,159 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::FORBIDDEN);
     }
 
+    #[tokio::test]
+    async fn test_admin_acts_on_behalf_of_user() {
+        let state = test_state();
+        let admin = create_user(&state.db, "admin", "admin").unwrap();
+        let admin_key = create_api_key(&state.db, &admin.id, "admin key").unwrap();
+        let bob = create_user(&state.db, "bob", "member").unwrap();
+        let bob_key = create_api_key(&state.db, &bob.id, "member key").unwrap();
+
+        let request = |uri: &str, key: &str, on_behalf_of: &str| {
+            HttpRequest::builder()
+                .uri(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {key}"))
+                .header(ON_BEHALF_OF_HEADER, on_behalf_of)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = auth_router(state.clone())
+            .oneshot(request("/whoami", &admin_key.plaintext, &bob.id))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), 1024).await.unwrap();
+        assert_eq!(&body[..], b"bob");
+
+        // The admin gets bob's role, not their own.
+        let resp = admin_router(state.clone())
+            .oneshot(request("/admin", &admin_key.plaintext, &bob.id))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let resp = auth_router(state.clone())
+            .oneshot(request("/whoami", &admin_key.plaintext, "no-such-user"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Members cannot impersonate anyone.
+        let resp = auth_router(state)
+            .oneshot(request("/whoami", &bob_key.plaintext, &admin.id))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_scoped_token_limited_to_proxy_endpoints() {
+        let state = test_state();
+        let user = create_user(&state.db, "alice", "admin").unwrap();
+        let key = create_api_key(&state.db, &user.id, "parent").unwrap();
+        let minted = tokens::mint(
+            &state.db,
+            &user.id,
+            &key.info.id,
+            &tokens::MintTokenRequest {
+                models: vec!["gpt-4o".into()],
+                max_tokens: None,
+                ttl_secs: None,
+            },
+        )
+        .unwrap();
+
+        let app = Router::new()
+            .route("/v1/models", get(whoami))
+            .route("/whoami", get(whoami))
+            .layer(middleware::from_fn_with_state(state.clone(), require_auth))
+            .with_state(state);
+        let request = |uri: &str| {
+            HttpRequest::builder()
+                .uri(uri)
+                .header(header::AUTHORIZATION, format!("Bearer {}", minted.token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(request("/v1/models")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app.oneshot(request("/whoami")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_health_public_but_detail_needs_auth() {
+        let state = test_state();
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(state.clone(), require_auth))
+            .with_state(state);
+        let request = |uri: &str| HttpRequest::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let resp = app.clone().oneshot(request("/health")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = app.oneshot(request("/health?detail=true")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_debug_errors_for_admins_and_flagged_keys() {
+        let state = test_state();
+        let admin = create_user(&state.db, "admin", "admin").unwrap();
+        let admin_key = create_api_key(&state.db, &admin.id, "admin key").unwrap();
+        let bob = create_user(&state.db, "bob", "member").unwrap();
+        let bob_key = create_api_key(&state.db, &bob.id, "member key").unwrap();
+
+        async fn rejected() -> AppError {
+            crate::providers::ProviderError::Api {
+                status: 400,
+                message: r#"{"message":"Content filtered","reason":"POLICY"}"#.into(),
+            }
+            .into()
+        }
+        let app = Router::new()
+            .route("/v1/chat/completions", get(rejected))
+            .layer(middleware::from_fn_with_state(state.clone(), debug_errors))
+            .layer(middleware::from_fn_with_state(state.clone(), require_auth))
+            .with_state(state.clone());
+        let upstream = |key: &str, debug: bool| {
+            let app = app.clone();
+            let key = key.to_string();
+            async move {
+                let mut request = HttpRequest::builder()
+                    .uri("/v1/chat/completions")
+                    .header(header::AUTHORIZATION, format!("Bearer {key}"));
+                if debug {
+                    request = request.header(DEBUG_ERRORS_HEADER, "true");
+                }
+                let resp = app
+                    .oneshot(request.body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+                let body = axum::body::to_bytes(resp.into_body(), 4096).await.unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                assert_eq!(
+                    body["error"]["message"],
+                    "Provider error (400): Content filtered"
+                );
+                body["error"]["upstream"].clone()
+            }
+        };
+
+        assert!(upstream(&admin_key.plaintext, false).await.is_null());
+        let detail = upstream(&admin_key.plaintext, true).await;
+        assert_eq!(detail["status"], 400);
+        assert_eq!(detail["body"]["reason"], "POLICY");
+
+        // Members need the flag on their key; the header alone is ignored.
+        assert!(upstream(&bob_key.plaintext, true).await.is_null());
+        users::set_api_key_debug_errors(&state.db, &bob_key.info.id, true).unwrap();
+        assert_eq!(upstream(&bob_key.plaintext, false).await["status"], 400);
+    }
+
     #[tokio::test]
     async fn test_empty_bearer_token() {
         let state = test_state();
@@ -536,4 +1074,63 @@ mod tests {
             .unwrap();
         assert_eq!(String::from_utf8(body.to_vec()).unwrap(), "charlie");
     }
+
+    fn tls_peer(client_auth: bool, client_cn: Option<&str>) -> ConnectInfo<TlsPeer> {
+        ConnectInfo(TlsPeer {
+            addr: "127.0.0.1:40000".parse().unwrap(),
+            client_auth,
+            client_cn: client_cn.map(String::from),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_tls_cert_auth_uses_verified_cert_and_mapping() {
+        let mut state = test_state_tls_cert(true);
+        let mut config = (*state.config).clone();
+        config
+            .auth
+            .tls_client_cert
+            .users
+            .insert("svc.internal".to_string(), "alice".to_string());
+        state.config = std::sync::Arc::new(config);
+        create_user(&state.db, "alice", "member").unwrap();
+        create_user(&state.db, "mallory", "admin").unwrap();
+        let app = auth_router(state);
+
+        // The verified certificate wins over a forged header.
+        let mut req = HttpRequest::builder()
+            .uri("/whoami")
+            .header("X-Client-Cert-CN", "mallory")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(tls_peer(true, Some("svc.internal")));
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(String::from_utf8(body.to_vec()).unwrap(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_tls_listener_without_client_auth_ignores_header() {
+        let state = test_state_tls_cert(true);
+        create_user(&state.db, "alice", "member").unwrap();
+        let app = auth_router(state);
+
+        // Built-in TLS without client verification on this listener: the
+        // header is not trusted and bearer auth is required.
+        let mut req = HttpRequest::builder()
+            .uri("/whoami")
+            .header("X-Client-Cert-CN", "alice")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(tls_peer(false, None));
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
 }
@@ -1,8 +1,19 @@
+pub mod audit_log;
+pub mod export;
 pub mod keys;
 pub mod middleware;
+pub mod provisioning;
+pub mod purge;
+pub mod sessions;
+pub mod throttle;
+pub mod tokens;
+pub mod totp;
 pub mod users;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::tokens::TokenScope;
+use crate::providers::reasoning::ReasoningMode;
 
 /// Authenticated user identity attached to request extensions by auth middleware.
 #[derive(Debug, Clone, Serialize)]
@@ -10,6 +21,23 @@ pub struct AuthUser {
     pub user_id: String,
     pub name: String,
     pub role: String,
+    /// Reasoning exposure policy of the credential used for this request.
+    pub reasoning_mode: ReasoningMode,
+    /// Team the user belongs to, for shared rate quotas.
+    pub team: Option<String>,
+    /// ID of the admin acting as this user via `X-Gaud-On-Behalf-Of`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<String>,
+    /// API key the request was authenticated with, directly or through a
+    /// web UI session or scoped token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// Restrictions of the scoped token the request was made with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<TokenScope>,
+    /// Where the request came from, recorded in its usage log entries.
+    #[serde(skip)]
+    pub origin: RequestOrigin,
 }
 
 impl AuthUser {
@@ -17,3 +45,19 @@ impl AuthUser {
         self.role == "admin"
     }
 }
+
+/// Client IP, user agent and path of an authenticated request. Empty for
+/// work not started by an HTTP request, such as the CLI.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestOrigin {
+    /// The connection's peer address, or behind a reverse proxy the
+    /// rightmost `X-Forwarded-For` hop that is not a trusted proxy, as
+    /// resolved by [`throttle::client_ip`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_ip: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Request path, such as `/v1/chat/completions`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
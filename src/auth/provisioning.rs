@@ -0,0 +1,325 @@
+//! Reconcile the `[provisioning]` config section into the database.
+//!
+//! Runs once at startup, before the admin bootstrap. Declared users are
+//! created or updated (matched by name), their budget limits and team
+//! quotas set to exactly what is declared, and their keys inserted by hash.
+//! Users that are not declared are left alone, so admins created through
+//! the API keep working.
+
+use serde::Serialize;
+
+use crate::auth::{keys, users};
+use crate::budget::quota::{QuotaScope, RateLimits};
+use crate::budget::scheduler::Priority;
+use crate::budget::tracker::BudgetTracker;
+use crate::config::ProvisioningConfig;
+use crate::db::Database;
+use crate::error::AppError;
+
+/// What a reconciliation changed.
+#[derive(Debug, Default, Serialize)]
+pub struct ProvisionSummary {
+    pub users_created: usize,
+    pub users_updated: usize,
+    pub keys_added: usize,
+    pub keys_revoked: usize,
+}
+
+/// Bring the database in line with `config`. The section is validated as
+/// a whole first; an invalid section changes nothing.
+pub fn reconcile(
+    db: &Database,
+    budget: &BudgetTracker,
+    config: &ProvisioningConfig,
+) -> Result<ProvisionSummary, AppError> {
+    validate(config)?;
+
+    let mut summary = ProvisionSummary::default();
+
+    for team in &config.teams {
+        let limits = RateLimits {
+            rpm: team.rpm,
+            tpm: team.tpm,
+            rpd: team.rpd,
+        };
+        budget.set_rate_limits(QuotaScope::Team, &team.name, limits)?;
+    }
+
+    for declared in &config.users {
+        let team = declared.team.as_deref().filter(|t| !t.is_empty());
+        let user = match users::get_user_by_name(db, &declared.name) {
+            Ok(existing) => {
+                let mut changed = false;
+                if existing.role != declared.role {
+                    users::set_user_role(db, &existing.id, &declared.role)?;
+                    changed = true;
+                }
+                if existing.team.as_deref() != team {
+                    users::set_user_team(db, &existing.id, team)?;
+                    changed = true;
+                }
+                if changed {
+                    summary.users_updated += 1;
+                }
+                existing
+            }
+            Err(AppError::NotFound(_)) => {
+                let created = users::create_user(db, &declared.name, &declared.role)?;
+                if team.is_some() {
+                    users::set_user_team(db, &created.id, team)?;
+                }
+                summary.users_created += 1;
+                created
+            }
+            Err(e) => return Err(e),
+        };
+
+        let has_limits = [
+            declared.monthly_limit,
+            declared.daily_limit,
+            declared.monthly_premium_limit,
+            declared.daily_premium_limit,
+        ]
+        .iter()
+        .any(Option::is_some);
+        if has_limits || budget.get_budget(&user.id)?.is_some() {
+            budget.set_budget(&user.id, declared.monthly_limit, declared.daily_limit)?;
+            budget.set_premium_limits(
+                &user.id,
+                declared.monthly_premium_limit,
+                declared.daily_premium_limit,
+            )?;
+        }
+
+        let mut declared_ids = Vec::new();
+        for key in &declared.keys {
+            match users::find_api_key_by_hash(db, &key.hash)? {
+                Some(existing) if existing.user_id != user.id => {
+                    return Err(AppError::BadRequest(format!(
+                        "Provisioned key '{}' of user '{}' is stored for another user",
+                        key.label, declared.name
+                    )));
+                }
+                Some(existing) => {
+                    users::set_api_key_provisioned_hash(db, &existing.id, &key.hash)?;
+                    if existing.label != key.label {
+                        users::set_api_key_label(db, &existing.id, &key.label)?;
+                    }
+                    if existing.reasoning_mode != key.reasoning_mode {
+                        users::set_api_key_reasoning_mode(db, &existing.id, key.reasoning_mode)?;
+                    }
+                    if existing.priority != key.priority {
+                        users::set_api_key_priority(db, &existing.id, key.priority)?;
+                    }
+                    declared_ids.push(existing.id);
+                }
+                None => {
+                    let info = users::insert_api_key_hash(
+                        db,
+                        &user.id,
+                        &key.hash,
+                        &key.prefix,
+                        &key.label,
+                        key.reasoning_mode,
+                    )?;
+                    if key.priority != Priority::Default {
+                        users::set_api_key_priority(db, &info.id, key.priority)?;
+                    }
+                    summary.keys_added += 1;
+                    declared_ids.push(info.id);
+                }
+            }
+        }
+
+        if config.prune_keys {
+            for key in users::list_api_keys(db, &user.id)? {
+                if !declared_ids.contains(&key.id) {
+                    users::revoke_api_key(db, &key.id)?;
+                    summary.keys_revoked += 1;
+                }
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+fn validate(config: &ProvisioningConfig) -> Result<(), AppError> {
+    let mut names = std::collections::HashSet::new();
+    let mut hashes = std::collections::HashSet::new();
+    for user in &config.users {
+        if user.name.trim().is_empty() {
+            return Err(AppError::BadRequest(
+                "Provisioned user name must not be empty".to_string(),
+            ));
+        }
+        if user.role != "admin" && user.role != "member" {
+            return Err(AppError::BadRequest(format!(
+                "Invalid role '{}' for provisioned user '{}': must be 'admin' or 'member'",
+                user.role, user.name
+            )));
+        }
+        if !names.insert(user.name.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Provisioned user '{}' is declared more than once",
+                user.name
+            )));
+        }
+        for key in &user.keys {
+            if !keys::is_key_hash(&key.hash) {
+                return Err(AppError::BadRequest(format!(
+                    "Provisioned key '{}' of user '{}' does not have a valid hash",
+                    key.label, user.name
+                )));
+            }
+            if !hashes.insert(key.hash.as_str()) {
+                return Err(AppError::BadRequest(format!(
+                    "Provisioned key '{}' of user '{}' is declared more than once",
+                    key.label, user.name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ProvisionedKey, ProvisionedTeam, ProvisionedUser};
+    use crate::providers::reasoning::ReasoningMode;
+
+    fn setup() -> (Database, BudgetTracker) {
+        let db = Database::open_in_memory().unwrap();
+        let budget = BudgetTracker::new(db.clone());
+        (db, budget)
+    }
+
+    fn user(name: &str, keys: Vec<ProvisionedKey>) -> ProvisionedUser {
+        ProvisionedUser {
+            name: name.to_string(),
+            role: "member".to_string(),
+            team: Some("research".to_string()),
+            monthly_limit: Some(50.0),
+            daily_limit: None,
+            monthly_premium_limit: None,
+            daily_premium_limit: None,
+            keys,
+        }
+    }
+
+    fn key(hash: &str, label: &str) -> ProvisionedKey {
+        ProvisionedKey {
+            hash: hash.to_string(),
+            prefix: "sk-prx-abcdefgh...".to_string(),
+            label: label.to_string(),
+            reasoning_mode: ReasoningMode::Forward,
+            priority: Priority::Default,
+        }
+    }
+
+    #[test]
+    fn test_reconcile_creates_users_keys_and_quotas() {
+        let (db, budget) = setup();
+        let plaintext = "sk-prx-abcdefghabcdefghabcdefghabcdefgh";
+        let hash = keys::hash_key(plaintext).unwrap();
+        let config = ProvisioningConfig {
+            prune_keys: false,
+            teams: vec![ProvisionedTeam {
+                name: "research".to_string(),
+                rpm: Some(60),
+                tpm: None,
+                rpd: None,
+            }],
+            users: vec![user("alice", vec![key(&hash, "ci")])],
+        };
+
+        let summary = reconcile(&db, &budget, &config).unwrap();
+        assert_eq!(summary.users_created, 1);
+        assert_eq!(summary.keys_added, 1);
+
+        let auth = users::validate_api_key(&db, plaintext).unwrap();
+        assert_eq!(auth.name, "alice");
+        assert_eq!(auth.team.as_deref(), Some("research"));
+        let b = budget.get_budget(&auth.user_id).unwrap().unwrap();
+        assert_eq!(b.monthly_limit, Some(50.0));
+        assert_eq!(
+            budget
+                .get_rate_limits(QuotaScope::Team, "research")
+                .unwrap()
+                .rpm,
+            Some(60)
+        );
+
+        // A second run changes nothing.
+        let again = reconcile(&db, &budget, &config).unwrap();
+        assert_eq!(
+            again.users_created + again.users_updated + again.keys_added,
+            0
+        );
+    }
+
+    #[test]
+    fn test_rehashed_key_stays_provisioned() {
+        let (db, budget) = setup();
+        let plaintext = "sk-prx-abcdefghabcdefghabcdefghabcdefgh";
+        let config = ProvisioningConfig {
+            prune_keys: true,
+            teams: Vec::new(),
+            users: vec![user("alice", vec![key(&keys::v1_hash(plaintext), "ci")])],
+        };
+        reconcile(&db, &budget, &config).unwrap();
+
+        // Using the key upgrades its stored hash.
+        let (key_id, _) = users::validate_api_key_with_id(&db, plaintext).unwrap();
+        let stored: String = db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT key_hash FROM api_keys WHERE id = ?1",
+                    [&key_id],
+                    |row| row.get(0),
+                )
+            })
+            .unwrap();
+        assert!(!keys::needs_rehash(&stored));
+
+        // The declared hash still identifies the key.
+        let again = reconcile(&db, &budget, &config).unwrap();
+        assert_eq!(again.keys_added + again.keys_revoked, 0);
+        assert!(users::validate_api_key(&db, plaintext).is_ok());
+    }
+
+    #[test]
+    fn test_reconcile_prunes_undeclared_keys() {
+        let (db, budget) = setup();
+        let alice = users::create_user(&db, "alice", "admin").unwrap();
+        let manual = users::create_api_key(&db, &alice.id, "manual").unwrap();
+        let hash = keys::hash_key("sk-prx-abcdefghabcdefghabcdefghabcdefgh").unwrap();
+
+        let config = ProvisioningConfig {
+            prune_keys: true,
+            teams: Vec::new(),
+            users: vec![user("alice", vec![key(&hash, "ci")])],
+        };
+        let summary = reconcile(&db, &budget, &config).unwrap();
+        assert_eq!(summary.users_updated, 1);
+        assert_eq!(summary.keys_revoked, 1);
+        assert!(users::get_api_key(&db, &manual.info.id).is_err());
+        assert_eq!(users::get_user(&db, &alice.id).unwrap().role, "member");
+    }
+
+    #[test]
+    fn test_reconcile_rejects_invalid_hashes() {
+        let (db, budget) = setup();
+        let config = ProvisioningConfig {
+            prune_keys: false,
+            teams: Vec::new(),
+            users: vec![user("alice", vec![key("not-a-hash", "ci")])],
+        };
+        assert!(matches!(
+            reconcile(&db, &budget, &config),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(users::list_users(&db).unwrap().is_empty());
+    }
+}
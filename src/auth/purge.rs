@@ -0,0 +1,211 @@
+//! Erase a user's personal data (GDPR-style purge).
+//!
+//! A purge deletes the user's usage records, stored `/v1/responses`
+//! conversations, uploaded files and images, semantic cache entries and API keys (which
+//! ends their web sessions), drops them from the live request log, and
+//! detaches their ID from security events. The user record itself, their
+//! budget and quota settings, and the append-only admin audit log are kept.
+//! A dry run reports the same counts without changing anything.
+
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::budget::tail::RequestTail;
+use crate::cache::SemanticCacheService;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::files::FileStore;
+
+/// What a purge removed, or would remove for a dry run.
+#[derive(Debug, Default, Serialize)]
+pub struct PurgeReport {
+    pub user_id: String,
+    pub dry_run: bool,
+    pub usage_records: u64,
+    pub stored_responses: u64,
+    pub conversations: u64,
+    pub files: u64,
+    pub cache_entries: u64,
+    pub api_keys: u64,
+    pub web_sessions: u64,
+    /// Security events whose user ID is cleared.
+    pub security_events: u64,
+    /// Entries dropped from the in-memory live request log.
+    pub recent_requests: u64,
+}
+
+/// Where a user's data lives besides the database tables.
+pub struct PurgeTargets<'a> {
+    pub files: &'a FileStore,
+    pub cache: Option<&'a SemanticCacheService>,
+    pub tail: &'a RequestTail,
+}
+
+/// Purge `user_id`'s data, or only count it when `dry_run` is set.
+pub async fn purge_user(
+    db: &Database,
+    targets: PurgeTargets<'_>,
+    user_id: &str,
+    dry_run: bool,
+) -> Result<PurgeReport, AppError> {
+    let mut report = count_rows(db, user_id)?;
+    report.dry_run = dry_run;
+    report.files = targets.files.list(user_id, None)?.len() as u64;
+
+    if dry_run {
+        if let Some(cache) = targets.cache {
+            report.cache_entries = cache
+                .count_user(user_id)
+                .await
+                .map_err(|e| AppError::Internal(e.to_string()))?;
+        }
+        return Ok(report);
+    }
+
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM usage_log WHERE user_id = ?1", params![user_id])?;
+        tx.execute("DELETE FROM responses WHERE user_id = ?1", params![user_id])?;
+        tx.execute(
+            "DELETE FROM conversations WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        tx.execute(
+            "DELETE FROM image_uploads WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        // Sessions go with their keys.
+        tx.execute("DELETE FROM api_keys WHERE user_id = ?1", params![user_id])?;
+        tx.execute(
+            "UPDATE security_events SET user_id = NULL WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        tx.commit()
+    })?;
+    report.files = targets.files.delete_all(user_id)? as u64;
+    if let Some(cache) = targets.cache {
+        report.cache_entries = cache
+            .flush_user(user_id)
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+    }
+    report.recent_requests = targets.tail.forget_user(user_id) as u64;
+
+    tracing::info!(
+        user_id = %user_id,
+        usage_records = report.usage_records,
+        files = report.files,
+        cache_entries = report.cache_entries,
+        "User data purged"
+    );
+    Ok(report)
+}
+
+fn count_rows(db: &Database, user_id: &str) -> Result<PurgeReport, AppError> {
+    let count = |sql: &str| -> Result<u64, AppError> {
+        let n: i64 =
+            db.with_conn(|conn| conn.query_row(sql, params![user_id], |row| row.get(0)))?;
+        Ok(n as u64)
+    };
+    Ok(PurgeReport {
+        user_id: user_id.to_string(),
+        usage_records: count("SELECT COUNT(*) FROM usage_log WHERE user_id = ?1")?,
+        stored_responses: count("SELECT COUNT(*) FROM responses WHERE user_id = ?1")?,
+        conversations: count("SELECT COUNT(*) FROM conversations WHERE user_id = ?1")?,
+        api_keys: count("SELECT COUNT(*) FROM api_keys WHERE user_id = ?1")?,
+        web_sessions: count(
+            "SELECT COUNT(*) FROM web_sessions s JOIN api_keys k ON s.api_key_id = k.id \
+             WHERE k.user_id = ?1",
+        )?,
+        security_events: count("SELECT COUNT(*) FROM security_events WHERE user_id = ?1")?,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{sessions, users};
+    use crate::files::NewFile;
+
+    fn seed(db: &Database, files: &FileStore) -> (String, String) {
+        let alice = users::create_user(db, "alice", "member").unwrap();
+        let bob = users::create_user(db, "bob", "member").unwrap();
+        for user in [&alice.id, &bob.id] {
+            let key = users::create_api_key(db, user, "k").unwrap();
+            sessions::create_session(db, &key.info.id, 1).unwrap();
+            db.with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO usage_log (id, user_id, request_id, provider, model) \
+                     VALUES (?1, ?2, 'req', 'claude', 'm')",
+                    params![format!("log-{user}"), user],
+                )
+            })
+            .unwrap();
+            files
+                .create(
+                    user,
+                    NewFile {
+                        filename: "a.txt",
+                        purpose: "user_data",
+                        content_type: None,
+                        data: b"hi",
+                    },
+                )
+                .unwrap();
+        }
+        (alice.id, bob.id)
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_changes_nothing() {
+        let db = Database::open_in_memory().unwrap();
+        let files = FileStore::new(db.clone(), None);
+        let tail = RequestTail::new();
+        let (alice, _) = seed(&db, &files);
+
+        let targets = PurgeTargets {
+            files: &files,
+            cache: None,
+            tail: &tail,
+        };
+        let report = purge_user(&db, targets, &alice, true).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.usage_records, 1);
+        assert_eq!(report.files, 1);
+        assert_eq!(report.api_keys, 1);
+        assert_eq!(report.web_sessions, 1);
+        assert_eq!(users::list_api_keys(&db, &alice).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_purge_removes_only_that_users_data() {
+        let db = Database::open_in_memory().unwrap();
+        let files = FileStore::new(db.clone(), None);
+        let tail = RequestTail::new();
+        let (alice, bob) = seed(&db, &files);
+
+        let targets = PurgeTargets {
+            files: &files,
+            cache: None,
+            tail: &tail,
+        };
+        let report = purge_user(&db, targets, &alice, false).await.unwrap();
+        assert_eq!(report.usage_records, 1);
+        assert_eq!(report.files, 1);
+
+        let targets = PurgeTargets {
+            files: &files,
+            cache: None,
+            tail: &tail,
+        };
+        let after = purge_user(&db, targets, &alice, true).await.unwrap();
+        assert_eq!(after.usage_records + after.files + after.api_keys, 0);
+        assert_eq!(after.web_sessions, 0);
+
+        assert_eq!(users::list_api_keys(&db, &bob).unwrap().len(), 1);
+        assert_eq!(files.list(&bob, None).unwrap().len(), 1);
+        // With their usage gone the user can now be deleted.
+        users::delete_user(&db, &alice).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Server-side sessions for the web UI.
+//!
+//! The login page posts an API key once; gaud validates it and answers with
+//! an HttpOnly session cookie. Sessions are tied to the API key they were
+//! created with, so revoking the key (or deleting its user) ends them. Only
+//! a SHA-256 digest of the token is stored.
+
+use axum::http::{HeaderMap, header};
+use rusqlite::{OptionalExtension, params};
+
+use crate::auth::AuthUser;
+use crate::auth::keys;
+use crate::auth::users;
+use crate::db::Database;
+use crate::error::AppError;
+
+/// Name of the session cookie.
+pub const SESSION_COOKIE: &str = "gaud_session";
+
+/// Start a session for the API key `api_key_id`, returning the token to set
+/// as the cookie value.
+pub fn create_session(db: &Database, api_key_id: &str, ttl_hours: u64) -> Result<String, AppError> {
+    let token = keys::generate_session_token();
+    let hash = keys::hash_session_token(&token);
+    db.with_conn(|conn| {
+        // Opportunistically drop expired sessions.
+        conn.execute(
+            "DELETE FROM web_sessions WHERE expires_at <= datetime('now')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO web_sessions (token_hash, api_key_id, expires_at) \
+             VALUES (?1, ?2, datetime('now', ?3))",
+            params![hash, api_key_id, format!("+{ttl_hours} hours")],
+        )
+    })?;
+    tracing::info!(key_id = %api_key_id, "Web UI session started");
+    Ok(token)
+}
+
+/// Resolve a session token to its user, if the session exists and has not
+/// expired.
+pub fn validate_session(db: &Database, token: &str) -> Result<AuthUser, AppError> {
+    let hash = keys::hash_session_token(token);
+    let row = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT u.id, u.name, u.role, ak.reasoning_mode, u.team, ak.id \
+             FROM web_sessions s \
+             JOIN api_keys ak ON s.api_key_id = ak.id \
+             JOIN users u ON ak.user_id = u.id \
+             WHERE s.token_hash = ?1 AND s.expires_at > datetime('now') \
+             AND ak.disabled_at IS NULL",
+            params![hash],
+            |row| {
+                Ok(AuthUser {
+                    user_id: row.get(0)?,
+                    name: row.get(1)?,
+                    role: row.get(2)?,
+                    reasoning_mode: users::parse_reasoning_mode(row.get(3)?),
+                    team: row.get(4)?,
+                    impersonator: None,
+                    key_id: Some(row.get(5)?),
+                    scope: None,
+                    origin: Default::default(),
+                })
+            },
+        )
+        .optional()
+    })?;
+    row.ok_or_else(|| AppError::Unauthorized("Session expired or invalid".to_string()))
+}
+
+/// End a session. Unknown tokens are ignored.
+pub fn delete_session(db: &Database, token: &str) -> Result<(), AppError> {
+    let hash = keys::hash_session_token(token);
+    db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM web_sessions WHERE token_hash = ?1",
+            params![hash],
+        )
+    })?;
+    Ok(())
+}
+
+/// The session token from the request's `Cookie` header.
+pub fn session_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// `Set-Cookie` value for a new session. `secure` adds the `Secure`
+/// attribute, for connections made over HTTPS.
+pub fn session_cookie(token: &str, ttl_hours: u64, secure: bool) -> String {
+    let max_age = ttl_hours * 3600;
+    let secure = if secure { "; Secure" } else { "" };
+    format!(
+        "{SESSION_COOKIE}={token}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Strict{secure}"
+    )
+}
+
+/// `Set-Cookie` value that removes the session cookie.
+pub fn clear_session_cookie(secure: bool) -> String {
+    let secure = if secure { "; Secure" } else { "" };
+    format!("{SESSION_COOKIE}=; Path=/; Max-Age=0; HttpOnly; SameSite=Strict{secure}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::users::{create_api_key, create_user, revoke_api_key};
+    use axum::http::HeaderValue;
+
+    fn test_db() -> Database {
+        Database::open_in_memory().unwrap()
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        let db = test_db();
+        let user = create_user(&db, "alice", "member").unwrap();
+        let key = create_api_key(&db, &user.id, "web").unwrap();
+
+        let token = create_session(&db, &key.info.id, 12).unwrap();
+        let auth = validate_session(&db, &token).unwrap();
+        assert_eq!(auth.user_id, user.id);
+        assert_eq!(auth.role, "member");
+
+        delete_session(&db, &token).unwrap();
+        assert!(validate_session(&db, &token).is_err());
+    }
+
+    #[test]
+    fn test_session_ends_with_its_key() {
+        let db = test_db();
+        let user = create_user(&db, "alice", "admin").unwrap();
+        let key = create_api_key(&db, &user.id, "web").unwrap();
+        let token = create_session(&db, &key.info.id, 12).unwrap();
+
+        revoke_api_key(&db, &key.info.id).unwrap();
+        assert!(validate_session(&db, &token).is_err());
+    }
+
+    #[test]
+    fn test_expired_session_is_rejected() {
+        let db = test_db();
+        let user = create_user(&db, "alice", "admin").unwrap();
+        let key = create_api_key(&db, &user.id, "web").unwrap();
+        let token = create_session(&db, &key.info.id, 0).unwrap();
+        assert!(validate_session(&db, &token).is_err());
+    }
+
+    #[test]
+    fn test_session_token_from_cookie_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; gaud_session=abc123; other=1"),
+        );
+        assert_eq!(session_token(&headers).as_deref(), Some("abc123"));
+
+        headers.insert(header::COOKIE, HeaderValue::from_static("theme=dark"));
+        assert_eq!(session_token(&headers), None);
+    }
+
+    #[test]
+    fn test_session_cookie_attributes() {
+        let cookie = session_cookie("tok", 2, true);
+        assert!(cookie.starts_with("gaud_session=tok;"));
+        assert!(cookie.contains("Max-Age=7200"));
+        assert!(cookie.contains("HttpOnly"));
+        assert!(cookie.contains("SameSite=Strict"));
+        assert!(cookie.ends_with("; Secure"));
+        assert!(!clear_session_cookie(false).contains("Secure"));
+    }
+}
@@ -0,0 +1,542 @@
+//! Brute-force protection for API key validation, web UI login and the
+//! two-factor codes sent with sensitive API requests.
+//!
+//! Failed attempts are counted per client IP and per account. Once either
+//! reaches `auth.login_throttle.max_failures` within `window_secs`, further
+//! attempts from that IP (or against that account) are refused with `429`
+//! for `lockout_secs`, before any key is checked. Each lockout is recorded
+//! in the `security_events` table. Counters live in memory and reset on
+//! restart.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap};
+use rusqlite::params;
+
+use crate::AppState;
+use crate::auth::{AuthUser, totp, users};
+use crate::config::LoginThrottleConfig;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::tls::TlsPeer;
+
+/// Tracked IPs and accounts beyond which stale entries are pruned.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Failures of one IP or account.
+#[derive(Debug, Default)]
+struct Attempts {
+    /// Failures in the current window.
+    failures: u32,
+    window_start: Option<Instant>,
+    locked_until: Option<Instant>,
+}
+
+/// What a throttle key identifies, for the security log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Subject {
+    Ip,
+    Account,
+}
+
+/// Per-IP and per-account failure counters.
+pub struct LoginThrottle {
+    config: LoginThrottleConfig,
+    db: Database,
+    attempts: Mutex<HashMap<(Subject, String), Attempts>>,
+}
+
+impl LoginThrottle {
+    pub fn new(config: LoginThrottleConfig, db: Database) -> Self {
+        Self {
+            config,
+            db,
+            attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Validate an API key under the throttle: refused while the client IP,
+    /// or the account owning a key with the same prefix, is locked out, and
+    /// counted as a failure when invalid. `context` names the entry point
+    /// for the security log.
+    pub fn validate_api_key(
+        &self,
+        ip: Option<IpAddr>,
+        plaintext: &str,
+        context: &str,
+    ) -> Result<(String, AuthUser), AppError> {
+        let account = users::key_prefix_owner(&self.db, plaintext)?;
+        self.check(ip, account.as_deref())?;
+        match users::validate_api_key_from(&self.db, plaintext, ip) {
+            Err(e @ AppError::Unauthorized(_)) => {
+                self.record_failure(ip, account.as_deref(), context);
+                Err(e)
+            }
+            result => result,
+        }
+    }
+
+    /// Require `user`'s second factor under the throttle: refused while the
+    /// client IP or the account is locked out, and a wrong code counted as a
+    /// failure. Asking for a missing code is not a failure.
+    pub fn require_second_factor(
+        &self,
+        ip: Option<IpAddr>,
+        user: &AuthUser,
+        code: Option<&str>,
+        context: &str,
+    ) -> Result<(), AppError> {
+        let code = code.map(str::trim).filter(|c| !c.is_empty());
+        self.check(ip, Some(&user.user_id))?;
+        let result = totp::require_second_factor(&self.db, user, code);
+        if result.is_err() && code.is_some() {
+            self.record_failure(ip, Some(&user.user_id), context);
+        }
+        result
+    }
+
+    /// Refuse the attempt if its IP or account is locked out.
+    pub fn check(&self, ip: Option<IpAddr>, user_id: Option<&str>) -> Result<(), AppError> {
+        self.check_at(ip, user_id, Instant::now())
+    }
+
+    /// Count a failed attempt, locking out the IP or account that reaches
+    /// the limit.
+    pub fn record_failure(&self, ip: Option<IpAddr>, user_id: Option<&str>, context: &str) {
+        self.record_failure_at(ip, user_id, context, Instant::now());
+    }
+
+    /// Clear an account's failures after a complete login (key and second
+    /// factor). IP counters are kept, so one valid key cannot reset them for
+    /// guesses at others.
+    pub fn record_success(&self, user_id: &str) {
+        if !self.config.enabled {
+            return;
+        }
+        self.attempts
+            .lock()
+            .unwrap()
+            .remove(&(Subject::Account, user_id.to_string()));
+    }
+
+    fn check_at(
+        &self,
+        ip: Option<IpAddr>,
+        user_id: Option<&str>,
+        now: Instant,
+    ) -> Result<(), AppError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let attempts = self.attempts.lock().unwrap();
+        let remaining = subjects(ip, user_id)
+            .filter_map(|key| attempts.get(&key)?.locked_until)
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+            .max();
+        match remaining {
+            Some(wait) => Err(AppError::RateLimited(format!(
+                "Too many failed login attempts; try again in {} seconds",
+                wait.as_secs().max(1)
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn record_failure_at(
+        &self,
+        ip: Option<IpAddr>,
+        user_id: Option<&str>,
+        context: &str,
+        now: Instant,
+    ) {
+        if !self.config.enabled {
+            return;
+        }
+        let window = Duration::from_secs(self.config.window_secs);
+        let lockout = Duration::from_secs(self.config.lockout_secs);
+        let mut locked = Vec::new();
+        {
+            let mut attempts = self.attempts.lock().unwrap();
+            if attempts.len() > PRUNE_THRESHOLD {
+                attempts.retain(|_, a| {
+                    a.locked_until.is_some_and(|t| t > now)
+                        || a.window_start.is_some_and(|t| now - t < window)
+                });
+            }
+            for key in subjects(ip, user_id) {
+                let entry = attempts.entry(key.clone()).or_default();
+                if entry.locked_until.is_some_and(|t| t > now) {
+                    continue;
+                }
+                if entry.window_start.is_none_or(|t| now - t >= window) {
+                    entry.window_start = Some(now);
+                    entry.failures = 0;
+                }
+                entry.failures += 1;
+                if entry.failures >= self.config.max_failures {
+                    entry.locked_until = Some(now + lockout);
+                    entry.failures = 0;
+                    entry.window_start = None;
+                    locked.push(key);
+                }
+            }
+        }
+
+        for (subject, value) in locked {
+            tracing::warn!(
+                subject = ?subject,
+                value = %value,
+                context,
+                lockout_secs = self.config.lockout_secs,
+                "Login lockout after repeated failures"
+            );
+            let (ip, user_id) = match subject {
+                Subject::Ip => (Some(value), user_id.map(String::from)),
+                Subject::Account => (ip.map(|ip| ip.to_string()), Some(value)),
+            };
+            let detail = format!(
+                "{} locked for {}s after {} failures ({context})",
+                match subject {
+                    Subject::Ip => "IP",
+                    Subject::Account => "Account",
+                },
+                self.config.lockout_secs,
+                self.config.max_failures
+            );
+            if let Err(e) = record_security_event(
+                &self.db,
+                "lockout",
+                ip.as_deref(),
+                user_id.as_deref(),
+                &detail,
+            ) {
+                tracing::error!(error = %e, "Failed to record lockout event");
+            }
+        }
+    }
+}
+
+fn subjects(ip: Option<IpAddr>, user_id: Option<&str>) -> impl Iterator<Item = (Subject, String)> {
+    ip.map(|ip| (Subject::Ip, ip.to_string()))
+        .into_iter()
+        .chain(user_id.map(|u| (Subject::Account, u.to_string())))
+}
+
+/// Append an entry to the `security_events` table.
+pub fn record_security_event(
+    db: &Database,
+    event: &str,
+    ip: Option<&str>,
+    user_id: Option<&str>,
+    detail: &str,
+) -> Result<(), AppError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO security_events (event, ip, user_id, detail) VALUES (?1, ?2, ?3, ?4)",
+            params![event, ip, user_id, detail],
+        )
+    })?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Client IP
+// ---------------------------------------------------------------------------
+
+/// The client's IP address.
+///
+/// Without `trust_forwarded_for` this is the connection's peer address. With
+/// it, gaud runs behind reverse proxies that each append the address they
+/// received from to `X-Forwarded-For`, so only the right end of the header
+/// can be trusted: the client is the rightmost hop that is not one of
+/// `trusted_proxies`, or simply the rightmost hop when none are configured.
+/// Hops left of it may have been forged by the client. A peer that is not a
+/// trusted proxy is the client itself, whatever the header says.
+pub fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    config: &LoginThrottleConfig,
+) -> Option<IpAddr> {
+    let peer = peer_ip(extensions);
+    if !config.trust_forwarded_for {
+        return peer;
+    }
+    let trusted = config.trusted_proxy_nets();
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !trusted.is_empty()
+        && let Some(ip) = peer
+        && !is_trusted(&ip)
+    {
+        return peer;
+    }
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .collect();
+    let mut client = peer;
+    for hop in hops.into_iter().rev() {
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            break;
+        };
+        client = Some(ip);
+        if !is_trusted(&ip) {
+            break;
+        }
+    }
+    client
+}
+
+/// The connection's peer address, over plain TCP or TLS.
+fn peer_ip(extensions: &Extensions) -> Option<IpAddr> {
+    if let Some(ConnectInfo(addr)) = extensions.get::<ConnectInfo<SocketAddr>>() {
+        return Some(addr.ip());
+    }
+    extensions
+        .get::<ConnectInfo<TlsPeer>>()
+        .map(|ConnectInfo(peer)| peer.addr.ip())
+}
+
+/// Extractor for the client's IP address (see [`client_ip`]).
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self(client_ip(
+            &parts.headers,
+            &parts.extensions,
+            &state.config.auth.login_throttle,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn throttle(max_failures: u32) -> LoginThrottle {
+        let config = LoginThrottleConfig {
+            max_failures,
+            window_secs: 60,
+            lockout_secs: 300,
+            ..Default::default()
+        };
+        LoginThrottle::new(config, Database::open_in_memory().unwrap())
+    }
+
+    fn lockouts(db: &Database) -> i64 {
+        db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT COUNT(*) FROM security_events WHERE event = 'lockout'",
+                [],
+                |row| row.get(0),
+            )
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ip_locks_out_after_max_failures() {
+        let t = throttle(3);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "203.0.113.8".parse().unwrap();
+        let now = Instant::now();
+
+        for _ in 0..2 {
+            t.record_failure_at(Some(ip), None, "api_key", now);
+        }
+        assert!(t.check_at(Some(ip), None, now).is_ok());
+        t.record_failure_at(Some(ip), None, "api_key", now);
+        assert!(matches!(
+            t.check_at(Some(ip), None, now),
+            Err(AppError::RateLimited(_))
+        ));
+        assert!(t.check_at(Some(other), None, now).is_ok());
+        assert_eq!(lockouts(&t.db), 1);
+
+        // The lockout expires.
+        let later = now + Duration::from_secs(301);
+        assert!(t.check_at(Some(ip), None, later).is_ok());
+    }
+
+    #[test]
+    fn test_account_lockout_applies_from_any_ip() {
+        let t = throttle(2);
+        let now = Instant::now();
+        t.record_failure_at(
+            Some("198.51.100.1".parse().unwrap()),
+            Some("usr_1"),
+            "totp",
+            now,
+        );
+        t.record_failure_at(
+            Some("198.51.100.2".parse().unwrap()),
+            Some("usr_1"),
+            "totp",
+            now,
+        );
+        let fresh_ip: IpAddr = "198.51.100.3".parse().unwrap();
+        assert!(t.check_at(Some(fresh_ip), Some("usr_1"), now).is_err());
+        assert!(t.check_at(Some(fresh_ip), Some("usr_2"), now).is_ok());
+    }
+
+    #[test]
+    fn test_failures_outside_window_are_forgotten() {
+        let t = throttle(2);
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        let now = Instant::now();
+        t.record_failure_at(Some(ip), None, "api_key", now);
+        t.record_failure_at(Some(ip), None, "api_key", now + Duration::from_secs(61));
+        assert!(
+            t.check_at(Some(ip), None, now + Duration::from_secs(61))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_success_clears_account_failures() {
+        let t = throttle(2);
+        let now = Instant::now();
+        t.record_failure_at(None, Some("usr_1"), "login", now);
+        t.record_success("usr_1");
+        t.record_failure_at(None, Some("usr_1"), "login", now);
+        assert!(t.check_at(None, Some("usr_1"), now).is_ok());
+    }
+
+    #[test]
+    fn test_disabled_never_locks() {
+        let config = LoginThrottleConfig {
+            enabled: false,
+            max_failures: 1,
+            ..Default::default()
+        };
+        let t = LoginThrottle::new(config, Database::open_in_memory().unwrap());
+        let ip: IpAddr = "192.0.2.1".parse().unwrap();
+        t.record_failure(Some(ip), None, "api_key");
+        assert!(t.check(Some(ip), None).is_ok());
+    }
+
+    #[test]
+    fn test_wrong_totp_codes_lock_the_account() {
+        let t = throttle(3);
+        let user = users::create_user(&t.db, "root", "admin").unwrap();
+        let key = users::create_api_key(&t.db, &user.id, "cli").unwrap();
+        totp::begin_enrollment(&t.db, &user.id, "root").unwrap();
+        t.db.with_conn(|conn| conn.execute("UPDATE user_totp SET enabled = 1", []))
+            .unwrap();
+        let auth = users::validate_api_key(&t.db, &key.plaintext).unwrap();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // A missing code is asked for, not counted.
+        for _ in 0..3 {
+            assert!(matches!(
+                t.require_second_factor(Some(ip), &auth, None, "key_create_totp"),
+                Err(AppError::SecondFactorRequired(_))
+            ));
+        }
+        for _ in 0..3 {
+            assert!(matches!(
+                t.require_second_factor(Some(ip), &auth, Some("12345x"), "key_create_totp"),
+                Err(AppError::SecondFactorRequired(_))
+            ));
+        }
+        assert!(matches!(
+            t.require_second_factor(Some(ip), &auth, Some("12345x"), "key_create_totp"),
+            Err(AppError::RateLimited(_))
+        ));
+        assert_eq!(lockouts(&t.db), 2);
+
+        // The account stays locked from another IP, including for its keys.
+        let other: IpAddr = "198.51.100.9".parse().unwrap();
+        assert!(matches!(
+            t.require_second_factor(Some(other), &auth, Some("12345x"), "key_create_totp"),
+            Err(AppError::RateLimited(_))
+        ));
+        assert!(matches!(
+            t.validate_api_key(Some(other), &key.plaintext, "api_key"),
+            Err(AppError::RateLimited(_))
+        ));
+    }
+
+    fn forwarded(config: &LoginThrottleConfig, xff: &str, peer: &str) -> Option<IpAddr> {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_str(xff).unwrap());
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo::<SocketAddr>(peer.parse().unwrap()));
+        client_ip(&headers, &extensions, config)
+    }
+
+    #[test]
+    fn test_client_ip_prefers_forwarded_for_only_when_trusted() {
+        let trusting = LoginThrottleConfig {
+            trust_forwarded_for: true,
+            trusted_proxies: vec!["10.0.0.0/8".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            forwarded(&trusting, "203.0.113.9, 10.0.0.2", "10.0.0.1:5000"),
+            Some("203.0.113.9".parse().unwrap())
+        );
+        assert_eq!(
+            forwarded(
+                &LoginThrottleConfig::default(),
+                "203.0.113.9",
+                "10.0.0.1:5000"
+            ),
+            Some("10.0.0.1".parse().unwrap())
+        );
+        // A peer that is not a trusted proxy is the client.
+        assert_eq!(
+            forwarded(&trusting, "203.0.113.9", "198.51.100.4:5000"),
+            Some("198.51.100.4".parse().unwrap())
+        );
+        assert_eq!(
+            client_ip(&HeaderMap::new(), &Extensions::new(), &Default::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_client_ip_ignores_spoofed_first_hop() {
+        // The client sent "X-Forwarded-For: 192.0.2.66"; the proxy appended
+        // the address it actually saw.
+        let xff = "192.0.2.66, 203.0.113.9";
+        let config = LoginThrottleConfig {
+            trust_forwarded_for: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            forwarded(&config, xff, "10.0.0.1:5000"),
+            Some("203.0.113.9".parse().unwrap())
+        );
+        // Two proxies, both trusted: still the hop left of the inner one.
+        let config = LoginThrottleConfig {
+            trust_forwarded_for: true,
+            trusted_proxies: vec!["10.0.0.1".to_string(), "172.16.0.0/12".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            forwarded(
+                &config,
+                "192.0.2.66, 203.0.113.9, 172.16.5.5",
+                "10.0.0.1:5000"
+            ),
+            Some("203.0.113.9".parse().unwrap())
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Scoped tokens: short-lived, signed credentials minted from an API key.
+//!
+//! A token names the models it may request and how many tokens it may
+//! consume, and expires within a day, so it can be handed to a browser
+//! client without exposing the key it was minted from. Tokens look like
+//! `sk-tmp-<payload>.<signature>`: the payload is the [`TokenScope`] as
+//! base64url JSON, signed with HMAC-SHA256 under a random key kept in the
+//! database. Each token also has a row counting its usage. The row is
+//! removed with the parent key, so revoking the key revokes its tokens.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{Duration, Utc};
+use rand::Rng;
+use ring::hmac;
+use rusqlite::{OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::auth::users;
+use crate::db::Database;
+use crate::error::AppError;
+use crate::providers::rules::glob_match;
+
+/// Prefix of scoped tokens, telling them apart from API keys.
+pub const TOKEN_PREFIX: &str = "sk-tmp-";
+
+/// Lifetime of tokens minted without `ttl_secs`.
+const DEFAULT_TTL_SECS: u64 = 15 * 60;
+/// Longest lifetime a token may be minted with.
+const MAX_TTL_SECS: u64 = 24 * 60 * 60;
+/// Name of the signing key in `signing_keys`.
+const SIGNING_KEY_NAME: &str = "scoped_tokens";
+
+/// Endpoints scoped tokens may be used on; everything else is refused.
+const ALLOWED_PATHS: &[&str] = &[
+    "/v1/chat/completions",
+    "/v1/chat/completions/ws",
+    "/v1/responses",
+    "/v1/models",
+    "/v1/messages",
+    "/v1/messages/count_tokens",
+];
+
+/// The restrictions signed into a token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenScope {
+    /// Token ID.
+    pub id: String,
+    /// Models the token may request; `*` matches any run of characters.
+    pub models: Vec<String>,
+    /// Most tokens (input plus output) the token may consume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// Expiry, in seconds since the Unix epoch.
+    pub exp: i64,
+}
+
+impl TokenScope {
+    /// Whether the token may request `model`.
+    pub fn allows_model(&self, model: &str) -> bool {
+        self.models.iter().any(|pattern| glob_match(pattern, model))
+    }
+}
+
+/// Body of `POST /me/tokens`.
+#[derive(Debug, Deserialize)]
+pub struct MintTokenRequest {
+    /// Models the token may request; `*` matches any run of characters.
+    pub models: Vec<String>,
+    /// Most tokens (input plus output) the token may consume.
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Lifetime in seconds (default 900, at most 86400).
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+}
+
+/// A newly minted token. The token itself is only returned here.
+#[derive(Debug, Serialize)]
+pub struct MintedToken {
+    pub token: String,
+    pub id: String,
+    pub models: Vec<String>,
+    pub max_tokens: Option<u64>,
+    /// RFC 3339 expiry.
+    pub expires_at: String,
+}
+
+/// Mint a token for `user_id`, tied to the API key `key_id`.
+pub fn mint(
+    db: &Database,
+    user_id: &str,
+    key_id: &str,
+    request: &MintTokenRequest,
+) -> Result<MintedToken, AppError> {
+    if request.models.is_empty() || request.models.iter().any(|m| m.trim().is_empty()) {
+        return Err(AppError::BadRequest(
+            "models must list at least one model name or pattern".to_string(),
+        ));
+    }
+    if request.max_tokens == Some(0) {
+        return Err(AppError::BadRequest(
+            "max_tokens must be greater than 0".to_string(),
+        ));
+    }
+    let ttl = request.ttl_secs.unwrap_or(DEFAULT_TTL_SECS);
+    if !(1..=MAX_TTL_SECS).contains(&ttl) {
+        return Err(AppError::BadRequest(format!(
+            "ttl_secs must be between 1 and {MAX_TTL_SECS}"
+        )));
+    }
+
+    let expires_at = Utc::now() + Duration::seconds(ttl as i64);
+    let scope = TokenScope {
+        id: Uuid::new_v4().to_string(),
+        models: request
+            .models
+            .iter()
+            .map(|m| m.trim().to_string())
+            .collect(),
+        max_tokens: request.max_tokens,
+        exp: expires_at.timestamp(),
+    };
+    let payload = URL_SAFE_NO_PAD.encode(
+        serde_json::to_vec(&scope)
+            .map_err(|e| AppError::Internal(format!("Failed to encode token: {e}")))?,
+    );
+    let signature = hmac::sign(&signing_key(db)?, payload.as_bytes());
+
+    db.with_conn(|conn| {
+        // Opportunistically drop expired tokens.
+        conn.execute(
+            "DELETE FROM scoped_tokens WHERE expires_at <= datetime('now')",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO scoped_tokens (id, api_key_id, user_id, expires_at) \
+             VALUES (?1, ?2, ?3, datetime('now', ?4))",
+            params![scope.id, key_id, user_id, format!("+{ttl} seconds")],
+        )
+    })?;
+    tracing::info!(
+        token_id = %scope.id,
+        key_id = %key_id,
+        user_id = %user_id,
+        ttl_secs = ttl,
+        "Scoped token minted"
+    );
+
+    Ok(MintedToken {
+        token: format!(
+            "{TOKEN_PREFIX}{payload}.{}",
+            URL_SAFE_NO_PAD.encode(signature.as_ref())
+        ),
+        id: scope.id,
+        models: scope.models,
+        max_tokens: scope.max_tokens,
+        expires_at: expires_at.to_rfc3339(),
+    })
+}
+
+/// Whether `token` has the form of a scoped token rather than an API key.
+pub fn is_scoped_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+/// Resolve a scoped token to its user, checking the signature, expiry and
+/// that the parent key still exists. The user never gets the admin role.
+pub fn validate(db: &Database, token: &str) -> Result<AuthUser, AppError> {
+    let invalid = || AppError::Unauthorized("Invalid scoped token".to_string());
+    let (payload, signature) = token
+        .strip_prefix(TOKEN_PREFIX)
+        .and_then(|t| t.split_once('.'))
+        .ok_or_else(invalid)?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+    hmac::verify(&signing_key(db)?, payload.as_bytes(), &signature).map_err(|_| invalid())?;
+    let scope: TokenScope = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or_else(invalid)?;
+    if scope.exp <= Utc::now().timestamp() {
+        return Err(AppError::Unauthorized("Scoped token expired".to_string()));
+    }
+
+    let row = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT u.id, u.name, ak.reasoning_mode, u.team, ak.id \
+             FROM scoped_tokens t \
+             JOIN api_keys ak ON t.api_key_id = ak.id \
+             JOIN users u ON t.user_id = u.id \
+             WHERE t.id = ?1 AND ak.disabled_at IS NULL",
+            params![scope.id],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            },
+        )
+        .optional()
+    })?;
+    let (user_id, name, reasoning_mode, team, key_id) =
+        row.ok_or_else(|| AppError::Unauthorized("Scoped token revoked".to_string()))?;
+
+    Ok(AuthUser {
+        user_id,
+        name,
+        role: "member".to_string(),
+        reasoning_mode: users::parse_reasoning_mode(reasoning_mode),
+        team,
+        impersonator: None,
+        key_id: Some(key_id),
+        scope: Some(scope),
+        origin: Default::default(),
+    })
+}
+
+/// Whether scoped tokens may be used on `path`.
+pub fn allows_path(path: &str) -> bool {
+    ALLOWED_PATHS.contains(&path) || path.starts_with("/v1/responses/")
+}
+
+/// Tokens `scope` may still consume, or `None` without a limit.
+pub fn remaining_tokens(db: &Database, scope: &TokenScope) -> Result<Option<u64>, AppError> {
+    let Some(max_tokens) = scope.max_tokens else {
+        return Ok(None);
+    };
+    let used: i64 = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT used_tokens FROM scoped_tokens WHERE id = ?1",
+            params![scope.id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map(Option::unwrap_or_default)
+    })?;
+    Ok(Some(max_tokens.saturating_sub(used.max(0) as u64)))
+}
+
+/// The key tokens are signed with, created on first use.
+fn signing_key(db: &Database) -> Result<hmac::Key, AppError> {
+    let mut fresh = [0u8; 32];
+    rand::rng().fill(&mut fresh);
+    let secret: Vec<u8> = db.with_conn(|conn| {
+        conn.execute(
+            "INSERT OR IGNORE INTO signing_keys (name, secret) VALUES (?1, ?2)",
+            params![SIGNING_KEY_NAME, fresh.as_slice()],
+        )?;
+        conn.query_row(
+            "SELECT secret FROM signing_keys WHERE name = ?1",
+            params![SIGNING_KEY_NAME],
+            |row| row.get(0),
+        )
+    })?;
+    Ok(hmac::Key::new(hmac::HMAC_SHA256, &secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::users::{create_api_key, create_user, revoke_api_key};
+
+    fn setup() -> (Database, String, String) {
+        let db = Database::open_in_memory().unwrap();
+        let user = create_user(&db, "alice", "admin").unwrap();
+        let key = create_api_key(&db, &user.id, "parent").unwrap();
+        (db, user.id, key.info.id)
+    }
+
+    fn request(models: &[&str], max_tokens: Option<u64>) -> MintTokenRequest {
+        MintTokenRequest {
+            models: models.iter().map(|m| m.to_string()).collect(),
+            max_tokens,
+            ttl_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_mint_and_validate() {
+        let (db, user_id, key_id) = setup();
+        let minted = mint(&db, &user_id, &key_id, &request(&["gpt-4o*"], Some(500))).unwrap();
+        assert!(is_scoped_token(&minted.token));
+
+        let user = validate(&db, &minted.token).unwrap();
+        assert_eq!(user.user_id, user_id);
+        // Tokens never carry admin rights.
+        assert_eq!(user.role, "member");
+        let scope = user.scope.unwrap();
+        assert!(scope.allows_model("gpt-4o-mini"));
+        assert!(!scope.allows_model("claude-opus-4"));
+        assert_eq!(remaining_tokens(&db, &scope).unwrap(), Some(500));
+    }
+
+    #[test]
+    fn test_tampered_and_revoked_tokens_are_refused() {
+        let (db, user_id, key_id) = setup();
+        let minted = mint(&db, &user_id, &key_id, &request(&["gpt-4o"], None)).unwrap();
+
+        // Widening the models invalidates the signature.
+        let (_, signature) = minted.token.split_once('.').unwrap();
+        let forged = TokenScope {
+            id: minted.id.clone(),
+            models: vec!["*".into()],
+            max_tokens: None,
+            exp: Utc::now().timestamp() + 60,
+        };
+        let forged = format!(
+            "{TOKEN_PREFIX}{}.{signature}",
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap())
+        );
+        assert!(matches!(
+            validate(&db, &forged),
+            Err(AppError::Unauthorized(_))
+        ));
+
+        revoke_api_key(&db, &key_id).unwrap();
+        assert!(matches!(
+            validate(&db, &minted.token),
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_mint_validation() {
+        let (db, user_id, key_id) = setup();
+        assert!(mint(&db, &user_id, &key_id, &request(&[], None)).is_err());
+        assert!(mint(&db, &user_id, &key_id, &request(&["m"], Some(0))).is_err());
+        let mut long = request(&["m"], None);
+        long.ttl_secs = Some(MAX_TTL_SECS + 1);
+        assert!(mint(&db, &user_id, &key_id, &long).is_err());
+    }
+
+    #[test]
+    fn test_allowed_paths() {
+        assert!(allows_path("/v1/chat/completions"));
+        assert!(allows_path("/v1/responses/resp_123"));
+        assert!(!allows_path("/v1/embeddings"));
+        assert!(!allows_path("/keys"));
+    }
+}
@@ -0,0 +1,432 @@
+//! TOTP two-factor authentication (RFC 6238) for admin accounts.
+//!
+//! Enrollment is two-step: [`begin_enrollment`] stores a fresh secret and
+//! returns it for the authenticator app, and [`confirm_enrollment`] turns
+//! two-factor on once the user proves they can produce codes, handing out
+//! single-use recovery codes. Recovery codes are stored as SHA-256 digests.
+//!
+//! With two-factor on, web UI login and admin API key issuance require a
+//! current code (or an unused recovery code) in addition to the API key.
+
+use axum::http::HeaderMap;
+use rand::Rng;
+use ring::hmac;
+use rusqlite::{OptionalExtension, params};
+use serde::Serialize;
+
+use crate::auth::AuthUser;
+use crate::auth::keys;
+use crate::db::Database;
+use crate::error::AppError;
+
+/// Header carrying a TOTP or recovery code on API requests.
+pub const TOTP_HEADER: &str = "x-totp-code";
+
+/// Issuer shown in authenticator apps.
+const ISSUER: &str = "gaud";
+const STEP_SECS: u64 = 30;
+const DIGITS: u32 = 6;
+/// Secret length in bytes (160 bits, as RFC 4226 recommends).
+const SECRET_LEN: usize = 20;
+/// Steps either side of the current one accepted, for clock drift.
+const SKEW_STEPS: u64 = 1;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Two-factor state of a user.
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpStatus {
+    pub enabled: bool,
+    /// Unused recovery codes left.
+    pub recovery_codes_remaining: u32,
+}
+
+/// A pending enrollment: the secret to add to an authenticator app.
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32 secret, for manual entry.
+    pub secret: String,
+    /// `otpauth://` URI, for apps that import links or QR codes.
+    pub otpauth_url: String,
+}
+
+/// Two-factor state of `user_id`.
+pub fn status(db: &Database, user_id: &str) -> Result<TotpStatus, AppError> {
+    let enabled = is_enabled(db, user_id)?;
+    let remaining: u32 = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT COUNT(*) FROM totp_recovery_codes WHERE user_id = ?1 AND used_at IS NULL",
+            params![user_id],
+            |row| row.get(0),
+        )
+    })?;
+    Ok(TotpStatus {
+        enabled,
+        recovery_codes_remaining: if enabled { remaining } else { 0 },
+    })
+}
+
+/// Whether `user_id` has confirmed two-factor enrollment.
+pub fn is_enabled(db: &Database, user_id: &str) -> Result<bool, AppError> {
+    let enabled = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT enabled FROM user_totp WHERE user_id = ?1",
+            params![user_id],
+            |row| row.get::<_, bool>(0),
+        )
+        .optional()
+    })?;
+    Ok(enabled.unwrap_or(false))
+}
+
+/// Start (or restart) enrollment with a new secret. Refused while two-factor
+/// is on; disable it first.
+pub fn begin_enrollment(
+    db: &Database,
+    user_id: &str,
+    account_name: &str,
+) -> Result<TotpEnrollment, AppError> {
+    if is_enabled(db, user_id)? {
+        return Err(AppError::BadRequest(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+    let mut secret = [0u8; SECRET_LEN];
+    rand::rng().fill(&mut secret);
+    let secret = base32_encode(&secret);
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO user_totp (user_id, secret, enabled, last_step) VALUES (?1, ?2, 0, 0) \
+             ON CONFLICT(user_id) DO UPDATE SET secret = excluded.secret, enabled = 0, \
+             last_step = 0, created_at = datetime('now')",
+            params![user_id, secret],
+        )
+    })?;
+    let label = urlencoding::encode(&format!("{ISSUER}:{account_name}")).into_owned();
+    let otpauth_url = format!(
+        "otpauth://totp/{label}?secret={secret}&issuer={ISSUER}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}"
+    );
+    Ok(TotpEnrollment {
+        secret,
+        otpauth_url,
+    })
+}
+
+/// Finish enrollment with a code from the authenticator app. Returns the
+/// recovery codes, which are shown exactly once.
+pub fn confirm_enrollment(
+    db: &Database,
+    user_id: &str,
+    code: &str,
+) -> Result<Vec<String>, AppError> {
+    let pending = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT secret, enabled FROM user_totp WHERE user_id = ?1",
+            params![user_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+        )
+        .optional()
+    })?;
+    let secret = match pending {
+        Some((_, true)) => {
+            return Err(AppError::BadRequest(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+        Some((secret, false)) => secret,
+        None => {
+            return Err(AppError::BadRequest(
+                "Start two-factor enrollment first".to_string(),
+            ));
+        }
+    };
+    let step = check_code(&secret, code, unix_now(), 0)
+        .ok_or_else(|| AppError::SecondFactorRequired("Invalid TOTP code".to_string()))?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| keys::generate_recovery_code())
+        .collect();
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "UPDATE user_totp SET enabled = 1, last_step = ?2 WHERE user_id = ?1",
+            params![user_id, step as i64],
+        )?;
+        tx.execute(
+            "DELETE FROM totp_recovery_codes WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        for code in &codes {
+            tx.execute(
+                "INSERT INTO totp_recovery_codes (user_id, code_hash) VALUES (?1, ?2)",
+                params![user_id, keys::hash_recovery_code(code)],
+            )?;
+        }
+        tx.commit()
+    })?;
+    tracing::info!(user_id = %user_id, "Two-factor authentication enabled");
+    Ok(codes)
+}
+
+/// Turn two-factor off and drop the secret and recovery codes.
+pub fn disable(db: &Database, user_id: &str) -> Result<(), AppError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM totp_recovery_codes WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        conn.execute("DELETE FROM user_totp WHERE user_id = ?1", params![user_id])
+    })?;
+    tracing::info!(user_id = %user_id, "Two-factor authentication disabled");
+    Ok(())
+}
+
+/// Check a TOTP code, or failing that an unused recovery code, for a user
+/// with two-factor enabled. Each TOTP code and recovery code works once.
+pub fn verify(db: &Database, user_id: &str, code: &str) -> Result<(), AppError> {
+    let row = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT secret, last_step FROM user_totp WHERE user_id = ?1 AND enabled = 1",
+            params![user_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .optional()
+    })?;
+    let Some((secret, last_step)) = row else {
+        return Err(AppError::BadRequest(
+            "Two-factor authentication is not enabled".to_string(),
+        ));
+    };
+
+    if let Some(step) = check_code(&secret, code, unix_now(), last_step as u64) {
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE user_totp SET last_step = ?2 WHERE user_id = ?1",
+                params![user_id, step as i64],
+            )
+        })?;
+        return Ok(());
+    }
+
+    let used = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE totp_recovery_codes SET used_at = datetime('now') \
+             WHERE user_id = ?1 AND code_hash = ?2 AND used_at IS NULL",
+            params![user_id, keys::hash_recovery_code(code)],
+        )
+    })?;
+    if used == 1 {
+        tracing::warn!(user_id = %user_id, "Two-factor recovery code used");
+        return Ok(());
+    }
+    Err(AppError::SecondFactorRequired(
+        "Invalid TOTP code".to_string(),
+    ))
+}
+
+/// Require a second factor from `user` when they have two-factor enabled.
+/// `code` is the TOTP or recovery code sent with the request, if any.
+pub fn require_second_factor(
+    db: &Database,
+    user: &AuthUser,
+    code: Option<&str>,
+) -> Result<(), AppError> {
+    if !is_enabled(db, &user.user_id)? {
+        return Ok(());
+    }
+    match code.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(code) => verify(db, &user.user_id, code),
+        None => Err(AppError::SecondFactorRequired(
+            "TOTP code required".to_string(),
+        )),
+    }
+}
+
+/// The code from the [`TOTP_HEADER`] header.
+pub fn header_code(headers: &HeaderMap) -> Option<&str> {
+    headers.get(TOTP_HEADER).and_then(|v| v.to_str().ok())
+}
+
+// ---------------------------------------------------------------------------
+// RFC 6238
+// ---------------------------------------------------------------------------
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+/// The time step `code` is valid for, within [`SKEW_STEPS`] of `now`, if
+/// that step is later than `last_step` (so a code cannot be replayed).
+fn check_code(secret: &str, code: &str, now: u64, last_step: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let key = base32_decode(secret)?;
+    let current = now / STEP_SECS;
+    (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .filter(|&step| step > last_step)
+        .find(|&step| keys::constant_time_eq(&hotp(&key, step).to_be_bytes(), &code.to_be_bytes()))
+}
+
+/// HOTP value (RFC 4226) of `counter`.
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+    let tag = hmac::sign(&key, &counter.to_be_bytes());
+    let digest = tag.as_ref();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(DIGITS)
+}
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Unpadded RFC 4648 base32, the format authenticator apps expect.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    for chunk in bytes.chunks(5) {
+        let mut buf = [0u8; 5];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let bits = buf.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        let chars = (chunk.len() * 8).div_ceil(5);
+        for i in 0..chars {
+            let index = (bits >> (35 - i * 5)) & 0x1f;
+            out.push(BASE32_ALPHABET[index as usize] as char);
+        }
+    }
+    out
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in s.bytes().filter(|&c| c != b'=' && c != b' ') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&a| a == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::users::create_user;
+
+    /// RFC 6238 test secret ("12345678901234567890").
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn code_now(secret: &str) -> String {
+        let key = base32_decode(secret).unwrap();
+        format!("{:06}", hotp(&key, unix_now() / STEP_SECS))
+    }
+
+    #[test]
+    fn test_base32_round_trip() {
+        assert_eq!(base32_encode(b"12345678901234567890"), RFC_SECRET);
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_decode("MY").unwrap(), b"f");
+        assert_eq!(
+            base32_decode(&RFC_SECRET.to_lowercase()).unwrap(),
+            b"12345678901234567890"
+        );
+        assert!(base32_decode("not base32!").is_none());
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        // Appendix B, SHA-1, truncated to six digits.
+        let key = b"12345678901234567890";
+        assert_eq!(hotp(key, 59 / STEP_SECS), 287082);
+        assert_eq!(hotp(key, 1111111109 / STEP_SECS), 81804);
+        assert_eq!(hotp(key, 1234567890 / STEP_SECS), 5924);
+        assert_eq!(hotp(key, 2000000000 / STEP_SECS), 279037);
+    }
+
+    #[test]
+    fn test_check_code_window_and_replay() {
+        let now = 1111111109;
+        assert_eq!(check_code(RFC_SECRET, "081804", now, 0), Some(now / 30));
+        // One step of drift is accepted, two are not.
+        assert!(check_code(RFC_SECRET, "081804", now + 30, 0).is_some());
+        assert!(check_code(RFC_SECRET, "081804", now + 60, 0).is_none());
+        // A step already used is rejected.
+        assert!(check_code(RFC_SECRET, "081804", now, now / 30).is_none());
+        assert!(check_code(RFC_SECRET, "81804", now, 0).is_none());
+    }
+
+    #[test]
+    fn test_enrollment_and_verification() {
+        let db = Database::open_in_memory().unwrap();
+        let user = create_user(&db, "root", "admin").unwrap();
+
+        let enrollment = begin_enrollment(&db, &user.id, "root").unwrap();
+        assert!(
+            enrollment
+                .otpauth_url
+                .starts_with("otpauth://totp/gaud%3Aroot?")
+        );
+        assert!(!is_enabled(&db, &user.id).unwrap());
+        assert!(confirm_enrollment(&db, &user.id, "not-a-code").is_err());
+
+        let code = code_now(&enrollment.secret);
+        let codes = confirm_enrollment(&db, &user.id, &code).unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(is_enabled(&db, &user.id).unwrap());
+        assert!(begin_enrollment(&db, &user.id, "root").is_err());
+
+        // The enrollment code was consumed, so it cannot be replayed.
+        assert!(verify(&db, &user.id, &code).is_err());
+
+        // Recovery codes work once.
+        verify(&db, &user.id, &codes[0]).unwrap();
+        assert!(verify(&db, &user.id, &codes[0]).is_err());
+        assert_eq!(
+            status(&db, &user.id).unwrap().recovery_codes_remaining,
+            RECOVERY_CODE_COUNT as u32 - 1
+        );
+
+        disable(&db, &user.id).unwrap();
+        assert!(!is_enabled(&db, &user.id).unwrap());
+    }
+
+    #[test]
+    fn test_require_second_factor() {
+        let db = Database::open_in_memory().unwrap();
+        let user = create_user(&db, "root", "admin").unwrap();
+        let auth = AuthUser {
+            user_id: user.id.clone(),
+            name: user.name.clone(),
+            role: user.role.clone(),
+            reasoning_mode: Default::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin: Default::default(),
+        };
+        // Without two-factor, nothing is required.
+        require_second_factor(&db, &auth, None).unwrap();
+
+        let enrollment = begin_enrollment(&db, &user.id, "root").unwrap();
+        let codes = confirm_enrollment(&db, &user.id, &code_now(&enrollment.secret)).unwrap();
+        assert!(matches!(
+            require_second_factor(&db, &auth, None),
+            Err(AppError::SecondFactorRequired(_))
+        ));
+        require_second_factor(&db, &auth, Some(&codes[1])).unwrap();
+    }
+}
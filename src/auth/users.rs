@@ -1,10 +1,18 @@
-use rusqlite::params;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rusqlite::{OptionalExtension, params};
 use serde::Serialize;
 use uuid::Uuid;
 
 use crate::auth::keys::{self, GeneratedKey};
+use crate::auth::{AuthUser, audit_log};
+use crate::budget::scheduler::Priority;
 use crate::db::Database;
 use crate::error::AppError;
+use crate::leader::Leader;
+use crate::providers::reasoning::ReasoningMode;
 
 /// Stored user record.
 #[derive(Debug, Clone, Serialize)]
@@ -13,6 +21,11 @@ pub struct User {
     pub name: String,
     pub role: String,
     pub created_at: String,
+    /// Team used for shared rate quotas, if any.
+    pub team: Option<String>,
+    /// Whether this is a service account: a tenant for pipelines and bots,
+    /// owned by `team`, that no person signs in as.
+    pub service_account: bool,
 }
 
 /// Stored API key metadata (never includes the hash).
@@ -24,6 +37,38 @@ pub struct ApiKeyInfo {
     pub label: String,
     pub created_at: String,
     pub last_used: Option<String>,
+    /// How `reasoning_content` is exposed to requests made with this key.
+    pub reasoning_mode: ReasoningMode,
+    /// Client IP of the last request made with the key, when known.
+    pub last_used_ip: Option<String>,
+    /// When the key was disabled for going unused; disabled keys are
+    /// refused until re-enabled.
+    pub disabled_at: Option<String>,
+    /// Whether provider errors on requests made with this key include the
+    /// upstream status and body.
+    pub debug_errors: bool,
+    /// Scheduling priority of requests made with this key.
+    pub priority: Priority,
+}
+
+/// Columns read by [`api_key_from_row`].
+const API_KEY_COLUMNS: &str = "id, user_id, key_prefix, label, created_at, last_used, reasoning_mode, last_used_ip, disabled_at, debug_errors, priority";
+
+/// Map a `SELECT {API_KEY_COLUMNS}` row.
+fn api_key_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ApiKeyInfo> {
+    Ok(ApiKeyInfo {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        key_prefix: row.get(2)?,
+        label: row.get(3)?,
+        created_at: row.get(4)?,
+        last_used: row.get(5)?,
+        reasoning_mode: parse_reasoning_mode(row.get(6)?),
+        last_used_ip: row.get(7)?,
+        disabled_at: row.get(8)?,
+        debug_errors: row.get(9)?,
+        priority: parse_priority(row.get(10)?),
+    })
 }
 
 /// Result of creating a new API key: metadata plus the one-time plaintext.
@@ -44,6 +89,18 @@ pub struct BootstrapResult {
 // User CRUD
 // ---------------------------------------------------------------------------
 
+/// Map a `SELECT id, name, role, created_at, team, service_account` row.
+fn user_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        role: row.get(2)?,
+        created_at: row.get(3)?,
+        team: row.get(4)?,
+        service_account: row.get(5)?,
+    })
+}
+
 /// Create a new user with the given name and role.
 pub fn create_user(db: &Database, name: &str, role: &str) -> Result<User, AppError> {
     if role != "admin" && role != "member" {
@@ -59,35 +116,49 @@ pub fn create_user(db: &Database, name: &str, role: &str) -> Result<User, AppErr
             params![id, name, role],
         )?;
 
-        let mut stmt =
-            conn.prepare("SELECT id, name, role, created_at FROM users WHERE id = ?1")?;
-        stmt.query_row(params![id], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                role: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })
+        let mut stmt = conn.prepare(
+            "SELECT id, name, role, created_at, team, service_account FROM users WHERE id = ?1",
+        )?;
+        stmt.query_row(params![id], user_from_row)
     })?;
 
     tracing::info!(user_id = %user.id, name = %user.name, role = %user.role, "User created");
     Ok(user)
 }
 
+/// Create a service account owned by `team`. Service accounts are members
+/// that keep a team and cannot become admins or sign in to the web UI;
+/// budgets, quotas and keys work as for any user.
+pub fn create_service_account(db: &Database, name: &str, team: &str) -> Result<User, AppError> {
+    if team.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "A service account must belong to a team".to_string(),
+        ));
+    }
+    let id = Uuid::new_v4().to_string();
+    let user = db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO users (id, name, role, team, service_account) \
+             VALUES (?1, ?2, 'member', ?3, 1)",
+            params![id, name, team.trim()],
+        )?;
+        conn.query_row(
+            "SELECT id, name, role, created_at, team, service_account FROM users WHERE id = ?1",
+            params![id],
+            user_from_row,
+        )
+    })?;
+
+    tracing::info!(user_id = %user.id, name = %user.name, team = %team, "Service account created");
+    Ok(user)
+}
+
 /// List all users.
 pub fn list_users(db: &Database) -> Result<Vec<User>, AppError> {
     let users = db.with_conn(|conn| {
         let mut stmt =
-            conn.prepare("SELECT id, name, role, created_at FROM users ORDER BY created_at")?;
-        let rows = stmt.query_map([], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                role: row.get(2)?,
-                created_at: row.get(3)?,
-            })
-        })?;
+            conn.prepare("SELECT id, name, role, created_at, team, service_account FROM users ORDER BY created_at")?;
+        let rows = stmt.query_map([], user_from_row)?;
         rows.collect::<Result<Vec<_>, _>>()
     })?;
     Ok(users)
@@ -97,16 +168,9 @@ pub fn list_users(db: &Database) -> Result<Vec<User>, AppError> {
 pub fn get_user_by_name(db: &Database, name: &str) -> Result<User, AppError> {
     db.with_conn(|conn| {
         conn.query_row(
-            "SELECT id, name, role, created_at FROM users WHERE name = ?1",
+            "SELECT id, name, role, created_at, team, service_account FROM users WHERE name = ?1",
             [name],
-            |row| {
-                Ok(User {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    role: row.get(2)?,
-                    created_at: row.get(3)?,
-                })
-            },
+            user_from_row,
         )
     })
     .map_err(|_| AppError::NotFound(format!("User not found: {name}")))
@@ -116,16 +180,10 @@ pub fn get_user_by_name(db: &Database, name: &str) -> Result<User, AppError> {
 pub fn get_user(db: &Database, user_id: &str) -> Result<User, AppError> {
     let user = db
         .with_conn(|conn| {
-            let mut stmt =
-                conn.prepare("SELECT id, name, role, created_at FROM users WHERE id = ?1")?;
-            stmt.query_row(params![user_id], |row| {
-                Ok(User {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    role: row.get(2)?,
-                    created_at: row.get(3)?,
-                })
-            })
+            let mut stmt = conn.prepare(
+                "SELECT id, name, role, created_at, team, service_account FROM users WHERE id = ?1",
+            )?;
+            stmt.query_row(params![user_id], user_from_row)
         })
         .map_err(|e| match e {
             rusqlite::Error::QueryReturnedNoRows => {
@@ -136,6 +194,55 @@ pub fn get_user(db: &Database, user_id: &str) -> Result<User, AppError> {
     Ok(user)
 }
 
+/// Assign a user to a team, or remove them from their team with `None`.
+pub fn set_user_team(db: &Database, user_id: &str, team: Option<&str>) -> Result<(), AppError> {
+    if team.is_none() && get_user(db, user_id)?.service_account {
+        return Err(AppError::BadRequest(
+            "A service account must belong to a team".to_string(),
+        ));
+    }
+    let updated = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE users SET team = ?1 WHERE id = ?2",
+            params![team, user_id],
+        )
+    })?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("User '{user_id}' not found")));
+    }
+
+    tracing::info!(user_id = %user_id, team = ?team, "User team updated");
+    Ok(())
+}
+
+/// Change a user's role.
+pub fn set_user_role(db: &Database, user_id: &str, role: &str) -> Result<(), AppError> {
+    if role != "admin" && role != "member" {
+        return Err(AppError::BadRequest(format!(
+            "Invalid role '{role}': must be 'admin' or 'member'"
+        )));
+    }
+    if role == "admin" && get_user(db, user_id)?.service_account {
+        return Err(AppError::BadRequest(
+            "Service accounts cannot be admins".to_string(),
+        ));
+    }
+    let updated = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE users SET role = ?1 WHERE id = ?2",
+            params![role, user_id],
+        )
+    })?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("User '{user_id}' not found")));
+    }
+
+    tracing::info!(user_id = %user_id, role = %role, "User role updated");
+    Ok(())
+}
+
 /// Delete a user and all associated API keys (cascade).
 pub fn delete_user(db: &Database, user_id: &str) -> Result<(), AppError> {
     let deleted =
@@ -179,19 +286,11 @@ pub fn create_api_key(
             params![key_id, user_id, hash, prefix, label],
         )?;
 
-        let mut stmt = conn.prepare(
-            "SELECT id, user_id, key_prefix, label, created_at, last_used FROM api_keys WHERE id = ?1",
-        )?;
-        stmt.query_row(params![key_id], |row| {
-            Ok(ApiKeyInfo {
-                id: row.get(0)?,
-                user_id: row.get(1)?,
-                key_prefix: row.get(2)?,
-                label: row.get(3)?,
-                created_at: row.get(4)?,
-                last_used: row.get(5)?,
-            })
-        })
+        conn.query_row(
+            &format!("SELECT {API_KEY_COLUMNS} FROM api_keys WHERE id = ?1"),
+            params![key_id],
+            api_key_from_row,
+        )
     })?;
 
     tracing::info!(
@@ -204,28 +303,205 @@ pub fn create_api_key(
     Ok(CreatedApiKey { info, plaintext })
 }
 
+/// Store a key whose plaintext is held elsewhere, from its argon2 hash and
+/// display prefix. Used for keys declared in `[provisioning]`; the declared
+/// hash is kept so the key is still found after its hash is upgraded.
+pub fn insert_api_key_hash(
+    db: &Database,
+    user_id: &str,
+    hash: &str,
+    prefix: &str,
+    label: &str,
+    reasoning_mode: ReasoningMode,
+) -> Result<ApiKeyInfo, AppError> {
+    let key_id = Uuid::new_v4().to_string();
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO api_keys (id, user_id, key_hash, key_prefix, label, reasoning_mode, \
+             provisioned_hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?3)",
+            params![
+                key_id,
+                user_id,
+                hash,
+                prefix,
+                label,
+                reasoning_mode.to_string()
+            ],
+        )
+    })?;
+    tracing::info!(key_id = %key_id, user_id = %user_id, prefix = %prefix, "API key provisioned");
+    get_api_key(db, &key_id)
+}
+
+/// The key stored or provisioned with exactly this hash, if any.
+pub fn find_api_key_by_hash(db: &Database, hash: &str) -> Result<Option<ApiKeyInfo>, AppError> {
+    let key = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT id FROM api_keys WHERE key_hash = ?1 OR provisioned_hash = ?1",
+            params![hash],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+    })?;
+    key.map(|id| get_api_key(db, &id)).transpose()
+}
+
+/// Remember the hash a key was provisioned with, for keys provisioned
+/// before it was recorded.
+pub fn set_api_key_provisioned_hash(
+    db: &Database,
+    key_id: &str,
+    hash: &str,
+) -> Result<(), AppError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE api_keys SET provisioned_hash = ?2 \
+             WHERE id = ?1 AND provisioned_hash IS NULL",
+            params![key_id, hash],
+        )
+    })?;
+    Ok(())
+}
+
+/// Change a key's label.
+pub fn set_api_key_label(db: &Database, key_id: &str, label: &str) -> Result<(), AppError> {
+    let updated = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE api_keys SET label = ?1 WHERE id = ?2",
+            params![label, key_id],
+        )
+    })?;
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("API key '{key_id}' not found")));
+    }
+    Ok(())
+}
+
 /// List all API keys for a user (metadata only, no hashes).
 pub fn list_api_keys(db: &Database, user_id: &str) -> Result<Vec<ApiKeyInfo>, AppError> {
     let keys = db.with_conn(|conn| {
-        let mut stmt = conn.prepare(
-            "SELECT id, user_id, key_prefix, label, created_at, last_used \
-             FROM api_keys WHERE user_id = ?1 ORDER BY created_at",
-        )?;
-        let rows = stmt.query_map(params![user_id], |row| {
-            Ok(ApiKeyInfo {
-                id: row.get(0)?,
-                user_id: row.get(1)?,
-                key_prefix: row.get(2)?,
-                label: row.get(3)?,
-                created_at: row.get(4)?,
-                last_used: row.get(5)?,
-            })
-        })?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {API_KEY_COLUMNS} FROM api_keys WHERE user_id = ?1 ORDER BY created_at"
+        ))?;
+        let rows = stmt.query_map(params![user_id], api_key_from_row)?;
         rows.collect::<Result<Vec<_>, _>>()
     })?;
     Ok(keys)
 }
 
+/// Look up one API key's metadata by its ID.
+pub fn get_api_key(db: &Database, key_id: &str) -> Result<ApiKeyInfo, AppError> {
+    let key = db.with_conn(|conn| {
+        conn.query_row(
+            &format!("SELECT {API_KEY_COLUMNS} FROM api_keys WHERE id = ?1"),
+            params![key_id],
+            api_key_from_row,
+        )
+        .optional()
+    })?;
+    key.ok_or_else(|| AppError::NotFound(format!("API key '{key_id}' not found")))
+}
+
+/// Change how `reasoning_content` is exposed for requests made with a key.
+pub fn set_api_key_reasoning_mode(
+    db: &Database,
+    key_id: &str,
+    mode: ReasoningMode,
+) -> Result<(), AppError> {
+    let updated = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE api_keys SET reasoning_mode = ?1 WHERE id = ?2",
+            params![mode.to_string(), key_id],
+        )
+    })?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("API key '{key_id}' not found")));
+    }
+
+    tracing::info!(key_id = %key_id, reasoning_mode = %mode, "API key reasoning mode updated");
+    Ok(())
+}
+
+/// Turn the upstream error detail on or off for requests made with a key.
+pub fn set_api_key_debug_errors(
+    db: &Database,
+    key_id: &str,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let updated = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE api_keys SET debug_errors = ?1 WHERE id = ?2",
+            params![enabled, key_id],
+        )
+    })?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("API key '{key_id}' not found")));
+    }
+
+    tracing::info!(key_id = %key_id, enabled, "API key error debugging updated");
+    Ok(())
+}
+
+/// Whether a key has the upstream error detail turned on. Unknown keys
+/// have not.
+pub fn api_key_debug_errors(db: &Database, key_id: &str) -> Result<bool, AppError> {
+    let enabled = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT debug_errors FROM api_keys WHERE id = ?1",
+            params![key_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    Ok(enabled.unwrap_or(false))
+}
+
+/// Set the scheduling priority of requests made with a key.
+pub fn set_api_key_priority(
+    db: &Database,
+    key_id: &str,
+    priority: Priority,
+) -> Result<(), AppError> {
+    let updated = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE api_keys SET priority = ?1 WHERE id = ?2",
+            params![priority.to_string(), key_id],
+        )
+    })?;
+
+    if updated == 0 {
+        return Err(AppError::NotFound(format!("API key '{key_id}' not found")));
+    }
+
+    tracing::info!(key_id = %key_id, priority = %priority, "API key priority updated");
+    Ok(())
+}
+
+/// A key's scheduling priority. Unknown keys have the default.
+pub fn api_key_priority(db: &Database, key_id: &str) -> Result<Priority, AppError> {
+    let priority: Option<String> = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT priority FROM api_keys WHERE id = ?1",
+            params![key_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    Ok(priority.map(parse_priority).unwrap_or_default())
+}
+
+/// Parse a stored priority, falling back to the default for unknown values.
+fn parse_priority(value: String) -> Priority {
+    value.parse().unwrap_or_default()
+}
+
+/// Parse a stored reasoning mode, falling back to the default for unknown values.
+pub(crate) fn parse_reasoning_mode(value: String) -> ReasoningMode {
+    value.parse().unwrap_or_default()
+}
+
 /// Revoke (delete) an API key by its ID.
 pub fn revoke_api_key(db: &Database, key_id: &str) -> Result<(), AppError> {
     let deleted =
@@ -239,20 +515,178 @@ pub fn revoke_api_key(db: &Database, key_id: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+/// Revoke one of `user_id`'s own API keys. Keys of other users are reported
+/// as not found.
+pub fn revoke_user_api_key(db: &Database, user_id: &str, key_id: &str) -> Result<(), AppError> {
+    let deleted = db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM api_keys WHERE id = ?1 AND user_id = ?2",
+            params![key_id, user_id],
+        )
+    })?;
+
+    if deleted == 0 {
+        return Err(AppError::NotFound(format!("API key '{key_id}' not found")));
+    }
+
+    tracing::info!(key_id = %key_id, user_id = %user_id, "API key revoked by its owner");
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Stale keys
+// ---------------------------------------------------------------------------
+
+/// How often the stale key sweep runs.
+const STALE_KEY_SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// An enabled API key that has not been used for a while.
+#[derive(Debug, Clone, Serialize)]
+pub struct StaleApiKey {
+    #[serde(flatten)]
+    pub key: ApiKeyInfo,
+    /// Name of the key's user.
+    pub user_name: String,
+}
+
+/// Enabled keys not used in the last `days` days, least recently used
+/// first. Keys never used count from their creation.
+pub fn stale_api_keys(db: &Database, days: u32) -> Result<Vec<StaleApiKey>, AppError> {
+    let keys = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {API_KEY_COLUMNS}, (SELECT name FROM users WHERE id = api_keys.user_id) \
+             FROM api_keys \
+             WHERE disabled_at IS NULL \
+             AND COALESCE(last_used, created_at) < datetime('now', ?1) \
+             ORDER BY COALESCE(last_used, created_at)"
+        ))?;
+        let rows = stmt.query_map(params![format!("-{days} days")], |row| {
+            Ok(StaleApiKey {
+                key: api_key_from_row(row)?,
+                user_name: row.get(11)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(keys)
+}
+
+/// Disable every key not used in the last `days` days, returning them.
+pub fn disable_stale_api_keys(db: &Database, days: u32) -> Result<Vec<StaleApiKey>, AppError> {
+    let mut disabled = Vec::new();
+    for mut stale in stale_api_keys(db, days)? {
+        let updated = db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE api_keys SET disabled_at = datetime('now') \
+                 WHERE id = ?1 AND disabled_at IS NULL",
+                params![stale.key.id],
+            )
+        })?;
+        if updated == 1 {
+            tracing::info!(key_id = %stale.key.id, user_id = %stale.key.user_id, "Stale API key disabled");
+            stale.key = get_api_key(db, &stale.key.id)?;
+            disabled.push(stale);
+        }
+    }
+    Ok(disabled)
+}
+
+/// Re-enable a disabled key. Re-enabling counts as a use, so the key gets
+/// a full period before it is considered stale again.
+pub fn enable_api_key(db: &Database, key_id: &str) -> Result<ApiKeyInfo, AppError> {
+    let updated = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE api_keys SET disabled_at = NULL, last_used = datetime('now') \
+             WHERE id = ?1 AND disabled_at IS NOT NULL",
+            params![key_id],
+        )
+    })?;
+    let key = get_api_key(db, key_id)?;
+    if updated == 1 {
+        tracing::info!(key_id = %key_id, "API key re-enabled");
+    }
+    Ok(key)
+}
+
+/// Disable keys unused for `days` days every hour, on the leader only.
+/// Each disabled key is recorded in the admin audit log.
+pub fn spawn_stale_key_sweep(
+    db: Database,
+    days: u32,
+    leader: Arc<Leader>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let actor = AuthUser {
+            user_id: "system".to_string(),
+            name: "system".to_string(),
+            role: "admin".to_string(),
+            reasoning_mode: ReasoningMode::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin: Default::default(),
+        };
+        let mut ticker = tokio::time::interval(STALE_KEY_SWEEP_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if !leader.is_leader() {
+                continue;
+            }
+            match disable_stale_api_keys(&db, days) {
+                Ok(disabled) => {
+                    for stale in disabled {
+                        audit_log::record(
+                            &db,
+                            &actor,
+                            "key.disable_stale",
+                            &stale.key.id,
+                            None,
+                            serde_json::to_value(&stale).ok(),
+                        );
+                    }
+                }
+                Err(e) => tracing::warn!(error = %e, "Stale API key sweep failed"),
+            }
+        }
+    })
+}
+
 // ---------------------------------------------------------------------------
 // Auth validation (used by middleware)
 // ---------------------------------------------------------------------------
 
 /// Validate a plaintext API key against the database.
 ///
-/// Iterates all stored key hashes and verifies with argon2. On success,
-/// updates `last_used` and returns the associated `AuthUser`.
+/// Iterates all enabled key hashes and verifies with argon2. On success,
+/// updates `last_used`, upgrades an outdated hash (see
+/// [`keys::needs_rehash`]) and returns the associated `AuthUser`.
 pub fn validate_api_key(db: &Database, plaintext: &str) -> Result<crate::auth::AuthUser, AppError> {
+    validate_api_key_with_id(db, plaintext).map(|(_, user)| user)
+}
+
+/// Like [`validate_api_key`], also returning the ID of the matching key.
+pub fn validate_api_key_with_id(
+    db: &Database,
+    plaintext: &str,
+) -> Result<(String, crate::auth::AuthUser), AppError> {
+    validate_api_key_from(db, plaintext, None)
+}
+
+/// Like [`validate_api_key_with_id`], recording `ip` as the key's last
+/// client IP.
+pub fn validate_api_key_from(
+    db: &Database,
+    plaintext: &str,
+    ip: Option<IpAddr>,
+) -> Result<(String, crate::auth::AuthUser), AppError> {
     let rows = db.with_conn(|conn| {
         let mut stmt = conn.prepare(
-            "SELECT ak.id, ak.key_hash, u.id, u.name, u.role \
+            "SELECT ak.id, ak.key_hash, u.id, u.name, u.role, ak.reasoning_mode, u.team \
              FROM api_keys ak \
-             JOIN users u ON ak.user_id = u.id",
+             JOIN users u ON ak.user_id = u.id \
+             WHERE ak.disabled_at IS NULL",
         )?;
         let mapped = stmt.query_map([], |row| {
             Ok((
@@ -261,35 +695,91 @@ pub fn validate_api_key(db: &Database, plaintext: &str) -> Result<crate::auth::A
                 row.get::<_, String>(2)?,
                 row.get::<_, String>(3)?,
                 row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, Option<String>>(6)?,
             ))
         })?;
         mapped.collect::<Result<Vec<_>, _>>()
     })?;
 
-    for (key_id, key_hash, user_id, name, role) in &rows {
+    for (key_id, key_hash, user_id, name, role, reasoning_mode, team) in &rows {
         let ok = keys::verify_key(plaintext, key_hash)
             .map_err(|e| AppError::Internal(format!("Key verification error: {e}")))?;
 
         if ok {
-            // Update last_used timestamp asynchronously (best-effort).
+            // Update last_used timestamp and IP (best-effort).
             let _ = db.with_conn(|conn| {
                 conn.execute(
-                    "UPDATE api_keys SET last_used = datetime('now') WHERE id = ?1",
-                    params![key_id],
+                    "UPDATE api_keys SET last_used = datetime('now'), \
+                     last_used_ip = COALESCE(?2, last_used_ip) WHERE id = ?1",
+                    params![key_id, ip.map(|ip| ip.to_string())],
                 )
             });
+            if keys::needs_rehash(key_hash) {
+                rehash_api_key(db, key_id, key_hash, plaintext);
+            }
 
-            return Ok(crate::auth::AuthUser {
-                user_id: user_id.clone(),
-                name: name.clone(),
-                role: role.clone(),
-            });
+            return Ok((
+                key_id.clone(),
+                crate::auth::AuthUser {
+                    user_id: user_id.clone(),
+                    name: name.clone(),
+                    role: role.clone(),
+                    reasoning_mode: parse_reasoning_mode(reasoning_mode.clone()),
+                    team: team.clone(),
+                    impersonator: None,
+                    key_id: Some(key_id.clone()),
+                    scope: None,
+                    origin: Default::default(),
+                },
+            ));
         }
     }
 
     Err(AppError::Unauthorized("Invalid API key".to_string()))
 }
 
+/// Replace the outdated hash of a key that just verified with a hash in the
+/// current format. Best-effort: the old hash keeps working if this fails.
+fn rehash_api_key(db: &Database, key_id: &str, old_hash: &str, plaintext: &str) {
+    let hash = match keys::hash_key(plaintext) {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::warn!(key_id = %key_id, error = %e, "Failed to rehash API key");
+            return;
+        }
+    };
+    // Matching the old hash keeps a concurrent rehash from being overwritten.
+    let result = db.with_conn(|conn| {
+        conn.execute(
+            "UPDATE api_keys SET key_hash = ?1 WHERE id = ?2 AND key_hash = ?3",
+            params![hash, key_id, old_hash],
+        )
+    });
+    match result {
+        Ok(1) => tracing::info!(key_id = %key_id, "Upgraded API key hash"),
+        Ok(_) => {}
+        Err(e) => tracing::warn!(key_id = %key_id, error = %e, "Failed to store rehashed API key"),
+    }
+}
+
+/// Owner of the stored API key with the same display prefix as `plaintext`,
+/// so failed attempts with a known prefix count against that account.
+pub fn key_prefix_owner(db: &Database, plaintext: &str) -> Result<Option<String>, AppError> {
+    let Some(prefix) = keys::display_prefix(plaintext) else {
+        return Ok(None);
+    };
+    let owner = db.with_conn(|conn| {
+        conn.query_row(
+            "SELECT user_id FROM api_keys WHERE key_prefix = ?1 LIMIT 1",
+            params![prefix],
+            |row| row.get(0),
+        )
+        .optional()
+    })?;
+    Ok(owner)
+}
+
 // ---------------------------------------------------------------------------
 // Bootstrap
 // ---------------------------------------------------------------------------
@@ -350,6 +840,29 @@ mod tests {
         assert_eq!(fetched.id, user.id);
     }
 
+    #[test]
+    fn test_service_account_rules() {
+        let db = test_db();
+        assert!(create_service_account(&db, "ci-bot", " ").is_err());
+        let bot = create_service_account(&db, "ci-bot", "platform").unwrap();
+        assert!(bot.service_account);
+        assert_eq!(bot.role, "member");
+        assert_eq!(bot.team.as_deref(), Some("platform"));
+        assert!(!create_user(&db, "alice", "member").unwrap().service_account);
+
+        // Owned by a team, never an admin.
+        assert!(set_user_team(&db, &bot.id, None).is_err());
+        assert!(set_user_team(&db, &bot.id, Some("data")).is_ok());
+        assert!(set_user_role(&db, &bot.id, "admin").is_err());
+
+        // Keys work as for any user.
+        let key = create_api_key(&db, &bot.id, "pipeline").unwrap();
+        assert_eq!(
+            validate_api_key(&db, &key.plaintext).unwrap().user_id,
+            bot.id
+        );
+    }
+
     #[test]
     fn test_get_user_by_name() {
         let db = test_db();
@@ -448,6 +961,20 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_revoke_user_api_key_only_own_keys() {
+        let db = test_db();
+        let alice = create_user(&db, "alice", "member").unwrap();
+        let bob = create_user(&db, "bob", "member").unwrap();
+        let created = create_api_key(&db, &alice.id, "alice's key").unwrap();
+
+        assert!(revoke_user_api_key(&db, &bob.id, &created.info.id).is_err());
+        assert_eq!(list_api_keys(&db, &alice.id).unwrap().len(), 1);
+
+        revoke_user_api_key(&db, &alice.id, &created.info.id).unwrap();
+        assert!(list_api_keys(&db, &alice.id).unwrap().is_empty());
+    }
+
     #[test]
     fn test_validate_api_key_success() {
         let db = test_db();
@@ -483,6 +1010,111 @@ mod tests {
         validate_api_key(&db, &created.plaintext).unwrap();
         let keys = list_api_keys(&db, &user.id).unwrap();
         assert!(keys[0].last_used.is_some());
+        assert!(keys[0].last_used_ip.is_none());
+
+        let ip = "203.0.113.7".parse().ok();
+        validate_api_key_from(&db, &created.plaintext, ip).unwrap();
+        let key = get_api_key(&db, &created.info.id).unwrap();
+        assert_eq!(key.last_used_ip.as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn test_stale_keys_are_reported_and_disabled() {
+        let db = test_db();
+        let user = create_user(&db, "alice", "member").unwrap();
+        let old = create_api_key(&db, &user.id, "old").unwrap();
+        let fresh = create_api_key(&db, &user.id, "fresh").unwrap();
+        let never_used = create_api_key(&db, &user.id, "never").unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE api_keys SET last_used = datetime('now', '-120 days') WHERE id = ?1",
+                params![old.info.id],
+            )?;
+            conn.execute(
+                "UPDATE api_keys SET created_at = datetime('now', '-200 days')",
+                [],
+            )
+        })
+        .unwrap();
+        validate_api_key(&db, &fresh.plaintext).unwrap();
+
+        // Never-used keys count from their creation, oldest first.
+        let stale = stale_api_keys(&db, 90).unwrap();
+        let ids: Vec<&str> = stale.iter().map(|s| s.key.id.as_str()).collect();
+        assert_eq!(ids, [never_used.info.id.as_str(), old.info.id.as_str()]);
+        assert_eq!(stale[0].user_name, "alice");
+        assert_eq!(stale_api_keys(&db, 150).unwrap().len(), 1);
+
+        let disabled = disable_stale_api_keys(&db, 90).unwrap();
+        assert_eq!(disabled.len(), 2);
+        assert!(disabled.iter().all(|s| s.key.disabled_at.is_some()));
+        assert!(stale_api_keys(&db, 90).unwrap().is_empty());
+        assert!(validate_api_key(&db, &old.plaintext).is_err());
+        assert!(validate_api_key(&db, &fresh.plaintext).is_ok());
+
+        // Re-enabling restarts the clock.
+        let key = enable_api_key(&db, &old.info.id).unwrap();
+        assert!(key.disabled_at.is_none());
+        assert!(validate_api_key(&db, &old.plaintext).is_ok());
+        assert_eq!(stale_api_keys(&db, 90).unwrap().len(), 0);
+        assert!(enable_api_key(&db, "missing").is_err());
+    }
+
+    #[test]
+    fn test_set_api_key_reasoning_mode() {
+        let db = test_db();
+        let user = create_user(&db, "alice", "admin").unwrap();
+        let created = create_api_key(&db, &user.id, "thinking").unwrap();
+        assert_eq!(created.info.reasoning_mode, ReasoningMode::Forward);
+
+        set_api_key_reasoning_mode(&db, &created.info.id, ReasoningMode::Inline).unwrap();
+
+        let keys = list_api_keys(&db, &user.id).unwrap();
+        assert_eq!(keys[0].reasoning_mode, ReasoningMode::Inline);
+        let auth_user = validate_api_key(&db, &created.plaintext).unwrap();
+        assert_eq!(auth_user.reasoning_mode, ReasoningMode::Inline);
+
+        assert!(set_api_key_reasoning_mode(&db, "nonexistent", ReasoningMode::Strip).is_err());
+    }
+
+    #[test]
+    fn test_set_api_key_debug_errors() {
+        let db = test_db();
+        let user = create_user(&db, "alice", "member").unwrap();
+        let created = create_api_key(&db, &user.id, "debug").unwrap();
+        assert!(!created.info.debug_errors);
+        assert!(!api_key_debug_errors(&db, &created.info.id).unwrap());
+
+        set_api_key_debug_errors(&db, &created.info.id, true).unwrap();
+        assert!(api_key_debug_errors(&db, &created.info.id).unwrap());
+        assert!(get_api_key(&db, &created.info.id).unwrap().debug_errors);
+
+        assert!(!api_key_debug_errors(&db, "nonexistent").unwrap());
+        assert!(set_api_key_debug_errors(&db, "nonexistent", true).is_err());
+    }
+
+    #[test]
+    fn test_set_api_key_priority() {
+        let db = test_db();
+        let user = create_user(&db, "alice", "member").unwrap();
+        let created = create_api_key(&db, &user.id, "etl").unwrap();
+        assert_eq!(created.info.priority, Priority::Default);
+
+        set_api_key_priority(&db, &created.info.id, Priority::Batch).unwrap();
+        assert_eq!(
+            api_key_priority(&db, &created.info.id).unwrap(),
+            Priority::Batch
+        );
+        assert_eq!(
+            get_api_key(&db, &created.info.id).unwrap().priority,
+            Priority::Batch
+        );
+
+        assert_eq!(
+            api_key_priority(&db, "nonexistent").unwrap(),
+            Priority::Default
+        );
+        assert!(set_api_key_priority(&db, "nonexistent", Priority::Batch).is_err());
     }
 
     #[test]
@@ -0,0 +1,192 @@
+//! Cost attribution beyond the authenticated user.
+//!
+//! Clients can say who a request is really for with OpenAI's `user` field
+//! (their own end user) and label it with an `X-Gaud-Tags` header such as
+//! `project=alpha,env=prod`. Both are stored on the usage log entry, where
+//! the usage endpoints can filter and group by them. Requests an admin sent
+//! on a user's behalf also record the admin, and requests made with a
+//! scoped token the token, whose token budget they count against. Every
+//! request also records the API key it was made with and where it came from
+//! ([`RequestOrigin`]), for abuse investigation and per-application
+//! breakdowns.
+
+use std::collections::BTreeMap;
+
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{AuthUser, RequestOrigin};
+use crate::error::AppError;
+use crate::experiments::Assignment;
+
+/// Request header carrying cost attribution tags.
+pub const TAGS_HEADER: &str = "x-gaud-tags";
+
+/// Most tags accepted on one request.
+const MAX_TAGS: usize = 16;
+/// Longest accepted tag key.
+const MAX_KEY_LEN: usize = 64;
+/// Longest accepted tag value or end user.
+const MAX_VALUE_LEN: usize = 256;
+
+/// Who a request is attributed to, besides the API key's user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attribution {
+    /// The client's end user (OpenAI `user`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_user: Option<String>,
+    /// Tags from `X-Gaud-Tags`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// Admin who sent the request as the user (`X-Gaud-On-Behalf-Of`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<String>,
+    /// ID of the scoped token the request was made with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoped_token: Option<String>,
+    /// Experiment variant the request was given (`[[experiments]]`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experiment: Option<Assignment>,
+    /// API key the request was made with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// Client IP, user agent and path of the request.
+    #[serde(default, flatten)]
+    pub origin: RequestOrigin,
+}
+
+impl Attribution {
+    /// Read the tags header and take `end_user` from the request body.
+    pub fn from_request(headers: &HeaderMap, end_user: Option<&str>) -> Result<Self, AppError> {
+        Self::new(end_user, tags_from_headers(headers)?)
+    }
+
+    /// Attribution with already parsed `tags`.
+    pub fn new(end_user: Option<&str>, tags: BTreeMap<String, String>) -> Result<Self, AppError> {
+        let end_user = end_user.map(str::trim).filter(|u| !u.is_empty());
+        if end_user.is_some_and(|u| u.chars().count() > MAX_VALUE_LEN) {
+            return Err(AppError::InvalidParam {
+                param: "user".to_string(),
+                message: format!("longer than {MAX_VALUE_LEN} characters"),
+            });
+        }
+        Ok(Self {
+            end_user: end_user.map(String::from),
+            tags,
+            impersonator: None,
+            scoped_token: None,
+            experiment: None,
+            key_id: None,
+            origin: RequestOrigin::default(),
+        })
+    }
+
+    /// Record the admin impersonating `user` and the scoped token used,
+    /// if any, and the key and origin of the request.
+    pub fn with_user(mut self, user: &AuthUser) -> Self {
+        self.impersonator = user.impersonator.clone();
+        self.scoped_token = user.scope.as_ref().map(|s| s.id.clone());
+        self.key_id = user.key_id.clone();
+        self.origin = user.origin.clone();
+        self
+    }
+
+    /// Tags as stored in `usage_log.tags`, or `None` without tags.
+    pub fn tags_json(&self) -> Option<String> {
+        (!self.tags.is_empty()).then(|| serde_json::to_string(&self.tags).unwrap_or_default())
+    }
+}
+
+/// Tags from the `X-Gaud-Tags` header, if present.
+pub fn tags_from_headers(headers: &HeaderMap) -> Result<BTreeMap<String, String>, AppError> {
+    let Some(value) = headers.get(TAGS_HEADER) else {
+        return Ok(BTreeMap::new());
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| AppError::BadRequest("X-Gaud-Tags must be plain ASCII".to_string()))?;
+    parse_tags(value).map_err(AppError::BadRequest)
+}
+
+/// Parse `key=value` pairs separated by commas. Keys are letters, digits,
+/// `-` and `_`; a key given twice keeps its last value.
+pub fn parse_tags(s: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut tags = BTreeMap::new();
+    for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let Some((key, value)) = pair.split_once('=') else {
+            return Err(format!("Invalid tag '{pair}', expected key=value"));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if !is_valid_key(key) {
+            return Err(format!(
+                "Invalid tag key '{key}': use 1-{MAX_KEY_LEN} letters, digits, '-' or '_'"
+            ));
+        }
+        if value.is_empty() || value.chars().count() > MAX_VALUE_LEN {
+            return Err(format!(
+                "Tag '{key}' needs a value of 1-{MAX_VALUE_LEN} characters"
+            ));
+        }
+        tags.insert(key.to_string(), value.to_string());
+    }
+    if tags.len() > MAX_TAGS {
+        return Err(format!("At most {MAX_TAGS} tags are allowed per request"));
+    }
+    Ok(tags)
+}
+
+/// Whether `key` may be used as a tag key.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// JSON path of a tag in `usage_log.tags`, for `json_extract`.
+pub fn tag_path(key: &str) -> String {
+    format!("$.\"{key}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags() {
+        let tags = parse_tags("project=alpha, env=prod,,team=ml-ops").unwrap();
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags["project"], "alpha");
+        assert_eq!(tags["team"], "ml-ops");
+        assert!(parse_tags("").unwrap().is_empty());
+
+        assert!(parse_tags("project").is_err());
+        assert!(parse_tags("pro ject=alpha").is_err());
+        assert!(parse_tags("project=").is_err());
+        let many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("k{i}=v")).collect();
+        assert!(parse_tags(&many.join(",")).is_err());
+    }
+
+    #[test]
+    fn test_from_request() {
+        let mut headers = HeaderMap::new();
+        headers.insert(TAGS_HEADER, "project=alpha".parse().unwrap());
+        let attribution = Attribution::from_request(&headers, Some(" customer-42 ")).unwrap();
+        assert_eq!(attribution.end_user.as_deref(), Some("customer-42"));
+        assert_eq!(
+            attribution.tags_json().as_deref(),
+            Some(r#"{"project":"alpha"}"#)
+        );
+
+        let none = Attribution::from_request(&HeaderMap::new(), Some("")).unwrap();
+        assert_eq!(none, Attribution::default());
+        assert!(none.tags_json().is_none());
+
+        headers.insert(TAGS_HEADER, "bad".parse().unwrap());
+        assert!(matches!(
+            Attribution::from_request(&headers, None),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
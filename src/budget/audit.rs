@@ -1,12 +1,15 @@
 use std::time::Duration;
 
-use rusqlite::params;
+use rusqlite::{OptionalExtension, params};
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
 use crate::budget::AuditEntry;
 use crate::budget::BudgetTracker;
+use crate::budget::rollup;
+use crate::budget::tail::RequestTail;
 use crate::db::Database;
+use crate::providers::cost::CostCalculator;
 
 /// Maximum number of entries to buffer before flushing, regardless of timer.
 const BATCH_SIZE: usize = 100;
@@ -16,14 +19,20 @@ const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Spawn a background task that reads `AuditEntry` values from the channel
 /// and batch-writes them to the `usage_log` table.  Also atomically updates
-/// the user's `monthly_used` / `daily_used` counters in the `budgets` table.
+/// the user's `monthly_used` / `daily_used` and premium request counters in
+/// the `budgets` table, and charges each entry's tokens to the
+/// tokens-per-minute quotas as soon as it arrives. User and team markups
+/// from `costs` are applied before logging; the provider cost is kept in
+/// `raw_cost`.
 ///
 /// The returned `JoinHandle` can be used to wait for graceful shutdown (the
 /// task exits when the sender half is dropped and remaining entries are
 /// flushed).
 pub fn spawn_audit_logger(
     db: Database,
-    _budget: std::sync::Arc<BudgetTracker>,
+    budget: std::sync::Arc<BudgetTracker>,
+    costs: std::sync::Arc<CostCalculator>,
+    tail: std::sync::Arc<RequestTail>,
     mut rx: mpsc::UnboundedReceiver<AuditEntry>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
@@ -37,15 +46,20 @@ pub fn spawn_audit_logger(
                 entry = rx.recv() => {
                     match entry {
                         Some(e) => {
+                            tail.publish(&e);
+                            budget.record_tokens(
+                                &e.user_id,
+                                u64::from(e.input_tokens) + u64::from(e.output_tokens),
+                            );
                             buffer.push(e);
                             if buffer.len() >= BATCH_SIZE {
-                                flush_batch(&db, &mut buffer);
+                                flush_batch(&db, &costs, &mut buffer);
                             }
                         }
                         None => {
                             // Channel closed -- flush remaining and exit.
                             if !buffer.is_empty() {
-                                flush_batch(&db, &mut buffer);
+                                flush_batch(&db, &costs, &mut buffer);
                             }
                             tracing::info!("Audit logger shutting down");
                             break;
@@ -54,7 +68,7 @@ pub fn spawn_audit_logger(
                 }
                 _ = interval.tick() => {
                     if !buffer.is_empty() {
-                        flush_batch(&db, &mut buffer);
+                        flush_batch(&db, &costs, &mut buffer);
                     }
                 }
             }
@@ -63,11 +77,11 @@ pub fn spawn_audit_logger(
 }
 
 /// Write a batch of audit entries to the database in a single transaction.
-fn flush_batch(db: &Database, buffer: &mut Vec<AuditEntry>) {
+fn flush_batch(db: &Database, costs: &CostCalculator, buffer: &mut Vec<AuditEntry>) {
     let entries = std::mem::take(buffer);
     let count = entries.len();
 
-    if let Err(e) = write_entries(db, &entries) {
+    if let Err(e) = write_entries(db, costs, &entries) {
         tracing::error!(count, error = %e, "Failed to flush audit batch");
         // Put entries back so we can retry on the next tick.
         buffer.extend(entries);
@@ -79,25 +93,62 @@ fn flush_batch(db: &Database, buffer: &mut Vec<AuditEntry>) {
 /// Perform the actual DB writes inside a transaction.
 ///
 /// Inserts rows into `usage_log` and atomically updates the `budgets` table
-/// counters (`monthly_used`, `daily_used`) for each entry with a non-zero cost.
-fn write_entries(db: &Database, entries: &[AuditEntry]) -> Result<(), Box<dyn std::error::Error>> {
+/// counters (`monthly_used`, `daily_used`) for each entry with a non-zero
+/// marked-up cost, and the premium counters for each entry that consumed
+/// premium requests. Also adds the batch to the hourly aggregates.
+fn write_entries(
+    db: &Database,
+    costs: &CostCalculator,
+    entries: &[AuditEntry],
+) -> Result<(), Box<dyn std::error::Error>> {
     db.with_conn(|conn| {
         let tx = conn.unchecked_transaction()?;
 
         {
             let mut insert_stmt = tx.prepare_cached(
                 "INSERT INTO usage_log (id, user_id, request_id, provider, model, \
-                 input_tokens, output_tokens, cost, latency_ms, status) \
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                 input_tokens, output_tokens, audio_seconds, cost, latency_ms, status, \
+                 premium_requests, raw_cost, end_user, tags, impersonated_by, finish_reason, \
+                 experiment, experiment_variant, cost_saved, key_id, client_ip, user_agent, \
+                 path) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, \
+                 ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
             )?;
 
+            let mut team_stmt = tx.prepare_cached("SELECT team FROM users WHERE id = ?1")?;
+
             let mut update_budget_stmt = tx.prepare_cached(
                 "UPDATE budgets SET monthly_used = monthly_used + ?1, \
                  daily_used = daily_used + ?1 WHERE user_id = ?2",
             )?;
 
+            let mut update_token_stmt = tx.prepare_cached(
+                "UPDATE scoped_tokens SET used_tokens = used_tokens + ?1 WHERE id = ?2",
+            )?;
+
+            let mut update_premium_stmt = tx.prepare_cached(
+                "UPDATE budgets SET monthly_premium_used = monthly_premium_used + ?1, \
+                 daily_premium_used = daily_premium_used + ?1 WHERE user_id = ?2",
+            )?;
+
+            let has_markups = costs.has_markups();
+            let mut charged = Vec::with_capacity(entries.len());
             for entry in entries {
                 let id = Uuid::new_v4().to_string();
+                let cost = if has_markups {
+                    let team: Option<String> = team_stmt
+                        .query_row(params![entry.user_id], |row| row.get(0))
+                        .optional()?
+                        .flatten();
+                    costs.apply_markup(
+                        &entry.user_id,
+                        team.as_deref(),
+                        entry.cost,
+                        u64::from(entry.input_tokens) + u64::from(entry.output_tokens),
+                    )
+                } else {
+                    entry.cost
+                };
                 insert_stmt.execute(params![
                     id,
                     entry.user_id,
@@ -106,16 +157,40 @@ fn write_entries(db: &Database, entries: &[AuditEntry]) -> Result<(), Box<dyn st
                     entry.model,
                     entry.input_tokens,
                     entry.output_tokens,
-                    entry.cost,
+                    entry.audio_seconds,
+                    cost,
                     entry.latency_ms,
                     entry.status,
+                    entry.premium_requests,
+                    entry.cost,
+                    entry.attribution.end_user,
+                    entry.attribution.tags_json(),
+                    entry.attribution.impersonator,
+                    entry.finish_reason,
+                    entry.attribution.experiment.as_ref().map(|e| &e.experiment),
+                    entry.attribution.experiment.as_ref().map(|e| &e.variant),
+                    entry.cost_saved,
+                    entry.attribution.key_id,
+                    entry.attribution.origin.client_ip,
+                    entry.attribution.origin.user_agent,
+                    entry.attribution.origin.path,
                 ])?;
 
                 // Update budget counters atomically within the same transaction.
-                if entry.cost > 0.0 {
-                    update_budget_stmt.execute(params![entry.cost, entry.user_id])?;
+                if cost > 0.0 {
+                    update_budget_stmt.execute(params![cost, entry.user_id])?;
                 }
+                if let Some(token_id) = &entry.attribution.scoped_token {
+                    let tokens = u64::from(entry.input_tokens) + u64::from(entry.output_tokens);
+                    update_token_stmt.execute(params![tokens as i64, token_id])?;
+                }
+                if entry.premium_requests > 0.0 {
+                    update_premium_stmt.execute(params![entry.premium_requests, entry.user_id])?;
+                }
+                charged.push(cost);
             }
+
+            rollup::record_batch(&tx, chrono::Utc::now(), entries, &charged)?;
         }
 
         tx.commit()?;
@@ -131,7 +206,9 @@ fn write_entries(db: &Database, entries: &[AuditEntry]) -> Result<(), Box<dyn st
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::budget::BudgetTracker;
+    use crate::budget::quota::QuotaScope;
+    use crate::budget::{Attribution, BudgetTracker};
+    use crate::providers::cost::CostMarkup;
     use std::sync::Arc;
 
     fn test_db() -> Database {
@@ -155,9 +232,14 @@ mod tests {
             model: "test-model".to_string(),
             input_tokens: 100,
             output_tokens: 50,
+            audio_seconds: 0.0,
+            premium_requests: 0.0,
             cost,
+            cost_saved: 0.0,
             latency_ms: 200,
             status: "success".to_string(),
+            finish_reason: None,
+            attribution: Attribution::default(),
         }
     }
 
@@ -166,7 +248,7 @@ mod tests {
         let db = test_db();
         let entries = vec![make_entry("user1", 0.5)];
 
-        write_entries(&db, &entries).unwrap();
+        write_entries(&db, &CostCalculator::new(), &entries).unwrap();
 
         let count: i64 = db
             .with_conn(|conn| {
@@ -176,6 +258,55 @@ mod tests {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn test_write_entries_flags_impersonation() {
+        let db = test_db();
+        let mut entry = make_entry("user1", 0.5);
+        entry.attribution.impersonator = Some("admin-1".to_string());
+
+        write_entries(&db, &CostCalculator::new(), &[entry]).unwrap();
+
+        let impersonated_by: Option<String> = db
+            .with_conn(|conn| {
+                conn.query_row("SELECT impersonated_by FROM usage_log", [], |row| {
+                    row.get(0)
+                })
+            })
+            .unwrap();
+        assert_eq!(impersonated_by.as_deref(), Some("admin-1"));
+    }
+
+    #[test]
+    fn test_write_entries_records_origin() {
+        let db = test_db();
+        let mut entry = make_entry("user1", 0.5);
+        entry.attribution.key_id = Some("key-1".to_string());
+        entry.attribution.origin.client_ip = Some("203.0.113.7".to_string());
+        entry.attribution.origin.user_agent = Some("curl/8.5".to_string());
+        entry.attribution.origin.path = Some("/v1/chat/completions".to_string());
+
+        write_entries(&db, &CostCalculator::new(), &[entry]).unwrap();
+
+        let row: (String, String, String, String) = db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT key_id, client_ip, user_agent, path FROM usage_log",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+            })
+            .unwrap();
+        assert_eq!(
+            row,
+            (
+                "key-1".to_string(),
+                "203.0.113.7".to_string(),
+                "curl/8.5".to_string(),
+                "/v1/chat/completions".to_string()
+            )
+        );
+    }
+
     #[test]
     fn test_write_entries_updates_budget() {
         let db = test_db();
@@ -185,7 +316,7 @@ mod tests {
         budget.set_budget("user1", Some(100.0), Some(10.0)).unwrap();
 
         let entries = vec![make_entry("user1", 2.5)];
-        write_entries(&db, &entries).unwrap();
+        write_entries(&db, &CostCalculator::new(), &entries).unwrap();
 
         let b = budget.get_budget("user1").unwrap().unwrap();
         assert!((b.monthly_used - 2.5).abs() < f64::EPSILON);
@@ -197,7 +328,7 @@ mod tests {
         let db = test_db();
 
         let entries: Vec<AuditEntry> = (0..10).map(|_| make_entry("user1", 0.1)).collect();
-        write_entries(&db, &entries).unwrap();
+        write_entries(&db, &CostCalculator::new(), &entries).unwrap();
 
         let count: i64 = db
             .with_conn(|conn| {
@@ -215,19 +346,84 @@ mod tests {
         budget.set_budget("user1", Some(100.0), Some(10.0)).unwrap();
 
         let entries = vec![make_entry("user1", 0.0)];
-        write_entries(&db, &entries).unwrap();
+        write_entries(&db, &CostCalculator::new(), &entries).unwrap();
 
         let b = budget.get_budget("user1").unwrap().unwrap();
         assert!((b.monthly_used - 0.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_write_entries_records_premium_requests() {
+        let db = test_db();
+        let budget = BudgetTracker::new(db.clone());
+
+        budget.set_budget("user1", Some(100.0), Some(10.0)).unwrap();
+
+        let mut entry = make_entry("user1", 0.0);
+        entry.premium_requests = 1.5;
+        write_entries(&db, &CostCalculator::new(), &[entry]).unwrap();
+
+        let b = budget.get_budget("user1").unwrap().unwrap();
+        assert!((b.monthly_premium_used - 1.5).abs() < f64::EPSILON);
+        assert!((b.daily_premium_used - 1.5).abs() < f64::EPSILON);
+        assert!((b.monthly_used - 0.0).abs() < f64::EPSILON);
+
+        let logged: f64 = db
+            .with_conn(|conn| {
+                conn.query_row("SELECT premium_requests FROM usage_log", [], |row| {
+                    row.get(0)
+                })
+            })
+            .unwrap();
+        assert!((logged - 1.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_write_entries_applies_team_markup() {
+        let db = test_db();
+        let budget = BudgetTracker::new(db.clone());
+        budget.set_budget("user1", Some(100.0), Some(10.0)).unwrap();
+        db.with_conn(|conn| conn.execute("UPDATE users SET team = 'eng' WHERE id = 'user1'", []))
+            .unwrap();
+
+        let costs = CostCalculator::new();
+        costs.set_markup(
+            QuotaScope::Team,
+            "eng",
+            CostMarkup {
+                percent: Some(50.0),
+                per_million_tokens: None,
+            },
+        );
+        write_entries(&db, &costs, &[make_entry("user1", 2.0)]).unwrap();
+
+        let (cost, raw_cost): (f64, f64) = db
+            .with_conn(|conn| {
+                conn.query_row("SELECT cost, raw_cost FROM usage_log", [], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+            })
+            .unwrap();
+        assert!((cost - 3.0).abs() < f64::EPSILON);
+        assert!((raw_cost - 2.0).abs() < f64::EPSILON);
+
+        let b = budget.get_budget("user1").unwrap().unwrap();
+        assert!((b.monthly_used - 3.0).abs() < f64::EPSILON);
+    }
+
     #[tokio::test]
     async fn test_spawn_audit_logger_flushes_on_close() {
         let db = test_db();
         let budget = Arc::new(BudgetTracker::new(db.clone()));
         let (tx, rx) = mpsc::unbounded_channel();
 
-        let handle = spawn_audit_logger(db.clone(), budget, rx);
+        let handle = spawn_audit_logger(
+            db.clone(),
+            budget,
+            Arc::new(CostCalculator::new()),
+            Arc::new(RequestTail::new()),
+            rx,
+        );
 
         tx.send(make_entry("user1", 1.0)).unwrap();
         tx.send(make_entry("user1", 2.0)).unwrap();
@@ -252,7 +448,13 @@ mod tests {
         let budget = Arc::new(BudgetTracker::new(db.clone()));
         let (tx, rx) = mpsc::unbounded_channel();
 
-        let _handle = spawn_audit_logger(db.clone(), budget, rx);
+        let _handle = spawn_audit_logger(
+            db.clone(),
+            budget,
+            Arc::new(CostCalculator::new()),
+            Arc::new(RequestTail::new()),
+            rx,
+        );
 
         tx.send(make_entry("user1", 0.5)).unwrap();
 
@@ -0,0 +1,433 @@
+//! Monthly chargeback reports.
+//!
+//! Rolls the usage log of one calendar month (UTC) up per team and per user
+//! so platform teams can bill their consumers. Reports are served by
+//! `GET /admin/reports/chargeback` as JSON or CSV and, when
+//! `reports.chargeback_dir` is set, written there once each month has ended.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Datelike, NaiveDate, Utc};
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::leader::Leader;
+use crate::providers::cost::CostCalculator;
+
+/// How often the scheduled task checks whether last month's report exists.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Usage totals of a user, a team, or the whole report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ChargebackTotals {
+    /// Logged requests, including cache hits and failures.
+    pub requests: i64,
+    /// Requests served from the semantic cache.
+    pub cache_hits: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Provider cost before markups.
+    pub raw_cost: f64,
+    /// Cost charged, including markups.
+    pub cost: f64,
+    /// Estimated cost avoided by cache hits: the cost saved logged with each
+    /// hit, or for hits logged without one, the month's average cost per
+    /// successful request of the same model.
+    pub cache_savings: f64,
+}
+
+impl ChargebackTotals {
+    fn add(&mut self, other: &Self) {
+        self.requests += other.requests;
+        self.cache_hits += other.cache_hits;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.raw_cost += other.raw_cost;
+        self.cost += other.cost;
+        self.cache_savings += other.cache_savings;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UserChargeback {
+    pub user_id: String,
+    /// User name, `None` if the user has been deleted.
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub totals: ChargebackTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TeamChargeback {
+    /// Team name, `None` for users without a team.
+    pub team: Option<String>,
+    #[serde(flatten)]
+    pub totals: ChargebackTotals,
+    pub users: Vec<UserChargeback>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChargebackReport {
+    /// Month as `YYYY-MM`.
+    pub period: String,
+    /// Currency of all costs (`pricing.currency`).
+    pub currency: String,
+    pub teams: Vec<TeamChargeback>,
+    pub totals: ChargebackTotals,
+}
+
+impl ChargebackReport {
+    /// One CSV row per user, followed by one per team and the grand total.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "period,currency,team,user_id,user,requests,cache_hits,input_tokens,\
+             output_tokens,raw_cost,cost,cache_savings\n",
+        );
+        let mut row = |team: &str, user_id: &str, user: &str, t: &ChargebackTotals| {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{:.6},{:.6},{:.6}\n",
+                self.period,
+                self.currency,
+                csv_field(team),
+                csv_field(user_id),
+                csv_field(user),
+                t.requests,
+                t.cache_hits,
+                t.input_tokens,
+                t.output_tokens,
+                t.raw_cost,
+                t.cost,
+                t.cache_savings,
+            ));
+        };
+        for team in &self.teams {
+            let name = team.team.as_deref().unwrap_or("");
+            for user in &team.users {
+                row(
+                    name,
+                    &user.user_id,
+                    user.name.as_deref().unwrap_or(""),
+                    &user.totals,
+                );
+            }
+        }
+        for team in &self.teams {
+            row(team.team.as_deref().unwrap_or(""), "", "", &team.totals);
+        }
+        row("", "", "", &self.totals);
+        out
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Parse a `YYYY-MM` period into the first days of the month and the next.
+pub fn parse_period(period: &str) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let start = NaiveDate::parse_from_str(&format!("{period}-01"), "%Y-%m-%d").map_err(|_| {
+        AppError::BadRequest(format!("Invalid period '{period}', expected YYYY-MM"))
+    })?;
+    let end = start
+        .checked_add_months(chrono::Months::new(1))
+        .ok_or_else(|| AppError::BadRequest(format!("Invalid period '{period}'")))?;
+    Ok((start, end))
+}
+
+/// The `YYYY-MM` period of the month before `date`.
+pub fn previous_period(date: NaiveDate) -> String {
+    let first = date.with_day(1).unwrap_or(date);
+    let prev = first - chrono::Duration::days(1);
+    prev.format("%Y-%m").to_string()
+}
+
+/// Roll up the usage log of `period` (`YYYY-MM`) per team and user.
+pub fn chargeback_report(
+    db: &Database,
+    period: &str,
+    currency: &str,
+) -> Result<ChargebackReport, AppError> {
+    let (start, end) = parse_period(period)?;
+    let start = start.format("%Y-%m-%d").to_string();
+    let end = end.format("%Y-%m-%d").to_string();
+
+    // (user_id, name, team, model, totals with logged savings, hits logged
+    // without savings)
+    type Row = (
+        String,
+        Option<String>,
+        Option<String>,
+        String,
+        ChargebackTotals,
+        i64,
+    );
+    let (rows, avg_cost) = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT l.user_id, u.name, u.team, l.model, COUNT(*), \
+             SUM(l.status LIKE '%cache_hit%'), SUM(l.input_tokens), SUM(l.output_tokens), \
+             SUM(COALESCE(l.raw_cost, l.cost)), SUM(l.cost), SUM(l.cost_saved), \
+             SUM(l.status LIKE '%cache_hit%' AND l.cost_saved = 0) \
+             FROM usage_log l LEFT JOIN users u ON u.id = l.user_id \
+             WHERE l.created_at >= ?1 AND l.created_at < ?2 \
+             GROUP BY l.user_id, l.model",
+        )?;
+        let rows = stmt
+            .query_map(params![start, end], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    ChargebackTotals {
+                        requests: row.get(4)?,
+                        cache_hits: row.get(5)?,
+                        input_tokens: row.get(6)?,
+                        output_tokens: row.get(7)?,
+                        raw_cost: row.get(8)?,
+                        cost: row.get(9)?,
+                        cache_savings: row.get(10)?,
+                    },
+                    row.get(11)?,
+                ))
+            })?
+            .collect::<Result<Vec<Row>, _>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT model, AVG(cost) FROM usage_log \
+             WHERE created_at >= ?1 AND created_at < ?2 AND status = 'success' \
+             GROUP BY model",
+        )?;
+        let avg_cost = stmt
+            .query_map(params![start, end], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+            })?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok((rows, avg_cost))
+    })?;
+
+    // team -> user_id -> user
+    let mut teams: BTreeMap<Option<String>, BTreeMap<String, UserChargeback>> = BTreeMap::new();
+    for (user_id, name, team, model, mut totals, unsaved_hits) in rows {
+        totals.cache_savings += unsaved_hits as f64 * avg_cost.get(&model).copied().unwrap_or(0.0);
+        teams
+            .entry(team)
+            .or_default()
+            .entry(user_id.clone())
+            .or_insert_with(|| UserChargeback {
+                user_id,
+                name,
+                totals: ChargebackTotals::default(),
+            })
+            .totals
+            .add(&totals);
+    }
+
+    let mut report = ChargebackReport {
+        period: period.to_string(),
+        currency: currency.to_string(),
+        teams: Vec::with_capacity(teams.len()),
+        totals: ChargebackTotals::default(),
+    };
+    for (team, users) in teams {
+        let mut team_totals = ChargebackTotals::default();
+        let users: Vec<UserChargeback> = users.into_values().collect();
+        for user in &users {
+            team_totals.add(&user.totals);
+        }
+        report.totals.add(&team_totals);
+        report.teams.push(TeamChargeback {
+            team,
+            totals: team_totals,
+            users,
+        });
+    }
+    Ok(report)
+}
+
+/// Write `report` to `dir` as `chargeback-<period>.json` and `.csv`.
+pub fn write_report(dir: &Path, report: &ChargebackReport) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let json = serde_json::to_vec_pretty(report).map_err(std::io::Error::other)?;
+    std::fs::write(dir.join(format!("chargeback-{}.json", report.period)), json)?;
+    let csv_path = dir.join(format!("chargeback-{}.csv", report.period));
+    std::fs::write(&csv_path, report.to_csv())?;
+    Ok(csv_path)
+}
+
+/// Write last month's report to `dir` once the month has ended, checking
+/// every hour. A report that already exists is left untouched. Only the
+/// elected `leader` writes.
+pub fn spawn_chargeback_reports(
+    db: Database,
+    costs: Arc<CostCalculator>,
+    dir: PathBuf,
+    leader: Arc<Leader>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if !leader.is_leader() {
+                continue;
+            }
+
+            let period = previous_period(Utc::now().date_naive());
+            if dir.join(format!("chargeback-{period}.csv")).exists() {
+                continue;
+            }
+            let result = chargeback_report(&db, &period, costs.currency())
+                .map_err(|e| e.to_string())
+                .and_then(|report| write_report(&dir, &report).map_err(|e| e.to_string()));
+            match result {
+                Ok(path) => {
+                    tracing::info!(period = %period, path = %path.display(), "Chargeback report written")
+                }
+                Err(e) => {
+                    tracing::warn!(period = %period, error = %e, "Failed to write chargeback report")
+                }
+            }
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.with_conn(|conn| {
+            conn.execute_batch(
+                "INSERT INTO users (id, name, role, team) VALUES ('u1', 'alice', 'member', 'eng');
+                 INSERT INTO users (id, name, role, team) VALUES ('u2', 'bob', 'member', 'eng');
+                 INSERT INTO users (id, name, role) VALUES ('u3', 'carol', 'member');",
+            )
+        })
+        .unwrap();
+        db
+    }
+
+    fn log(db: &Database, user: &str, model: &str, cost: f64, status: &str, at: &str) {
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO usage_log (id, user_id, request_id, provider, model, \
+                 input_tokens, output_tokens, cost, raw_cost, status, created_at) \
+                 VALUES (?1, ?2, 'r', 'claude', ?3, 10, 5, ?4, ?4, ?5, ?6)",
+                params![
+                    uuid::Uuid::new_v4().to_string(),
+                    user,
+                    model,
+                    cost,
+                    status,
+                    at
+                ],
+            )
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_report_rolls_up_by_team_and_user() {
+        let db = test_db();
+        log(&db, "u1", "m", 2.0, "success", "2025-01-05 10:00:00");
+        log(
+            &db,
+            "u1",
+            "m",
+            0.0,
+            "cache_hit_exact",
+            "2025-01-06 10:00:00",
+        );
+        log(&db, "u2", "m", 4.0, "success", "2025-01-31 23:59:59");
+        log(
+            &db,
+            "u2",
+            "m",
+            0.0,
+            "stream_cache_hit_exact",
+            "2025-01-20 10:00:00",
+        );
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE usage_log SET cost_saved = 0.5 WHERE status = 'stream_cache_hit_exact'",
+                [],
+            )
+        })
+        .unwrap();
+        log(&db, "u3", "m", 1.0, "success", "2025-01-10 00:00:00");
+        // Outside the period.
+        log(&db, "u1", "m", 100.0, "success", "2025-02-01 00:00:00");
+
+        let report = chargeback_report(&db, "2025-01", "USD").unwrap();
+        assert_eq!(report.totals.requests, 5);
+        assert_eq!(report.totals.cost, 7.0);
+        assert_eq!(report.teams.len(), 2);
+
+        // Users without a team sort first.
+        assert_eq!(report.teams[0].team, None);
+        let eng = &report.teams[1];
+        assert_eq!(eng.team.as_deref(), Some("eng"));
+        assert_eq!(eng.totals.cost, 6.0);
+        assert_eq!(eng.users.len(), 2);
+
+        let alice = &eng.users[0];
+        assert_eq!(alice.name.as_deref(), Some("alice"));
+        assert_eq!(alice.totals.cache_hits, 1);
+        // Logged without a saving: the average successful cost of `m` in
+        // January, (2 + 4 + 1) / 3.
+        assert!((alice.totals.cache_savings - 7.0 / 3.0).abs() < 1e-9);
+        // Logged with one.
+        let bob = &eng.users[1];
+        assert_eq!(bob.totals.cache_hits, 1);
+        assert_eq!(bob.totals.cache_savings, 0.5);
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("period,currency,team,user_id"));
+        assert!(csv.contains("2025-01,USD,eng,u1,alice,2,1,20,10,"));
+        assert_eq!(csv.lines().count(), 1 + 3 + 2 + 1);
+    }
+
+    #[test]
+    fn test_periods() {
+        assert!(parse_period("2025-13").is_err());
+        let (start, end) = parse_period("2024-12").unwrap();
+        assert_eq!(start.to_string(), "2024-12-01");
+        assert_eq!(end.to_string(), "2025-01-01");
+        assert_eq!(
+            previous_period(NaiveDate::from_ymd_opt(2025, 1, 15).unwrap()),
+            "2024-12"
+        );
+    }
+
+    #[test]
+    fn test_csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_write_report() {
+        let db = test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let report = chargeback_report(&db, "2025-01", "EUR").unwrap();
+        let path = write_report(dir.path(), &report).unwrap();
+        assert!(path.ends_with("chargeback-2025-01.csv"));
+        assert!(dir.path().join("chargeback-2025-01.json").exists());
+    }
+}
@@ -1,12 +1,17 @@
 use axum::extract::State;
-use axum::http::Request;
+use axum::http::{HeaderMap, HeaderValue, Request, header};
 use axum::middleware::Next;
-use axum::response::Response;
+use axum::response::{IntoResponse, Response};
 
 use crate::AppState;
 use crate::auth::AuthUser;
+use crate::budget::quota::{QuotaDecision, QuotaSnapshot};
 use crate::budget::tracker::BudgetStatus;
 use crate::error::AppError;
+use crate::notify::Notification;
+use crate::providers::cost::CostCalculator;
+use crate::providers::tokens;
+use crate::providers::types::{ChatRequest, Usage};
 
 /// Axum middleware that enforces per-user budget limits.
 ///
@@ -36,7 +41,7 @@ pub async fn budget_middleware(
         None => return Ok(next.run(request).await),
     };
 
-    let warning_threshold = state.config.budget.warning_threshold_percent;
+    let warning_threshold = state.settings.config().budget.warning_threshold_percent;
     let status = state
         .budget
         .check_budget(&user.user_id, warning_threshold)?;
@@ -47,6 +52,10 @@ pub async fn budget_middleware(
                 user_id = %user.user_id,
                 "Budget exceeded, rejecting request"
             );
+            state.notifier.notify(Notification::BudgetExhausted {
+                user: user.name.clone(),
+                limit: "spending".to_string(),
+            });
             Err(AppError::BudgetExceeded(format!(
                 "Budget exceeded for user '{}'",
                 user.name
@@ -69,6 +78,150 @@ pub async fn budget_middleware(
     }
 }
 
+/// Pre-flight checks of a chat request, once its model is final and before
+/// it is queued or sent upstream. Input tokens are estimated with the
+/// tokenizer of the model's family ([`tokens::estimate`]).
+///
+/// - A request whose input plus `max_tokens` is over the model's context
+///   window (from the pricing database) is refused with 400
+///   `context_length_exceeded`.
+/// - With budgeting enabled, a request whose input alone would cost more
+///   than is left of the user's budget is refused with 429.
+///
+/// Returns the estimated input tokens.
+pub fn preflight(
+    state: &AppState,
+    user: &AuthUser,
+    request: &ChatRequest,
+) -> Result<u64, AppError> {
+    let input_tokens = tokens::estimate(request);
+    let calculator = &state.cost_calculator;
+
+    check_context_window(calculator, request, input_tokens)?;
+
+    if state.config.budget.enabled && calculator.has_pricing(&request.model) {
+        let usage = Usage {
+            prompt_tokens: u32::try_from(input_tokens).unwrap_or(u32::MAX),
+            ..Default::default()
+        };
+        let cost = calculator.calculate_cost(&request.model, &usage);
+        if state.budget.check_projected_cost(&user.user_id, cost)? == BudgetStatus::Exceeded {
+            tracing::warn!(
+                user_id = %user.user_id,
+                input_tokens,
+                cost,
+                "Projected request cost exceeds budget, rejecting request"
+            );
+            state.notifier.notify(Notification::BudgetExhausted {
+                user: user.name.clone(),
+                limit: "spending".to_string(),
+            });
+            return Err(AppError::BudgetExceeded(format!(
+                "The input of this request (about {input_tokens} tokens) would exceed the budget of user '{}'",
+                user.name
+            )));
+        }
+    }
+
+    Ok(input_tokens)
+}
+
+/// Refuse a request whose `input_tokens` plus `max_tokens` is over its
+/// model's context window. Models without a known window pass.
+fn check_context_window(
+    calculator: &CostCalculator,
+    request: &ChatRequest,
+    input_tokens: u64,
+) -> Result<(), AppError> {
+    let Some(window) = calculator
+        .get_capabilities(&request.model)
+        .and_then(|c| c.context_window)
+    else {
+        return Ok(());
+    };
+    let output_tokens = u64::from(request.max_tokens.unwrap_or(0));
+    if input_tokens + output_tokens <= u64::from(window) {
+        return Ok(());
+    }
+    let max_tokens = match request.max_tokens {
+        Some(max) => format!(" plus max_tokens {max}"),
+        None => String::new(),
+    };
+    Err(AppError::ContextWindow(format!(
+        "About {input_tokens} input tokens{max_tokens} is over the \
+         {window}-token context window of model '{}'",
+        request.model
+    )))
+}
+
+/// Axum middleware that enforces per-user and per-team rate quotas
+/// (requests per minute, tokens per minute, requests per day).
+///
+/// Must run **after** the auth middleware. Only `/v1/` requests are counted.
+/// Responses from users with quotas carry OpenAI-style `x-ratelimit-*`
+/// headers; an exhausted quota returns 429 with `retry-after`.
+pub async fn quota_middleware(
+    State(state): State<AppState>,
+    request: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !request.uri().path().starts_with("/v1/") {
+        return Ok(next.run(request).await);
+    }
+
+    let Some(user) = request.extensions().get::<AuthUser>().cloned() else {
+        return Ok(next.run(request).await);
+    };
+
+    match state
+        .budget
+        .check_rate_limits(&user.user_id, user.team.as_deref())?
+    {
+        QuotaDecision::Allowed(snapshot) => {
+            let mut response = next.run(request).await;
+            insert_quota_headers(response.headers_mut(), &snapshot);
+            Ok(response)
+        }
+        QuotaDecision::Limited {
+            snapshot,
+            message,
+            retry_after_secs,
+        } => {
+            tracing::warn!(
+                user_id = %user.user_id,
+                team = ?user.team,
+                %message,
+                "Rate quota exceeded, rejecting request"
+            );
+            let mut response = AppError::RateLimited(message).into_response();
+            let headers = response.headers_mut();
+            insert_quota_headers(headers, &snapshot);
+            headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+            Ok(response)
+        }
+    }
+}
+
+/// Add `x-ratelimit-{limit,remaining,reset}-{requests,tokens}` headers.
+fn insert_quota_headers(headers: &mut HeaderMap, snapshot: &QuotaSnapshot) {
+    for (kind, allowance) in [("requests", snapshot.requests), ("tokens", snapshot.tokens)] {
+        let Some(a) = allowance else { continue };
+        let values = [
+            ("limit", a.limit.to_string()),
+            ("remaining", a.remaining.to_string()),
+            ("reset", format!("{}s", a.reset_secs)),
+        ];
+        for (field, value) in values {
+            if let (Ok(name), Ok(value)) = (
+                header::HeaderName::try_from(format!("x-ratelimit-{field}-{kind}")),
+                HeaderValue::try_from(value),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,4 +236,45 @@ mod tests {
         assert!(matches!(warning, BudgetStatus::Warning(pct) if (pct - 85.0).abs() < f64::EPSILON));
         assert_eq!(exceeded, BudgetStatus::Exceeded);
     }
+
+    #[test]
+    fn test_check_context_window() {
+        let calculator = CostCalculator::new();
+        let mut request: ChatRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "hello"}]
+        }))
+        .unwrap();
+        assert!(check_context_window(&calculator, &request, 100_000).is_ok());
+
+        request.max_tokens = Some(30_000);
+        let err = check_context_window(&calculator, &request, 100_000).unwrap_err();
+        assert!(matches!(err, AppError::ContextWindow(_)));
+        assert!(err.to_string().contains("128000-token context window"));
+
+        // No known window: nothing to check against.
+        request.model = "my-local-model".to_string();
+        assert!(check_context_window(&calculator, &request, 10_000_000).is_ok());
+    }
+
+    #[test]
+    fn test_insert_quota_headers() {
+        use crate::budget::quota::Allowance;
+
+        let snapshot = QuotaSnapshot {
+            requests: Some(Allowance {
+                limit: 60,
+                remaining: 59,
+                reset_secs: 12,
+            }),
+            tokens: None,
+        };
+        let mut headers = HeaderMap::new();
+        insert_quota_headers(&mut headers, &snapshot);
+
+        assert_eq!(headers["x-ratelimit-limit-requests"], "60");
+        assert_eq!(headers["x-ratelimit-remaining-requests"], "59");
+        assert_eq!(headers["x-ratelimit-reset-requests"], "12s");
+        assert!(!headers.contains_key("x-ratelimit-limit-tokens"));
+    }
 }
@@ -1,11 +1,20 @@
+pub mod attribution;
 pub mod audit;
+pub mod chargeback;
 pub mod middleware;
+pub mod quota;
+pub mod reconcile;
+pub mod rollup;
+pub mod scheduler;
+pub mod streams;
+pub mod tail;
 pub mod tracker;
 
 use serde::{Deserialize, Serialize};
 
+pub use self::attribution::Attribution;
 pub use self::audit::spawn_audit_logger;
-pub use self::middleware::budget_middleware;
+pub use self::middleware::{budget_middleware, quota_middleware};
 pub use self::tracker::BudgetTracker;
 
 /// A single usage event to be recorded asynchronously by the audit logger.
@@ -17,7 +26,26 @@ pub struct AuditEntry {
     pub model: String,
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Seconds of audio processed (transcription input). Zero for text-only
+    /// requests.
+    #[serde(default)]
+    pub audio_seconds: f64,
+    /// Upstream premium requests consumed (GitHub Copilot). Zero for
+    /// providers billed by tokens only.
+    #[serde(default)]
+    pub premium_requests: f64,
     pub cost: f64,
+    /// Estimated cost of the request had it gone upstream, for requests
+    /// served from the response cache. Zero otherwise.
+    #[serde(default)]
+    pub cost_saved: f64,
     pub latency_ms: u64,
     pub status: String,
+    /// Why the model stopped (`stop`, `length`, `tool_calls`, ...), when
+    /// the request produced a completion.
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+    /// End user and tags the client attributed the request to.
+    #[serde(default)]
+    pub attribution: Attribution,
 }
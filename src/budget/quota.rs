@@ -0,0 +1,343 @@
+//! Request and token rate quotas (RPM / TPM / RPD).
+//!
+//! Limits are stored per user or per team in the `rate_limits` table.
+//! Counters live in memory in fixed windows aligned to the UTC minute and
+//! UTC day, so they reset on restart.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+const MINUTE: u64 = 60;
+const DAY: u64 = 86_400;
+
+/// Who a set of rate limits applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaScope {
+    User,
+    Team,
+}
+
+impl QuotaScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Team => "team",
+        }
+    }
+}
+
+impl fmt::Display for QuotaScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Rate limits for a user or team. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimits {
+    /// Requests per minute.
+    pub rpm: Option<u64>,
+    /// Tokens (input + output) per minute.
+    pub tpm: Option<u64>,
+    /// Requests per day.
+    pub rpd: Option<u64>,
+}
+
+impl RateLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.rpm.is_none() && self.tpm.is_none() && self.rpd.is_none()
+    }
+}
+
+/// Remaining allowance of the most constraining limit, reported to clients
+/// as `x-ratelimit-*` headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuotaSnapshot {
+    pub requests: Option<Allowance>,
+    pub tokens: Option<Allowance>,
+}
+
+/// One dimension of a [`QuotaSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Allowance {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Seconds until the window resets.
+    pub reset_secs: u64,
+}
+
+impl Allowance {
+    fn new(limit: u64, used: u64, reset_secs: u64) -> Self {
+        Self {
+            limit,
+            remaining: limit.saturating_sub(used),
+            reset_secs,
+        }
+    }
+
+    /// Keep whichever allowance runs out first.
+    fn tightest(current: Option<Self>, other: Self) -> Option<Self> {
+        match current {
+            Some(c)
+                if c.remaining < other.remaining
+                    || (c.remaining == other.remaining && c.reset_secs >= other.reset_secs) =>
+            {
+                Some(c)
+            }
+            _ => Some(other),
+        }
+    }
+}
+
+/// Outcome of [`QuotaState::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaDecision {
+    /// The request was counted.
+    Allowed(QuotaSnapshot),
+    /// A limit is exhausted; the request was not counted.
+    Limited {
+        snapshot: QuotaSnapshot,
+        message: String,
+        retry_after_secs: u64,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    minute: u64,
+    minute_requests: u64,
+    minute_tokens: u64,
+    day: u64,
+    day_requests: u64,
+}
+
+impl Counters {
+    fn roll(&mut self, now: u64) {
+        if self.minute != now / MINUTE {
+            self.minute = now / MINUTE;
+            self.minute_requests = 0;
+            self.minute_tokens = 0;
+        }
+        if self.day != now / DAY {
+            self.day = now / DAY;
+            self.day_requests = 0;
+        }
+    }
+}
+
+/// In-memory usage counters for every user and team with limits.
+#[derive(Debug, Default)]
+pub struct QuotaState {
+    counters: HashMap<(QuotaScope, String), Counters>,
+    /// Team of each user seen by `check`, so token usage reported later by
+    /// user ID is also charged to the team.
+    teams: HashMap<String, String>,
+}
+
+impl QuotaState {
+    /// Check `limits` for each subject and count the request against all of
+    /// them if none is exhausted. `now` is seconds since the Unix epoch.
+    pub fn check(
+        &mut self,
+        user_id: &str,
+        team: Option<&str>,
+        limits: &[(QuotaScope, String, RateLimits)],
+        now: u64,
+    ) -> QuotaDecision {
+        match team {
+            Some(team) => {
+                self.teams.insert(user_id.to_string(), team.to_string());
+            }
+            None => {
+                self.teams.remove(user_id);
+            }
+        }
+
+        let minute_reset = MINUTE - now % MINUTE;
+        let day_reset = DAY - now % DAY;
+        let mut snapshot = QuotaSnapshot::default();
+        let mut exhausted: Option<(String, u64)> = None;
+
+        for (scope, subject, limits) in limits {
+            let counters = self.counters.entry((*scope, subject.clone())).or_default();
+            counters.roll(now);
+
+            let checks = [
+                (
+                    "requests per minute",
+                    limits.rpm,
+                    counters.minute_requests,
+                    minute_reset,
+                ),
+                (
+                    "requests per day",
+                    limits.rpd,
+                    counters.day_requests,
+                    day_reset,
+                ),
+            ];
+            for (what, limit, used, reset) in checks {
+                let Some(limit) = limit else { continue };
+                let allowance = Allowance::new(limit, used, reset);
+                snapshot.requests = Allowance::tightest(snapshot.requests, allowance);
+                if allowance.remaining == 0 && exhausted.is_none() {
+                    exhausted = Some((
+                        format!("{scope} '{subject}' reached its limit of {limit} {what}"),
+                        reset,
+                    ));
+                }
+            }
+            if let Some(limit) = limits.tpm {
+                let allowance = Allowance::new(limit, counters.minute_tokens, minute_reset);
+                snapshot.tokens = Allowance::tightest(snapshot.tokens, allowance);
+                if allowance.remaining == 0 && exhausted.is_none() {
+                    exhausted = Some((
+                        format!(
+                            "{scope} '{subject}' reached its limit of {limit} tokens per minute"
+                        ),
+                        minute_reset,
+                    ));
+                }
+            }
+        }
+
+        if let Some((message, retry_after_secs)) = exhausted {
+            return QuotaDecision::Limited {
+                snapshot,
+                message,
+                retry_after_secs,
+            };
+        }
+
+        for (scope, subject, _) in limits {
+            if let Some(counters) = self.counters.get_mut(&(*scope, subject.clone())) {
+                counters.minute_requests += 1;
+                counters.day_requests += 1;
+            }
+        }
+        if let Some(requests) = snapshot.requests.as_mut() {
+            requests.remaining = requests.remaining.saturating_sub(1);
+        }
+        QuotaDecision::Allowed(snapshot)
+    }
+
+    /// Charge `tokens` to the user and their team for the current minute.
+    /// Subjects without limits are not tracked.
+    pub fn record_tokens(&mut self, user_id: &str, tokens: u64, now: u64) {
+        let team = self.teams.get(user_id).cloned();
+        let keys = std::iter::once((QuotaScope::User, user_id.to_string()))
+            .chain(team.map(|t| (QuotaScope::Team, t)));
+        for key in keys {
+            if let Some(counters) = self.counters.get_mut(&key) {
+                counters.roll(now);
+                counters.minute_tokens += tokens;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_limits(limits: RateLimits) -> Vec<(QuotaScope, String, RateLimits)> {
+        vec![(QuotaScope::User, "u1".to_string(), limits)]
+    }
+
+    #[test]
+    fn test_rpm_limit_resets_next_minute() {
+        let mut state = QuotaState::default();
+        let limits = user_limits(RateLimits {
+            rpm: Some(2),
+            ..Default::default()
+        });
+        let now = 1_000 * MINUTE + 10;
+
+        let QuotaDecision::Allowed(snap) = state.check("u1", None, &limits, now) else {
+            panic!("first request should pass");
+        };
+        let requests = snap.requests.unwrap();
+        assert_eq!(
+            (requests.limit, requests.remaining, requests.reset_secs),
+            (2, 1, 50)
+        );
+
+        assert!(matches!(
+            state.check("u1", None, &limits, now),
+            QuotaDecision::Allowed(_)
+        ));
+        match state.check("u1", None, &limits, now + 5) {
+            QuotaDecision::Limited {
+                retry_after_secs,
+                message,
+                ..
+            } => {
+                assert_eq!(retry_after_secs, 45);
+                assert!(message.contains("requests per minute"));
+            }
+            other => panic!("expected limit, got {other:?}"),
+        }
+
+        assert!(matches!(
+            state.check("u1", None, &limits, now + MINUTE),
+            QuotaDecision::Allowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_tpm_limit_counts_recorded_tokens() {
+        let mut state = QuotaState::default();
+        let limits = user_limits(RateLimits {
+            tpm: Some(1_000),
+            ..Default::default()
+        });
+        let now = 5 * DAY;
+
+        assert!(matches!(
+            state.check("u1", None, &limits, now),
+            QuotaDecision::Allowed(_)
+        ));
+        state.record_tokens("u1", 1_200, now + 1);
+        state.record_tokens("someone-else", 50, now + 1);
+
+        let QuotaDecision::Limited { snapshot, .. } = state.check("u1", None, &limits, now + 2)
+        else {
+            panic!("token quota should be exhausted");
+        };
+        assert_eq!(snapshot.tokens.unwrap().remaining, 0);
+    }
+
+    #[test]
+    fn test_team_limit_is_shared() {
+        let mut state = QuotaState::default();
+        let team = RateLimits {
+            rpd: Some(2),
+            ..Default::default()
+        };
+        let limits_for = |user: &str| {
+            vec![
+                (QuotaScope::User, user.to_string(), RateLimits::default()),
+                (QuotaScope::Team, "eng".to_string(), team),
+            ]
+        };
+        let now = 9 * DAY;
+
+        assert!(matches!(
+            state.check("u1", Some("eng"), &limits_for("u1"), now),
+            QuotaDecision::Allowed(_)
+        ));
+        assert!(matches!(
+            state.check("u2", Some("eng"), &limits_for("u2"), now),
+            QuotaDecision::Allowed(_)
+        ));
+        let QuotaDecision::Limited { message, .. } =
+            state.check("u1", Some("eng"), &limits_for("u1"), now)
+        else {
+            panic!("team quota should be exhausted");
+        };
+        assert!(message.contains("team 'eng'"));
+    }
+}
@@ -0,0 +1,395 @@
+//! Reconcile gaud's computed costs against the spend reported by LiteLLM.
+//!
+//! LiteLLM prices requests with its own model cost map, which may differ
+//! from gaud's pricing table. A background task periodically pulls LiteLLM's
+//! spend per model and day, compares it with the cost gaud logged for the
+//! same model and day, and stores the result in `cost_reconciliation`.
+//! Differences above the configured tolerance are flagged for the admin
+//! report (`GET /admin/reconciliation/litellm`).
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::leader::Leader;
+use crate::providers::cost::CostCalculator;
+use crate::providers::litellm::{DailyModelSpend, LitellmProvider};
+
+/// Provider name under which LiteLLM reconciliations are stored.
+pub const LITELLM: &str = "litellm";
+
+/// Days reconciled on every run, including today. Yesterday is included so
+/// requests logged just before midnight are settled once both sides
+/// recorded them.
+const LOOKBACK_DAYS: i64 = 2;
+
+/// Differences below this amount (in the billing currency) are never flagged.
+const MIN_DISCREPANCY: f64 = 0.01;
+
+/// Comparison of one model on one day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReconciliationEntry {
+    pub provider: String,
+    /// Day (UTC) as `YYYY-MM-DD`.
+    pub day: String,
+    pub model: String,
+    /// Cost gaud computed for the successful requests of the day.
+    pub gaud_cost: f64,
+    pub gaud_requests: i64,
+    /// Spend the upstream proxy recorded.
+    pub upstream_cost: f64,
+    /// `upstream_cost - gaud_cost`.
+    pub difference: f64,
+    /// Whether the difference exceeds the tolerance.
+    pub flagged: bool,
+    pub checked_at: String,
+}
+
+/// Compare LiteLLM's spend for the days `start..=end` with gaud's usage log
+/// and store the result, replacing earlier results for the same days.
+///
+/// Only models LiteLLM reported, or requested with the `litellm:` prefix,
+/// are compared, since other rows of the usage log were served elsewhere.
+pub fn reconcile_litellm(
+    db: &Database,
+    upstream: &[DailyModelSpend],
+    start: &str,
+    end: &str,
+    tolerance_percent: f64,
+) -> Result<Vec<ReconciliationEntry>, AppError> {
+    let upstream_models: HashSet<&str> = upstream.iter().map(|s| s.model.as_str()).collect();
+
+    // (day, model) -> (gaud cost, gaud requests, upstream cost)
+    let mut totals: BTreeMap<(String, String), (f64, i64, f64)> = BTreeMap::new();
+
+    let gaud_rows = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT date(created_at), model, SUM(COALESCE(raw_cost, cost)), COUNT(*) FROM usage_log \
+             WHERE date(created_at) BETWEEN ?1 AND ?2 AND status = 'success' \
+             GROUP BY date(created_at), model",
+        )?;
+        let rows = stmt.query_map(params![start, end], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, f64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+
+    for (day, model, cost, requests) in gaud_rows {
+        let prefixed = model.starts_with("litellm:");
+        let model = model.strip_prefix("litellm:").unwrap_or(&model).to_string();
+        if !prefixed && !upstream_models.contains(model.as_str()) {
+            continue;
+        }
+        let total = totals.entry((day, model)).or_default();
+        total.0 += cost;
+        total.1 += requests;
+    }
+
+    for spend in upstream {
+        if spend.day.as_str() < start || spend.day.as_str() > end {
+            continue;
+        }
+        let total = totals
+            .entry((spend.day.clone(), spend.model.clone()))
+            .or_default();
+        total.2 += spend.spend;
+    }
+
+    let checked_at = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let entries: Vec<ReconciliationEntry> = totals
+        .into_iter()
+        .map(
+            |((day, model), (gaud_cost, gaud_requests, upstream_cost))| {
+                let difference = upstream_cost - gaud_cost;
+                ReconciliationEntry {
+                    provider: LITELLM.to_string(),
+                    day,
+                    model,
+                    gaud_cost,
+                    gaud_requests,
+                    upstream_cost,
+                    difference,
+                    flagged: is_discrepancy(gaud_cost, upstream_cost, tolerance_percent),
+                    checked_at: checked_at.clone(),
+                }
+            },
+        )
+        .collect();
+
+    db.with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM cost_reconciliation WHERE provider = ?1 AND day BETWEEN ?2 AND ?3",
+            params![LITELLM, start, end],
+        )?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO cost_reconciliation (provider, day, model, gaud_cost, \
+                 gaud_requests, upstream_cost, difference, flagged, checked_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+            for e in &entries {
+                insert.execute(params![
+                    e.provider,
+                    e.day,
+                    e.model,
+                    e.gaud_cost,
+                    e.gaud_requests,
+                    e.upstream_cost,
+                    e.difference,
+                    e.flagged,
+                    e.checked_at,
+                ])?;
+            }
+        }
+        tx.commit()
+    })?;
+
+    Ok(entries)
+}
+
+/// Stored reconciliation results of `provider`, newest day first, optionally
+/// limited to a day range and to flagged entries.
+pub fn reconciliation_report(
+    db: &Database,
+    provider: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+    flagged_only: bool,
+) -> Result<Vec<ReconciliationEntry>, AppError> {
+    let entries = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT provider, day, model, gaud_cost, gaud_requests, upstream_cost, \
+             difference, flagged, checked_at FROM cost_reconciliation \
+             WHERE provider = ?1 AND (?2 IS NULL OR day >= ?2) AND (?3 IS NULL OR day <= ?3) \
+             AND (?4 = 0 OR flagged = 1) \
+             ORDER BY day DESC, model",
+        )?;
+        let rows = stmt.query_map(params![provider, from, to, flagged_only], |row| {
+            Ok(ReconciliationEntry {
+                provider: row.get(0)?,
+                day: row.get(1)?,
+                model: row.get(2)?,
+                gaud_cost: row.get(3)?,
+                gaud_requests: row.get(4)?,
+                upstream_cost: row.get(5)?,
+                difference: row.get(6)?,
+                flagged: row.get(7)?,
+                checked_at: row.get(8)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(entries)
+}
+
+/// Whether two costs differ by more than `tolerance_percent` of the larger
+/// one (and by at least [`MIN_DISCREPANCY`]).
+fn is_discrepancy(gaud_cost: f64, upstream_cost: f64, tolerance_percent: f64) -> bool {
+    let difference = (upstream_cost - gaud_cost).abs();
+    let allowed = gaud_cost.max(upstream_cost) * tolerance_percent.max(0.0) / 100.0;
+    difference >= MIN_DISCREPANCY && difference > allowed
+}
+
+/// Pull LiteLLM's spend every `interval` and reconcile the last
+/// [`LOOKBACK_DAYS`] days. LiteLLM reports spend in USD; it is converted to
+/// the billing currency of `costs` before comparing. Failures are logged and
+/// retried on the next tick. Only the elected `leader` runs a pass.
+pub fn spawn_litellm_reconciliation(
+    db: Database,
+    provider: Arc<LitellmProvider>,
+    costs: Arc<CostCalculator>,
+    interval: Duration,
+    tolerance_percent: f64,
+    leader: Arc<Leader>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if !leader.is_leader() {
+                continue;
+            }
+
+            let end = Utc::now().date_naive();
+            let start = end - chrono::Duration::days(LOOKBACK_DAYS - 1);
+            let mut spend = match provider.daily_spend(start, end).await {
+                Ok(spend) => spend,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to fetch LiteLLM spend");
+                    continue;
+                }
+            };
+
+            for s in &mut spend {
+                s.spend = costs.convert_usd(s.spend);
+            }
+            let start = start.format("%Y-%m-%d").to_string();
+            let end = end.format("%Y-%m-%d").to_string();
+            match reconcile_litellm(&db, &spend, &start, &end, tolerance_percent) {
+                Ok(entries) => {
+                    let flagged = entries.iter().filter(|e| e.flagged).count();
+                    if flagged > 0 {
+                        tracing::warn!(
+                            flagged,
+                            from = %start,
+                            to = %end,
+                            "LiteLLM spend differs from gaud's computed cost"
+                        );
+                    } else {
+                        tracing::debug!(models = entries.len(), "LiteLLM spend reconciled");
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "LiteLLM spend reconciliation failed");
+                }
+            }
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO users (id, name, role) VALUES ('user1', 'alice', 'member')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        db
+    }
+
+    fn log_usage(db: &Database, model: &str, cost: f64, status: &str, created_at: &str) {
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO usage_log (id, user_id, request_id, provider, model, cost, \
+                 status, created_at) VALUES (?1, 'user1', 'req', 'p', ?2, ?3, ?4, ?5)",
+                params![
+                    uuid::Uuid::new_v4().to_string(),
+                    model,
+                    cost,
+                    status,
+                    created_at
+                ],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    fn spend(day: &str, model: &str, spend: f64) -> DailyModelSpend {
+        DailyModelSpend {
+            day: day.to_string(),
+            model: model.to_string(),
+            spend,
+        }
+    }
+
+    #[test]
+    fn test_is_discrepancy() {
+        assert!(!is_discrepancy(1.0, 1.04, 5.0));
+        assert!(is_discrepancy(1.0, 1.2, 5.0));
+        // Tiny absolute differences are ignored.
+        assert!(!is_discrepancy(0.001, 0.005, 5.0));
+        assert!(is_discrepancy(0.0, 0.5, 5.0));
+    }
+
+    #[test]
+    fn test_reconcile_litellm() {
+        let db = test_db();
+        log_usage(
+            &db,
+            "litellm:gpt-4o",
+            0.40,
+            "success",
+            "2025-01-15 10:00:00",
+        );
+        log_usage(&db, "gpt-4o", 0.60, "success", "2025-01-15 11:00:00");
+        log_usage(&db, "gpt-4o", 5.00, "error: boom", "2025-01-15 12:00:00");
+        log_usage(
+            &db,
+            "litellm:claude-sonnet-4",
+            1.00,
+            "success",
+            "2025-01-15 12:00:00",
+        );
+        // Served by another provider and unknown to LiteLLM.
+        log_usage(
+            &db,
+            "gemini-2.5-pro",
+            3.00,
+            "success",
+            "2025-01-15 12:00:00",
+        );
+        // Outside the range.
+        log_usage(
+            &db,
+            "litellm:gpt-4o",
+            9.00,
+            "success",
+            "2025-01-14 12:00:00",
+        );
+
+        let upstream = vec![
+            spend("2025-01-15", "gpt-4o", 1.02),
+            spend("2025-01-15", "claude-sonnet-4", 1.50),
+            spend("2025-01-16", "mistral-large", 0.30),
+        ];
+        let entries = reconcile_litellm(&db, &upstream, "2025-01-15", "2025-01-16", 5.0).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let claude = &entries[0];
+        assert_eq!(claude.model, "claude-sonnet-4");
+        assert!((claude.difference - 0.5).abs() < 1e-9);
+        assert!(claude.flagged);
+
+        let gpt = &entries[1];
+        assert_eq!(gpt.model, "gpt-4o");
+        assert_eq!(gpt.gaud_requests, 2);
+        assert!((gpt.gaud_cost - 1.0).abs() < 1e-9);
+        assert!(!gpt.flagged);
+
+        let mistral = &entries[2];
+        assert_eq!(mistral.day, "2025-01-16");
+        assert_eq!(mistral.gaud_requests, 0);
+        assert!(mistral.flagged);
+
+        let report = reconciliation_report(&db, LITELLM, None, None, false).unwrap();
+        assert_eq!(report.len(), 3);
+        assert_eq!(report[0].day, "2025-01-16");
+
+        let flagged =
+            reconciliation_report(&db, LITELLM, Some("2025-01-15"), Some("2025-01-15"), true)
+                .unwrap();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].model, "claude-sonnet-4");
+
+        // A later run replaces the results of the same days.
+        reconcile_litellm(&db, &[], "2025-01-15", "2025-01-16", 5.0).unwrap();
+        let report = reconciliation_report(&db, LITELLM, None, None, false).unwrap();
+        assert_eq!(report.len(), 2);
+        assert!(report.iter().all(|e| e.upstream_cost == 0.0));
+    }
+}
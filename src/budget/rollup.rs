@@ -0,0 +1,413 @@
+//! Hourly usage aggregates.
+//!
+//! The audit logger adds every batch it writes to `usage_hourly`, one row
+//! per UTC hour and provider with request, token and cost totals and a
+//! latency histogram. Time-series queries for the dashboard read these rows
+//! instead of scanning `usage_log`.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+
+use crate::budget::AuditEntry;
+use crate::db::Database;
+use crate::error::AppError;
+
+/// Upper bounds (inclusive, in ms) of the latency histogram buckets. A last
+/// bucket counts everything slower.
+pub const LATENCY_BOUNDS_MS: [u64; 12] = [
+    50, 100, 250, 500, 1_000, 2_000, 5_000, 10_000, 20_000, 30_000, 60_000, 120_000,
+];
+
+/// Request counts per [`LATENCY_BOUNDS_MS`] bucket.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LatencyHistogram(Vec<u64>);
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency_ms: u64) {
+        let bucket = LATENCY_BOUNDS_MS
+            .iter()
+            .position(|&bound| latency_ms <= bound)
+            .unwrap_or(LATENCY_BOUNDS_MS.len());
+        if self.0.len() <= LATENCY_BOUNDS_MS.len() {
+            self.0.resize(LATENCY_BOUNDS_MS.len() + 1, 0);
+        }
+        self.0[bucket] += 1;
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (count, other) in self.0.iter_mut().zip(&other.0) {
+            *count += other;
+        }
+    }
+
+    /// Upper bound of the bucket holding the `percentile` (0-100) request,
+    /// `None` without requests. Requests slower than the last bound report
+    /// that bound.
+    pub fn percentile(&self, percentile: f64) -> Option<u64> {
+        let total: u64 = self.0.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((percentile / 100.0) * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.0.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let last = LATENCY_BOUNDS_MS.len() - 1;
+                return Some(LATENCY_BOUNDS_MS[bucket.min(last)]);
+            }
+        }
+        None
+    }
+}
+
+/// Totals of one provider in one hour, or in a longer interval once merged.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Totals {
+    requests: i64,
+    errors: i64,
+    input_tokens: i64,
+    output_tokens: i64,
+    cost: f64,
+    cost_saved: f64,
+    latency: LatencyHistogram,
+}
+
+impl Totals {
+    fn merge(&mut self, other: &Self) {
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+        self.cost_saved += other.cost_saved;
+        self.latency.merge(&other.latency);
+    }
+}
+
+/// Whether a logged status counts as an error. Cache hits do not.
+fn is_error(status: &str) -> bool {
+    status != "success" && !status.contains("cache_hit")
+}
+
+/// Add a batch of entries, logged at `now` and charged `costs[i]`, to the
+/// hourly aggregates. Runs inside the audit logger's transaction.
+pub(crate) fn record_batch(
+    conn: &Connection,
+    now: DateTime<Utc>,
+    entries: &[AuditEntry],
+    costs: &[f64],
+) -> rusqlite::Result<()> {
+    let hour = now.format("%Y-%m-%d %H:00:00").to_string();
+
+    let mut by_provider: HashMap<&str, Totals> = HashMap::new();
+    for (entry, cost) in entries.iter().zip(costs) {
+        let totals = by_provider.entry(entry.provider.as_str()).or_default();
+        totals.requests += 1;
+        totals.errors += i64::from(is_error(&entry.status));
+        totals.input_tokens += i64::from(entry.input_tokens);
+        totals.output_tokens += i64::from(entry.output_tokens);
+        totals.cost += cost;
+        totals.cost_saved += entry.cost_saved;
+        totals.latency.record(entry.latency_ms);
+    }
+
+    let mut select = conn.prepare_cached(
+        "SELECT latency_histogram FROM usage_hourly WHERE hour = ?1 AND provider = ?2",
+    )?;
+    let mut upsert = conn.prepare_cached(
+        "INSERT INTO usage_hourly (hour, provider, requests, errors, input_tokens, \
+         output_tokens, cost, latency_histogram, cost_saved) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9) \
+         ON CONFLICT(hour, provider) DO UPDATE SET requests = requests + ?3, \
+         errors = errors + ?4, input_tokens = input_tokens + ?5, \
+         output_tokens = output_tokens + ?6, cost = cost + ?7, latency_histogram = ?8, \
+         cost_saved = cost_saved + ?9",
+    )?;
+    for (provider, mut totals) in by_provider {
+        let stored: Option<String> = select
+            .query_row(params![hour, provider], |row| row.get(0))
+            .optional()?;
+        if let Some(stored) = stored
+            && let Ok(stored) = serde_json::from_str::<LatencyHistogram>(&stored)
+        {
+            totals.latency.merge(&stored);
+        }
+        let histogram = serde_json::to_string(&totals.latency).unwrap_or_else(|_| "[]".into());
+        upsert.execute(params![
+            hour,
+            provider,
+            totals.requests,
+            totals.errors,
+            totals.input_tokens,
+            totals.output_tokens,
+            totals.cost,
+            histogram,
+            totals.cost_saved,
+        ])?;
+    }
+    Ok(())
+}
+
+/// Width of the time-series buckets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interval {
+    #[default]
+    Hour,
+    Day,
+}
+
+impl Interval {
+    /// Range covered when a query gives no bounds: the last 24 hours, or the
+    /// last 30 days, up to `now`.
+    pub fn default_range(self, now: DateTime<Utc>) -> (String, String) {
+        let span = match self {
+            Self::Hour => chrono::Duration::hours(24),
+            Self::Day => chrono::Duration::days(30),
+        };
+        let format = "%Y-%m-%d %H:%M:%S";
+        (
+            (now - span).format(format).to_string(),
+            now.format(format).to_string(),
+        )
+    }
+}
+
+/// Query parameters of the usage time-series endpoints.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TimeseriesQuery {
+    /// Start (`YYYY-MM-DD[ HH:MM:SS]`, UTC).
+    pub from: Option<String>,
+    /// End (`YYYY-MM-DD[ HH:MM:SS]`, UTC).
+    pub to: Option<String>,
+    #[serde(default)]
+    pub interval: Interval,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeseriesResponse {
+    pub interval: Interval,
+    pub from: String,
+    pub to: String,
+    /// Currency of `cost` (`pricing.currency`).
+    pub currency: String,
+    pub data: Vec<UsagePoint>,
+}
+
+/// Answer a [`TimeseriesQuery`], filling in missing bounds.
+pub fn query_timeseries(
+    db: &Database,
+    query: TimeseriesQuery,
+    currency: &str,
+) -> Result<TimeseriesResponse, AppError> {
+    let (default_from, default_to) = query.interval.default_range(Utc::now());
+    let from = query.from.unwrap_or(default_from);
+    let to = query.to.unwrap_or(default_to);
+    let data = usage_timeseries(db, &from, &to, query.interval)?;
+    Ok(TimeseriesResponse {
+        interval: query.interval,
+        from,
+        to,
+        currency: currency.to_string(),
+        data,
+    })
+}
+
+/// Usage of one provider in one bucket.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsagePoint {
+    /// Start of the bucket (UTC), `YYYY-MM-DD HH:00:00` or `YYYY-MM-DD`.
+    pub time: String,
+    pub provider: String,
+    pub requests: i64,
+    pub errors: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost: f64,
+    /// Estimated cost saved by serving requests from the cache (only on the
+    /// `cache` provider).
+    pub cost_saved: f64,
+    /// 95th percentile latency, as the upper bound of its histogram bucket.
+    pub p95_latency_ms: Option<u64>,
+}
+
+/// Usage per provider and `interval` between `from` and `to` (inclusive,
+/// `YYYY-MM-DD[ HH:MM:SS]`, UTC), oldest first.
+pub fn usage_timeseries(
+    db: &Database,
+    from: &str,
+    to: &str,
+    interval: Interval,
+) -> Result<Vec<UsagePoint>, AppError> {
+    // Rows are keyed by the start of their hour.
+    let from = match interval {
+        Interval::Hour => from
+            .get(..13)
+            .map_or(from.to_string(), |h| format!("{h}:00:00")),
+        Interval::Day => from.get(..10).unwrap_or(from).to_string(),
+    };
+    // A bare date as the end covers that whole day.
+    let to = if to.len() == 10 {
+        format!("{to} 23:59:59")
+    } else {
+        to.to_string()
+    };
+    let rows = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT hour, provider, requests, errors, input_tokens, output_tokens, cost, \
+             latency_histogram, cost_saved FROM usage_hourly WHERE hour >= ?1 AND hour <= ?2",
+        )?;
+        let rows = stmt.query_map(params![from, to], |row| {
+            let histogram: String = row.get(7)?;
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                Totals {
+                    requests: row.get(2)?,
+                    errors: row.get(3)?,
+                    input_tokens: row.get(4)?,
+                    output_tokens: row.get(5)?,
+                    cost: row.get(6)?,
+                    cost_saved: row.get(8)?,
+                    latency: serde_json::from_str(&histogram).unwrap_or_default(),
+                },
+            ))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+
+    let mut buckets: BTreeMap<(String, String), Totals> = BTreeMap::new();
+    for (hour, provider, totals) in rows {
+        let time = match interval {
+            Interval::Hour => hour,
+            Interval::Day => hour.chars().take(10).collect(),
+        };
+        buckets.entry((time, provider)).or_default().merge(&totals);
+    }
+
+    Ok(buckets
+        .into_iter()
+        .map(|((time, provider), totals)| UsagePoint {
+            time,
+            provider,
+            requests: totals.requests,
+            errors: totals.errors,
+            input_tokens: totals.input_tokens,
+            output_tokens: totals.output_tokens,
+            cost: totals.cost,
+            cost_saved: totals.cost_saved,
+            p95_latency_ms: totals.latency.percentile(95.0),
+        })
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::Attribution;
+    use chrono::TimeZone;
+
+    fn entry(provider: &str, latency_ms: u64, status: &str) -> AuditEntry {
+        AuditEntry {
+            user_id: "u1".to_string(),
+            request_id: "r".to_string(),
+            provider: provider.to_string(),
+            model: "m".to_string(),
+            input_tokens: 10,
+            output_tokens: 5,
+            audio_seconds: 0.0,
+            premium_requests: 0.0,
+            cost: 0.0,
+            cost_saved: 0.0,
+            latency_ms,
+            status: status.to_string(),
+            finish_reason: None,
+            attribution: Attribution::default(),
+        }
+    }
+
+    #[test]
+    fn test_histogram_percentile() {
+        let mut h = LatencyHistogram::default();
+        assert_eq!(h.percentile(95.0), None);
+        for _ in 0..95 {
+            h.record(80);
+        }
+        for _ in 0..5 {
+            h.record(4_000);
+        }
+        assert_eq!(h.percentile(50.0), Some(100));
+        assert_eq!(h.percentile(95.0), Some(100));
+        assert_eq!(h.percentile(99.0), Some(5_000));
+
+        h.record(1_000_000);
+        assert_eq!(h.percentile(100.0), Some(120_000));
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let db = Database::open_in_memory().unwrap();
+        let at = |h| Utc.with_ymd_and_hms(2025, 1, 5, h, 30, 0).unwrap();
+
+        db.with_conn(|conn| {
+            record_batch(
+                conn,
+                at(10),
+                &[
+                    entry("claude", 200, "success"),
+                    entry("claude", 900, "error"),
+                ],
+                &[1.0, 0.0],
+            )?;
+            record_batch(conn, at(10), &[entry("claude", 300, "success")], &[2.0])?;
+            record_batch(
+                conn,
+                at(11),
+                &[
+                    entry("gemini", 40, "success"),
+                    AuditEntry {
+                        cost_saved: 0.25,
+                        ..entry("cache", 1, "cache_hit_exact")
+                    },
+                ],
+                &[0.5, 0.0],
+            )
+        })
+        .unwrap();
+
+        let hourly =
+            usage_timeseries(&db, "2025-01-05", "2025-01-05 23:59:59", Interval::Hour).unwrap();
+        assert_eq!(hourly.len(), 3);
+        let claude = &hourly[0];
+        assert_eq!(claude.time, "2025-01-05 10:00:00");
+        assert_eq!(claude.requests, 3);
+        assert_eq!(claude.errors, 1);
+        assert_eq!(claude.cost, 3.0);
+        assert_eq!(claude.p95_latency_ms, Some(1_000));
+        let cache = &hourly[1];
+        assert_eq!(cache.provider, "cache");
+        assert_eq!(cache.errors, 0);
+        assert_eq!(cache.cost, 0.0);
+        assert_eq!(cache.cost_saved, 0.25);
+
+        let daily =
+            usage_timeseries(&db, "2025-01-05", "2025-01-05 23:59:59", Interval::Day).unwrap();
+        assert_eq!(daily.len(), 3);
+        assert!(daily.iter().all(|p| p.time == "2025-01-05"));
+
+        let none = usage_timeseries(&db, "2025-01-06", "2025-01-07", Interval::Hour).unwrap();
+        assert!(none.is_empty());
+    }
+}
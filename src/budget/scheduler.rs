@@ -0,0 +1,605 @@
+//! Fair scheduling of upstream requests under saturation (`[limits]`).
+//!
+//! With `limits.max_in_flight` set, at most that many chat requests are
+//! sent to providers at once. Requests past it wait in a queue per user,
+//! and freed slots are handed to the queues by deficit round-robin: each
+//! waiting user earns [`QUANTUM`] per turn and a request costs units by its
+//! size ([`cost`]), so a user with a backlog of large requests gets the
+//! same share of slots as one sending a few small ones instead of starving
+//! them. Requests of [`Priority::Batch`] keys and teams wait in a tier of
+//! their own, served only when no interactive request is waiting. A request
+//! that waits longer than `limits.queue_timeout_secs` is refused with 429.
+//! Per-user queue waits are kept for `GET /admin/queue`.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::config::LimitsConfig;
+use crate::error::AppError;
+
+/// Units a waiting user earns each time its turn comes round.
+pub const QUANTUM: u32 = 1;
+/// Input tokens per cost unit beyond the first.
+const TOKENS_PER_UNIT: u64 = 4_096;
+/// Largest cost of one request, so a huge request still gets its turn.
+const MAX_COST: u32 = 16;
+
+/// Scheduling cost of a request with `input_tokens` estimated input tokens
+/// ([`preflight`](crate::budget::middleware::preflight)): one unit, plus
+/// one per [`TOKENS_PER_UNIT`] tokens.
+pub fn cost(input_tokens: u64) -> u32 {
+    let extra = input_tokens / TOKENS_PER_UNIT;
+    (1 + u32::try_from(extra).unwrap_or(u32::MAX)).min(MAX_COST)
+}
+
+/// Scheduling priority of an API key or team.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// For a key, the priority of its user's team; otherwise interactive.
+    #[default]
+    Default,
+    /// Latency-sensitive traffic, served first.
+    Interactive,
+    /// Bulk traffic, served when no interactive request is waiting and kept
+    /// off providers close to their upstream rate limits.
+    Batch,
+}
+
+impl Priority {
+    /// This priority, or `fallback` if it is [`Priority::Default`].
+    pub fn or(self, fallback: Priority) -> Priority {
+        match self {
+            Self::Default => fallback,
+            other => other,
+        }
+    }
+
+    pub fn is_batch(self) -> bool {
+        self == Self::Batch
+    }
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Interactive => write!(f, "interactive"),
+            Self::Batch => write!(f, "batch"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Self::Default),
+            "interactive" => Ok(Self::Interactive),
+            "batch" => Ok(Self::Batch),
+            _ => Err(format!("Unknown priority: {s}")),
+        }
+    }
+}
+
+/// Slots for upstream requests and the users waiting for one.
+#[derive(Debug, Default)]
+pub struct FairScheduler {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    in_flight: u32,
+    next_id: u64,
+    interactive: Tier,
+    batch: Tier,
+    stats: BTreeMap<String, WaitStats>,
+}
+
+/// Requests of one priority waiting for a slot.
+#[derive(Debug, Default)]
+struct Tier {
+    /// Waiting requests per user, oldest first.
+    queues: HashMap<String, VecDeque<Waiter>>,
+    /// Users with waiting requests, in round-robin order.
+    active: VecDeque<String>,
+    deficit: HashMap<String, u32>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    id: u64,
+    user_id: String,
+    cost: u32,
+    enqueued: Instant,
+    tx: oneshot::Sender<()>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct WaitStats {
+    queued: u32,
+    served: u64,
+    total_wait_ms: u64,
+    max_wait_ms: u64,
+    timed_out: u64,
+}
+
+/// A slot for one upstream request, handed on when dropped.
+#[derive(Debug)]
+pub struct SchedulerPermit {
+    scheduler: Arc<FairScheduler>,
+}
+
+/// Current load and per-user queue waits.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct QueueReport {
+    /// `limits.max_in_flight` (0 = unlimited).
+    pub max_in_flight: u32,
+    pub in_flight: u32,
+    pub queued: u32,
+    /// Of `queued`, the requests of batch priority.
+    pub queued_batch: u32,
+    pub users: Vec<UserQueueReport>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UserQueueReport {
+    pub user_id: String,
+    /// Requests waiting now.
+    pub queued: u32,
+    /// Requests given a slot since startup.
+    pub served: u64,
+    pub avg_wait_ms: u64,
+    pub max_wait_ms: u64,
+    /// Requests refused after `limits.queue_timeout_secs`.
+    pub timed_out: u64,
+}
+
+impl FairScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait for a slot for a request of `cost` by `user_id`. Returns `None`
+    /// when `limits.max_in_flight` is unlimited.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        user_id: &str,
+        priority: Priority,
+        cost: u32,
+        limits: &LimitsConfig,
+    ) -> Result<Option<SchedulerPermit>, AppError> {
+        if limits.max_in_flight == 0 {
+            return Ok(None);
+        }
+        let permit = || SchedulerPermit {
+            scheduler: Arc::clone(self),
+        };
+
+        let (id, rx) = {
+            let mut inner = self.lock();
+            if inner.in_flight < limits.max_in_flight
+                && inner.interactive.is_empty()
+                && inner.batch.is_empty()
+            {
+                inner.in_flight += 1;
+                inner.stats.entry(user_id.to_string()).or_default().served += 1;
+                return Ok(Some(permit()));
+            }
+            let (tx, rx) = oneshot::channel();
+            inner.next_id += 1;
+            let id = inner.next_id;
+            let waiter = Waiter {
+                id,
+                user_id: user_id.to_string(),
+                cost,
+                enqueued: Instant::now(),
+                tx,
+            };
+            inner.tier(priority).push(waiter);
+            inner.stats.entry(user_id.to_string()).or_default().queued += 1;
+            // Slots freed up by a raised limit go to whoever is next.
+            while inner.in_flight < limits.max_in_flight {
+                let Some(waiter) = next_waiter(&mut inner) else {
+                    break;
+                };
+                if grant(&mut inner, waiter) {
+                    inner.in_flight += 1;
+                }
+            }
+            (id, rx)
+        };
+        tracing::debug!(user_id, %priority, cost, "Upstream slots full, queueing request");
+
+        // Removes the request from the queue if the caller gives up waiting.
+        let mut waiting = Waiting {
+            scheduler: self,
+            user_id,
+            id,
+            done: false,
+        };
+        let timeout = Duration::from_secs(limits.queue_timeout_secs);
+        let granted = match tokio::time::timeout(timeout, rx).await {
+            Ok(result) => result.is_ok(),
+            // Handed a slot just as the wait ran out: keep it.
+            Err(_) => !self.remove(user_id, id, true),
+        };
+        waiting.done = true;
+        if granted {
+            Ok(Some(permit()))
+        } else {
+            Err(AppError::RateLimited(format!(
+                "Server busy: no upstream slot within {}s; retry later",
+                limits.queue_timeout_secs
+            )))
+        }
+    }
+
+    /// Load and queue waits, for the admin API.
+    pub fn report(&self, limits: &LimitsConfig) -> QueueReport {
+        let inner = self.lock();
+        let users: Vec<UserQueueReport> = inner
+            .stats
+            .iter()
+            .map(|(user_id, s)| UserQueueReport {
+                user_id: user_id.clone(),
+                queued: s.queued,
+                served: s.served,
+                avg_wait_ms: s.total_wait_ms.checked_div(s.served).unwrap_or(0),
+                max_wait_ms: s.max_wait_ms,
+                timed_out: s.timed_out,
+            })
+            .collect();
+        QueueReport {
+            max_in_flight: limits.max_in_flight,
+            in_flight: inner.in_flight,
+            queued: users.iter().map(|u| u.queued).sum(),
+            queued_batch: inner.batch.len(),
+            users,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take a waiting request out of the queue. Returns whether it was
+    /// still there.
+    fn remove(&self, user_id: &str, id: u64, timed_out: bool) -> bool {
+        let mut inner = self.lock();
+        if !inner.interactive.remove(user_id, id) && !inner.batch.remove(user_id, id) {
+            return false;
+        }
+        let stats = inner.stats.entry(user_id.to_string()).or_default();
+        stats.queued -= 1;
+        if timed_out {
+            stats.timed_out += 1;
+        }
+        true
+    }
+
+    /// Hand a freed slot to the next waiting request, or free it.
+    /// Hand slots to waiting requests after `limits.max_in_flight` was
+    /// raised, or to all of them once it is unlimited (0).
+    pub fn resize(&self, max_in_flight: u32) {
+        let mut inner = self.lock();
+        while max_in_flight == 0 || inner.in_flight < max_in_flight {
+            let Some(waiter) = next_waiter(&mut inner) else {
+                break;
+            };
+            if grant(&mut inner, waiter) {
+                inner.in_flight += 1;
+            }
+        }
+    }
+
+    fn release(&self) {
+        let mut inner = self.lock();
+        while let Some(waiter) = next_waiter(&mut inner) {
+            if grant(&mut inner, waiter) {
+                return;
+            }
+        }
+        inner.in_flight = inner.in_flight.saturating_sub(1);
+    }
+}
+
+/// Wake a waiting request and record its wait. Returns false if its caller
+/// has already gone.
+fn grant(inner: &mut Inner, waiter: Waiter) -> bool {
+    let wait_ms = waiter.enqueued.elapsed().as_millis() as u64;
+    let stats = inner.stats.entry(waiter.user_id).or_default();
+    stats.queued -= 1;
+    if waiter.tx.send(()).is_err() {
+        return false;
+    }
+    stats.served += 1;
+    stats.total_wait_ms += wait_ms;
+    stats.max_wait_ms = stats.max_wait_ms.max(wait_ms);
+    true
+}
+
+/// The next request to serve: interactive ones first.
+fn next_waiter(inner: &mut Inner) -> Option<Waiter> {
+    inner.interactive.next().or_else(|| inner.batch.next())
+}
+
+impl Inner {
+    fn tier(&mut self, priority: Priority) -> &mut Tier {
+        if priority.is_batch() {
+            &mut self.batch
+        } else {
+            &mut self.interactive
+        }
+    }
+}
+
+impl Tier {
+    fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Requests waiting in the tier.
+    fn len(&self) -> u32 {
+        self.queues.values().map(|q| q.len() as u32).sum()
+    }
+
+    fn push(&mut self, waiter: Waiter) {
+        let queue = self.queues.entry(waiter.user_id.clone()).or_default();
+        if queue.is_empty() {
+            self.active.push_back(waiter.user_id.clone());
+        }
+        queue.push_back(waiter);
+    }
+
+    /// Take a waiting request out. Returns whether it was here.
+    fn remove(&mut self, user_id: &str, id: u64) -> bool {
+        let Some(queue) = self.queues.get_mut(user_id) else {
+            return false;
+        };
+        let Some(pos) = queue.iter().position(|w| w.id == id) else {
+            return false;
+        };
+        queue.remove(pos);
+        if queue.is_empty() {
+            self.queues.remove(user_id);
+            self.active.retain(|u| u != user_id);
+            self.deficit.remove(user_id);
+        }
+        true
+    }
+
+    /// Pick the next request by deficit round-robin.
+    fn next(&mut self) -> Option<Waiter> {
+        loop {
+            let user_id = self.active.front()?.clone();
+            let Some(queue) = self.queues.get_mut(&user_id) else {
+                self.active.pop_front();
+                continue;
+            };
+            let head_cost = queue.front().map_or(0, |w| w.cost);
+            let credit = self.deficit.entry(user_id.clone()).or_default();
+            if *credit >= head_cost {
+                *credit -= head_cost;
+                let waiter = queue.pop_front();
+                if queue.is_empty() {
+                    self.queues.remove(&user_id);
+                    self.active.pop_front();
+                    self.deficit.remove(&user_id);
+                }
+                if let Some(waiter) = waiter {
+                    return Some(waiter);
+                }
+                continue;
+            }
+            *credit += QUANTUM;
+            self.active.rotate_left(1);
+        }
+    }
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+/// A queued request, dequeued if its caller stops waiting.
+struct Waiting<'a> {
+    scheduler: &'a FairScheduler,
+    user_id: &'a str,
+    id: u64,
+    done: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.scheduler.remove(self.user_id, self.id, false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_in_flight: u32) -> LimitsConfig {
+        LimitsConfig {
+            max_in_flight,
+            queue_timeout_secs: 5,
+            ..Default::default()
+        }
+    }
+
+    fn enqueue(inner: &mut Inner, user_id: &str, cost: u32) -> oneshot::Receiver<()> {
+        enqueue_with(inner, user_id, Priority::Default, cost)
+    }
+
+    fn enqueue_with(
+        inner: &mut Inner,
+        user_id: &str,
+        priority: Priority,
+        cost: u32,
+    ) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        inner.next_id += 1;
+        let waiter = Waiter {
+            id: inner.next_id,
+            user_id: user_id.to_string(),
+            cost,
+            enqueued: Instant::now(),
+            tx,
+        };
+        inner.tier(priority).push(waiter);
+        rx
+    }
+
+    #[test]
+    fn test_round_robin_across_users() {
+        let mut inner = Inner::default();
+        let mut _rx: Vec<_> = (0..4).map(|_| enqueue(&mut inner, "bulk", 1)).collect();
+        _rx.push(enqueue(&mut inner, "interactive", 1));
+        let order: Vec<String> = std::iter::from_fn(|| next_waiter(&mut inner))
+            .map(|w| w.user_id)
+            .collect();
+        assert_eq!(order, ["bulk", "interactive", "bulk", "bulk", "bulk"]);
+        assert!(inner.interactive.is_empty());
+    }
+
+    #[test]
+    fn test_interactive_served_before_batch() {
+        let mut inner = Inner::default();
+        let _a: Vec<_> = (0..2)
+            .map(|_| enqueue_with(&mut inner, "etl", Priority::Batch, 1))
+            .collect();
+        let _b = enqueue_with(&mut inner, "alice", Priority::Interactive, 1);
+        let _c = enqueue(&mut inner, "bob", 1);
+        let order: Vec<String> = std::iter::from_fn(|| next_waiter(&mut inner))
+            .map(|w| w.user_id)
+            .collect();
+        assert_eq!(order, ["alice", "bob", "etl", "etl"]);
+    }
+
+    #[test]
+    fn test_priority_fallback() {
+        assert_eq!(Priority::Default.or(Priority::Batch), Priority::Batch);
+        assert_eq!(
+            Priority::Interactive.or(Priority::Batch),
+            Priority::Interactive
+        );
+        assert_eq!("Batch".parse::<Priority>().unwrap(), Priority::Batch);
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn test_large_requests_cost_more_turns() {
+        let mut inner = Inner::default();
+        let _a = enqueue(&mut inner, "big", 3);
+        let _b = enqueue(&mut inner, "big", 3);
+        let _c: Vec<_> = (0..4).map(|_| enqueue(&mut inner, "small", 1)).collect();
+        let order: Vec<String> = std::iter::from_fn(|| next_waiter(&mut inner))
+            .map(|w| w.user_id)
+            .collect();
+        // A big request waits out three turns; small ones go meanwhile.
+        assert_eq!(order, ["small", "small", "big", "small", "small", "big"]);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_a_slot() {
+        let scheduler = Arc::new(FairScheduler::new());
+        let limits = limits(1);
+        assert!(
+            scheduler
+                .acquire("u1", Priority::Default, 1, &LimitsConfig::default())
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let first = scheduler
+            .acquire("u1", Priority::Default, 1, &limits)
+            .await
+            .unwrap();
+        let waiter = {
+            let scheduler = Arc::clone(&scheduler);
+            let limits = limits.clone();
+            tokio::spawn(async move {
+                scheduler
+                    .acquire("u2", Priority::Default, 1, &limits)
+                    .await
+                    .map(|p| p.is_some())
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(scheduler.report(&limits).queued, 1);
+
+        drop(first);
+        assert!(waiter.await.unwrap().unwrap());
+        let report = scheduler.report(&limits);
+        assert_eq!(report.in_flight, 0);
+        assert_eq!(report.queued, 0);
+        let u2 = report.users.iter().find(|u| u.user_id == "u2").unwrap();
+        assert_eq!(u2.served, 1);
+        assert!(u2.max_wait_ms >= 10);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_times_out() {
+        let scheduler = Arc::new(FairScheduler::new());
+        let limits = LimitsConfig {
+            max_in_flight: 1,
+            queue_timeout_secs: 0,
+            ..Default::default()
+        };
+        let _held = scheduler
+            .acquire("u1", Priority::Default, 1, &limits)
+            .await
+            .unwrap();
+        let err = scheduler
+            .acquire("u2", Priority::Default, 1, &limits)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::RateLimited(_)));
+        let report = scheduler.report(&limits);
+        assert_eq!(report.in_flight, 1);
+        assert_eq!(report.queued, 0);
+        assert_eq!(report.users[1].timed_out, 1);
+    }
+
+    #[test]
+    fn test_cost_counts_input_tokens() {
+        assert_eq!(cost(0), 1);
+        assert_eq!(cost(TOKENS_PER_UNIT * 3), 4);
+        assert_eq!(cost(u64::MAX), MAX_COST);
+    }
+
+    #[tokio::test]
+    async fn test_resize_wakes_waiters() {
+        let scheduler = Arc::new(FairScheduler::new());
+        let limits = limits(1);
+        let _held = scheduler
+            .acquire("u1", Priority::Default, 1, &limits)
+            .await
+            .unwrap();
+        let waiter = {
+            let scheduler = Arc::clone(&scheduler);
+            let limits = limits.clone();
+            tokio::spawn(
+                async move { scheduler.acquire("u2", Priority::Default, 1, &limits).await },
+            )
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(scheduler.report(&limits).queued, 1);
+
+        scheduler.resize(2);
+        let _granted = waiter.await.unwrap().unwrap().unwrap();
+        assert_eq!(scheduler.report(&limits).in_flight, 2);
+    }
+}
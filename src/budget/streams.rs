@@ -0,0 +1,189 @@
+//! Limits on concurrently open streaming responses (`[limits]`).
+//!
+//! Each streaming completion holds a [`StreamPermit`] for its user and API
+//! key until the stream ends or is dropped. A stream that would take a user
+//! or key past `limits.max_streams_per_user` / `max_streams_per_key` is
+//! refused with 429, so one client cannot hold all of the providers'
+//! concurrency.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use tokio_stream::Stream;
+
+use crate::auth::AuthUser;
+use crate::config::LimitsConfig;
+use crate::error::AppError;
+
+/// Open streams per user and per key.
+#[derive(Debug, Default)]
+pub struct StreamLimiter {
+    open: Mutex<HashMap<String, u32>>,
+}
+
+/// A slot for one open stream, given back when dropped.
+#[derive(Debug)]
+pub struct StreamPermit {
+    limiter: Arc<StreamLimiter>,
+    slots: Vec<String>,
+}
+
+impl StreamLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a slot for a new stream by `user`, or refuse it if the user or
+    /// its key already has as many open as `limits` allow. Returns `None`
+    /// when no limit applies.
+    pub fn acquire(
+        self: &Arc<Self>,
+        user: &AuthUser,
+        limits: &LimitsConfig,
+    ) -> Result<Option<StreamPermit>, AppError> {
+        let mut checks = Vec::new();
+        if limits.max_streams_per_user > 0 {
+            checks.push((
+                format!("user:{}", user.user_id),
+                limits.max_streams_per_user,
+                format!("user '{}'", user.name),
+            ));
+        }
+        if limits.max_streams_per_key > 0
+            && let Some(key_id) = &user.key_id
+        {
+            checks.push((
+                format!("key:{key_id}"),
+                limits.max_streams_per_key,
+                "this API key".to_string(),
+            ));
+        }
+        if checks.is_empty() {
+            return Ok(None);
+        }
+
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        for (slot, limit, who) in &checks {
+            if open.get(slot).copied().unwrap_or(0) >= *limit {
+                return Err(AppError::RateLimited(format!(
+                    "Too many open streams for {who} (limit {limit}); \
+                     wait for one to finish"
+                )));
+            }
+        }
+        let slots: Vec<String> = checks.into_iter().map(|(slot, ..)| slot).collect();
+        for slot in &slots {
+            *open.entry(slot.clone()).or_default() += 1;
+        }
+        Ok(Some(StreamPermit {
+            limiter: Arc::clone(self),
+            slots,
+        }))
+    }
+
+    /// Streams open for a user.
+    pub fn open_for_user(&self, user_id: &str) -> u32 {
+        let open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        open.get(&format!("user:{user_id}")).copied().unwrap_or(0)
+    }
+}
+
+impl Drop for StreamPermit {
+    fn drop(&mut self) {
+        let mut open = self.limiter.open.lock().unwrap_or_else(|e| e.into_inner());
+        for slot in &self.slots {
+            if let Some(count) = open.get_mut(slot) {
+                *count -= 1;
+                if *count == 0 {
+                    open.remove(slot);
+                }
+            }
+        }
+    }
+}
+
+/// A stream holding a permit (a [`StreamPermit`], a scheduler slot) for as
+/// long as it lives.
+pub struct PermittedStream<S, P = StreamPermit> {
+    inner: S,
+    _permit: P,
+}
+
+impl<S, P> PermittedStream<S, P> {
+    pub fn new(inner: S, permit: P) -> Self {
+        Self {
+            inner,
+            _permit: permit,
+        }
+    }
+}
+
+impl<S: Stream + Unpin, P: Unpin> Stream for PermittedStream<S, P> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::reasoning::ReasoningMode;
+
+    fn user(key_id: &str) -> AuthUser {
+        AuthUser {
+            user_id: "u1".to_string(),
+            name: "alice".to_string(),
+            role: "member".to_string(),
+            reasoning_mode: ReasoningMode::default(),
+            team: None,
+            impersonator: None,
+            key_id: Some(key_id.to_string()),
+            scope: None,
+            origin: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_user_limit() {
+        let limiter = Arc::new(StreamLimiter::new());
+        let limits = LimitsConfig {
+            max_streams_per_user: 2,
+            ..Default::default()
+        };
+        let first = limiter.acquire(&user("k1"), &limits).unwrap();
+        let _second = limiter.acquire(&user("k2"), &limits).unwrap();
+        assert_eq!(limiter.open_for_user("u1"), 2);
+        let err = limiter.acquire(&user("k1"), &limits).unwrap_err();
+        assert!(matches!(err, AppError::RateLimited(_)));
+
+        drop(first);
+        assert_eq!(limiter.open_for_user("u1"), 1);
+        assert!(limiter.acquire(&user("k1"), &limits).is_ok());
+    }
+
+    #[test]
+    fn test_key_limit() {
+        let limiter = Arc::new(StreamLimiter::new());
+        let limits = LimitsConfig {
+            max_streams_per_key: 1,
+            ..Default::default()
+        };
+        let _held = limiter.acquire(&user("k1"), &limits).unwrap().unwrap();
+        assert!(limiter.acquire(&user("k1"), &limits).is_err());
+        // Another key of the same user has its own allowance.
+        assert!(limiter.acquire(&user("k2"), &limits).is_ok());
+    }
+
+    #[test]
+    fn test_unlimited_takes_no_permit() {
+        let limiter = Arc::new(StreamLimiter::new());
+        let permit = limiter
+            .acquire(&user("k1"), &LimitsConfig::default())
+            .unwrap();
+        assert!(permit.is_none());
+    }
+}
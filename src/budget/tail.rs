@@ -0,0 +1,219 @@
+//! Live tail of completed requests for the web UI's log page.
+//!
+//! The audit logger publishes every entry it receives here, before batching
+//! it to the database, so subscribers see requests as they finish. The last
+//! [`TAIL_CAPACITY`] entries are kept in memory to seed new subscribers.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use super::AuditEntry;
+
+/// Number of recent requests kept for new subscribers.
+pub const TAIL_CAPACITY: usize = 200;
+
+/// One completed request as shown in the live tail.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TailEntry {
+    /// Completion time (UTC, RFC 3339).
+    pub time: String,
+    pub request_id: String,
+    pub user_id: String,
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub latency_ms: u64,
+    /// Provider cost before markups, in `pricing.currency`.
+    pub cost: f64,
+    pub status: String,
+    /// Served from the semantic cache.
+    pub cache_hit: bool,
+}
+
+impl TailEntry {
+    fn from_audit(entry: &AuditEntry) -> Self {
+        Self {
+            time: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            request_id: entry.request_id.clone(),
+            user_id: entry.user_id.clone(),
+            provider: entry.provider.clone(),
+            model: entry.model.clone(),
+            input_tokens: entry.input_tokens,
+            output_tokens: entry.output_tokens,
+            latency_ms: entry.latency_ms,
+            cost: entry.cost,
+            status: entry.status.clone(),
+            cache_hit: entry.status.starts_with("cache_hit"),
+        }
+    }
+}
+
+/// Restricts the tail to one user and/or provider.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TailFilter {
+    pub user: Option<String>,
+    pub provider: Option<String>,
+}
+
+impl TailFilter {
+    pub fn matches(&self, entry: &TailEntry) -> bool {
+        let user = self.user.as_deref().filter(|u| !u.is_empty());
+        let provider = self.provider.as_deref().filter(|p| !p.is_empty());
+        user.is_none_or(|u| u == entry.user_id)
+            && provider.is_none_or(|p| p.eq_ignore_ascii_case(&entry.provider))
+    }
+}
+
+/// Recent requests plus a broadcast channel of new ones.
+pub struct RequestTail {
+    recent: Mutex<VecDeque<TailEntry>>,
+    tx: broadcast::Sender<TailEntry>,
+}
+
+impl Default for RequestTail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestTail {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(TAIL_CAPACITY);
+        Self {
+            recent: Mutex::new(VecDeque::with_capacity(TAIL_CAPACITY)),
+            tx,
+        }
+    }
+
+    /// Record a finished request and send it to current subscribers.
+    pub fn publish(&self, entry: &AuditEntry) {
+        let entry = TailEntry::from_audit(entry);
+        // Send under the lock so `subscribe` never sees an entry twice.
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == TAIL_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(entry.clone());
+        // No receivers is fine: nobody has the log page open.
+        let _ = self.tx.send(entry);
+    }
+
+    /// Drop a user's requests from the recent list, returning how many
+    /// there were.
+    pub fn forget_user(&self, user_id: &str) -> usize {
+        let mut recent = self.recent.lock().unwrap();
+        let before = recent.len();
+        recent.retain(|e| e.user_id != user_id);
+        before - recent.len()
+    }
+
+    /// Recent requests matching `filter`, oldest first.
+    pub fn recent(&self, filter: &TailFilter) -> Vec<TailEntry> {
+        Self::matching(&self.recent.lock().unwrap(), filter)
+    }
+
+    /// Recent requests matching `filter`, plus a receiver of everything
+    /// published afterwards. A receiver that falls more than
+    /// [`TAIL_CAPACITY`] entries behind skips the oldest ones.
+    pub fn subscribe(
+        &self,
+        filter: &TailFilter,
+    ) -> (Vec<TailEntry>, broadcast::Receiver<TailEntry>) {
+        let recent = self.recent.lock().unwrap();
+        (Self::matching(&recent, filter), self.tx.subscribe())
+    }
+
+    /// The most recent request logged under `request_id`, if still held.
+    pub fn find(&self, request_id: &str) -> Option<TailEntry> {
+        self.recent
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|e| e.request_id == request_id)
+            .cloned()
+    }
+
+    fn matching(recent: &VecDeque<TailEntry>, filter: &TailFilter) -> Vec<TailEntry> {
+        recent
+            .iter()
+            .filter(|e| filter.matches(e))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::budget::Attribution;
+
+    fn entry(user_id: &str, provider: &str, status: &str) -> AuditEntry {
+        AuditEntry {
+            user_id: user_id.to_string(),
+            request_id: "req-1".to_string(),
+            provider: provider.to_string(),
+            model: "m".to_string(),
+            input_tokens: 1,
+            output_tokens: 2,
+            audio_seconds: 0.0,
+            premium_requests: 0.0,
+            cost: 0.0,
+            cost_saved: 0.0,
+            latency_ms: 10,
+            status: status.to_string(),
+            finish_reason: None,
+            attribution: Attribution::default(),
+        }
+    }
+
+    #[test]
+    fn test_recent_is_bounded_and_filtered() {
+        let tail = RequestTail::new();
+        for i in 0..TAIL_CAPACITY + 5 {
+            let provider = if i % 2 == 0 { "claude" } else { "gemini" };
+            tail.publish(&entry(&format!("u{}", i % 3), provider, "success"));
+        }
+        assert_eq!(tail.recent(&TailFilter::default()).len(), TAIL_CAPACITY);
+
+        let filter = TailFilter {
+            user: Some("u0".to_string()),
+            provider: Some("Claude".to_string()),
+        };
+        let matched = tail.recent(&filter);
+        assert!(!matched.is_empty());
+        assert!(
+            matched
+                .iter()
+                .all(|e| e.user_id == "u0" && e.provider == "claude")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_new_entries() {
+        let tail = RequestTail::new();
+        tail.publish(&entry("u0", "claude", "success"));
+        let (backlog, mut rx) = tail.subscribe(&TailFilter::default());
+        assert_eq!(backlog.len(), 1);
+        tail.publish(&entry("u1", "cache", "cache_hit_exact"));
+        let received = rx.recv().await.unwrap();
+        assert_eq!(received.user_id, "u1");
+        assert!(received.cache_hit);
+    }
+
+    #[test]
+    fn test_find_by_request_id() {
+        let tail = RequestTail::new();
+        let mut e = entry("u1", "claude", "success");
+        e.request_id = "req-2".to_string();
+        e.cost = 0.5;
+        tail.publish(&e);
+        tail.publish(&entry("u1", "gemini", "success"));
+        assert_eq!(tail.find("req-2").unwrap().cost, 0.5);
+        assert!(tail.find("req-3").is_none());
+    }
+}
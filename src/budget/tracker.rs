@@ -1,7 +1,10 @@
+use std::sync::Mutex;
+
 use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
 use rusqlite::params;
 use serde::Serialize;
 
+use crate::budget::quota::{QuotaDecision, QuotaScope, QuotaState, RateLimits};
 use crate::db::Database;
 use crate::error::AppError;
 
@@ -15,6 +18,12 @@ pub struct Budget {
     pub daily_used: f64,
     pub period_start: String,
     pub day_start: String,
+    /// Cap on upstream premium requests (GitHub Copilot) per month.
+    pub monthly_premium_limit: Option<f64>,
+    /// Cap on upstream premium requests per day.
+    pub daily_premium_limit: Option<f64>,
+    pub monthly_premium_used: f64,
+    pub daily_premium_used: f64,
 }
 
 /// Result of a budget check.
@@ -28,14 +37,19 @@ pub enum BudgetStatus {
     Exceeded,
 }
 
-/// Tracks per-user budget allocation and usage against the SQLite database.
+/// Tracks per-user budget allocation and usage against the SQLite database,
+/// plus per-user and per-team rate quotas.
 pub struct BudgetTracker {
     db: Database,
+    quotas: Mutex<QuotaState>,
 }
 
 impl BudgetTracker {
     pub fn new(db: Database) -> Self {
-        Self { db }
+        Self {
+            db,
+            quotas: Mutex::new(QuotaState::default()),
+        }
     }
 
     /// Check whether a user is within their budget, resetting period counters
@@ -91,6 +105,68 @@ impl BudgetTracker {
         Ok(BudgetStatus::Ok)
     }
 
+    /// Check whether a request with a known up-front cost fits in the user's
+    /// remaining budget.
+    ///
+    /// Used for requests priced per unit (e.g. images), where the cost can be
+    /// estimated before the provider is called. Returns `Exceeded` if adding
+    /// `estimated_cost` would cross either limit.
+    pub fn check_projected_cost(
+        &self,
+        user_id: &str,
+        estimated_cost: f64,
+    ) -> Result<BudgetStatus, AppError> {
+        self.maybe_reset_periods(user_id)?;
+
+        let budget = match self.get_budget(user_id)? {
+            Some(b) => b,
+            None => return Ok(BudgetStatus::Ok),
+        };
+
+        let over = |limit: Option<f64>, used: f64| {
+            limit.is_some_and(|limit| limit > 0.0 && used + estimated_cost > limit)
+        };
+        if over(budget.monthly_limit, budget.monthly_used)
+            || over(budget.daily_limit, budget.daily_used)
+        {
+            return Ok(BudgetStatus::Exceeded);
+        }
+
+        Ok(BudgetStatus::Ok)
+    }
+
+    /// Check whether a request consuming `premium_requests` upstream premium
+    /// requests fits in the user's premium request limits.
+    ///
+    /// Premium limits are separate from the dollar budget: a user over their
+    /// premium allowance can still use models that consume none.
+    pub fn check_premium_requests(
+        &self,
+        user_id: &str,
+        premium_requests: f64,
+    ) -> Result<BudgetStatus, AppError> {
+        if premium_requests <= 0.0 {
+            return Ok(BudgetStatus::Ok);
+        }
+        self.maybe_reset_periods(user_id)?;
+
+        let budget = match self.get_budget(user_id)? {
+            Some(b) => b,
+            None => return Ok(BudgetStatus::Ok),
+        };
+
+        let over = |limit: Option<f64>, used: f64| {
+            limit.is_some_and(|limit| limit >= 0.0 && used + premium_requests > limit)
+        };
+        if over(budget.monthly_premium_limit, budget.monthly_premium_used)
+            || over(budget.daily_premium_limit, budget.daily_premium_used)
+        {
+            return Ok(BudgetStatus::Exceeded);
+        }
+
+        Ok(BudgetStatus::Ok)
+    }
+
     /// Add cost to both daily and monthly usage counters.
     pub fn record_usage(&self, user_id: &str, cost: f64) -> Result<(), AppError> {
         // Reset stale periods first.
@@ -111,7 +187,8 @@ impl BudgetTracker {
     pub fn get_budget(&self, user_id: &str) -> Result<Option<Budget>, AppError> {
         let result = self.db.with_conn(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT user_id, monthly_limit, daily_limit, monthly_used, daily_used, period_start, day_start \
+                "SELECT user_id, monthly_limit, daily_limit, monthly_used, daily_used, period_start, day_start, \
+                 monthly_premium_limit, daily_premium_limit, monthly_premium_used, daily_premium_used \
                  FROM budgets WHERE user_id = ?1",
             )?;
             let budget = stmt.query_row(params![user_id], |row| {
@@ -123,6 +200,10 @@ impl BudgetTracker {
                     daily_used: row.get(4)?,
                     period_start: row.get(5)?,
                     day_start: row.get(6)?,
+                    monthly_premium_limit: row.get(7)?,
+                    daily_premium_limit: row.get(8)?,
+                    monthly_premium_used: row.get(9)?,
+                    daily_premium_used: row.get(10)?,
                 })
             });
 
@@ -168,6 +249,134 @@ impl BudgetTracker {
         Ok(())
     }
 
+    /// Set the premium request limits of a user, creating the budget row
+    /// without dollar limits if there is none.
+    pub fn set_premium_limits(
+        &self,
+        user_id: &str,
+        monthly_limit: Option<f64>,
+        daily_limit: Option<f64>,
+    ) -> Result<(), AppError> {
+        let now = Utc::now();
+        let period_start = now.format("%Y-%m-%d %H:%M:%S").to_string();
+        let day_start = now.format("%Y-%m-%d").to_string();
+
+        self.db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO budgets (user_id, monthly_premium_limit, daily_premium_limit, \
+                 period_start, day_start) \
+                 VALUES (?1, ?2, ?3, ?4, ?5) \
+                 ON CONFLICT(user_id) DO UPDATE SET \
+                   monthly_premium_limit = ?2, \
+                   daily_premium_limit = ?3",
+                params![user_id, monthly_limit, daily_limit, period_start, day_start],
+            )?;
+            Ok(())
+        })?;
+
+        tracing::info!(
+            user_id = %user_id,
+            monthly_premium_limit = ?monthly_limit,
+            daily_premium_limit = ?daily_limit,
+            "Premium request limits set"
+        );
+        Ok(())
+    }
+
+    /// Upsert rate limits for a user or team. All-`None` limits remove the row.
+    pub fn set_rate_limits(
+        &self,
+        scope: QuotaScope,
+        subject: &str,
+        limits: RateLimits,
+    ) -> Result<(), AppError> {
+        self.db.with_conn(|conn| {
+            if limits.is_unlimited() {
+                conn.execute(
+                    "DELETE FROM rate_limits WHERE scope = ?1 AND subject = ?2",
+                    params![scope.as_str(), subject],
+                )?;
+            } else {
+                conn.execute(
+                    "INSERT INTO rate_limits (scope, subject, rpm, tpm, rpd) \
+                     VALUES (?1, ?2, ?3, ?4, ?5) \
+                     ON CONFLICT(scope, subject) DO UPDATE SET rpm = ?3, tpm = ?4, rpd = ?5",
+                    params![
+                        scope.as_str(),
+                        subject,
+                        limits.rpm.map(|v| v as i64),
+                        limits.tpm.map(|v| v as i64),
+                        limits.rpd.map(|v| v as i64),
+                    ],
+                )?;
+            }
+            Ok(())
+        })?;
+
+        tracing::info!(
+            scope = %scope,
+            subject = %subject,
+            rpm = ?limits.rpm,
+            tpm = ?limits.tpm,
+            rpd = ?limits.rpd,
+            "Rate limits set"
+        );
+        Ok(())
+    }
+
+    /// Rate limits for a user or team (unlimited if none are stored).
+    pub fn get_rate_limits(
+        &self,
+        scope: QuotaScope,
+        subject: &str,
+    ) -> Result<RateLimits, AppError> {
+        let limits = self.db.with_conn(|conn| {
+            let row = conn.query_row(
+                "SELECT rpm, tpm, rpd FROM rate_limits WHERE scope = ?1 AND subject = ?2",
+                params![scope.as_str(), subject],
+                rate_limits_from_row,
+            );
+            match row {
+                Ok(l) => Ok(l),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(RateLimits::default()),
+                Err(e) => Err(e),
+            }
+        })?;
+        Ok(limits)
+    }
+
+    /// Check the user's and their team's rate limits, counting the request
+    /// if it is allowed.
+    pub fn check_rate_limits(
+        &self,
+        user_id: &str,
+        team: Option<&str>,
+    ) -> Result<QuotaDecision, AppError> {
+        let mut limits = vec![(
+            QuotaScope::User,
+            user_id.to_string(),
+            self.get_rate_limits(QuotaScope::User, user_id)?,
+        )];
+        if let Some(team) = team {
+            limits.push((
+                QuotaScope::Team,
+                team.to_string(),
+                self.get_rate_limits(QuotaScope::Team, team)?,
+            ));
+        }
+        limits.retain(|(_, _, l)| !l.is_unlimited());
+
+        let mut quotas = self.quotas.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(quotas.check(user_id, team, &limits, unix_now()))
+    }
+
+    /// Charge tokens from a finished request to the user's (and team's)
+    /// tokens-per-minute quota.
+    pub fn record_tokens(&self, user_id: &str, tokens: u64) {
+        let mut quotas = self.quotas.lock().unwrap_or_else(|e| e.into_inner());
+        quotas.record_tokens(user_id, tokens, unix_now());
+    }
+
     /// Reset monthly/daily counters if the current period has elapsed.
     fn maybe_reset_periods(&self, user_id: &str) -> Result<(), AppError> {
         let budget = match self.get_budget(user_id)? {
@@ -206,17 +415,20 @@ impl BudgetTracker {
                 if needs_monthly_reset && needs_daily_reset {
                     conn.execute(
                         "UPDATE budgets SET monthly_used = 0.0, daily_used = 0.0, \
+                         monthly_premium_used = 0.0, daily_premium_used = 0.0, \
                          period_start = ?1, day_start = ?2 WHERE user_id = ?3",
                         params![new_period_start, new_day_start, user_id],
                     )?;
                 } else if needs_monthly_reset {
                     conn.execute(
-                        "UPDATE budgets SET monthly_used = 0.0, period_start = ?1 WHERE user_id = ?2",
+                        "UPDATE budgets SET monthly_used = 0.0, monthly_premium_used = 0.0, \
+                         period_start = ?1 WHERE user_id = ?2",
                         params![new_period_start, user_id],
                     )?;
                 } else {
                     conn.execute(
-                        "UPDATE budgets SET daily_used = 0.0, day_start = ?1 WHERE user_id = ?2",
+                        "UPDATE budgets SET daily_used = 0.0, daily_premium_used = 0.0, \
+                         day_start = ?1 WHERE user_id = ?2",
                         params![new_day_start, user_id],
                     )?;
                 }
@@ -228,6 +440,22 @@ impl BudgetTracker {
     }
 }
 
+fn rate_limits_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RateLimits> {
+    let get = |i| {
+        row.get::<_, Option<i64>>(i)
+            .map(|v| v.map(|v| v.max(0) as u64))
+    };
+    Ok(RateLimits {
+        rpm: get(0)?,
+        tpm: get(1)?,
+        rpd: get(2)?,
+    })
+}
+
+fn unix_now() -> u64 {
+    Utc::now().timestamp().max(0) as u64
+}
+
 /// Add one month to a NaiveDateTime, clamping the day to the last day of the
 /// target month.
 fn add_one_month(dt: NaiveDateTime) -> NaiveDateTime {
@@ -323,6 +551,77 @@ mod tests {
         assert!((budget.daily_used - 5.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_check_projected_cost() {
+        let db = test_db();
+        let tracker = BudgetTracker::new(db);
+
+        // No budget row => unlimited.
+        assert_eq!(
+            tracker.check_projected_cost("user1", 1000.0).unwrap(),
+            BudgetStatus::Ok
+        );
+
+        tracker.set_budget("user1", Some(100.0), Some(1.0)).unwrap();
+        tracker.record_usage("user1", 0.9).unwrap();
+        assert_eq!(
+            tracker.check_projected_cost("user1", 0.08).unwrap(),
+            BudgetStatus::Ok
+        );
+        assert_eq!(
+            tracker.check_projected_cost("user1", 0.16).unwrap(),
+            BudgetStatus::Exceeded
+        );
+    }
+
+    #[test]
+    fn test_check_premium_requests() {
+        let db = test_db();
+        let tracker = BudgetTracker::new(db.clone());
+
+        // No budget row => unlimited.
+        assert_eq!(
+            tracker.check_premium_requests("user1", 10.0).unwrap(),
+            BudgetStatus::Ok
+        );
+
+        tracker
+            .set_premium_limits("user1", Some(300.0), Some(2.0))
+            .unwrap();
+        let budget = tracker.get_budget("user1").unwrap().unwrap();
+        assert_eq!(budget.monthly_limit, None);
+        assert_eq!(budget.daily_premium_limit, Some(2.0));
+
+        db.with_conn(|conn| {
+            conn.execute(
+                "UPDATE budgets SET daily_premium_used = 1.5 WHERE user_id = 'user1'",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            tracker.check_premium_requests("user1", 0.33).unwrap(),
+            BudgetStatus::Ok
+        );
+        assert_eq!(
+            tracker.check_premium_requests("user1", 1.0).unwrap(),
+            BudgetStatus::Exceeded
+        );
+        // Models without a premium charge are never blocked.
+        assert_eq!(
+            tracker.check_premium_requests("user1", 0.0).unwrap(),
+            BudgetStatus::Ok
+        );
+        // Premium limits do not count against the dollar budget.
+        assert_eq!(tracker.check_budget("user1", 80).unwrap(), BudgetStatus::Ok);
+
+        // Setting dollar limits keeps the premium limits.
+        tracker.set_budget("user1", Some(100.0), None).unwrap();
+        let budget = tracker.get_budget("user1").unwrap().unwrap();
+        assert_eq!(budget.monthly_premium_limit, Some(300.0));
+    }
+
     #[test]
     fn test_budget_check_ok() {
         let db = test_db();
@@ -475,4 +774,51 @@ mod tests {
         assert!((budget.monthly_used - 10.0).abs() < f64::EPSILON);
         assert!((budget.daily_used - 10.0).abs() < f64::EPSILON);
     }
+
+    #[test]
+    fn test_rate_limits_roundtrip_and_check() {
+        let tracker = BudgetTracker::new(test_db());
+        assert!(
+            tracker
+                .get_rate_limits(QuotaScope::User, "user1")
+                .unwrap()
+                .is_unlimited()
+        );
+        assert!(matches!(
+            tracker.check_rate_limits("user1", None).unwrap(),
+            QuotaDecision::Allowed(s) if s.requests.is_none()
+        ));
+
+        let limits = RateLimits {
+            rpm: Some(1),
+            tpm: None,
+            rpd: Some(100),
+        };
+        tracker
+            .set_rate_limits(QuotaScope::User, "user1", limits)
+            .unwrap();
+        assert_eq!(
+            tracker.get_rate_limits(QuotaScope::User, "user1").unwrap(),
+            limits
+        );
+
+        assert!(matches!(
+            tracker.check_rate_limits("user1", None).unwrap(),
+            QuotaDecision::Allowed(_)
+        ));
+        assert!(matches!(
+            tracker.check_rate_limits("user1", None).unwrap(),
+            QuotaDecision::Limited { .. }
+        ));
+
+        tracker
+            .set_rate_limits(QuotaScope::User, "user1", RateLimits::default())
+            .unwrap();
+        assert!(
+            tracker
+                .get_rate_limits(QuotaScope::User, "user1")
+                .unwrap()
+                .is_unlimited()
+        );
+    }
 }
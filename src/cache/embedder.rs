@@ -1,5 +1,5 @@
 use crate::cache::types::CacheError;
-use std::net::IpAddr;
+use crate::net::is_public_ip;
 use url::Url;
 
 /// Call an OpenAI-compatible `/v1/embeddings` endpoint and return the vector.
@@ -89,15 +89,6 @@ async fn ensure_safe_url(url_str: &str, allow_local: bool) -> Result<(), CacheEr
     Ok(())
 }
 
-fn is_public_ip(addr: &IpAddr) -> bool {
-    match addr {
-        IpAddr::V4(ipv4) => !ipv4.is_private() && !ipv4.is_loopback() && !ipv4.is_link_local(),
-        IpAddr::V6(ipv6) => {
-            !ipv6.is_loopback() && !ipv6.is_unique_local() && !ipv6.is_unicast_link_local()
-        }
-    }
-}
-
 // ---------------------------------------------------------------------------
 // Response types (minimal, just what we need)
 // ---------------------------------------------------------------------------
@@ -24,6 +24,11 @@ pub fn should_skip(request: &ChatRequest, config: &CacheConfig) -> bool {
         return true;
     }
 
+    // Search-grounded answers depend on the current web results.
+    if request.google_search {
+        return true;
+    }
+
     false
 }
 
@@ -42,6 +47,11 @@ pub fn should_skip_stream(request: &ChatRequest, config: &CacheConfig) -> bool {
         return true;
     }
 
+    // Search-grounded answers depend on the current web results.
+    if request.google_search {
+        return true;
+    }
+
     false
 }
 
@@ -101,6 +111,16 @@ pub fn exact_hash(request: &ChatRequest) -> String {
         hasher.update(tc.to_string().as_bytes());
     }
 
+    // Provider-specific parameters (only when present, so existing keys stay
+    // valid)
+    if let Some(ref extra) = request.extra_body {
+        hasher.update(b"|");
+        match serde_json::to_string(extra) {
+            Ok(extra_json) => hasher.update(extra_json.as_bytes()),
+            Err(_) => hasher.update(b"<extra-body-serialization-error>"),
+        }
+    }
+
     format!("{:x}", hasher.finalize())
 }
 
@@ -236,6 +256,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         }
     }
 
@@ -258,6 +281,10 @@ mod tests {
         let mut req3 = req1.clone();
         req3.temperature = Some(0.8);
         assert_ne!(exact_hash(&req1), exact_hash(&req3));
+
+        let mut req4 = req1.clone();
+        req4.extra_body = serde_json::json!({"top_k": 5}).as_object().cloned();
+        assert_ne!(exact_hash(&req1), exact_hash(&req4));
     }
 
     #[test]
@@ -277,6 +304,11 @@ mod tests {
 
         req2.stream = true;
         assert!(should_skip(&req2, &config));
+
+        let mut req3 = default_request();
+        req3.google_search = true;
+        assert!(should_skip(&req3, &config));
+        assert!(should_skip_stream(&req3, &config));
     }
 
     #[test]
@@ -20,6 +20,7 @@ use crate::providers::types::{
 
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use self::store::CacheStore;
 use self::types::{
@@ -27,6 +28,29 @@ use self::types::{
     CacheStatsSnapshot,
 };
 
+/// The store of a node-local cache: persistent at `config.path` when built
+/// with `cache-persistent`, otherwise in memory.
+#[cfg(feature = "cache-persistent")]
+async fn local_store(config: &CacheConfig) -> Result<CacheStore, CacheError> {
+    CacheStore::persistent(
+        config.path.to_str().unwrap_or("gaud.cache"),
+        config.embedding_dimension,
+    )
+    .await
+}
+
+#[cfg(all(not(feature = "cache-persistent"), feature = "cache-ephemeral"))]
+async fn local_store(config: &CacheConfig) -> Result<CacheStore, CacheError> {
+    CacheStore::ephemeral(config.embedding_dimension).await
+}
+
+#[cfg(all(not(feature = "cache-persistent"), not(feature = "cache-ephemeral")))]
+async fn local_store(_config: &CacheConfig) -> Result<CacheStore, CacheError> {
+    Err(CacheError::InitFailed(
+        "No cache storage backend enabled (persistent or ephemeral)".into(),
+    ))
+}
+
 // ---------------------------------------------------------------------------
 // SemanticCacheService -- public facade
 // ---------------------------------------------------------------------------
@@ -35,29 +59,33 @@ use self::types::{
 pub struct SemanticCacheService {
     store: CacheStore,
     config: CacheConfig,
+    /// `config.similarity_threshold` as f32 bits, changeable at runtime.
+    similarity_threshold: AtomicU32,
     stats: CacheStats,
 }
 
 impl SemanticCacheService {
     /// Initialize the cache service with the given configuration.
     pub async fn new(config: &CacheConfig) -> Result<Self, CacheError> {
-        #[cfg(feature = "cache-persistent")]
-        let store = CacheStore::persistent(
-            config.path.to_str().unwrap_or("gaud.cache"),
-            config.embedding_dimension,
-        )
-        .await?;
-
-        #[cfg(all(not(feature = "cache-persistent"), feature = "cache-ephemeral"))]
-        let store = CacheStore::ephemeral(config.embedding_dimension).await?;
-
-        #[cfg(all(not(feature = "cache-persistent"), not(feature = "cache-ephemeral")))]
-        return Err(CacheError::InitFailed(
-            "No cache storage backend enabled (persistent or ephemeral)".into(),
-        ));
+        if let Some(url) = config.backend_url.as_deref().filter(|u| !u.is_empty()) {
+            #[cfg(feature = "cache-distributed")]
+            return Ok(Self {
+                store: CacheStore::remote(url, config.embedding_dimension).await?,
+                similarity_threshold: AtomicU32::new(config.similarity_threshold.to_bits()),
+                config: config.clone(),
+                stats: CacheStats::new(),
+            });
+
+            #[cfg(not(feature = "cache-distributed"))]
+            return Err(CacheError::InitFailed(format!(
+                "cache.backend_url is set to '{url}' but this build lacks the \
+                 `cache-distributed` feature"
+            )));
+        }
 
         Ok(Self {
-            store,
+            store: local_store(config).await?,
+            similarity_threshold: AtomicU32::new(config.similarity_threshold.to_bits()),
             config: config.clone(),
             stats: CacheStats::new(),
         })
@@ -69,11 +97,24 @@ impl SemanticCacheService {
     pub(crate) fn new_with_store(store: CacheStore, config: CacheConfig) -> Self {
         Self {
             store,
+            similarity_threshold: AtomicU32::new(config.similarity_threshold.to_bits()),
             config,
             stats: CacheStats::new(),
         }
     }
 
+    /// Cosine similarity a semantic match needs.
+    pub fn similarity_threshold(&self) -> f32 {
+        f32::from_bits(self.similarity_threshold.load(Ordering::Relaxed))
+    }
+
+    /// Change the similarity threshold (`cache.similarity_threshold`) of
+    /// later lookups.
+    pub fn set_similarity_threshold(&self, threshold: f32) {
+        self.similarity_threshold
+            .store(threshold.to_bits(), Ordering::Relaxed);
+    }
+
     /// Check whether this request should be checked against the cache.
     pub fn should_check(&self, request: &ChatRequest) -> bool {
         !key::should_skip(request, &self.config)
@@ -127,7 +168,7 @@ impl SemanticCacheService {
                 &exact_hash,
                 embedding.as_deref(),
                 &metadata,
-                self.config.similarity_threshold,
+                self.similarity_threshold(),
                 self.config.ttl_secs,
             )
             .await?;
@@ -148,11 +189,12 @@ impl SemanticCacheService {
         Ok(result)
     }
 
-    /// Store a request→response pair in the cache.
+    /// Store a request→response pair in the cache on behalf of `user_id`.
     pub async fn store(
         &self,
         request: &ChatRequest,
         response: &ChatResponse,
+        user_id: &str,
     ) -> Result<(), CacheError> {
         // Only cache responses with finish_reason "stop"
         let should_cache = response
@@ -208,6 +250,7 @@ impl SemanticCacheService {
             hash_version: "v1".to_string(),
             stream_events: None,
             stream_format: None,
+            user_id: Some(user_id.to_string()),
         };
 
         self.store.insert(&entry, &metadata).await?;
@@ -235,6 +278,16 @@ impl SemanticCacheService {
         self.store.flush_model(model).await
     }
 
+    /// Remove the entries stored by a user, returning how many there were.
+    pub async fn flush_user(&self, user_id: &str) -> Result<u64, CacheError> {
+        self.store.flush_user(user_id).await
+    }
+
+    /// Number of entries stored by a user.
+    pub async fn count_user(&self, user_id: &str) -> Result<u64, CacheError> {
+        self.store.count_user(user_id).await
+    }
+
     /// Get cache statistics snapshot.
     pub fn stats(&self) -> CacheStatsSnapshot {
         self.stats.snapshot()
@@ -326,7 +379,7 @@ impl SemanticCacheService {
                 &exact_hash,
                 embedding.as_deref(),
                 &metadata,
-                self.config.similarity_threshold,
+                self.similarity_threshold(),
                 self.config.ttl_secs,
             )
             .await?;
@@ -361,6 +414,7 @@ impl SemanticCacheService {
     pub async fn store_stream(
         &self,
         request: &ChatRequest,
+        user_id: &str,
         events: &[String],
     ) -> Result<(), CacheError> {
         let exact_hash = key::exact_hash(request);
@@ -384,6 +438,7 @@ impl SemanticCacheService {
             hash_version: "v1".to_string(),
             stream_events: Some(events.to_vec()),
             stream_format: Some("openai_sse_v1".to_string()),
+            user_id: Some(user_id.to_string()),
         };
 
         // Try to reconstruct a full response for non-stream compatibility
@@ -414,7 +469,7 @@ impl SemanticCacheService {
     }
 
     /// Reconstruct a full ChatResponse from a sequence of SSE chunks.
-    fn reconstruct_response(
+    pub(crate) fn reconstruct_response(
         _request: &ChatRequest,
         events: &[String],
     ) -> Result<ChatResponse, CacheError> {
@@ -465,10 +520,12 @@ impl SemanticCacheService {
                     content: Some(full_content),
                     reasoning_content: None,
                     tool_calls: None, // TODO: support tool calls reconstruction
+                    grounding: None,
                 },
                 finish_reason,
             }],
             usage: final_usage,
+            tool_calls_emulated: false,
         })
     }
 }
@@ -498,10 +555,11 @@ pub trait StreamCacheOps: Send + Sync {
         >,
     >;
 
-    /// Store stream events for a completed streaming response.
+    /// Store stream events for a completed streaming response of `user_id`.
     fn put_stream_events<'a>(
         &'a self,
         request: &'a ChatRequest,
+        user_id: &'a str,
         events: Vec<String>,
     ) -> Pin<Box<dyn Future<Output = Result<(), CacheError>> + Send + 'a>>;
 
@@ -544,9 +602,10 @@ impl StreamCacheOps for SemanticCacheService {
     fn put_stream_events<'a>(
         &'a self,
         request: &'a ChatRequest,
+        user_id: &'a str,
         events: Vec<String>,
     ) -> Pin<Box<dyn Future<Output = Result<(), CacheError>> + Send + 'a>> {
-        Box::pin(async move { self.store_stream(request, &events).await })
+        Box::pin(async move { self.store_stream(request, user_id, &events).await })
     }
 
     fn max_stream_events(&self) -> usize {
@@ -586,9 +645,23 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         }
     }
 
+    #[cfg(not(feature = "cache-distributed"))]
+    #[tokio::test]
+    async fn test_backend_url_requires_distributed_feature() {
+        let config = crate::config::CacheConfig {
+            backend_url: Some("tikv://127.0.0.1:2379".into()),
+            ..Default::default()
+        };
+        let err = SemanticCacheService::new(&config).await.err().unwrap();
+        assert!(err.to_string().contains("cache-distributed"));
+    }
+
     #[tokio::test]
     async fn test_service_stream_hit_no_events_returns_miss() {
         let store = Arc::new(CacheStore::ephemeral(3).await.expect("ephemeral init"));
@@ -622,6 +695,7 @@ mod tests {
             hash_version: "v1".into(),
             stream_events: None, // KEY: This is what triggers the miss behavior
             stream_format: None,
+            user_id: None,
         };
         store
             .insert(&entry, &metadata)
@@ -675,7 +749,7 @@ mod tests {
             "data: [DONE]\n\n".to_string(),
         ];
         service
-            .store_stream(&request, &events)
+            .store_stream(&request, "u1", &events)
             .await
             .expect("store failed");
 
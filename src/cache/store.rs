@@ -63,6 +63,37 @@ impl CacheStore {
             hash_version: "v1".to_string(),
         };
         store.apply_schema().await?;
+        store.purge_incompatible().await?;
+        store.warmup().await?;
+
+        Ok(store)
+    }
+
+    /// Connect to a shared cache store at `url` (`tikv://pd-host:2379`), so
+    /// several gaud nodes read and write the same entries. Fails when the
+    /// store holds embeddings of another dimension.
+    #[cfg(feature = "cache-distributed")]
+    pub async fn remote(url: &str, dimension: u16) -> Result<Self, CacheError> {
+        if !url.starts_with("tikv://") {
+            return Err(CacheError::InitFailed(format!(
+                "Unsupported cache backend URL '{url}': expected tikv://host:port"
+            )));
+        }
+        let mut db = Database::new_tikv(url)
+            .await
+            .map_err(|e| CacheError::InitFailed(e.to_string()))?;
+
+        db.use_ns_db("gaud", "cache")
+            .await
+            .map_err(|e| CacheError::InitFailed(e.to_string()))?;
+
+        let store = Self {
+            db,
+            dimension,
+            hash_version: "v1".to_string(),
+        };
+        store.apply_schema().await?;
+        store.check_shared_dimension().await?;
         store.warmup().await?;
 
         Ok(store)
@@ -89,7 +120,7 @@ impl CacheStore {
         Ok(store)
     }
 
-    /// Apply schema with versioning and compatibility checks.
+    /// Apply schema with versioning.
     async fn apply_schema(&self) -> Result<(), CacheError> {
         // 1. Schema versioning
         self.db
@@ -164,6 +195,7 @@ impl CacheStore {
 
             DEFINE FIELD IF NOT EXISTS stream_events           ON cache TYPE option<array<string>>;
             DEFINE FIELD IF NOT EXISTS stream_format           ON cache TYPE option<string>;
+            DEFINE FIELD IF NOT EXISTS user_id                 ON cache TYPE option<string>;
 
             DEFINE INDEX IF NOT EXISTS idx_prompt_hash ON cache FIELDS exact_hash UNIQUE;
             DEFINE INDEX IF NOT EXISTS hnsw_embedding ON cache FIELDS embedding
@@ -174,52 +206,80 @@ impl CacheStore {
 
         self.db.query(&schema).await.schema_err()?;
 
-        // 3. Compatibility Guard: Verify embedding dimension
-        // We can check if any existing row has a different dimension.
+        Ok(())
+    }
+
+    /// Dimension of the embeddings already stored, if there are any.
+    #[cfg(any(test, feature = "cache-persistent", feature = "cache-distributed"))]
+    async fn stored_dimension(&self) -> Result<Option<u16>, CacheError> {
         let mut response = self
             .db
             .query("SELECT array::len(embedding) AS len FROM cache WHERE embedding IS NOT NONE LIMIT 1")
             .await
             .schema_err()?;
 
-        if let Ok(v) = response.take::<srrldb::types::Value>(0usize) {
-            if let srrldb::types::Value::Array(arr) = v {
-                if let Some(row) = arr.first() {
-                    if let srrldb::types::Value::Object(obj) = row {
-                        if let Some(srrldb::types::Value::Number(n)) = obj.get("len") {
-                            let existing_dim = n.to_int().unwrap_or(0) as u16;
-                            if existing_dim > 0 && existing_dim != self.dimension {
-                                tracing::error!(
-                                    expected = self.dimension,
-                                    actual = existing_dim,
-                                    "Cache embedding dimension mismatch! Purging incompatible cache."
-                                );
-                                self.flush_all().await?;
-                                // DEFINE INDEX IF NOT EXISTS won't update the
-                                // dimension on an existing index, so we must
-                                // drop and recreate it after purging.
-                                let reindex = format!(
-                                    "REMOVE INDEX IF EXISTS hnsw_embedding ON cache; \
-                                     DEFINE INDEX hnsw_embedding ON cache FIELDS embedding \
-                                     HNSW DIMENSION {} DIST COSINE;",
-                                    self.dimension
-                                );
-                                self.db.query(&reindex).await.schema_err()?;
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let Ok(srrldb::types::Value::Array(arr)) = response.take::<srrldb::types::Value>(0usize)
+        else {
+            return Ok(None);
+        };
+        let dimension = match arr.first() {
+            Some(srrldb::types::Value::Object(obj)) => match obj.get("len") {
+                Some(srrldb::types::Value::Number(n)) => n.to_int().unwrap_or(0) as u16,
+                _ => 0,
+            },
+            _ => 0,
+        };
+        Ok((dimension > 0).then_some(dimension))
+    }
 
+    /// Compatibility guard of a local store: purge it when its embeddings
+    /// have another dimension, such as after a change of embedding model.
+    #[cfg(feature = "cache-persistent")]
+    async fn purge_incompatible(&self) -> Result<(), CacheError> {
+        let Some(existing_dim) = self.stored_dimension().await? else {
+            return Ok(());
+        };
+        if existing_dim != self.dimension {
+            tracing::error!(
+                expected = self.dimension,
+                actual = existing_dim,
+                "Cache embedding dimension mismatch! Purging incompatible cache."
+            );
+            self.flush_all().await?;
+            // DEFINE INDEX IF NOT EXISTS won't update the dimension on an
+            // existing index, so we must drop and recreate it after purging.
+            let reindex = format!(
+                "REMOVE INDEX IF EXISTS hnsw_embedding ON cache; \
+                 DEFINE INDEX hnsw_embedding ON cache FIELDS embedding \
+                 HNSW DIMENSION {} DIST COSINE;",
+                self.dimension
+            );
+            self.db.query(&reindex).await.schema_err()?;
+        }
         Ok(())
     }
 
+    /// Compatibility guard of a shared store: refuse it when its embeddings
+    /// have another dimension. The other nodes still use those entries, so a
+    /// misconfigured node must not purge them.
+    #[cfg(any(test, feature = "cache-distributed"))]
+    async fn check_shared_dimension(&self) -> Result<(), CacheError> {
+        match self.stored_dimension().await? {
+            Some(existing_dim) if existing_dim != self.dimension => {
+                Err(CacheError::IncompatibleSharedStore {
+                    expected: self.dimension,
+                    actual: existing_dim,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Synthetic ANN query to eager-load the HNSW index.
     ///
-    /// Only called from `persistent()`. Ephemeral stores start empty, so
-    /// there is nothing to warm up.
-    #[cfg(feature = "cache-persistent")]
+    /// Only called from `persistent()` and `remote()`. Ephemeral stores
+    /// start empty, so there is nothing to warm up.
+    #[cfg(any(feature = "cache-persistent", feature = "cache-distributed"))]
     async fn warmup(&self) -> Result<(), CacheError> {
         let count = self.entry_count().await?;
 
@@ -394,11 +454,13 @@ impl CacheStore {
             temperature: $temp,
             confidence: $conf,
             created_at: time::now(),
-            hit_count: 0
+            hit_count: 0,
+            user_id: $user_id
         } ON DUPLICATE KEY UPDATE
             response_json = $resp_json,
             embedding = $emb,
             hit_count += 1,
+            user_id = $user_id,
             system_prompt_hash = $sys_hash,
             tool_definitions_hash = $tool_hash,
             temperature = $temp,
@@ -418,6 +480,7 @@ impl CacheStore {
             .bind(("hash_ver", self.hash_version.clone()))
             .bind(("temp", metadata.temperature))
             .bind(("conf", metadata.confidence))
+            .bind(("user_id", entry.user_id.clone()))
             .await
             .insert_err()?;
 
@@ -451,11 +514,13 @@ impl CacheStore {
             stream_events: $stream_events,
             stream_format: $stream_format,
             created_at: time::now(),
-            hit_count: 0
+            hit_count: 0,
+            user_id: $user_id
         } ON DUPLICATE KEY UPDATE
             stream_events = $stream_events,
             stream_format = $stream_format,
-            response_json = $resp_json";
+            response_json = $resp_json,
+            user_id = $user_id";
 
         self.db
             .query(sql)
@@ -472,6 +537,7 @@ impl CacheStore {
             .bind(("conf", metadata.confidence))
             .bind(("stream_events", events.to_vec()))
             .bind(("stream_format", "openai_sse_v1".to_string()))
+            .bind(("user_id", entry.user_id.clone()))
             .await
             .insert_err()?;
 
@@ -570,6 +636,30 @@ impl CacheStore {
         self.entry_count().await
     }
 
+    /// Delete the entries stored by `user_id`, returning how many there were.
+    pub async fn flush_user(&self, user_id: &str) -> Result<u64, CacheError> {
+        let mut response = self
+            .db
+            .query("DELETE FROM cache WHERE user_id = $user_id RETURN BEFORE")
+            .bind(("user_id", user_id.to_string()))
+            .await
+            .lookup_err()?;
+        let removed: Vec<CacheEntry> = response.take_vec(0usize).lookup_err()?;
+        Ok(removed.len() as u64)
+    }
+
+    /// Number of entries stored by `user_id`.
+    pub async fn count_user(&self, user_id: &str) -> Result<u64, CacheError> {
+        let mut response = self
+            .db
+            .query("SELECT count() FROM cache WHERE user_id = $user_id GROUP ALL")
+            .bind(("user_id", user_id.to_string()))
+            .await
+            .lookup_err()?;
+        let val: srrldb::types::Value = response.take(0usize).lookup_err()?;
+        Ok(count_from(val))
+    }
+
     // -----------------------------------------------------------------------
     // Private helpers
     // -----------------------------------------------------------------------
@@ -586,17 +676,22 @@ impl CacheStore {
             .lookup_err()?;
 
         let val: srrldb::types::Value = response.take(0usize).lookup_err()?;
-        if let srrldb::types::Value::Array(vec) = val {
-            if let Some(row) = vec.first() {
-                if let srrldb::types::Value::Object(obj) = row {
-                    if let Some(srrldb::types::Value::Number(n)) = obj.get("count") {
-                        return Ok(n.clone().to_int().map(|i| i as u64).unwrap_or(0));
-                    }
+        Ok(count_from(val))
+    }
+}
+
+/// The `count` of a `SELECT count() … GROUP ALL` result (0 for no rows).
+fn count_from(val: srrldb::types::Value) -> u64 {
+    if let srrldb::types::Value::Array(vec) = val {
+        if let Some(row) = vec.first() {
+            if let srrldb::types::Value::Object(obj) = row {
+                if let Some(srrldb::types::Value::Number(n)) = obj.get("count") {
+                    return n.clone().to_int().map(|i| i as u64).unwrap_or(0);
                 }
             }
         }
-        Ok(0)
     }
+    0
 }
 
 #[cfg(test)]
@@ -632,6 +727,7 @@ mod tests {
             hash_version: "v1".into(),
             stream_events: None,
             stream_format: None,
+            user_id: None,
         };
 
         store.insert(&entry, &metadata).await?;
@@ -714,6 +810,7 @@ mod tests {
             hash_version: "v1".into(),
             stream_events: None,
             stream_format: None,
+            user_id: None,
         };
 
         let res = store.insert(&entry, &metadata).await;
@@ -759,6 +856,7 @@ mod tests {
                 hash_version: "v1".into(),
                 stream_events: None,
                 stream_format: None,
+                user_id: None,
             };
             store.insert(&entry, &metadata).await?;
             // Small sleep to ensure different created_at
@@ -796,6 +894,54 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_shared_store_refuses_dimension_mismatch() -> Result<(), CacheError> {
+        let store = CacheStore::ephemeral(3).await?;
+        let metadata = CacheMetadata {
+            model: "m".into(),
+            system_prompt_hash: "s".into(),
+            tool_definitions_hash: "t".into(),
+            temperature: None,
+            confidence: None,
+        };
+        let entry = CacheEntry {
+            exact_hash: "h".into(),
+            model: "m".into(),
+            system_prompt_hash: "s".into(),
+            tool_definitions_hash: "t".into(),
+            semantic_text: "txt".into(),
+            embedding: Some(vec![1.0, 0.0, 0.0]),
+            request_json: "{}".into(),
+            response_json: "{}".into(),
+            created_at: srrldb::types::Datetime::now(),
+            hit_count: 0,
+            last_hit: None,
+            hash_version: "v1".into(),
+            stream_events: None,
+            stream_format: None,
+            user_id: None,
+        };
+        store.insert(&entry, &metadata).await?;
+        store.check_shared_dimension().await?;
+
+        // Another node configured with dimension 4 on the same store.
+        let other = CacheStore {
+            dimension: 4,
+            ..store.clone()
+        };
+        let err = other.check_shared_dimension().await.unwrap_err();
+        assert!(matches!(
+            err,
+            CacheError::IncompatibleSharedStore {
+                expected: 4,
+                actual: 3
+            }
+        ));
+        assert_eq!(store.count().await?, 1, "shared entries must be kept");
+
+        Ok(())
+    }
+
     #[cfg(feature = "cache-persistent")]
     #[tokio::test]
     async fn test_compatibility_guard_purges_on_dimension_mismatch() -> Result<(), CacheError> {
@@ -828,6 +974,7 @@ mod tests {
                 hash_version: "v1".into(),
                 stream_events: None,
                 stream_format: None,
+                user_id: None,
             };
             store.insert(&entry, &metadata).await?;
             assert_eq!(store.count().await?, 1);
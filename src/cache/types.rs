@@ -26,6 +26,10 @@ pub struct CacheEntry {
     pub stream_events: Option<Vec<String>>,
     /// Format version tag for the stream event log (e.g. "openai_sse_v1").
     pub stream_format: Option<String>,
+    /// User whose request last stored the entry, so a user purge can remove
+    /// it. `None` for entries written before entries had owners.
+    #[serde(default)]
+    pub user_id: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -215,6 +219,11 @@ pub enum CacheError {
     #[error("Invalid embedding: expected dimension {expected}, got {actual}")]
     DimensionMismatch { expected: u16, actual: usize },
 
+    #[error(
+        "Shared cache holds embeddings of dimension {actual}, but embedding_dimension is {expected}"
+    )]
+    IncompatibleSharedStore { expected: u16, actual: u16 },
+
     #[error("Embedding vector is not normalized (magnitude: {magnitude:.4})")]
     NotNormalized { magnitude: f32 },
 
@@ -273,6 +282,7 @@ mod tests {
             hash_version: "v1".into(),
             stream_events: None,
             stream_format: None,
+            user_id: None,
         };
 
         let info = CacheHitInfo {
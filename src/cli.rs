@@ -0,0 +1,399 @@
+//! Admin subcommands: `gaud user`, `gaud key`, `gaud budget`, `gaud usage`.
+//!
+//! They work on the database named in the config file directly, so a
+//! deployment can be managed from a shell without the web UI or an admin
+//! key. Changes are recorded in the admin audit log with the actor `cli`.
+//! `gaud loadtest` is listed here too but runs from [`crate::loadtest`].
+
+use std::io::Write;
+
+use anyhow::{Context, bail};
+
+use crate::api::admin::{self, UsageQuery};
+use crate::auth::{AuthUser, audit_log, users};
+use crate::budget::BudgetTracker;
+use crate::db::Database;
+use crate::providers::reasoning::ReasoningMode;
+
+/// Names of the subcommands, as the first non-option argument.
+pub const COMMANDS: &[&str] = &["user", "key", "budget", "usage", "loadtest", "mcp"];
+
+pub const USAGE: &str = "\
+SUBCOMMANDS:
+    user add <name> [--role admin|member|service] [--team <team>]
+    user list
+    key issue <user> [--label <label>]
+    key revoke <key-id>
+    budget set <user> [--monthly <amount|none>] [--daily <amount|none>]
+    usage show [--user <user>] [--provider <provider>] [--end-user <id>] [--tags <k=v,...>]
+               [--model <model>] [--status <status>] [--search <text>]
+               [--from <date>] [--to <date>] [--limit <n>]
+    loadtest (--model <model>... | --replay <file>) [--rps <n>] [--duration <secs>]
+             [--concurrency <n>] [--url <url>] [--key <api-key>]
+    mcp [--url <url>] [--key <api-key>]
+
+    <user> is a user name or ID. `--role service` creates a service account,
+    which needs --team.
+";
+
+/// Run a subcommand. `args` starts with the subcommand name.
+pub fn run(
+    db: &Database,
+    currency: &str,
+    args: &[String],
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let (command, rest) = args.split_first().context("missing subcommand")?;
+    let (action, rest) = rest
+        .split_first()
+        .with_context(|| format!("missing action for '{command}'"))?;
+    let mut opts = Options::parse(rest)?;
+
+    match (command.as_str(), action.as_str()) {
+        ("user", "add") => {
+            let name = opts.positional(0, "user name")?;
+            let role = opts.take("--role").unwrap_or_else(|| "member".to_string());
+            let team = opts.take("--team");
+            opts.finish()?;
+            let user = if role == "service" {
+                let team = team.context("a service account needs --team")?;
+                let user = users::create_service_account(db, &name, &team)?;
+                record(
+                    db,
+                    "service_account.create",
+                    &user.id,
+                    None,
+                    Some(json(&user)),
+                );
+                user
+            } else {
+                let mut user = users::create_user(db, &name, &role)?;
+                if let Some(team) = team.filter(|t| !t.is_empty()) {
+                    users::set_user_team(db, &user.id, Some(&team))?;
+                    user.team = Some(team);
+                }
+                record(db, "user.create", &user.id, None, Some(json(&user)));
+                user
+            };
+            writeln!(
+                out,
+                "Created user {} ({}, {})",
+                user.name, user.id, user.role
+            )?;
+        }
+        ("user", "list") => {
+            opts.finish()?;
+            writeln!(
+                out,
+                "{:<38} {:<20} {:<7} {:<12} CREATED",
+                "ID", "NAME", "ROLE", "TEAM"
+            )?;
+            for user in users::list_users(db)? {
+                writeln!(
+                    out,
+                    "{:<38} {:<20} {:<7} {:<12} {}",
+                    user.id,
+                    user.name,
+                    if user.service_account {
+                        "service"
+                    } else {
+                        &user.role
+                    },
+                    user.team.as_deref().unwrap_or("-"),
+                    user.created_at
+                )?;
+            }
+        }
+        ("key", "issue") => {
+            let user = find_user(db, &opts.positional(0, "user")?)?;
+            let label = opts.take("--label").unwrap_or_default();
+            opts.finish()?;
+            let created = users::create_api_key(db, &user.id, &label)?;
+            record(
+                db,
+                "key.create",
+                &created.info.id,
+                None,
+                Some(json(&created.info)),
+            );
+            writeln!(out, "Issued key {} for {}", created.info.id, user.name)?;
+            writeln!(out, "{}", created.plaintext)?;
+            writeln!(out, "Save this key now -- it will not be shown again.")?;
+        }
+        ("key", "revoke") => {
+            let id = opts.positional(0, "key ID")?;
+            opts.finish()?;
+            let before = users::get_api_key(db, &id)?;
+            users::revoke_api_key(db, &id)?;
+            record(db, "key.revoke", &id, Some(json(&before)), None);
+            writeln!(out, "Revoked key {id} ({})", before.key_prefix)?;
+        }
+        ("budget", "set") => {
+            let user = find_user(db, &opts.positional(0, "user")?)?;
+            let monthly = opts
+                .take("--monthly")
+                .map(|v| parse_limit(&v))
+                .transpose()?;
+            let daily = opts.take("--daily").map(|v| parse_limit(&v)).transpose()?;
+            opts.finish()?;
+            if monthly.is_none() && daily.is_none() {
+                bail!("nothing to set: pass --monthly and/or --daily");
+            }
+            let tracker = BudgetTracker::new(db.clone());
+            let before = tracker.get_budget(&user.id)?;
+            // Limits not given on the command line stay as they are.
+            let monthly = monthly.unwrap_or_else(|| before.as_ref().and_then(|b| b.monthly_limit));
+            let daily = daily.unwrap_or_else(|| before.as_ref().and_then(|b| b.daily_limit));
+            tracker.set_budget(&user.id, monthly, daily)?;
+            let after = tracker.get_budget(&user.id)?;
+            record(
+                db,
+                "budget.update",
+                &user.id,
+                before.as_ref().map(json),
+                after.as_ref().map(json),
+            );
+            writeln!(
+                out,
+                "Budget for {}: monthly {}, daily {} ({currency})",
+                user.name,
+                format_limit(monthly),
+                format_limit(daily)
+            )?;
+        }
+        ("usage", "show") => {
+            let user_id = opts
+                .take("--user")
+                .map(|u| find_user(db, &u).map(|u| u.id))
+                .transpose()?;
+            let limit = opts
+                .take("--limit")
+                .map(|v| v.parse::<u32>().context("--limit must be a number"))
+                .transpose()?
+                .unwrap_or(20);
+            let query = UsageQuery {
+                user_id,
+                provider: opts.take("--provider"),
+                end_user: opts.take("--end-user"),
+                tags: opts.take("--tags"),
+                model: opts.take("--model"),
+                status: opts.take("--status"),
+                search: opts.take("--search"),
+                from: opts.take("--from"),
+                to: opts.take("--to"),
+                page: 1,
+                per_page: limit,
+                ..Default::default()
+            };
+            opts.finish()?;
+            let page = admin::usage_page(db, &query, currency)?;
+            writeln!(
+                out,
+                "{:<19} {:<12} {:<32} {:>8} {:>8} {:>10} STATUS",
+                "TIME", "PROVIDER", "MODEL", "IN", "OUT", "COST"
+            )?;
+            for row in &page.data {
+                writeln!(
+                    out,
+                    "{:<19} {:<12} {:<32} {:>8} {:>8} {:>10.4} {}",
+                    row.created_at,
+                    row.provider,
+                    row.model,
+                    row.input_tokens,
+                    row.output_tokens,
+                    row.cost,
+                    row.status
+                )?;
+            }
+            writeln!(
+                out,
+                "{} of {} requests shown (costs in {})",
+                page.data.len(),
+                page.total,
+                page.currency
+            )?;
+        }
+        _ => bail!("unknown subcommand '{command} {action}'"),
+    }
+    Ok(())
+}
+
+/// Parsed `--flag value` options and positional arguments.
+pub(crate) struct Options {
+    flags: Vec<(String, String)>,
+    positional: Vec<String>,
+}
+
+impl Options {
+    pub(crate) fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut flags = Vec::new();
+        let mut positional = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg.starts_with("--") {
+                let value = iter
+                    .next()
+                    .with_context(|| format!("{arg} requires a value"))?;
+                flags.push((arg.clone(), value.clone()));
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        Ok(Self { flags, positional })
+    }
+
+    pub(crate) fn take(&mut self, name: &str) -> Option<String> {
+        let index = self.flags.iter().position(|(flag, _)| flag == name)?;
+        Some(self.flags.remove(index).1)
+    }
+
+    fn positional(&mut self, index: usize, what: &str) -> anyhow::Result<String> {
+        if index >= self.positional.len() {
+            bail!("missing {what}");
+        }
+        Ok(std::mem::take(&mut self.positional[index]))
+    }
+
+    /// Reject anything left over.
+    pub(crate) fn finish(self) -> anyhow::Result<()> {
+        if let Some((flag, _)) = self.flags.first() {
+            bail!("unknown option '{flag}'");
+        }
+        if let Some(arg) = self.positional.iter().find(|a| !a.is_empty()) {
+            bail!("unexpected argument '{arg}'");
+        }
+        Ok(())
+    }
+}
+
+/// Look a user up by ID, then by name.
+fn find_user(db: &Database, user: &str) -> anyhow::Result<users::User> {
+    users::get_user(db, user)
+        .or_else(|_| users::get_user_by_name(db, user))
+        .map_err(|_| anyhow::anyhow!("no user with ID or name '{user}'"))
+}
+
+/// A budget limit: an amount, or `none` to remove it.
+fn parse_limit(value: &str) -> anyhow::Result<Option<f64>> {
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+    match value.parse::<f64>() {
+        Ok(v) if v.is_finite() && v >= 0.0 => Ok(Some(v)),
+        _ => bail!("invalid limit '{value}': expected a non-negative amount or 'none'"),
+    }
+}
+
+fn format_limit(limit: Option<f64>) -> String {
+    limit.map_or_else(|| "unlimited".to_string(), |v| format!("{v:.2}"))
+}
+
+fn record(
+    db: &Database,
+    action: &str,
+    target: &str,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+) {
+    let actor = AuthUser {
+        user_id: "cli".to_string(),
+        name: "cli".to_string(),
+        role: "admin".to_string(),
+        reasoning_mode: ReasoningMode::default(),
+        team: None,
+        impersonator: None,
+        key_id: None,
+        scope: None,
+        origin: Default::default(),
+    };
+    audit_log::record(db, &actor, action, target, before, after);
+}
+
+fn json<T: serde::Serialize>(value: &T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_ok(db: &Database, args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let mut out = Vec::new();
+        run(db, "USD", &args, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn run_err(db: &Database, args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        run(db, "USD", &args, &mut Vec::new())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_user_key_and_budget_commands() {
+        let db = Database::open_in_memory().unwrap();
+        let output = run_ok(&db, &["user", "add", "alice", "--team", "research"]);
+        assert!(output.starts_with("Created user alice"));
+        assert!(run_ok(&db, &["user", "list"]).contains("research"));
+        run_ok(
+            &db,
+            &[
+                "user", "add", "ci-bot", "--role", "service", "--team", "research",
+            ],
+        );
+        assert!(
+            users::get_user_by_name(&db, "ci-bot")
+                .unwrap()
+                .service_account
+        );
+
+        let output = run_ok(&db, &["key", "issue", "alice", "--label", "ci"]);
+        let plaintext = output.lines().nth(1).unwrap();
+        let auth = users::validate_api_key(&db, plaintext).unwrap();
+        assert_eq!(auth.name, "alice");
+
+        run_ok(&db, &["budget", "set", "alice", "--monthly", "25"]);
+        run_ok(&db, &["budget", "set", "alice", "--daily", "5"]);
+        let budget = BudgetTracker::new(db.clone())
+            .get_budget(&auth.user_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(budget.monthly_limit, Some(25.0));
+        assert_eq!(budget.daily_limit, Some(5.0));
+
+        let key_id = users::list_api_keys(&db, &auth.user_id).unwrap()[0]
+            .id
+            .clone();
+        run_ok(&db, &["key", "revoke", &key_id]);
+        assert!(users::validate_api_key(&db, plaintext).is_err());
+
+        let audit = audit_log::query(&db, &Default::default()).unwrap();
+        assert_eq!(audit.total, 6);
+        assert!(audit.data.iter().all(|e| e.actor_id == "cli"));
+    }
+
+    #[test]
+    fn test_usage_show_empty() {
+        let db = Database::open_in_memory().unwrap();
+        let output = run_ok(&db, &["usage", "show", "--limit", "5"]);
+        assert!(output.contains("0 of 0 requests shown (costs in USD)"));
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        let db = Database::open_in_memory().unwrap();
+        assert!(run_err(&db, &["user"]).contains("missing action"));
+        assert!(run_err(&db, &["user", "add"]).contains("missing user name"));
+        assert!(run_err(&db, &["user", "list", "--bogus", "1"]).contains("unknown option"));
+        assert!(run_err(&db, &["key", "issue", "nobody"]).contains("no user"));
+        run_ok(&db, &["user", "add", "bob"]);
+        assert!(
+            run_err(&db, &["budget", "set", "bob", "--monthly", "-1"]).contains("invalid limit")
+        );
+        assert!(run_err(&db, &["budget", "set", "bob"]).contains("nothing to set"));
+        assert!(run_err(&db, &["user", "add", "bot", "--role", "service"]).contains("--team"));
+        assert!(run_err(&db, &["cache", "flush"]).contains("unknown subcommand"));
+    }
+}
@@ -1,8 +1,17 @@
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use crate::budget::scheduler::Priority;
+use crate::experiments::Experiment;
+use crate::net::OutboundProxy;
+use crate::providers::params::ModelParams;
+use crate::providers::reasoning::ReasoningMode;
+use crate::providers::retry::RetryPolicy;
+use crate::providers::rules::RoutingRule;
+
 /// Global User-Agent string for the application.
 pub const GAUD_USER_AGENT: &str = concat!("gaud/", env!("CARGO_PKG_VERSION"));
 
@@ -67,8 +76,28 @@ pub struct SettingEntry {
     /// Whether the value is sensitive (should be masked in UI).
     #[serde(default)]
     pub sensitive: bool,
+    /// Whether a change applies at once instead of after a restart.
+    pub runtime: bool,
+    /// Whether the value was changed at runtime and is stored in the
+    /// database, overriding the config file.
+    pub stored: bool,
 }
 
+/// Settings that apply as soon as they are changed through
+/// `PUT /admin/settings`. Changes are stored in the database rather than
+/// the config file, and win over the file (see [`crate::settings`]).
+pub const RUNTIME_SETTINGS: &[&str] = &[
+    "cache.similarity_threshold",
+    "providers.default_max_tokens",
+    "providers.retry.max_retries",
+    "providers.retry.initial_backoff_ms",
+    "providers.retry.max_backoff_ms",
+    "limits.max_in_flight",
+    "limits.queue_timeout_secs",
+    "budget.warning_threshold_percent",
+    "notifications.cooldown_secs",
+];
+
 // ---------------------------------------------------------------------------
 // Main configuration
 // ---------------------------------------------------------------------------
@@ -86,9 +115,32 @@ pub struct Config {
     #[serde(default)]
     pub budget: BudgetConfig,
     #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub files: FilesConfig,
+    #[serde(default)]
+    pub pricing: PricingConfig,
+    #[serde(default)]
+    pub reports: ReportsConfig,
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    #[serde(default)]
     pub logging: LoggingConfig,
     #[serde(default)]
+    pub observability: ObservabilityConfig,
+    #[serde(default)]
     pub cache: CacheConfig,
+    #[serde(default)]
+    pub mcp: McpConfig,
+    #[serde(default)]
+    pub agents: AgentsConfig,
+    #[serde(default)]
+    pub conversations: ConversationsConfig,
+    #[serde(default, skip_serializing_if = "ProvisioningConfig::is_empty")]
+    pub provisioning: ProvisioningConfig,
+    /// A/B experiments (`[[experiments]]`), first match wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub experiments: Vec<Experiment>,
     /// Env var overrides are not serialized to TOML.
     #[serde(skip)]
     pub env_overrides: EnvOverrides,
@@ -102,6 +154,32 @@ pub struct ServerConfig {
     pub port: u16,
     #[serde(default)]
     pub cors_origins: Vec<String>,
+    /// CORS policies of individual route groups, overriding `cors_origins`.
+    #[serde(default, skip_serializing_if = "CorsConfig::is_empty")]
+    pub cors: CorsConfig,
+    /// PEM certificate chain for built-in HTTPS. Must be set together with
+    /// `tls_key_path`; when both are unset gaud serves plain HTTP.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_cert_path: Option<String>,
+    /// PEM private key matching `tls_cert_path`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_key_path: Option<String>,
+    /// Obtain certificates automatically via ACME instead of reading them
+    /// from disk. Requires the `acme` build feature.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acme: Option<AcmeConfig>,
+    /// Serve the admin API and web UI on a separate listener. When set, the
+    /// main listener only serves `/health` and `/v1/*`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin: Option<AdminListenerConfig>,
+    /// On shutdown, how long in-flight requests and streams may keep running
+    /// before their connections are closed.
+    #[serde(default = "default_shutdown_drain_secs")]
+    pub shutdown_drain_secs: u64,
 }
 
 impl Default for ServerConfig {
@@ -110,20 +188,162 @@ impl Default for ServerConfig {
             host: default_host(),
             port: default_port(),
             cors_origins: Vec::new(),
+            cors: CorsConfig::default(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            acme: None,
+            admin: None,
+            shutdown_drain_secs: default_shutdown_drain_secs(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Whether the server terminates TLS itself.
+    pub fn tls_enabled(&self) -> bool {
+        self.acme.is_some() || self.tls_cert_path.is_some() || self.tls_key_path.is_some()
+    }
+
+    /// Address of the dedicated admin listener, if configured.
+    pub fn admin_listen_addr(&self) -> Option<String> {
+        let admin = self.admin.as_ref()?;
+        let host = admin.host.as_deref().unwrap_or(&self.host);
+        Some(format!("{host}:{}", admin.port))
+    }
+
+    /// Origins that may call `group` cross-origin, `"*"` meaning any. Empty
+    /// when the group allows no cross-origin requests.
+    pub fn cors_origins_for(&self, group: RouteGroup) -> Vec<String> {
+        let origins = match group {
+            RouteGroup::Proxy => &self.cors.proxy,
+            RouteGroup::Admin => &self.cors.admin,
+            RouteGroup::Ui => &self.cors.ui,
+        };
+        match origins {
+            Some(origins) => origins.clone(),
+            // An empty `cors_origins` has always meant any origin.
+            None if self.cors_origins.is_empty() => vec!["*".to_string()],
+            None => self.cors_origins.clone(),
         }
     }
 }
 
+/// Route groups with their own CORS policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    /// `/health` and `/v1/*`.
+    Proxy,
+    /// `/admin/*` and `/me/*`.
+    Admin,
+    /// The web UI (`/ui/*`) and OAuth callbacks.
+    Ui,
+}
+
+/// Allowed CORS origins per route group (`[server.cors]`). `"*"` allows
+/// any origin and an empty list none. A group left unset falls back to
+/// `server.cors_origins`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CorsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui: Option<Vec<String>>,
+}
+
+impl CorsConfig {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Parse a comma-separated list, as used for origins and trusted proxies.
+fn parse_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Parse a route group's origins as set through an env var or the settings
+/// page: empty falls back to `server.cors_origins` and `none` allows none.
+fn parse_group_origins(s: &str) -> Option<Vec<String>> {
+    match s.trim() {
+        "" => None,
+        "none" => Some(Vec::new()),
+        s => Some(parse_list(s)),
+    }
+}
+
+/// Inverse of [`parse_group_origins`], for the settings page.
+fn format_group_origins(origins: Option<&Vec<String>>) -> String {
+    match origins {
+        None => String::new(),
+        Some(origins) if origins.is_empty() => "none".to_string(),
+        Some(origins) => origins.join(", "),
+    }
+}
+
+/// Dedicated listener for the management plane (`/admin/*` and `/ui/*`),
+/// e.g. bound to localhost while the `/v1/*` API is public. Uses the same
+/// TLS settings as the main listener.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AdminListenerConfig {
+    /// Bind address. Defaults to `server.host`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    pub port: u16,
+}
+
+/// Automatic TLS certificates from Let's Encrypt (TLS-ALPN-01 challenge).
+///
+/// The server must be reachable on port 443 under every listed domain.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AcmeConfig {
+    /// Domains to request a certificate for.
+    pub domains: Vec<String>,
+    /// Contact addresses for the ACME account, e.g. "mailto:ops@example.com".
+    #[serde(default)]
+    pub contact: Vec<String>,
+    /// Directory where the account key and certificates are cached.
+    #[serde(default = "default_acme_cache_dir")]
+    pub cache_dir: String,
+    /// Use the Let's Encrypt production directory. Defaults to staging so a
+    /// misconfiguration does not run into production rate limits.
+    #[serde(default)]
+    pub production: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct DatabaseConfig {
     #[serde(default = "default_db_path")]
     pub path: PathBuf,
+    /// Elect one process, through a lease in the database, to run periodic
+    /// jobs that write shared state. Enable when several processes open the
+    /// same database.
+    #[serde(default)]
+    pub leader_election: bool,
+    /// How long the leader's lease lasts without renewal, in seconds.
+    #[serde(default = "default_leader_lease_secs")]
+    pub leader_lease_secs: u64,
+}
+
+impl DatabaseConfig {
+    /// The leader lease, at least three seconds so it can be renewed
+    /// before it lapses.
+    pub fn leader_lease(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.leader_lease_secs.max(3))
+    }
 }
 
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
             path: default_db_path(),
+            leader_election: false,
+            leader_lease_secs: default_leader_lease_secs(),
         }
     }
 }
@@ -137,9 +357,23 @@ pub struct AuthConfig {
     pub default_admin_name: String,
     #[serde(default)]
     pub bootstrap_key: Option<String>,
+    /// Most API keys a member may hold when creating their own through the
+    /// self-service `/me/keys` endpoints. `0` disables self-service creation.
+    #[serde(default = "default_self_service_max_keys")]
+    pub self_service_max_keys: u32,
+    /// Lifetime of a web UI login session, in hours.
+    #[serde(default = "default_session_ttl_hours")]
+    pub session_ttl_hours: u64,
+    /// Disable API keys not used for this many days. `0` keeps unused keys
+    /// enabled.
+    #[serde(default)]
+    pub disable_stale_keys_after_days: u32,
     /// TLS client certificate authentication.
     #[serde(default)]
     pub tls_client_cert: TlsClientCertConfig,
+    /// Lockouts after repeated failed logins.
+    #[serde(default)]
+    pub login_throttle: LoginThrottleConfig,
 }
 
 impl Default for AuthConfig {
@@ -148,7 +382,11 @@ impl Default for AuthConfig {
             enabled: true,
             default_admin_name: default_admin_name(),
             bootstrap_key: None,
+            self_service_max_keys: default_self_service_max_keys(),
+            session_ttl_hours: default_session_ttl_hours(),
+            disable_stale_keys_after_days: 0,
             tls_client_cert: TlsClientCertConfig::default(),
+            login_throttle: LoginThrottleConfig::default(),
         }
     }
 }
@@ -158,13 +396,18 @@ impl Default for AuthConfig {
 /// When a TLS-terminating reverse proxy (nginx, envoy, etc.) is in front of
 /// gaud, it can pass the client certificate common name via a header. This
 /// section configures gaud to trust that header for authentication.
+///
+/// When gaud terminates TLS itself (`server.tls_cert_path`), it verifies
+/// client certificates against `ca_cert_path` during the handshake instead
+/// and the header is ignored.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TlsClientCertConfig {
     /// Enable TLS client cert header-based auth.
     #[serde(default)]
     pub enabled: bool,
-    /// Path to the CA certificate used to verify client certs (informational
-    /// only; the actual verification is done by the reverse proxy).
+    /// Path to the CA certificate used to verify client certs. Required with
+    /// built-in TLS; informational only behind a reverse proxy, which does the
+    /// actual verification.
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ca_cert_path: Option<String>,
@@ -177,6 +420,16 @@ pub struct TlsClientCertConfig {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub header_name: Option<String>,
+    /// With built-in TLS and `[server.admin]`, only ask for client
+    /// certificates on the admin listener; the main listener keeps using API
+    /// keys.
+    #[serde(default)]
+    pub admin_only: bool,
+    /// Map certificate common names to gaud user names. CNs not listed here
+    /// are looked up as user names directly.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub users: HashMap<String, String>,
 }
 
 impl Default for TlsClientCertConfig {
@@ -186,6 +439,8 @@ impl Default for TlsClientCertConfig {
             ca_cert_path: None,
             require_cert: false,
             header_name: None,
+            admin_only: false,
+            users: HashMap::new(),
         }
     }
 }
@@ -195,6 +450,79 @@ impl TlsClientCertConfig {
     pub fn effective_header(&self) -> &str {
         self.header_name.as_deref().unwrap_or("X-Client-Cert-CN")
     }
+
+    /// The gaud user name a certificate common name maps to.
+    pub fn user_for_cn<'a>(&'a self, cn: &'a str) -> &'a str {
+        self.users.get(cn).map(String::as_str).unwrap_or(cn)
+    }
+}
+
+/// Brute-force protection for API key validation and web UI login.
+///
+/// Failed attempts are counted per client IP and per account; reaching
+/// `max_failures` within `window_secs` locks the IP or account out for
+/// `lockout_secs`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoginThrottleConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Failed attempts allowed per IP or account within the window.
+    #[serde(default = "default_login_max_failures")]
+    pub max_failures: u32,
+    /// Window over which failures are counted, in seconds.
+    #[serde(default = "default_login_window_secs")]
+    pub window_secs: u64,
+    /// How long a lockout lasts, in seconds.
+    #[serde(default = "default_login_lockout_secs")]
+    pub lockout_secs: u64,
+    /// Take the client IP from `X-Forwarded-For`. Only enable behind a
+    /// reverse proxy that sets it, since clients can forge the header.
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
+    /// Addresses or CIDR ranges of the reverse proxies in front of gaud.
+    /// The client IP is the rightmost `X-Forwarded-For` hop that is not one
+    /// of them; when empty, the rightmost hop.
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_failures: default_login_max_failures(),
+            window_secs: default_login_window_secs(),
+            lockout_secs: default_login_lockout_secs(),
+            trust_forwarded_for: false,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl LoginThrottleConfig {
+    /// The parsed `trusted_proxies`; entries that fail to parse are skipped
+    /// (`validate_trusted_proxies` rejects them at load).
+    pub fn trusted_proxy_nets(&self) -> Vec<IpNet> {
+        self.trusted_proxies
+            .iter()
+            .filter_map(|s| parse_trusted_proxy(s).ok())
+            .collect()
+    }
+
+    fn validate_trusted_proxies(&self) -> Result<(), String> {
+        for proxy in &self.trusted_proxies {
+            parse_trusted_proxy(proxy)?;
+        }
+        Ok(())
+    }
+}
+
+/// A trusted proxy entry: a CIDR range or a single address.
+fn parse_trusted_proxy(s: &str) -> Result<IpNet, String> {
+    let s = s.trim();
+    s.parse::<IpNet>()
+        .or_else(|_| s.parse::<std::net::IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("Invalid trusted proxy '{s}': expected an IP address or CIDR range"))
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -216,10 +544,465 @@ pub struct ProvidersConfig {
     pub litellm: Option<LitellmProviderConfig>,
     #[serde(default)]
     pub routing_strategy: RoutingStrategy,
+    /// How a token is picked when a provider has several OAuth accounts.
+    #[serde(default)]
+    pub account_selection: AccountSelection,
     #[serde(default = "default_token_storage_dir")]
     pub token_storage_dir: PathBuf,
     #[serde(default = "default_storage_backend")]
     pub storage_backend: StorageBackend,
+    /// Secret the `database` storage backend encrypts tokens with. Every
+    /// instance sharing the database needs the same value.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_encryption_key: Option<String>,
+    /// How often OAuth tokens close to expiry are refreshed in the
+    /// background, in seconds (default 300). `0` disables the background
+    /// refresh; tokens are then refreshed on the first request after expiry.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_refresh_interval_secs: Option<u64>,
+    /// Check every provider's credentials and health before accepting
+    /// traffic, retrying failures until `warmup_timeout_secs` runs out.
+    #[serde(default)]
+    pub warmup: bool,
+    /// How long the startup warm-up may take, in seconds (default 30).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warmup_timeout_secs: Option<u64>,
+    /// Emulate tool calling through the prompt for models known to lack
+    /// it, instead of refusing requests with tools.
+    #[serde(default)]
+    pub tool_emulation: bool,
+    /// Answer every request with a canned response instead of calling the
+    /// providers, for staging without spend. Each provider section can also
+    /// set `dry_run` for that provider alone.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// `max_tokens` for chat requests that set none and match no
+    /// `model_params` default. `0` leaves it to the provider.
+    #[serde(default)]
+    pub default_max_tokens: u32,
+    /// Retries of a failed provider call before falling back to the next
+    /// provider (`[providers.retry]`).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "RetryConfig::is_default")]
+    pub retry: RetryConfig,
+    /// Outbound HTTP or SOCKS5 proxy for provider and OAuth traffic
+    /// (`http://`, `https://`, `socks5://`, `socks5h://`). When unset, the
+    /// standard `HTTP(S)_PROXY` environment variables are honoured.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that bypass `proxy` (`NO_PROXY` syntax).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    /// Connection pool, HTTP/2 and TCP tuning for upstream clients.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HttpClientConfig::is_empty")]
+    pub http: HttpClientConfig,
+    /// Virtual models by name, requested as `gaud:<name>`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub virtual_models: BTreeMap<String, VirtualModelConfig>,
+    /// Routing rules (`[[providers.rules]]`), first match wins.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RoutingRule>,
+    /// Per-model parameter defaults and clamps
+    /// (`[[providers.model_params]]`), first match wins.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub model_params: Vec<ModelParams>,
+}
+
+/// A virtual model (`[providers.virtual_models.<name>]`): a list of concrete
+/// models one of which serves each request for `gaud:<name>`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VirtualModelConfig {
+    /// Concrete models in order of preference.
+    pub models: Vec<String>,
+    /// Relative weights, one per model. When set, models are picked at
+    /// random in these proportions instead of by the routing strategy.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<u32>,
+}
+
+/// Tuning for outbound HTTP clients (`[providers.http]` and
+/// `[providers.<name>.http]`). Unset fields keep reqwest's defaults.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    /// Maximum idle connections kept per host.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Close pooled connections after this many idle seconds.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_idle_timeout_secs: Option<u64>,
+    /// TCP keepalive interval in seconds.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp_keepalive_secs: Option<u64>,
+    /// Disable Nagle's algorithm.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp_nodelay: Option<bool>,
+    /// Speak HTTP/2 without ALPN negotiation (`h2c` for plain-text upstreams).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http2_prior_knowledge: Option<bool>,
+    /// Interval between HTTP/2 PING frames on open connections.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http2_keepalive_interval_secs: Option<u64>,
+    /// How long to wait for a PING acknowledgement before closing.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http2_keepalive_timeout_secs: Option<u64>,
+    /// Use adaptive HTTP/2 flow-control windows.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http2_adaptive_window: Option<bool>,
+}
+
+impl HttpClientConfig {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Layer `over` on top of `self`; fields set in `over` win.
+    pub fn merged(&self, over: &HttpClientConfig) -> HttpClientConfig {
+        HttpClientConfig {
+            pool_max_idle_per_host: over.pool_max_idle_per_host.or(self.pool_max_idle_per_host),
+            pool_idle_timeout_secs: over.pool_idle_timeout_secs.or(self.pool_idle_timeout_secs),
+            tcp_keepalive_secs: over.tcp_keepalive_secs.or(self.tcp_keepalive_secs),
+            tcp_nodelay: over.tcp_nodelay.or(self.tcp_nodelay),
+            http2_prior_knowledge: over.http2_prior_knowledge.or(self.http2_prior_knowledge),
+            http2_keepalive_interval_secs: over
+                .http2_keepalive_interval_secs
+                .or(self.http2_keepalive_interval_secs),
+            http2_keepalive_timeout_secs: over
+                .http2_keepalive_timeout_secs
+                .or(self.http2_keepalive_timeout_secs),
+            http2_adaptive_window: over.http2_adaptive_window.or(self.http2_adaptive_window),
+        }
+    }
+}
+
+/// Daily and monthly request quota of a provider account
+/// (`[providers.<name>.quota]`). Unset limits are not enforced locally, but
+/// the provider is still skipped after the upstream reports exhaustion.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProviderQuotaConfig {
+    /// Requests allowed per day.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_requests: Option<u64>,
+    /// Requests allowed per calendar month.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_requests: Option<u64>,
+    /// UTC hour (0-23) at which the daily quota resets (default: 0).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_hour_utc: Option<u8>,
+}
+
+impl ProviderQuotaConfig {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Retry policy of provider calls (`[providers.retry]`). Retries back off
+/// exponentially from `initial_backoff_ms`, doubling up to `max_backoff_ms`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Retries after the first attempt; `0` falls back at once.
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        let policy = RetryPolicy::new();
+        Self {
+            max_retries: policy.max_retries,
+            initial_backoff_ms: policy.initial_backoff_ms,
+            max_backoff_ms: policy.max_backoff_ms,
+        }
+    }
+}
+
+impl RetryConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The router's retry policy for these settings.
+    pub fn policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.max_retries,
+            initial_backoff_ms: self.initial_backoff_ms,
+            max_backoff_ms: self.max_backoff_ms,
+            ..RetryPolicy::new()
+        }
+    }
+
+    /// Backoff bounds must be in order.
+    fn validate(&self) -> Result<(), String> {
+        if self.initial_backoff_ms > self.max_backoff_ms {
+            return Err(format!(
+                "providers.retry.initial_backoff_ms ({}) exceeds max_backoff_ms ({})",
+                self.initial_backoff_ms, self.max_backoff_ms
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Sections editable through `/admin/providers/config`, in display order.
+pub const PROVIDER_SECTIONS: [&str; 6] = [
+    "claude",
+    "gemini",
+    "copilot",
+    "kiro",
+    "litellm",
+    "virtual_models",
+];
+
+/// Default interval of the background OAuth token refresh.
+const DEFAULT_TOKEN_REFRESH_INTERVAL_SECS: u64 = 300;
+
+/// Default limit of the startup warm-up.
+const DEFAULT_WARMUP_TIMEOUT_SECS: u64 = 30;
+
+impl ProvidersConfig {
+    /// Interval of the background token refresh, or `None` when disabled.
+    pub fn token_refresh_interval(&self) -> Option<std::time::Duration> {
+        match self
+            .token_refresh_interval_secs
+            .unwrap_or(DEFAULT_TOKEN_REFRESH_INTERVAL_SECS)
+        {
+            0 => None,
+            secs => Some(std::time::Duration::from_secs(secs)),
+        }
+    }
+
+    /// How long the startup warm-up may take.
+    pub fn warmup_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(
+            self.warmup_timeout_secs
+                .unwrap_or(DEFAULT_WARMUP_TIMEOUT_SECS),
+        )
+    }
+
+    /// Resolve the outbound proxy for `provider`, applying its `proxy`
+    /// override on top of the global setting.
+    pub fn proxy_for(&self, provider: &str) -> OutboundProxy {
+        let own = match provider {
+            "claude" => self.claude.as_ref().and_then(|c| c.proxy.as_deref()),
+            "gemini" => self.gemini.as_ref().and_then(|c| c.proxy.as_deref()),
+            "copilot" => self.copilot.as_ref().and_then(|c| c.proxy.as_deref()),
+            "kiro" => self.kiro.as_ref().and_then(|c| c.proxy.as_deref()),
+            "litellm" => self.litellm.as_ref().and_then(|c| c.proxy.as_deref()),
+            _ => None,
+        };
+        match own.or(self.proxy.as_deref()) {
+            None => OutboundProxy::System,
+            Some("") => OutboundProxy::Direct,
+            Some(url) => OutboundProxy::Url {
+                url: url.to_string(),
+                no_proxy: self.no_proxy.clone(),
+            },
+        }
+    }
+
+    /// Resolve the HTTP client tuning for `provider`.
+    pub fn http_for(&self, provider: &str) -> HttpClientConfig {
+        let own = match provider {
+            "claude" => self.claude.as_ref().map(|c| &c.http),
+            "gemini" => self.gemini.as_ref().map(|c| &c.http),
+            "copilot" => self.copilot.as_ref().map(|c| &c.http),
+            "kiro" => self.kiro.as_ref().map(|c| &c.http),
+            "litellm" => self.litellm.as_ref().map(|c| &c.http),
+            _ => None,
+        };
+        match own {
+            Some(own) => self.http.merged(own),
+            None => self.http.clone(),
+        }
+    }
+
+    /// Quota configuration for `provider`.
+    pub fn quota_for(&self, provider: &str) -> ProviderQuotaConfig {
+        let own = match provider {
+            "claude" => self.claude.as_ref().map(|c| &c.quota),
+            "gemini" => self.gemini.as_ref().map(|c| &c.quota),
+            "copilot" => self.copilot.as_ref().map(|c| &c.quota),
+            "kiro" => self.kiro.as_ref().map(|c| &c.quota),
+            "litellm" => self.litellm.as_ref().map(|c| &c.quota),
+            other => other.strip_prefix("kiro:").and_then(|name| {
+                self.kiro
+                    .as_ref()
+                    .and_then(|c| c.profiles.get(name))
+                    .map(|p| &p.quota)
+            }),
+        };
+        own.cloned().unwrap_or_default()
+    }
+
+    /// Whether `provider` runs in dry-run mode, globally or on its own.
+    pub fn dry_run_for(&self, provider: &str) -> bool {
+        let own = match provider {
+            "claude" => self.claude.as_ref().map(|c| c.dry_run),
+            "gemini" => self.gemini.as_ref().map(|c| c.dry_run),
+            "copilot" => self.copilot.as_ref().map(|c| c.dry_run),
+            "kiro" => self.kiro.as_ref().map(|c| c.dry_run),
+            "litellm" => self.litellm.as_ref().map(|c| c.dry_run),
+            other => other.strip_prefix("kiro:").and_then(|name| {
+                self.kiro
+                    .as_ref()
+                    .and_then(|c| c.profiles.get(name))
+                    .map(|p| p.dry_run)
+            }),
+        };
+        self.dry_run || own.unwrap_or(false)
+    }
+
+    /// A section editable through `/admin/providers/config`: a provider's
+    /// settings, or `virtual_models`. `None` for unknown sections; `null`
+    /// for providers that are not configured.
+    pub fn section(&self, name: &str) -> Option<serde_json::Value> {
+        let value = match name {
+            "claude" => serde_json::to_value(&self.claude),
+            "gemini" => serde_json::to_value(&self.gemini),
+            "copilot" => serde_json::to_value(&self.copilot),
+            "kiro" => serde_json::to_value(&self.kiro),
+            "litellm" => serde_json::to_value(&self.litellm),
+            "virtual_models" => serde_json::to_value(&self.virtual_models),
+            _ => return None,
+        };
+        value.ok()
+    }
+
+    /// Replace a section from its JSON form; `null` removes a provider.
+    pub fn set_section(&mut self, name: &str, value: serde_json::Value) -> Result<(), String> {
+        fn parse<T: serde::de::DeserializeOwned>(
+            name: &str,
+            value: serde_json::Value,
+        ) -> Result<T, String> {
+            serde_json::from_value(value).map_err(|e| format!("Invalid {name} settings: {e}"))
+        }
+        match name {
+            "claude" => self.claude = parse(name, value)?,
+            "gemini" => self.gemini = parse(name, value)?,
+            "copilot" => self.copilot = parse(name, value)?,
+            "kiro" => self.kiro = parse(name, value)?,
+            "litellm" => self.litellm = parse(name, value)?,
+            "virtual_models" => self.virtual_models = parse(name, value)?,
+            _ => return Err(format!("Unknown provider section '{name}'")),
+        }
+        Ok(())
+    }
+
+    /// Check every configured proxy URL.
+    fn validate_proxies(&self) -> Result<(), String> {
+        for provider in ["claude", "gemini", "copilot", "kiro", "litellm"] {
+            self.proxy_for(provider).validate()?;
+        }
+        Ok(())
+    }
+
+    /// The `database` storage backend cannot work without an encryption key.
+    fn validate_token_storage(&self) -> Result<(), String> {
+        let has_key = self
+            .token_encryption_key
+            .as_deref()
+            .is_some_and(|k| !k.is_empty());
+        if self.storage_backend == StorageBackend::Database && !has_key {
+            return Err(
+                "providers.storage_backend = \"database\" requires providers.token_encryption_key"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Virtual model names become part of a model ID (`gaud:<name>`).
+    fn validate_virtual_models(&self) -> Result<(), String> {
+        for (name, group) in &self.virtual_models {
+            let valid = !name.is_empty()
+                && name.len() <= 32
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(format!(
+                    "Invalid virtual model name '{name}': use 1-32 letters, digits, '-' or '_'"
+                ));
+            }
+            if group.models.is_empty() {
+                return Err(format!("Virtual model '{name}' lists no models"));
+            }
+            if let Some(model) = group.models.iter().find(|m| m.starts_with("gaud:")) {
+                return Err(format!(
+                    "Virtual model '{name}' cannot contain another virtual model ('{model}')"
+                ));
+            }
+            if !group.weights.is_empty()
+                && (group.weights.len() != group.models.len()
+                    || group.weights.iter().all(|w| *w == 0))
+            {
+                return Err(format!(
+                    "Virtual model '{name}' needs one weight per model, not all zero"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Routing rules must be well-formed and uniquely named.
+    fn validate_rules(&self) -> Result<(), String> {
+        let mut names = std::collections::HashSet::new();
+        for rule in &self.rules {
+            rule.validate()?;
+            if !names.insert(rule.name.as_str()) {
+                return Err(format!("Duplicate routing rule name '{}'", rule.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Model parameter bounds must be consistent.
+    fn validate_model_params(&self) -> Result<(), String> {
+        self.model_params.iter().try_for_each(ModelParams::validate)
+    }
+
+    /// Kiro profile names become part of a provider ID.
+    fn validate_kiro_profiles(&self) -> Result<(), String> {
+        let Some(kiro) = &self.kiro else {
+            return Ok(());
+        };
+        for name in kiro.profiles.keys() {
+            let valid = !name.is_empty()
+                && name.len() <= 32
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(format!(
+                    "Invalid Kiro profile name '{name}': use 1-32 letters, digits, '-' or '_'"
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -237,6 +1020,24 @@ pub struct ClaudeProviderConfig {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
+    /// Outbound proxy for this provider, overriding `providers.proxy`. An
+    /// empty string connects directly.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Connection tuning for this provider, overriding `providers.http`
+    /// field by field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HttpClientConfig::is_empty")]
+    pub http: HttpClientConfig,
+    /// Known request quota of the upstream account.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProviderQuotaConfig::is_empty")]
+    pub quota: ProviderQuotaConfig,
+    /// Answer with canned responses instead of calling upstream; see
+    /// `providers.dry_run`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -255,17 +1056,74 @@ pub struct GeminiProviderConfig {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
+    /// Outbound proxy for this provider, overriding `providers.proxy`. An
+    /// empty string connects directly.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Connection tuning for this provider, overriding `providers.http`
+    /// field by field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HttpClientConfig::is_empty")]
+    pub http: HttpClientConfig,
+    /// Known request quota of the upstream account.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProviderQuotaConfig::is_empty")]
+    pub quota: ProviderQuotaConfig,
+    /// Answer with canned responses instead of calling upstream; see
+    /// `providers.dry_run`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    /// Cloud Code project to send requests to. Skips project discovery and
+    /// onboarding, e.g. for a paid Google Cloud project.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CopilotProviderConfig {
     pub client_id: String,
+    /// Fetch the model list from the Copilot API instead of using the
+    /// built-in list.
+    #[serde(default = "default_true")]
+    pub discover_models: bool,
+    /// Seconds between model list refreshes. 0 only fetches it at startup.
+    #[serde(default = "default_copilot_model_refresh")]
+    pub model_refresh_secs: u64,
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_model: Option<String>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
+    /// Outbound proxy for this provider, overriding `providers.proxy`. An
+    /// empty string connects directly.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Connection tuning for this provider, overriding `providers.http`
+    /// field by field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HttpClientConfig::is_empty")]
+    pub http: HttpClientConfig,
+    /// Known request quota of the upstream account.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProviderQuotaConfig::is_empty")]
+    pub quota: ProviderQuotaConfig,
+    /// Answer with canned responses instead of calling upstream; see
+    /// `providers.dry_run`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+impl CopilotProviderConfig {
+    /// Interval of the model list refresh, `None` when it is only fetched
+    /// at startup.
+    pub fn model_refresh_interval(&self) -> Option<std::time::Duration> {
+        (self.model_refresh_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.model_refresh_secs))
+    }
 }
 
 /// Kiro provider configuration (Amazon Q / AWS CodeWhisperer).
@@ -283,6 +1141,12 @@ pub struct CopilotProviderConfig {
 /// | `credentials_file` | `GAUD_KIRO_CREDS_FILE`     | Path to JSON file containing refreshToken etc.   |
 /// | `region`           | `GAUD_KIRO_REGION`         | AWS region (default: `us-east-1`)                |
 /// | `profile_arn`      | `GAUD_KIRO_PROFILE_ARN`    | AWS CodeWhisperer profile ARN (optional)         |
+///
+/// Alternatively, sign in from the OAuth page with the AWS SSO device flow;
+/// the token is then written to [`KiroProviderConfig::credentials_path`].
+///
+/// Additional accounts go in `[providers.kiro.profiles.<name>]`; each is
+/// registered as provider `kiro:<name>` serving the same models.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct KiroProviderConfig {
     /// Authentication method to use.
@@ -315,11 +1179,98 @@ pub struct KiroProviderConfig {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kiro_db_path: Option<String>,
-    /// Default model to use when none is specified.
+    /// IAM Identity Center start URL for the browser sign-in (default: AWS
+    /// Builder ID).
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub default_model: Option<String>,
-}
+    pub sso_start_url: Option<String>,
+    /// Region of the IAM Identity Center instance (default: `region`).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sso_region: Option<String>,
+    /// Default model to use when none is specified.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    /// Outbound proxy for this provider, overriding `providers.proxy`. An
+    /// empty string connects directly.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Connection tuning for this provider, overriding `providers.http`
+    /// field by field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HttpClientConfig::is_empty")]
+    pub http: HttpClientConfig,
+    /// Known request quota of the upstream account.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProviderQuotaConfig::is_empty")]
+    pub quota: ProviderQuotaConfig,
+    /// Answer with canned responses instead of calling upstream; see
+    /// `providers.dry_run`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    /// Additional Kiro accounts by profile name.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, KiroProfileConfig>,
+}
+
+/// An additional Kiro account, registered as provider `kiro:<name>`.
+///
+/// A profile only reads the credential sources configured here; it never
+/// probes the default Kiro CLI or AWS SSO cache locations, so two profiles
+/// cannot silently share one account.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+pub struct KiroProfileConfig {
+    /// Path to a JSON credentials file (default: `kiro-sso-<name>.json` in
+    /// the token storage directory).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credentials_file: Option<String>,
+    /// Path to a Kiro CLI SQLite database.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kiro_db_path: Option<String>,
+    /// Path to an AWS SSO cache directory.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sso_cache_dir: Option<String>,
+    /// AWS region (default: the main Kiro region).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// AWS CodeWhisperer profile ARN (default: read from the credentials).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile_arn: Option<String>,
+    /// Known request quota of this account.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProviderQuotaConfig::is_empty")]
+    pub quota: ProviderQuotaConfig,
+    /// Answer with canned responses instead of calling upstream; see
+    /// `providers.dry_run`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+impl KiroProfileConfig {
+    /// Credentials file of the profile: `credentials_file`, else
+    /// `kiro-sso-<name>.json` in the token storage directory.
+    pub fn credentials_path(&self, name: &str, token_storage_dir: &Path) -> PathBuf {
+        match &self.credentials_file {
+            Some(p) => shellexpand::full(p)
+                .map(|e| PathBuf::from(e.as_ref()))
+                .unwrap_or_else(|_| PathBuf::from(p)),
+            None => token_storage_dir.join(format!("kiro-sso-{name}.json")),
+        }
+    }
+}
+
+/// Provider ID of a Kiro profile.
+pub fn kiro_profile_provider_id(name: &str) -> String {
+    format!("kiro:{name}")
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -399,6 +1350,28 @@ impl KiroProviderConfig {
         std::env::var("GAUD_KIRO_REGION").unwrap_or_else(|_| self.region.clone())
     }
 
+    /// Region of the AWS SSO OIDC endpoint used by the browser sign-in.
+    pub fn effective_sso_region(&self) -> String {
+        self.sso_region
+            .clone()
+            .unwrap_or_else(|| self.effective_region())
+    }
+
+    /// Credentials file the Kiro provider reads and the browser sign-in
+    /// writes: `credentials_file`, else `GAUD_KIRO_CREDS_FILE`, else
+    /// `kiro-sso.json` in the token storage directory.
+    pub fn credentials_path(&self, token_storage_dir: &Path) -> PathBuf {
+        self.credentials_file
+            .clone()
+            .or_else(|| std::env::var("GAUD_KIRO_CREDS_FILE").ok())
+            .map(|p| {
+                shellexpand::full(&p)
+                    .map(|e| PathBuf::from(e.as_ref()))
+                    .unwrap_or_else(|_| PathBuf::from(&p))
+            })
+            .unwrap_or_else(|| token_storage_dir.join("kiro-sso.json"))
+    }
+
     /// Resolve the effective profile ARN (config → env → creds file).
     pub fn effective_profile_arn(&self) -> Option<String> {
         if let Some(ref arn) = self.profile_arn {
@@ -497,12 +1470,51 @@ pub struct LitellmProviderConfig {
     /// Request timeout in seconds for chat completions.
     #[serde(default = "default_litellm_timeout")]
     pub timeout_secs: u64,
+    /// Seconds between pulls of LiteLLM's spend data to reconcile it
+    /// against gaud's computed costs. 0 disables reconciliation. Needs a key
+    /// that may read `/spend/logs`.
+    #[serde(default)]
+    pub reconcile_interval_secs: u64,
+    /// Difference between gaud's and LiteLLM's cost of a model on a day, in
+    /// percent of the larger one, above which it is flagged.
+    #[serde(default = "default_litellm_reconcile_tolerance")]
+    pub reconcile_tolerance_percent: f64,
+    /// Outbound proxy for this provider, overriding `providers.proxy`. An
+    /// empty string connects directly.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Connection tuning for this provider, overriding `providers.http`
+    /// field by field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HttpClientConfig::is_empty")]
+    pub http: HttpClientConfig,
+    /// Known request quota of the upstream account.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "ProviderQuotaConfig::is_empty")]
+    pub quota: ProviderQuotaConfig,
+    /// Answer with canned responses instead of calling upstream; see
+    /// `providers.dry_run`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
 }
 
 const fn default_litellm_timeout() -> u64 {
     120
 }
 
+const fn default_litellm_reconcile_tolerance() -> f64 {
+    5.0
+}
+
+impl LitellmProviderConfig {
+    /// Interval of the spend reconciliation, `None` when it is disabled.
+    pub fn reconcile_interval(&self) -> Option<std::time::Duration> {
+        (self.reconcile_interval_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.reconcile_interval_secs))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum RoutingStrategy {
@@ -537,6 +1549,37 @@ impl FromStr for RoutingStrategy {
     }
 }
 
+/// Order in which the OAuth accounts of one provider are used.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountSelection {
+    /// Cycle through the accounts in turn.
+    #[default]
+    RoundRobin,
+    /// Pick the account that has gone unused the longest.
+    LeastRecentlyUsed,
+}
+
+impl std::fmt::Display for AccountSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RoundRobin => write!(f, "round_robin"),
+            Self::LeastRecentlyUsed => write!(f, "least_recently_used"),
+        }
+    }
+}
+
+impl FromStr for AccountSelection {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "round_robin" | "roundrobin" => Ok(Self::RoundRobin),
+            "least_recently_used" | "lru" => Ok(Self::LeastRecentlyUsed),
+            _ => Err(format!("Unknown account selection: {s}")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum StorageBackend {
@@ -544,6 +1587,7 @@ pub enum StorageBackend {
     File,
     Keyring,
     Memory,
+    Database,
 }
 
 impl std::fmt::Display for StorageBackend {
@@ -552,6 +1596,7 @@ impl std::fmt::Display for StorageBackend {
             Self::File => write!(f, "file"),
             Self::Keyring => write!(f, "keyring"),
             Self::Memory => write!(f, "memory"),
+            Self::Database => write!(f, "database"),
         }
     }
 }
@@ -563,6 +1608,7 @@ impl FromStr for StorageBackend {
             "file" => Ok(Self::File),
             "keyring" => Ok(Self::Keyring),
             "memory" => Ok(Self::Memory),
+            "database" => Ok(Self::Database),
             _ => Err(format!("Unknown storage backend: {s}")),
         }
     }
@@ -585,182 +1631,760 @@ impl Default for BudgetConfig {
     }
 }
 
+impl BudgetConfig {
+    /// The warning threshold is a percentage of the budget.
+    fn validate_warning_threshold(&self) -> Result<(), String> {
+        if self.warning_threshold_percent > 100 {
+            return Err(format!(
+                "budget.warning_threshold_percent must be at most 100, got {}",
+                self.warning_threshold_percent
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Concurrency limits (`[limits]`). `0` means unlimited.
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct LoggingConfig {
-    #[serde(default = "default_log_level")]
-    pub level: String,
+pub struct LimitsConfig {
+    /// Most streaming responses one user may have open at once.
     #[serde(default)]
-    pub json: bool,
+    pub max_streams_per_user: u32,
+    /// Most streaming responses one API key may have open at once.
     #[serde(default)]
-    pub log_content: bool,
+    pub max_streams_per_key: u32,
+    /// Most chat requests in flight to providers at once. Requests past it
+    /// are queued and served fairly across users.
+    #[serde(default)]
+    pub max_in_flight: u32,
+    /// Seconds a queued request waits for a slot before it is refused.
+    #[serde(default = "default_queue_timeout_secs")]
+    pub queue_timeout_secs: u64,
+    /// Largest request body accepted by the API, in MiB. Audio and file
+    /// uploads have limits of their own.
+    #[serde(default = "default_max_body_mb")]
+    pub max_body_mb: u32,
+    /// Most messages in one chat request.
+    #[serde(default)]
+    pub max_messages: u32,
+    /// Largest inline (`data:` URL) image in a chat request, in MiB.
+    #[serde(default)]
+    pub max_image_mb: u32,
+    /// Scheduling priority per team, for keys left at `default`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub team_priority: BTreeMap<String, Priority>,
 }
 
-impl Default for LoggingConfig {
-    fn default() -> Self {
-        Self {
-            level: default_log_level(),
-            json: false,
-            log_content: false,
-        }
+impl LimitsConfig {
+    /// Priority of requests made with a key of `key_priority` by a member
+    /// of `team`.
+    pub fn priority(&self, key_priority: Priority, team: Option<&str>) -> Priority {
+        let team_priority = team
+            .and_then(|t| self.team_priority.get(t).copied())
+            .unwrap_or_default();
+        key_priority.or(team_priority).or(Priority::Interactive)
     }
 }
 
-// ---------------------------------------------------------------------------
-// Cache configuration
-// ---------------------------------------------------------------------------
-
-/// Cache operating mode.
-#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
-#[serde(rename_all = "snake_case")]
-pub enum CacheMode {
-    /// Only exact SHA-256 hash matching.
-    Exact,
-    /// Only semantic (embedding + KNN) matching.
-    Semantic,
-    /// Both exact and semantic matching (exact checked first).
-    #[default]
-    Both,
+fn default_queue_timeout_secs() -> u64 {
+    30
 }
 
-impl std::fmt::Display for CacheMode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Exact => write!(f, "exact"),
-            Self::Semantic => write!(f, "semantic"),
-            Self::Both => write!(f, "both"),
-        }
-    }
+fn default_max_body_mb() -> u32 {
+    20
 }
 
-impl FromStr for CacheMode {
-    type Err = String;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "exact" => Ok(Self::Exact),
-            "semantic" => Ok(Self::Semantic),
-            "both" => Ok(Self::Both),
-            _ => Err(format!("Unknown cache mode: {s}")),
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_streams_per_user: 0,
+            max_streams_per_key: 0,
+            max_in_flight: 0,
+            queue_timeout_secs: default_queue_timeout_secs(),
+            max_body_mb: default_max_body_mb(),
+            max_messages: 0,
+            max_image_mb: 0,
+            team_priority: BTreeMap::new(),
         }
     }
 }
 
-/// Semantic cache configuration.
-///
-/// When `enabled` is true, chat completion results are checked against
-/// an embedded SurrealDB cache with optional HNSW vector search.
-/// Streaming responses can also be cached as replayable SSE event logs
-/// when `stream_cache_enabled` is true.
+/// Storage for files uploaded through `/v1/files`.
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct CacheConfig {
-    /// Master switch.
-    #[serde(default)]
-    pub enabled: bool,
-    /// Cache matching mode: "exact", "semantic", or "both".
+pub struct FilesConfig {
+    /// Directory for file contents. When unset, contents are stored as
+    /// blobs in the database.
     #[serde(default)]
-    pub mode: CacheMode,
-    /// Path to the cache database (for persistent mode).
-    #[serde(default = "default_cache_path")]
-    pub path: PathBuf,
+    pub storage_dir: Option<String>,
+    /// Largest accepted upload, in megabytes.
+    #[serde(default = "default_max_upload_mb")]
+    pub max_upload_mb: u32,
+    /// Seconds an image uploaded to `/v1/uploads/images` can be referenced.
+    #[serde(default = "default_image_ttl_secs")]
+    pub image_ttl_secs: u64,
+}
 
-    /// Cosine similarity threshold for semantic matches (0.0 – 1.0).
-    #[serde(default = "default_similarity_threshold")]
-    pub similarity_threshold: f32,
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            storage_dir: None,
+            max_upload_mb: default_max_upload_mb(),
+            image_ttl_secs: default_image_ttl_secs(),
+        }
+    }
+}
 
-    /// URL of an OpenAI-compatible embeddings endpoint.
+/// Sources of model prices beyond the built-in table. Both may be set; the
+/// URL's prices win over the file's.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PricingConfig {
+    /// Local JSON or TOML price list.
     #[serde(default)]
-    pub embedding_url: Option<String>,
-    /// Embedding model name (e.g. "text-embedding-3-small").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Remote JSON price list, in gaud's format or LiteLLM's
+    /// `model_prices_and_context_window.json`.
     #[serde(default)]
-    pub embedding_model: Option<String>,
-    /// API key for the embedding endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Seconds between reloads of `file` and `url`. 0 loads them only at
+    /// startup.
+    #[serde(default = "default_pricing_refresh")]
+    pub refresh_interval_secs: u64,
+    /// ISO 4217 code of the currency costs, budgets and reports are
+    /// denominated in. Provider prices are in USD and converted.
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Units of `currency` per USD. Ignored for USD.
     #[serde(default)]
-    pub embedding_api_key: Option<String>,
-    /// Embedding vector dimension.
-    #[serde(default = "default_embedding_dim")]
-    pub embedding_dimension: u16,
-    /// Allow the embedding URL to resolve to local/private addresses.
-    ///
-    /// Set this to `true` when running a self-hosted embedding server
-    /// (e.g. Ollama, vLLM, text-embeddings-inference) on localhost or a
-    /// private network. When `false` (the default), SSRF protection
-    /// blocks requests to private/loopback addresses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exchange_rate: Option<f64>,
+    /// JSON endpoint returning USD exchange rates (`{"rates": {"EUR": 0.92}}`),
+    /// fetched on each price refresh. Wins over `exchange_rate`.
     #[serde(default)]
-    pub embedding_allow_local: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exchange_rate_url: Option<String>,
+}
 
-    /// HNSW M parameter (max connections per layer).
-    #[serde(default = "default_hnsw_m")]
-    pub hnsw_m: u8,
-    /// HNSW ef_construction parameter.
-    #[serde(default = "default_hnsw_efc")]
-    pub hnsw_ef_construction: u16,
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            url: None,
+            refresh_interval_secs: default_pricing_refresh(),
+            currency: default_currency(),
+            exchange_rate: None,
+            exchange_rate_url: None,
+        }
+    }
+}
 
-    /// Maximum number of cached entries.
-    #[serde(default = "default_max_entries")]
-    pub max_entries: usize,
-    /// Time-to-live in seconds.
-    #[serde(default = "default_cache_ttl")]
-    pub ttl_secs: u64,
+impl PricingConfig {
+    /// Interval of the price refresh, `None` when prices load only at startup.
+    pub fn refresh_interval(&self) -> Option<std::time::Duration> {
+        (self.refresh_interval_secs > 0)
+            .then(|| std::time::Duration::from_secs(self.refresh_interval_secs))
+    }
 
-    /// Skip caching for requests that include tool definitions.
-    #[serde(default = "default_true")]
-    pub skip_tool_requests: bool,
-    /// Models to exclude from caching.
-    #[serde(default)]
-    pub skip_models: Vec<String>,
+    /// The configured currency must be a three-letter code, with a rate
+    /// unless it is USD.
+    fn validate_currency(&self) -> Result<(), String> {
+        if self.currency.len() != 3 || !self.currency.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err(format!(
+                "pricing.currency must be a three-letter ISO 4217 code, got '{}'",
+                self.currency
+            ));
+        }
+        if self
+            .exchange_rate
+            .is_some_and(|r| !(r > 0.0 && r.is_finite()))
+        {
+            return Err("pricing.exchange_rate must be a positive number".to_string());
+        }
+        if self.currency != "USD"
+            && self.exchange_rate.is_none()
+            && self.exchange_rate_url.is_none()
+        {
+            return Err(format!(
+                "pricing.currency = \"{}\" requires pricing.exchange_rate or pricing.exchange_rate_url",
+                self.currency
+            ));
+        }
+        Ok(())
+    }
+}
 
-    // -- Streaming cache (replay) -------------------------------------------
-    /// Whether to cache streaming responses as replayable SSE event logs.
+/// Scheduled reports.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ReportsConfig {
+    /// Directory the previous month's chargeback report is written to once
+    /// the month has ended. Unset disables scheduled reports.
     #[serde(default)]
-    pub stream_cache_enabled: bool,
-    /// Max events to buffer per streaming request before disabling caching.
-    #[serde(default = "default_stream_cache_max_events")]
-    pub stream_cache_max_events: usize,
-    /// Max bytes to buffer per streaming request before disabling caching.
-    #[serde(default = "default_stream_cache_max_bytes")]
-    pub stream_cache_max_bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chargeback_dir: Option<String>,
 }
 
-impl Default for CacheConfig {
+/// Operational alerts posted to chat webhooks.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NotificationsConfig {
+    /// Slack incoming webhook URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slack_webhook_url: Option<String>,
+    /// Discord webhook URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discord_webhook_url: Option<String>,
+    /// Event kinds to send (`provider_outage`, `circuit_open`,
+    /// `budget_exhausted`, `reauth_required`). Empty sends all of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+    /// Seconds before the same event (kind and subject) is sent again.
+    #[serde(default = "default_notification_cooldown")]
+    pub cooldown_secs: u64,
+}
+
+impl NotificationsConfig {
+    /// Every event kind must be one gaud sends.
+    fn validate_events(&self) -> Result<(), String> {
+        for event in &self.events {
+            if !crate::notify::EVENT_KINDS.contains(&event.as_str()) {
+                return Err(format!(
+                    "Unknown notifications event '{event}' (expected one of: {})",
+                    crate::notify::EVENT_KINDS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for NotificationsConfig {
     fn default() -> Self {
         Self {
-            enabled: false,
-            mode: CacheMode::default(),
-            path: default_cache_path(),
-            similarity_threshold: default_similarity_threshold(),
-            embedding_url: None,
-            embedding_model: None,
-            embedding_api_key: None,
-            embedding_dimension: default_embedding_dim(),
-            embedding_allow_local: false,
-            hnsw_m: default_hnsw_m(),
-            hnsw_ef_construction: default_hnsw_efc(),
-            max_entries: default_max_entries(),
-            ttl_secs: default_cache_ttl(),
-            skip_tool_requests: true,
-            skip_models: Vec::new(),
-            stream_cache_enabled: false,
-            stream_cache_max_events: default_stream_cache_max_events(),
-            stream_cache_max_bytes: default_stream_cache_max_bytes(),
+            slack_webhook_url: None,
+            discord_webhook_url: None,
+            events: Vec::new(),
+            cooldown_secs: default_notification_cooldown(),
         }
     }
 }
 
-fn default_cache_path() -> PathBuf {
-    dirs::data_local_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("gaud")
-        .join("cache")
+fn default_notification_cooldown() -> u64 {
+    900
 }
 
-const fn default_similarity_threshold() -> f32 {
-    0.92
-}
-const fn default_embedding_dim() -> u16 {
-    1536
-}
-const fn default_hnsw_m() -> u8 {
-    16
-}
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LoggingConfig {
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    #[serde(default)]
+    pub json: bool,
+    #[serde(default)]
+    pub log_content: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_log_level(),
+            json: false,
+            log_content: false,
+        }
+    }
+}
+
+/// What the request traces and debug logs contain (see `observability`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ObservabilityConfig {
+    /// Share of HTTP requests given a trace span, from 0.0 to 1.0.
+    #[serde(default = "default_trace_sample_ratio")]
+    pub trace_sample_ratio: f64,
+    /// Record request headers in the trace span.
+    #[serde(default)]
+    pub log_headers: bool,
+    /// Log prompts and upstream request bodies at debug level. Also turned
+    /// on by `logging.log_content`.
+    #[serde(default)]
+    pub log_prompts: bool,
+    /// Mask bearer tokens, API keys, cookies and OAuth secrets in logged
+    /// headers, URLs and bodies.
+    #[serde(default = "default_true")]
+    pub redact: bool,
+    /// Recent failed requests kept for `/admin/debug/failures`; 0 keeps
+    /// none.
+    #[serde(default = "default_failure_buffer")]
+    pub failure_buffer: usize,
+}
+
+impl Default for ObservabilityConfig {
+    fn default() -> Self {
+        Self {
+            trace_sample_ratio: default_trace_sample_ratio(),
+            log_headers: false,
+            log_prompts: false,
+            redact: true,
+            failure_buffer: default_failure_buffer(),
+        }
+    }
+}
+
+impl ObservabilityConfig {
+    /// The sampling ratio must be a share.
+    fn validate_sample_ratio(&self) -> Result<(), String> {
+        if (0.0..=1.0).contains(&self.trace_sample_ratio) {
+            Ok(())
+        } else {
+            Err(format!(
+                "observability.trace_sample_ratio must be between 0.0 and 1.0, got {}",
+                self.trace_sample_ratio
+            ))
+        }
+    }
+}
+
+fn default_trace_sample_ratio() -> f64 {
+    1.0
+}
+
+fn default_failure_buffer() -> usize {
+    crate::providers::failures::DEFAULT_CAPACITY
+}
+
+/// Model Context Protocol: the server at `/mcp` (see `api::mcp`) and the
+/// external tool servers attached to chat requests (see `mcp::client`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct McpConfig {
+    /// Serve MCP clients. Off by default.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Most rounds of server-side tool calls in one request.
+    #[serde(default = "default_mcp_max_tool_rounds")]
+    pub max_tool_rounds: u32,
+    /// External MCP servers whose tools are offered to models, by name.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub servers: BTreeMap<String, McpServerConfig>,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_tool_rounds: default_mcp_max_tool_rounds(),
+            servers: BTreeMap::new(),
+        }
+    }
+}
+
+const fn default_mcp_max_tool_rounds() -> u32 {
+    8
+}
+
+impl McpConfig {
+    /// Server names become part of tool names (`<server>__<tool>`).
+    fn validate_servers(&self) -> Result<(), String> {
+        for (name, server) in &self.servers {
+            let valid = !name.is_empty()
+                && name.len() <= 32
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                && !name.contains("__");
+            if !valid {
+                return Err(format!(
+                    "Invalid MCP server name '{name}': use 1-32 letters, digits or '-'"
+                ));
+            }
+            if !server.url.starts_with("http://") && !server.url.starts_with("https://") {
+                return Err(format!("MCP server '{name}' needs an http(s) url"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Server-side agent runs (`/v1/agents/run`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AgentsConfig {
+    /// Most model calls in one run. Runs may ask for fewer.
+    #[serde(default = "default_agent_max_steps")]
+    pub max_steps: u32,
+    /// Timeout of each call to a callback tool.
+    #[serde(default = "default_agent_callback_timeout")]
+    pub callback_timeout_secs: u64,
+    /// Allow callback URLs on loopback and private networks.
+    #[serde(default)]
+    pub allow_private_callbacks: bool,
+}
+
+impl Default for AgentsConfig {
+    fn default() -> Self {
+        Self {
+            max_steps: default_agent_max_steps(),
+            callback_timeout_secs: default_agent_callback_timeout(),
+            allow_private_callbacks: false,
+        }
+    }
+}
+
+const fn default_agent_max_steps() -> u32 {
+    10
+}
+
+const fn default_agent_callback_timeout() -> u64 {
+    30
+}
+
+/// Server-side conversation storage (`/v1/conversations`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConversationsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Most messages kept per conversation, not counting system messages.
+    #[serde(default = "default_conversation_max_messages")]
+    pub max_messages: usize,
+    /// Most estimated tokens of kept messages (0 = no limit).
+    #[serde(default)]
+    pub max_history_tokens: u32,
+    /// What happens to messages over the limits.
+    #[serde(default)]
+    pub compaction: ConversationCompaction,
+    /// Model that writes summaries; the request's model when unset.
+    #[serde(default)]
+    pub summary_model: Option<String>,
+}
+
+impl Default for ConversationsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_messages: default_conversation_max_messages(),
+            max_history_tokens: 0,
+            compaction: ConversationCompaction::default(),
+            summary_model: None,
+        }
+    }
+}
+
+const fn default_conversation_max_messages() -> usize {
+    100
+}
+
+/// How a conversation over its limits is cut back.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationCompaction {
+    /// Drop the oldest turns.
+    #[default]
+    Truncate,
+    /// Replace the oldest turns with a model-written summary.
+    Summarize,
+}
+
+impl std::fmt::Display for ConversationCompaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncate => write!(f, "truncate"),
+            Self::Summarize => write!(f, "summarize"),
+        }
+    }
+}
+
+impl FromStr for ConversationCompaction {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "truncate" => Ok(Self::Truncate),
+            "summarize" => Ok(Self::Summarize),
+            _ => Err(format!("Unknown conversation compaction: {s}")),
+        }
+    }
+}
+
+/// An external MCP server reached over the Streamable HTTP transport.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct McpServerConfig {
+    /// Endpoint URL, e.g. `https://mcp.example.com/mcp`.
+    pub url: String,
+    /// Sent as a bearer token.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// Models whose requests get this server's tools; `*` matches any run
+    /// of characters.
+    #[serde(default = "default_mcp_server_models")]
+    pub models: Vec<String>,
+    /// Timeout of each call to the server.
+    #[serde(default = "default_mcp_server_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_mcp_server_models() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+const fn default_mcp_server_timeout() -> u64 {
+    60
+}
+
+// ---------------------------------------------------------------------------
+// Provisioning configuration
+// ---------------------------------------------------------------------------
+
+/// Users, teams, budgets and keys declared in the config file and
+/// reconciled into the database at startup.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProvisioningConfig {
+    /// Revoke keys of declared users that are not declared themselves.
+    #[serde(default)]
+    pub prune_keys: bool,
+    #[serde(default)]
+    pub teams: Vec<ProvisionedTeam>,
+    #[serde(default)]
+    pub users: Vec<ProvisionedUser>,
+}
+
+impl ProvisioningConfig {
+    pub fn is_empty(&self) -> bool {
+        self.teams.is_empty() && self.users.is_empty()
+    }
+}
+
+/// A team and its shared rate quotas.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProvisionedTeam {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpm: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tpm: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpd: Option<u64>,
+}
+
+/// A user. Budget limits left out are removed, so the file stays the
+/// source of truth.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProvisionedUser {
+    pub name: String,
+    #[serde(default = "default_provisioned_role")]
+    pub role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_limit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_limit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_premium_limit: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_premium_limit: Option<f64>,
+    #[serde(default)]
+    pub keys: Vec<ProvisionedKey>,
+}
+
+fn default_provisioned_role() -> String {
+    "member".to_string()
+}
+
+/// An API key declared by its argon2 hash, as printed by `gaud --hash-key`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProvisionedKey {
+    pub hash: String,
+    /// Display prefix (`sk-prx-a1b2c3d4...`), shown in key listings.
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub reasoning_mode: ReasoningMode,
+    #[serde(default)]
+    pub priority: Priority,
+}
+
+// ---------------------------------------------------------------------------
+// Cache configuration
+// ---------------------------------------------------------------------------
+
+/// Cache operating mode.
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheMode {
+    /// Only exact SHA-256 hash matching.
+    Exact,
+    /// Only semantic (embedding + KNN) matching.
+    Semantic,
+    /// Both exact and semantic matching (exact checked first).
+    #[default]
+    Both,
+}
+
+impl std::fmt::Display for CacheMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact => write!(f, "exact"),
+            Self::Semantic => write!(f, "semantic"),
+            Self::Both => write!(f, "both"),
+        }
+    }
+}
+
+impl FromStr for CacheMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exact" => Ok(Self::Exact),
+            "semantic" => Ok(Self::Semantic),
+            "both" => Ok(Self::Both),
+            _ => Err(format!("Unknown cache mode: {s}")),
+        }
+    }
+}
+
+/// Semantic cache configuration.
+///
+/// When `enabled` is true, chat completion results are checked against
+/// an embedded SurrealDB cache with optional HNSW vector search.
+/// Streaming responses can also be cached as replayable SSE event logs
+/// when `stream_cache_enabled` is true, and made resumable via
+/// `Last-Event-ID` when `stream_resume_enabled` is true.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CacheConfig {
+    /// Master switch.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Cache matching mode: "exact", "semantic", or "both".
+    #[serde(default)]
+    pub mode: CacheMode,
+    /// Path to the cache database (for persistent mode).
+    #[serde(default = "default_cache_path")]
+    pub path: PathBuf,
+    /// URL of a shared cache store (`tikv://pd-host:2379`). When set, the
+    /// cache lives there instead of at `path`, so every gaud node pointed
+    /// at it shares entries. Needs the `cache-distributed` build feature.
+    #[serde(default)]
+    pub backend_url: Option<String>,
+
+    /// Cosine similarity threshold for semantic matches (0.0 – 1.0).
+    #[serde(default = "default_similarity_threshold")]
+    pub similarity_threshold: f32,
+
+    /// URL of an OpenAI-compatible embeddings endpoint.
+    #[serde(default)]
+    pub embedding_url: Option<String>,
+    /// Embedding model name (e.g. "text-embedding-3-small").
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+    /// API key for the embedding endpoint.
+    #[serde(default)]
+    pub embedding_api_key: Option<String>,
+    /// Embedding vector dimension.
+    #[serde(default = "default_embedding_dim")]
+    pub embedding_dimension: u16,
+    /// Allow the embedding URL to resolve to local/private addresses.
+    ///
+    /// Set this to `true` when running a self-hosted embedding server
+    /// (e.g. Ollama, vLLM, text-embeddings-inference) on localhost or a
+    /// private network. When `false` (the default), SSRF protection
+    /// blocks requests to private/loopback addresses.
+    #[serde(default)]
+    pub embedding_allow_local: bool,
+
+    /// HNSW M parameter (max connections per layer).
+    #[serde(default = "default_hnsw_m")]
+    pub hnsw_m: u8,
+    /// HNSW ef_construction parameter.
+    #[serde(default = "default_hnsw_efc")]
+    pub hnsw_ef_construction: u16,
+
+    /// Maximum number of cached entries.
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// Time-to-live in seconds.
+    #[serde(default = "default_cache_ttl")]
+    pub ttl_secs: u64,
+
+    /// Skip caching for requests that include tool definitions.
+    #[serde(default = "default_true")]
+    pub skip_tool_requests: bool,
+    /// Models to exclude from caching.
+    #[serde(default)]
+    pub skip_models: Vec<String>,
+
+    // -- Streaming cache (replay) -------------------------------------------
+    /// Whether to cache streaming responses as replayable SSE event logs.
+    #[serde(default)]
+    pub stream_cache_enabled: bool,
+    /// Max events to buffer per streaming request before disabling caching.
+    #[serde(default = "default_stream_cache_max_events")]
+    pub stream_cache_max_events: usize,
+    /// Max bytes to buffer per streaming request before disabling caching.
+    #[serde(default = "default_stream_cache_max_bytes")]
+    pub stream_cache_max_bytes: usize,
+    /// Whether SSE streams carry event IDs and can be resumed with
+    /// `Last-Event-ID`. Independent of `enabled`.
+    #[serde(default)]
+    pub stream_resume_enabled: bool,
+    /// How long a finished stream stays resumable, in seconds.
+    #[serde(default = "default_stream_resume_ttl")]
+    pub stream_resume_ttl_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: CacheMode::default(),
+            path: default_cache_path(),
+            backend_url: None,
+            similarity_threshold: default_similarity_threshold(),
+            embedding_url: None,
+            embedding_model: None,
+            embedding_api_key: None,
+            embedding_dimension: default_embedding_dim(),
+            embedding_allow_local: false,
+            hnsw_m: default_hnsw_m(),
+            hnsw_ef_construction: default_hnsw_efc(),
+            max_entries: default_max_entries(),
+            ttl_secs: default_cache_ttl(),
+            skip_tool_requests: true,
+            skip_models: Vec::new(),
+            stream_cache_enabled: false,
+            stream_cache_max_events: default_stream_cache_max_events(),
+            stream_cache_max_bytes: default_stream_cache_max_bytes(),
+            stream_resume_enabled: false,
+            stream_resume_ttl_secs: default_stream_resume_ttl(),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// The similarity threshold is a cosine similarity.
+    fn validate_similarity_threshold(&self) -> Result<(), String> {
+        if (0.0..=1.0).contains(&self.similarity_threshold) {
+            Ok(())
+        } else {
+            Err(format!(
+                "cache.similarity_threshold must be between 0.0 and 1.0, got {}",
+                self.similarity_threshold
+            ))
+        }
+    }
+}
+
+fn default_cache_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("gaud")
+        .join("cache")
+}
+
+const fn default_similarity_threshold() -> f32 {
+    0.92
+}
+const fn default_embedding_dim() -> u16 {
+    1536
+}
+const fn default_hnsw_m() -> u8 {
+    16
+}
 const fn default_hnsw_efc() -> u16 {
     200
 }
@@ -773,6 +2397,9 @@ const fn default_stream_cache_max_events() -> usize {
 const fn default_stream_cache_max_bytes() -> usize {
     8_388_608 // 8 MB
 }
+const fn default_stream_resume_ttl() -> u64 {
+    60
+}
 const fn default_cache_ttl() -> u64 {
     3600
 }
@@ -781,21 +2408,49 @@ const fn default_cache_ttl() -> u64 {
 // Default value functions
 // ---------------------------------------------------------------------------
 
+fn default_acme_cache_dir() -> String {
+    "~/.local/share/gaud/acme".to_string()
+}
+
 const fn default_port() -> u16 {
     8400
 }
+const fn default_shutdown_drain_secs() -> u64 {
+    30
+}
 fn default_host() -> String {
     "127.0.0.1".to_string()
 }
 fn default_db_path() -> PathBuf {
     PathBuf::from("gaud.db")
 }
+fn default_leader_lease_secs() -> u64 {
+    30
+}
 fn default_admin_name() -> String {
     "admin".to_string()
 }
+const fn default_self_service_max_keys() -> u32 {
+    5
+}
+const fn default_session_ttl_hours() -> u64 {
+    12
+}
+const fn default_login_max_failures() -> u32 {
+    10
+}
+const fn default_login_window_secs() -> u64 {
+    300
+}
+const fn default_login_lockout_secs() -> u64 {
+    900
+}
 const fn default_true() -> bool {
     true
 }
+const fn default_copilot_model_refresh() -> u64 {
+    3600
+}
 fn default_token_storage_dir() -> PathBuf {
     dirs_default_token_storage()
 }
@@ -832,6 +2487,18 @@ const fn default_budget_check_enabled() -> bool {
 const fn default_warning_threshold() -> u8 {
     80
 }
+const fn default_max_upload_mb() -> u32 {
+    100
+}
+const fn default_image_ttl_secs() -> u64 {
+    3600
+}
+const fn default_pricing_refresh() -> u64 {
+    86400
+}
+fn default_currency() -> String {
+    "USD".to_string()
+}
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -857,9 +2524,53 @@ impl Config {
             Self::default()
         };
         config.apply_env_overrides();
+        config.validate().map_err(|e| anyhow::anyhow!(e))?;
         Ok(config)
     }
 
+    /// Check the settings that serde cannot: proxies, token storage,
+    /// profiles, virtual models, rules and the other cross-field rules.
+    pub fn validate(&self) -> Result<(), String> {
+        self.auth.login_throttle.validate_trusted_proxies()?;
+        self.providers.validate_proxies()?;
+        self.providers.validate_token_storage()?;
+        self.providers.validate_kiro_profiles()?;
+        self.providers.validate_virtual_models()?;
+        self.providers.validate_rules()?;
+        self.providers.validate_model_params()?;
+        self.providers.retry.validate()?;
+        self.pricing.validate_currency()?;
+        self.notifications.validate_events()?;
+        self.mcp.validate_servers()?;
+        self.observability.validate_sample_ratio()?;
+        self.cache.validate_similarity_threshold()?;
+        self.budget.validate_warning_threshold()?;
+        self.validate_experiments()?;
+        Ok(())
+    }
+
+    /// The configuration as written in the file at `path`, without
+    /// environment overrides; defaults when there is no file.
+    pub fn load_file(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Experiments must be well-formed and uniquely named.
+    fn validate_experiments(&self) -> Result<(), String> {
+        let mut names = std::collections::HashSet::new();
+        for experiment in &self.experiments {
+            experiment.validate()?;
+            if !names.insert(experiment.name.as_str()) {
+                return Err(format!("Duplicate experiment name '{}'", experiment.name));
+            }
+        }
+        Ok(())
+    }
+
     pub fn listen_addr(&self) -> String {
         format!("{}:{}", self.server.host, self.server.port)
     }
@@ -930,16 +2641,81 @@ impl Config {
         env_str!("server.host", "GAUD_SERVER_HOST", self.server.host);
         env_parse!("server.port", "GAUD_SERVER_PORT", self.server.port);
         if let Ok(val) = std::env::var("GAUD_SERVER_CORS_ORIGINS") {
-            self.server.cors_origins = val
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
+            self.server.cors_origins = parse_list(&val);
             ov.record("server.cors_origins", "GAUD_SERVER_CORS_ORIGINS");
         }
+        for (key, env_var, origins) in [
+            (
+                "server.cors.proxy",
+                "GAUD_SERVER_CORS_PROXY",
+                &mut self.server.cors.proxy,
+            ),
+            (
+                "server.cors.admin",
+                "GAUD_SERVER_CORS_ADMIN",
+                &mut self.server.cors.admin,
+            ),
+            (
+                "server.cors.ui",
+                "GAUD_SERVER_CORS_UI",
+                &mut self.server.cors.ui,
+            ),
+        ] {
+            if let Ok(val) = std::env::var(env_var) {
+                *origins = parse_group_origins(&val);
+                ov.record(key, env_var);
+            }
+        }
+        env_opt_str!(
+            "server.tls_cert_path",
+            "GAUD_SERVER_TLS_CERT",
+            self.server.tls_cert_path
+        );
+        env_opt_str!(
+            "server.tls_key_path",
+            "GAUD_SERVER_TLS_KEY",
+            self.server.tls_key_path
+        );
+        if let Ok(val) = std::env::var("GAUD_SERVER_ADMIN_PORT") {
+            if val.is_empty() {
+                self.server.admin = None;
+                ov.record("server.admin.port", "GAUD_SERVER_ADMIN_PORT");
+            } else if let Ok(port) = val.parse() {
+                match self.server.admin {
+                    Some(ref mut admin) => admin.port = port,
+                    None => {
+                        self.server.admin = Some(AdminListenerConfig { host: None, port });
+                    }
+                }
+                ov.record("server.admin.port", "GAUD_SERVER_ADMIN_PORT");
+            }
+        }
+        // Only meaningful once the admin listener has a port.
+        if let (Ok(val), Some(admin)) = (
+            std::env::var("GAUD_SERVER_ADMIN_HOST"),
+            self.server.admin.as_mut(),
+        ) {
+            admin.host = if val.is_empty() { None } else { Some(val) };
+            ov.record("server.admin.host", "GAUD_SERVER_ADMIN_HOST");
+        }
+        env_parse!(
+            "server.shutdown_drain_secs",
+            "GAUD_SERVER_SHUTDOWN_DRAIN_SECS",
+            self.server.shutdown_drain_secs
+        );
 
         // -- Database --
         env_path!("database.path", "GAUD_DATABASE_PATH", self.database.path);
+        env_bool!(
+            "database.leader_election",
+            "GAUD_DATABASE_LEADER_ELECTION",
+            self.database.leader_election
+        );
+        env_parse!(
+            "database.leader_lease_secs",
+            "GAUD_DATABASE_LEADER_LEASE_SECS",
+            self.database.leader_lease_secs
+        );
 
         // -- Auth --
         env_bool!("auth.enabled", "GAUD_AUTH_ENABLED", self.auth.enabled);
@@ -953,6 +2729,21 @@ impl Config {
             "GAUD_AUTH_BOOTSTRAP_KEY",
             self.auth.bootstrap_key
         );
+        env_parse!(
+            "auth.self_service_max_keys",
+            "GAUD_AUTH_SELF_SERVICE_MAX_KEYS",
+            self.auth.self_service_max_keys
+        );
+        env_parse!(
+            "auth.session_ttl_hours",
+            "GAUD_AUTH_SESSION_TTL_HOURS",
+            self.auth.session_ttl_hours
+        );
+        env_parse!(
+            "auth.disable_stale_keys_after_days",
+            "GAUD_AUTH_DISABLE_STALE_KEYS_AFTER_DAYS",
+            self.auth.disable_stale_keys_after_days
+        );
         env_bool!(
             "auth.tls_client_cert.enabled",
             "GAUD_AUTH_TLS_ENABLED",
@@ -973,6 +2764,43 @@ impl Config {
             "GAUD_AUTH_TLS_HEADER",
             self.auth.tls_client_cert.header_name
         );
+        env_bool!(
+            "auth.tls_client_cert.admin_only",
+            "GAUD_AUTH_TLS_ADMIN_ONLY",
+            self.auth.tls_client_cert.admin_only
+        );
+        env_bool!(
+            "auth.login_throttle.enabled",
+            "GAUD_AUTH_LOGIN_THROTTLE_ENABLED",
+            self.auth.login_throttle.enabled
+        );
+        env_parse!(
+            "auth.login_throttle.max_failures",
+            "GAUD_AUTH_LOGIN_MAX_FAILURES",
+            self.auth.login_throttle.max_failures
+        );
+        env_parse!(
+            "auth.login_throttle.window_secs",
+            "GAUD_AUTH_LOGIN_WINDOW_SECS",
+            self.auth.login_throttle.window_secs
+        );
+        env_parse!(
+            "auth.login_throttle.lockout_secs",
+            "GAUD_AUTH_LOGIN_LOCKOUT_SECS",
+            self.auth.login_throttle.lockout_secs
+        );
+        env_bool!(
+            "auth.login_throttle.trust_forwarded_for",
+            "GAUD_AUTH_LOGIN_TRUST_FORWARDED_FOR",
+            self.auth.login_throttle.trust_forwarded_for
+        );
+        if let Ok(val) = std::env::var("GAUD_AUTH_LOGIN_TRUSTED_PROXIES") {
+            self.auth.login_throttle.trusted_proxies = parse_list(&val);
+            ov.record(
+                "auth.login_throttle.trusted_proxies",
+                "GAUD_AUTH_LOGIN_TRUSTED_PROXIES",
+            );
+        }
 
         // -- Providers --
         if let Ok(val) = std::env::var("GAUD_KIRO_AUTH_METHOD") {
@@ -1000,6 +2828,22 @@ impl Config {
                 kiro.kiro_db_path = if val.is_empty() { None } else { Some(val) };
                 ov.record("providers.kiro.kiro_db_path", "GAUD_KIRO_DB_PATH");
             }
+            if let Ok(val) = std::env::var("GAUD_KIRO_SSO_START_URL") {
+                kiro.sso_start_url = if val.is_empty() { None } else { Some(val) };
+                ov.record("providers.kiro.sso_start_url", "GAUD_KIRO_SSO_START_URL");
+            }
+            if let Ok(val) = std::env::var("GAUD_KIRO_SSO_REGION") {
+                kiro.sso_region = if val.is_empty() { None } else { Some(val) };
+                ov.record("providers.kiro.sso_region", "GAUD_KIRO_SSO_REGION");
+            }
+        }
+
+        if let (Some(gemini), Ok(val)) = (
+            self.providers.gemini.as_mut(),
+            std::env::var("GAUD_GEMINI_PROJECT_ID"),
+        ) {
+            gemini.project_id = if val.is_empty() { None } else { Some(val) };
+            ov.record("providers.gemini.project_id", "GAUD_GEMINI_PROJECT_ID");
         }
 
         if let Ok(val) = std::env::var("GAUD_PROVIDERS_ROUTING") {
@@ -1008,11 +2852,76 @@ impl Config {
                 ov.record("providers.routing_strategy", "GAUD_PROVIDERS_ROUTING");
             }
         }
+        if let Some(selection) = std::env::var("GAUD_PROVIDERS_ACCOUNT_SELECTION")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.providers.account_selection = selection;
+            ov.record(
+                "providers.account_selection",
+                "GAUD_PROVIDERS_ACCOUNT_SELECTION",
+            );
+        }
         env_path!(
             "providers.token_storage_dir",
             "GAUD_PROVIDERS_TOKEN_DIR",
             self.providers.token_storage_dir
         );
+        if let Some(secs) = std::env::var("GAUD_PROVIDERS_TOKEN_REFRESH_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.providers.token_refresh_interval_secs = Some(secs);
+            ov.record(
+                "providers.token_refresh_interval_secs",
+                "GAUD_PROVIDERS_TOKEN_REFRESH_INTERVAL_SECS",
+            );
+        }
+        env_bool!(
+            "providers.warmup",
+            "GAUD_PROVIDERS_WARMUP",
+            self.providers.warmup
+        );
+        env_bool!(
+            "providers.tool_emulation",
+            "GAUD_PROVIDERS_TOOL_EMULATION",
+            self.providers.tool_emulation
+        );
+        env_bool!(
+            "providers.dry_run",
+            "GAUD_PROVIDERS_DRY_RUN",
+            self.providers.dry_run
+        );
+        env_parse!(
+            "providers.default_max_tokens",
+            "GAUD_PROVIDERS_DEFAULT_MAX_TOKENS",
+            self.providers.default_max_tokens
+        );
+        env_parse!(
+            "providers.retry.max_retries",
+            "GAUD_PROVIDERS_RETRY_MAX_RETRIES",
+            self.providers.retry.max_retries
+        );
+        env_parse!(
+            "providers.retry.initial_backoff_ms",
+            "GAUD_PROVIDERS_RETRY_INITIAL_BACKOFF_MS",
+            self.providers.retry.initial_backoff_ms
+        );
+        env_parse!(
+            "providers.retry.max_backoff_ms",
+            "GAUD_PROVIDERS_RETRY_MAX_BACKOFF_MS",
+            self.providers.retry.max_backoff_ms
+        );
+        if let Some(secs) = std::env::var("GAUD_PROVIDERS_WARMUP_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.providers.warmup_timeout_secs = Some(secs);
+            ov.record(
+                "providers.warmup_timeout_secs",
+                "GAUD_PROVIDERS_WARMUP_TIMEOUT_SECS",
+            );
+        }
         if let Ok(val) = std::env::var("GAUD_PROVIDERS_STORAGE_BACKEND") {
             if let Ok(backend) = val.parse() {
                 self.providers.storage_backend = backend;
@@ -1022,6 +2931,21 @@ impl Config {
                 );
             }
         }
+        env_opt_str!(
+            "providers.token_encryption_key",
+            "GAUD_PROVIDERS_TOKEN_ENCRYPTION_KEY",
+            self.providers.token_encryption_key
+        );
+        env_opt_str!(
+            "providers.proxy",
+            "GAUD_PROVIDERS_PROXY",
+            self.providers.proxy
+        );
+        env_opt_str!(
+            "providers.no_proxy",
+            "GAUD_PROVIDERS_NO_PROXY",
+            self.providers.no_proxy
+        );
 
         // -- Budget --
         env_bool!("budget.enabled", "GAUD_BUDGET_ENABLED", self.budget.enabled);
@@ -1031,6 +2955,110 @@ impl Config {
             self.budget.warning_threshold_percent
         );
 
+        // -- Limits --
+        env_parse!(
+            "limits.max_streams_per_user",
+            "GAUD_LIMITS_MAX_STREAMS_PER_USER",
+            self.limits.max_streams_per_user
+        );
+        env_parse!(
+            "limits.max_streams_per_key",
+            "GAUD_LIMITS_MAX_STREAMS_PER_KEY",
+            self.limits.max_streams_per_key
+        );
+        env_parse!(
+            "limits.max_in_flight",
+            "GAUD_LIMITS_MAX_IN_FLIGHT",
+            self.limits.max_in_flight
+        );
+        env_parse!(
+            "limits.queue_timeout_secs",
+            "GAUD_LIMITS_QUEUE_TIMEOUT_SECS",
+            self.limits.queue_timeout_secs
+        );
+        env_parse!(
+            "limits.max_body_mb",
+            "GAUD_LIMITS_MAX_BODY_MB",
+            self.limits.max_body_mb
+        );
+        env_parse!(
+            "limits.max_messages",
+            "GAUD_LIMITS_MAX_MESSAGES",
+            self.limits.max_messages
+        );
+        env_parse!(
+            "limits.max_image_mb",
+            "GAUD_LIMITS_MAX_IMAGE_MB",
+            self.limits.max_image_mb
+        );
+
+        // -- Files --
+        env_opt_str!(
+            "files.storage_dir",
+            "GAUD_FILES_STORAGE_DIR",
+            self.files.storage_dir
+        );
+        env_parse!(
+            "files.max_upload_mb",
+            "GAUD_FILES_MAX_UPLOAD_MB",
+            self.files.max_upload_mb
+        );
+        env_parse!(
+            "files.image_ttl_secs",
+            "GAUD_FILES_IMAGE_TTL_SECS",
+            self.files.image_ttl_secs
+        );
+
+        // -- Pricing --
+        env_opt_str!("pricing.file", "GAUD_PRICING_FILE", self.pricing.file);
+        env_opt_str!("pricing.url", "GAUD_PRICING_URL", self.pricing.url);
+        env_parse!(
+            "pricing.refresh_interval_secs",
+            "GAUD_PRICING_REFRESH_INTERVAL",
+            self.pricing.refresh_interval_secs
+        );
+        env_str!(
+            "pricing.currency",
+            "GAUD_PRICING_CURRENCY",
+            self.pricing.currency
+        );
+        if let Some(rate) = std::env::var("GAUD_PRICING_EXCHANGE_RATE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.pricing.exchange_rate = Some(rate);
+            ov.record("pricing.exchange_rate", "GAUD_PRICING_EXCHANGE_RATE");
+        }
+        env_opt_str!(
+            "pricing.exchange_rate_url",
+            "GAUD_PRICING_EXCHANGE_RATE_URL",
+            self.pricing.exchange_rate_url
+        );
+
+        // -- Reports --
+        env_opt_str!(
+            "reports.chargeback_dir",
+            "GAUD_REPORTS_CHARGEBACK_DIR",
+            self.reports.chargeback_dir
+        );
+
+        // -- Notifications --
+        env_opt_str!(
+            "notifications.slack_webhook_url",
+            "GAUD_NOTIFICATIONS_SLACK_WEBHOOK_URL",
+            self.notifications.slack_webhook_url
+        );
+        env_opt_str!(
+            "notifications.discord_webhook_url",
+            "GAUD_NOTIFICATIONS_DISCORD_WEBHOOK_URL",
+            self.notifications.discord_webhook_url
+        );
+        env_parse!(
+            "notifications.cooldown_secs",
+            "GAUD_NOTIFICATIONS_COOLDOWN_SECS",
+            self.notifications.cooldown_secs
+        );
+
         // -- Logging --
         env_str!("logging.level", "GAUD_LOG_LEVEL", self.logging.level);
         env_bool!("logging.json", "GAUD_LOG_JSON", self.logging.json);
@@ -1040,6 +3068,85 @@ impl Config {
             self.logging.log_content
         );
 
+        // -- Observability --
+        env_parse!(
+            "observability.trace_sample_ratio",
+            "GAUD_OBSERVABILITY_TRACE_SAMPLE_RATIO",
+            self.observability.trace_sample_ratio
+        );
+        env_bool!(
+            "observability.log_headers",
+            "GAUD_OBSERVABILITY_LOG_HEADERS",
+            self.observability.log_headers
+        );
+        env_bool!(
+            "observability.log_prompts",
+            "GAUD_OBSERVABILITY_LOG_PROMPTS",
+            self.observability.log_prompts
+        );
+        env_bool!(
+            "observability.redact",
+            "GAUD_OBSERVABILITY_REDACT",
+            self.observability.redact
+        );
+        env_parse!(
+            "observability.failure_buffer",
+            "GAUD_OBSERVABILITY_FAILURE_BUFFER",
+            self.observability.failure_buffer
+        );
+
+        // -- MCP --
+        env_bool!("mcp.enabled", "GAUD_MCP_ENABLED", self.mcp.enabled);
+        env_parse!(
+            "mcp.max_tool_rounds",
+            "GAUD_MCP_MAX_TOOL_ROUNDS",
+            self.mcp.max_tool_rounds
+        );
+
+        // -- Agents --
+        env_parse!(
+            "agents.max_steps",
+            "GAUD_AGENTS_MAX_STEPS",
+            self.agents.max_steps
+        );
+        env_parse!(
+            "agents.callback_timeout_secs",
+            "GAUD_AGENTS_CALLBACK_TIMEOUT",
+            self.agents.callback_timeout_secs
+        );
+        env_bool!(
+            "agents.allow_private_callbacks",
+            "GAUD_AGENTS_ALLOW_PRIVATE_CALLBACKS",
+            self.agents.allow_private_callbacks
+        );
+
+        // -- Conversations --
+        env_bool!(
+            "conversations.enabled",
+            "GAUD_CONVERSATIONS_ENABLED",
+            self.conversations.enabled
+        );
+        env_parse!(
+            "conversations.max_messages",
+            "GAUD_CONVERSATIONS_MAX_MESSAGES",
+            self.conversations.max_messages
+        );
+        env_parse!(
+            "conversations.max_history_tokens",
+            "GAUD_CONVERSATIONS_MAX_HISTORY_TOKENS",
+            self.conversations.max_history_tokens
+        );
+        env_parse!(
+            "conversations.compaction",
+            "GAUD_CONVERSATIONS_COMPACTION",
+            self.conversations.compaction
+        );
+        env_opt_str!(
+            "conversations.summary_model",
+            "GAUD_CONVERSATIONS_SUMMARY_MODEL",
+            self.conversations.summary_model
+        );
+
         // -- Cache --
         env_bool!("cache.enabled", "GAUD_CACHE_ENABLED", self.cache.enabled);
         if let Ok(val) = std::env::var("GAUD_CACHE_MODE") {
@@ -1080,6 +3187,11 @@ impl Config {
             self.cache.max_entries
         );
         env_path!("cache.path", "GAUD_CACHE_PATH", self.cache.path);
+        env_opt_str!(
+            "cache.backend_url",
+            "GAUD_CACHE_BACKEND_URL",
+            self.cache.backend_url
+        );
         env_bool!(
             "cache.skip_tool_requests",
             "GAUD_CACHE_SKIP_TOOLS",
@@ -1099,6 +3211,12 @@ impl Config {
                 discover_models: true,
                 models: Vec::new(),
                 timeout_secs: default_litellm_timeout(),
+                reconcile_interval_secs: 0,
+                reconcile_tolerance_percent: default_litellm_reconcile_tolerance(),
+                proxy: None,
+                http: HttpClientConfig::default(),
+                quota: ProviderQuotaConfig::default(),
+                dry_run: false,
             });
             litellm.url = url;
             ov.record("providers.litellm.url", "GAUD_LITELLM_URL");
@@ -1124,6 +3242,17 @@ impl Config {
                 }
             }
         }
+        if let Ok(val) = std::env::var("GAUD_LITELLM_RECONCILE_INTERVAL") {
+            if let Some(ref mut litellm) = self.providers.litellm {
+                if let Ok(secs) = val.parse() {
+                    litellm.reconcile_interval_secs = secs;
+                    ov.record(
+                        "providers.litellm.reconcile_interval_secs",
+                        "GAUD_LITELLM_RECONCILE_INTERVAL",
+                    );
+                }
+            }
+        }
 
         self.env_overrides = ov;
     }
@@ -1153,6 +3282,8 @@ impl Config {
                 input_type: input_type.to_string(),
                 options: None,
                 sensitive: false,
+                runtime: RUNTIME_SETTINGS.contains(&key),
+                stored: false,
             }
         };
 
@@ -1182,6 +3313,76 @@ impl Config {
                 "GAUD_SERVER_CORS_ORIGINS",
                 "text",
             ),
+            se(
+                "server.cors.proxy",
+                "Server",
+                "CORS Origins for /v1",
+                serde_json::json!(format_group_origins(self.server.cors.proxy.as_ref())),
+                "GAUD_SERVER_CORS_PROXY",
+                "text",
+            ),
+            se(
+                "server.cors.admin",
+                "Server",
+                "CORS Origins for /admin",
+                serde_json::json!(format_group_origins(self.server.cors.admin.as_ref())),
+                "GAUD_SERVER_CORS_ADMIN",
+                "text",
+            ),
+            se(
+                "server.cors.ui",
+                "Server",
+                "CORS Origins for /ui",
+                serde_json::json!(format_group_origins(self.server.cors.ui.as_ref())),
+                "GAUD_SERVER_CORS_UI",
+                "text",
+            ),
+            se(
+                "server.tls_cert_path",
+                "Server",
+                "TLS Certificate Path",
+                serde_json::json!(self.server.tls_cert_path.as_deref().unwrap_or("")),
+                "GAUD_SERVER_TLS_CERT",
+                "text",
+            ),
+            se(
+                "server.tls_key_path",
+                "Server",
+                "TLS Private Key Path",
+                serde_json::json!(self.server.tls_key_path.as_deref().unwrap_or("")),
+                "GAUD_SERVER_TLS_KEY",
+                "text",
+            ),
+            se(
+                "server.admin.host",
+                "Server",
+                "Admin Bind Address",
+                serde_json::json!(
+                    self.server
+                        .admin
+                        .as_ref()
+                        .and_then(|a| a.host.as_deref())
+                        .unwrap_or("")
+                ),
+                "GAUD_SERVER_ADMIN_HOST",
+                "text",
+            ),
+            se(
+                "server.admin.port",
+                "Server",
+                "Admin Port",
+                serde_json::json!(self.server.admin.as_ref().map(|a| a.port)),
+                "GAUD_SERVER_ADMIN_PORT",
+                "number",
+            ),
+            se(
+                "server.shutdown_drain_secs",
+                "Server",
+                "Shutdown Drain Timeout (s)",
+                serde_json::json!(self.server.shutdown_drain_secs),
+                "GAUD_SERVER_SHUTDOWN_DRAIN_SECS",
+                "number",
+            ),
             // -- Database --
             se(
                 "database.path",
@@ -1191,6 +3392,22 @@ impl Config {
                 "GAUD_DATABASE_PATH",
                 "text",
             ),
+            se(
+                "database.leader_election",
+                "Database",
+                "Leader Election",
+                serde_json::json!(self.database.leader_election),
+                "GAUD_DATABASE_LEADER_ELECTION",
+                "bool",
+            ),
+            se(
+                "database.leader_lease_secs",
+                "Database",
+                "Leader Lease (s)",
+                serde_json::json!(self.database.leader_lease_secs),
+                "GAUD_DATABASE_LEADER_LEASE_SECS",
+                "number",
+            ),
             // -- Auth --
             se(
                 "auth.enabled",
@@ -1208,6 +3425,30 @@ impl Config {
                 "GAUD_AUTH_ADMIN_NAME",
                 "text",
             ),
+            se(
+                "auth.self_service_max_keys",
+                "Authentication",
+                "Self-Service Key Limit",
+                serde_json::json!(self.auth.self_service_max_keys),
+                "GAUD_AUTH_SELF_SERVICE_MAX_KEYS",
+                "number",
+            ),
+            se(
+                "auth.session_ttl_hours",
+                "Authentication",
+                "Web Session Lifetime (h)",
+                serde_json::json!(self.auth.session_ttl_hours),
+                "GAUD_AUTH_SESSION_TTL_HOURS",
+                "number",
+            ),
+            se(
+                "auth.disable_stale_keys_after_days",
+                "Authentication",
+                "Disable Unused Keys After (days)",
+                serde_json::json!(self.auth.disable_stale_keys_after_days),
+                "GAUD_AUTH_DISABLE_STALE_KEYS_AFTER_DAYS",
+                "number",
+            ),
             se(
                 "auth.tls_client_cert.enabled",
                 "Authentication",
@@ -1246,6 +3487,62 @@ impl Config {
                 "GAUD_AUTH_TLS_HEADER",
                 "text",
             ),
+            se(
+                "auth.tls_client_cert.admin_only",
+                "Authentication",
+                "Client Certs on Admin Listener Only",
+                serde_json::json!(self.auth.tls_client_cert.admin_only),
+                "GAUD_AUTH_TLS_ADMIN_ONLY",
+                "bool",
+            ),
+            se(
+                "auth.login_throttle.enabled",
+                "Authentication",
+                "Login Lockouts",
+                serde_json::json!(self.auth.login_throttle.enabled),
+                "GAUD_AUTH_LOGIN_THROTTLE_ENABLED",
+                "bool",
+            ),
+            se(
+                "auth.login_throttle.max_failures",
+                "Authentication",
+                "Failed Logins Before Lockout",
+                serde_json::json!(self.auth.login_throttle.max_failures),
+                "GAUD_AUTH_LOGIN_MAX_FAILURES",
+                "number",
+            ),
+            se(
+                "auth.login_throttle.window_secs",
+                "Authentication",
+                "Failed Login Window (s)",
+                serde_json::json!(self.auth.login_throttle.window_secs),
+                "GAUD_AUTH_LOGIN_WINDOW_SECS",
+                "number",
+            ),
+            se(
+                "auth.login_throttle.lockout_secs",
+                "Authentication",
+                "Lockout Duration (s)",
+                serde_json::json!(self.auth.login_throttle.lockout_secs),
+                "GAUD_AUTH_LOGIN_LOCKOUT_SECS",
+                "number",
+            ),
+            se(
+                "auth.login_throttle.trust_forwarded_for",
+                "Authentication",
+                "Trust X-Forwarded-For",
+                serde_json::json!(self.auth.login_throttle.trust_forwarded_for),
+                "GAUD_AUTH_LOGIN_TRUST_FORWARDED_FOR",
+                "bool",
+            ),
+            se(
+                "auth.login_throttle.trusted_proxies",
+                "Authentication",
+                "Trusted Proxies",
+                serde_json::json!(self.auth.login_throttle.trusted_proxies.join(", ")),
+                "GAUD_AUTH_LOGIN_TRUSTED_PROXIES",
+                "text",
+            ),
             // -- Providers --
             {
                 let mut e = se(
@@ -1264,6 +3561,21 @@ impl Config {
                 ]);
                 e
             },
+            {
+                let mut e = se(
+                    "providers.account_selection",
+                    "Providers",
+                    "OAuth Account Selection",
+                    serde_json::json!(self.providers.account_selection.to_string()),
+                    "GAUD_PROVIDERS_ACCOUNT_SELECTION",
+                    "select",
+                );
+                e.options = Some(vec![
+                    "round_robin".to_string(),
+                    "least_recently_used".to_string(),
+                ]);
+                e
+            },
             se(
                 "providers.token_storage_dir",
                 "Providers",
@@ -1272,6 +3584,82 @@ impl Config {
                 "GAUD_PROVIDERS_TOKEN_DIR",
                 "text",
             ),
+            se(
+                "providers.token_refresh_interval_secs",
+                "Providers",
+                "Token Refresh Interval (s)",
+                serde_json::json!(
+                    self.providers
+                        .token_refresh_interval_secs
+                        .unwrap_or(DEFAULT_TOKEN_REFRESH_INTERVAL_SECS)
+                ),
+                "GAUD_PROVIDERS_TOKEN_REFRESH_INTERVAL_SECS",
+                "number",
+            ),
+            se(
+                "providers.warmup",
+                "Providers",
+                "Startup Warm-up",
+                serde_json::json!(self.providers.warmup),
+                "GAUD_PROVIDERS_WARMUP",
+                "bool",
+            ),
+            se(
+                "providers.tool_emulation",
+                "Providers",
+                "Tool Calling Emulation",
+                serde_json::json!(self.providers.tool_emulation),
+                "GAUD_PROVIDERS_TOOL_EMULATION",
+                "bool",
+            ),
+            se(
+                "providers.dry_run",
+                "Providers",
+                "Dry Run (no upstream calls)",
+                serde_json::json!(self.providers.dry_run),
+                "GAUD_PROVIDERS_DRY_RUN",
+                "bool",
+            ),
+            se(
+                "providers.default_max_tokens",
+                "Providers",
+                "Default max_tokens (0 = provider default)",
+                serde_json::json!(self.providers.default_max_tokens),
+                "GAUD_PROVIDERS_DEFAULT_MAX_TOKENS",
+                "number",
+            ),
+            se(
+                "providers.retry.max_retries",
+                "Providers",
+                "Retries per Provider",
+                serde_json::json!(self.providers.retry.max_retries),
+                "GAUD_PROVIDERS_RETRY_MAX_RETRIES",
+                "number",
+            ),
+            se(
+                "providers.retry.initial_backoff_ms",
+                "Providers",
+                "Initial Retry Backoff (ms)",
+                serde_json::json!(self.providers.retry.initial_backoff_ms),
+                "GAUD_PROVIDERS_RETRY_INITIAL_BACKOFF_MS",
+                "number",
+            ),
+            se(
+                "providers.retry.max_backoff_ms",
+                "Providers",
+                "Max Retry Backoff (ms)",
+                serde_json::json!(self.providers.retry.max_backoff_ms),
+                "GAUD_PROVIDERS_RETRY_MAX_BACKOFF_MS",
+                "number",
+            ),
+            se(
+                "providers.warmup_timeout_secs",
+                "Providers",
+                "Warm-up Timeout (s)",
+                serde_json::json!(self.providers.warmup_timeout().as_secs()),
+                "GAUD_PROVIDERS_WARMUP_TIMEOUT_SECS",
+                "number",
+            ),
             {
                 let mut e = se(
                     "providers.storage_backend",
@@ -1285,9 +3673,26 @@ impl Config {
                     "file".to_string(),
                     "keyring".to_string(),
                     "memory".to_string(),
+                    "database".to_string(),
                 ]);
                 e
             },
+            se(
+                "providers.proxy",
+                "Providers",
+                "Outbound Proxy",
+                serde_json::json!(self.providers.proxy.as_deref().unwrap_or("")),
+                "GAUD_PROVIDERS_PROXY",
+                "text",
+            ),
+            se(
+                "providers.no_proxy",
+                "Providers",
+                "Proxy Bypass Hosts",
+                serde_json::json!(self.providers.no_proxy.as_deref().unwrap_or("")),
+                "GAUD_PROVIDERS_NO_PROXY",
+                "text",
+            ),
             // -- LiteLLM --
             se(
                 "providers.litellm.url",
@@ -1331,6 +3736,20 @@ impl Config {
                 "GAUD_LITELLM_TIMEOUT",
                 "number",
             ),
+            se(
+                "providers.litellm.reconcile_interval_secs",
+                "LiteLLM",
+                "Spend Reconciliation Interval (seconds, 0 = off)",
+                serde_json::json!(
+                    self.providers
+                        .litellm
+                        .as_ref()
+                        .map(|l| l.reconcile_interval_secs)
+                        .unwrap_or(0)
+                ),
+                "GAUD_LITELLM_RECONCILE_INTERVAL",
+                "number",
+            ),
             // -- Budget --
             se(
                 "budget.enabled",
@@ -1348,6 +3767,155 @@ impl Config {
                 "GAUD_BUDGET_WARNING_THRESHOLD",
                 "number",
             ),
+            // -- Limits --
+            se(
+                "limits.max_streams_per_user",
+                "Limits",
+                "Open Streams per User (0 = unlimited)",
+                serde_json::json!(self.limits.max_streams_per_user),
+                "GAUD_LIMITS_MAX_STREAMS_PER_USER",
+                "number",
+            ),
+            se(
+                "limits.max_streams_per_key",
+                "Limits",
+                "Open Streams per API Key (0 = unlimited)",
+                serde_json::json!(self.limits.max_streams_per_key),
+                "GAUD_LIMITS_MAX_STREAMS_PER_KEY",
+                "number",
+            ),
+            se(
+                "limits.max_in_flight",
+                "Limits",
+                "Upstream Requests in Flight (0 = unlimited)",
+                serde_json::json!(self.limits.max_in_flight),
+                "GAUD_LIMITS_MAX_IN_FLIGHT",
+                "number",
+            ),
+            se(
+                "limits.queue_timeout_secs",
+                "Limits",
+                "Queue Timeout (seconds)",
+                serde_json::json!(self.limits.queue_timeout_secs),
+                "GAUD_LIMITS_QUEUE_TIMEOUT_SECS",
+                "number",
+            ),
+            se(
+                "limits.max_body_mb",
+                "Limits",
+                "Max Request Body (MiB)",
+                serde_json::json!(self.limits.max_body_mb),
+                "GAUD_LIMITS_MAX_BODY_MB",
+                "number",
+            ),
+            se(
+                "limits.max_messages",
+                "Limits",
+                "Max Messages per Request (0 = unlimited)",
+                serde_json::json!(self.limits.max_messages),
+                "GAUD_LIMITS_MAX_MESSAGES",
+                "number",
+            ),
+            se(
+                "limits.max_image_mb",
+                "Limits",
+                "Max Inline Image (MiB, 0 = unlimited)",
+                serde_json::json!(self.limits.max_image_mb),
+                "GAUD_LIMITS_MAX_IMAGE_MB",
+                "number",
+            ),
+            // -- Files --
+            se(
+                "files.storage_dir",
+                "Files",
+                "Storage Directory",
+                serde_json::json!(self.files.storage_dir.as_deref().unwrap_or("")),
+                "GAUD_FILES_STORAGE_DIR",
+                "text",
+            ),
+            se(
+                "files.max_upload_mb",
+                "Files",
+                "Max Upload Size (MB)",
+                serde_json::json!(self.files.max_upload_mb),
+                "GAUD_FILES_MAX_UPLOAD_MB",
+                "number",
+            ),
+            se(
+                "files.image_ttl_secs",
+                "Files",
+                "Uploaded Image Lifetime (seconds)",
+                serde_json::json!(self.files.image_ttl_secs),
+                "GAUD_FILES_IMAGE_TTL_SECS",
+                "number",
+            ),
+            // -- Pricing --
+            se(
+                "pricing.file",
+                "Pricing",
+                "Price List File",
+                serde_json::json!(self.pricing.file.as_deref().unwrap_or("")),
+                "GAUD_PRICING_FILE",
+                "text",
+            ),
+            se(
+                "pricing.url",
+                "Pricing",
+                "Price List URL",
+                serde_json::json!(self.pricing.url.as_deref().unwrap_or("")),
+                "GAUD_PRICING_URL",
+                "text",
+            ),
+            se(
+                "pricing.refresh_interval_secs",
+                "Pricing",
+                "Refresh Interval (seconds, 0 = startup only)",
+                serde_json::json!(self.pricing.refresh_interval_secs),
+                "GAUD_PRICING_REFRESH_INTERVAL",
+                "number",
+            ),
+            se(
+                "pricing.currency",
+                "Pricing",
+                "Currency",
+                serde_json::json!(self.pricing.currency),
+                "GAUD_PRICING_CURRENCY",
+                "text",
+            ),
+            se(
+                "pricing.exchange_rate",
+                "Pricing",
+                "Exchange Rate (per USD)",
+                serde_json::json!(self.pricing.exchange_rate),
+                "GAUD_PRICING_EXCHANGE_RATE",
+                "number",
+            ),
+            se(
+                "pricing.exchange_rate_url",
+                "Pricing",
+                "Exchange Rate URL",
+                serde_json::json!(self.pricing.exchange_rate_url.as_deref().unwrap_or("")),
+                "GAUD_PRICING_EXCHANGE_RATE_URL",
+                "text",
+            ),
+            // -- Reports --
+            se(
+                "reports.chargeback_dir",
+                "Reports",
+                "Chargeback Report Directory",
+                serde_json::json!(self.reports.chargeback_dir.as_deref().unwrap_or("")),
+                "GAUD_REPORTS_CHARGEBACK_DIR",
+                "text",
+            ),
+            // -- Notifications --
+            se(
+                "notifications.cooldown_secs",
+                "Notifications",
+                "Repeat Cooldown (s)",
+                serde_json::json!(self.notifications.cooldown_secs),
+                "GAUD_NOTIFICATIONS_COOLDOWN_SECS",
+                "number",
+            ),
             // -- Logging --
             {
                 let mut e = se(
@@ -1383,6 +3951,149 @@ impl Config {
                 "GAUD_LOG_CONTENT",
                 "bool",
             ),
+            // -- Observability --
+            se(
+                "observability.trace_sample_ratio",
+                "Observability",
+                "Trace Sample Ratio",
+                serde_json::json!(self.observability.trace_sample_ratio),
+                "GAUD_OBSERVABILITY_TRACE_SAMPLE_RATIO",
+                "number",
+            ),
+            se(
+                "observability.log_headers",
+                "Observability",
+                "Log Request Headers",
+                serde_json::json!(self.observability.log_headers),
+                "GAUD_OBSERVABILITY_LOG_HEADERS",
+                "bool",
+            ),
+            se(
+                "observability.log_prompts",
+                "Observability",
+                "Log Prompts",
+                serde_json::json!(self.observability.log_prompts),
+                "GAUD_OBSERVABILITY_LOG_PROMPTS",
+                "bool",
+            ),
+            se(
+                "observability.redact",
+                "Observability",
+                "Redact Secrets in Logs",
+                serde_json::json!(self.observability.redact),
+                "GAUD_OBSERVABILITY_REDACT",
+                "bool",
+            ),
+            se(
+                "observability.failure_buffer",
+                "Observability",
+                "Failed Requests Kept",
+                serde_json::json!(self.observability.failure_buffer),
+                "GAUD_OBSERVABILITY_FAILURE_BUFFER",
+                "number",
+            ),
+            // -- MCP --
+            se(
+                "mcp.enabled",
+                "MCP",
+                "MCP Server Enabled",
+                serde_json::json!(self.mcp.enabled),
+                "GAUD_MCP_ENABLED",
+                "bool",
+            ),
+            se(
+                "mcp.max_tool_rounds",
+                "MCP",
+                "Max Tool Rounds",
+                serde_json::json!(self.mcp.max_tool_rounds),
+                "GAUD_MCP_MAX_TOOL_ROUNDS",
+                "number",
+            ),
+            // -- Agents --
+            se(
+                "agents.max_steps",
+                "Agents",
+                "Max Steps per Run",
+                serde_json::json!(self.agents.max_steps),
+                "GAUD_AGENTS_MAX_STEPS",
+                "number",
+            ),
+            se(
+                "agents.callback_timeout_secs",
+                "Agents",
+                "Callback Timeout (secs)",
+                serde_json::json!(self.agents.callback_timeout_secs),
+                "GAUD_AGENTS_CALLBACK_TIMEOUT",
+                "number",
+            ),
+            se(
+                "agents.allow_private_callbacks",
+                "Agents",
+                "Allow Private Callback URLs",
+                serde_json::json!(self.agents.allow_private_callbacks),
+                "GAUD_AGENTS_ALLOW_PRIVATE_CALLBACKS",
+                "bool",
+            ),
+            // -- Conversations --
+            se(
+                "conversations.enabled",
+                "Conversations",
+                "Enable Conversation Storage",
+                serde_json::json!(self.conversations.enabled),
+                "GAUD_CONVERSATIONS_ENABLED",
+                "bool",
+            ),
+            se(
+                "conversations.max_messages",
+                "Conversations",
+                "Max Messages Kept",
+                serde_json::json!(self.conversations.max_messages),
+                "GAUD_CONVERSATIONS_MAX_MESSAGES",
+                "number",
+            ),
+            se(
+                "conversations.max_history_tokens",
+                "Conversations",
+                "Max History Tokens (0 = no limit)",
+                serde_json::json!(self.conversations.max_history_tokens),
+                "GAUD_CONVERSATIONS_MAX_HISTORY_TOKENS",
+                "number",
+            ),
+            {
+                let mut e = se(
+                    "conversations.compaction",
+                    "Conversations",
+                    "Compaction",
+                    serde_json::json!(self.conversations.compaction.to_string()),
+                    "GAUD_CONVERSATIONS_COMPACTION",
+                    "select",
+                );
+                e.options = Some(vec!["truncate".to_string(), "summarize".to_string()]);
+                e
+            },
+            se(
+                "conversations.summary_model",
+                "Conversations",
+                "Summary Model",
+                serde_json::json!(self.conversations.summary_model.as_deref().unwrap_or("")),
+                "GAUD_CONVERSATIONS_SUMMARY_MODEL",
+                "text",
+            ),
+            // -- Gemini --
+            se(
+                "providers.gemini.project_id",
+                "Gemini",
+                "Project ID",
+                serde_json::json!(
+                    self.providers
+                        .gemini
+                        .as_ref()
+                        .and_then(|g| g.project_id.as_deref())
+                        .unwrap_or("")
+                ),
+                "GAUD_GEMINI_PROJECT_ID",
+                "text",
+            ),
             // -- Kiro --
             {
                 let mut e = se(
@@ -1463,6 +4174,34 @@ impl Config {
                 "GAUD_KIRO_DB_PATH",
                 "text",
             ),
+            se(
+                "providers.kiro.sso_start_url",
+                "Kiro",
+                "SSO Start URL",
+                serde_json::json!(
+                    self.providers
+                        .kiro
+                        .as_ref()
+                        .and_then(|k| k.sso_start_url.as_deref())
+                        .unwrap_or("")
+                ),
+                "GAUD_KIRO_SSO_START_URL",
+                "text",
+            ),
+            se(
+                "providers.kiro.sso_region",
+                "Kiro",
+                "SSO Region",
+                serde_json::json!(
+                    self.providers
+                        .kiro
+                        .as_ref()
+                        .and_then(|k| k.sso_region.as_deref())
+                        .unwrap_or("")
+                ),
+                "GAUD_KIRO_SSO_REGION",
+                "text",
+            ),
         ];
 
         // -- Cache --
@@ -1498,6 +4237,14 @@ impl Config {
             "GAUD_CACHE_PATH",
             "text",
         ));
+        entries.push(se(
+            "cache.backend_url",
+            "Cache",
+            "Shared Cache Backend URL",
+            serde_json::json!(self.cache.backend_url.as_deref().unwrap_or("")),
+            "GAUD_CACHE_BACKEND_URL",
+            "text",
+        ));
         entries.push(se(
             "cache.similarity_threshold",
             "Cache",
@@ -1583,6 +4330,33 @@ impl Config {
             entries.push(ek);
         }
 
+        // Webhook URLs carry their own credentials.
+        for (key, label, env, value) in [
+            (
+                "notifications.slack_webhook_url",
+                "Slack Webhook URL",
+                "GAUD_NOTIFICATIONS_SLACK_WEBHOOK_URL",
+                &self.notifications.slack_webhook_url,
+            ),
+            (
+                "notifications.discord_webhook_url",
+                "Discord Webhook URL",
+                "GAUD_NOTIFICATIONS_DISCORD_WEBHOOK_URL",
+                &self.notifications.discord_webhook_url,
+            ),
+        ] {
+            let mut e = se(
+                key,
+                "Notifications",
+                label,
+                serde_json::json!(value.as_deref().map(|_| "********").unwrap_or("")),
+                env,
+                "text",
+            );
+            e.sensitive = true;
+            entries.push(e);
+        }
+
         // Mark litellm api_key as sensitive.
         let mut lk = se(
             "providers.litellm.api_key",
@@ -1628,6 +4402,31 @@ impl Config {
         bk.sensitive = true;
         entries.insert(6, bk);
 
+        // Mark the token encryption key as sensitive.
+        let mut tk = se(
+            "providers.token_encryption_key",
+            "Providers",
+            "Token Encryption Key",
+            serde_json::json!(
+                self.providers
+                    .token_encryption_key
+                    .as_deref()
+                    .map(|_| "********")
+                    .unwrap_or("")
+            ),
+            "GAUD_PROVIDERS_TOKEN_ENCRYPTION_KEY",
+            "text",
+        );
+        tk.sensitive = true;
+        if let Some(pos) = entries
+            .iter()
+            .position(|e| e.key == "providers.storage_backend")
+        {
+            entries.insert(pos + 1, tk);
+        } else {
+            entries.push(tk);
+        }
+
         entries
     }
 
@@ -1648,21 +4447,97 @@ impl Config {
             }
             "server.cors_origins" => {
                 let s = value.as_str().ok_or("Expected string")?;
-                self.server.cors_origins = s
-                    .split(',')
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect();
+                self.server.cors_origins = parse_list(s);
+            }
+            "server.cors.proxy" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.server.cors.proxy = parse_group_origins(s);
+            }
+            "server.cors.admin" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.server.cors.admin = parse_group_origins(s);
+            }
+            "server.cors.ui" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.server.cors.ui = parse_group_origins(s);
+            }
+            "server.tls_cert_path" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.server.tls_cert_path = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "server.admin.host" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                let admin = self
+                    .server
+                    .admin
+                    .as_mut()
+                    .ok_or("Set server.admin.port first")?;
+                admin.host = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "server.admin.port" => {
+                if value.is_null() {
+                    self.server.admin = None;
+                } else {
+                    let port = value
+                        .as_u64()
+                        .ok_or("Expected number")?
+                        .try_into()
+                        .map_err(|_| "Port out of range")?;
+                    match self.server.admin {
+                        Some(ref mut admin) => admin.port = port,
+                        None => self.server.admin = Some(AdminListenerConfig { host: None, port }),
+                    }
+                }
+            }
+            "server.tls_key_path" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.server.tls_key_path = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "server.shutdown_drain_secs" => {
+                self.server.shutdown_drain_secs = value.as_u64().ok_or("Expected number")?;
             }
             "database.path" => {
                 self.database.path = PathBuf::from(value.as_str().ok_or("Expected string")?);
             }
+            "database.leader_election" => {
+                self.database.leader_election = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "database.leader_lease_secs" => {
+                self.database.leader_lease_secs = value.as_u64().ok_or("Expected number")?;
+            }
             "auth.enabled" => {
                 self.auth.enabled = value.as_bool().ok_or("Expected boolean")?;
             }
             "auth.default_admin_name" => {
                 self.auth.default_admin_name = value.as_str().ok_or("Expected string")?.to_string();
             }
+            "auth.self_service_max_keys" => {
+                self.auth.self_service_max_keys = value
+                    .as_u64()
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or("Expected number")?;
+            }
+            "auth.session_ttl_hours" => {
+                self.auth.session_ttl_hours = value.as_u64().ok_or("Expected number")?;
+            }
+            "auth.disable_stale_keys_after_days" => {
+                self.auth.disable_stale_keys_after_days = value
+                    .as_u64()
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or("Expected number")?;
+            }
             "auth.tls_client_cert.enabled" => {
                 self.auth.tls_client_cert.enabled = value.as_bool().ok_or("Expected boolean")?;
             }
@@ -1686,36 +4561,323 @@ impl Config {
                     Some(s.to_string())
                 };
             }
+            "auth.tls_client_cert.admin_only" => {
+                self.auth.tls_client_cert.admin_only = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "auth.login_throttle.enabled" => {
+                self.auth.login_throttle.enabled = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "auth.login_throttle.max_failures" => {
+                self.auth.login_throttle.max_failures = value
+                    .as_u64()
+                    .and_then(|v| u32::try_from(v).ok())
+                    .filter(|v| *v > 0)
+                    .ok_or("Expected positive number")?;
+            }
+            "auth.login_throttle.window_secs" => {
+                self.auth.login_throttle.window_secs = value.as_u64().ok_or("Expected number")?;
+            }
+            "auth.login_throttle.lockout_secs" => {
+                self.auth.login_throttle.lockout_secs = value.as_u64().ok_or("Expected number")?;
+            }
+            "auth.login_throttle.trust_forwarded_for" => {
+                self.auth.login_throttle.trust_forwarded_for =
+                    value.as_bool().ok_or("Expected boolean")?;
+            }
+            "auth.login_throttle.trusted_proxies" => {
+                let proxies = parse_list(value.as_str().ok_or("Expected string")?);
+                for proxy in &proxies {
+                    parse_trusted_proxy(proxy)?;
+                }
+                self.auth.login_throttle.trusted_proxies = proxies;
+            }
             "providers.routing_strategy" => {
                 let s = value.as_str().ok_or("Expected string")?;
                 self.providers.routing_strategy = s.parse().map_err(|e: String| e)?;
             }
+            "providers.account_selection" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.providers.account_selection = s.parse().map_err(|e: String| e)?;
+            }
             "providers.token_storage_dir" => {
                 self.providers.token_storage_dir =
                     PathBuf::from(value.as_str().ok_or("Expected string")?);
             }
+            "providers.token_refresh_interval_secs" => {
+                self.providers.token_refresh_interval_secs =
+                    Some(value.as_u64().ok_or("Expected number")?);
+            }
+            "providers.warmup" => {
+                self.providers.warmup = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "providers.tool_emulation" => {
+                self.providers.tool_emulation = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "providers.dry_run" => {
+                self.providers.dry_run = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "providers.default_max_tokens" => {
+                self.providers.default_max_tokens = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "providers.retry.max_retries" => {
+                self.providers.retry.max_retries = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "providers.retry.initial_backoff_ms" => {
+                self.providers.retry.initial_backoff_ms =
+                    value.as_u64().ok_or("Expected number")?;
+            }
+            "providers.retry.max_backoff_ms" => {
+                self.providers.retry.max_backoff_ms = value.as_u64().ok_or("Expected number")?;
+            }
+            "providers.warmup_timeout_secs" => {
+                self.providers.warmup_timeout_secs = Some(value.as_u64().ok_or("Expected number")?);
+            }
             "providers.storage_backend" => {
                 let s = value.as_str().ok_or("Expected string")?;
                 self.providers.storage_backend = s.parse().map_err(|e: String| e)?;
             }
-            "budget.enabled" => {
-                self.budget.enabled = value.as_bool().ok_or("Expected boolean")?;
+            "providers.proxy" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.providers.proxy = if s.is_empty() {
+                    None
+                } else {
+                    let proxy = OutboundProxy::Url {
+                        url: s.to_string(),
+                        no_proxy: None,
+                    };
+                    proxy.validate()?;
+                    Some(s.to_string())
+                };
+            }
+            "providers.no_proxy" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.providers.no_proxy = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "budget.enabled" => {
+                self.budget.enabled = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "budget.warning_threshold_percent" => {
+                self.budget.warning_threshold_percent = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "limits.max_streams_per_user" => {
+                self.limits.max_streams_per_user = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "limits.max_streams_per_key" => {
+                self.limits.max_streams_per_key = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "limits.max_in_flight" => {
+                self.limits.max_in_flight = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "limits.queue_timeout_secs" => {
+                self.limits.queue_timeout_secs = value.as_u64().ok_or("Expected number")?;
+            }
+            "limits.max_body_mb" => {
+                self.limits.max_body_mb = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "limits.max_messages" => {
+                self.limits.max_messages = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "limits.max_image_mb" => {
+                self.limits.max_image_mb = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "files.storage_dir" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.files.storage_dir = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "files.max_upload_mb" => {
+                self.files.max_upload_mb = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "files.image_ttl_secs" => {
+                self.files.image_ttl_secs = value.as_u64().ok_or("Expected number")?;
+            }
+            "pricing.file" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.pricing.file = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "pricing.url" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.pricing.url = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "pricing.refresh_interval_secs" => {
+                self.pricing.refresh_interval_secs = value.as_u64().ok_or("Expected number")?;
+            }
+            "pricing.currency" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.pricing.currency = s.to_ascii_uppercase();
+            }
+            "pricing.exchange_rate" => {
+                self.pricing.exchange_rate = if value.is_null() {
+                    None
+                } else {
+                    Some(value.as_f64().ok_or("Expected number")?)
+                };
+            }
+            "pricing.exchange_rate_url" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.pricing.exchange_rate_url = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "reports.chargeback_dir" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.reports.chargeback_dir = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
+            "notifications.slack_webhook_url" | "notifications.discord_webhook_url" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                if s != "********" {
+                    let url = if s.is_empty() {
+                        None
+                    } else {
+                        Some(s.to_string())
+                    };
+                    if key == "notifications.slack_webhook_url" {
+                        self.notifications.slack_webhook_url = url;
+                    } else {
+                        self.notifications.discord_webhook_url = url;
+                    }
+                }
+            }
+            "notifications.cooldown_secs" => {
+                self.notifications.cooldown_secs = value.as_u64().ok_or("Expected number")?;
+            }
+            "logging.level" => {
+                self.logging.level = value.as_str().ok_or("Expected string")?.to_string();
+            }
+            "logging.json" => {
+                self.logging.json = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "logging.log_content" => {
+                self.logging.log_content = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "observability.trace_sample_ratio" => {
+                let ratio = value.as_f64().ok_or("Expected number")?;
+                if !(0.0..=1.0).contains(&ratio) {
+                    return Err("Expected a number between 0.0 and 1.0".into());
+                }
+                self.observability.trace_sample_ratio = ratio;
+            }
+            "observability.log_headers" => {
+                self.observability.log_headers = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "observability.log_prompts" => {
+                self.observability.log_prompts = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "observability.redact" => {
+                self.observability.redact = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "observability.failure_buffer" => {
+                self.observability.failure_buffer =
+                    value.as_u64().ok_or("Expected number")? as usize;
+            }
+            "mcp.enabled" => {
+                self.mcp.enabled = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "mcp.max_tool_rounds" => {
+                self.mcp.max_tool_rounds = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "agents.max_steps" => {
+                self.agents.max_steps = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
+            }
+            "agents.callback_timeout_secs" => {
+                self.agents.callback_timeout_secs = value.as_u64().ok_or("Expected number")?;
+            }
+            "agents.allow_private_callbacks" => {
+                self.agents.allow_private_callbacks = value.as_bool().ok_or("Expected boolean")?;
+            }
+            "conversations.enabled" => {
+                self.conversations.enabled = value.as_bool().ok_or("Expected boolean")?;
             }
-            "budget.warning_threshold_percent" => {
-                self.budget.warning_threshold_percent = value
+            "conversations.max_messages" => {
+                self.conversations.max_messages = value
                     .as_u64()
                     .ok_or("Expected number")?
                     .try_into()
                     .map_err(|_| "Value out of range")?;
             }
-            "logging.level" => {
-                self.logging.level = value.as_str().ok_or("Expected string")?.to_string();
+            "conversations.max_history_tokens" => {
+                self.conversations.max_history_tokens = value
+                    .as_u64()
+                    .ok_or("Expected number")?
+                    .try_into()
+                    .map_err(|_| "Value out of range")?;
             }
-            "logging.json" => {
-                self.logging.json = value.as_bool().ok_or("Expected boolean")?;
+            "conversations.compaction" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.conversations.compaction = s.parse().map_err(|e: String| e)?;
             }
-            "logging.log_content" => {
-                self.logging.log_content = value.as_bool().ok_or("Expected boolean")?;
+            "conversations.summary_model" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.conversations.summary_model = (!s.is_empty()).then(|| s.to_string());
             }
             "providers.litellm.url" => {
                 let url = value.as_str().ok_or("Expected string")?.to_string();
@@ -1729,6 +4891,12 @@ impl Config {
                         discover_models: true,
                         models: Vec::new(),
                         timeout_secs: default_litellm_timeout(),
+                        reconcile_interval_secs: 0,
+                        reconcile_tolerance_percent: default_litellm_reconcile_tolerance(),
+                        proxy: None,
+                        http: HttpClientConfig::default(),
+                        quota: ProviderQuotaConfig::default(),
+                        dry_run: false,
                     });
                     litellm.url = url;
                 }
@@ -1753,6 +4921,11 @@ impl Config {
                     litellm.timeout_secs = value.as_u64().ok_or("Expected number")?;
                 }
             }
+            "providers.litellm.reconcile_interval_secs" => {
+                if let Some(ref mut litellm) = self.providers.litellm {
+                    litellm.reconcile_interval_secs = value.as_u64().ok_or("Expected number")?;
+                }
+            }
             "cache.enabled" => {
                 self.cache.enabled = value.as_bool().ok_or("Expected boolean")?;
             }
@@ -1763,6 +4936,14 @@ impl Config {
             "cache.path" => {
                 self.cache.path = PathBuf::from(value.as_str().ok_or("Expected string")?);
             }
+            "cache.backend_url" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                self.cache.backend_url = if s.is_empty() {
+                    None
+                } else {
+                    Some(s.to_string())
+                };
+            }
             "cache.similarity_threshold" => {
                 self.cache.similarity_threshold = value.as_f64().ok_or("Expected number")? as f32;
             }
@@ -1804,6 +4985,16 @@ impl Config {
             "cache.skip_tool_requests" => {
                 self.cache.skip_tool_requests = value.as_bool().ok_or("Expected boolean")?;
             }
+            "providers.gemini.project_id" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                if let Some(ref mut gemini) = self.providers.gemini {
+                    gemini.project_id = if s.is_empty() {
+                        None
+                    } else {
+                        Some(s.to_string())
+                    };
+                }
+            }
             "providers.kiro.auth_method" => {
                 let s = value.as_str().ok_or("Expected string")?;
                 if let Some(ref mut kiro) = self.providers.kiro {
@@ -1846,6 +5037,26 @@ impl Config {
                     };
                 }
             }
+            "providers.kiro.sso_start_url" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                if let Some(ref mut kiro) = self.providers.kiro {
+                    kiro.sso_start_url = if s.is_empty() {
+                        None
+                    } else {
+                        Some(s.to_string())
+                    };
+                }
+            }
+            "providers.kiro.sso_region" => {
+                let s = value.as_str().ok_or("Expected string")?;
+                if let Some(ref mut kiro) = self.providers.kiro {
+                    kiro.sso_region = if s.is_empty() {
+                        None
+                    } else {
+                        Some(s.to_string())
+                    };
+                }
+            }
             _ => return Err(format!("Unknown setting key: {key}")),
         }
         Ok(())
@@ -1860,8 +5071,19 @@ impl Default for Config {
             auth: AuthConfig::default(),
             providers: ProvidersConfig::default(),
             budget: BudgetConfig::default(),
+            limits: LimitsConfig::default(),
+            files: FilesConfig::default(),
+            pricing: PricingConfig::default(),
+            reports: ReportsConfig::default(),
+            notifications: NotificationsConfig::default(),
             logging: LoggingConfig::default(),
+            observability: ObservabilityConfig::default(),
             cache: CacheConfig::default(),
+            mcp: McpConfig::default(),
+            agents: AgentsConfig::default(),
+            conversations: ConversationsConfig::default(),
+            provisioning: ProvisioningConfig::default(),
+            experiments: Vec::new(),
             env_overrides: EnvOverrides::default(),
         }
     }
@@ -1918,6 +5140,81 @@ mod tests {
         assert_eq!(tls.effective_header(), "X-SSL-Client-CN");
     }
 
+    #[test]
+    fn test_tls_client_cert_user_for_cn() {
+        let tls = TlsClientCertConfig {
+            users: HashMap::from([("svc-ci.internal".to_string(), "ci".to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(tls.user_for_cn("svc-ci.internal"), "ci");
+        assert_eq!(tls.user_for_cn("alice"), "alice");
+    }
+
+    #[test]
+    fn test_admin_listen_addr() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [server]
+            host = "0.0.0.0"
+
+            [server.admin]
+            port = 8401
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.server.admin_listen_addr().as_deref(),
+            Some("0.0.0.0:8401")
+        );
+
+        config.server.admin.as_mut().unwrap().host = Some("127.0.0.1".to_string());
+        assert_eq!(
+            config.server.admin_listen_addr().as_deref(),
+            Some("127.0.0.1:8401")
+        );
+
+        assert!(Config::default().server.admin_listen_addr().is_none());
+    }
+
+    #[test]
+    fn test_proxy_for_provider() {
+        let config: Config = toml::from_str(
+            r#"
+            [providers]
+            proxy = "http://proxy.corp:3128"
+            no_proxy = "localhost,.internal"
+
+            [providers.litellm]
+            url = "http://litellm.internal:4000"
+            proxy = ""
+
+            [providers.copilot]
+            client_id = "abc"
+            proxy = "socks5h://127.0.0.1:1080"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.providers.proxy_for("kiro"),
+            OutboundProxy::Url {
+                url: "http://proxy.corp:3128".to_string(),
+                no_proxy: Some("localhost,.internal".to_string()),
+            }
+        );
+        assert_eq!(config.providers.proxy_for("litellm"), OutboundProxy::Direct);
+        assert!(matches!(
+            config.providers.proxy_for("copilot"),
+            OutboundProxy::Url { ref url, .. } if url == "socks5h://127.0.0.1:1080"
+        ));
+        assert!(config.providers.validate_proxies().is_ok());
+
+        assert_eq!(
+            ProvidersConfig::default().proxy_for("claude"),
+            OutboundProxy::System
+        );
+    }
+
     #[test]
     fn test_routing_strategy_from_str() {
         assert_eq!(
@@ -1943,6 +5240,38 @@ mod tests {
         assert!("unknown".parse::<RoutingStrategy>().is_err());
     }
 
+    #[test]
+    fn test_token_refresh_interval() {
+        let mut providers = ProvidersConfig::default();
+        assert_eq!(
+            providers.token_refresh_interval(),
+            Some(std::time::Duration::from_secs(300))
+        );
+        providers.token_refresh_interval_secs = Some(0);
+        assert_eq!(providers.token_refresh_interval(), None);
+    }
+
+    #[test]
+    fn test_account_selection_from_str() {
+        assert_eq!(
+            "round-robin".parse::<AccountSelection>().unwrap(),
+            AccountSelection::RoundRobin
+        );
+        assert_eq!(
+            "least_recently_used".parse::<AccountSelection>().unwrap(),
+            AccountSelection::LeastRecentlyUsed
+        );
+        assert_eq!(
+            "lru".parse::<AccountSelection>().unwrap(),
+            AccountSelection::LeastRecentlyUsed
+        );
+        assert!("random".parse::<AccountSelection>().is_err());
+        assert_eq!(
+            AccountSelection::LeastRecentlyUsed.to_string(),
+            "least_recently_used"
+        );
+    }
+
     #[test]
     fn test_routing_strategy_display() {
         assert_eq!(RoutingStrategy::Priority.to_string(), "priority");
@@ -1965,6 +5294,10 @@ mod tests {
             "memory".parse::<StorageBackend>().unwrap(),
             StorageBackend::Memory
         );
+        assert_eq!(
+            "database".parse::<StorageBackend>().unwrap(),
+            StorageBackend::Database
+        );
         assert!("unknown".parse::<StorageBackend>().is_err());
     }
 
@@ -1973,6 +5306,363 @@ mod tests {
         assert_eq!(StorageBackend::File.to_string(), "file");
         assert_eq!(StorageBackend::Keyring.to_string(), "keyring");
         assert_eq!(StorageBackend::Memory.to_string(), "memory");
+        assert_eq!(StorageBackend::Database.to_string(), "database");
+    }
+
+    #[test]
+    fn test_database_storage_requires_encryption_key() {
+        let mut providers = ProvidersConfig {
+            storage_backend: StorageBackend::Database,
+            ..Default::default()
+        };
+        assert!(providers.validate_token_storage().is_err());
+
+        providers.token_encryption_key = Some("s3cret".to_string());
+        assert!(providers.validate_token_storage().is_ok());
+    }
+
+    #[test]
+    fn test_trusted_proxies_validation() {
+        let mut throttle = LoginThrottleConfig {
+            trusted_proxies: vec!["10.0.0.1".to_string(), "172.16.0.0/12".to_string()],
+            ..Default::default()
+        };
+        assert!(throttle.validate_trusted_proxies().is_ok());
+        assert_eq!(throttle.trusted_proxy_nets().len(), 2);
+
+        throttle.trusted_proxies.push("proxy.internal".to_string());
+        assert!(throttle.validate_trusted_proxies().is_err());
+    }
+
+    #[test]
+    fn test_pricing_currency_validation() {
+        let mut pricing = PricingConfig::default();
+        assert!(pricing.validate_currency().is_ok());
+
+        pricing.currency = "EUR".to_string();
+        assert!(pricing.validate_currency().is_err());
+        pricing.exchange_rate = Some(0.92);
+        assert!(pricing.validate_currency().is_ok());
+
+        pricing.currency = "euro".to_string();
+        assert!(pricing.validate_currency().is_err());
+    }
+
+    #[test]
+    fn test_kiro_profiles() {
+        let config: Config = toml::from_str(
+            r#"
+            [providers.kiro]
+            quota = { daily_requests = 100 }
+
+            [providers.kiro.profiles.work]
+            credentials_file = "/etc/gaud/kiro-work.json"
+            region = "eu-west-1"
+            quota = { daily_requests = 50 }
+
+            [providers.kiro.profiles.personal]
+            "#,
+        )
+        .unwrap();
+        let providers = &config.providers;
+        let kiro = providers.kiro.as_ref().unwrap();
+        assert_eq!(
+            kiro.profiles.keys().collect::<Vec<_>>(),
+            ["personal", "work"]
+        );
+        assert!(providers.validate_kiro_profiles().is_ok());
+
+        let dir = Path::new("/var/lib/gaud/tokens");
+        assert_eq!(
+            kiro.profiles["work"].credentials_path("work", dir),
+            PathBuf::from("/etc/gaud/kiro-work.json")
+        );
+        assert_eq!(
+            kiro.profiles["personal"].credentials_path("personal", dir),
+            dir.join("kiro-sso-personal.json")
+        );
+
+        assert_eq!(kiro_profile_provider_id("work"), "kiro:work");
+        assert_eq!(providers.quota_for("kiro").daily_requests, Some(100));
+        assert_eq!(providers.quota_for("kiro:work").daily_requests, Some(50));
+        assert_eq!(providers.quota_for("kiro:personal").daily_requests, None);
+        assert_eq!(providers.quota_for("kiro:unknown").daily_requests, None);
+    }
+
+    #[test]
+    fn test_kiro_profile_name_validation() {
+        let mut providers = ProvidersConfig::default();
+        let mut kiro: KiroProviderConfig = toml::from_str("").unwrap();
+        kiro.profiles
+            .insert("bad name".to_string(), KiroProfileConfig::default());
+        providers.kiro = Some(kiro);
+        assert!(providers.validate_kiro_profiles().is_err());
+    }
+
+    #[test]
+    fn test_dry_run_for() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [providers.kiro]
+            dry_run = true
+
+            [providers.litellm]
+            url = "http://localhost:4000"
+            "#,
+        )
+        .unwrap();
+        assert!(config.providers.dry_run_for("kiro"));
+        assert!(!config.providers.dry_run_for("litellm"));
+        assert!(!config.providers.dry_run_for("claude"));
+
+        config.providers.dry_run = true;
+        assert!(config.providers.dry_run_for("litellm"));
+        assert!(config.providers.dry_run_for("claude"));
+    }
+
+    #[test]
+    fn test_provider_sections() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.providers.section("litellm"),
+            Some(serde_json::Value::Null)
+        );
+        assert!(config.providers.section("openai").is_none());
+
+        config
+            .providers
+            .set_section(
+                "litellm",
+                serde_json::json!({"url": "http://localhost:4000", "timeout_secs": 60}),
+            )
+            .unwrap();
+        let litellm = config.providers.litellm.as_ref().unwrap();
+        assert_eq!(litellm.url, "http://localhost:4000");
+        assert_eq!(litellm.timeout_secs, 60);
+
+        // Wrong types and missing fields are refused.
+        let err = config
+            .providers
+            .set_section("litellm", serde_json::json!({"timeout_secs": "soon"}))
+            .unwrap_err();
+        assert!(err.starts_with("Invalid litellm settings"));
+
+        config
+            .providers
+            .set_section(
+                "virtual_models",
+                serde_json::json!({"smart": {"models": ["a", "b"], "weights": [3, 1]}}),
+            )
+            .unwrap();
+        assert_eq!(config.providers.virtual_models["smart"].weights, [3, 1]);
+        config
+            .providers
+            .set_section("litellm", serde_json::Value::Null)
+            .unwrap();
+        assert!(config.providers.litellm.is_none());
+    }
+
+    #[test]
+    fn test_observability_defaults_and_ratio() {
+        let config: Config = toml::from_str("[observability]\nlog_headers = true").unwrap();
+        assert_eq!(config.observability.trace_sample_ratio, 1.0);
+        assert!(config.observability.redact);
+        assert!(!config.observability.log_prompts);
+        assert!(config.observability.validate_sample_ratio().is_ok());
+
+        let mut config = Config::default();
+        config.observability.trace_sample_ratio = 1.5;
+        assert!(config.observability.validate_sample_ratio().is_err());
+        assert!(
+            config
+                .update_setting("observability.trace_sample_ratio", &serde_json::json!(-0.1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_retry_config() {
+        let config = Config::default();
+        assert_eq!(config.providers.retry.policy().max_retries, 3);
+        assert!(
+            !toml::to_string(&config)
+                .unwrap()
+                .contains("[providers.retry]")
+        );
+
+        let mut config: Config = toml::from_str(
+            "[providers.retry]
+max_retries = 0
+initial_backoff_ms = 250",
+        )
+        .unwrap();
+        let policy = config.providers.retry.policy();
+        assert_eq!(policy.max_retries, 0);
+        assert_eq!(policy.initial_backoff_ms, 250);
+        assert_eq!(policy.max_backoff_ms, RetryPolicy::new().max_backoff_ms);
+
+        config
+            .update_setting(
+                "providers.retry.initial_backoff_ms",
+                &serde_json::json!(90_000),
+            )
+            .unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_virtual_models() {
+        let config: Config = toml::from_str(
+            r#"
+            [providers.virtual_models.smart]
+            models = ["claude-sonnet-4-20250514", "gemini-2.5-pro"]
+            weights = [3, 1]
+
+            [providers.virtual_models.fast]
+            models = ["gemini-2.5-flash"]
+            "#,
+        )
+        .unwrap();
+        let providers = &config.providers;
+        assert_eq!(providers.virtual_models["smart"].weights, [3, 1]);
+        assert!(providers.virtual_models["fast"].weights.is_empty());
+        assert!(providers.validate_virtual_models().is_ok());
+
+        let mut bad = providers.clone();
+        bad.virtual_models.get_mut("smart").unwrap().weights = vec![1];
+        assert!(bad.validate_virtual_models().is_err());
+        let mut bad = providers.clone();
+        bad.virtual_models.get_mut("fast").unwrap().models = vec!["gaud:smart".into()];
+        assert!(bad.validate_virtual_models().is_err());
+        let mut bad = providers.clone();
+        bad.virtual_models
+            .insert("no way".into(), providers.virtual_models["fast"].clone());
+        assert!(bad.validate_virtual_models().is_err());
+    }
+
+    #[test]
+    fn test_mcp_servers() {
+        let config: Config = toml::from_str(
+            r#"
+            [mcp.servers.search]
+            url = "https://mcp.example.com/mcp"
+            models = ["claude-*"]
+            "#,
+        )
+        .unwrap();
+        let mcp = &config.mcp;
+        assert!(!mcp.enabled);
+        assert_eq!(mcp.max_tool_rounds, 8);
+        assert_eq!(mcp.servers["search"].models, ["claude-*"]);
+        assert_eq!(mcp.servers["search"].timeout_secs, 60);
+        assert!(mcp.validate_servers().is_ok());
+
+        let mut bad = mcp.clone();
+        bad.servers
+            .insert("a__b".into(), mcp.servers["search"].clone());
+        assert!(bad.validate_servers().is_err());
+        let mut bad = mcp.clone();
+        bad.servers.get_mut("search").unwrap().url = "stdio:server".into();
+        assert!(bad.validate_servers().is_err());
+    }
+
+    #[test]
+    fn test_routing_rules() {
+        let config: Config = toml::from_str(
+            r#"
+            [[providers.rules]]
+            name = "long-context"
+            min_input_chars = 400000
+            providers = ["gemini"]
+
+            [[providers.rules]]
+            name = "no-cache"
+            team = "security"
+            cache = false
+            "#,
+        )
+        .unwrap();
+        let providers = &config.providers;
+        assert_eq!(providers.rules.len(), 2);
+        assert_eq!(providers.rules[1].cache, Some(false));
+        assert!(providers.validate_rules().is_ok());
+
+        let mut dup = providers.clone();
+        dup.rules[1].name = "long-context".into();
+        assert!(dup.validate_rules().is_err());
+    }
+
+    #[test]
+    fn test_model_params() {
+        let config: Config = toml::from_str(
+            r#"
+            [[providers.model_params]]
+            model = "o3*"
+            temperature = { default = 1.0, min = 1.0, max = 1.0 }
+            max_tokens = { max = 100000 }
+            "#,
+        )
+        .unwrap();
+        let params = &config.providers.model_params[0];
+        assert_eq!(params.temperature.min, Some(1.0));
+        assert_eq!(params.max_tokens.max, Some(100000));
+        assert!(params.top_p.is_empty());
+        assert!(config.providers.validate_model_params().is_ok());
+    }
+
+    #[test]
+    fn test_team_priority() {
+        let config: Config = toml::from_str(
+            r#"
+            [limits]
+            max_in_flight = 8
+            team_priority = { etl = "batch" }
+            "#,
+        )
+        .unwrap();
+        let limits = &config.limits;
+        assert_eq!(limits.queue_timeout_secs, 30);
+        assert_eq!(
+            limits.priority(Priority::Default, Some("etl")),
+            Priority::Batch
+        );
+        assert_eq!(
+            limits.priority(Priority::Interactive, Some("etl")),
+            Priority::Interactive
+        );
+        assert_eq!(
+            limits.priority(Priority::Default, Some("research")),
+            Priority::Interactive
+        );
+        assert_eq!(limits.priority(Priority::Batch, None), Priority::Batch);
+    }
+
+    #[test]
+    fn test_experiments() {
+        let config: Config = toml::from_str(
+            r#"
+            [[experiments]]
+            name = "haiku-trial"
+            model = "claude-sonnet*"
+            split_percent = 20
+
+            [experiments.a]
+
+            [experiments.b]
+            model = "claude-haiku"
+            "#,
+        )
+        .unwrap();
+        let experiment = &config.experiments[0];
+        assert_eq!(experiment.traffic_percent, 100);
+        assert_eq!(experiment.split_percent, 20);
+        assert!(experiment.sticky);
+        assert_eq!(experiment.b.model.as_deref(), Some("claude-haiku"));
+        assert!(config.validate_experiments().is_ok());
+
+        let mut dup = config.clone();
+        dup.experiments.push(experiment.clone());
+        assert!(dup.validate_experiments().is_err());
     }
 
     #[test]
@@ -2066,6 +5756,65 @@ mod tests {
         }
     }
 
+    #[test]
+    #[serial]
+    fn test_cors_origins_per_route_group() {
+        let mut config: Config = toml::from_str(
+            r#"
+[server]
+cors_origins = ["https://app.example.com"]
+
+[server.cors]
+proxy = ["*"]
+admin = []
+"#,
+        )
+        .unwrap();
+        let server = &config.server;
+        assert_eq!(server.cors_origins_for(RouteGroup::Proxy), ["*"]);
+        assert!(server.cors_origins_for(RouteGroup::Admin).is_empty());
+        assert_eq!(
+            server.cors_origins_for(RouteGroup::Ui),
+            ["https://app.example.com"]
+        );
+        assert_eq!(
+            Config::default().server.cors_origins_for(RouteGroup::Ui),
+            ["*"]
+        );
+
+        // SAFETY: Tests are run sequentially for env-mutating tests.
+        unsafe {
+            std::env::set_var("GAUD_SERVER_CORS_UI", "none");
+            std::env::set_var("GAUD_SERVER_CORS_ADMIN", "");
+        }
+        config.apply_env_overrides();
+        unsafe {
+            std::env::remove_var("GAUD_SERVER_CORS_UI");
+            std::env::remove_var("GAUD_SERVER_CORS_ADMIN");
+        }
+        assert_eq!(config.server.cors.ui, Some(Vec::new()));
+        assert_eq!(config.server.cors.admin, None);
+        assert!(config.env_overrides.is_overridden("server.cors.ui"));
+
+        config
+            .update_setting(
+                "server.cors.admin",
+                &serde_json::json!("https://a.example.com, https://b.example.com"),
+            )
+            .unwrap();
+        let report = config.settings_report();
+        let admin = report
+            .iter()
+            .find(|e| e.key == "server.cors.admin")
+            .unwrap();
+        assert_eq!(
+            admin.value,
+            serde_json::json!("https://a.example.com, https://b.example.com")
+        );
+        let ui = report.iter().find(|e| e.key == "server.cors.ui").unwrap();
+        assert_eq!(ui.value, serde_json::json!("none"));
+    }
+
     #[test]
     fn test_settings_report_completeness() {
         let config = Config::default();
@@ -8,6 +8,12 @@ pub struct Database {
     conn: Arc<Mutex<Connection>>,
 }
 
+impl std::fmt::Debug for Database {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Database").finish_non_exhaustive()
+    }
+}
+
 impl Database {
     /// Open or create the database at the given path with WAL mode.
     pub fn open(path: &Path) -> anyhow::Result<Self> {
@@ -50,18 +56,89 @@ impl Database {
     fn run_migrations(&self) -> anyhow::Result<()> {
         self.with_conn(|conn| {
             conn.execute_batch(SCHEMA)?;
+            for (table, column, definition) in COLUMN_MIGRATIONS {
+                add_column_if_missing(conn, table, column, definition)?;
+            }
             Ok(())
         })?;
         Ok(())
     }
 }
 
+/// Add a column to an existing table unless it is already present.
+///
+/// `CREATE TABLE IF NOT EXISTS` leaves tables from older databases untouched,
+/// so columns introduced after the initial schema are added here.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), rusqlite::Error> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{table}') WHERE name = ?1"),
+        [column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+        ))?;
+    }
+    Ok(())
+}
+
+/// Columns added after the initial schema: (table, column, definition).
+///
+/// New databases get these from `SCHEMA` directly; existing databases are
+/// upgraded in place on open.
+const COLUMN_MIGRATIONS: &[(&str, &str, &str)] = &[
+    (
+        "api_keys",
+        "reasoning_mode",
+        "TEXT NOT NULL DEFAULT 'forward'",
+    ),
+    ("usage_log", "audio_seconds", "REAL NOT NULL DEFAULT 0.0"),
+    ("users", "team", "TEXT"),
+    ("oauth_state", "account", "TEXT NOT NULL DEFAULT 'default'"),
+    ("usage_log", "premium_requests", "REAL NOT NULL DEFAULT 0.0"),
+    ("budgets", "monthly_premium_limit", "REAL"),
+    ("budgets", "daily_premium_limit", "REAL"),
+    (
+        "budgets",
+        "monthly_premium_used",
+        "REAL NOT NULL DEFAULT 0.0",
+    ),
+    ("budgets", "daily_premium_used", "REAL NOT NULL DEFAULT 0.0"),
+    ("usage_log", "raw_cost", "REAL"),
+    ("usage_log", "end_user", "TEXT"),
+    ("usage_log", "tags", "TEXT"),
+    ("usage_log", "impersonated_by", "TEXT"),
+    ("api_keys", "provisioned_hash", "TEXT"),
+    ("users", "service_account", "INTEGER NOT NULL DEFAULT 0"),
+    ("api_keys", "last_used_ip", "TEXT"),
+    ("api_keys", "disabled_at", "TEXT"),
+    ("api_keys", "debug_errors", "INTEGER NOT NULL DEFAULT 0"),
+    ("usage_log", "finish_reason", "TEXT"),
+    ("usage_log", "experiment", "TEXT"),
+    ("usage_log", "experiment_variant", "TEXT"),
+    ("api_keys", "priority", "TEXT NOT NULL DEFAULT 'default'"),
+    ("usage_log", "cost_saved", "REAL NOT NULL DEFAULT 0.0"),
+    ("usage_hourly", "cost_saved", "REAL NOT NULL DEFAULT 0.0"),
+    ("usage_log", "key_id", "TEXT"),
+    ("usage_log", "client_ip", "TEXT"),
+    ("usage_log", "user_agent", "TEXT"),
+    ("usage_log", "path", "TEXT"),
+];
+
 const SCHEMA: &str = r#"
 CREATE TABLE IF NOT EXISTS users (
     id          TEXT PRIMARY KEY,
     name        TEXT NOT NULL UNIQUE,
     role        TEXT NOT NULL DEFAULT 'member' CHECK (role IN ('admin', 'member')),
-    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    team        TEXT,
+    service_account INTEGER NOT NULL DEFAULT 0
 );
 
 CREATE TABLE IF NOT EXISTS api_keys (
@@ -71,7 +148,13 @@ CREATE TABLE IF NOT EXISTS api_keys (
     key_prefix  TEXT NOT NULL,
     label       TEXT NOT NULL DEFAULT '',
     created_at  TEXT NOT NULL DEFAULT (datetime('now')),
-    last_used   TEXT
+    last_used   TEXT,
+    reasoning_mode TEXT NOT NULL DEFAULT 'forward',
+    provisioned_hash TEXT,
+    last_used_ip TEXT,
+    disabled_at TEXT,
+    debug_errors INTEGER NOT NULL DEFAULT 0,
+    priority    TEXT NOT NULL DEFAULT 'default'
 );
 CREATE INDEX IF NOT EXISTS idx_api_keys_hash ON api_keys(key_hash);
 CREATE INDEX IF NOT EXISTS idx_api_keys_user ON api_keys(user_id);
@@ -83,7 +166,20 @@ CREATE TABLE IF NOT EXISTS budgets (
     monthly_used    REAL NOT NULL DEFAULT 0.0,
     daily_used      REAL NOT NULL DEFAULT 0.0,
     period_start    TEXT NOT NULL DEFAULT (datetime('now', 'start of month')),
-    day_start       TEXT NOT NULL DEFAULT (date('now'))
+    day_start       TEXT NOT NULL DEFAULT (date('now')),
+    monthly_premium_limit   REAL,
+    daily_premium_limit     REAL,
+    monthly_premium_used    REAL NOT NULL DEFAULT 0.0,
+    daily_premium_used      REAL NOT NULL DEFAULT 0.0
+);
+
+CREATE TABLE IF NOT EXISTS rate_limits (
+    scope       TEXT NOT NULL CHECK (scope IN ('user', 'team')),
+    subject     TEXT NOT NULL,
+    rpm         INTEGER,
+    tpm         INTEGER,
+    rpd         INTEGER,
+    PRIMARY KEY (scope, subject)
 );
 
 CREATE TABLE IF NOT EXISTS usage_log (
@@ -94,20 +190,228 @@ CREATE TABLE IF NOT EXISTS usage_log (
     model           TEXT NOT NULL,
     input_tokens    INTEGER NOT NULL DEFAULT 0,
     output_tokens   INTEGER NOT NULL DEFAULT 0,
+    audio_seconds   REAL NOT NULL DEFAULT 0.0,
     cost            REAL NOT NULL DEFAULT 0.0,
     latency_ms      INTEGER NOT NULL DEFAULT 0,
     status          TEXT NOT NULL DEFAULT 'success',
-    created_at      TEXT NOT NULL DEFAULT (datetime('now'))
+    created_at      TEXT NOT NULL DEFAULT (datetime('now')),
+    premium_requests REAL NOT NULL DEFAULT 0.0,
+    raw_cost        REAL,
+    end_user        TEXT,
+    tags            TEXT,
+    impersonated_by TEXT,
+    finish_reason   TEXT,
+    experiment      TEXT,
+    experiment_variant TEXT,
+    cost_saved      REAL NOT NULL DEFAULT 0.0,
+    key_id          TEXT,
+    client_ip       TEXT,
+    user_agent      TEXT,
+    path            TEXT
 );
 CREATE INDEX IF NOT EXISTS idx_usage_log_user ON usage_log(user_id);
 CREATE INDEX IF NOT EXISTS idx_usage_log_provider ON usage_log(provider);
 CREATE INDEX IF NOT EXISTS idx_usage_log_created ON usage_log(created_at);
 
+CREATE TABLE IF NOT EXISTS responses (
+    id          TEXT PRIMARY KEY,
+    user_id     TEXT NOT NULL,
+    model       TEXT NOT NULL,
+    messages    TEXT NOT NULL,
+    response    TEXT NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE INDEX IF NOT EXISTS idx_responses_user ON responses(user_id);
+
+CREATE TABLE IF NOT EXISTS conversations (
+    id          TEXT PRIMARY KEY,
+    user_id     TEXT NOT NULL,
+    messages    TEXT NOT NULL,
+    summary     TEXT,
+    metadata    TEXT,
+    version     INTEGER NOT NULL DEFAULT 0,
+    created_at  INTEGER NOT NULL,
+    updated_at  INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_conversations_user ON conversations(user_id);
+
+CREATE TABLE IF NOT EXISTS files (
+    id              TEXT PRIMARY KEY,
+    user_id         TEXT NOT NULL,
+    filename        TEXT NOT NULL,
+    purpose         TEXT NOT NULL,
+    content_type    TEXT,
+    bytes           INTEGER NOT NULL,
+    data            BLOB,
+    path            TEXT,
+    created_at      INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_files_user ON files(user_id);
+
+CREATE TABLE IF NOT EXISTS image_uploads (
+    id          TEXT PRIMARY KEY,
+    user_id     TEXT NOT NULL,
+    mime_type   TEXT NOT NULL,
+    data        BLOB NOT NULL,
+    created_at  INTEGER NOT NULL,
+    expires_at  INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_image_uploads_expires ON image_uploads(expires_at);
+
 CREATE TABLE IF NOT EXISTS oauth_state (
     state_token     TEXT PRIMARY KEY,
     provider        TEXT NOT NULL,
     code_verifier   TEXT NOT NULL,
+    account         TEXT NOT NULL DEFAULT 'default',
     created_at      TEXT NOT NULL DEFAULT (datetime('now')),
     expires_at      TEXT NOT NULL
 );
+
+CREATE TABLE IF NOT EXISTS oauth_accounts (
+    provider    TEXT NOT NULL,
+    account     TEXT NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (provider, account)
+);
+
+CREATE TABLE IF NOT EXISTS oauth_tokens (
+    provider    TEXT PRIMARY KEY,
+    token       TEXT NOT NULL,
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS thinking_signatures (
+    kind            TEXT NOT NULL,
+    key             TEXT NOT NULL,
+    signature       TEXT NOT NULL,
+    model_family    TEXT NOT NULL,
+    expires_at      INTEGER NOT NULL,
+    PRIMARY KEY (kind, key)
+);
+CREATE INDEX IF NOT EXISTS idx_thinking_signatures_signature ON thinking_signatures(signature);
+CREATE INDEX IF NOT EXISTS idx_thinking_signatures_expires ON thinking_signatures(expires_at);
+
+CREATE TABLE IF NOT EXISTS cost_reconciliation (
+    provider        TEXT NOT NULL,
+    day             TEXT NOT NULL,
+    model           TEXT NOT NULL,
+    gaud_cost       REAL NOT NULL DEFAULT 0.0,
+    gaud_requests   INTEGER NOT NULL DEFAULT 0,
+    upstream_cost   REAL NOT NULL DEFAULT 0.0,
+    difference      REAL NOT NULL DEFAULT 0.0,
+    flagged         INTEGER NOT NULL DEFAULT 0,
+    checked_at      TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (provider, day, model)
+);
+
+CREATE TABLE IF NOT EXISTS pricing_overrides (
+    model                         TEXT PRIMARY KEY,
+    provider                      TEXT NOT NULL,
+    input_cost_per_million        REAL NOT NULL,
+    output_cost_per_million       REAL NOT NULL,
+    cached_input_cost_per_million REAL,
+    updated_at                    TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS usage_hourly (
+    hour                TEXT NOT NULL,
+    provider            TEXT NOT NULL,
+    requests            INTEGER NOT NULL DEFAULT 0,
+    errors              INTEGER NOT NULL DEFAULT 0,
+    input_tokens        INTEGER NOT NULL DEFAULT 0,
+    output_tokens       INTEGER NOT NULL DEFAULT 0,
+    cost                REAL NOT NULL DEFAULT 0.0,
+    latency_histogram   TEXT NOT NULL DEFAULT '[]',
+    cost_saved          REAL NOT NULL DEFAULT 0.0,
+    PRIMARY KEY (hour, provider)
+);
+
+CREATE TABLE IF NOT EXISTS cost_markups (
+    scope               TEXT NOT NULL CHECK (scope IN ('user', 'team')),
+    subject             TEXT NOT NULL,
+    percent             REAL,
+    per_million_tokens  REAL,
+    PRIMARY KEY (scope, subject)
+);
+
+CREATE TABLE IF NOT EXISTS web_sessions (
+    token_hash  TEXT PRIMARY KEY,
+    api_key_id  TEXT NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    expires_at  TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_web_sessions_expires ON web_sessions(expires_at);
+
+CREATE TABLE IF NOT EXISTS scoped_tokens (
+    id          TEXT PRIMARY KEY,
+    api_key_id  TEXT NOT NULL REFERENCES api_keys(id) ON DELETE CASCADE,
+    user_id     TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    used_tokens INTEGER NOT NULL DEFAULT 0,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    expires_at  TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS signing_keys (
+    name        TEXT PRIMARY KEY,
+    secret      BLOB NOT NULL,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id     TEXT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret      TEXT NOT NULL,
+    enabled     INTEGER NOT NULL DEFAULT 0,
+    last_step   INTEGER NOT NULL DEFAULT 0,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS security_events (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    event       TEXT NOT NULL,
+    ip          TEXT,
+    user_id     TEXT,
+    detail      TEXT NOT NULL DEFAULT ''
+);
+CREATE INDEX IF NOT EXISTS idx_security_events_created ON security_events(created_at);
+
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    user_id     TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash   TEXT NOT NULL,
+    used_at     TEXT,
+    PRIMARY KEY (user_id, code_hash)
+);
+
+CREATE TABLE IF NOT EXISTS admin_audit_log (
+    id          INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    actor_id    TEXT NOT NULL,
+    actor_name  TEXT NOT NULL,
+    action      TEXT NOT NULL,
+    target      TEXT NOT NULL DEFAULT '',
+    before_json TEXT,
+    after_json  TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_admin_audit_log_created ON admin_audit_log(created_at);
+CREATE TRIGGER IF NOT EXISTS admin_audit_log_no_update BEFORE UPDATE ON admin_audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'admin_audit_log is append-only');
+END;
+CREATE TRIGGER IF NOT EXISTS admin_audit_log_no_delete BEFORE DELETE ON admin_audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'admin_audit_log is append-only');
+END;
+
+CREATE TABLE IF NOT EXISTS runtime_settings (
+    key         TEXT PRIMARY KEY,
+    value       TEXT NOT NULL,
+    updated_by  TEXT NOT NULL,
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS leader_lease (
+    name        TEXT PRIMARY KEY,
+    holder      TEXT NOT NULL,
+    expires_at  INTEGER NOT NULL
+);
 "#;
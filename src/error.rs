@@ -1,8 +1,12 @@
-use axum::http::StatusCode;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{StatusCode, header};
+use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
 use serde::Serialize;
 
 use crate::providers::ProviderError;
+use crate::providers::redact;
 
 /// Unified application error type following OpenAI error format.
 #[derive(Debug, thiserror::Error)]
@@ -10,15 +14,27 @@ pub enum AppError {
     #[error("Authentication required: {0}")]
     Unauthorized(String),
 
+    /// A second factor (TOTP or recovery code) is missing or wrong.
+    #[error("Two-factor authentication required: {0}")]
+    SecondFactorRequired(String),
+
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
     #[error("Not found: {0}")]
     NotFound(String),
 
+    /// The resource changed while the request was being handled.
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    /// A request field has an invalid value; `param` names the field.
+    #[error("Invalid value for '{param}': {message}")]
+    InvalidParam { param: String, message: String },
+
     #[error("Budget exceeded: {0}")]
     BudgetExceeded(String),
 
@@ -28,12 +44,30 @@ pub enum AppError {
     #[error("Context window exceeded: {0}")]
     ContextWindow(String),
 
+    /// The request, or a part of it, is over a size limit (`[limits]`).
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
     #[error("Provider error ({status}): {message}")]
-    ProviderWithStatus { status: u16, message: String },
+    ProviderWithStatus {
+        status: u16,
+        /// Summary of the upstream error.
+        message: String,
+        /// Upstream response body, shown only in debug mode; see
+        /// [`with_upstream_detail`].
+        body: Option<String>,
+    },
 
     #[error("Provider error: {0}")]
     Provider(String),
 
+    /// The provider did not answer in time.
+    #[error("Provider timeout: {0}")]
+    Timeout(String),
+
+    #[error("Not implemented: {0}")]
+    NotImplemented(String),
+
     #[error("OAuth error: {0}")]
     OAuth(String),
 
@@ -55,20 +89,40 @@ struct ErrorDetail {
     message: String,
     r#type: String,
     code: Option<String>,
+    /// Request field the error is about, if any.
+    param: Option<String>,
+}
+
+/// Largest plain-text error body [`wrap_plain_errors`] reads.
+const MAX_PLAIN_ERROR_BYTES: usize = 64 * 1024;
+
+/// Status and sanitized body of the upstream response behind a provider
+/// error. Kept in the extensions of the error response, where
+/// [`with_upstream_detail`] finds it.
+#[derive(Debug, Clone)]
+pub struct UpstreamError {
+    pub status: u16,
+    pub body: String,
 }
 
 impl AppError {
     fn status_code(&self) -> StatusCode {
         match self {
-            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Unauthorized(_) | Self::SecondFactorRequired(_) => StatusCode::UNAUTHORIZED,
             Self::Forbidden(_) => StatusCode::FORBIDDEN,
             Self::NotFound(_) => StatusCode::NOT_FOUND,
-            Self::BadRequest(_) | Self::ContextWindow(_) => StatusCode::BAD_REQUEST,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::BadRequest(_) | Self::InvalidParam { .. } | Self::ContextWindow(_) => {
+                StatusCode::BAD_REQUEST
+            }
             Self::BudgetExceeded(_) | Self::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
             Self::ProviderWithStatus { status, .. } => {
                 StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY)
             }
             Self::Provider(_) => StatusCode::BAD_GATEWAY,
+            Self::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            Self::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
             Self::OAuth(_) => StatusCode::BAD_REQUEST,
             Self::Database(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -76,40 +130,88 @@ impl AppError {
 
     fn error_type(&self) -> &str {
         match self {
-            Self::Unauthorized(_) => "authentication_error",
+            Self::Unauthorized(_) | Self::SecondFactorRequired(_) => "authentication_error",
             Self::Forbidden(_) => "permission_error",
             Self::NotFound(_) => "not_found_error",
-            Self::BadRequest(_) | Self::ContextWindow(_) => "invalid_request_error",
+            Self::BadRequest(_)
+            | Self::InvalidParam { .. }
+            | Self::ContextWindow(_)
+            | Self::PayloadTooLarge(_)
+            | Self::Conflict(_) => "invalid_request_error",
             Self::BudgetExceeded(_) => "rate_limit_error",
             Self::RateLimited(_) => "rate_limit_error",
-            Self::Provider(_) | Self::ProviderWithStatus { .. } => "api_error",
+            Self::Provider(_) | Self::ProviderWithStatus { .. } | Self::Timeout(_) => "api_error",
+            Self::NotImplemented(_) => "not_implemented_error",
             Self::OAuth(_) => "oauth_error",
             Self::Database(_) | Self::Internal(_) => "server_error",
         }
     }
 
-    fn error_code(&self) -> Option<&str> {
+    /// Machine-readable code; the full list is in docs/api-reference.md.
+    fn error_code(&self) -> &str {
         match self {
-            Self::BudgetExceeded(_) => Some("budget_exceeded"),
-            Self::RateLimited(_) => Some("rate_limit_exceeded"),
-            Self::ContextWindow(_) => Some("context_length_exceeded"),
-            Self::Unauthorized(_) => Some("invalid_api_key"),
+            Self::Unauthorized(_) => "invalid_api_key",
+            Self::SecondFactorRequired(_) => "totp_required",
+            Self::Forbidden(_) => "permission_denied",
+            Self::NotFound(_) => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::BadRequest(_) => "invalid_request",
+            Self::InvalidParam { .. } => "invalid_value",
+            Self::BudgetExceeded(_) => "budget_exceeded",
+            Self::RateLimited(_) => "rate_limit_exceeded",
+            Self::ContextWindow(_) => "context_length_exceeded",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::ProviderWithStatus { .. } => "upstream_error",
+            Self::Provider(_) => "provider_error",
+            Self::Timeout(_) => "upstream_timeout",
+            Self::NotImplemented(_) => "not_implemented",
+            Self::OAuth(_) => "oauth_error",
+            Self::Database(_) => "database_error",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+
+    fn param(&self) -> Option<&str> {
+        match self {
+            Self::InvalidParam { param, .. } => Some(param),
             _ => None,
         }
     }
-}
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let status = self.status_code();
-        let body = ErrorResponse {
+    /// OpenAI-compatible error body for this error.
+    fn body(&self) -> ErrorResponse {
+        ErrorResponse {
             error: ErrorDetail {
                 message: self.to_string(),
                 r#type: self.error_type().to_string(),
-                code: self.error_code().map(String::from),
+                code: Some(self.error_code().to_string()),
+                param: self.param().map(String::from),
             },
-        };
-        (status, axum::Json(body)).into_response()
+        }
+    }
+
+    /// Serialize the error body to JSON, for transports that cannot carry an
+    /// HTTP status (e.g. WebSocket frames).
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.body()).unwrap_or_default()
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let mut response = (self.status_code(), axum::Json(self.body())).into_response();
+        if let Self::ProviderWithStatus {
+            status,
+            body: Some(body),
+            ..
+        } = &self
+        {
+            response.extensions_mut().insert(UpstreamError {
+                status: *status,
+                body: redact::sanitize(body),
+            });
+        }
+        response
     }
 }
 
@@ -123,6 +225,9 @@ impl From<rusqlite::Error> for AppError {
 impl From<reqwest::Error> for AppError {
     fn from(err: reqwest::Error) -> Self {
         tracing::error!(error = %err, "HTTP client error");
+        if err.is_timeout() {
+            return Self::Timeout(err.to_string());
+        }
         Self::Provider(err.to_string())
     }
 }
@@ -143,7 +248,9 @@ impl From<ProviderError> for AppError {
     fn from(err: ProviderError) -> Self {
         match &err {
             // Preserve 429 status for rate limiting.
-            ProviderError::RateLimited { .. } => Self::RateLimited(err.to_string()),
+            ProviderError::RateLimited { .. } | ProviderError::QuotaExhausted { .. } => {
+                Self::RateLimited(err.to_string())
+            }
 
             // Context window errors -> 400.
             ProviderError::ContextWindowExceeded { .. } => Self::ContextWindow(err.to_string()),
@@ -156,10 +263,15 @@ impl From<ProviderError> for AppError {
             // Invalid request -> 400.
             ProviderError::InvalidRequest(_) => Self::BadRequest(err.to_string()),
 
-            // API errors preserve upstream status code.
+            ProviderError::Timeout { .. } => Self::Timeout(err.to_string()),
+            ProviderError::Http(e) if e.is_timeout() => Self::Timeout(err.to_string()),
+
+            // API errors preserve upstream status code. The body is summarized;
+            // it is only passed on in full to callers in debug mode.
             ProviderError::Api { status, message } => Self::ProviderWithStatus {
                 status: *status,
-                message: message.clone(),
+                message: redact::summary(message),
+                body: Some(message.clone()),
             },
 
             // Everything else -> 502 (Bad Gateway).
@@ -167,3 +279,216 @@ impl From<ProviderError> for AppError {
         }
     }
 }
+
+/// Middleware rewriting error responses that are not JSON, such as axum's
+/// plain-text rejections of malformed bodies or query strings and wrong
+/// methods, into the OpenAI error envelope.
+pub async fn wrap_plain_errors(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    if !(status.is_client_error() || status.is_server_error()) || is_json(&response) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let message = match axum::body::to_bytes(body, MAX_PLAIN_ERROR_BYTES).await {
+        Ok(bytes) if !bytes.is_empty() => String::from_utf8_lossy(&bytes).trim().to_string(),
+        _ => status
+            .canonical_reason()
+            .unwrap_or("Request failed")
+            .to_string(),
+    };
+    let (r#type, code) = plain_error_kind(status);
+    let body = ErrorResponse {
+        error: ErrorDetail {
+            message,
+            r#type: r#type.to_string(),
+            code: Some(code.to_string()),
+            param: None,
+        },
+    };
+
+    let mut response = axum::Json(body).into_response();
+    *response.status_mut() = status;
+    for (name, value) in &parts.headers {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            response.headers_mut().insert(name, value.clone());
+        }
+    }
+    response
+}
+
+/// Add the upstream status and body of a provider error response to its
+/// error object as `upstream`, for callers debugging provider rejections.
+/// Other responses are returned unchanged.
+pub async fn with_upstream_detail(response: Response) -> Response {
+    let Some(upstream) = response.extensions().get::<UpstreamError>().cloned() else {
+        return response;
+    };
+    let (parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, MAX_PLAIN_ERROR_BYTES).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let mut value: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    if let Some(error) = value.get_mut("error").and_then(|e| e.as_object_mut()) {
+        // JSON bodies are embedded as JSON, anything else as a string.
+        let body = serde_json::from_str(&upstream.body)
+            .unwrap_or(serde_json::Value::String(upstream.body));
+        error.insert(
+            "upstream".to_string(),
+            serde_json::json!({ "status": upstream.status, "body": body }),
+        );
+    }
+
+    let mut response = axum::Json(value).into_response();
+    *response.status_mut() = parts.status;
+    for (name, value) in &parts.headers {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            response.headers_mut().insert(name, value.clone());
+        }
+    }
+    response
+}
+
+fn is_json(response: &Response<Body>) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"))
+}
+
+/// Error type and code for a plain error response with `status`.
+fn plain_error_kind(status: StatusCode) -> (&'static str, &'static str) {
+    match status {
+        StatusCode::UNAUTHORIZED => ("authentication_error", "invalid_api_key"),
+        StatusCode::FORBIDDEN => ("permission_error", "permission_denied"),
+        StatusCode::NOT_FOUND => ("not_found_error", "not_found"),
+        StatusCode::METHOD_NOT_ALLOWED => ("invalid_request_error", "method_not_allowed"),
+        StatusCode::PAYLOAD_TOO_LARGE => ("invalid_request_error", "payload_too_large"),
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => ("invalid_request_error", "unsupported_media_type"),
+        StatusCode::TOO_MANY_REQUESTS => ("rate_limit_error", "rate_limit_exceeded"),
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => {
+            ("api_error", "upstream_timeout")
+        }
+        StatusCode::NOT_IMPLEMENTED => ("not_implemented_error", "not_implemented"),
+        s if s.is_server_error() => ("server_error", "internal_error"),
+        _ => ("invalid_request_error", "invalid_request"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    fn body_of(err: &AppError) -> serde_json::Value {
+        serde_json::from_str(&err.to_json()).unwrap()
+    }
+
+    #[test]
+    fn test_every_error_has_a_code_and_param() {
+        let body = body_of(&AppError::Forbidden("nope".into()));
+        assert_eq!(body["error"]["type"], "permission_error");
+        assert_eq!(body["error"]["code"], "permission_denied");
+        assert!(body["error"]["param"].is_null());
+        assert!(body["error"].as_object().unwrap().contains_key("param"));
+
+        let body = body_of(&AppError::InvalidParam {
+            param: "temperature".into(),
+            message: "must be between 0 and 2".into(),
+        });
+        assert_eq!(body["error"]["code"], "invalid_value");
+        assert_eq!(body["error"]["param"], "temperature");
+    }
+
+    #[test]
+    fn test_provider_timeout_maps_to_504() {
+        let err = AppError::from(ProviderError::Timeout { timeout_secs: 30 });
+        assert_eq!(err.status_code(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body_of(&err)["error"]["code"], "upstream_timeout");
+    }
+
+    #[tokio::test]
+    async fn test_upstream_detail_only_when_requested() {
+        let err = AppError::from(ProviderError::Api {
+            status: 400,
+            message: r#"{"message":"Input is too long.","reason":"CONTENT_LENGTH_EXCEEDS_THRESHOLD","token":"abc"}"#
+                .to_string(),
+        });
+        assert_eq!(err.to_string(), "Provider error (400): Input is too long.");
+
+        let response = err.into_response();
+        assert_eq!(
+            response.extensions().get::<UpstreamError>().unwrap().status,
+            400
+        );
+        let response = with_upstream_detail(response).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "upstream_error");
+        assert_eq!(body["error"]["upstream"]["status"], 400);
+        let upstream = &body["error"]["upstream"]["body"];
+        assert_eq!(upstream["reason"], "CONTENT_LENGTH_EXCEEDS_THRESHOLD");
+        assert_eq!(upstream["token"], "[redacted]");
+
+        // Errors without an upstream response are left alone.
+        let response = with_upstream_detail(AppError::NotFound("x".into()).into_response()).await;
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(body["error"].get("upstream").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wrap_plain_errors() {
+        async fn echo(
+            axum::Json(v): axum::Json<serde_json::Value>,
+        ) -> axum::Json<serde_json::Value> {
+            axum::Json(v)
+        }
+        let app = Router::new()
+            .route("/echo", post(echo))
+            .layer(axum::middleware::from_fn(wrap_plain_errors));
+
+        let request = |uri: &str, body: &str| {
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        // Malformed JSON body: axum's plain-text rejection is wrapped.
+        let response = app.clone().oneshot(request("/echo", "{")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["code"], "invalid_request");
+        assert!(!body["error"]["message"].as_str().unwrap().is_empty());
+
+        let response = app
+            .clone()
+            .oneshot(Request::post("/echo").body(Body::from("{}")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "unsupported_media_type");
+
+        // Successful responses pass through untouched.
+        let response = app.oneshot(request("/echo", r#"{"a":1}"#)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,437 @@
+//! A/B experiments (`[[experiments]]`).
+//!
+//! An experiment splits the chat requests it matches between two variants,
+//! `a` and `b`, each of which may swap the model and the system prompt.
+//! The variant is recorded on the request's usage log entry, so
+//! `GET /admin/experiments/{name}` can compare the two on cost, latency,
+//! errors and finish reasons. Experiments are tried in order and the first
+//! that matches and enrols a request wins.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::budget::attribution;
+use crate::db::Database;
+use crate::providers::rules::glob_match;
+use crate::providers::types::{ChatMessage, ChatRequest, MessageContent, MessageRole};
+
+/// Names of the two variants, as recorded in the usage log.
+pub const VARIANTS: [&str; 2] = ["a", "b"];
+
+/// One `[[experiments]]` entry.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Experiment {
+    /// Name used in the usage log and the admin endpoint.
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    // -- conditions --
+    /// Model name pattern; `*` matches any run of characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// User name or ID pattern.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Team name pattern. Users without a team never match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+
+    // -- split --
+    /// Percentage of matching requests enrolled in the experiment.
+    #[serde(default = "default_traffic_percent")]
+    pub traffic_percent: u8,
+    /// Percentage of enrolled requests given variant `b`.
+    #[serde(default = "default_split_percent")]
+    pub split_percent: u8,
+    /// Keep each end user (or, without one, each gaud user) on one variant.
+    /// Otherwise every request is assigned afresh.
+    #[serde(default = "default_true")]
+    pub sticky: bool,
+
+    pub a: Variant,
+    pub b: Variant,
+}
+
+/// What a variant changes about a request. An empty variant is the
+/// control: the request as sent.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Variant {
+    /// Model to use instead of the requested one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Replaces the request's leading system message, or is put in front
+    /// of a request without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+}
+
+/// The experiment and variant a request was given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Assignment {
+    pub experiment: String,
+    pub variant: String,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+const fn default_traffic_percent() -> u8 {
+    100
+}
+
+const fn default_split_percent() -> u8 {
+    50
+}
+
+impl Experiment {
+    /// Check the experiment is well-formed and compares something.
+    pub fn validate(&self) -> Result<(), String> {
+        if !attribution::is_valid_key(&self.name) {
+            return Err(format!(
+                "Invalid experiment name '{}': use letters, digits, '-' or '_'",
+                self.name
+            ));
+        }
+        if self.traffic_percent > 100 || self.split_percent > 100 {
+            return Err(format!(
+                "Experiment '{}': traffic_percent and split_percent must be 0-100",
+                self.name
+            ));
+        }
+        if self.a == self.b {
+            return Err(format!(
+                "Experiment '{}': variants a and b are the same",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the experiment applies to a request for `model`.
+    pub fn matches(&self, model: &str, user_id: &str, user_name: &str, team: Option<&str>) -> bool {
+        let pattern = |p: &Option<String>, values: &[&str]| match p {
+            None => true,
+            Some(p) => values.iter().any(|v| glob_match(p, v)),
+        };
+        self.enabled
+            && pattern(&self.model, &[model])
+            && pattern(&self.user, &[user_id, user_name])
+            && match (&self.team, team) {
+                (None, _) => true,
+                (Some(p), Some(team)) => glob_match(p, team),
+                (Some(_), None) => false,
+            }
+    }
+
+    /// The variant for a request, or `None` if it is not enrolled. `key`
+    /// is who the request is for; sticky experiments assign by it.
+    pub fn assign(&self, key: &str) -> Option<(&'static str, &Variant)> {
+        let (enrol, split) = if self.sticky {
+            let digest = Sha256::digest(format!("{}\0{key}", self.name));
+            let word =
+                |i: usize| u64::from_be_bytes(digest[i..i + 8].try_into().expect("8 bytes")) % 100;
+            (word(0), word(8))
+        } else {
+            (
+                u64::from(rand::random::<u32>() % 100),
+                u64::from(rand::random::<u32>() % 100),
+            )
+        };
+        if enrol >= u64::from(self.traffic_percent) {
+            return None;
+        }
+        Some(if split < u64::from(self.split_percent) {
+            (VARIANTS[1], &self.b)
+        } else {
+            (VARIANTS[0], &self.a)
+        })
+    }
+}
+
+impl Variant {
+    /// Apply the variant to `request`.
+    pub fn apply(&self, request: &mut ChatRequest) {
+        if let Some(model) = &self.model {
+            request.model = model.clone();
+        }
+        if let Some(prompt) = &self.system_prompt {
+            let content = Some(MessageContent::Text(prompt.clone()));
+            match request.messages.first_mut() {
+                Some(first) if first.role == MessageRole::System => first.content = content,
+                _ => request.messages.insert(
+                    0,
+                    ChatMessage {
+                        role: MessageRole::System,
+                        content,
+                        name: None,
+                        tool_calls: None,
+                        tool_call_id: None,
+                    },
+                ),
+            }
+        }
+    }
+}
+
+/// Put `request` in the first experiment that matches and enrols it,
+/// applying the variant. `key` is who the request is for.
+pub fn enrol(
+    experiments: &[Experiment],
+    request: &mut ChatRequest,
+    user_id: &str,
+    user_name: &str,
+    team: Option<&str>,
+    key: &str,
+) -> Option<Assignment> {
+    experiments.iter().find_map(|experiment| {
+        if !experiment.matches(&request.model, user_id, user_name, team) {
+            return None;
+        }
+        let (name, variant) = experiment.assign(key)?;
+        variant.apply(request);
+        Some(Assignment {
+            experiment: experiment.name.clone(),
+            variant: name.to_string(),
+        })
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Stats
+// ---------------------------------------------------------------------------
+
+/// Usage of one variant of an experiment.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct VariantStats {
+    pub variant: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub cache_hits: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+    /// Mean cost of a successful request.
+    pub avg_cost: f64,
+    /// Latency of successful requests, in milliseconds.
+    pub avg_latency_ms: f64,
+    pub p50_latency_ms: u64,
+    pub p95_latency_ms: u64,
+    /// Requests by finish reason (`none` where there was no completion).
+    pub finish_reasons: std::collections::BTreeMap<String, u64>,
+}
+
+/// Per-variant usage of the experiment `name`, optionally between `from`
+/// and `to` (`YYYY-MM-DD HH:MM:SS`, UTC).
+pub fn stats(
+    db: &Database,
+    name: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> rusqlite::Result<Vec<VariantStats>> {
+    let from = from.unwrap_or("0000");
+    let to = to.unwrap_or("9999");
+    let scope = "experiment = ?1 AND experiment_variant = ?2 AND created_at >= ?3 \
+                 AND created_at <= ?4";
+    db.with_conn(|conn| {
+        let mut out = Vec::new();
+        for variant in VARIANTS {
+            let mut stats = conn.query_row(
+                &format!(
+                    "SELECT COUNT(*),
+                            COALESCE(SUM(status LIKE 'error%'), 0),
+                            COALESCE(SUM(status LIKE '%cache_hit%'), 0),
+                            COALESCE(SUM(input_tokens), 0),
+                            COALESCE(SUM(output_tokens), 0),
+                            COALESCE(SUM(cost), 0.0),
+                            COALESCE(AVG(CASE WHEN status NOT LIKE 'error%' THEN cost END), 0.0),
+                            COALESCE(AVG(CASE WHEN status NOT LIKE 'error%' THEN latency_ms END), 0.0)
+                     FROM usage_log WHERE {scope}"
+                ),
+                params![name, variant, from, to],
+                |row| {
+                    Ok(VariantStats {
+                        variant: variant.to_string(),
+                        requests: row.get::<_, i64>(0)? as u64,
+                        errors: row.get::<_, i64>(1)? as u64,
+                        cache_hits: row.get::<_, i64>(2)? as u64,
+                        input_tokens: row.get::<_, i64>(3)? as u64,
+                        output_tokens: row.get::<_, i64>(4)? as u64,
+                        cost: row.get(5)?,
+                        avg_cost: row.get(6)?,
+                        avg_latency_ms: row.get(7)?,
+                        ..Default::default()
+                    })
+                },
+            )?;
+
+            let successes = stats.requests - stats.errors;
+            let percentile = |p: u64| -> rusqlite::Result<u64> {
+                if successes == 0 {
+                    return Ok(0);
+                }
+                let offset = ((successes - 1) * p / 100) as i64;
+                conn.query_row(
+                    &format!(
+                        "SELECT latency_ms FROM usage_log
+                         WHERE {scope} AND status NOT LIKE 'error%'
+                         ORDER BY latency_ms LIMIT 1 OFFSET ?5"
+                    ),
+                    params![name, variant, from, to, offset],
+                    |row| row.get::<_, i64>(0).map(|v| v as u64),
+                )
+            };
+            stats.p50_latency_ms = percentile(50)?;
+            stats.p95_latency_ms = percentile(95)?;
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT COALESCE(finish_reason, 'none'), COUNT(*) FROM usage_log
+                 WHERE {scope} GROUP BY 1"
+            ))?;
+            let reasons = stmt.query_map(params![name, variant, from, to], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+            })?;
+            stats.finish_reasons = reasons.collect::<Result<_, _>>()?;
+            out.push(stats);
+        }
+        Ok(out)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn experiment() -> Experiment {
+        Experiment {
+            name: "haiku-trial".to_string(),
+            enabled: true,
+            model: Some("claude-*".to_string()),
+            user: None,
+            team: None,
+            traffic_percent: 100,
+            split_percent: 50,
+            sticky: true,
+            a: Variant::default(),
+            b: Variant {
+                model: Some("claude-haiku".to_string()),
+                system_prompt: Some("Be terse.".to_string()),
+            },
+        }
+    }
+
+    fn request(model: &str) -> ChatRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "hi"}],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(experiment().validate().is_ok());
+        let mut bad = experiment();
+        bad.name = "has space".to_string();
+        assert!(bad.validate().is_err());
+        let mut bad = experiment();
+        bad.split_percent = 101;
+        assert!(bad.validate().is_err());
+        let mut bad = experiment();
+        bad.b = Variant::default();
+        assert!(bad.validate().unwrap_err().contains("the same"));
+    }
+
+    #[test]
+    fn test_sticky_assignment_splits_users() {
+        let experiment = experiment();
+        let mut counts = [0; 2];
+        for i in 0..1000 {
+            let key = format!("user-{i}");
+            let (variant, _) = experiment.assign(&key).unwrap();
+            // The same key always gets the same variant.
+            assert_eq!(experiment.assign(&key).unwrap().0, variant);
+            counts[usize::from(variant == "b")] += 1;
+        }
+        assert!((400..600).contains(&counts[1]), "{counts:?}");
+
+        let mut none = experiment.clone();
+        none.traffic_percent = 0;
+        assert!(none.assign("user-1").is_none());
+        let mut all_b = experiment;
+        all_b.split_percent = 100;
+        assert_eq!(all_b.assign("user-1").unwrap().0, "b");
+    }
+
+    #[test]
+    fn test_enrol_applies_variant() {
+        let mut experiment = experiment();
+        experiment.split_percent = 100;
+        let experiments = vec![experiment];
+
+        let mut req = request("claude-sonnet");
+        let assignment = enrol(&experiments, &mut req, "u1", "alice", None, "u1").unwrap();
+        assert_eq!(assignment.experiment, "haiku-trial");
+        assert_eq!(assignment.variant, "b");
+        assert_eq!(req.model, "claude-haiku");
+        assert_eq!(req.messages.len(), 2);
+        assert_eq!(req.messages[0].role, MessageRole::System);
+
+        // A leading system message is replaced, not added to.
+        experiments[0].b.apply(&mut req);
+        assert_eq!(req.messages.len(), 2);
+
+        let mut other = request("gpt-4o");
+        assert!(enrol(&experiments, &mut other, "u1", "alice", None, "u1").is_none());
+        assert_eq!(other.model, "gpt-4o");
+    }
+
+    #[test]
+    fn test_stats_compare_variants() {
+        let db = Database::open_in_memory().unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO users (id, name, role) VALUES ('u1', 'alice', 'member')",
+                [],
+            )
+        })
+        .unwrap();
+        let rows = [
+            ("a", 100, "success", Some("stop")),
+            ("a", 300, "success", Some("length")),
+            ("a", 50, "error: boom", None),
+            ("b", 200, "success", Some("stop")),
+        ];
+        db.with_conn(|conn| {
+            for (i, (variant, latency_ms, status, finish_reason)) in rows.iter().enumerate() {
+                conn.execute(
+                    "INSERT INTO usage_log (id, user_id, request_id, provider, model, \
+                     input_tokens, output_tokens, cost, latency_ms, status, finish_reason, \
+                     experiment, experiment_variant) \
+                     VALUES (?1, 'u1', ?1, 'claude', 'm', 10, 5, 0.5, ?2, ?3, ?4, \
+                     'haiku-trial', ?5)",
+                    params![i.to_string(), latency_ms, status, finish_reason, variant],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        let stats = stats(&db, "haiku-trial", None, None).unwrap();
+        let a = &stats[0];
+        assert_eq!(a.variant, "a");
+        assert_eq!(a.requests, 3);
+        assert_eq!(a.errors, 1);
+        assert_eq!(a.input_tokens, 30);
+        assert_eq!(a.avg_latency_ms, 200.0);
+        assert_eq!(a.p50_latency_ms, 100);
+        assert_eq!(a.p95_latency_ms, 100);
+        assert_eq!(a.finish_reasons["stop"], 1);
+        assert_eq!(a.finish_reasons["length"], 1);
+        assert_eq!(a.finish_reasons["none"], 1);
+        assert_eq!(stats[1].requests, 1);
+        assert_eq!(stats[1].p95_latency_ms, 200);
+    }
+}
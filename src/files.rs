@@ -0,0 +1,301 @@
+//! Uploaded file storage for the `/v1/files` API.
+//!
+//! File metadata always lives in the `files` table. Contents are stored
+//! either inline as a blob in the same row or, when `files.storage_dir` is
+//! configured, as one file per upload in that directory (the row then keeps
+//! the path). Files are scoped to the user that uploaded them.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{OptionalExtension, params};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::AppError;
+
+/// Accepted values for the `purpose` field (OpenAI-compatible).
+pub const PURPOSES: &[&str] = &[
+    "assistants",
+    "batch",
+    "fine-tune",
+    "vision",
+    "user_data",
+    "evals",
+];
+
+/// File metadata returned by the API (OpenAI `file` object).
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FileObject {
+    pub id: String,
+    pub object: &'static str,
+    pub bytes: u64,
+    pub created_at: i64,
+    pub filename: String,
+    pub purpose: String,
+}
+
+/// A file about to be stored.
+#[derive(Debug)]
+pub struct NewFile<'a> {
+    pub filename: &'a str,
+    pub purpose: &'a str,
+    pub content_type: Option<&'a str>,
+    pub data: &'a [u8],
+}
+
+/// File metadata and content storage.
+#[derive(Clone)]
+pub struct FileStore {
+    db: Database,
+    dir: Option<PathBuf>,
+}
+
+impl FileStore {
+    /// Create a store. `dir` selects on-disk storage; `None` keeps contents in
+    /// the database.
+    pub fn new(db: Database, dir: Option<&str>) -> Self {
+        Self {
+            db,
+            dir: dir.map(|d| PathBuf::from(shellexpand::tilde(d).as_ref())),
+        }
+    }
+
+    /// Store a new file for `user_id` and return its metadata.
+    pub fn create(&self, user_id: &str, file: NewFile<'_>) -> Result<FileObject, AppError> {
+        let id = format!("file-{}", Uuid::new_v4().simple());
+        let created_at = chrono::Utc::now().timestamp();
+
+        let (blob, path) = match self.dir {
+            Some(ref dir) => {
+                std::fs::create_dir_all(dir).map_err(|e| {
+                    AppError::Internal(format!("Failed to create file storage dir: {e}"))
+                })?;
+                let path = dir.join(&id);
+                std::fs::write(&path, file.data)
+                    .map_err(|e| AppError::Internal(format!("Failed to write file: {e}")))?;
+                (None, Some(path.display().to_string()))
+            }
+            None => (Some(file.data), None),
+        };
+
+        let inserted = self.db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO files (id, user_id, filename, purpose, content_type, bytes, data, path, created_at) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    id,
+                    user_id,
+                    file.filename,
+                    file.purpose,
+                    file.content_type,
+                    file.data.len() as i64,
+                    blob,
+                    path,
+                    created_at,
+                ],
+            )
+        });
+        if let Err(e) = inserted {
+            // Don't leave an orphaned blob on disk.
+            if let Some(ref path) = path {
+                let _ = std::fs::remove_file(path);
+            }
+            return Err(e.into());
+        }
+
+        Ok(FileObject {
+            id,
+            object: "file",
+            bytes: file.data.len() as u64,
+            created_at,
+            filename: file.filename.to_string(),
+            purpose: file.purpose.to_string(),
+        })
+    }
+
+    /// List a user's files, newest first, optionally filtered by purpose.
+    pub fn list(&self, user_id: &str, purpose: Option<&str>) -> Result<Vec<FileObject>, AppError> {
+        let files = self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, bytes, created_at, filename, purpose FROM files \
+                 WHERE user_id = ?1 AND (?2 IS NULL OR purpose = ?2) \
+                 ORDER BY created_at DESC, id",
+            )?;
+            let rows = stmt.query_map(params![user_id, purpose], file_from_row)?;
+            rows.collect::<Result<Vec<_>, _>>()
+        })?;
+        Ok(files)
+    }
+
+    /// Look up a single file's metadata.
+    pub fn get(&self, user_id: &str, id: &str) -> Result<FileObject, AppError> {
+        self.db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT id, bytes, created_at, filename, purpose FROM files \
+                     WHERE id = ?1 AND user_id = ?2",
+                    params![id, user_id],
+                    file_from_row,
+                )
+                .optional()
+            })?
+            .ok_or_else(|| not_found(id))
+    }
+
+    /// Read a file's contents, returning them with the stored content type.
+    pub fn content(&self, user_id: &str, id: &str) -> Result<(Vec<u8>, Option<String>), AppError> {
+        let row = self.db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT data, path, content_type FROM files WHERE id = ?1 AND user_id = ?2",
+                params![id, user_id],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<Vec<u8>>>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                },
+            )
+            .optional()
+        })?;
+
+        let (data, path, content_type) = row.ok_or_else(|| not_found(id))?;
+        let data = match (data, path) {
+            (Some(data), _) => data,
+            (None, Some(path)) => std::fs::read(Path::new(&path))
+                .map_err(|e| AppError::Internal(format!("Failed to read file {id}: {e}")))?,
+            (None, None) => Vec::new(),
+        };
+        Ok((data, content_type))
+    }
+
+    /// Delete every file of a user with its contents, returning how many
+    /// there were.
+    pub fn delete_all(&self, user_id: &str) -> Result<usize, AppError> {
+        let paths = self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare("SELECT path FROM files WHERE user_id = ?1")?;
+            let paths = stmt
+                .query_map(params![user_id], |row| row.get::<_, Option<String>>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            conn.execute("DELETE FROM files WHERE user_id = ?1", params![user_id])?;
+            Ok(paths)
+        })?;
+        for path in paths.iter().flatten() {
+            if let Err(e) = std::fs::remove_file(path) {
+                tracing::warn!(path = %path, error = %e, "Failed to remove file contents");
+            }
+        }
+        Ok(paths.len())
+    }
+
+    /// Delete a file and its contents.
+    pub fn delete(&self, user_id: &str, id: &str) -> Result<(), AppError> {
+        let path = self.db.with_conn(|conn| {
+            let path: Option<Option<String>> = conn
+                .query_row(
+                    "SELECT path FROM files WHERE id = ?1 AND user_id = ?2",
+                    params![id, user_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if path.is_some() {
+                conn.execute(
+                    "DELETE FROM files WHERE id = ?1 AND user_id = ?2",
+                    params![id, user_id],
+                )?;
+            }
+            Ok(path)
+        })?;
+
+        match path {
+            None => Err(not_found(id)),
+            Some(Some(path)) => {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    tracing::warn!(file_id = %id, path = %path, error = %e, "Failed to remove file contents");
+                }
+                Ok(())
+            }
+            Some(None) => Ok(()),
+        }
+    }
+}
+
+fn file_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FileObject> {
+    Ok(FileObject {
+        id: row.get(0)?,
+        object: "file",
+        bytes: row.get::<_, i64>(1)? as u64,
+        created_at: row.get(2)?,
+        filename: row.get(3)?,
+        purpose: row.get(4)?,
+    })
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("No such file: '{id}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_file<'a>(data: &'a [u8], purpose: &'a str) -> NewFile<'a> {
+        NewFile {
+            filename: "input.jsonl",
+            purpose,
+            content_type: Some("application/jsonl"),
+            data,
+        }
+    }
+
+    fn roundtrip(store: &FileStore) {
+        let created = store.create("user1", new_file(b"hello", "batch")).unwrap();
+        assert!(created.id.starts_with("file-"));
+        assert_eq!(created.bytes, 5);
+
+        store.create("user1", new_file(b"{}", "user_data")).unwrap();
+        store.create("user2", new_file(b"other", "batch")).unwrap();
+
+        assert_eq!(store.list("user1", None).unwrap().len(), 2);
+        assert_eq!(
+            store.list("user1", Some("batch")).unwrap(),
+            vec![created.clone()]
+        );
+        assert_eq!(store.get("user1", &created.id).unwrap(), created);
+        assert!(matches!(
+            store.get("user2", &created.id),
+            Err(AppError::NotFound(_))
+        ));
+
+        let (data, content_type) = store.content("user1", &created.id).unwrap();
+        assert_eq!(data, b"hello");
+        assert_eq!(content_type.as_deref(), Some("application/jsonl"));
+
+        store.delete("user1", &created.id).unwrap();
+        assert!(matches!(
+            store.delete("user1", &created.id),
+            Err(AppError::NotFound(_))
+        ));
+        assert_eq!(store.list("user1", None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_database_storage() {
+        let store = FileStore::new(Database::open_in_memory().unwrap(), None);
+        roundtrip(&store);
+    }
+
+    #[test]
+    fn test_directory_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(
+            Database::open_in_memory().unwrap(),
+            Some(dir.path().to_str().unwrap()),
+        );
+        roundtrip(&store);
+
+        // Only the two remaining files are left on disk.
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+}
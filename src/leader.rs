@@ -0,0 +1,168 @@
+//! Leader election for periodic background jobs.
+//!
+//! When several gaud processes open the same database, jobs that write
+//! shared state (OAuth token refresh, LiteLLM reconciliation, chargeback
+//! reports, eviction of a shared cache) should run in only one of them.
+//! Each process tries to hold the single row of `leader_lease`; the holder
+//! renews it every third of the lease and another process takes over once
+//! it lapses. With `database.leader_election` off every process leads.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use rusqlite::params;
+
+use crate::db::Database;
+use crate::error::AppError;
+
+/// Name of the lease row covering all background jobs.
+const LEASE_NAME: &str = "background";
+
+/// This process's claim on the background-job lease.
+pub struct Leader {
+    /// `None` when election is disabled.
+    db: Option<Database>,
+    instance_id: String,
+    lease: Duration,
+    leading: AtomicBool,
+}
+
+impl Leader {
+    /// Election disabled: this process always leads.
+    pub fn always() -> Self {
+        Self {
+            db: None,
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            lease: Duration::ZERO,
+            leading: AtomicBool::new(true),
+        }
+    }
+
+    /// Compete for the lease in `db`. Call [`Leader::try_acquire`] (or
+    /// [`spawn_lease_renewal`]) before relying on [`Leader::is_leader`].
+    pub fn elected(db: Database, lease: Duration) -> Self {
+        Self {
+            db: Some(db),
+            instance_id: uuid::Uuid::new_v4().to_string(),
+            lease,
+            leading: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether this process should run leader-only jobs right now.
+    pub fn is_leader(&self) -> bool {
+        self.leading.load(Ordering::Relaxed)
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Take the lease if it is free or expired, or renew it if this process
+    /// holds it. Returns whether this process leads afterwards.
+    pub fn try_acquire(&self) -> Result<bool, AppError> {
+        self.try_acquire_at(chrono::Utc::now().timestamp_millis())
+    }
+
+    fn try_acquire_at(&self, now_ms: i64) -> Result<bool, AppError> {
+        let Some(db) = &self.db else {
+            return Ok(true);
+        };
+        let expires_at = now_ms + self.lease.as_millis() as i64;
+        let changed = db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO leader_lease (name, holder, expires_at) VALUES (?1, ?2, ?3) \
+                 ON CONFLICT(name) DO UPDATE SET holder = excluded.holder, expires_at = excluded.expires_at \
+                 WHERE leader_lease.holder = excluded.holder OR leader_lease.expires_at <= ?4",
+                params![LEASE_NAME, self.instance_id, expires_at, now_ms],
+            )
+        })?;
+        let leading = changed > 0;
+        let was_leading = self.leading.swap(leading, Ordering::Relaxed);
+        if leading && !was_leading {
+            tracing::info!(instance = %self.instance_id, "Became leader for background jobs");
+        } else if !leading && was_leading {
+            tracing::warn!(instance = %self.instance_id, "Lost background job leadership");
+        }
+        Ok(leading)
+    }
+
+    /// Give the lease up so another process can take over without waiting
+    /// for it to expire.
+    pub fn release(&self) {
+        let Some(db) = &self.db else {
+            return;
+        };
+        self.leading.store(false, Ordering::Relaxed);
+        let result = db.with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM leader_lease WHERE name = ?1 AND holder = ?2",
+                params![LEASE_NAME, self.instance_id],
+            )
+        });
+        if let Err(e) = result {
+            tracing::warn!(error = %e, "Failed to release background job lease");
+        }
+    }
+}
+
+/// Renew (or try to take) the lease every third of its duration. A
+/// database error counts as losing it, so two processes never both lead.
+pub fn spawn_lease_renewal(leader: Arc<Leader>) -> Option<tokio::task::JoinHandle<()>> {
+    leader.db.as_ref()?;
+    let interval = (leader.lease / 3).max(Duration::from_secs(1));
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = leader.try_acquire() {
+                tracing::warn!(error = %e, "Failed to renew background job lease");
+                leader.leading.store(false, Ordering::Relaxed);
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_election_always_leads() {
+        let leader = Leader::always();
+        assert!(leader.is_leader());
+        assert!(leader.try_acquire().unwrap());
+    }
+
+    #[test]
+    fn test_single_leader_and_takeover() {
+        let db = Database::open_in_memory().unwrap();
+        let a = Leader::elected(db.clone(), Duration::from_secs(30));
+        let b = Leader::elected(db.clone(), Duration::from_secs(30));
+
+        assert!(a.try_acquire_at(1_000).unwrap());
+        assert!(!b.try_acquire_at(2_000).unwrap());
+        assert!(!b.is_leader());
+        // The holder renews.
+        assert!(a.try_acquire_at(20_000).unwrap());
+        // Still held until 50s.
+        assert!(!b.try_acquire_at(45_000).unwrap());
+        // Lapsed: b takes over and a loses it on its next attempt.
+        assert!(b.try_acquire_at(50_000).unwrap());
+        assert!(!a.try_acquire_at(51_000).unwrap());
+        assert!(!a.is_leader());
+    }
+
+    #[test]
+    fn test_release_hands_over_immediately() {
+        let db = Database::open_in_memory().unwrap();
+        let a = Leader::elected(db.clone(), Duration::from_secs(30));
+        let b = Leader::elected(db.clone(), Duration::from_secs(30));
+        assert!(a.try_acquire_at(1_000).unwrap());
+        a.release();
+        assert!(!a.is_leader());
+        assert!(b.try_acquire_at(2_000).unwrap());
+    }
+}
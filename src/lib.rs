@@ -9,14 +9,27 @@ pub mod api;
 pub mod auth;
 pub mod budget;
 pub mod cache;
+pub mod cli;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod experiments;
+pub mod files;
+pub mod leader;
+pub mod loadtest;
+pub mod mcp;
+pub mod net;
+pub mod notify;
 pub mod oauth;
+pub mod observability;
 pub mod providers;
+pub mod settings;
+pub mod tls;
+pub mod uploads;
 
 pub mod web;
 
+use crate::api::resume::StreamRegistry;
 use crate::budget::BudgetTracker;
 use crate::cache::SemanticCacheService;
 use crate::config::Config;
@@ -28,6 +41,7 @@ use crate::providers::router::ProviderRouter;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tokio_util::task::TaskTracker;
 
 /// Shared application state accessible from all handlers.
 #[derive(Clone)]
@@ -41,4 +55,30 @@ pub struct AppState {
     pub cost_calculator: Arc<CostCalculator>,
     pub cache: Option<Arc<SemanticCacheService>>,
     pub oauth_manager: Arc<OAuthManager>,
+    /// Builds providers from `[providers.*]` sections, for rebuilding one
+    /// when its settings change.
+    pub providers: Arc<providers::factory::ProviderFactory>,
+    /// Open MCP HTTP+SSE sessions (`GET /mcp/sse`).
+    pub mcp_sessions: Arc<api::mcp::McpSessions>,
+    /// External MCP servers whose tools are attached to requests
+    /// (`[mcp.servers]`).
+    pub mcp_tools: Arc<mcp::client::McpToolServers>,
+    /// Buffers of resumable SSE streams (see `cache.stream_resume_enabled`).
+    pub stream_registry: Arc<StreamRegistry>,
+    /// Recently completed requests, for the web UI's live log.
+    pub request_tail: Arc<budget::tail::RequestTail>,
+    /// Open streaming responses per user and key (`[limits]`).
+    pub stream_limiter: Arc<budget::streams::StreamLimiter>,
+    /// Fair queue for upstream chat requests (`limits.max_in_flight`).
+    pub scheduler: Arc<budget::scheduler::FairScheduler>,
+    /// Settings changed at runtime through `/admin/settings`. Read runtime
+    /// settings (`config::RUNTIME_SETTINGS`) here rather than from `config`.
+    pub settings: Arc<settings::RuntimeSettings>,
+    /// Failed-login counters and lockouts.
+    pub login_throttle: Arc<auth::throttle::LoginThrottle>,
+    /// Operational alerts to Slack / Discord (`[notifications]`).
+    pub notifier: Arc<notify::Notifier>,
+    /// Background work that must finish before shutdown (cache writes,
+    /// resumable stream pumps).
+    pub background: TaskTracker,
 }
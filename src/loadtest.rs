@@ -0,0 +1,388 @@
+//! `gaud loadtest`: drive a running server at a fixed request rate.
+//!
+//! Requests go to `/v1/chat/completions` on an open-loop schedule: the
+//! `n`th request is due at `n / rps` seconds whether or not earlier ones
+//! have finished, and its latency is measured from when it was due, so a
+//! server that falls behind shows up in the percentiles instead of
+//! silently slowing the test down. Bodies come from a built-in prompt mix
+//! or from a file of recorded request bodies.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use serde_json::{Value, json};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::cli::Options;
+use crate::config::Config;
+
+/// Synthetic prompts, from a one-line question to a longer coding task.
+/// Repeats are deliberate so the semantic cache sees realistic hits.
+const PROMPTS: &[&str] = &[
+    "What is the capital of France?",
+    "Explain the difference between a process and a thread in two sentences.",
+    "Write a Rust function that returns the nth Fibonacci number iteratively.",
+    "What is the capital of France?",
+    "Summarise the main causes of the French Revolution in one paragraph.",
+    "List three ways to reduce tail latency in a web service.",
+];
+
+/// Where requests come from.
+enum Workload {
+    /// Cycle through [`PROMPTS`] and the given models.
+    Synthetic { models: Vec<String> },
+    /// Cycle through recorded request bodies, optionally forcing the model.
+    Replay {
+        bodies: Vec<Value>,
+        models: Vec<String>,
+    },
+}
+
+impl Workload {
+    fn body(&self, n: usize) -> Value {
+        match self {
+            Self::Synthetic { models } => json!({
+                "model": models[n % models.len()],
+                "messages": [{ "role": "user", "content": PROMPTS[n % PROMPTS.len()] }],
+                "max_tokens": 256,
+            }),
+            Self::Replay { bodies, models } => {
+                let mut body = bodies[n % bodies.len()].clone();
+                if !models.is_empty() {
+                    body["model"] = json!(models[n % models.len()]);
+                }
+                body
+            }
+        }
+    }
+}
+
+/// One finished request.
+struct Sample {
+    /// `None` when the request failed before a response arrived.
+    status: Option<u16>,
+    /// From the scheduled send time to the end of the response body.
+    latency: Duration,
+    /// From the scheduled send time to the response headers.
+    first_byte: Duration,
+}
+
+struct Plan {
+    url: String,
+    api_key: Option<String>,
+    rps: f64,
+    duration: Duration,
+    concurrency: usize,
+    workload: Workload,
+}
+
+/// Run a load test. `args` are the arguments after `loadtest`.
+pub async fn run(config: &Config, args: &[String], out: &mut impl Write) -> anyhow::Result<()> {
+    let plan = parse(config, args)?;
+    let total = (plan.rps * plan.duration.as_secs_f64()).round() as usize;
+    writeln!(
+        out,
+        "Sending {total} requests to {} at {} req/s",
+        plan.url, plan.rps
+    )?;
+
+    let http = reqwest::Client::new();
+    let endpoint = format!("{}/v1/chat/completions", plan.url);
+    let slots = Arc::new(Semaphore::new(plan.concurrency));
+    let mut tasks = JoinSet::new();
+    let mut skipped = 0usize;
+    let start = Instant::now();
+
+    for n in 0..total {
+        let due = start + Duration::from_secs_f64(n as f64 / plan.rps);
+        tokio::time::sleep_until(due).await;
+        // Past the concurrency cap the server is already far behind; count
+        // the request instead of queueing it.
+        let Ok(slot) = slots.clone().try_acquire_owned() else {
+            skipped += 1;
+            continue;
+        };
+        let mut request = http.post(&endpoint).json(&plan.workload.body(n));
+        if let Some(key) = &plan.api_key {
+            request = request.bearer_auth(key);
+        }
+        tasks.spawn(async move {
+            let _slot = slot;
+            match request.send().await {
+                Ok(response) => {
+                    let first_byte = due.elapsed();
+                    let status = response.status().as_u16();
+                    let complete = response.bytes().await.is_ok();
+                    Sample {
+                        status: complete.then_some(status),
+                        latency: due.elapsed(),
+                        first_byte,
+                    }
+                }
+                Err(_) => Sample {
+                    status: None,
+                    latency: due.elapsed(),
+                    first_byte: due.elapsed(),
+                },
+            }
+        });
+    }
+
+    let mut samples = Vec::with_capacity(total);
+    while let Some(sample) = tasks.join_next().await {
+        samples.extend(sample.ok());
+    }
+    report(
+        &samples,
+        skipped,
+        start.elapsed(),
+        plan.rps,
+        plan.concurrency,
+        out,
+    )
+}
+
+fn parse(config: &Config, args: &[String]) -> anyhow::Result<Plan> {
+    let mut opts = Options::parse(args)?;
+    let mut models = Vec::new();
+    while let Some(model) = opts.take("--model") {
+        models.push(model);
+    }
+    let replay = opts.take("--replay");
+    let rps = number(opts.take("--rps"), "--rps", 5.0)?;
+    let duration = number(opts.take("--duration"), "--duration", 30.0)?;
+    let concurrency = number(opts.take("--concurrency"), "--concurrency", 256.0)? as usize;
+    let url = opts
+        .take("--url")
+        .unwrap_or_else(|| local_url(config))
+        .trim_end_matches('/')
+        .to_string();
+    let api_key = opts
+        .take("--key")
+        .or_else(|| std::env::var("GAUD_API_KEY").ok())
+        .filter(|k| !k.is_empty());
+    opts.finish()?;
+
+    if api_key.is_none() && config.auth.enabled {
+        bail!("an API key is required: pass --key or set GAUD_API_KEY");
+    }
+    let workload = match replay {
+        Some(path) => Workload::Replay {
+            bodies: read_bodies(Path::new(&path))?,
+            models,
+        },
+        None if models.is_empty() => bail!("nothing to send: pass --model or --replay"),
+        None => Workload::Synthetic { models },
+    };
+    Ok(Plan {
+        url,
+        api_key,
+        rps,
+        duration: Duration::from_secs_f64(duration),
+        concurrency: concurrency.max(1),
+        workload,
+    })
+}
+
+/// A positive number option.
+fn number(value: Option<String>, name: &str, default: f64) -> anyhow::Result<f64> {
+    let Some(value) = value else {
+        return Ok(default);
+    };
+    match value.parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => bail!("{name} must be a positive number"),
+    }
+}
+
+/// The server from the config file, reached over loopback.
+pub(crate) fn local_url(config: &Config) -> String {
+    let server = &config.server;
+    let scheme = if server.tls_cert_path.is_some() || server.acme.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let host = match server.host.as_str() {
+        "" | "0.0.0.0" => "127.0.0.1",
+        "::" => "[::1]",
+        host => host,
+    };
+    format!("{scheme}://{host}:{}", server.port)
+}
+
+/// Request bodies, one JSON object per line; blank lines are skipped.
+fn read_bodies(path: &Path) -> anyhow::Result<Vec<Value>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let bodies = raw
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str::<Value>(line)
+                .ok()
+                .filter(Value::is_object)
+                .with_context(|| format!("{}:{}: not a JSON object", path.display(), i + 1))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if bodies.is_empty() {
+        bail!("{} contains no requests", path.display());
+    }
+    Ok(bodies)
+}
+
+fn report(
+    samples: &[Sample],
+    skipped: usize,
+    elapsed: Duration,
+    rps: f64,
+    concurrency: usize,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let ok = samples
+        .iter()
+        .filter(|s| s.status.is_some_and(|c| c < 400))
+        .count();
+    let mut statuses = BTreeMap::new();
+    for sample in samples {
+        let key = sample
+            .status
+            .map_or_else(|| "error".to_string(), |c| c.to_string());
+        *statuses.entry(key).or_insert(0usize) += 1;
+    }
+
+    writeln!(
+        out,
+        "Requests:   {} sent, {ok} succeeded, {} failed, {skipped} skipped (over {concurrency} in flight)",
+        samples.len(),
+        samples.len() - ok
+    )?;
+    writeln!(
+        out,
+        "Throughput: {:.2} req/s (target {rps}) over {:.1}s",
+        ok as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        elapsed.as_secs_f64()
+    )?;
+    let statuses: Vec<String> = statuses.iter().map(|(s, n)| format!("{s}: {n}")).collect();
+    writeln!(out, "Status:     {}", statuses.join(", "))?;
+
+    let mut latency: Vec<Duration> = samples.iter().map(|s| s.latency).collect();
+    let mut first_byte: Vec<Duration> = samples.iter().map(|s| s.first_byte).collect();
+    latency.sort();
+    first_byte.sort();
+    writeln!(out, "Latency:    {}", distribution(&latency))?;
+    writeln!(out, "First byte: {}", distribution(&first_byte))?;
+    Ok(())
+}
+
+/// Percentiles of sorted durations, in milliseconds.
+fn distribution(sorted: &[Duration]) -> String {
+    if sorted.is_empty() {
+        return "-".to_string();
+    }
+    let ms = |p: f64| percentile(sorted, p).as_secs_f64() * 1000.0;
+    format!(
+        "p50 {:.0}ms, p90 {:.0}ms, p99 {:.0}ms, max {:.0}ms",
+        ms(50.0),
+        ms(90.0),
+        ms(99.0),
+        ms(100.0)
+    )
+}
+
+/// Nearest-rank percentile of a non-empty sorted slice.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let sorted: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&sorted, 50.0), Duration::from_millis(5));
+        assert_eq!(percentile(&sorted, 90.0), Duration::from_millis(9));
+        assert_eq!(percentile(&sorted, 99.0), Duration::from_millis(10));
+        assert_eq!(percentile(&sorted, 0.0), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        let config = Config::default();
+        let err = |a: &[&str]| parse(&config, &args(a)).err().unwrap().to_string();
+        assert!(err(&["--model", "m"]).contains("API key is required"));
+        assert!(err(&["--key", "k"]).contains("nothing to send"));
+        assert!(err(&["--key", "k", "--model", "m", "--rps", "0"]).contains("--rps"));
+        assert!(err(&["--key", "k", "--replay", "/nonexistent"]).contains("failed to read"));
+    }
+
+    #[test]
+    fn test_workload_cycles_models_and_bodies() {
+        let synthetic = Workload::Synthetic {
+            models: vec!["a".into(), "b".into()],
+        };
+        assert_eq!(synthetic.body(0)["model"], "a");
+        assert_eq!(synthetic.body(1)["model"], "b");
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("traffic.jsonl");
+        std::fs::write(
+            &file,
+            "{\"model\":\"x\",\"messages\":[]}\n\n{\"model\":\"y\",\"messages\":[]}\n",
+        )
+        .unwrap();
+        let replay = Workload::Replay {
+            bodies: read_bodies(&file).unwrap(),
+            models: Vec::new(),
+        };
+        assert_eq!(replay.body(1)["model"], "y");
+        assert_eq!(replay.body(2)["model"], "x");
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_throughput_and_latency() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+            .expect(10)
+            .mount(&server)
+            .await;
+
+        let mut out = Vec::new();
+        let a = args(&[
+            "--url",
+            &server.uri(),
+            "--key",
+            "sk-test",
+            "--model",
+            "m",
+            "--rps",
+            "50",
+            "--duration",
+            "0.2",
+        ]);
+        run(&Config::default(), &a, &mut out).await.unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("10 sent, 10 succeeded, 0 failed, 0 skipped"));
+        assert!(out.contains("Status:     200: 10"));
+        assert!(out.contains("Latency:    p50 "));
+    }
+}
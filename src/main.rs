@@ -6,18 +6,24 @@
 //!   - Admin user bootstrapping
 //!   - Provider router creation
 //!   - Budget tracker + audit logger
-//!   - Combined HTTP server (API + Web UI)
+//!   - Combined HTTP server (API + Web UI), optionally over TLS
 //!   - Graceful shutdown on SIGTERM / SIGINT
 
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 use axum::Router;
+use axum::body::Body;
+use axum::extract::DefaultBodyLimit;
 use axum::middleware;
+use axum::routing::get;
 use tokio::net::TcpListener;
 use tokio::signal;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, watch};
+use tokio::time::Instant;
+use tokio_util::task::TaskTracker;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
@@ -25,30 +31,55 @@ use tracing_subscriber::EnvFilter;
 
 use gaud::AppState;
 use gaud::api;
-use gaud::auth::middleware::require_auth;
-use gaud::auth::users::bootstrap_admin;
-use gaud::budget::{BudgetTracker, spawn_audit_logger};
+use gaud::auth::middleware::{debug_errors, require_auth};
+use gaud::auth::provisioning;
+use gaud::auth::throttle::LoginThrottle;
+use gaud::auth::users::{bootstrap_admin, spawn_stale_key_sweep};
+use gaud::budget::tail::RequestTail;
+use gaud::budget::{BudgetTracker, chargeback, quota_middleware, spawn_audit_logger};
 use gaud::cache::SemanticCacheService;
-use gaud::config::{Config, KiroProviderConfig, LitellmProviderConfig};
+use gaud::cli;
+use gaud::config::{Config, RouteGroup, TlsClientCertConfig};
 use gaud::db::Database;
-use gaud::oauth::OAuthManager;
-use gaud::providers::LlmProvider;
-use gaud::providers::kiro::KiroProvider;
-use gaud::providers::litellm::{LitellmConfig, LitellmProvider};
+use gaud::error;
+use gaud::leader::{self, Leader};
+use gaud::loadtest;
+use gaud::mcp;
+use gaud::mcp::client::McpToolServers;
+use gaud::net::HttpClientFactory;
+use gaud::notify::Notifier;
+use gaud::oauth::{OAuthManager, spawn_token_refresh};
+use gaud::observability;
+use gaud::providers::cost::{self, CostCalculator, spawn_pricing_refresh};
+use gaud::providers::factory::{self, ProviderFactory};
+use gaud::providers::gemini::GLOBAL_SIGNATURE_CACHE;
+use gaud::providers::pricing::{self, ExchangeRateSource, PricingSource};
 use gaud::providers::router::ProviderRouter;
+use gaud::settings::{self, RuntimeSettings};
+use gaud::tls::{TlsListener, TlsPeer};
 use gaud::web;
 
+/// Minimum time the audit logger gets to flush on shutdown.
+const AUDIT_FLUSH_GRACE: Duration = Duration::from_secs(5);
+
+/// Pause between warm-up attempts at providers that failed.
+const WARMUP_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
 // ---------------------------------------------------------------------------
 // CLI argument parsing (minimal, no clap dependency)
 // ---------------------------------------------------------------------------
 
 struct CliArgs {
     config_path: PathBuf,
+    /// Admin subcommand and its arguments (`gaud user list`), empty to
+    /// run the server.
+    command: Vec<String>,
 }
 
 fn parse_args() -> CliArgs {
     let mut args = std::env::args().skip(1);
     let mut config_path = PathBuf::from("llm-proxy.toml");
+    let mut command = Vec::new();
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -64,10 +95,18 @@ fn parse_args() -> CliArgs {
                 print_usage();
                 std::process::exit(0);
             }
+            "--generate-key" => {
+                print_generated_key();
+                std::process::exit(0);
+            }
             "--version" | "-V" => {
                 println!("gaud {}", env!("CARGO_PKG_VERSION"));
                 std::process::exit(0);
             }
+            other if cli::COMMANDS.contains(&other) => {
+                command.push(arg);
+                command.extend(args.by_ref());
+            }
             other => {
                 eprintln!("Unknown argument: {other}");
                 eprintln!("Run with --help for usage information.");
@@ -76,7 +115,10 @@ fn parse_args() -> CliArgs {
         }
     }
 
-    CliArgs { config_path }
+    CliArgs {
+        config_path,
+        command,
+    }
 }
 
 fn print_usage() {
@@ -86,20 +128,43 @@ gaud {version} -- Multi-user LLM proxy
 
 USAGE:
     gaud [OPTIONS]
+    gaud [OPTIONS] <SUBCOMMAND>
 
 OPTIONS:
     -c, --config <PATH>    Path to configuration file [default: llm-proxy.toml]
+        --generate-key     Generate an API key and print its hash for [provisioning]
     -h, --help             Print this help message
     -V, --version          Print version information
 
+{subcommands}
 ENVIRONMENT:
     RUST_LOG               Override log level (e.g. RUST_LOG=debug)
     GAUD_CONFIG            Alternative to --config flag
 ",
-        version = env!("CARGO_PKG_VERSION")
+        version = env!("CARGO_PKG_VERSION"),
+        subcommands = cli::USAGE,
     );
 }
 
+/// Print a new API key with the hash and prefix to declare it under
+/// `[[provisioning.users.keys]]`. Nothing is stored.
+fn print_generated_key() {
+    match gaud::auth::keys::generate_api_key() {
+        Ok(key) => {
+            println!("API key (save it now, it is not stored): {}", key.plaintext);
+            println!();
+            println!("# Declare it under the user it belongs to:");
+            println!("[[provisioning.users.keys]]");
+            println!("hash = '{}'", key.hash);
+            println!("prefix = \"{}\"", key.prefix);
+        }
+        Err(e) => {
+            eprintln!("Error: failed to generate key: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Main
 // ---------------------------------------------------------------------------
@@ -123,7 +188,27 @@ async fn async_main() -> anyhow::Result<()> {
         .unwrap_or(cli.config_path);
 
     // 2. Load configuration
-    let config = Config::load(&config_path)?;
+    let mut config = Config::load(&config_path)?;
+
+    // Admin subcommands work on the database and exit; `loadtest` and `mcp`
+    // talk to a running server.
+    if !cli.command.is_empty() {
+        let mut stdout = std::io::stdout();
+        let result = if cli.command[0] == "loadtest" {
+            loadtest::run(&config, &cli.command[1..], &mut stdout).await
+        } else if cli.command[0] == "mcp" {
+            let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+            mcp::stdio::run(&config, &cli.command[1..], stdin, &mut stdout).await
+        } else {
+            let db = Database::open(&config.database.path)?;
+            cli::run(&db, &config.pricing.currency, &cli.command, &mut stdout)
+        };
+        if let Err(e) = result {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     // 3. Initialize tracing/logging
     init_tracing(&config);
@@ -138,7 +223,49 @@ async fn async_main() -> anyhow::Result<()> {
     let db = Database::open(&config.database.path)?;
     tracing::info!(path = %config.database.path.display(), "Database opened");
 
-    // 5. Bootstrap admin user (creates admin + prints API key on first run)
+    // Settings changed at runtime through the admin API win over the file.
+    settings::apply_stored(&mut config, &db)?;
+
+    // Thinking signatures outlive restarts and are shared between instances.
+    GLOBAL_SIGNATURE_CACHE.attach_database(db.clone());
+
+    // 4b. Elect the process that runs jobs writing shared state (token
+    //     refresh, reconciliation, reports, shared cache eviction)
+    let leader = if config.database.leader_election {
+        let leader = Arc::new(Leader::elected(db.clone(), config.database.leader_lease()));
+        if let Err(e) = leader.try_acquire() {
+            tracing::warn!(error = %e, "Failed to acquire background job lease");
+        }
+        leader::spawn_lease_renewal(leader.clone());
+        tracing::info!(
+            instance = %leader.instance_id(),
+            leading = leader.is_leader(),
+            "Leader election enabled"
+        );
+        leader
+    } else {
+        Arc::new(Leader::always())
+    };
+
+    // 5. Apply declared users and keys, then bootstrap the admin user
+    //    (creates admin + prints API key on first run without any users)
+    if !config.provisioning.is_empty() {
+        let tracker = BudgetTracker::new(db.clone());
+        match provisioning::reconcile(&db, &tracker, &config.provisioning) {
+            Ok(summary) => tracing::info!(
+                users_created = summary.users_created,
+                users_updated = summary.users_updated,
+                keys_added = summary.keys_added,
+                keys_revoked = summary.keys_revoked,
+                "Provisioning applied"
+            ),
+            Err(err) => {
+                tracing::error!(error = %err, "Failed to apply [provisioning]");
+                return Err(err.into());
+            }
+        }
+    }
+
     match bootstrap_admin(&db, &config.auth.default_admin_name) {
         Ok(Some(result)) => {
             tracing::info!(
@@ -155,10 +282,61 @@ async fn async_main() -> anyhow::Result<()> {
         }
     }
 
-    // 5b. Create OAuth manager (needed for provider registration)
+    // 5b. Create the notifier and the OAuth manager (needed for provider
+    //     registration)
+    let http = HttpClientFactory::new(&config.providers);
+    let notifier = Arc::new(Notifier::new(
+        &config.notifications,
+        http.client("notifications"),
+    ));
+    if notifier.is_enabled() {
+        tracing::info!("Operational notifications enabled");
+    }
     let config_arc = Arc::new(config.clone());
-    let oauth_manager = Arc::new(OAuthManager::from_config(config_arc.clone(), db.clone()));
+    let oauth_manager = Arc::new(
+        OAuthManager::from_config(config_arc.clone(), db.clone()).with_notifier(notifier.clone()),
+    );
     tracing::debug!("OAuth manager initialized");
+    if let Some(interval) = config.providers.token_refresh_interval() {
+        spawn_token_refresh(oauth_manager.clone(), interval, leader.clone());
+        tracing::debug!(
+            interval_secs = interval.as_secs(),
+            "Token refresh task spawned"
+        );
+    }
+
+    // 5c. Load model prices, exchange rate and cost markups
+    let mut cost_calculator = CostCalculator::new().with_currency(
+        &config.pricing.currency,
+        config.pricing.exchange_rate.unwrap_or(1.0),
+    );
+    if let Some(source) = PricingSource::from_config(&config.pricing, http.client("pricing")) {
+        cost_calculator = cost_calculator.with_source(source);
+    }
+    if let Some(source) = ExchangeRateSource::from_config(&config.pricing, http.client("pricing")) {
+        cost_calculator = cost_calculator.with_exchange_rate_source(source);
+    }
+    match pricing::load_overrides(&db) {
+        Ok(overrides) => {
+            for pricing in overrides {
+                cost_calculator.set_override(pricing);
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "Failed to load price overrides"),
+    }
+    match cost::load_markups(&db) {
+        Ok(markups) => {
+            for (scope, subject, markup) in markups {
+                cost_calculator.set_markup(scope, &subject, markup);
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "Failed to load cost markups"),
+    }
+    let cost_calculator = Arc::new(cost_calculator);
+    if cost_calculator.has_source() || cost_calculator.has_exchange_rate_source() {
+        spawn_pricing_refresh(cost_calculator.clone(), config.pricing.refresh_interval());
+        tracing::debug!("Pricing refresh task spawned");
+    }
 
     // 6. Create provider router
     //
@@ -168,39 +346,52 @@ async fn async_main() -> anyhow::Result<()> {
     //    here and let it be populated once the OAuth/token infrastructure is
     //    ready. The router is behind an Arc<RwLock<>> so it can be updated.
     let mut provider_router = ProviderRouter::new();
-
-    // Register Kiro provider if configured.
-    if let Some(ref kiro_config) = config.providers.kiro {
-        match build_kiro_provider(kiro_config).await {
-            Ok(provider) => {
-                provider_router.register(Arc::new(provider));
-                tracing::info!("Kiro provider registered");
-            }
-            Err(e) => {
-                tracing::warn!(error = %e, "Failed to initialize Kiro provider, skipping");
-            }
+    provider_router.set_notifier(notifier.clone());
+    provider_router.set_virtual_models(config.providers.virtual_models.clone());
+    provider_router.set_rules(config.providers.rules.clone());
+    provider_router.set_retry_policy(config.providers.retry.policy());
+
+    // Register the configured providers in priority order. The factory is
+    // kept so the admin API can rebuild a provider section in place.
+    let provider_factory = Arc::new(ProviderFactory::new(
+        db.clone(),
+        oauth_manager.clone(),
+        cost_calculator.clone(),
+        leader.clone(),
+    ));
+    for section in factory::SECTIONS {
+        for provider in provider_factory.build(&config.providers, section).await {
+            provider_router.register(provider);
         }
     }
 
-    // Register LiteLLM provider if configured.
-    if let Some(ref litellm_config) = config.providers.litellm {
-        match build_litellm_provider(litellm_config).await {
-            Ok(provider) => {
-                let model_count = provider.models().len();
-                provider_router.register(Arc::new(provider));
-                tracing::info!(models = model_count, "LiteLLM provider registered");
-            }
-            Err(e) => {
-                tracing::warn!(error = %e, "Failed to initialize LiteLLM provider, skipping");
-            }
+    provider_router
+        .failures()
+        .set_capacity(config.observability.failure_buffer);
+    for id in provider_router.provider_ids().to_vec() {
+        provider_router.set_quota(&id, config.providers.quota_for(&id));
+        if config.providers.dry_run_for(&id) {
+            provider_router.set_dry_run(&id);
+            tracing::warn!(provider = %id, "Dry-run mode: provider answers without calling upstream");
         }
     }
 
-    // Register Gemini provider if configured
-    if config.providers.gemini.is_some() {
-        let gemini = gaud::providers::gemini::provider::GeminiProvider::new(oauth_manager.clone());
-        provider_router.register(Arc::new(gemini));
-        tracing::info!("Gemini provider registered");
+    // 6b. Optional warm-up: check every provider before the listeners open,
+    //     so broken credentials show up in the startup log.
+    if config.providers.warmup {
+        let timeout = config.providers.warmup_timeout();
+        tracing::info!(timeout_secs = timeout.as_secs(), "Warming up providers");
+        let failures = provider_router
+            .warm_up(timeout, WARMUP_RETRY_INTERVAL)
+            .await;
+        if failures.is_empty() {
+            tracing::info!("All providers passed the warm-up");
+        } else {
+            tracing::warn!(
+                failed = failures.len(),
+                "Starting with providers that failed the warm-up"
+            );
+        }
     }
 
     let provider_router = Arc::new(RwLock::new(provider_router));
@@ -212,9 +403,32 @@ async fn async_main() -> anyhow::Result<()> {
     let (audit_tx, audit_rx) = tokio::sync::mpsc::unbounded_channel();
     let audit_db = db.clone();
     let audit_budget = budget.clone();
-    let _audit_handle = spawn_audit_logger(audit_db, audit_budget, audit_rx);
+    let request_tail = Arc::new(RequestTail::new());
+    let audit_handle = spawn_audit_logger(
+        audit_db,
+        audit_budget,
+        cost_calculator.clone(),
+        request_tail.clone(),
+        audit_rx,
+    );
     tracing::debug!("Audit logger spawned");
 
+    if config.auth.disable_stale_keys_after_days > 0 {
+        let days = config.auth.disable_stale_keys_after_days;
+        spawn_stale_key_sweep(db.clone(), days, leader.clone());
+        tracing::debug!(days, "Stale API key sweep spawned");
+    }
+
+    if let Some(ref dir) = config.reports.chargeback_dir {
+        chargeback::spawn_chargeback_reports(
+            db.clone(),
+            cost_calculator.clone(),
+            PathBuf::from(dir),
+            leader.clone(),
+        );
+        tracing::debug!(dir = %dir, "Chargeback report task spawned");
+    }
+
     // 9. Initialize semantic cache (if enabled)
     let cache = if config.cache.enabled {
         match SemanticCacheService::new(&config.cache).await {
@@ -223,10 +437,16 @@ async fn async_main() -> anyhow::Result<()> {
                 // Spawn TTL eviction every 5 minutes.
                 let c2 = Arc::clone(&c);
                 let ttl = config.cache.ttl_secs;
+                // A shared cache only needs evicting by one process.
+                let shared = config.cache.backend_url.is_some();
+                let leader = leader.clone();
                 tokio::spawn(async move {
                     let mut interval = tokio::time::interval(Duration::from_secs(300));
                     loop {
                         interval.tick().await;
+                        if shared && !leader.is_leader() {
+                            continue;
+                        }
                         match c2.evict_expired(ttl).await {
                             Ok(n) if n > 0 => {
                                 tracing::debug!(evicted = n, "Cache TTL eviction");
@@ -263,9 +483,8 @@ async fn async_main() -> anyhow::Result<()> {
     // (OAuth manager already initialized at step 5b)
 
     // 11. Build shared application state
-    let cost_calculator = Arc::new(gaud::providers::cost::CostCalculator::new());
     let state = AppState {
-        config: config_arc,
+        config: config_arc.clone(),
         config_path: config_path.clone(),
         db: db.clone(),
         router: provider_router,
@@ -274,155 +493,297 @@ async fn async_main() -> anyhow::Result<()> {
         cost_calculator,
         cache,
         oauth_manager,
+        providers: provider_factory,
+        mcp_sessions: Arc::new(gaud::api::mcp::McpSessions::new()),
+        mcp_tools: Arc::new(McpToolServers::new(&config.mcp, http.client("mcp"))),
+        stream_registry: Arc::new(gaud::api::resume::StreamRegistry::from_config(
+            &config.cache,
+        )),
+        request_tail,
+        stream_limiter: Arc::new(gaud::budget::streams::StreamLimiter::new()),
+        scheduler: Arc::new(gaud::budget::scheduler::FairScheduler::new()),
+        settings: Arc::new(RuntimeSettings::new(config_arc.clone())),
+        login_throttle: Arc::new(LoginThrottle::new(
+            config.auth.login_throttle.clone(),
+            db.clone(),
+        )),
+        notifier: notifier.clone(),
+        background: TaskTracker::new(),
     };
 
-    // 12. Build the combined router
-    let app = build_app(state.clone());
-
-    // 13. Bind and serve
+    // 12. Bind listeners
     let listen_addr = config.listen_addr();
-    let listener = TcpListener::bind(&listen_addr).await?;
-    tracing::info!(addr = %listen_addr, "Listening");
+    let admin_addr = config.server.admin_listen_addr();
+    let client_certs = &config.auth.tls_client_cert;
+    let main_client_certs = (client_certs.enabled
+        && !(client_certs.admin_only && admin_addr.is_some()))
+    .then_some(client_certs);
+    let admin_client_certs = client_certs.enabled.then_some(client_certs);
+
+    let listener = bind(&config, &listen_addr, main_client_certs).await?;
+    let admin_listener = match admin_addr {
+        Some(ref addr) => Some(bind(&config, addr, admin_client_certs).await?),
+        None => None,
+    };
+    let scheme = if config.server.tls_enabled() {
+        "https"
+    } else {
+        "http"
+    };
+    print_banner(scheme, &listen_addr, admin_addr.as_deref());
 
-    println!();
-    println!("  gaud v{} is running", env!("CARGO_PKG_VERSION"));
-    println!("  API:       http://{listen_addr}/v1/");
-    println!("  Dashboard: http://{listen_addr}/ui/dashboard");
-    println!("  Health:    http://{listen_addr}/health");
-    println!();
+    // 13. Build the routers
+    let (app, admin_app) = if admin_listener.is_some() {
+        (
+            build_app(state.clone(), Routes::Proxy),
+            Some(build_app(state.clone(), Routes::Admin)),
+        )
+    } else {
+        (build_app(state.clone(), Routes::All), None)
+    };
 
-    // 14. Serve with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
-        .await?;
+    // 14. Serve with graceful shutdown. Once a signal arrives the listeners
+    // stop accepting and in-flight requests get until the drain deadline.
+    let drain = Duration::from_secs(config.server.shutdown_drain_secs);
+    let (shutdown_tx, shutdown_rx) = watch::channel(None);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(Some(Instant::now() + drain));
+    });
+    let servers = async {
+        match (admin_listener, admin_app) {
+            (Some(admin_listener), Some(admin_app)) => {
+                tokio::try_join!(
+                    serve(listener, app, shutdown_rx.clone()),
+                    serve(admin_listener, admin_app, shutdown_rx.clone()),
+                )?;
+            }
+            _ => serve(listener, app, shutdown_rx.clone()).await?,
+        }
+        std::io::Result::Ok(())
+    };
+    let mut deadline_rx = shutdown_rx.clone();
+    let deadline = async move {
+        let deadline = deadline_rx
+            .wait_for(Option::is_some)
+            .await
+            .ok()
+            .and_then(|d| *d);
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        result = servers => result?,
+        () = deadline => {
+            tracing::warn!(
+                drain_secs = drain.as_secs(),
+                "Drain timeout reached, closing remaining connections"
+            );
+        }
+    }
 
     // 15. Cleanup
     tracing::info!("Shutting down gracefully");
-    // Database is dropped automatically via Arc/Mutex.
-    // The audit_tx sender is dropped here, which will cause the audit logger
-    // to drain remaining entries and exit.
-
-    Ok(())
-}
-
-// ---------------------------------------------------------------------------
-// Kiro provider builder
-// ---------------------------------------------------------------------------
+    let deadline = shutdown_rx
+        .borrow()
+        .unwrap_or_else(|| Instant::now() + drain);
+
+    // Let detached work (cache writes, resumable stream pumps) finish; it may
+    // still produce audit entries.
+    let background = state.background.clone();
+    background.close();
+    if tokio::time::timeout_at(deadline, background.wait())
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            pending = background.len(),
+            "Background tasks still running at drain timeout"
+        );
+    }
 
-/// Build a [`KiroProvider`] from the configuration.
-///
-/// Constructs a `KiroClient` using the refresh-token auth flow that matches
-/// the kiro-aws reference implementation.
-async fn build_kiro_provider(kiro_config: &KiroProviderConfig) -> anyhow::Result<KiroProvider> {
-    use gaud::providers::kiro::{
-        AutoDetectProvider, KiroAuthManager, KiroClient, KiroProvider, machine_fingerprint,
-    };
+    leader.release();
 
-    let region = kiro_config.effective_region();
-    let fingerprint = machine_fingerprint();
+    // Dropping the last audit sender makes the logger flush and exit. It
+    // gets a short grace period even when the drain window is used up.
+    drop(state);
+    let flush_deadline = deadline.max(Instant::now() + AUDIT_FLUSH_GRACE);
+    if tokio::time::timeout_at(flush_deadline, audit_handle)
+        .await
+        .is_err()
+    {
+        tracing::warn!("Audit log was not flushed before exit");
+    }
 
-    let manager = Arc::new(KiroAuthManager::new(fingerprint.clone(), region.clone()));
+    Ok(())
+}
 
-    let auth = Arc::new(
-        AutoDetectProvider::new(
-            manager,
-            kiro_config.credentials_file.as_ref().map(PathBuf::from),
-            kiro_config.kiro_db_path.as_ref().map(PathBuf::from),
-            kiro_config.sso_cache_dir.as_ref().map(PathBuf::from),
-        )
-        .await,
-    );
+fn print_banner(scheme: &str, listen_addr: &str, admin_addr: Option<&str>) {
+    tracing::info!(addr = %listen_addr, admin_addr, scheme, "Listening");
 
-    let client = KiroClient::new(
-        auth,
-        region,
-        kiro_config.effective_profile_arn(),
-        fingerprint,
-    );
-    Ok(KiroProvider::new(client))
+    let ui_addr = admin_addr.unwrap_or(listen_addr);
+    println!();
+    println!("  gaud v{} is running", env!("CARGO_PKG_VERSION"));
+    println!("  API:       {scheme}://{listen_addr}/v1/");
+    if let Some(admin_addr) = admin_addr {
+        println!("  Admin:     {scheme}://{admin_addr}/admin/");
+    }
+    println!("  Dashboard: {scheme}://{ui_addr}/ui/dashboard");
+    println!("  Health:    {scheme}://{listen_addr}/health");
+    println!();
 }
 
 // ---------------------------------------------------------------------------
-// LiteLLM provider builder
+// Listeners
 // ---------------------------------------------------------------------------
 
-/// Build a [`LitellmProvider`] from the configuration.
-///
-/// The provider connects to the LiteLLM proxy at the configured URL and
-/// optionally discovers available models from its `/v1/models` endpoint.
-async fn build_litellm_provider(config: &LitellmProviderConfig) -> anyhow::Result<LitellmProvider> {
-    let litellm_config = LitellmConfig {
-        url: config.url.clone(),
-        api_key: config.api_key.clone(),
-        discover_models: config.discover_models,
-        models: config.models.clone(),
-        timeout_secs: config.timeout_secs,
-    };
+/// A bound listener, with or without TLS.
+enum ServerListener {
+    Plain(TcpListener),
+    Tls(TlsListener),
+}
 
-    let provider = LitellmProvider::new(litellm_config)
-        .await
-        .map_err(|e| anyhow::anyhow!("LiteLLM provider init failed: {e}"))?;
+/// Bind `addr`, wrapping it in TLS when the server is configured for it.
+/// `client_certs` turns on client certificate verification for this listener.
+async fn bind(
+    config: &Config,
+    addr: &str,
+    client_certs: Option<&TlsClientCertConfig>,
+) -> anyhow::Result<ServerListener> {
+    let listener = TcpListener::bind(addr).await?;
+    if !config.server.tls_enabled() {
+        return Ok(ServerListener::Plain(listener));
+    }
+    let listener = TlsListener::from_config(listener, &config.server, client_certs)?;
+    Ok(ServerListener::Tls(listener))
+}
 
-    Ok(provider)
+/// Serve `app` on `listener` until a drain deadline is published on
+/// `shutdown`, then wait for open connections to finish.
+async fn serve(
+    listener: ServerListener,
+    app: Router,
+    mut shutdown: watch::Receiver<Option<Instant>>,
+) -> std::io::Result<()> {
+    let shutdown = async move {
+        let _ = shutdown.wait_for(Option::is_some).await;
+    };
+    match listener {
+        // The peer address feeds login throttling (`ClientIp`).
+        ServerListener::Plain(listener) => {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown)
+            .await
+        }
+        // TLS connections expose the verified client certificate to the auth
+        // middleware via `ConnectInfo<TlsPeer>`.
+        ServerListener::Tls(listener) => {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<TlsPeer>(),
+            )
+            .with_graceful_shutdown(shutdown)
+            .await
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Router assembly
 // ---------------------------------------------------------------------------
 
-/// Build the combined application router with all middleware layers.
-fn build_app(state: AppState) -> Router {
-    let config = &state.config;
+/// Which routes a listener serves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Routes {
+    /// Everything (single listener).
+    All,
+    /// `/health` and `/v1/*` when the admin API has its own listener.
+    Proxy,
+    /// Admin API and web UI on the dedicated admin listener.
+    Admin,
+}
 
-    // -- CORS layer -----------------------------------------------------------
-    let cors = build_cors_layer(config);
+/// Build the application router for `routes` with all middleware layers.
+fn build_app(state: AppState, routes: Routes) -> Router {
+    let config = &state.config;
 
     // -- Request ID layer (X-Request-ID) --------------------------------------
     let request_id = SetRequestIdLayer::x_request_id(MakeRequestUuid);
     let propagate_id = PropagateRequestIdLayer::x_request_id();
 
     // -- Tracing layer --------------------------------------------------------
-    let trace = TraceLayer::new_for_http();
+    let trace = TraceLayer::new_for_http().make_span_with(observability::make_span::<Body>);
 
     // -- API routes (require auth) --------------------------------------------
-    let api_routes =
-        api::build_api_router().layer(middleware::from_fn_with_state(state.clone(), require_auth));
+    // Each route group gets its own CORS policy, outside auth so preflight
+    // requests are answered without credentials.
+    let with_api_layers = |router: Router<AppState>, group: RouteGroup| {
+        router
+            .layer(DefaultBodyLimit::max(
+                config.limits.max_body_mb as usize * 1024 * 1024,
+            ))
+            .layer(middleware::from_fn_with_state(state.clone(), debug_errors))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                quota_middleware,
+            ))
+            .layer(middleware::from_fn_with_state(state.clone(), require_auth))
+            .layer(middleware::from_fn(error::wrap_plain_errors))
+            .layer(build_cors_layer(config, group))
+    };
+    let proxy_routes = match routes {
+        Routes::All | Routes::Proxy => {
+            with_api_layers(api::build_proxy_router(), RouteGroup::Proxy)
+        }
+        Routes::Admin => Router::new(),
+    };
+    let admin_routes = match routes {
+        Routes::All => with_api_layers(api::build_admin_router(), RouteGroup::Admin),
+        Routes::Proxy => Router::new(),
+        // The web UI polls /health, so keep it reachable next to the UI.
+        Routes::Admin => with_api_layers(
+            api::build_admin_router().route("/health", get(api::health::health_check)),
+            RouteGroup::Admin,
+        ),
+    };
 
     // -- Web UI routes (no API auth middleware) --------------------------------
-    let web_routes = web::build_web_router();
+    let web_routes = match routes {
+        Routes::Proxy => Router::new(),
+        Routes::All | Routes::Admin => {
+            web::build_web_router(&state).layer(build_cors_layer(config, RouteGroup::Ui))
+        }
+    };
 
     // -- Combine all routes ---------------------------------------------------
     Router::new()
         .merge(web_routes)
-        .merge(api_routes)
+        .merge(proxy_routes)
+        .merge(admin_routes)
+        .fallback(api::route_not_found)
         // Global middleware stack (applied to all routes)
         .layer(propagate_id)
         .layer(request_id)
         .layer(trace)
-        .layer(cors)
         .with_state(state)
 }
 
-/// Build the CORS layer from config.
-fn build_cors_layer(config: &Config) -> CorsLayer {
-    if config.server.cors_origins.is_empty() {
-        // Default: allow all origins for development convenience
-        CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any)
+/// Build the CORS layer of one route group from config.
+fn build_cors_layer(config: &Config, group: RouteGroup) -> CorsLayer {
+    let origins = config.server.cors_origins_for(group);
+    let layer = CorsLayer::new().allow_methods(Any).allow_headers(Any);
+    if origins.iter().any(|o| o == "*") {
+        layer.allow_origin(Any)
     } else {
-        let origins: Vec<_> = config
-            .server
-            .cors_origins
-            .iter()
-            .filter_map(|o| o.parse().ok())
-            .collect();
-
-        CorsLayer::new()
-            .allow_origin(origins)
-            .allow_methods(Any)
-            .allow_headers(Any)
+        // No origins leaves the layer without an allowed origin, so browsers
+        // refuse cross-origin requests.
+        let origins: Vec<_> = origins.iter().filter_map(|o| o.parse().ok()).collect();
+        layer.allow_origin(origins)
     }
 }
 
@@ -446,6 +807,7 @@ fn init_tracing(config: &Config) {
         .with_file(false)
         .with_line_number(false);
 
+    observability::init(config);
     if config.logging.json {
         subscriber.json().init();
     } else {
@@ -503,7 +865,7 @@ mod tests {
     #[test]
     fn test_build_cors_layer_empty_origins() {
         let config = Config::default();
-        let _cors = build_cors_layer(&config);
+        let _cors = build_cors_layer(&config, RouteGroup::Proxy);
         // No panic means success.
     }
 
@@ -511,6 +873,8 @@ mod tests {
     fn test_build_cors_layer_with_origins() {
         let mut config = Config::default();
         config.server.cors_origins = vec!["http://localhost:3000".to_string()];
-        let _cors = build_cors_layer(&config);
+        config.server.cors.admin = Some(Vec::new());
+        let _cors = build_cors_layer(&config, RouteGroup::Proxy);
+        let _cors = build_cors_layer(&config, RouteGroup::Admin);
     }
 }
@@ -0,0 +1,537 @@
+//! Client for external MCP tool servers (`[mcp.servers.*]`).
+//!
+//! The tools of every server whose `models` match a request's model are
+//! offered to the model as OpenAI function tools named `<server>__<tool>`.
+//! When the model calls them, gaud runs the calls against their servers,
+//! appends the results and asks again (see `api::chat`), so the caller
+//! only sees the final answer. Servers are reached over the Streamable
+//! HTTP transport; tool lists are cached for [`TOOLS_TTL`].
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use serde_json::{Value, json};
+
+use crate::api::mcp::PROTOCOL_VERSIONS;
+use crate::config::{McpConfig, McpServerConfig};
+use crate::providers::rules::glob_match;
+use crate::providers::types::{FunctionDef, Tool, ToolCall};
+
+/// Separates the server name from the tool name in a function name.
+pub const TOOL_SEPARATOR: &str = "__";
+/// How long a server's tool list is reused before it is fetched again.
+pub const TOOLS_TTL: Duration = Duration::from_secs(300);
+/// Longest function name OpenAI-compatible providers accept.
+const MAX_FUNCTION_NAME: usize = 64;
+/// Pages of `tools/list` read from one server.
+const MAX_TOOL_PAGES: usize = 10;
+
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// The configured tool servers.
+pub struct McpToolServers {
+    servers: Vec<ToolServer>,
+    max_rounds: u32,
+}
+
+impl McpToolServers {
+    pub fn new(config: &McpConfig, http: reqwest::Client) -> Self {
+        let servers = config
+            .servers
+            .iter()
+            .map(|(name, server)| ToolServer::new(name, server, http.clone()))
+            .collect();
+        Self {
+            servers,
+            max_rounds: config.max_tool_rounds,
+        }
+    }
+
+    /// No servers configured.
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    /// Most rounds of tool calls gaud runs for one request.
+    pub fn max_rounds(&self) -> u32 {
+        self.max_rounds
+    }
+
+    /// Tools of the servers attached to `model`. A server that cannot be
+    /// reached contributes no tools; the request goes ahead without them.
+    pub async fn tools_for(&self, model: &str) -> Vec<Tool> {
+        let lists = self
+            .servers
+            .iter()
+            .filter(|s| s.models.iter().any(|p| glob_match(p, model)))
+            .map(ToolServer::tools);
+        futures::future::join_all(lists)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Tools of the server named `server`, whatever models it is attached
+    /// to; `None` when no such server is configured.
+    pub async fn server_tools(&self, server: &str) -> Option<Vec<Tool>> {
+        let server = self.servers.iter().find(|s| s.name == server)?;
+        Some(server.tools().await)
+    }
+
+    /// Whether `function` names a tool of one of the servers.
+    pub fn owns(&self, function: &str) -> bool {
+        self.server_for(function).is_some()
+    }
+
+    /// Run a tool call, returning the text to give the model. Failures are
+    /// reported in the text rather than failing the request.
+    pub async fn call(&self, call: &ToolCall) -> String {
+        let Some((server, tool)) = self.server_for(&call.function.name) else {
+            return format!("Error: unknown tool {}", call.function.name);
+        };
+        let arguments = match call.function.arguments.trim() {
+            "" => json!({}),
+            raw => match serde_json::from_str::<Value>(raw) {
+                Ok(args) => args,
+                Err(e) => return format!("Error: tool arguments are not valid JSON: {e}"),
+            },
+        };
+        match server
+            .rpc(
+                "tools/call",
+                json!({ "name": tool, "arguments": arguments }),
+            )
+            .await
+        {
+            Ok(result) => tool_output(&result),
+            Err(e) => {
+                tracing::warn!(server = %server.name, tool, error = %e, "MCP tool call failed");
+                format!("Error: {e}")
+            }
+        }
+    }
+
+    fn server_for<'a>(&self, function: &'a str) -> Option<(&ToolServer, &'a str)> {
+        let (server, tool) = function.split_once(TOOL_SEPARATOR)?;
+        let server = self.servers.iter().find(|s| s.name == server)?;
+        Some((server, tool))
+    }
+}
+
+/// Text of a `tools/call` result: its text content, with other content
+/// kinds summarised.
+fn tool_output(result: &Value) -> String {
+    let parts: Vec<String> = result["content"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|part| match part["type"].as_str() {
+            Some("text") => part["text"].as_str().unwrap_or_default().to_string(),
+            Some("resource") => part["resource"]["text"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| format!("[resource {}]", part["resource"]["uri"])),
+            Some(kind) => format!("[{kind} content omitted]"),
+            None => String::new(),
+        })
+        .collect();
+    let mut text = parts.join("\n");
+    if text.is_empty()
+        && let Some(structured) = result.get("structuredContent")
+    {
+        text = structured.to_string();
+    }
+    if result["isError"].as_bool() == Some(true) {
+        text = format!("Error: {text}");
+    }
+    text
+}
+
+/// A failed exchange with a server.
+#[derive(Debug)]
+enum RpcFailure {
+    /// The server no longer knows our session (HTTP 404).
+    SessionExpired,
+    Other(String),
+}
+
+impl std::fmt::Display for RpcFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SessionExpired => f.write_str("MCP session expired"),
+            Self::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+struct ToolServer {
+    name: String,
+    url: String,
+    api_key: Option<String>,
+    models: Vec<String>,
+    timeout: Duration,
+    http: reqwest::Client,
+    /// `Some` once initialized; the inner value is the server's session ID,
+    /// if it issued one.
+    session: Mutex<Option<Option<String>>>,
+    tools: Mutex<Option<(Instant, Vec<Tool>)>>,
+    next_id: AtomicU64,
+}
+
+impl ToolServer {
+    fn new(name: &str, config: &McpServerConfig, http: reqwest::Client) -> Self {
+        Self {
+            name: name.to_string(),
+            url: config.url.clone(),
+            api_key: config.api_key.clone().filter(|k| !k.is_empty()),
+            models: config.models.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+            http,
+            session: Mutex::new(None),
+            tools: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// The server's tools as function definitions, from the cache when it
+    /// is fresh. On failure the last known list is kept for another
+    /// [`TOOLS_TTL`], so a server that is down does not slow every request.
+    async fn tools(&self) -> Vec<Tool> {
+        let cached = self.tools.lock().unwrap().clone();
+        if let Some((fetched, tools)) = &cached
+            && fetched.elapsed() < TOOLS_TTL
+        {
+            return tools.clone();
+        }
+        match self.list_tools().await {
+            Ok(tools) => {
+                *self.tools.lock().unwrap() = Some((Instant::now(), tools.clone()));
+                tools
+            }
+            Err(e) => {
+                tracing::warn!(server = %self.name, error = %e, "Failed to list MCP tools");
+                let tools = cached.map(|(_, tools)| tools).unwrap_or_default();
+                *self.tools.lock().unwrap() = Some((Instant::now(), tools.clone()));
+                tools
+            }
+        }
+    }
+
+    async fn list_tools(&self) -> Result<Vec<Tool>, RpcFailure> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..MAX_TOOL_PAGES {
+            let params = match &cursor {
+                Some(c) => json!({ "cursor": c }),
+                None => json!({}),
+            };
+            let result = self.rpc("tools/list", params).await?;
+            for tool in result["tools"].as_array().into_iter().flatten() {
+                let Some(name) = tool["name"].as_str() else {
+                    continue;
+                };
+                let function = format!("{}{TOOL_SEPARATOR}{name}", self.name);
+                let valid = function.len() <= MAX_FUNCTION_NAME
+                    && function
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if !valid {
+                    tracing::debug!(server = %self.name, tool = name, "Skipping MCP tool with an unusable name");
+                    continue;
+                }
+                tools.push(Tool {
+                    r#type: "function".to_string(),
+                    function: FunctionDef {
+                        name: function,
+                        description: tool["description"].as_str().map(String::from),
+                        parameters: Some(tool["inputSchema"].clone())
+                            .filter(|s| s.is_object())
+                            .or_else(|| Some(json!({ "type": "object" }))),
+                    },
+                });
+            }
+            cursor = result["nextCursor"].as_str().map(String::from);
+            if cursor.is_none() {
+                break;
+            }
+        }
+        Ok(tools)
+    }
+
+    /// Send a request in the current session, starting a new session if
+    /// there is none or the server forgot it.
+    async fn rpc(&self, method: &str, params: Value) -> Result<Value, RpcFailure> {
+        let session = self.session().await?;
+        match self
+            .request(method, params.clone(), session.as_deref())
+            .await
+        {
+            Err(RpcFailure::SessionExpired) => {
+                *self.session.lock().unwrap() = None;
+                let session = self.session().await?;
+                self.request(method, params, session.as_deref())
+                    .await
+                    .map(|(_, result)| result)
+            }
+            other => other.map(|(_, result)| result),
+        }
+    }
+
+    /// The session ID, initializing the connection first if needed.
+    async fn session(&self) -> Result<Option<String>, RpcFailure> {
+        if let Some(session) = self.session.lock().unwrap().clone() {
+            return Ok(session);
+        }
+        let params = json!({
+            "protocolVersion": PROTOCOL_VERSIONS[0],
+            "capabilities": {},
+            "clientInfo": { "name": "gaud", "version": env!("CARGO_PKG_VERSION") },
+        });
+        let (session, _) = self.request("initialize", params, None).await?;
+        self.notify("notifications/initialized", session.as_deref())
+            .await?;
+        *self.session.lock().unwrap() = Some(session.clone());
+        Ok(session)
+    }
+
+    fn post(&self, body: &Value, session: Option<&str>) -> reqwest::RequestBuilder {
+        let mut request = self
+            .http
+            .post(&self.url)
+            .timeout(self.timeout)
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        if let Some(session) = session {
+            request = request.header(SESSION_HEADER, session);
+        }
+        request
+    }
+
+    async fn notify(&self, method: &str, session: Option<&str>) -> Result<(), RpcFailure> {
+        let body = json!({ "jsonrpc": "2.0", "method": method });
+        let response = self
+            .post(&body, session)
+            .send()
+            .await
+            .map_err(|e| RpcFailure::Other(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(RpcFailure::Other(format!(
+                "{method} returned HTTP {}",
+                response.status().as_u16()
+            )));
+        }
+        Ok(())
+    }
+
+    /// One request/response exchange. Returns the session ID the server
+    /// sent, if any, and the result.
+    async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        session: Option<&str>,
+    ) -> Result<(Option<String>, Value), RpcFailure> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let response = self
+            .post(&body, session)
+            .send()
+            .await
+            .map_err(|e| RpcFailure::Other(e.to_string()))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND && session.is_some() {
+            return Err(RpcFailure::SessionExpired);
+        }
+        if !status.is_success() {
+            return Err(RpcFailure::Other(format!(
+                "{method} returned HTTP {}",
+                status.as_u16()
+            )));
+        }
+        let new_session = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let is_sse = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let text = response
+            .text()
+            .await
+            .map_err(|e| RpcFailure::Other(e.to_string()))?;
+
+        let message = if is_sse {
+            find_sse_response(&text, id)
+        } else {
+            serde_json::from_str::<Value>(&text).ok()
+        }
+        .ok_or_else(|| RpcFailure::Other(format!("{method}: malformed response")))?;
+
+        if let Some(error) = message.get("error") {
+            let text = error["message"].as_str().unwrap_or("unknown error");
+            return Err(RpcFailure::Other(format!("{method}: {text}")));
+        }
+        Ok((
+            new_session.or_else(|| session.map(String::from)),
+            message["result"].clone(),
+        ))
+    }
+}
+
+/// The response to request `id` among the `data:` lines of an SSE body.
+fn find_sse_response(body: &str, id: u64) -> Option<Value> {
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+        .find(|message| message["id"] == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::types::FunctionCall;
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn servers(url: &str, models: &[&str]) -> McpToolServers {
+        let mut config = McpConfig::default();
+        config.servers.insert(
+            "search".to_string(),
+            McpServerConfig {
+                url: url.to_string(),
+                api_key: Some("secret".to_string()),
+                models: models.iter().map(|m| m.to_string()).collect(),
+                timeout_secs: 5,
+            },
+        );
+        McpToolServers::new(&config, reqwest::Client::new())
+    }
+
+    fn tool_call(name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            index: None,
+            id: "call_1".to_string(),
+            r#type: "function".to_string(),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    async fn mount_server(server: &MockServer) {
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": "initialize" })))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header(SESSION_HEADER, "s1")
+                    .set_body_json(json!({ "jsonrpc": "2.0", "id": 1, "result": {} })),
+            )
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                json!({ "method": "notifications/initialized" }),
+            ))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({ "method": "tools/list" })))
+            .and(header(SESSION_HEADER, "s1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "jsonrpc": "2.0", "id": 2,
+                "result": { "tools": [
+                    { "name": "web_search", "description": "Search the web",
+                      "inputSchema": { "type": "object", "properties": { "q": { "type": "string" } } } },
+                    { "name": "bad name!" },
+                ] },
+            })))
+            .mount(server)
+            .await;
+        // Answered as an event stream, as Streamable HTTP servers may.
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({
+                "method": "tools/call",
+                "params": { "name": "web_search", "arguments": { "q": "rust" } },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "event: message\n\
+                         data: {\"jsonrpc\":\"2.0\",\"id\":3,\"result\":\
+                         {\"content\":[{\"type\":\"text\",\"text\":\"Rust is a language\"}]}}\n\n",
+                "text/event-stream",
+            ))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_tools_are_listed_and_called() {
+        let server = MockServer::start().await;
+        mount_server(&server).await;
+        let servers = servers(&server.uri(), &["claude-*"]);
+
+        assert!(servers.tools_for("gpt-4o").await.is_empty());
+        let tools = servers.tools_for("claude-sonnet-4").await;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].function.name, "search__web_search");
+        assert_eq!(
+            tools[0].function.description.as_deref(),
+            Some("Search the web")
+        );
+
+        assert_eq!(servers.server_tools("search").await.unwrap().len(), 1);
+        assert!(servers.server_tools("other").await.is_none());
+        assert!(servers.owns("search__web_search"));
+        assert!(!servers.owns("get_weather"));
+        let output = servers
+            .call(&tool_call("search__web_search", r#"{"q":"rust"}"#))
+            .await;
+        assert_eq!(output, "Rust is a language");
+
+        let output = servers.call(&tool_call("search__web_search", "{")).await;
+        assert!(output.starts_with("Error: tool arguments are not valid JSON"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_contributes_no_tools() {
+        let servers = servers("http://127.0.0.1:1/mcp", &["*"]);
+        assert!(servers.tools_for("any").await.is_empty());
+        let output = servers.call(&tool_call("search__web_search", "{}")).await;
+        assert!(output.starts_with("Error: "));
+    }
+
+    #[test]
+    fn test_tool_output() {
+        let result = json!({
+            "content": [
+                { "type": "text", "text": "a" },
+                { "type": "image", "data": "...", "mimeType": "image/png" },
+            ],
+            "isError": true,
+        });
+        assert_eq!(tool_output(&result), "Error: a\n[image content omitted]");
+        let result = json!({ "content": [], "structuredContent": { "n": 1 } });
+        assert_eq!(tool_output(&result), r#"{"n":1}"#);
+    }
+
+    #[test]
+    fn test_find_sse_response() {
+        let body = "data: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+                    data: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{}}\n\n";
+        assert_eq!(find_sse_response(body, 7).unwrap()["result"], json!({}));
+        assert!(find_sse_response(body, 8).is_none());
+    }
+}
@@ -0,0 +1,6 @@
+//! Model Context Protocol support outside the HTTP API: the stdio relay
+//! behind `gaud mcp`, and the client for external tool servers. The server
+//! itself is [`crate::api::mcp`].
+
+pub mod client;
+pub mod stdio;
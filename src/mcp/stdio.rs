@@ -0,0 +1,193 @@
+//! `gaud mcp`: the stdio transport of the MCP server.
+//!
+//! MCP clients such as Claude Desktop launch their servers as child
+//! processes and exchange newline-delimited JSON-RPC over stdin/stdout.
+//! This relays each line to `POST /mcp` on a running server and writes the
+//! reply back, so the calls are authenticated, routed and budgeted there
+//! (see [`crate::api::mcp`]). Messages are relayed one at a time.
+
+use std::io::Write;
+
+use anyhow::bail;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::cli::Options;
+use crate::config::Config;
+use crate::loadtest::local_url;
+
+/// JSON-RPC error code for a message the server could not answer.
+const RELAY_ERROR: i64 = -32603;
+
+struct Bridge {
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+/// Relay `input` to the server until it ends. `args` are the arguments
+/// after `mcp`.
+pub async fn run(
+    config: &Config,
+    args: &[String],
+    input: impl AsyncBufRead + Unpin,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let bridge = parse(config, args)?;
+    let http = reqwest::Client::new();
+    let mut lines = input.lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(reply) = bridge.relay(&http, &line).await {
+            writeln!(out, "{reply}")?;
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn parse(config: &Config, args: &[String]) -> anyhow::Result<Bridge> {
+    let mut opts = Options::parse(args)?;
+    let url = opts
+        .take("--url")
+        .unwrap_or_else(|| local_url(config))
+        .trim_end_matches('/')
+        .to_string();
+    let api_key = opts
+        .take("--key")
+        .or_else(|| std::env::var("GAUD_API_KEY").ok())
+        .filter(|k| !k.is_empty());
+    opts.finish()?;
+
+    if api_key.is_none() && config.auth.enabled {
+        bail!("an API key is required: pass --key or set GAUD_API_KEY");
+    }
+    Ok(Bridge {
+        endpoint: format!("{url}/mcp"),
+        api_key,
+    })
+}
+
+impl Bridge {
+    /// Send one message; `None` when it needs no reply.
+    async fn relay(&self, http: &reqwest::Client, line: &str) -> Option<Value> {
+        let mut request = http
+            .post(&self.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(line.to_string());
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let failure = match request.send().await {
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                if status.is_success() {
+                    // 202: a notification, or a response from the client.
+                    return serde_json::from_str(&body).ok();
+                }
+                // gaud's errors carry an OpenAI error envelope.
+                serde_json::from_str::<Value>(&body)
+                    .ok()
+                    .and_then(|v| v["error"]["message"].as_str().map(String::from))
+                    .unwrap_or_else(|| format!("gaud returned HTTP {}", status.as_u16()))
+            }
+            Err(e) => format!("gaud is unreachable at {}: {e}", self.endpoint),
+        };
+
+        // A request gets the failure as its answer. Notifications are not
+        // answered, so it goes to stderr, which MCP clients log.
+        match serde_json::from_str::<Value>(line)
+            .ok()
+            .and_then(|m| m.get("id").cloned())
+        {
+            Some(id) => Some(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": RELAY_ERROR, "message": failure },
+            })),
+            None => {
+                eprintln!("gaud mcp: {failure}");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_key_required_when_auth_enabled() {
+        let err = parse(&Config::default(), &[]).err().unwrap().to_string();
+        assert!(err.contains("API key is required"));
+
+        let bridge = parse(
+            &Config::default(),
+            &args(&["--key", "k", "--url", "http://h:1/"]),
+        )
+        .unwrap();
+        assert_eq!(bridge.endpoint, "http://h:1/mcp");
+    }
+
+    #[tokio::test]
+    async fn test_relays_lines_and_reports_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/mcp"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"jsonrpc":"2.0","id":1,"result":{}}"#),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/mcp"))
+            .respond_with(ResponseTemplate::new(404).set_body_string(
+                r#"{"error":{"message":"The MCP server is disabled (mcp.enabled)"}}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#,
+            "\n\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "\n",
+        );
+        let mut out = Vec::new();
+        let a = args(&["--url", &server.uri(), "--key", "sk-test"]);
+        run(&Config::default(), &a, input.as_bytes(), &mut out)
+            .await
+            .unwrap();
+
+        let replies: Vec<Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(replies.len(), 2);
+        assert_eq!(replies[0]["result"], json!({}));
+        assert_eq!(replies[1]["id"], 2);
+        assert_eq!(replies[1]["error"]["code"], RELAY_ERROR);
+        assert!(
+            replies[1]["error"]["message"]
+                .as_str()
+                .unwrap()
+                .contains("disabled")
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Operational notifications to Slack and Discord webhooks.
+//!
+//! Provider outages, circuit-breaker trips, exhausted budgets and OAuth
+//! accounts that need signing in again are posted to the webhooks under
+//! `[notifications]`. Delivery happens in the background and never delays
+//! or fails a request. The same event (kind and subject) is sent at most
+//! once per `cooldown_secs`, so a flapping provider doesn't flood a channel.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::json;
+
+use crate::config::NotificationsConfig;
+
+/// Every event kind, as used in `notifications.events`.
+pub const EVENT_KINDS: &[&str] = &[
+    "provider_outage",
+    "circuit_open",
+    "budget_exhausted",
+    "reauth_required",
+];
+
+/// Something an operator should know about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notification {
+    /// Every provider serving `model` failed a request.
+    ProviderOutage { model: String, error: String },
+    /// A provider's circuit breaker opened; it is skipped until it recovers.
+    CircuitOpen { provider: String },
+    /// A user was turned away for exceeding a spending or premium limit.
+    BudgetExhausted { user: String, limit: String },
+    /// An OAuth account's refresh token was rejected.
+    ReauthRequired { provider: String, account: String },
+}
+
+impl Notification {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ProviderOutage { .. } => "provider_outage",
+            Self::CircuitOpen { .. } => "circuit_open",
+            Self::BudgetExhausted { .. } => "budget_exhausted",
+            Self::ReauthRequired { .. } => "reauth_required",
+        }
+    }
+
+    /// What the event is about; repeats are suppressed per kind and subject.
+    fn subject(&self) -> String {
+        match self {
+            Self::ProviderOutage { model, .. } => model.clone(),
+            Self::CircuitOpen { provider } => provider.clone(),
+            Self::BudgetExhausted { user, limit } => format!("{user}/{limit}"),
+            Self::ReauthRequired { provider, account } => format!("{provider}/{account}"),
+        }
+    }
+
+    /// One-line message for the channel.
+    pub fn message(&self) -> String {
+        match self {
+            Self::ProviderOutage { model, error } => {
+                format!(":rotating_light: All providers failed for model `{model}`: {error}")
+            }
+            Self::CircuitOpen { provider } => format!(
+                ":warning: Circuit breaker opened for provider `{provider}`; it is skipped until it recovers"
+            ),
+            Self::BudgetExhausted { user, limit } => {
+                format!(":moneybag: User `{user}` has exhausted their {limit} budget")
+            }
+            Self::ReauthRequired { provider, account } => format!(
+                ":key: OAuth account `{account}` of `{provider}` was rejected; sign in again from the web UI"
+            ),
+        }
+    }
+}
+
+/// Posts [`Notification`]s to the configured webhooks.
+pub struct Notifier {
+    http: reqwest::Client,
+    slack: Option<String>,
+    discord: Option<String>,
+    /// Kinds to send; empty sends all.
+    events: Vec<String>,
+    cooldown: Mutex<Duration>,
+    last_sent: Mutex<HashMap<(&'static str, String), Instant>>,
+}
+
+impl Notifier {
+    pub fn new(config: &NotificationsConfig, http: reqwest::Client) -> Self {
+        let url = |u: &Option<String>| u.clone().filter(|u| !u.is_empty());
+        Self {
+            http,
+            slack: url(&config.slack_webhook_url),
+            discord: url(&config.discord_webhook_url),
+            events: config.events.clone(),
+            cooldown: Mutex::new(Duration::from_secs(config.cooldown_secs)),
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A notifier with no channels, which drops everything.
+    pub fn disabled() -> Self {
+        Self::new(&NotificationsConfig::default(), reqwest::Client::new())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.slack.is_some() || self.discord.is_some()
+    }
+
+    /// Change how long the same event is held back after being sent.
+    pub fn set_cooldown(&self, cooldown: Duration) {
+        *self.cooldown.lock().unwrap_or_else(|e| e.into_inner()) = cooldown;
+    }
+
+    /// Send `event` in the background unless its kind is filtered out or it
+    /// was sent within the cooldown.
+    pub fn notify(&self, event: Notification) {
+        if !self.should_send(&event, Instant::now()) {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        tracing::info!(kind = event.kind(), "Sending notification");
+        let message = event.message();
+        for (channel, url, body) in [
+            ("slack", &self.slack, json!({ "text": message })),
+            ("discord", &self.discord, json!({ "content": message })),
+        ] {
+            let Some(url) = url.clone() else {
+                continue;
+            };
+            let http = self.http.clone();
+            runtime.spawn(async move {
+                let result = http
+                    .post(&url)
+                    .json(&body)
+                    .timeout(Duration::from_secs(10))
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status());
+                if let Err(e) = result {
+                    tracing::warn!(channel, error = %e, "Failed to deliver notification");
+                }
+            });
+        }
+    }
+
+    /// Record `event` as sent at `now` if it should go out.
+    fn should_send(&self, event: &Notification, now: Instant) -> bool {
+        if !self.is_enabled() {
+            return false;
+        }
+        let kind = event.kind();
+        if !self.events.is_empty() && !self.events.iter().any(|e| e == kind) {
+            return false;
+        }
+        let cooldown = *self.cooldown.lock().unwrap_or_else(|e| e.into_inner());
+        let mut last_sent = self.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (kind, event.subject());
+        if last_sent
+            .get(&key)
+            .is_some_and(|at| now.duration_since(*at) < cooldown)
+        {
+            return false;
+        }
+        last_sent.insert(key, now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn notifier(slack: Option<String>, discord: Option<String>) -> Notifier {
+        let config = NotificationsConfig {
+            slack_webhook_url: slack,
+            discord_webhook_url: discord,
+            events: Vec::new(),
+            cooldown_secs: 60,
+        };
+        Notifier::new(&config, reqwest::Client::new())
+    }
+
+    fn circuit(provider: &str) -> Notification {
+        Notification::CircuitOpen {
+            provider: provider.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cooldown_is_per_subject() {
+        let n = notifier(Some("http://hook".into()), None);
+        let now = Instant::now();
+        assert!(n.should_send(&circuit("claude"), now));
+        assert!(!n.should_send(&circuit("claude"), now + Duration::from_secs(30)));
+        assert!(n.should_send(&circuit("gemini"), now + Duration::from_secs(30)));
+        assert!(n.should_send(&circuit("claude"), now + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn test_event_filter_and_disabled() {
+        assert!(!Notifier::disabled().should_send(&circuit("claude"), Instant::now()));
+
+        let mut n = notifier(Some("http://hook".into()), None);
+        n.events = vec!["reauth_required".to_string()];
+        assert!(!n.should_send(&circuit("claude"), Instant::now()));
+        let reauth = Notification::ReauthRequired {
+            provider: "gemini".into(),
+            account: "work".into(),
+        };
+        assert!(n.should_send(&reauth, Instant::now()));
+    }
+
+    #[tokio::test]
+    async fn test_delivers_to_slack_and_discord() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/slack"))
+            .and(body_partial_json(json!({
+                "text": ":moneybag: User `alice` has exhausted their spending budget"
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/discord"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let n = notifier(
+            Some(format!("{}/slack", server.uri())),
+            Some(format!("{}/discord", server.uri())),
+        );
+        let event = Notification::BudgetExhausted {
+            user: "alice".into(),
+            limit: "spending".into(),
+        };
+        n.notify(event.clone());
+        // Suppressed by the cooldown.
+        n.notify(event);
+
+        for _ in 0..50 {
+            if server.received_requests().await.unwrap_or_default().len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        server.verify().await;
+    }
+}
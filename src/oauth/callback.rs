@@ -68,28 +68,30 @@ pub fn validate_callback_params(params: &CallbackParams) -> Result<(String, Stri
 
 /// Look up and validate the state token against the oauth_state table.
 ///
-/// Retrieves the associated provider and code_verifier, and removes the
-/// row (state tokens are single-use). Returns an error if the state is
-/// not found or has expired.
+/// Retrieves the associated provider, code_verifier and account, and
+/// removes the row (state tokens are single-use). Returns an error if the
+/// state is not found or has expired.
 pub fn validate_state_from_db(
     db: &crate::db::Database,
     state_token: &str,
-) -> Result<(String, String), OAuthError> {
+) -> Result<(String, String, String), OAuthError> {
     db.with_conn(|conn| {
         // Look up state and verify it hasn't expired
         let mut stmt = conn.prepare(
-            "SELECT provider, code_verifier, expires_at FROM oauth_state WHERE state_token = ?1",
+            "SELECT provider, code_verifier, expires_at, account FROM oauth_state \
+             WHERE state_token = ?1",
         )?;
 
         let result = stmt.query_row([state_token], |row| {
             let provider: String = row.get(0)?;
             let code_verifier: String = row.get(1)?;
             let expires_at: String = row.get(2)?;
-            Ok((provider, code_verifier, expires_at))
+            let account: String = row.get(3)?;
+            Ok((provider, code_verifier, expires_at, account))
         });
 
         match result {
-            Ok((provider, code_verifier, expires_at)) => {
+            Ok((provider, code_verifier, expires_at, account)) => {
                 // Delete the state token (single-use)
                 conn.execute(
                     "DELETE FROM oauth_state WHERE state_token = ?1",
@@ -102,7 +104,7 @@ pub fn validate_state_from_db(
                     return Ok(Err(OAuthError::FlowExpired));
                 }
 
-                Ok(Ok((provider, code_verifier)))
+                Ok(Ok((provider, code_verifier, account)))
             }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(Err(OAuthError::InvalidState)),
             Err(e) => Err(e),
@@ -111,20 +113,23 @@ pub fn validate_state_from_db(
     .map_err(|e| OAuthError::Storage(format!("Database error: {}", e)))?
 }
 
-/// Store a new OAuth state token in the database.
+/// Store a new OAuth state token in the database, remembering which
+/// account of the provider the flow signs in.
 ///
 /// The state token expires after 15 minutes.
 pub fn store_state_in_db(
     db: &crate::db::Database,
     state_token: &str,
     provider: &str,
+    account: &str,
     code_verifier: &str,
 ) -> Result<(), OAuthError> {
     db.with_conn(|conn| {
         conn.execute(
-            "INSERT INTO oauth_state (state_token, provider, code_verifier, created_at, expires_at) \
-             VALUES (?1, ?2, ?3, datetime('now'), datetime('now', '+15 minutes'))",
-            rusqlite::params![state_token, provider, code_verifier],
+            "INSERT INTO oauth_state \
+             (state_token, provider, code_verifier, account, created_at, expires_at) \
+             VALUES (?1, ?2, ?3, ?4, datetime('now'), datetime('now', '+15 minutes'))",
+            rusqlite::params![state_token, provider, code_verifier, account],
         )?;
         Ok(())
     })
@@ -379,12 +384,13 @@ mod tests {
         let db = crate::db::Database::open_in_memory().unwrap();
 
         // Store state
-        store_state_in_db(&db, "test-state-123", "claude", "verifier-abc").unwrap();
+        store_state_in_db(&db, "test-state-123", "claude", "work", "verifier-abc").unwrap();
 
         // Validate state
-        let (provider, verifier) = validate_state_from_db(&db, "test-state-123").unwrap();
+        let (provider, verifier, account) = validate_state_from_db(&db, "test-state-123").unwrap();
         assert_eq!(provider, "claude");
         assert_eq!(verifier, "verifier-abc");
+        assert_eq!(account, "work");
 
         // State should be deleted after use (single-use)
         let result = validate_state_from_db(&db, "test-state-123");
@@ -12,8 +12,12 @@
 //! # Endpoints
 //! - Authorization: `https://claude.ai/oauth/authorize`
 //! - Token: `https://console.anthropic.com/v1/oauth/token`
+//!
+//! Tokens obtained by Claude Code can also be imported from its credentials
+//! file instead of running the flow again.
 
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 
 use oauth2::TokenResponse as _;
@@ -22,6 +26,7 @@ use oauth2::{
     AuthType, AuthUrl, AuthorizationCode, ClientId, CsrfToken, HttpClientError, PkceCodeChallenge,
     PkceCodeVerifier, RedirectUrl, RefreshToken, Scope, TokenUrl,
 };
+use serde::Deserialize;
 use tracing::{debug, warn};
 
 use super::OAuthClient;
@@ -319,6 +324,71 @@ pub async fn refresh_token(
     Ok(token)
 }
 
+// =============================================================================
+// Claude Code credential import
+// =============================================================================
+
+/// Layout of Claude Code's `.credentials.json`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClaudeCodeCredentials {
+    claude_ai_oauth: Option<ClaudeCodeOAuth>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClaudeCodeOAuth {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Expiry as Unix time in milliseconds.
+    expires_at: Option<i64>,
+}
+
+/// Default location of Claude Code's credentials file:
+/// `$CLAUDE_CONFIG_DIR/.credentials.json`, else `~/.claude/.credentials.json`.
+pub fn claude_code_credentials_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("CLAUDE_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".claude"),
+    };
+    Some(dir.join(".credentials.json"))
+}
+
+/// Parse the contents of a Claude Code credentials file into a token.
+pub fn parse_claude_code_credentials(content: &str) -> Result<TokenInfo, OAuthError> {
+    let credentials: ClaudeCodeCredentials = serde_json::from_str(content)
+        .map_err(|e| OAuthError::Other(format!("Invalid Claude Code credentials file: {}", e)))?;
+    let oauth = credentials.claude_ai_oauth.ok_or_else(|| {
+        OAuthError::TokenNotFound("claude (no claudeAiOauth entry in credentials file)".into())
+    })?;
+    if oauth.access_token.is_empty() {
+        return Err(OAuthError::TokenNotFound(
+            "claude (empty access token in credentials file)".into(),
+        ));
+    }
+    Ok(TokenInfo::with_expires_at(
+        oauth.access_token,
+        oauth.refresh_token.filter(|t| !t.is_empty()),
+        oauth.expires_at.map(|ms| ms / 1000),
+        PROVIDER_ID,
+    ))
+}
+
+/// Read the Claude Code credentials file at `path`.
+///
+/// This performs **blocking filesystem I/O**.
+pub fn read_claude_code_credentials(path: &Path) -> Result<TokenInfo, OAuthError> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            OAuthError::TokenNotFound(format!("claude (no credentials at {})", path.display()))
+        } else {
+            OAuthError::Storage(format!("Failed to read {}: {}", path.display(), e))
+        }
+    })?;
+    debug!(path = %path.display(), "Importing Claude Code credentials");
+    parse_claude_code_credentials(&content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -723,4 +793,40 @@ mod tests {
         assert_eq!(project.as_deref(), Some("proj-only"));
         assert!(managed.is_none());
     }
+
+    // =========================================================================
+    // Claude Code credential import
+    // =========================================================================
+
+    #[test]
+    fn test_parse_claude_code_credentials() {
+        let content = r#"{
+            "claudeAiOauth": {
+                "accessToken": "sk-ant-oat01-abc",
+                "refreshToken": "sk-ant-ort01-def",
+                "expiresAt": 1767225600000,
+                "scopes": ["user:inference", "user:profile"],
+                "subscriptionType": "pro"
+            }
+        }"#;
+        let token = parse_claude_code_credentials(content).unwrap();
+        assert_eq!(token.access_token, "sk-ant-oat01-abc");
+        assert_eq!(token.refresh_token.as_deref(), Some("sk-ant-ort01-def"));
+        assert_eq!(token.expires_at, Some(1_767_225_600));
+        assert_eq!(token.provider, PROVIDER_ID);
+    }
+
+    #[test]
+    fn test_parse_claude_code_credentials_without_oauth() {
+        let err = parse_claude_code_credentials(r#"{"mcpOAuth": {}}"#).unwrap_err();
+        assert!(matches!(err, OAuthError::TokenNotFound(_)));
+        assert!(parse_claude_code_credentials("not json").is_err());
+    }
+
+    #[test]
+    fn test_read_claude_code_credentials_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = read_claude_code_credentials(&dir.path().join(".credentials.json")).unwrap_err();
+        assert!(matches!(err, OAuthError::TokenNotFound(_)));
+    }
 }
@@ -12,6 +12,11 @@
 //! # Endpoints
 //! - Authorization: `https://accounts.google.com/o/oauth2/v2/auth`
 //! - Token: `https://oauth2.googleapis.com/token`
+//!
+//! Tokens obtained by gemini-cli can also be imported from its credentials
+//! file instead of running the flow again.
+
+use std::path::{Path, PathBuf};
 
 use oauth2::TokenResponse as _;
 use oauth2::basic::BasicClient;
@@ -19,6 +24,7 @@ use oauth2::{
     AuthType, AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge,
     PkceCodeVerifier, RedirectUrl, RefreshToken, Scope, TokenUrl,
 };
+use serde::Deserialize;
 use tracing::debug;
 
 use super::OAuthClient;
@@ -267,6 +273,59 @@ pub async fn refresh_token(
     Ok(token)
 }
 
+// =============================================================================
+// gemini-cli credential import
+// =============================================================================
+
+/// Layout of gemini-cli's `oauth_creds.json` (a Google OAuth token response
+/// with an absolute expiry).
+#[derive(Deserialize)]
+struct GeminiCliCredentials {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Expiry as Unix time in milliseconds.
+    expiry_date: Option<i64>,
+}
+
+/// Default location of gemini-cli's credentials file:
+/// `~/.gemini/oauth_creds.json`.
+pub fn gemini_cli_credentials_path() -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+    Some(home.join(".gemini").join("oauth_creds.json"))
+}
+
+/// Parse the contents of a gemini-cli credentials file into a token.
+pub fn parse_gemini_cli_credentials(content: &str) -> Result<TokenInfo, OAuthError> {
+    let credentials: GeminiCliCredentials = serde_json::from_str(content)
+        .map_err(|e| OAuthError::Other(format!("Invalid gemini-cli credentials file: {}", e)))?;
+    if credentials.access_token.is_empty() {
+        return Err(OAuthError::TokenNotFound(
+            "gemini (empty access token in credentials file)".into(),
+        ));
+    }
+    Ok(TokenInfo::with_expires_at(
+        credentials.access_token,
+        credentials.refresh_token.filter(|t| !t.is_empty()),
+        credentials.expiry_date.map(|ms| ms / 1000),
+        PROVIDER_ID,
+    ))
+}
+
+/// Read the gemini-cli credentials file at `path`.
+///
+/// This performs **blocking filesystem I/O**.
+pub fn read_gemini_cli_credentials(path: &Path) -> Result<TokenInfo, OAuthError> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            OAuthError::TokenNotFound(format!("gemini (no credentials at {})", path.display()))
+        } else {
+            OAuthError::Storage(format!("Failed to read {}: {}", path.display(), e))
+        }
+    })?;
+    debug!(path = %path.display(), "Importing gemini-cli credentials");
+    parse_gemini_cli_credentials(&content)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -631,4 +690,34 @@ mod tests {
         assert_eq!(project.as_deref(), Some("proj-only"));
         assert!(managed.is_none());
     }
+
+    // =========================================================================
+    // gemini-cli credential import
+    // =========================================================================
+
+    #[test]
+    fn test_parse_gemini_cli_credentials() {
+        let content = r#"{
+            "access_token": "ya29.a0-test",
+            "refresh_token": "1//0g-test",
+            "scope": "https://www.googleapis.com/auth/cloud-platform openid",
+            "token_type": "Bearer",
+            "id_token": "eyJ...",
+            "expiry_date": 1767225600123
+        }"#;
+        let token = parse_gemini_cli_credentials(content).unwrap();
+        assert_eq!(token.access_token, "ya29.a0-test");
+        assert_eq!(token.refresh_token.as_deref(), Some("1//0g-test"));
+        assert_eq!(token.expires_at, Some(1_767_225_600));
+        assert_eq!(token.provider, PROVIDER_ID);
+    }
+
+    #[test]
+    fn test_parse_gemini_cli_credentials_invalid() {
+        assert!(parse_gemini_cli_credentials(r#"{"refresh_token": "x"}"#).is_err());
+        assert!(matches!(
+            parse_gemini_cli_credentials(r#"{"access_token": ""}"#),
+            Err(OAuthError::TokenNotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,524 @@
+//! Kiro (AWS) SSO OIDC device authorization flow.
+//!
+//! Signs in to AWS Builder ID or IAM Identity Center without a pre-existing
+//! credentials file:
+//!
+//! 1. Register a public OIDC client (`RegisterClient`)
+//! 2. Request a device code (`StartDeviceAuthorization`)
+//! 3. User visits the verification URL and approves the code
+//! 4. Poll `CreateToken` until the user completes authorization
+//!
+//! The result is written as a Kiro credentials file (the JSON layout the
+//! Kiro provider's `JsonFileStore` reads), including the client
+//! registration, so the provider refreshes it through AWS SSO OIDC.
+//!
+//! # Endpoints
+//! - `https://oidc.{region}.amazonaws.com/client/register`
+//! - `https://oidc.{region}.amazonaws.com/device_authorization`
+//! - `https://oidc.{region}.amazonaws.com/token`
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tracing::{debug, info};
+
+use super::OAuthError;
+
+/// Provider identifier for Kiro.
+pub const PROVIDER_ID: &str = "kiro";
+
+/// Start URL of AWS Builder ID, used when no Identity Center URL is set.
+pub const BUILDER_ID_START_URL: &str = "https://view.awsapps.com/start";
+
+/// Client name shown on the AWS approval page.
+const CLIENT_NAME: &str = "gaud";
+
+/// Scopes needed for the Kiro (CodeWhisperer) API.
+const SCOPES: &[&str] = &[
+    "codewhisperer:completions",
+    "codewhisperer:analysis",
+    "codewhisperer:conversations",
+];
+
+/// Configuration for the Kiro device authorization flow.
+#[derive(Debug, Clone)]
+pub struct KiroOidcConfig {
+    /// Base URL of the SSO OIDC service.
+    pub oidc_url: String,
+    /// Region of the SSO OIDC service.
+    pub sso_region: String,
+    /// Kiro API region written to the credentials file.
+    pub region: String,
+    /// Builder ID or IAM Identity Center start URL.
+    pub start_url: String,
+}
+
+impl KiroOidcConfig {
+    /// Create config from the Kiro provider config.
+    pub fn from_provider_config(config: &crate::config::KiroProviderConfig) -> Self {
+        let sso_region = config.effective_sso_region();
+        Self {
+            oidc_url: format!("https://oidc.{}.amazonaws.com", sso_region),
+            sso_region,
+            region: config.effective_region(),
+            start_url: config
+                .sso_start_url
+                .clone()
+                .unwrap_or_else(|| BUILDER_ID_START_URL.to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegisterClientResponse {
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeviceAuthorizationResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    #[serde(default)]
+    verification_uri_complete: Option<String>,
+    expires_in: u64,
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+/// A device authorization in progress.
+///
+/// The client registration stays on the server; only the device and user
+/// codes and the verification URL are shown to the user.
+#[derive(Clone)]
+pub struct KiroDeviceFlow {
+    pub client_id: String,
+    pub client_secret: String,
+    /// The device code used for polling.
+    pub device_code: String,
+    /// The code the user must confirm at the verification URL.
+    pub user_code: String,
+    /// The URL where the user enters the code.
+    pub verification_uri: String,
+    /// Verification URL with the user code pre-filled.
+    pub verification_uri_complete: Option<String>,
+    /// Seconds until the device code expires.
+    pub expires_in: u64,
+    /// Minimum seconds between poll attempts.
+    pub interval: u64,
+}
+
+impl std::fmt::Debug for KiroDeviceFlow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KiroDeviceFlow")
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"[REDACTED]")
+            .field("user_code", &self.user_code)
+            .field("verification_uri", &self.verification_uri)
+            .field("expires_in", &self.expires_in)
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Response from the token polling endpoint.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateTokenResponse {
+    #[serde(default)]
+    access_token: Option<String>,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    error_description: Option<String>,
+}
+
+/// Credentials obtained by a completed flow.
+#[derive(Clone)]
+pub struct KiroCredentials {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// Seconds since the Unix epoch.
+    pub expires_at: i64,
+    pub region: String,
+    pub sso_region: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl std::fmt::Debug for KiroCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KiroCredentials")
+            .field("access_token", &"[REDACTED]")
+            .field("refresh_token", &"[REDACTED]")
+            .field("expires_at", &self.expires_at)
+            .field("region", &self.region)
+            .field("sso_region", &self.sso_region)
+            .field("client_id", &self.client_id)
+            .field("client_secret", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl KiroCredentials {
+    /// The credentials in the Kiro credentials file layout.
+    pub fn to_json(&self) -> serde_json::Value {
+        let expires_at = DateTime::from_timestamp(self.expires_at, 0).unwrap_or_else(Utc::now);
+        serde_json::json!({
+            "accessToken": self.access_token,
+            "refreshToken": self.refresh_token,
+            "expiresAt": expires_at.to_rfc3339(),
+            "region": self.region,
+            "ssoRegion": self.sso_region,
+            "clientId": self.client_id,
+            "clientSecret": self.client_secret,
+        })
+    }
+}
+
+/// Result of a single poll attempt.
+#[derive(Debug)]
+pub enum PollResult {
+    /// User hasn't completed authorization yet, keep polling.
+    Pending,
+    /// Server asked us to slow down, increase interval.
+    SlowDown,
+    /// Authorization complete.
+    Complete(KiroCredentials),
+}
+
+/// Register a client and request a device code.
+///
+/// The caller should display the `user_code` and `verification_uri` to the
+/// user and keep the returned flow for polling.
+pub async fn start_device_flow(
+    http_client: &reqwest::Client,
+    config: &KiroOidcConfig,
+) -> Result<KiroDeviceFlow, OAuthError> {
+    info!(region = %config.sso_region, "Registering AWS SSO OIDC client for Kiro");
+
+    let response = http_client
+        .post(format!("{}/client/register", config.oidc_url))
+        .json(&serde_json::json!({
+            "clientName": CLIENT_NAME,
+            "clientType": "public",
+            "scopes": SCOPES,
+        }))
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(OAuthError::ExchangeFailed(format!(
+            "Client registration failed (HTTP {}): {}",
+            status.as_u16(),
+            body
+        )));
+    }
+    let client: RegisterClientResponse = serde_json::from_str(&body).map_err(|e| {
+        OAuthError::ExchangeFailed(format!("Failed to parse client registration: {}", e))
+    })?;
+
+    let response = http_client
+        .post(format!("{}/device_authorization", config.oidc_url))
+        .json(&serde_json::json!({
+            "clientId": client.client_id,
+            "clientSecret": client.client_secret,
+            "startUrl": config.start_url,
+        }))
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(OAuthError::ExchangeFailed(format!(
+            "Device authorization failed (HTTP {}): {}",
+            status.as_u16(),
+            body
+        )));
+    }
+    let device: DeviceAuthorizationResponse = serde_json::from_str(&body).map_err(|e| {
+        OAuthError::ExchangeFailed(format!("Failed to parse device authorization: {}", e))
+    })?;
+
+    debug!(
+        user_code = %device.user_code,
+        verification_uri = %device.verification_uri,
+        expires_in = device.expires_in,
+        "Kiro device code obtained"
+    );
+
+    Ok(KiroDeviceFlow {
+        client_id: client.client_id,
+        client_secret: client.client_secret,
+        device_code: device.device_code,
+        user_code: device.user_code,
+        verification_uri: device.verification_uri,
+        verification_uri_complete: device.verification_uri_complete,
+        expires_in: device.expires_in,
+        interval: device.interval.unwrap_or(5),
+    })
+}
+
+/// Poll the token endpoint once.
+pub async fn poll_for_token(
+    http_client: &reqwest::Client,
+    config: &KiroOidcConfig,
+    flow: &KiroDeviceFlow,
+) -> Result<PollResult, OAuthError> {
+    let response = http_client
+        .post(format!("{}/token", config.oidc_url))
+        .json(&serde_json::json!({
+            "clientId": flow.client_id,
+            "clientSecret": flow.client_secret,
+            "grantType": "urn:ietf:params:oauth:grant-type:device_code",
+            "deviceCode": flow.device_code,
+        }))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let body = response.text().await?;
+
+    let poll_response: CreateTokenResponse = serde_json::from_str(&body).map_err(|_| {
+        OAuthError::ExchangeFailed(format!(
+            "Unexpected poll response (HTTP {}): {}",
+            status.as_u16(),
+            body
+        ))
+    })?;
+
+    if let (Some(access_token), Some(refresh_token)) =
+        (poll_response.access_token, poll_response.refresh_token)
+    {
+        return Ok(PollResult::Complete(KiroCredentials {
+            access_token,
+            refresh_token,
+            expires_at: Utc::now().timestamp() + poll_response.expires_in.unwrap_or(3600),
+            region: config.region.clone(),
+            sso_region: config.sso_region.clone(),
+            client_id: flow.client_id.clone(),
+            client_secret: flow.client_secret.clone(),
+        }));
+    }
+
+    match poll_response.error.as_deref() {
+        Some("authorization_pending") => Ok(PollResult::Pending),
+        Some("slow_down") => Ok(PollResult::SlowDown),
+        Some("expired_token") => Err(OAuthError::FlowExpired),
+        Some("access_denied") => Err(OAuthError::ExchangeFailed(
+            "User denied authorization".to_string(),
+        )),
+        Some(error) => Err(OAuthError::ExchangeFailed(format!(
+            "Poll error: {} - {}",
+            error,
+            poll_response.error_description.unwrap_or_default()
+        ))),
+        None => Err(OAuthError::ExchangeFailed(format!(
+            "Unexpected poll response (HTTP {}): {}",
+            status.as_u16(),
+            body
+        ))),
+    }
+}
+
+/// Write credentials to `path` with owner-only permissions, replacing any
+/// previous sign-in.
+///
+/// This performs **blocking filesystem I/O**.
+pub fn save_credentials(path: &Path, credentials: &KiroCredentials) -> Result<(), OAuthError> {
+    let storage_err = |e: std::io::Error| {
+        OAuthError::Storage(format!("Failed to write {}: {}", path.display(), e))
+    };
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(storage_err)?;
+    }
+    let content = serde_json::to_string_pretty(&credentials.to_json())
+        .map_err(|e| OAuthError::Storage(format!("Failed to serialize credentials: {}", e)))?;
+
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, content).map_err(storage_err)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))
+            .map_err(storage_err)?;
+    }
+    std::fs::rename(&tmp, path).map_err(storage_err)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn mock_config(uri: &str) -> KiroOidcConfig {
+        KiroOidcConfig {
+            oidc_url: uri.to_string(),
+            sso_region: "eu-west-1".to_string(),
+            region: "us-east-1".to_string(),
+            start_url: BUILDER_ID_START_URL.to_string(),
+        }
+    }
+
+    fn test_flow() -> KiroDeviceFlow {
+        KiroDeviceFlow {
+            client_id: "cid".into(),
+            client_secret: "csecret".into(),
+            device_code: "dev-123".into(),
+            user_code: "ABCD-EFGH".into(),
+            verification_uri: "https://device.sso.eu-west-1.amazonaws.com/".into(),
+            verification_uri_complete: None,
+            expires_in: 600,
+            interval: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_device_flow() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/client/register"))
+            .and(body_partial_json(
+                serde_json::json!({ "clientType": "public" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "clientId": "cid",
+                "clientSecret": "csecret",
+                "clientSecretExpiresAt": 1_900_000_000
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/device_authorization"))
+            .and(body_partial_json(serde_json::json!({
+                "clientId": "cid",
+                "startUrl": BUILDER_ID_START_URL
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "deviceCode": "dev-123",
+                "userCode": "ABCD-EFGH",
+                "verificationUri": "https://device.sso.eu-west-1.amazonaws.com/",
+                "verificationUriComplete": "https://device.sso.eu-west-1.amazonaws.com/?user_code=ABCD-EFGH",
+                "expiresIn": 600,
+                "interval": 1
+            })))
+            .mount(&server)
+            .await;
+
+        let flow = start_device_flow(&reqwest::Client::new(), &mock_config(&server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(flow.client_secret, "csecret");
+        assert_eq!(flow.device_code, "dev-123");
+        assert_eq!(flow.user_code, "ABCD-EFGH");
+        assert_eq!(flow.interval, 1);
+        assert!(flow.verification_uri_complete.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_poll_pending_then_complete() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": "authorization_pending",
+                "error_description": null
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_partial_json(serde_json::json!({
+                "deviceCode": "dev-123",
+                "grantType": "urn:ietf:params:oauth:grant-type:device_code"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "accessToken": "aoa-access",
+                "refreshToken": "aor-refresh",
+                "expiresIn": 3600,
+                "tokenType": "Bearer"
+            })))
+            .mount(&server)
+            .await;
+
+        let http = reqwest::Client::new();
+        let config = mock_config(&server.uri());
+        let flow = test_flow();
+        assert!(matches!(
+            poll_for_token(&http, &config, &flow).await.unwrap(),
+            PollResult::Pending
+        ));
+        let PollResult::Complete(creds) = poll_for_token(&http, &config, &flow).await.unwrap()
+        else {
+            panic!("expected completed flow");
+        };
+        assert_eq!(creds.access_token, "aoa-access");
+        assert_eq!(creds.client_secret, "csecret");
+        assert_eq!(creds.sso_region, "eu-west-1");
+        assert!(creds.expires_at > Utc::now().timestamp());
+    }
+
+    #[tokio::test]
+    async fn test_poll_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .set_body_json(serde_json::json!({ "error": "expired_token" })),
+            )
+            .mount(&server)
+            .await;
+
+        let result = poll_for_token(
+            &reqwest::Client::new(),
+            &mock_config(&server.uri()),
+            &test_flow(),
+        )
+        .await;
+        assert!(matches!(result, Err(OAuthError::FlowExpired)));
+    }
+
+    #[test]
+    fn test_save_credentials_in_kiro_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("kiro-sso.json");
+        let creds = KiroCredentials {
+            access_token: "aoa".into(),
+            refresh_token: "aor".into(),
+            expires_at: 1_767_225_600,
+            region: "us-east-1".into(),
+            sso_region: "eu-west-1".into(),
+            client_id: "cid".into(),
+            client_secret: "csecret".into(),
+        };
+        save_credentials(&path, &creds).unwrap();
+
+        let saved: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["refreshToken"], "aor");
+        assert_eq!(saved["clientSecret"], "csecret");
+        assert_eq!(saved["ssoRegion"], "eu-west-1");
+        assert_eq!(saved["expiresAt"], "2026-01-01T00:00:00+00:00");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -4,14 +4,16 @@
 //! - Claude (Anthropic) - PKCE authorization code flow
 //! - Gemini (Google) - PKCE authorization code flow with client secret
 //! - Copilot (GitHub) - Device Code flow (RFC 8628)
+//! - Kiro (AWS) - SSO OIDC device authorization flow
 //!
 //! # Architecture
 //!
-//! - [`OAuthManager`] - Central manager that orchestrates flows, stores state in SQLite
-//! - [`TokenStorage`] - Pluggable token persistence (file, keyring, memory)
+//! - [`OAuthManager`] - Central manager that orchestrates flows, stores state in SQLite,
+//!   and spreads requests across several accounts per provider
+//! - [`TokenStorage`] - Pluggable token persistence (file, keyring, memory, database)
 //! - [`TokenInfo`] - Token data with composite format and expiry checking
 //! - [`Pkce`] - PKCE S256 challenge/verifier generation
-//! - Provider modules (`claude`, `gemini`, `copilot`) - Provider-specific flows
+//! - Provider modules (`claude`, `gemini`, `copilot`, `kiro`) - Provider-specific flows
 //! - [`callback`] - OAuth callback handler with HTML responses
 //!
 //! # Example
@@ -33,6 +35,7 @@ pub mod callback;
 pub mod claude;
 pub mod copilot;
 pub mod gemini;
+pub mod kiro;
 pub mod pkce;
 pub mod storage;
 pub mod token;
@@ -43,19 +46,24 @@ pub use callback::{
     success_html, validate_callback_params, validate_state_from_db,
 };
 pub use pkce::Pkce;
-pub use storage::{FileTokenStorage, MemoryTokenStorage, TokenStorage};
+pub use storage::{DatabaseTokenStorage, FileTokenStorage, MemoryTokenStorage, TokenStorage};
 pub use token::TokenInfo;
 
 #[cfg(feature = "system-keyring")]
 pub use storage::KeyringTokenStorage;
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
 use oauth2::basic::BasicErrorResponseType;
 use tracing::{debug, info, warn};
 
-use crate::config::{Config, StorageBackend};
+use crate::config::{AccountSelection, Config, StorageBackend};
 use crate::db::Database;
+use crate::net::HttpClientFactory;
+use crate::providers::gemini::discovery::{ProjectInfo, SubscriptionTier};
 use crate::providers::{ProviderError, TokenService};
 
 // =============================================================================
@@ -182,11 +190,165 @@ pub(crate) fn map_oauth_token_error<RE: std::error::Error + 'static>(
     }
 }
 
+// =============================================================================
+// Accounts
+// =============================================================================
+
+/// Name of the account signed in without an explicit label. Its token is
+/// stored under the bare provider name, as before accounts existed.
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// Storage key for one account of a provider.
+pub fn account_key(provider: &str, account: &str) -> String {
+    if account == DEFAULT_ACCOUNT {
+        provider.to_string()
+    } else {
+        format!("{provider}@{account}")
+    }
+}
+
+/// Check an account label: 1-32 ASCII letters, digits, `-` or `_`.
+pub fn validate_account_name(account: &str) -> Result<(), OAuthError> {
+    let valid = !account.is_empty()
+        && account.len() <= 32
+        && account
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(OAuthError::Other(format!(
+            "Invalid account name '{account}': use 1-32 letters, digits, '-' or '_'"
+        )))
+    }
+}
+
+/// In-memory usage of one account, used for selection and reporting.
+#[derive(Debug, Clone, Default)]
+struct AccountUsage {
+    last_used: Option<chrono::DateTime<chrono::Utc>>,
+    requests: u64,
+}
+
+/// Round-robin cursors and usage per provider account.
+#[derive(Debug, Default)]
+struct AccountRotation {
+    cursors: HashMap<String, usize>,
+    usage: HashMap<String, AccountUsage>,
+}
+
+impl AccountRotation {
+    /// Order `accounts` (of `provider`) by preference for the next request.
+    fn order(
+        &mut self,
+        provider: &str,
+        mut accounts: Vec<String>,
+        selection: AccountSelection,
+    ) -> Vec<String> {
+        match selection {
+            AccountSelection::RoundRobin => {
+                if !accounts.is_empty() {
+                    let cursor = self.cursors.entry(provider.to_string()).or_default();
+                    let start = *cursor % accounts.len();
+                    accounts.rotate_left(start);
+                    *cursor = cursor.wrapping_add(1);
+                }
+            }
+            AccountSelection::LeastRecentlyUsed => {
+                accounts.sort_by_cached_key(|account| {
+                    self.usage
+                        .get(&account_key(provider, account))
+                        .and_then(|u| u.last_used)
+                });
+            }
+        }
+        accounts
+    }
+
+    fn record_use(&mut self, provider: &str, account: &str) {
+        let usage = self
+            .usage
+            .entry(account_key(provider, account))
+            .or_default();
+        usage.last_used = Some(chrono::Utc::now());
+        usage.requests += 1;
+    }
+
+    fn usage(&self, provider: &str, account: &str) -> AccountUsage {
+        self.usage
+            .get(&account_key(provider, account))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+// =============================================================================
+// Credential import
+// =============================================================================
+
+/// A local CLI whose stored OAuth credentials can be imported into a
+/// provider's pool instead of running the flow again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CredentialSource {
+    /// Claude Code (`~/.claude/.credentials.json`).
+    ClaudeCode,
+    /// gemini-cli (`~/.gemini/oauth_creds.json`).
+    GeminiCli,
+}
+
+impl CredentialSource {
+    /// Provider the imported token belongs to.
+    pub fn provider(&self) -> &'static str {
+        match self {
+            Self::ClaudeCode => claude::PROVIDER_ID,
+            Self::GeminiCli => gemini::PROVIDER_ID,
+        }
+    }
+
+    /// Where the tool keeps its credentials for the current user.
+    pub fn default_path(&self) -> Option<PathBuf> {
+        match self {
+            Self::ClaudeCode => claude::claude_code_credentials_path(),
+            Self::GeminiCli => gemini::gemini_cli_credentials_path(),
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<TokenInfo, OAuthError> {
+        match self {
+            Self::ClaudeCode => claude::read_claude_code_credentials(path),
+            Self::GeminiCli => gemini::read_gemini_cli_credentials(path),
+        }
+    }
+}
+
+impl std::fmt::Display for CredentialSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ClaudeCode => write!(f, "claude-code"),
+            Self::GeminiCli => write!(f, "gemini-cli"),
+        }
+    }
+}
+
+impl FromStr for CredentialSource {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "claude-code" => Ok(Self::ClaudeCode),
+            "gemini-cli" => Ok(Self::GeminiCli),
+            _ => Err(format!("Unknown credential source: {s}")),
+        }
+    }
+}
+
 // =============================================================================
 // OAuthStatus
 // =============================================================================
 
 /// Status of OAuth authentication for a provider.
+///
+/// The top-level fields summarize all accounts: the provider counts as
+/// authenticated if any account is, and as expired only if all are.
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct OAuthStatus {
     /// Provider identifier.
@@ -199,6 +361,32 @@ pub struct OAuthStatus {
     pub needs_refresh: bool,
     /// Seconds until the token expires (None if no expiry or not authenticated).
     pub expires_in_secs: Option<u64>,
+    /// Per-account status, in sign-in order.
+    pub accounts: Vec<AccountStatus>,
+    /// Cloud Code project requests are sent to (Gemini only): the configured
+    /// override, else the project discovered earlier.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    /// Subscription tier detected during project discovery (Gemini only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_tier: Option<String>,
+}
+
+/// Status of one signed-in account of a provider.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountStatus {
+    /// Account label ([`DEFAULT_ACCOUNT`] for the unlabeled account).
+    pub account: String,
+    /// Whether the token is expired.
+    pub expired: bool,
+    /// Whether the token needs proactive refresh.
+    pub needs_refresh: bool,
+    /// Seconds until the token expires (None if no expiry).
+    pub expires_in_secs: Option<u64>,
+    /// When the account last served a request since startup.
+    pub last_used: Option<chrono::DateTime<chrono::Utc>>,
+    /// Requests served by the account since startup.
+    pub requests: u64,
 }
 
 // =============================================================================
@@ -213,26 +401,60 @@ pub struct OAuthManager {
     config: Arc<Config>,
     db: Database,
     storage: Arc<dyn TokenStorage>,
+    /// Client for each OAuth provider, routed through its outbound proxy.
+    http_clients: HashMap<&'static str, reqwest::Client>,
+    /// Client for anything else, routed through the global proxy.
     http_client: reqwest::Client,
+    /// Account selection state for providers with several accounts.
+    rotation: Mutex<AccountRotation>,
+    /// Serializes refreshes so a request and the background refresh never
+    /// spend the same (possibly single-use) refresh token twice.
+    refresh_lock: tokio::sync::Mutex<()>,
+    /// Kiro device flows awaiting approval, by device code, with their
+    /// deadline. Holds the client registration, which never leaves the
+    /// server.
+    kiro_flows: Mutex<HashMap<String, (kiro::KiroDeviceFlow, std::time::Instant)>>,
+    /// Told when a refresh token is rejected and an account needs signing in.
+    notifier: Option<Arc<crate::notify::Notifier>>,
 }
 
 impl OAuthManager {
     /// Create a new OAuthManager.
     pub fn new(config: Arc<Config>, db: Database, storage: Arc<dyn TokenStorage>) -> Self {
-        let http_client = reqwest::Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .unwrap_or_default();
+        let factory = HttpClientFactory::new(&config.providers);
+        let build_client = |provider: &str| {
+            factory
+                .builder(provider)
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .unwrap_or_default()
+        };
+        let http_clients = ["claude", "gemini", "copilot", "kiro"]
+            .into_iter()
+            .map(|provider| (provider, build_client(provider)))
+            .collect();
+        let http_client = build_client("");
 
         Self {
             config,
             db,
             storage,
+            http_clients,
             http_client,
+            rotation: Mutex::new(AccountRotation::default()),
+            refresh_lock: tokio::sync::Mutex::new(()),
+            kiro_flows: Mutex::new(HashMap::new()),
+            notifier: None,
         }
     }
 
+    /// Announce accounts whose refresh token was rejected through `notifier`.
+    pub fn with_notifier(mut self, notifier: Arc<crate::notify::Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
     /// Create an OAuthManager using the storage backend from config.
     pub fn from_config(config: Arc<Config>, db: Database) -> Self {
         let storage: Arc<dyn TokenStorage> = match config.providers.storage_backend {
@@ -249,14 +471,28 @@ impl OAuthManager {
                 Arc::new(FileTokenStorage::new(&config.providers.token_storage_dir))
             }
             StorageBackend::Memory => Arc::new(MemoryTokenStorage::new()),
+            StorageBackend::Database => match config
+                .providers
+                .token_encryption_key
+                .as_deref()
+                .filter(|k| !k.is_empty())
+            {
+                Some(secret) => Arc::new(DatabaseTokenStorage::new(db.clone(), secret)),
+                None => {
+                    tracing::warn!(
+                        "Database storage requested but providers.token_encryption_key is not set, falling back to file storage"
+                    );
+                    Arc::new(FileTokenStorage::new(&config.providers.token_storage_dir))
+                }
+            },
         };
 
         Self::new(config, db, storage)
     }
 
-    /// Get a reference to the HTTP client.
-    pub fn http_client(&self) -> &reqwest::Client {
-        &self.http_client
+    /// Get the HTTP client for `provider`'s OAuth endpoints.
+    pub fn http_client(&self, provider: &str) -> &reqwest::Client {
+        self.http_clients.get(provider).unwrap_or(&self.http_client)
     }
 
     /// Get a reference to the token storage.
@@ -277,9 +513,16 @@ impl OAuthManager {
     /// URL. The caller should also use `start_copilot_device_flow()` for
     /// the full device code response.
     pub fn start_flow(&self, provider: &str) -> Result<String, OAuthError> {
+        self.start_account_flow(provider, DEFAULT_ACCOUNT)
+    }
+
+    /// Start an OAuth flow that signs in `account` of the given provider,
+    /// adding it to the provider's account pool once completed.
+    pub fn start_account_flow(&self, provider: &str, account: &str) -> Result<String, OAuthError> {
+        validate_account_name(account)?;
         match provider {
-            "claude" => self.start_claude_flow(),
-            "gemini" => self.start_gemini_flow(),
+            "claude" => self.start_claude_flow(account),
+            "gemini" => self.start_gemini_flow(account),
             "copilot" => Ok("https://github.com/login/device".to_string()),
             "kiro" => Err(OAuthError::Other(
                 "Kiro uses internal auth (refresh token / AWS SSO); no browser OAuth flow required"
@@ -289,7 +532,7 @@ impl OAuthManager {
         }
     }
 
-    fn start_claude_flow(&self) -> Result<String, OAuthError> {
+    fn start_claude_flow(&self, account: &str) -> Result<String, OAuthError> {
         let provider_config = self
             .config
             .providers
@@ -307,13 +550,13 @@ impl OAuthManager {
         let (url, verifier) = claude::build_authorize_url(&oauth_config, &state)?;
 
         // Store state in DB
-        store_state_in_db(&self.db, &state, "claude", &verifier)?;
+        store_state_in_db(&self.db, &state, "claude", account, &verifier)?;
 
         info!(provider = "claude", "Started OAuth flow");
         Ok(url)
     }
 
-    fn start_gemini_flow(&self) -> Result<String, OAuthError> {
+    fn start_gemini_flow(&self, account: &str) -> Result<String, OAuthError> {
         let provider_config = self
             .config
             .providers
@@ -332,7 +575,7 @@ impl OAuthManager {
         let state = uuid::Uuid::new_v4().to_string();
         let (url, verifier) = gemini::build_authorize_url(&oauth_config, &state)?;
 
-        store_state_in_db(&self.db, &state, "gemini", &verifier)?;
+        store_state_in_db(&self.db, &state, "gemini", account, &verifier)?;
 
         info!(provider = "gemini", "Started OAuth flow");
         Ok(url)
@@ -355,7 +598,77 @@ impl OAuthManager {
         let oauth_config =
             copilot::CopilotOAuthConfig::from_provider_config(&provider_config.client_id);
 
-        copilot::request_device_code(&self.http_client, &oauth_config).await
+        copilot::request_device_code(self.http_client("copilot"), &oauth_config).await
+    }
+
+    /// Start the Kiro AWS SSO device authorization flow.
+    ///
+    /// Returns the flow to display; its device code identifies it in
+    /// [`poll_kiro_device_flow`](Self::poll_kiro_device_flow).
+    pub async fn start_kiro_device_flow(&self) -> Result<kiro::KiroDeviceFlow, OAuthError> {
+        let provider_config = self
+            .config
+            .providers
+            .kiro
+            .as_ref()
+            .ok_or_else(|| OAuthError::Other("Kiro provider not configured".to_string()))?;
+        let oidc_config = kiro::KiroOidcConfig::from_provider_config(provider_config);
+
+        let flow = kiro::start_device_flow(self.http_client("kiro"), &oidc_config).await?;
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(flow.expires_in);
+        let mut flows = self.kiro_flows.lock().unwrap();
+        let now = std::time::Instant::now();
+        flows.retain(|_, (_, until)| *until > now);
+        flows.insert(flow.device_code.clone(), (flow.clone(), deadline));
+        Ok(flow)
+    }
+
+    /// Poll a Kiro device flow once. On completion the credentials are
+    /// written to the Kiro credentials file, where the Kiro provider picks
+    /// them up.
+    pub async fn poll_kiro_device_flow(
+        &self,
+        device_code: &str,
+    ) -> Result<kiro::PollResult, OAuthError> {
+        let provider_config = self
+            .config
+            .providers
+            .kiro
+            .as_ref()
+            .ok_or_else(|| OAuthError::Other("Kiro provider not configured".to_string()))?;
+        let oidc_config = kiro::KiroOidcConfig::from_provider_config(provider_config);
+
+        let flow = match self.kiro_flows.lock().unwrap().get(device_code) {
+            Some((_, until)) if *until <= std::time::Instant::now() => None,
+            Some((flow, _)) => Some(flow.clone()),
+            None => None,
+        };
+        let Some(flow) = flow else {
+            self.kiro_flows.lock().unwrap().remove(device_code);
+            return Err(OAuthError::FlowExpired);
+        };
+
+        let result = kiro::poll_for_token(self.http_client("kiro"), &oidc_config, &flow).await;
+        match &result {
+            Ok(kiro::PollResult::Complete(credentials)) => {
+                self.kiro_flows.lock().unwrap().remove(device_code);
+                let path =
+                    provider_config.credentials_path(&self.config.providers.token_storage_dir);
+                let credentials = credentials.clone();
+                tokio::task::spawn_blocking(move || kiro::save_credentials(&path, &credentials))
+                    .await
+                    .map_err(|e| OAuthError::Other(format!("Save task failed: {}", e)))??;
+                info!(
+                    provider = "kiro",
+                    "Device flow completed, credentials stored"
+                );
+            }
+            Ok(_) => {}
+            Err(_) => {
+                self.kiro_flows.lock().unwrap().remove(device_code);
+            }
+        }
+        result
     }
 
     // =========================================================================
@@ -365,7 +678,8 @@ impl OAuthManager {
     /// Complete an OAuth flow by exchanging the authorization code for tokens.
     ///
     /// Validates the state token against the database, exchanges the code
-    /// using the stored PKCE verifier, and saves the resulting tokens.
+    /// using the stored PKCE verifier, and saves the resulting tokens for the
+    /// account the flow was started for.
     pub async fn complete_flow(
         &self,
         provider: &str,
@@ -373,7 +687,7 @@ impl OAuthManager {
         state: &str,
     ) -> Result<TokenInfo, OAuthError> {
         // Validate state and get verifier from DB
-        let (db_provider, code_verifier) = validate_state_from_db(&self.db, state)?;
+        let (db_provider, code_verifier, account) = validate_state_from_db(&self.db, state)?;
 
         // Verify provider matches
         if db_provider != provider {
@@ -397,8 +711,8 @@ impl OAuthManager {
         };
 
         // Store the token
-        self.storage.save(provider, &token)?;
-        info!(provider, "OAuth flow completed, token stored");
+        self.save_account_token(provider, &account, &token)?;
+        info!(provider, %account, "OAuth flow completed, token stored");
 
         Ok(token)
     }
@@ -421,7 +735,7 @@ impl OAuthManager {
             provider_config.callback_port,
         );
 
-        claude::exchange_code(&self.http_client, &oauth_config, code, verifier).await
+        claude::exchange_code(self.http_client("claude"), &oauth_config, code, verifier).await
     }
 
     async fn complete_gemini_flow(
@@ -444,7 +758,7 @@ impl OAuthManager {
             provider_config.callback_port,
         );
 
-        gemini::exchange_code(&self.http_client, &oauth_config, code, verifier).await
+        gemini::exchange_code(self.http_client("gemini"), &oauth_config, code, verifier).await
     }
 
     /// Complete the Copilot device code flow by polling until authorized.
@@ -467,7 +781,7 @@ impl OAuthManager {
             copilot::CopilotOAuthConfig::from_provider_config(&provider_config.client_id);
 
         let access_token = copilot::poll_until_complete(
-            &self.http_client,
+            self.http_client("copilot"),
             &oauth_config,
             device_response,
             on_pending,
@@ -488,21 +802,32 @@ impl OAuthManager {
     // Token management
     // =========================================================================
 
-    /// Refresh the token for a provider.
+    /// Refresh the token for a provider's default account.
     ///
     /// Loads the current token, uses its refresh token to obtain a new
     /// access token, and saves the updated token.
     pub async fn refresh_token(&self, provider: &str) -> Result<TokenInfo, OAuthError> {
+        self.refresh_account_token(provider, DEFAULT_ACCOUNT).await
+    }
+
+    /// Refresh the token for one account of a provider.
+    pub async fn refresh_account_token(
+        &self,
+        provider: &str,
+        account: &str,
+    ) -> Result<TokenInfo, OAuthError> {
+        let _guard = self.refresh_lock.lock().await;
+        let key = account_key(provider, account);
         let current = self
             .storage
-            .load(provider)?
+            .load(&key)?
             .ok_or_else(|| OAuthError::TokenNotFound(provider.to_string()))?;
 
         let refresh = current.refresh_token.as_deref().ok_or_else(|| {
             OAuthError::ExchangeFailed(format!("No refresh token for {}", provider))
         })?;
 
-        let new_token = match provider {
+        let refreshed = match provider {
             "claude" => {
                 let pc = self.config.providers.claude.as_ref().ok_or_else(|| {
                     OAuthError::Other("Claude provider not configured".to_string())
@@ -512,7 +837,7 @@ impl OAuthManager {
                     &pc.auth_url,
                     pc.callback_port,
                 );
-                claude::refresh_token(&self.http_client, &oc, refresh).await?
+                claude::refresh_token(self.http_client("claude"), &oc, refresh).await
             }
             "gemini" => {
                 let pc = self.config.providers.gemini.as_ref().ok_or_else(|| {
@@ -525,7 +850,7 @@ impl OAuthManager {
                     &pc.token_url,
                     pc.callback_port,
                 );
-                gemini::refresh_token(&self.http_client, &oc, refresh).await?
+                gemini::refresh_token(self.http_client("gemini"), &oc, refresh).await
             }
             "copilot" => {
                 // Copilot doesn't use refresh tokens in the traditional sense;
@@ -540,11 +865,74 @@ impl OAuthManager {
             }
         };
 
-        self.storage.save(provider, &new_token)?;
-        debug!(provider, "Token refreshed successfully");
+        let mut new_token = match refreshed {
+            Ok(token) => token,
+            Err(e @ OAuthError::TokenExpired(_)) => {
+                warn!(provider, %account, "Refresh token rejected, re-authentication required");
+                if let Some(notifier) = &self.notifier {
+                    notifier.notify(crate::notify::Notification::ReauthRequired {
+                        provider: provider.to_string(),
+                        account: account.to_string(),
+                    });
+                }
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        };
+
+        // The tier belongs to the project IDs carried over in the refresh token.
+        if new_token.project_id().is_some() {
+            new_token.subscription_tier = current.subscription_tier.clone();
+        }
+
+        self.storage.save(&key, &new_token)?;
+        debug!(provider, %account, "Token refreshed successfully");
         Ok(new_token)
     }
 
+    /// Refresh every stored token that is close to expiry, across all
+    /// accounts of the configured OAuth providers. Failures are logged and
+    /// left for the next request to surface. Returns the number of tokens
+    /// refreshed.
+    pub async fn refresh_expiring_tokens(&self) -> usize {
+        let providers = [
+            ("claude", self.config.providers.claude.is_some()),
+            ("gemini", self.config.providers.gemini.is_some()),
+        ];
+        let mut refreshed = 0;
+        for (provider, configured) in providers {
+            if !configured {
+                continue;
+            }
+            let accounts = match self.accounts(provider) {
+                Ok(accounts) => accounts,
+                Err(e) => {
+                    warn!(provider, error = %e, "Failed to list OAuth accounts");
+                    continue;
+                }
+            };
+            for account in accounts {
+                let due = matches!(
+                    self.storage.load(&account_key(provider, &account)),
+                    Ok(Some(ref t)) if t.needs_refresh() && t.refresh_token.is_some()
+                );
+                if !due {
+                    continue;
+                }
+                match self.refresh_account_token(provider, &account).await {
+                    Ok(_) => {
+                        info!(provider, %account, "Refreshed token ahead of expiry");
+                        refreshed += 1;
+                    }
+                    Err(e) => {
+                        warn!(provider, %account, error = %e, "Background token refresh failed");
+                    }
+                }
+            }
+        }
+        refreshed
+    }
+
     /// Get the current OAuth status for a provider.
     ///
     /// For Kiro, auth is managed internally by the kiro-gateway client.
@@ -558,75 +946,331 @@ impl OAuthManager {
             let configured = kiro_config.is_some();
             // Report as authenticated only if configured and credential source
             // is specified (refresh token, credentials file, or SQLite DB).
-            let has_credentials = kiro_config.map(|c| c.has_credentials()).unwrap_or(false);
+            let has_credentials = kiro_config.is_some_and(|c| {
+                c.has_credentials()
+                    || c.credentials_path(&self.config.providers.token_storage_dir)
+                        .exists()
+            });
             return Ok(OAuthStatus {
                 provider: provider.to_string(),
                 authenticated: configured && has_credentials,
                 expired: false,
                 needs_refresh: false,
                 expires_in_secs: None,
+                accounts: Vec::new(),
+                project_id: None,
+                subscription_tier: None,
             });
         }
 
-        let token = self.storage.load(provider)?;
+        let mut accounts = Vec::new();
+        for account in self.accounts(provider)? {
+            if let Some(status) = self.account_status(provider, account)? {
+                accounts.push(status);
+            }
+        }
 
-        match token {
-            Some(t) => Ok(OAuthStatus {
-                provider: provider.to_string(),
-                authenticated: true,
-                expired: t.is_expired(),
-                needs_refresh: t.needs_refresh(),
-                expires_in_secs: t.expires_at.map(|exp| {
-                    let now = chrono::Utc::now().timestamp();
-                    if exp > now { (exp - now) as u64 } else { 0 }
-                }),
-            }),
-            None => Ok(OAuthStatus {
-                provider: provider.to_string(),
-                authenticated: false,
-                expired: false,
-                needs_refresh: false,
-                expires_in_secs: None,
+        let (project_id, subscription_tier) = if provider == "gemini" {
+            self.project_status()?
+        } else {
+            (None, None)
+        };
+
+        Ok(OAuthStatus {
+            provider: provider.to_string(),
+            authenticated: !accounts.is_empty(),
+            expired: !accounts.is_empty() && accounts.iter().all(|a| a.expired),
+            needs_refresh: !accounts.is_empty() && accounts.iter().all(|a| a.needs_refresh),
+            expires_in_secs: accounts.iter().filter_map(|a| a.expires_in_secs).max(),
+            accounts,
+            project_id,
+            subscription_tier,
+        })
+    }
+
+    /// Effective Gemini project and its tier, for the status report. The tier
+    /// is unknown when the project is overridden in config.
+    fn project_status(&self) -> Result<(Option<String>, Option<String>), OAuthError> {
+        let configured = self
+            .config
+            .providers
+            .gemini
+            .as_ref()
+            .and_then(|g| g.project_id.clone());
+        if configured.is_some() {
+            return Ok((configured, None));
+        }
+        Ok(match self.stored_project("gemini")? {
+            Some(project) => (
+                Some(project.project_id),
+                Some(project.subscription_tier.to_string()),
+            ),
+            None => (None, None),
+        })
+    }
+
+    /// Cloud Code project stored with a provider's credentials.
+    ///
+    /// Discovery runs once per process and its result is shared by all
+    /// accounts, so it is kept with the account listed first.
+    pub fn stored_project(&self, provider: &str) -> Result<Option<ProjectInfo>, OAuthError> {
+        let Some(account) = self.accounts(provider)?.into_iter().next() else {
+            return Ok(None);
+        };
+        let Some(token) = self.storage.load(&account_key(provider, &account))? else {
+            return Ok(None);
+        };
+        Ok(token.project_id().map(|project_id| {
+            let tier = token
+                .subscription_tier
+                .as_deref()
+                .map(SubscriptionTier::parse)
+                .unwrap_or(SubscriptionTier::Unknown);
+            ProjectInfo::new(project_id, token.managed_project_id(), tier)
+        }))
+    }
+
+    /// Store a discovered Cloud Code project with the credentials of the
+    /// provider's first account, embedded in its refresh token.
+    pub async fn save_project(
+        &self,
+        provider: &str,
+        project: &ProjectInfo,
+    ) -> Result<(), OAuthError> {
+        // Serialize with refreshes, which rewrite the same token.
+        let _guard = self.refresh_lock.lock().await;
+        let Some(account) = self.accounts(provider)?.into_iter().next() else {
+            return Ok(());
+        };
+        let key = account_key(provider, &account);
+        let Some(token) = self.storage.load(&key)? else {
+            return Ok(());
+        };
+        // Without a refresh token there is nothing to embed the IDs in.
+        if token.refresh_token.is_none() {
+            return Ok(());
+        }
+
+        let mut token =
+            token.with_project_ids(&project.project_id, project.managed_project_id.as_deref());
+        token.subscription_tier = Some(project.subscription_tier.to_string());
+        self.storage.save(&key, &token)?;
+        debug!(provider, %account, project_id = %project.project_id, "Cached discovered project");
+        Ok(())
+    }
+
+    fn account_status(
+        &self,
+        provider: &str,
+        account: String,
+    ) -> Result<Option<AccountStatus>, OAuthError> {
+        let Some(token) = self.storage.load(&account_key(provider, &account))? else {
+            return Ok(None);
+        };
+        let usage = self.rotation.lock().unwrap().usage(provider, &account);
+        Ok(Some(AccountStatus {
+            account,
+            expired: token.is_expired(),
+            needs_refresh: token.needs_refresh(),
+            expires_in_secs: token.expires_at.map(|exp| {
+                let now = chrono::Utc::now().timestamp();
+                if exp > now { (exp - now) as u64 } else { 0 }
             }),
+            last_used: usage.last_used,
+            requests: usage.requests,
+        }))
+    }
+
+    /// Accounts of a provider that have a stored token, default account
+    /// first, then labeled accounts in sign-in order.
+    pub fn accounts(&self, provider: &str) -> Result<Vec<String>, OAuthError> {
+        let labeled: Vec<String> = self
+            .db
+            .with_conn(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT account FROM oauth_accounts WHERE provider = ?1 \
+                     ORDER BY created_at, account",
+                )?;
+                let rows = stmt.query_map([provider], |row| row.get(0))?;
+                rows.collect()
+            })
+            .map_err(|e| OAuthError::Storage(format!("Database error: {}", e)))?;
+
+        let mut accounts = Vec::new();
+        for account in std::iter::once(DEFAULT_ACCOUNT.to_string()).chain(labeled) {
+            if self.storage.exists(&account_key(provider, &account))? {
+                accounts.push(account);
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// Save a token for one account of a provider, adding the account to the
+    /// provider's pool.
+    pub fn save_account_token(
+        &self,
+        provider: &str,
+        account: &str,
+        token: &TokenInfo,
+    ) -> Result<(), OAuthError> {
+        validate_account_name(account)?;
+        self.storage.save(&account_key(provider, account), token)?;
+        if account != DEFAULT_ACCOUNT {
+            self.db
+                .with_conn(|conn| {
+                    conn.execute(
+                        "INSERT OR IGNORE INTO oauth_accounts (provider, account) VALUES (?1, ?2)",
+                        [provider, account],
+                    )?;
+                    Ok(())
+                })
+                .map_err(|e| OAuthError::Storage(format!("Failed to register account: {}", e)))?;
         }
+        Ok(())
     }
 
-    /// Get the stored token for a provider, if any.
+    /// Import the token a local CLI stored in its credentials file into
+    /// `account` of the matching provider's pool. `path` defaults to
+    /// [`CredentialSource::default_path`].
+    pub async fn import_credentials(
+        &self,
+        source: CredentialSource,
+        path: Option<PathBuf>,
+        account: &str,
+    ) -> Result<TokenInfo, OAuthError> {
+        validate_account_name(account)?;
+        let path = path
+            .or_else(|| source.default_path())
+            .ok_or_else(|| OAuthError::Other("Cannot locate the home directory".to_string()))?;
+        let token = tokio::task::spawn_blocking(move || source.read(&path))
+            .await
+            .map_err(|e| OAuthError::Other(format!("Import task failed: {}", e)))??;
+        self.save_account_token(source.provider(), account, &token)?;
+        info!(%source, %account, "Imported OAuth credentials");
+        Ok(token)
+    }
+
+    /// Get the stored token for a provider's default account, if any.
     pub fn get_token(&self, provider: &str) -> Result<Option<TokenInfo>, OAuthError> {
         self.storage.load(provider)
     }
 
     /// Get a valid access token for a provider, refreshing if needed.
     ///
+    /// When the provider has several accounts, one is picked according to
+    /// `providers.account_selection` so upstream rate limits are spread
+    /// across them. Accounts whose token cannot be refreshed are skipped.
+    ///
     /// Returns the access token string ready for use in API requests.
     pub async fn get_valid_token(&self, provider: &str) -> Result<String, OAuthError> {
+        let accounts = self.accounts(provider)?;
+        let selection = self.config.providers.account_selection;
+        let ordered = self
+            .rotation
+            .lock()
+            .unwrap()
+            .order(provider, accounts, selection);
+
+        let mut last_err = OAuthError::TokenNotFound(provider.to_string());
+        for account in ordered {
+            match self.account_token(provider, &account).await {
+                Ok(access_token) => {
+                    self.rotation.lock().unwrap().record_use(provider, &account);
+                    return Ok(access_token);
+                }
+                Err(e) => {
+                    warn!(provider, %account, error = %e, "Account token unavailable, trying next");
+                    last_err = e;
+                }
+            }
+        }
+        Err(last_err)
+    }
+
+    /// Valid access token of one account, refreshing if needed.
+    async fn account_token(&self, provider: &str, account: &str) -> Result<String, OAuthError> {
         let token = self
             .storage
-            .load(provider)?
+            .load(&account_key(provider, account))?
             .ok_or_else(|| OAuthError::TokenNotFound(provider.to_string()))?;
 
         if token.needs_refresh() {
-            debug!(provider, "Token needs refresh, refreshing...");
-            let new_token = self.refresh_token(provider).await?;
+            debug!(provider, %account, "Token needs refresh, refreshing...");
+            let new_token = self.refresh_account_token(provider, account).await?;
             return Ok(new_token.access_token);
         }
 
         Ok(token.access_token)
     }
 
-    /// Remove stored tokens for a provider (logout).
+    /// Remove stored tokens for a provider's default account (logout).
     pub fn remove_token(&self, provider: &str) -> Result<(), OAuthError> {
-        self.storage.remove(provider)?;
-        info!(provider, "Token removed");
+        self.remove_account(provider, DEFAULT_ACCOUNT)
+    }
+
+    /// Remove one account of a provider and its stored token.
+    pub fn remove_account(&self, provider: &str, account: &str) -> Result<(), OAuthError> {
+        self.storage.remove(&account_key(provider, account))?;
+        self.db
+            .with_conn(|conn| {
+                conn.execute(
+                    "DELETE FROM oauth_accounts WHERE provider = ?1 AND account = ?2",
+                    [provider, account],
+                )?;
+                Ok(())
+            })
+            .map_err(|e| OAuthError::Storage(format!("Failed to remove account: {}", e)))?;
+        info!(provider, %account, "Token removed");
         Ok(())
     }
 }
 
+/// Spawn a task that calls [`OAuthManager::refresh_expiring_tokens`] every
+/// `interval`, starting immediately, so requests after an idle period don't
+/// wait on (or fail on) an expired token. Only the elected `leader` refreshes,
+/// so processes sharing a database don't race on rotating refresh tokens.
+pub fn spawn_token_refresh(
+    manager: Arc<OAuthManager>,
+    interval: std::time::Duration,
+    leader: Arc<crate::leader::Leader>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if !leader.is_leader() {
+                continue;
+            }
+            let refreshed = manager.refresh_expiring_tokens().await;
+            if refreshed > 0 {
+                debug!(refreshed, "Background token refresh pass complete");
+            }
+        }
+    })
+}
+
 #[async_trait::async_trait]
 impl TokenService for OAuthManager {
     async fn get_token(&self, provider: &str) -> Result<String, ProviderError> {
         self.get_valid_token(provider).await.map_err(Into::into)
     }
+
+    async fn cached_project(&self, provider: &str) -> Option<ProjectInfo> {
+        match self.stored_project(provider) {
+            Ok(project) => project,
+            Err(e) => {
+                warn!(provider, error = %e, "Failed to read cached project");
+                None
+            }
+        }
+    }
+
+    async fn store_project(&self, provider: &str, project: &ProjectInfo) {
+        if let Err(e) = self.save_project(provider, project).await {
+            warn!(provider, error = %e, "Failed to cache discovered project");
+        }
+    }
 }
 
 #[allow(deprecated)]
@@ -787,6 +1431,217 @@ mod tests {
         assert!(url.contains("github.com"));
     }
 
+    fn pooled_manager(selection: AccountSelection) -> OAuthManager {
+        let mut config = test_config();
+        config.providers.account_selection = selection;
+        let manager = OAuthManager::new(
+            Arc::new(config),
+            test_db(),
+            Arc::new(MemoryTokenStorage::new()),
+        );
+        for (account, access) in [("default", "tok-a"), ("work", "tok-b")] {
+            let token = TokenInfo::new(access.into(), None, Some(3600), "gemini");
+            manager
+                .save_account_token("gemini", account, &token)
+                .unwrap();
+        }
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_get_valid_token_round_robin() {
+        let manager = pooled_manager(AccountSelection::RoundRobin);
+        assert_eq!(manager.accounts("gemini").unwrap(), vec!["default", "work"]);
+
+        let mut tokens = Vec::new();
+        for _ in 0..4 {
+            tokens.push(manager.get_valid_token("gemini").await.unwrap());
+        }
+        assert_eq!(tokens, vec!["tok-a", "tok-b", "tok-a", "tok-b"]);
+
+        let status = manager.get_status("gemini").unwrap();
+        assert_eq!(status.accounts.len(), 2);
+        assert!(status.accounts.iter().all(|a| a.requests == 2));
+    }
+
+    #[tokio::test]
+    async fn test_get_valid_token_least_recently_used() {
+        let manager = pooled_manager(AccountSelection::LeastRecentlyUsed);
+        assert_eq!(manager.get_valid_token("gemini").await.unwrap(), "tok-a");
+        assert_eq!(manager.get_valid_token("gemini").await.unwrap(), "tok-b");
+        assert_eq!(manager.get_valid_token("gemini").await.unwrap(), "tok-a");
+    }
+
+    #[tokio::test]
+    async fn test_get_valid_token_skips_unrefreshable_account() {
+        let manager = pooled_manager(AccountSelection::RoundRobin);
+        let expired = TokenInfo::new("stale".into(), None, Some(-60), "gemini");
+        manager
+            .save_account_token("gemini", DEFAULT_ACCOUNT, &expired)
+            .unwrap();
+
+        assert_eq!(manager.get_valid_token("gemini").await.unwrap(), "tok-b");
+        assert_eq!(manager.get_valid_token("gemini").await.unwrap(), "tok-b");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_expiring_tokens_skips_unrefreshable() {
+        // Gemini is not configured: nothing is attempted even though the
+        // token is due.
+        let manager = pooled_manager(AccountSelection::RoundRobin);
+        let due = TokenInfo::new("old".into(), Some("rt".into()), Some(60), "gemini");
+        manager
+            .save_account_token("gemini", DEFAULT_ACCOUNT, &due)
+            .unwrap();
+        assert_eq!(manager.refresh_expiring_tokens().await, 0);
+        assert_eq!(
+            manager.get_token("gemini").unwrap().unwrap().access_token,
+            "old"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_project_in_token() {
+        let manager = pooled_manager(AccountSelection::RoundRobin);
+        let token = TokenInfo::new("tok-a".into(), Some("refresh".into()), Some(3600), "gemini");
+        manager
+            .save_account_token("gemini", DEFAULT_ACCOUNT, &token)
+            .unwrap();
+        assert!(manager.cached_project("gemini").await.is_none());
+
+        let project = ProjectInfo::new(
+            "proj-123".into(),
+            Some("managed-456".into()),
+            SubscriptionTier::Pro,
+        );
+        manager.store_project("gemini", &project).await;
+
+        let stored = manager.storage().load("gemini").unwrap().unwrap();
+        assert_eq!(stored.base_refresh_token(), "refresh");
+        assert_eq!(stored.project_id().as_deref(), Some("proj-123"));
+        assert_eq!(stored.subscription_tier.as_deref(), Some("pro"));
+        assert_eq!(manager.cached_project("gemini").await, Some(project));
+
+        let status = manager.get_status("gemini").unwrap();
+        assert_eq!(status.project_id.as_deref(), Some("proj-123"));
+        assert_eq!(status.subscription_tier.as_deref(), Some("pro"));
+    }
+
+    #[test]
+    fn test_status_reports_configured_project() {
+        let mut config = test_config();
+        config.providers.gemini = Some(
+            toml::from_str(
+                r#"
+                client_id = "id"
+                client_secret = "secret"
+                project_id = "my-project"
+                "#,
+            )
+            .unwrap(),
+        );
+        let manager = OAuthManager::new(
+            Arc::new(config),
+            test_db(),
+            Arc::new(MemoryTokenStorage::new()),
+        );
+        let status = manager.get_status("gemini").unwrap();
+        assert_eq!(status.project_id.as_deref(), Some("my-project"));
+        assert!(status.subscription_tier.is_none());
+    }
+
+    #[test]
+    fn test_remove_account() {
+        let manager = pooled_manager(AccountSelection::RoundRobin);
+        manager.remove_account("gemini", "work").unwrap();
+        assert_eq!(manager.accounts("gemini").unwrap(), vec!["default"]);
+        assert!(manager.storage().load("gemini@work").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".credentials.json");
+        std::fs::write(
+            &path,
+            r#"{"claudeAiOauth":{"accessToken":"cc-access","refreshToken":"cc-refresh","expiresAt":4102444800000}}"#,
+        )
+        .unwrap();
+        let manager = OAuthManager::new(
+            Arc::new(test_config()),
+            test_db(),
+            Arc::new(MemoryTokenStorage::new()),
+        );
+
+        manager
+            .import_credentials(
+                CredentialSource::ClaudeCode,
+                Some(path.clone()),
+                DEFAULT_ACCOUNT,
+            )
+            .await
+            .unwrap();
+        manager
+            .import_credentials(CredentialSource::ClaudeCode, Some(path), "laptop")
+            .await
+            .unwrap();
+
+        let token = manager.get_token("claude").unwrap().unwrap();
+        assert_eq!(token.access_token, "cc-access");
+        assert_eq!(token.expires_at, Some(4_102_444_800));
+        assert_eq!(
+            manager.accounts("claude").unwrap(),
+            vec!["default", "laptop"]
+        );
+
+        let gemini_path = dir.path().join("oauth_creds.json");
+        std::fs::write(
+            &gemini_path,
+            r#"{"access_token":"ya29.x","refresh_token":"1//y","expiry_date":4102444800000}"#,
+        )
+        .unwrap();
+        manager
+            .import_credentials(
+                CredentialSource::GeminiCli,
+                Some(gemini_path),
+                DEFAULT_ACCOUNT,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.get_token("gemini").unwrap().unwrap().access_token,
+            "ya29.x"
+        );
+        assert!(
+            manager
+                .import_credentials(
+                    CredentialSource::GeminiCli,
+                    Some(dir.path().join("missing.json")),
+                    DEFAULT_ACCOUNT,
+                )
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_credential_source_names() {
+        for source in [CredentialSource::ClaudeCode, CredentialSource::GeminiCli] {
+            assert_eq!(source.to_string().parse::<CredentialSource>(), Ok(source));
+        }
+        assert_eq!(CredentialSource::GeminiCli.provider(), "gemini");
+        assert!("codex".parse::<CredentialSource>().is_err());
+    }
+
+    #[test]
+    fn test_account_names() {
+        assert_eq!(account_key("claude", DEFAULT_ACCOUNT), "claude");
+        assert_eq!(account_key("claude", "team-2"), "claude@team-2");
+        assert!(validate_account_name("team_2").is_ok());
+        assert!(validate_account_name("").is_err());
+        assert!(validate_account_name("../etc").is_err());
+    }
+
     #[test]
     fn test_oauth_error_display() {
         let err = OAuthError::TokenNotFound("claude".to_string());
@@ -9,6 +9,7 @@ use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use rand::Rng;
 use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 
 /// PKCE challenge method constant.
 const PKCE_METHOD: &str = "S256";
@@ -65,7 +66,7 @@ impl Pkce {
     #[must_use]
     pub fn verify(verifier: &str, challenge: &str) -> bool {
         let expected = Self::compute_challenge(verifier);
-        expected == challenge
+        expected.as_bytes().ct_eq(challenge.as_bytes()).into()
     }
 
     /// Compute the S256 challenge from a verifier.
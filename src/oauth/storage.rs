@@ -4,10 +4,16 @@
 //! - [`FileTokenStorage`] - Stores tokens as individual JSON files per provider
 //! - [`MemoryTokenStorage`] - In-memory storage for testing
 //! - [`KeyringTokenStorage`] - System keyring storage (requires `system-keyring` feature)
+//! - [`DatabaseTokenStorage`] - Encrypted rows in the gaud database
 //!
 //! All storage operations are synchronous and take a `provider` parameter to
 //! support multiple LLM providers in a single storage backend.
 
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use rusqlite::OptionalExtension;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
@@ -15,6 +21,7 @@ use tracing::instrument;
 
 use super::OAuthError;
 use super::token::TokenInfo;
+use crate::db::Database;
 
 // =============================================================================
 // TokenStorage trait
@@ -384,6 +391,155 @@ impl TokenStorage for KeyringTokenStorage {
     }
 }
 
+// =============================================================================
+// DatabaseTokenStorage
+// =============================================================================
+
+/// Token storage in the `oauth_tokens` table of the gaud database.
+///
+/// Tokens are encrypted with AES-256-GCM under a key derived from a
+/// configured secret, so instances sharing the database also share provider
+/// tokens without a shared token directory. Each row is stored as base64 of
+/// the random nonce followed by the ciphertext; the provider key is bound as
+/// associated data, so a row copied to another provider fails to decrypt.
+#[derive(Clone)]
+pub struct DatabaseTokenStorage {
+    db: Database,
+    key: [u8; 32],
+}
+
+impl std::fmt::Debug for DatabaseTokenStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DatabaseTokenStorage")
+            .finish_non_exhaustive()
+    }
+}
+
+impl DatabaseTokenStorage {
+    /// Create storage encrypting tokens with a key derived from `secret`.
+    pub fn new(db: Database, secret: &str) -> Self {
+        Self {
+            db,
+            key: Sha256::digest(secret.as_bytes()).into(),
+        }
+    }
+
+    fn cipher(&self) -> LessSafeKey {
+        let key = UnboundKey::new(&AES_256_GCM, &self.key).expect("AES-256 key is 32 bytes");
+        LessSafeKey::new(key)
+    }
+
+    fn encrypt(&self, provider: &str, plaintext: &[u8]) -> Result<String, OAuthError> {
+        let nonce_bytes: [u8; NONCE_LEN] = rand::random();
+        let mut sealed = plaintext.to_vec();
+        self.cipher()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce_bytes),
+                Aad::from(provider.as_bytes()),
+                &mut sealed,
+            )
+            .map_err(|_| OAuthError::Storage("Failed to encrypt token".to_string()))?;
+
+        let mut out = nonce_bytes.to_vec();
+        out.extend_from_slice(&sealed);
+        Ok(STANDARD.encode(out))
+    }
+
+    fn decrypt(&self, provider: &str, encoded: &str) -> Result<Vec<u8>, OAuthError> {
+        let mut data = STANDARD
+            .decode(encoded)
+            .map_err(|e| OAuthError::Storage(format!("Failed to decode stored token: {}", e)))?;
+        if data.len() < NONCE_LEN {
+            return Err(OAuthError::Storage("Stored token is truncated".to_string()));
+        }
+        let mut sealed = data.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&data)
+            .map_err(|_| OAuthError::Storage("Stored token is truncated".to_string()))?;
+        let plaintext = self
+            .cipher()
+            .open_in_place(nonce, Aad::from(provider.as_bytes()), &mut sealed)
+            .map_err(|_| {
+                OAuthError::Storage(format!(
+                    "Failed to decrypt token for {} (wrong token_encryption_key?)",
+                    provider
+                ))
+            })?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+impl TokenStorage for DatabaseTokenStorage {
+    #[instrument(skip(self))]
+    fn load(&self, provider: &str) -> Result<Option<TokenInfo>, OAuthError> {
+        let stored: Option<String> = self
+            .db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT token FROM oauth_tokens WHERE provider = ?1",
+                    [provider],
+                    |row| row.get(0),
+                )
+                .optional()
+            })
+            .map_err(|e| OAuthError::Storage(format!("Failed to load token: {}", e)))?;
+
+        let Some(stored) = stored else {
+            return Ok(None);
+        };
+        let json = self.decrypt(provider, &stored)?;
+        let token: TokenInfo = serde_json::from_slice(&json)
+            .map_err(|e| OAuthError::Storage(format!("Failed to parse stored token: {}", e)))?;
+        Ok(Some(token))
+    }
+
+    #[instrument(skip(self, token))]
+    fn save(&self, provider: &str, token: &TokenInfo) -> Result<(), OAuthError> {
+        let json = serde_json::to_vec(token)
+            .map_err(|e| OAuthError::Storage(format!("Failed to serialize token: {}", e)))?;
+        let encrypted = self.encrypt(provider, &json)?;
+        self.db
+            .with_conn(|conn| {
+                conn.execute(
+                    "INSERT INTO oauth_tokens (provider, token, updated_at)
+                     VALUES (?1, ?2, datetime('now'))
+                     ON CONFLICT(provider) DO UPDATE
+                     SET token = excluded.token, updated_at = excluded.updated_at",
+                    rusqlite::params![provider, encrypted],
+                )
+            })
+            .map_err(|e| OAuthError::Storage(format!("Failed to save token: {}", e)))?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    fn remove(&self, provider: &str) -> Result<(), OAuthError> {
+        self.db
+            .with_conn(|conn| {
+                conn.execute("DELETE FROM oauth_tokens WHERE provider = ?1", [provider])
+            })
+            .map_err(|e| OAuthError::Storage(format!("Failed to remove token: {}", e)))?;
+        Ok(())
+    }
+
+    fn exists(&self, provider: &str) -> Result<bool, OAuthError> {
+        self.db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT 1 FROM oauth_tokens WHERE provider = ?1",
+                    [provider],
+                    |_| Ok(()),
+                )
+                .optional()
+            })
+            .map(|row| row.is_some())
+            .map_err(|e| OAuthError::Storage(format!("Failed to check token: {}", e)))
+    }
+
+    fn name(&self) -> &str {
+        "database"
+    }
+}
+
 // =============================================================================
 // MemoryTokenStorage
 // =============================================================================
@@ -804,6 +960,73 @@ mod tests {
         assert_eq!(storage.name(), "file");
     }
 
+    // =========================================================================
+    // DatabaseTokenStorage tests
+    // =========================================================================
+
+    #[test]
+    fn test_database_save_load_remove() {
+        let db = Database::open_in_memory().unwrap();
+        let storage = DatabaseTokenStorage::new(db.clone(), "s3cret");
+        assert!(storage.load("claude").unwrap().is_none());
+
+        let token = TokenInfo::new(
+            "access".into(),
+            Some("refresh".into()),
+            Some(3600),
+            "claude",
+        );
+        storage.save("claude", &token).unwrap();
+        storage.save("claude", &token).unwrap();
+        assert!(storage.exists("claude").unwrap());
+
+        // A second instance on the same database sees the token.
+        let other = DatabaseTokenStorage::new(db.clone(), "s3cret");
+        let loaded = other.load("claude").unwrap().unwrap();
+        assert_eq!(loaded.access_token, "access");
+        assert_eq!(loaded.refresh_token.as_deref(), Some("refresh"));
+
+        storage.remove("claude").unwrap();
+        assert!(!other.exists("claude").unwrap());
+        assert_eq!(storage.name(), "database");
+    }
+
+    #[test]
+    fn test_database_tokens_are_encrypted() {
+        let db = Database::open_in_memory().unwrap();
+        let storage = DatabaseTokenStorage::new(db.clone(), "s3cret");
+        let token = TokenInfo::new("very-secret-access".into(), None, None, "gemini");
+        storage.save("gemini", &token).unwrap();
+
+        let raw: String = db
+            .with_conn(|conn| {
+                conn.query_row(
+                    "SELECT token FROM oauth_tokens WHERE provider = 'gemini'",
+                    [],
+                    |row| row.get(0),
+                )
+            })
+            .unwrap();
+        assert!(!raw.contains("very-secret-access"));
+        let decoded = STANDARD.decode(&raw).unwrap();
+        assert!(
+            !String::from_utf8_lossy(&decoded).contains("very-secret-access"),
+            "token must not be stored in plain text"
+        );
+
+        // Wrong key, or a row moved to another provider, fails to decrypt.
+        let wrong = DatabaseTokenStorage::new(db.clone(), "other");
+        assert!(matches!(wrong.load("gemini"), Err(OAuthError::Storage(_))));
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO oauth_tokens (provider, token) VALUES ('claude', ?1)",
+                [&raw],
+            )
+        })
+        .unwrap();
+        assert!(storage.load("claude").is_err());
+    }
+
     // =========================================================================
     // KeyringTokenStorage tests
     // =========================================================================
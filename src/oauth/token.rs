@@ -47,6 +47,10 @@ pub struct TokenInfo {
 
     /// Provider identifier (e.g., "claude", "gemini", "copilot").
     pub provider: String,
+
+    /// Subscription tier discovered along with the embedded project IDs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription_tier: Option<String>,
 }
 
 fn default_token_type() -> String {
@@ -64,6 +68,7 @@ impl std::fmt::Debug for TokenInfo {
             .field("expires_at", &self.expires_at)
             .field("token_type", &self.token_type)
             .field("provider", &self.provider)
+            .field("subscription_tier", &self.subscription_tier)
             .finish()
     }
 }
@@ -83,6 +88,7 @@ impl TokenInfo {
             expires_at,
             token_type: "Bearer".to_string(),
             provider: provider.to_string(),
+            subscription_tier: None,
         }
     }
 
@@ -99,6 +105,7 @@ impl TokenInfo {
             expires_at,
             token_type: "Bearer".to_string(),
             provider: provider.to_string(),
+            subscription_tier: None,
         }
     }
 
@@ -0,0 +1,209 @@
+//! Trace sampling and log redaction (`[observability]`).
+//!
+//! Request spans and debug logs are where secrets and prompts leak: an
+//! `Authorization` header in a span, a Gemini `?key=` in a logged URL, a
+//! provider request body with the whole conversation in it. [`init`] reads
+//! the settings once at startup; everything that logs headers, URLs or
+//! bodies goes through this module so the settings apply everywhere.
+//! Masking itself is [`providers::redact`](crate::providers::redact)'s.
+
+use std::sync::OnceLock;
+
+use axum::http::{HeaderMap, Request, Uri};
+use serde_json::Value;
+use tracing::Span;
+
+use crate::config::Config;
+use crate::providers::redact::{self, REDACTED};
+
+/// The `[observability]` settings in effect.
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    trace_sample_ratio: f64,
+    log_headers: bool,
+    log_prompts: bool,
+    redact: bool,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Apply `config`'s observability settings. Only the first call counts.
+pub fn init(config: &Config) {
+    let observability = &config.observability;
+    let _ = SETTINGS.set(Settings {
+        trace_sample_ratio: observability.trace_sample_ratio,
+        log_headers: observability.log_headers,
+        log_prompts: observability.log_prompts || config.logging.log_content,
+        redact: observability.redact,
+    });
+}
+
+fn settings() -> Settings {
+    SETTINGS.get().copied().unwrap_or(Settings {
+        trace_sample_ratio: 1.0,
+        log_headers: false,
+        log_prompts: false,
+        redact: true,
+    })
+}
+
+/// Whether prompts and provider request bodies may be logged.
+pub fn log_prompts() -> bool {
+    settings().log_prompts
+}
+
+/// `text` ready for a log: credentials masked unless redaction is off.
+pub fn scrub(text: &str) -> String {
+    if settings().redact {
+        redact::redact_text(text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// `value` serialized for a log, with secret keys and credentials masked
+/// unless redaction is off.
+pub fn scrub_json(value: &Value) -> String {
+    if settings().redact {
+        let mut value = value.clone();
+        redact::sanitize_value(&mut value);
+        value.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Whether to trace one more request, given the share of requests traced.
+pub fn sampled(ratio: f64) -> bool {
+    if ratio >= 1.0 {
+        true
+    } else if ratio <= 0.0 {
+        false
+    } else {
+        rand::random::<f64>() < ratio
+    }
+}
+
+/// The span of an HTTP request, for `TraceLayer::make_span_with`. Requests
+/// not sampled get a disabled span; headers are recorded only when
+/// `log_headers` is set.
+pub fn make_span<B>(request: &Request<B>) -> Span {
+    let settings = settings();
+    if !sampled(settings.trace_sample_ratio) {
+        return Span::none();
+    }
+    let uri = if settings.redact {
+        redact_uri(request.uri())
+    } else {
+        request.uri().to_string()
+    };
+    if settings.log_headers {
+        let headers = header_pairs(request.headers(), settings.redact);
+        tracing::debug_span!(
+            "request",
+            method = %request.method(),
+            uri = %uri,
+            version = ?request.version(),
+            headers = ?headers,
+        )
+    } else {
+        tracing::debug_span!(
+            "request",
+            method = %request.method(),
+            uri = %uri,
+            version = ?request.version(),
+        )
+    }
+}
+
+/// `headers` as name/value pairs, with secret values masked.
+pub fn redact_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    header_pairs(headers, true)
+}
+
+fn header_pairs(headers: &HeaderMap, redact: bool) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            let value = if !redact {
+                value.into_owned()
+            } else if redact::is_secret_key(name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                redact::redact_text(&value)
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+/// `uri` with secret query parameters such as Gemini's `key` masked.
+pub fn redact_uri(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return redact::redact_text(&uri.to_string());
+    };
+    let params: Vec<String> = query
+        .split('&')
+        .map(|param| match param.split_once('=') {
+            Some((name, _)) if redact::is_secret_key(name) => format!("{name}={REDACTED}"),
+            _ => redact::redact_text(param),
+        })
+        .collect();
+    format!("{}?{}", redact::redact_text(uri.path()), params.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_redact_uri() {
+        let uri: Uri = "/v1beta/models/gemini-pro:generateContent?alt=sse&key=AIzaSyXYZ"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            redact_uri(&uri),
+            "/v1beta/models/gemini-pro:generateContent?alt=sse&key=[redacted]"
+        );
+        let uri: Uri = "/v1/models?monkey=banana".parse().unwrap();
+        assert_eq!(redact_uri(&uri), "/v1/models?monkey=banana");
+    }
+
+    #[test]
+    fn test_redact_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Bearer sk-secret"),
+        );
+        headers.insert("x-goog-api-key", HeaderValue::from_static("AIzaSecret"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        let pairs = redact_headers(&headers);
+        assert!(pairs.contains(&("authorization".into(), REDACTED.into())));
+        assert!(pairs.contains(&("x-goog-api-key".into(), REDACTED.into())));
+        assert!(pairs.contains(&("content-type".into(), "application/json".into())));
+    }
+
+    #[test]
+    fn test_scrub_json() {
+        let body = serde_json::json!({"apiKey": "k1", "messages": ["Bearer abc"]});
+        assert_eq!(
+            scrub_json(&body),
+            r#"{"apiKey":"[redacted]","messages":["Bearer [redacted]"]}"#
+        );
+    }
+
+    #[test]
+    fn test_sampled_bounds() {
+        assert!(sampled(1.0));
+        assert!(!sampled(0.0));
+    }
+
+    #[test]
+    fn test_prompts_not_logged_by_default() {
+        assert!(!log_prompts());
+        assert!(!crate::config::ObservabilityConfig::default().log_prompts);
+    }
+}
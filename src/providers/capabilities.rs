@@ -0,0 +1,209 @@
+//! Model capabilities: what a request needs and whether a model has it.
+//!
+//! Limits and features per model come from the pricing database
+//! ([`ModelCapabilities`]). Requests needing a feature the model is known to
+//! lack (images sent to a text-only model, tools to one without function
+//! calling) are refused with a clear error before reaching a provider.
+//! Models without an entry are assumed capable, since nothing is known
+//! about them.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::providers::pricing::ModelCapabilities;
+use crate::providers::types::{ChatRequest, ContentPart, MessageContent};
+
+/// A feature that can be required of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Vision,
+    Tools,
+    Thinking,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 3] = [Self::Vision, Self::Tools, Self::Thinking];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Vision => "vision",
+            Self::Tools => "tools",
+            Self::Thinking => "thinking",
+        }
+    }
+
+    /// Whether a model with `capabilities` has this feature.
+    pub fn supported_by(self, capabilities: &ModelCapabilities) -> bool {
+        match self {
+            Self::Vision => capabilities.vision,
+            Self::Tools => capabilities.tools,
+            Self::Thinking => capabilities.thinking,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Vision => "image input",
+            Self::Tools => "tool calling",
+            Self::Thinking => "extended thinking",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|c| c.as_str() == s)
+            .ok_or_else(|| {
+                format!("Unknown capability '{s}'; expected one of: vision, tools, thinking")
+            })
+    }
+}
+
+/// Features `request` depends on.
+pub fn required(request: &ChatRequest) -> Vec<Capability> {
+    let mut needed = Vec::new();
+    let has_image = request.messages.iter().any(|m| {
+        matches!(&m.content, Some(MessageContent::Parts(parts))
+            if parts.iter().any(|p| matches!(p, ContentPart::ImageUrl { .. })))
+    });
+    if has_image {
+        needed.push(Capability::Vision);
+    }
+    if request.tools.as_ref().is_some_and(|t| !t.is_empty()) {
+        needed.push(Capability::Tools);
+    }
+    needed
+}
+
+/// Refuse `request` if its model is known to lack a feature it needs.
+pub fn check(
+    request: &ChatRequest,
+    capabilities: Option<&ModelCapabilities>,
+) -> Result<(), String> {
+    let Some(capabilities) = capabilities else {
+        return Ok(());
+    };
+    match required(request)
+        .into_iter()
+        .find(|c| !c.supported_by(capabilities))
+    {
+        Some(missing) => Err(format!(
+            "Model '{}' does not support {}; use /v1/models?capability={missing} to find one that does",
+            request.model,
+            missing.describe(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether a model with `capabilities` has every feature in `wanted` and at
+/// least `min_context` input tokens. Models without an entry never match.
+pub fn matches(
+    capabilities: Option<&ModelCapabilities>,
+    wanted: &[Capability],
+    min_context: Option<u32>,
+) -> bool {
+    let Some(capabilities) = capabilities else {
+        return wanted.is_empty() && min_context.is_none();
+    };
+    wanted.iter().all(|c| c.supported_by(capabilities))
+        && min_context.is_none_or(|min| capabilities.context_window.is_some_and(|w| w >= min))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::pricing::PricingDatabase;
+    use crate::providers::types::{ChatMessage, ImageUrl, MessageRole, Tool};
+
+    fn request(model: &str, image: bool, tools: bool) -> ChatRequest {
+        let content = if image {
+            MessageContent::Parts(vec![ContentPart::ImageUrl {
+                image_url: ImageUrl {
+                    url: "data:image/png;base64,iVBOR".into(),
+                    detail: None,
+                },
+            }])
+        } else {
+            MessageContent::Text("Hi".into())
+        };
+        let tools = tools.then(|| {
+            vec![
+                serde_json::from_value::<Tool>(serde_json::json!({
+                    "type": "function",
+                    "function": {"name": "f", "parameters": {}}
+                }))
+                .unwrap(),
+            ]
+        });
+        ChatRequest {
+            model: model.into(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: Some(content),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stream: false,
+            top_p: None,
+            stop: None,
+            tools,
+            tool_choice: None,
+            stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
+        }
+    }
+
+    #[test]
+    fn test_vision_request_to_text_model_is_refused() {
+        let db = PricingDatabase::new();
+        let req = request("o3-mini", true, false);
+        let err = check(&req, db.get_capabilities("o3-mini")).unwrap_err();
+        assert!(err.contains("does not support image input"));
+
+        let req = request("gpt-4o", true, true);
+        assert!(check(&req, db.get_capabilities("gpt-4o")).is_ok());
+        // Unknown models are let through.
+        let req = request("mystery", true, true);
+        assert!(check(&req, None).is_ok());
+    }
+
+    #[test]
+    fn test_required_capabilities() {
+        assert!(required(&request("m", false, false)).is_empty());
+        assert_eq!(
+            required(&request("m", true, true)),
+            vec![Capability::Vision, Capability::Tools]
+        );
+    }
+
+    #[test]
+    fn test_matches_filters() {
+        let db = PricingDatabase::new();
+        let sonnet = db.get_capabilities("claude-sonnet-4-20250514");
+        assert!(matches(sonnet, &[Capability::Vision], Some(200_000)));
+        assert!(!matches(sonnet, &[], Some(300_000)));
+        let o3 = db.get_capabilities("o3-mini");
+        assert!(!matches(o3, &[Capability::Vision, Capability::Tools], None));
+        assert!(matches(None, &[], None));
+        assert!(!matches(None, &[Capability::Tools], None));
+
+        assert_eq!("thinking".parse::<Capability>(), Ok(Capability::Thinking));
+        assert!("audio".parse::<Capability>().is_err());
+    }
+}
@@ -10,8 +10,12 @@ use futures::Stream;
 use futures::stream::{self, StreamExt};
 use reqwest::Client;
 
+use crate::net::HttpClientFactory;
 use crate::providers::pricing::ModelPricing;
-use crate::providers::transform::util::{detect_context_window_error, parse_rate_limit_headers};
+use crate::providers::ratelimit::{UpstreamRateLimit, UpstreamRateLimitState};
+use crate::providers::transform::util::{
+    detect_context_window_error, detect_quota_exhausted, parse_rate_limit_headers,
+};
 use crate::providers::transform::{ClaudeTransformer, SseEvent, SseParser};
 use crate::providers::transformer::{ProviderResponseMeta, ProviderTransformer};
 use crate::providers::types::*;
@@ -29,6 +33,8 @@ const SUPPORTED_MODELS: &[&str] = &[
 pub struct ClaudeProvider {
     http: Client,
     tokens: std::sync::Arc<dyn TokenService>,
+    rate_limits: UpstreamRateLimitState,
+    api_base: String,
 }
 
 impl ClaudeProvider {
@@ -37,9 +43,23 @@ impl ClaudeProvider {
         Self {
             http: Client::new(),
             tokens,
+            rate_limits: UpstreamRateLimitState::default(),
+            api_base: API_BASE.to_string(),
         }
     }
 
+    /// Use the shared client settings (proxy, pool and HTTP/2 tuning).
+    pub fn with_http(mut self, http: &HttpClientFactory) -> Self {
+        self.http = http.client("claude");
+        self
+    }
+
+    /// Override the API base URL (used by tests).
+    pub fn with_api_base(mut self, url: impl Into<String>) -> Self {
+        self.api_base = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     /// Retrieve an access token or return an error.
     async fn get_token(&self) -> Result<String, ProviderError> {
         self.tokens.get_token("claude").await
@@ -76,7 +96,7 @@ impl LlmProvider for ClaudeProvider {
 
             let resp = self
                 .http
-                .post(format!("{API_BASE}/messages"))
+                .post(format!("{}/messages", self.api_base))
                 .header("x-api-key", &token)
                 .header("anthropic-version", ANTHROPIC_VERSION)
                 .header("content-type", "application/json")
@@ -90,6 +110,12 @@ impl LlmProvider for ClaudeProvider {
                 .iter()
                 .map(|(k, v)| (k.as_str().to_string(), v.to_str().unwrap_or("").to_string()))
                 .collect();
+            let (retry_after, rate_limit_headers) =
+                parse_rate_limit_headers(&resp_headers, "claude");
+            self.rate_limits.record(
+                &rate_limit_headers,
+                retry_after.filter(|_| status.as_u16() == 429),
+            );
 
             if !status.is_success() {
                 let text = resp.text().await.unwrap_or_default();
@@ -99,9 +125,11 @@ impl LlmProvider for ClaudeProvider {
                 if let Some(ctx_err) = detect_context_window_error(code, &text, "claude") {
                     return Err(ctx_err);
                 }
+                if let Some(quota_err) = detect_quota_exhausted(code, &text, "claude") {
+                    return Err(quota_err);
+                }
 
                 if code == 429 {
-                    let (retry_after, _) = parse_rate_limit_headers(&resp_headers, "claude");
                     return Err(ProviderError::RateLimited {
                         retry_after_secs: retry_after.map(|d| d.as_secs()).unwrap_or(60),
                         retry_after,
@@ -113,7 +141,6 @@ impl LlmProvider for ClaudeProvider {
                 });
             }
 
-            let (_, rate_limit_headers) = parse_rate_limit_headers(&resp_headers, "claude");
             let response_json: serde_json::Value = resp.json().await?;
             let meta = ProviderResponseMeta {
                 provider: "claude".into(),
@@ -149,7 +176,7 @@ impl LlmProvider for ClaudeProvider {
 
             let resp = self
                 .http
-                .post(format!("{API_BASE}/messages"))
+                .post(format!("{}/messages", self.api_base))
                 .header("x-api-key", &token)
                 .header("anthropic-version", ANTHROPIC_VERSION)
                 .header("content-type", "application/json")
@@ -163,6 +190,12 @@ impl LlmProvider for ClaudeProvider {
                 .iter()
                 .map(|(k, v)| (k.as_str().to_string(), v.to_str().unwrap_or("").to_string()))
                 .collect();
+            let (retry_after, rate_limit_headers) =
+                parse_rate_limit_headers(&resp_headers, "claude");
+            self.rate_limits.record(
+                &rate_limit_headers,
+                retry_after.filter(|_| status.as_u16() == 429),
+            );
 
             if !status.is_success() {
                 let text = resp.text().await.unwrap_or_default();
@@ -171,9 +204,11 @@ impl LlmProvider for ClaudeProvider {
                 if let Some(ctx_err) = detect_context_window_error(code, &text, "claude") {
                     return Err(ctx_err);
                 }
+                if let Some(quota_err) = detect_quota_exhausted(code, &text, "claude") {
+                    return Err(quota_err);
+                }
 
                 if code == 429 {
-                    let (retry_after, _) = parse_rate_limit_headers(&resp_headers, "claude");
                     return Err(ProviderError::RateLimited {
                         retry_after_secs: retry_after.map(|d| d.as_secs()).unwrap_or(60),
                         retry_after,
@@ -258,12 +293,27 @@ impl LlmProvider for ClaudeProvider {
         Box::pin(async move { self.get_token().await.is_ok() })
     }
 
+    fn diagnose(
+        &self,
+    ) -> Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + '_>> {
+        Box::pin(async move {
+            self.get_token()
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
     fn pricing(&self) -> Vec<ModelPricing> {
         crate::providers::cost::CostCalculator::all()
             .into_iter()
             .filter(|p| p.provider == "claude")
             .collect()
     }
+
+    fn upstream_rate_limit(&self) -> Option<UpstreamRateLimit> {
+        self.rate_limits.get()
+    }
 }
 
 #[cfg(test)]
@@ -351,6 +401,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let result = p.chat(&req).await;
         assert!(matches!(result, Err(ProviderError::NoToken { .. })));
@@ -2,16 +2,33 @@
 //!
 //! Routes requests to the GitHub Copilot Chat Completions API, which natively
 //! accepts OpenAI-format payloads. Minimal conversion is needed.
+//!
+//! The model list is fetched from the Copilot `/models` endpoint, so newly
+//! released models become available without a gaud update. Until the first
+//! successful discovery a built-in list is used.
+//!
+//! Copilot bills some models in "premium requests": each request consumes
+//! the model's multiplier from the account's monthly allowance. Multipliers
+//! come from the `billing` section of `/models`, with a built-in table as
+//! fallback.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use futures::Stream;
 use futures::stream::{self, StreamExt};
 use reqwest::Client;
+use serde::Deserialize;
+use tracing::{debug, warn};
 
+use crate::net::HttpClientFactory;
 use crate::providers::pricing::ModelPricing;
-use crate::providers::transform::util::{detect_context_window_error, parse_rate_limit_headers};
+use crate::providers::ratelimit::{UpstreamRateLimit, UpstreamRateLimitState};
+use crate::providers::transform::util::{
+    detect_context_window_error, detect_quota_exhausted, parse_rate_limit_headers,
+};
 use crate::providers::transform::{CopilotTransformer, SseEvent, SseParser};
 use crate::providers::transformer::{ProviderResponseMeta, ProviderTransformer};
 use crate::providers::types::*;
@@ -21,10 +38,90 @@ use crate::providers::{LlmProvider, ProviderError, TokenService};
 // Constants
 // ---------------------------------------------------------------------------
 
-const API_ENDPOINT: &str = "https://api.githubcopilot.com/chat/completions";
+const API_BASE: &str = "https://api.githubcopilot.com";
 
+/// Models assumed until discovery succeeds.
 const SUPPORTED_MODELS: &[&str] = &["gpt-4o", "gpt-4-turbo", "o1", "o3-mini"];
 
+/// Premium request multipliers assumed until discovery reports them. Model
+/// names match exactly or as a prefix followed by `-`; the longest match
+/// wins.
+const PREMIUM_MULTIPLIERS: &[(&str, f64)] = &[
+    ("gpt-4o", 0.0),
+    ("gpt-4.1", 0.0),
+    ("gpt-4-turbo", 0.0),
+    ("gpt-5-mini", 0.0),
+    ("o3-mini", 0.33),
+    ("o4-mini", 0.33),
+    ("gemini-2.0-flash", 0.25),
+    ("o1", 10.0),
+    ("o1-mini", 1.0),
+    ("claude-opus-4", 10.0),
+];
+
+/// Multiplier of models missing from the table.
+const DEFAULT_PREMIUM_MULTIPLIER: f64 = 1.0;
+
+// ---------------------------------------------------------------------------
+// Model discovery types (from GET /models)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+struct CopilotModelsResponse {
+    #[serde(default)]
+    data: Vec<CopilotModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CopilotModel {
+    id: String,
+    #[serde(default)]
+    capabilities: Option<CopilotModelCapabilities>,
+    #[serde(default)]
+    policy: Option<CopilotModelPolicy>,
+    #[serde(default)]
+    billing: Option<CopilotModelBilling>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CopilotModelCapabilities {
+    /// `chat`, `embeddings`, ...
+    #[serde(default, rename = "type")]
+    kind: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CopilotModelPolicy {
+    /// `enabled`, `disabled` or `unconfigured`.
+    #[serde(default)]
+    state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CopilotModelBilling {
+    /// Premium requests consumed per request.
+    #[serde(default)]
+    multiplier: Option<f64>,
+}
+
+impl CopilotModel {
+    /// Chat models the account may use. Models without capabilities or
+    /// policy information are assumed to be usable.
+    fn is_usable_chat_model(&self) -> bool {
+        let chat = self
+            .capabilities
+            .as_ref()
+            .and_then(|c| c.kind.as_deref())
+            .is_none_or(|kind| kind == "chat");
+        let disabled = self
+            .policy
+            .as_ref()
+            .and_then(|p| p.state.as_deref())
+            .is_some_and(|state| state == "disabled");
+        chat && !disabled
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Copilot Provider
 // ---------------------------------------------------------------------------
@@ -32,22 +129,127 @@ const SUPPORTED_MODELS: &[&str] = &["gpt-4o", "gpt-4-turbo", "o1", "o3-mini"];
 /// LLM provider that communicates with the GitHub Copilot Chat API.
 pub struct CopilotProvider {
     http: Client,
-    tokens: std::sync::Arc<dyn TokenService>,
+    tokens: Arc<dyn TokenService>,
+    rate_limits: UpstreamRateLimitState,
+    api_base: String,
+    /// Models reported by the last successful discovery.
+    models: RwLock<Vec<String>>,
+    /// Premium request multipliers reported by the last discovery.
+    multipliers: RwLock<HashMap<String, f64>>,
 }
 
 impl CopilotProvider {
     /// Create a new Copilot provider backed by the given token service.
-    pub fn new(tokens: std::sync::Arc<dyn TokenService>) -> Self {
+    pub fn new(tokens: Arc<dyn TokenService>) -> Self {
         Self {
             http: Client::new(),
             tokens,
+            rate_limits: UpstreamRateLimitState::default(),
+            api_base: API_BASE.to_string(),
+            models: RwLock::new(SUPPORTED_MODELS.iter().map(|s| s.to_string()).collect()),
+            multipliers: RwLock::new(HashMap::new()),
         }
     }
 
+    /// Use the shared client settings (proxy, pool and HTTP/2 tuning).
+    pub fn with_http(mut self, http: &HttpClientFactory) -> Self {
+        self.http = http.client("copilot");
+        self
+    }
+
+    /// Override the API base URL (used by tests).
+    pub fn with_api_base(mut self, url: impl Into<String>) -> Self {
+        self.api_base = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     /// Retrieve an access token or return an error.
     async fn get_token(&self) -> Result<String, ProviderError> {
         self.tokens.get_token("copilot").await
     }
+
+    /// Fetch the chat models of the account from `GET /models` and replace
+    /// the model list. Returns the number of models found.
+    pub async fn discover_models(&self) -> Result<usize, ProviderError> {
+        let token = self.get_token().await?;
+        let resp = self
+            .http
+            .get(format!("{}/models", self.api_base))
+            .bearer_auth(&token)
+            .header("editor-version", "gaud/0.1.0")
+            .header("copilot-integration-id", "gaud")
+            .send()
+            .await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::Api {
+                status: status.as_u16(),
+                message: format!("Model discovery failed: {body}"),
+            });
+        }
+
+        let body: CopilotModelsResponse = resp.json().await?;
+        let mut models: Vec<String> = Vec::new();
+        let mut multipliers = HashMap::new();
+        for model in body.data.iter().filter(|m| m.is_usable_chat_model()) {
+            if !models.contains(&model.id) {
+                models.push(model.id.clone());
+            }
+            if let Some(multiplier) = model.billing.as_ref().and_then(|b| b.multiplier) {
+                multipliers.insert(model.id.clone(), multiplier.max(0.0));
+            }
+        }
+        if models.is_empty() {
+            return Err(ProviderError::Other(
+                "Copilot model discovery returned no chat models".into(),
+            ));
+        }
+
+        let count = models.len();
+        *self.models.write().unwrap_or_else(|e| e.into_inner()) = models;
+        *self.multipliers.write().unwrap_or_else(|e| e.into_inner()) = multipliers;
+        debug!(count, "Copilot models discovered");
+        Ok(count)
+    }
+}
+
+/// Built-in premium request multiplier of `model`.
+fn default_premium_multiplier(model: &str) -> f64 {
+    PREMIUM_MULTIPLIERS
+        .iter()
+        .filter(|(name, _)| {
+            model
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        })
+        .max_by_key(|(name, _)| name.len())
+        .map_or(DEFAULT_PREMIUM_MULTIPLIER, |(_, multiplier)| *multiplier)
+}
+
+/// Discover the Copilot models now and then every `interval`, or only once
+/// without one. Failures keep the previous list.
+pub fn spawn_model_refresh(
+    provider: Arc<CopilotProvider>,
+    interval: Option<Duration>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(interval) = interval else {
+            if let Err(e) = provider.discover_models().await {
+                warn!(error = %e, "Copilot model discovery failed, using built-in list");
+            }
+            return;
+        };
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = provider.discover_models().await {
+                warn!(error = %e, "Copilot model discovery failed, keeping current list");
+            }
+        }
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -64,11 +266,18 @@ impl LlmProvider for CopilotProvider {
     }
 
     fn models(&self) -> Vec<String> {
-        SUPPORTED_MODELS.iter().map(|s| s.to_string()).collect()
+        self.models
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
     }
 
     fn supports_model(&self, model: &str) -> bool {
-        SUPPORTED_MODELS.iter().any(|m| *m == model)
+        self.models
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|m| m == model)
     }
 
     fn chat(
@@ -82,16 +291,17 @@ impl LlmProvider for CopilotProvider {
             let transformer = CopilotTransformer::new();
             let body = transformer.transform_request(&request)?;
 
-            let resp = self
+            let mut req = self
                 .http
-                .post(API_ENDPOINT)
+                .post(format!("{}/chat/completions", self.api_base))
                 .bearer_auth(&token)
                 .header("content-type", "application/json")
                 .header("editor-version", "gaud/0.1.0")
-                .header("copilot-integration-id", "gaud")
-                .json(&body)
-                .send()
-                .await?;
+                .header("copilot-integration-id", "gaud");
+            if CopilotTransformer::has_vision_content(&request) {
+                req = req.header("copilot-vision-request", "true");
+            }
+            let resp = req.json(&body).send().await?;
 
             let status = resp.status();
             let resp_headers: Vec<(String, String)> = resp
@@ -99,6 +309,12 @@ impl LlmProvider for CopilotProvider {
                 .iter()
                 .map(|(k, v)| (k.as_str().to_string(), v.to_str().unwrap_or("").to_string()))
                 .collect();
+            let (retry_after, rate_limit_headers) =
+                parse_rate_limit_headers(&resp_headers, "copilot");
+            self.rate_limits.record(
+                &rate_limit_headers,
+                retry_after.filter(|_| status.as_u16() == 429),
+            );
 
             if !status.is_success() {
                 let text = resp.text().await.unwrap_or_default();
@@ -107,9 +323,11 @@ impl LlmProvider for CopilotProvider {
                 if let Some(ctx_err) = detect_context_window_error(code, &text, "copilot") {
                     return Err(ctx_err);
                 }
+                if let Some(quota_err) = detect_quota_exhausted(code, &text, "copilot") {
+                    return Err(quota_err);
+                }
 
                 if code == 429 {
-                    let (retry_after, _) = parse_rate_limit_headers(&resp_headers, "copilot");
                     return Err(ProviderError::RateLimited {
                         retry_after_secs: retry_after.map(|d| d.as_secs()).unwrap_or(60),
                         retry_after,
@@ -121,7 +339,6 @@ impl LlmProvider for CopilotProvider {
                 });
             }
 
-            let (_, rate_limit_headers) = parse_rate_limit_headers(&resp_headers, "copilot");
             let response_json: serde_json::Value = resp.json().await?;
             let meta = ProviderResponseMeta {
                 provider: "copilot".into(),
@@ -155,16 +372,17 @@ impl LlmProvider for CopilotProvider {
             let mut body = transformer.transform_request(&request)?;
             body["stream"] = serde_json::json!(true);
 
-            let resp = self
+            let mut req = self
                 .http
-                .post(API_ENDPOINT)
+                .post(format!("{}/chat/completions", self.api_base))
                 .bearer_auth(&token)
                 .header("content-type", "application/json")
                 .header("editor-version", "gaud/0.1.0")
-                .header("copilot-integration-id", "gaud")
-                .json(&body)
-                .send()
-                .await?;
+                .header("copilot-integration-id", "gaud");
+            if CopilotTransformer::has_vision_content(&request) {
+                req = req.header("copilot-vision-request", "true");
+            }
+            let resp = req.json(&body).send().await?;
 
             let status = resp.status();
             let resp_headers: Vec<(String, String)> = resp
@@ -172,6 +390,12 @@ impl LlmProvider for CopilotProvider {
                 .iter()
                 .map(|(k, v)| (k.as_str().to_string(), v.to_str().unwrap_or("").to_string()))
                 .collect();
+            let (retry_after, rate_limit_headers) =
+                parse_rate_limit_headers(&resp_headers, "copilot");
+            self.rate_limits.record(
+                &rate_limit_headers,
+                retry_after.filter(|_| status.as_u16() == 429),
+            );
 
             if !status.is_success() {
                 let text = resp.text().await.unwrap_or_default();
@@ -180,9 +404,11 @@ impl LlmProvider for CopilotProvider {
                 if let Some(ctx_err) = detect_context_window_error(code, &text, "copilot") {
                     return Err(ctx_err);
                 }
+                if let Some(quota_err) = detect_quota_exhausted(code, &text, "copilot") {
+                    return Err(quota_err);
+                }
 
                 if code == 429 {
-                    let (retry_after, _) = parse_rate_limit_headers(&resp_headers, "copilot");
                     return Err(ProviderError::RateLimited {
                         retry_after_secs: retry_after.map(|d| d.as_secs()).unwrap_or(60),
                         retry_after,
@@ -265,12 +491,36 @@ impl LlmProvider for CopilotProvider {
         Box::pin(async move { self.get_token().await.is_ok() })
     }
 
+    fn diagnose(
+        &self,
+    ) -> Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + '_>> {
+        Box::pin(async move {
+            self.get_token()
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
     fn pricing(&self) -> Vec<ModelPricing> {
         crate::providers::cost::CostCalculator::all()
             .into_iter()
             .filter(|p| p.provider == "copilot")
             .collect()
     }
+
+    fn upstream_rate_limit(&self) -> Option<UpstreamRateLimit> {
+        self.rate_limits.get()
+    }
+
+    fn premium_requests(&self, model: &str) -> f64 {
+        self.multipliers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(model)
+            .copied()
+            .unwrap_or_else(|| default_premium_multiplier(model))
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -280,7 +530,8 @@ impl LlmProvider for CopilotProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Arc;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     struct MockTokenService {
         token: Option<String>,
@@ -364,11 +615,74 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let result = p.chat(&req).await;
         assert!(matches!(result, Err(ProviderError::NoToken { .. })));
     }
 
+    #[tokio::test]
+    async fn test_discover_models() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("authorization", "Bearer test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [
+                    {"id": "gpt-5", "capabilities": {"type": "chat"}, "policy": {"state": "enabled"}, "billing": {"is_premium": true, "multiplier": 1}},
+                    {"id": "claude-sonnet-4", "capabilities": {"type": "chat"}, "billing": {"multiplier": 1.5}},
+                    {"id": "gpt-5", "capabilities": {"type": "chat"}},
+                    {"id": "text-embedding-3-small", "capabilities": {"type": "embeddings"}},
+                    {"id": "o1", "capabilities": {"type": "chat"}, "policy": {"state": "disabled"}}
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let p = CopilotProvider::new(Arc::new(MockTokenService::with_token("test")))
+            .with_api_base(server.uri());
+        assert!(p.supports_model("o1"));
+
+        assert_eq!(p.discover_models().await.unwrap(), 2);
+        assert_eq!(p.models(), vec!["gpt-5", "claude-sonnet-4"]);
+        assert!(p.supports_model("gpt-5"));
+        assert!(!p.supports_model("o1"));
+        assert!(!p.supports_model("text-embedding-3-small"));
+        assert_eq!(p.premium_requests("gpt-5"), 1.0);
+        assert_eq!(p.premium_requests("claude-sonnet-4"), 1.5);
+    }
+
+    #[test]
+    fn test_default_premium_multipliers() {
+        let p = CopilotProvider::new(Arc::new(MockTokenService::empty()));
+        assert_eq!(p.premium_requests("gpt-4o"), 0.0);
+        assert_eq!(p.premium_requests("gpt-4o-2024-11-20"), 0.0);
+        assert_eq!(p.premium_requests("o1"), 10.0);
+        assert_eq!(p.premium_requests("o3-mini"), 0.33);
+        assert_eq!(p.premium_requests("claude-sonnet-4"), 1.0);
+        assert_eq!(p.premium_requests("gpt-4o1"), 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_discover_models_failure_keeps_list() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("bad token"))
+            .mount(&server)
+            .await;
+
+        let p = CopilotProvider::new(Arc::new(MockTokenService::with_token("test")))
+            .with_api_base(server.uri());
+        assert!(matches!(
+            p.discover_models().await,
+            Err(ProviderError::Api { status: 401, .. })
+        ));
+        assert!(p.supports_model("gpt-4o"));
+    }
+
     #[test]
     fn test_pricing_returns_copilot_models() {
         let p = CopilotProvider::new(Arc::new(MockTokenService::empty()));
@@ -1,33 +1,236 @@
 //! Cost calculation for LLM API requests.
 //!
 //! Calculates the cost of API requests based on token usage and model pricing.
-//! Supports both token-based and cached token pricing.
-
-use super::pricing::{ModelPricing, PricingDatabase};
+//! Supports both token-based and cached token pricing. Prices can be
+//! reloaded from their configured source and overridden per model at runtime.
+//! Per-user and per-team markups add internal overhead on top of the
+//! provider cost. Prices are in USD; computed costs are converted to the
+//! configured billing currency.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::pricing::{
+    AudioPricing, ExchangeRateSource, ImagePricing, ModelCapabilities, ModelPricing,
+    PricingDatabase, PricingSource,
+};
 use super::types::Usage;
 #[cfg(test)]
 use super::types::UsageTokenDetails;
+use crate::budget::quota::QuotaScope;
+use crate::db::Database;
+use crate::error::AppError;
 use anyhow::{Context, Result};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
+
+// MARK: - Markups
+
+/// Overhead charged on top of the provider cost of a user's or team's
+/// requests. Both parts add up when set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostMarkup {
+    /// Percentage of the provider cost added (`15.0` adds 15%).
+    pub percent: Option<f64>,
+    /// Fixed surcharge in USD per million tokens (input + output).
+    pub per_million_tokens: Option<f64>,
+}
+
+impl CostMarkup {
+    pub fn is_none(&self) -> bool {
+        self.percent.is_none() && self.per_million_tokens.is_none()
+    }
+
+    /// The marked-up cost of a request that cost `cost` and used `tokens`.
+    pub fn apply(&self, cost: f64, tokens: u64) -> f64 {
+        let percent = self.percent.map_or(0.0, |p| cost * p / 100.0);
+        let surcharge = self
+            .per_million_tokens
+            .map_or(0.0, |s| (tokens as f64 / 1_000_000.0) * s);
+        cost + percent + surcharge
+    }
+}
 
 // MARK: - Cost Calculator
 
 /// Calculator for LLM API request costs.
 pub struct CostCalculator {
-    pricing_db: PricingDatabase,
+    pricing_db: RwLock<PricingDatabase>,
+    /// Per-model prices set through the admin API, taking precedence over
+    /// `pricing_db`.
+    overrides: RwLock<HashMap<String, ModelPricing>>,
+    /// Where [`Self::refresh`] reloads prices from.
+    source: Option<PricingSource>,
+    /// Markups by user ID or team name.
+    markups: RwLock<HashMap<(QuotaScope, String), CostMarkup>>,
+    /// ISO 4217 code of the currency costs are returned in.
+    currency: String,
+    /// Units of `currency` per USD.
+    exchange_rate: RwLock<f64>,
+    /// Where [`Self::refresh_exchange_rate`] fetches the rate from.
+    rate_source: Option<ExchangeRateSource>,
 }
 
 impl CostCalculator {
     /// Create a new cost calculator with the default pricing database.
     pub fn new() -> Self {
-        Self {
-            pricing_db: PricingDatabase::new(),
-        }
+        Self::with_pricing_db(PricingDatabase::new())
     }
 
     /// Create a cost calculator with a custom pricing database.
     pub fn with_pricing_db(pricing_db: PricingDatabase) -> Self {
-        Self { pricing_db }
+        Self {
+            pricing_db: RwLock::new(pricing_db),
+            overrides: RwLock::new(HashMap::new()),
+            source: None,
+            markups: RwLock::new(HashMap::new()),
+            currency: "USD".to_string(),
+            exchange_rate: RwLock::new(1.0),
+            rate_source: None,
+        }
+    }
+
+    /// Return costs in `currency`, converted from USD at `exchange_rate`.
+    pub fn with_currency(mut self, currency: &str, exchange_rate: f64) -> Self {
+        self.currency = currency.to_string();
+        *self
+            .exchange_rate
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner()) = exchange_rate;
+        self
+    }
+
+    /// Fetch the exchange rate from `source` on [`Self::refresh_exchange_rate`].
+    pub fn with_exchange_rate_source(mut self, source: ExchangeRateSource) -> Self {
+        self.rate_source = Some(source);
+        self
+    }
+
+    /// Reload prices from `source` on [`Self::refresh`].
+    pub fn with_source(mut self, source: PricingSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Replace the prices with a fresh load of the configured source. Keeps
+    /// the current prices if the source cannot be read. Returns the number of
+    /// token-priced models.
+    pub async fn refresh(&self) -> Result<usize, String> {
+        let Some(ref source) = self.source else {
+            return Err("No pricing file or URL configured".to_string());
+        };
+        let db = source.load().await?;
+        let models = db.all().len();
+        *self.pricing_db.write().unwrap_or_else(|e| e.into_inner()) = db;
+        Ok(models)
+    }
+
+    /// Whether prices can be reloaded from a file or URL.
+    pub fn has_source(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Replace the exchange rate with a fresh fetch. Keeps the current rate
+    /// if the fetch fails.
+    pub async fn refresh_exchange_rate(&self) -> Result<f64, String> {
+        let Some(ref source) = self.rate_source else {
+            return Err("No exchange rate URL configured".to_string());
+        };
+        let rate = source.fetch().await?;
+        *self
+            .exchange_rate
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = rate;
+        Ok(rate)
+    }
+
+    /// Whether the exchange rate is fetched from a URL.
+    pub fn has_exchange_rate_source(&self) -> bool {
+        self.rate_source.is_some()
+    }
+
+    /// ISO 4217 code of the currency costs are returned in.
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    /// Units of the billing currency per USD.
+    pub fn exchange_rate(&self) -> f64 {
+        *self.exchange_rate.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Convert a USD amount to the billing currency.
+    pub fn convert_usd(&self, usd: f64) -> f64 {
+        usd * self.exchange_rate()
+    }
+
+    /// Override the price of a model, replacing any earlier override.
+    pub fn set_override(&self, pricing: ModelPricing) {
+        self.overrides
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(pricing.model.clone(), pricing);
+    }
+
+    /// Remove the override of `model`. Returns whether one existed.
+    pub fn remove_override(&self, model: &str) -> bool {
+        self.overrides
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(model)
+            .is_some()
+    }
+
+    /// Whether the price of `model` is overridden.
+    pub fn is_overridden(&self, model: &str) -> bool {
+        self.overrides
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(model)
+    }
+
+    /// Set the markup of a user or team. An all-`None` markup removes it.
+    pub fn set_markup(&self, scope: QuotaScope, subject: &str, markup: CostMarkup) {
+        let mut markups = self.markups.write().unwrap_or_else(|e| e.into_inner());
+        if markup.is_none() {
+            markups.remove(&(scope, subject.to_string()));
+        } else {
+            markups.insert((scope, subject.to_string()), markup);
+        }
+    }
+
+    /// The markup of a user or team, if one is set.
+    pub fn get_markup(&self, scope: QuotaScope, subject: &str) -> Option<CostMarkup> {
+        self.markups
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(scope, subject.to_string()))
+            .copied()
+    }
+
+    /// Whether any user or team has a markup.
+    pub fn has_markups(&self) -> bool {
+        !self
+            .markups
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+    }
+
+    /// Apply the markup of `user_id`, or failing that of their `team`, to
+    /// the provider cost of a request. Returns `cost` unchanged when neither
+    /// has a markup.
+    pub fn apply_markup(&self, user_id: &str, team: Option<&str>, cost: f64, tokens: u64) -> f64 {
+        let markup = self
+            .get_markup(QuotaScope::User, user_id)
+            .or_else(|| team.and_then(|t| self.get_markup(QuotaScope::Team, t)));
+        match markup {
+            Some(markup) => markup.apply(cost, tokens),
+            None => cost,
+        }
     }
 
     /// Calculate the cost of a request based on usage and model.
@@ -51,11 +254,10 @@ impl CostCalculator {
     /// Calculate the cost of a request, returning an error if pricing is unavailable.
     pub fn try_calculate_cost(&self, model: &str, usage: &Usage) -> Result<f64> {
         let pricing = self
-            .pricing_db
-            .get(model)
+            .get_pricing(model)
             .context(format!("No pricing data for model: {}", model))?;
 
-        let cost = self.calculate_cost_with_pricing(pricing, usage);
+        let cost = self.calculate_cost_with_pricing(&pricing, usage);
 
         debug!(
             model = %model,
@@ -65,7 +267,7 @@ impl CostCalculator {
             "Calculated request cost"
         );
 
-        Ok(cost)
+        Ok(self.convert_usd(cost))
     }
 
     /// Calculate cost using specific pricing information.
@@ -100,19 +302,97 @@ impl CostCalculator {
         input_cost + output_cost
     }
 
-    /// Get pricing information for a model.
-    pub fn get_pricing(&self, model: &str) -> Option<&ModelPricing> {
-        self.pricing_db.get(model)
+    /// Calculate the cost of generating `count` images at `size`.
+    ///
+    /// Returns 0.0 and logs a warning if no image pricing is available.
+    pub fn calculate_image_cost(&self, model: &str, size: Option<&str>, count: u32) -> f64 {
+        match self.db().get_image(model) {
+            Some(pricing) => self.convert_usd(pricing.cost_for_size(size) * f64::from(count)),
+            None => {
+                warn!(model = %model, "No image pricing data, returning 0.0");
+                0.0
+            }
+        }
+    }
+
+    /// Calculate the cost of transcribing `seconds` of audio.
+    ///
+    /// Returns 0.0 and logs a warning if no audio pricing is available.
+    pub fn calculate_transcription_cost(&self, model: &str, seconds: f64) -> f64 {
+        match self.db().get_audio(model) {
+            Some(pricing) => self.convert_usd(seconds / 60.0 * pricing.cost_per_minute),
+            None => {
+                warn!(model = %model, "No audio pricing data, returning 0.0");
+                0.0
+            }
+        }
+    }
+
+    /// Calculate the cost of synthesizing speech from `characters` of input.
+    ///
+    /// Returns 0.0 and logs a warning if no audio pricing is available.
+    pub fn calculate_speech_cost(&self, model: &str, characters: usize) -> f64 {
+        match self.db().get_audio(model) {
+            Some(pricing) => self.convert_usd(
+                (characters as f64 / 1_000_000.0) * pricing.cost_per_million_characters,
+            ),
+            None => {
+                warn!(model = %model, "No audio pricing data, returning 0.0");
+                0.0
+            }
+        }
+    }
+
+    /// Get pricing information for a model, preferring an override.
+    pub fn get_pricing(&self, model: &str) -> Option<ModelPricing> {
+        let overrides = self.overrides.read().unwrap_or_else(|e| e.into_inner());
+        match overrides.get(model) {
+            Some(pricing) => Some(pricing.clone()),
+            None => self.db().get(model).cloned(),
+        }
+    }
+
+    /// Get the limits and features of a chat model.
+    pub fn get_capabilities(&self, model: &str) -> Option<ModelCapabilities> {
+        self.db().get_capabilities(model).cloned()
     }
 
     /// Check if pricing is available for a model.
     pub fn has_pricing(&self, model: &str) -> bool {
-        self.pricing_db.has_pricing(model)
+        self.is_overridden(model) || self.db().has_pricing(model)
+    }
+
+    /// Get all available pricing information, with overrides applied.
+    pub fn all_pricing(&self) -> Vec<ModelPricing> {
+        let overrides = self.overrides.read().unwrap_or_else(|e| e.into_inner());
+        let mut all: Vec<ModelPricing> = self
+            .db()
+            .all()
+            .into_iter()
+            .filter(|p| !overrides.contains_key(&p.model))
+            .cloned()
+            .collect();
+        all.extend(overrides.values().cloned());
+        all.sort_by(|a, b| a.model.cmp(&b.model));
+        all
+    }
+
+    /// Get all per-image pricing information.
+    pub fn all_image_pricing(&self) -> Vec<ImagePricing> {
+        let mut all: Vec<ImagePricing> = self.db().all_images().into_iter().cloned().collect();
+        all.sort_by(|a, b| a.model.cmp(&b.model));
+        all
+    }
+
+    /// Get all audio pricing information.
+    pub fn all_audio_pricing(&self) -> Vec<AudioPricing> {
+        let mut all: Vec<AudioPricing> = self.db().all_audio().into_iter().cloned().collect();
+        all.sort_by(|a, b| a.model.cmp(&b.model));
+        all
     }
 
-    /// Get all available pricing information.
-    pub fn all_pricing(&self) -> Vec<&ModelPricing> {
-        self.pricing_db.all()
+    fn db(&self) -> std::sync::RwLockReadGuard<'_, PricingDatabase> {
+        self.pricing_db.read().unwrap_or_else(|e| e.into_inner())
     }
 
     /// Get all pricing information (static method for backward compatibility).
@@ -127,6 +407,112 @@ impl Default for CostCalculator {
     }
 }
 
+/// Reload the calculator's prices and exchange rate now and then every
+/// `interval` (once when `None`). Failures are logged and the current values
+/// kept.
+pub fn spawn_pricing_refresh(
+    calculator: Arc<CostCalculator>,
+    interval: Option<Duration>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(interval) = interval else {
+            refresh_sources(&calculator, true).await;
+            return;
+        };
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut first = true;
+        loop {
+            ticker.tick().await;
+            refresh_sources(&calculator, first).await;
+            first = false;
+        }
+    })
+}
+
+async fn refresh_sources(calculator: &CostCalculator, first: bool) {
+    if calculator.has_source() {
+        match calculator.refresh().await {
+            Ok(models) if first => info!(models, "Model prices loaded"),
+            Ok(models) => info!(models, "Model prices refreshed"),
+            Err(e) if first => {
+                warn!(error = %e, "Failed to load model prices, using built-in prices")
+            }
+            Err(e) => warn!(error = %e, "Failed to refresh model prices, keeping current prices"),
+        }
+    }
+    if calculator.has_exchange_rate_source() {
+        let currency = calculator.currency();
+        match calculator.refresh_exchange_rate().await {
+            Ok(rate) => info!(currency, rate, "Exchange rate updated"),
+            Err(e) => warn!(
+                currency,
+                rate = calculator.exchange_rate(),
+                error = %e,
+                "Failed to fetch exchange rate, keeping current rate"
+            ),
+        }
+    }
+}
+
+/// Load the user and team markups set through the admin API.
+pub fn load_markups(db: &Database) -> Result<Vec<(QuotaScope, String, CostMarkup)>, AppError> {
+    let markups = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT scope, subject, percent, per_million_tokens FROM cost_markups \
+             ORDER BY scope, subject",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let scope = match row.get::<_, String>(0)?.as_str() {
+                "team" => QuotaScope::Team,
+                _ => QuotaScope::User,
+            };
+            Ok((
+                scope,
+                row.get(1)?,
+                CostMarkup {
+                    percent: row.get(2)?,
+                    per_million_tokens: row.get(3)?,
+                },
+            ))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(markups)
+}
+
+/// Store the markup of a user or team. An all-`None` markup removes the row.
+pub fn save_markup(
+    db: &Database,
+    scope: QuotaScope,
+    subject: &str,
+    markup: CostMarkup,
+) -> Result<(), AppError> {
+    db.with_conn(|conn| {
+        if markup.is_none() {
+            conn.execute(
+                "DELETE FROM cost_markups WHERE scope = ?1 AND subject = ?2",
+                params![scope.as_str(), subject],
+            )?;
+        } else {
+            conn.execute(
+                "INSERT INTO cost_markups (scope, subject, percent, per_million_tokens) \
+                 VALUES (?1, ?2, ?3, ?4) \
+                 ON CONFLICT(scope, subject) DO UPDATE SET percent = ?3, \
+                 per_million_tokens = ?4",
+                params![
+                    scope.as_str(),
+                    subject,
+                    markup.percent,
+                    markup.per_million_tokens
+                ],
+            )?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
 // MARK: - Tests
 
 #[cfg(test)]
@@ -151,6 +537,21 @@ mod tests {
         assert!((cost - 0.0105).abs() < 0.0001);
     }
 
+    #[test]
+    fn test_calculate_audio_costs() {
+        let calculator = CostCalculator::new();
+
+        // 90 seconds of whisper-1 at $0.006/min = $0.009
+        let cost = calculator.calculate_transcription_cost("whisper-1", 90.0);
+        assert!((cost - 0.009).abs() < 1e-9);
+
+        // 2,000 characters of tts-1 at $15/1M = $0.03
+        let cost = calculator.calculate_speech_cost("tts-1", 2_000);
+        assert!((cost - 0.03).abs() < 1e-9);
+
+        assert_eq!(calculator.calculate_speech_cost("unknown-tts", 2_000), 0.0);
+    }
+
     #[test]
     fn test_calculate_cost_with_caching() {
         let calculator = CostCalculator::new();
@@ -237,6 +638,136 @@ mod tests {
         assert!(calculator.has_pricing("gpt-4o"));
         assert!(!calculator.has_pricing("unknown-model"));
     }
+
+    #[test]
+    fn test_price_override() {
+        let calculator = CostCalculator::new();
+        let usage = Usage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 0,
+            total_tokens: 1_000_000,
+            prompt_tokens_details: None,
+            completion_tokens_details: None,
+        };
+
+        calculator.set_override(ModelPricing {
+            model: "claude-sonnet-4-20250514".to_string(),
+            provider: "claude".to_string(),
+            input_cost_per_million: 1.0,
+            output_cost_per_million: 1.0,
+            cached_input_cost_per_million: None,
+        });
+        assert_eq!(
+            calculator.calculate_cost("claude-sonnet-4-20250514", &usage),
+            1.0
+        );
+        let before = calculator.all_pricing().len();
+        assert_eq!(CostCalculator::new().all_pricing().len(), before);
+
+        assert!(calculator.remove_override("claude-sonnet-4-20250514"));
+        assert_eq!(
+            calculator.calculate_cost("claude-sonnet-4-20250514", &usage),
+            3.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prices.json");
+        std::fs::write(
+            &path,
+            r#"{"models": [{"model": "my-model", "provider": "litellm",
+                "input_cost_per_million": 1.0, "output_cost_per_million": 2.0}]}"#,
+        )
+        .unwrap();
+
+        let calculator = CostCalculator::new();
+        assert!(calculator.refresh().await.is_err());
+
+        let config = crate::config::PricingConfig {
+            file: Some(path.to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let source = PricingSource::from_config(&config, reqwest::Client::new()).unwrap();
+        let calculator = CostCalculator::new().with_source(source);
+        calculator.refresh().await.unwrap();
+        assert!(calculator.has_pricing("my-model"));
+        assert!(calculator.has_pricing("gpt-4o"));
+
+        // A broken source keeps the current prices.
+        std::fs::write(&path, "not json").unwrap();
+        assert!(calculator.refresh().await.is_err());
+        assert!(calculator.has_pricing("my-model"));
+    }
+
+    #[test]
+    fn test_markup_user_over_team() {
+        let calculator = CostCalculator::new();
+        assert_eq!(calculator.apply_markup("u1", Some("eng"), 2.0, 1000), 2.0);
+
+        calculator.set_markup(
+            QuotaScope::Team,
+            "eng",
+            CostMarkup {
+                percent: Some(10.0),
+                per_million_tokens: None,
+            },
+        );
+        assert!((calculator.apply_markup("u1", Some("eng"), 2.0, 1000) - 2.2).abs() < 1e-9);
+        assert_eq!(calculator.apply_markup("u1", None, 2.0, 1000), 2.0);
+
+        calculator.set_markup(
+            QuotaScope::User,
+            "u1",
+            CostMarkup {
+                percent: None,
+                per_million_tokens: Some(0.5),
+            },
+        );
+        let cost = calculator.apply_markup("u1", Some("eng"), 2.0, 2_000_000);
+        assert!((cost - 3.0).abs() < 1e-9);
+
+        calculator.set_markup(QuotaScope::User, "u1", CostMarkup::default());
+        assert!(calculator.get_markup(QuotaScope::User, "u1").is_none());
+    }
+
+    #[test]
+    fn test_currency_conversion() {
+        let usage = Usage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 0,
+            total_tokens: 1_000_000,
+            prompt_tokens_details: None,
+            completion_tokens_details: None,
+        };
+        let usd = CostCalculator::new().calculate_cost("claude-sonnet-4-20250514", &usage);
+        let calculator = CostCalculator::new().with_currency("EUR", 0.5);
+        assert_eq!(calculator.currency(), "EUR");
+        assert_eq!(
+            calculator.calculate_cost("claude-sonnet-4-20250514", &usage),
+            usd * 0.5
+        );
+        assert_eq!(
+            calculator.calculate_image_cost("dall-e-3", None, 2),
+            CostCalculator::new().calculate_image_cost("dall-e-3", None, 2) * 0.5
+        );
+    }
+
+    #[test]
+    fn test_markup_storage() {
+        let db = Database::open_in_memory().unwrap();
+        let markup = CostMarkup {
+            percent: Some(20.0),
+            per_million_tokens: Some(1.0),
+        };
+        save_markup(&db, QuotaScope::Team, "eng", markup).unwrap();
+        save_markup(&db, QuotaScope::User, "u1", markup).unwrap();
+        save_markup(&db, QuotaScope::User, "u1", CostMarkup::default()).unwrap();
+
+        let loaded = load_markups(&db).unwrap();
+        assert_eq!(loaded, vec![(QuotaScope::Team, "eng".to_string(), markup)]);
+    }
 }
 
 // MARK: - Property-Based Tests
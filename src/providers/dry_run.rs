@@ -0,0 +1,461 @@
+//! Dry-run mode: providers that answer without calling upstream.
+//!
+//! With `providers.dry_run` (or `dry_run` on one provider's section) set,
+//! the router wraps the provider in a [`DryRunProvider`]. Requests still go
+//! through authentication, routing, quotas, budgets, the cache and the
+//! usage log, but the provider returns a canned response instead of sending
+//! them upstream, so a staging deployment can be exercised without spend.
+//!
+//! Responses depend only on the request: the same request always gets the
+//! same text, ID and token counts, which keeps tests and cache behaviour
+//! reproducible. Token usage is estimated ([`LlmProvider::count_tokens`])
+//! and priced like real usage.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use futures::Stream;
+use sha2::{Digest, Sha256};
+
+use crate::providers::pricing::ModelPricing;
+use crate::providers::tokens;
+use crate::providers::types::{
+    ChatChunk, ChatRequest, ChatResponse, Choice, ChunkChoice, Delta, ImageData,
+    ImageGenerationRequest, ImageGenerationResponse, ResponseMessage, SpeechRequest,
+    TranscriptionRequest, TranscriptionResponse, Usage,
+};
+use crate::providers::{LlmProvider, NativeStream, ProviderError, SpeechResponse};
+
+/// A 1x1 transparent PNG, returned for every generated image.
+const PLACEHOLDER_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0d, 0x0a, 0x2d, 0xb4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+/// A provider that answers every call with a canned response.
+pub struct DryRunProvider {
+    inner: Arc<dyn LlmProvider>,
+}
+
+impl DryRunProvider {
+    /// Wrap `inner`, keeping its ID, models and pricing.
+    pub fn new(inner: Arc<dyn LlmProvider>) -> Self {
+        Self { inner }
+    }
+
+    /// The canned reply to a chat request.
+    fn reply(&self, model: &str) -> String {
+        format!(
+            "This is a dry-run response from {} for {model}. No request was sent upstream.",
+            self.inner.id()
+        )
+    }
+
+    fn usage(&self, request: &ChatRequest, reply: &str) -> Usage {
+        let prompt_tokens = u32::try_from(self.inner.count_tokens(request)).unwrap_or(u32::MAX);
+        let completion_tokens =
+            u32::try_from(tokens::count_text(&request.model, reply)).unwrap_or(u32::MAX);
+        Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens.saturating_add(completion_tokens),
+            ..Default::default()
+        }
+    }
+
+    fn response(&self, request: &ChatRequest) -> ChatResponse {
+        let reply = self.reply(&request.model);
+        ChatResponse {
+            id: response_id(request),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: request.model.clone(),
+            choices: vec![Choice {
+                index: 0,
+                message: ResponseMessage {
+                    role: "assistant".to_string(),
+                    content: Some(reply.clone()),
+                    reasoning_content: None,
+                    tool_calls: None,
+                    grounding: None,
+                },
+                finish_reason: Some("stop".to_string()),
+            }],
+            usage: self.usage(request, &reply),
+            tool_calls_emulated: false,
+        }
+    }
+
+    /// The canned reply as stream chunks: the role, one chunk per word, then
+    /// the finish reason with usage.
+    fn chunks(&self, request: &ChatRequest) -> Vec<ChatChunk> {
+        let reply = self.reply(&request.model);
+        let chunk = |delta: Delta, finish_reason: Option<&str>, usage: Option<Usage>| ChatChunk {
+            id: response_id(request),
+            object: "chat.completion.chunk".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: request.model.clone(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta,
+                finish_reason: finish_reason.map(str::to_string),
+            }],
+            usage,
+            tool_calls_emulated: false,
+        };
+        let delta = |role: Option<&str>, content: Option<&str>| Delta {
+            role: role.map(str::to_string),
+            content: content.map(str::to_string),
+            reasoning_content: None,
+            tool_calls: None,
+            grounding: None,
+        };
+
+        let mut chunks = vec![chunk(delta(Some("assistant"), None), None, None)];
+        chunks.extend(
+            reply
+                .split_inclusive(' ')
+                .map(|word| chunk(delta(None, Some(word)), None, None)),
+        );
+        chunks.push(chunk(
+            delta(None, None),
+            Some("stop"),
+            Some(self.usage(request, &reply)),
+        ));
+        chunks
+    }
+
+    /// A Gemini API `GenerateContentResponse` with the canned reply.
+    fn native_response(&self, model: &str, request: &serde_json::Value) -> serde_json::Value {
+        let reply = self.reply(model);
+        let prompt = request
+            .get("contents")
+            .map_or(String::new(), |c| c.to_string());
+        let prompt_tokens = tokens::count_text(model, &prompt);
+        let reply_tokens = tokens::count_text(model, &reply);
+        serde_json::json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": reply}]},
+                "finishReason": "STOP",
+                "index": 0
+            }],
+            "usageMetadata": {
+                "promptTokenCount": prompt_tokens,
+                "candidatesTokenCount": reply_tokens,
+                "totalTokenCount": prompt_tokens + reply_tokens
+            },
+            "modelVersion": model
+        })
+    }
+}
+
+/// ID of the response to `request`, derived from the request so that
+/// repeating it gives the same ID.
+fn response_id(request: &ChatRequest) -> String {
+    let body = serde_json::to_string(request).unwrap_or_default();
+    let digest = Sha256::digest(body.as_bytes());
+    let hex: String = digest.iter().take(12).map(|b| format!("{b:02x}")).collect();
+    format!("chatcmpl-dryrun-{hex}")
+}
+
+/// Half a second of silence as 8 kHz, 16-bit mono WAV.
+fn silent_wav() -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 8_000;
+    let data_len = SAMPLE_RATE; // 0.5 s of 2-byte samples
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.resize(44 + data_len as usize, 0);
+    wav
+}
+
+impl LlmProvider for DryRunProvider {
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn models(&self) -> Vec<String> {
+        self.inner.models()
+    }
+
+    fn supports_model(&self, model: &str) -> bool {
+        self.inner.supports_model(model)
+    }
+
+    fn chat(
+        &self,
+        request: &ChatRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ChatResponse, ProviderError>> + Send + '_>> {
+        let response = self.response(request);
+        Box::pin(async move { Ok(response) })
+    }
+
+    fn stream_chat(
+        &self,
+        request: &ChatRequest,
+    ) -> Pin<
+        Box<
+            dyn Future<
+                    Output = Result<
+                        Pin<Box<dyn Stream<Item = Result<ChatChunk, ProviderError>> + Send>>,
+                        ProviderError,
+                    >,
+                > + Send
+                + '_,
+        >,
+    > {
+        let chunks = self.chunks(request);
+        Box::pin(async move {
+            Ok(Box::pin(futures::stream::iter(chunks.into_iter().map(Ok)))
+                as Pin<
+                    Box<dyn Stream<Item = Result<ChatChunk, ProviderError>> + Send>,
+                >)
+        })
+    }
+
+    /// Always healthy: there is no upstream to reach.
+    fn health_check(&self) -> Pin<Box<dyn Future<Output = bool> + Send + '_>> {
+        Box::pin(async { true })
+    }
+
+    fn pricing(&self) -> Vec<ModelPricing> {
+        self.inner.pricing()
+    }
+
+    fn premium_requests(&self, model: &str) -> f64 {
+        self.inner.premium_requests(model)
+    }
+
+    fn count_tokens(&self, request: &ChatRequest) -> u64 {
+        self.inner.count_tokens(request)
+    }
+
+    fn supports_image_model(&self, model: &str) -> bool {
+        self.inner.supports_image_model(model)
+    }
+
+    fn generate_images(
+        &self,
+        request: &ImageGenerationRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ImageGenerationResponse, ProviderError>> + Send + '_>>
+    {
+        let n = request.n.unwrap_or(1).max(1);
+        let image = ImageData {
+            url: None,
+            b64_json: Some(STANDARD.encode(PLACEHOLDER_PNG)),
+            revised_prompt: Some(request.prompt.clone()),
+        };
+        Box::pin(async move {
+            Ok(ImageGenerationResponse {
+                created: chrono::Utc::now().timestamp(),
+                data: vec![image; n as usize],
+            })
+        })
+    }
+
+    fn supports_audio_model(&self, model: &str) -> bool {
+        self.inner.supports_audio_model(model)
+    }
+
+    fn transcribe(
+        &self,
+        request: &TranscriptionRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<TranscriptionResponse, ProviderError>> + Send + '_>>
+    {
+        let response = TranscriptionResponse {
+            text: format!(
+                "This is a dry-run transcription of {} from {}.",
+                request.filename,
+                self.inner.id()
+            ),
+            ..Default::default()
+        };
+        Box::pin(async move { Ok(response) })
+    }
+
+    fn speech(
+        &self,
+        _request: &SpeechRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<SpeechResponse, ProviderError>> + Send + '_>> {
+        let audio = bytes::Bytes::from(silent_wav());
+        Box::pin(async move {
+            Ok(SpeechResponse {
+                content_type: "audio/wav".to_string(),
+                audio: Box::pin(futures::stream::once(async move { Ok(audio) })),
+            })
+        })
+    }
+
+    fn supports_gemini_native(&self, model: &str) -> bool {
+        self.inner.supports_gemini_native(model)
+    }
+
+    fn generate_content(
+        &self,
+        model: &str,
+        request: &serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value, ProviderError>> + Send + '_>> {
+        let response = self.native_response(model, request);
+        Box::pin(async move { Ok(response) })
+    }
+
+    fn stream_generate_content(
+        &self,
+        model: &str,
+        request: &serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<NativeStream, ProviderError>> + Send + '_>> {
+        let response = self.native_response(model, request);
+        Box::pin(async move {
+            Ok(Box::pin(futures::stream::once(async move { Ok(response) })) as NativeStream)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    /// A provider whose upstream calls always fail.
+    struct Upstream;
+
+    impl LlmProvider for Upstream {
+        fn id(&self) -> &str {
+            "upstream"
+        }
+
+        fn name(&self) -> &str {
+            "Upstream"
+        }
+
+        fn models(&self) -> Vec<String> {
+            vec!["gpt-4o".to_string()]
+        }
+
+        fn supports_model(&self, model: &str) -> bool {
+            model == "gpt-4o"
+        }
+
+        fn chat(
+            &self,
+            _request: &ChatRequest,
+        ) -> Pin<Box<dyn Future<Output = Result<ChatResponse, ProviderError>> + Send + '_>>
+        {
+            Box::pin(async { Err(ProviderError::Other("called upstream".into())) })
+        }
+
+        fn stream_chat(
+            &self,
+            _request: &ChatRequest,
+        ) -> Pin<
+            Box<
+                dyn Future<
+                        Output = Result<
+                            Pin<Box<dyn Stream<Item = Result<ChatChunk, ProviderError>> + Send>>,
+                            ProviderError,
+                        >,
+                    > + Send
+                    + '_,
+            >,
+        > {
+            Box::pin(async { Err(ProviderError::Other("called upstream".into())) })
+        }
+
+        fn health_check(&self) -> Pin<Box<dyn Future<Output = bool> + Send + '_>> {
+            Box::pin(async { false })
+        }
+
+        fn pricing(&self) -> Vec<ModelPricing> {
+            vec![]
+        }
+    }
+
+    fn provider() -> DryRunProvider {
+        DryRunProvider::new(Arc::new(Upstream))
+    }
+
+    fn request() -> ChatRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hello"}]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chat_is_deterministic() {
+        let provider = provider();
+        let first = provider.chat(&request()).await.unwrap();
+        let second = provider.chat(&request()).await.unwrap();
+        assert_eq!(first.id, second.id);
+        assert!(first.id.starts_with("chatcmpl-dryrun-"));
+        let content = first.choices[0].message.content.as_deref().unwrap();
+        assert!(content.contains("dry-run"));
+        assert_eq!(
+            content,
+            second.choices[0].message.content.as_deref().unwrap()
+        );
+        assert!(first.usage.prompt_tokens > 0);
+        assert_eq!(
+            first.usage.total_tokens,
+            first.usage.prompt_tokens + first.usage.completion_tokens
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_matches_chat() {
+        let provider = provider();
+        let response = provider.chat(&request()).await.unwrap();
+        let chunks: Vec<ChatChunk> = provider
+            .stream_chat(&request())
+            .await
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| c.choices[0].delta.content.as_deref())
+            .collect();
+        assert_eq!(
+            Some(text.as_str()),
+            response.choices[0].message.content.as_deref()
+        );
+        let last = chunks.last().unwrap();
+        assert_eq!(last.choices[0].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(
+            last.usage.as_ref().unwrap().total_tokens,
+            response.usage.total_tokens
+        );
+        assert!(provider.health_check().await);
+    }
+
+    #[test]
+    fn test_silent_wav() {
+        let wav = silent_wav();
+        assert!(wav.starts_with(b"RIFF"));
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(wav.len(), 44 + 8_000);
+    }
+}
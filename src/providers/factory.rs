@@ -0,0 +1,250 @@
+//! Building providers from their `[providers.*]` sections.
+//!
+//! The server builds every configured provider through [`ProviderFactory`]
+//! at startup, and the admin API rebuilds a section's providers with it when
+//! the section is edited, so URLs, models, timeouts and proxies apply without
+//! a restart. The factory owns the background tasks started for a section
+//! (LiteLLM spend reconciliation, Copilot model refresh) and stops them when
+//! the section is rebuilt.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use tokio::task::JoinHandle;
+
+use crate::budget::reconcile;
+use crate::config::{
+    KiroProfileConfig, KiroProviderConfig, LitellmProviderConfig, ProvidersConfig,
+    kiro_profile_provider_id,
+};
+use crate::db::Database;
+use crate::leader::Leader;
+use crate::net::HttpClientFactory;
+use crate::oauth::OAuthManager;
+use crate::providers::LlmProvider;
+use crate::providers::copilot::{self, CopilotProvider};
+use crate::providers::cost::CostCalculator;
+use crate::providers::gemini::CloudCodeClient;
+use crate::providers::gemini::provider::GeminiProvider;
+use crate::providers::kiro::{
+    AutoDetectProvider, KiroAuthManager, KiroClient, KiroProvider, machine_fingerprint,
+};
+use crate::providers::litellm::{LitellmConfig, LitellmProvider};
+
+/// Sections with providers, in registration (priority) order.
+pub const SECTIONS: [&str; 4] = ["kiro", "litellm", "gemini", "copilot"];
+
+/// Builds the providers of a `[providers.*]` section.
+pub struct ProviderFactory {
+    db: Database,
+    oauth: Arc<OAuthManager>,
+    costs: Arc<CostCalculator>,
+    leader: Arc<Leader>,
+    /// Background tasks of the last build of each section.
+    tasks: Mutex<HashMap<&'static str, Vec<JoinHandle<()>>>>,
+}
+
+impl ProviderFactory {
+    pub fn new(
+        db: Database,
+        oauth: Arc<OAuthManager>,
+        costs: Arc<CostCalculator>,
+        leader: Arc<Leader>,
+    ) -> Self {
+        Self {
+            db,
+            oauth,
+            costs,
+            leader,
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build the providers of section `name` (one of [`SECTIONS`]) from
+    /// `config`; none when the section is not configured or fails to build.
+    /// Background tasks of an earlier build of the section are stopped.
+    pub async fn build(&self, config: &ProvidersConfig, name: &str) -> Vec<Arc<dyn LlmProvider>> {
+        let Some(section) = SECTIONS.into_iter().find(|s| *s == name) else {
+            return Vec::new();
+        };
+        let http = HttpClientFactory::new(config);
+        let mut providers: Vec<Arc<dyn LlmProvider>> = Vec::new();
+        let mut tasks = Vec::new();
+
+        match section {
+            "kiro" => {
+                if let Some(ref kiro_config) = config.kiro {
+                    let provider =
+                        build_kiro_provider(kiro_config, &config.token_storage_dir, &http).await;
+                    providers.push(Arc::new(provider));
+                    tracing::info!("Kiro provider registered");
+                    for (name, profile) in &kiro_config.profiles {
+                        let provider = build_kiro_profile_provider(
+                            kiro_config,
+                            name,
+                            profile,
+                            &config.token_storage_dir,
+                            &http,
+                        )
+                        .await;
+                        providers.push(Arc::new(provider));
+                        tracing::info!(profile = %name, "Kiro profile registered");
+                    }
+                }
+            }
+            "litellm" => {
+                if let Some(ref litellm_config) = config.litellm {
+                    match build_litellm_provider(litellm_config, http).await {
+                        Ok(provider) => {
+                            let model_count = provider.models().len();
+                            let provider = Arc::new(provider);
+                            if let Some(interval) = litellm_config.reconcile_interval() {
+                                tasks.push(reconcile::spawn_litellm_reconciliation(
+                                    self.db.clone(),
+                                    provider.clone(),
+                                    self.costs.clone(),
+                                    interval,
+                                    litellm_config.reconcile_tolerance_percent,
+                                    self.leader.clone(),
+                                ));
+                                tracing::debug!(
+                                    interval_secs = interval.as_secs(),
+                                    "LiteLLM spend reconciliation spawned"
+                                );
+                            }
+                            providers.push(provider);
+                            tracing::info!(models = model_count, "LiteLLM provider registered");
+                        }
+                        Err(e) => {
+                            tracing::warn!(error = %e, "Failed to initialize LiteLLM provider, skipping");
+                        }
+                    }
+                }
+            }
+            "gemini" => {
+                if let Some(ref gemini_config) = config.gemini {
+                    let client = CloudCodeClient::builder()
+                        .with_token_provider(self.oauth.clone())
+                        .with_http(&http)
+                        .with_project_id(gemini_config.project_id.clone())
+                        .build();
+                    providers.push(Arc::new(GeminiProvider::from_client(client)));
+                    tracing::info!("Gemini provider registered");
+                }
+            }
+            _ => {
+                // Copilot. Its model list is fetched in the background; the
+                // built-in list is used until then.
+                if let Some(ref copilot_config) = config.copilot {
+                    let copilot =
+                        Arc::new(CopilotProvider::new(self.oauth.clone()).with_http(&http));
+                    if copilot_config.discover_models {
+                        tasks.push(copilot::spawn_model_refresh(
+                            copilot.clone(),
+                            copilot_config.model_refresh_interval(),
+                        ));
+                    }
+                    providers.push(copilot);
+                    tracing::info!("Copilot provider registered");
+                }
+            }
+        }
+
+        let mut running = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        for task in running.insert(section, tasks).unwrap_or_default() {
+            task.abort();
+        }
+        providers
+    }
+}
+
+/// Build a [`KiroProvider`] from the configuration.
+///
+/// Constructs a `KiroClient` using the refresh-token auth flow that matches
+/// the kiro-aws reference implementation.
+async fn build_kiro_provider(
+    kiro_config: &KiroProviderConfig,
+    token_storage_dir: &Path,
+    http: &HttpClientFactory,
+) -> KiroProvider {
+    let region = kiro_config.effective_region();
+    let fingerprint = machine_fingerprint();
+
+    let manager =
+        Arc::new(KiroAuthManager::new(fingerprint.clone(), region.clone()).with_http(http));
+
+    let auth = Arc::new(
+        AutoDetectProvider::new(
+            manager,
+            Some(kiro_config.credentials_path(token_storage_dir)),
+            kiro_config.kiro_db_path.as_ref().map(PathBuf::from),
+            kiro_config.sso_cache_dir.as_ref().map(PathBuf::from),
+        )
+        .await,
+    );
+
+    let client = KiroClient::new(
+        auth,
+        region,
+        kiro_config.effective_profile_arn(),
+        fingerprint,
+    )
+    .with_http(http);
+    KiroProvider::new(client)
+}
+
+/// Build the [`KiroProvider`] of an additional Kiro profile, registered as
+/// `kiro:<name>`. Only the profile's own credential sources are read.
+async fn build_kiro_profile_provider(
+    kiro_config: &KiroProviderConfig,
+    name: &str,
+    profile: &KiroProfileConfig,
+    token_storage_dir: &Path,
+    http: &HttpClientFactory,
+) -> KiroProvider {
+    let region = profile
+        .region
+        .clone()
+        .unwrap_or_else(|| kiro_config.effective_region());
+    let fingerprint = machine_fingerprint();
+
+    let manager =
+        Arc::new(KiroAuthManager::new(fingerprint.clone(), region.clone()).with_http(http));
+
+    let auth = Arc::new(
+        AutoDetectProvider::explicit(
+            manager,
+            Some(profile.credentials_path(name, token_storage_dir)),
+            profile.kiro_db_path.as_ref().map(PathBuf::from),
+            profile.sso_cache_dir.as_ref().map(PathBuf::from),
+        )
+        .await,
+    );
+
+    let client =
+        KiroClient::new(auth, region, profile.profile_arn.clone(), fingerprint).with_http(http);
+    KiroProvider::new(client).with_id(kiro_profile_provider_id(name))
+}
+
+/// Build a [`LitellmProvider`] from the configuration.
+///
+/// The provider connects to the LiteLLM proxy at the configured URL and
+/// optionally discovers available models from its `/v1/models` endpoint.
+async fn build_litellm_provider(
+    config: &LitellmProviderConfig,
+    http: HttpClientFactory,
+) -> Result<LitellmProvider, String> {
+    let litellm_config = LitellmConfig {
+        url: config.url.clone(),
+        api_key: config.api_key.clone(),
+        discover_models: config.discover_models,
+        models: config.models.clone(),
+        timeout_secs: config.timeout_secs,
+        http,
+    };
+
+    LitellmProvider::new(litellm_config)
+        .await
+        .map_err(|e| format!("LiteLLM provider init failed: {e}"))
+}
@@ -0,0 +1,224 @@
+//! Recent failed requests, kept for `GET /admin/debug/failures`.
+//!
+//! When every provider for a request fails, the router records the request
+//! and each provider's error chain in a ring buffer. Operators chasing a
+//! transformer bug can then see exactly what was sent and how each
+//! provider answered, without turning on payload logging for all traffic.
+//! Payloads are stored with credentials masked and long strings (such as
+//! inline images) cut short.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::providers::ProviderError;
+use crate::providers::redact;
+use crate::providers::types::ChatRequest;
+
+/// Failures kept unless `observability.failure_buffer` says otherwise.
+pub const DEFAULT_CAPACITY: usize = 50;
+
+/// Longest string kept in a stored payload, in characters.
+const MAX_STRING_CHARS: usize = 2_000;
+
+/// One provider's failed attempt at a request.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedAttempt {
+    pub provider: String,
+    pub error: String,
+    /// The error's sources, outermost first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+}
+
+impl FailedAttempt {
+    pub fn new(provider: &str, err: &ProviderError) -> Self {
+        let mut causes = Vec::new();
+        let mut source = err.source();
+        while let Some(cause) = source {
+            causes.push(redact::redact_text(&cause.to_string()));
+            source = cause.source();
+        }
+        Self {
+            provider: provider.to_string(),
+            error: redact::redact_text(&err.to_string()),
+            causes,
+        }
+    }
+}
+
+/// A request every provider failed.
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureRecord {
+    /// Increasing number of the failure since startup.
+    pub id: u64,
+    pub at: DateTime<Utc>,
+    /// `chat` or `stream`.
+    pub operation: &'static str,
+    pub model: String,
+    /// The error returned to the client.
+    pub error: String,
+    pub attempts: Vec<FailedAttempt>,
+    /// The request as routed, sanitized.
+    pub request: Value,
+}
+
+#[derive(Debug)]
+struct Buffer {
+    records: VecDeque<FailureRecord>,
+    capacity: usize,
+    next_id: u64,
+}
+
+/// Ring buffer of the most recent failures.
+#[derive(Debug)]
+pub struct FailureLog {
+    buffer: Mutex<Buffer>,
+}
+
+impl Default for FailureLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl FailureLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Mutex::new(Buffer {
+                records: VecDeque::new(),
+                capacity,
+                next_id: 1,
+            }),
+        }
+    }
+
+    /// Keep at most `capacity` failures; 0 stops recording.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.capacity = capacity;
+        while buffer.records.len() > capacity {
+            buffer.records.pop_front();
+        }
+    }
+
+    /// Record that every provider failed `request`.
+    pub fn record(
+        &self,
+        operation: &'static str,
+        request: &ChatRequest,
+        attempts: Vec<FailedAttempt>,
+        err: &ProviderError,
+    ) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        if buffer.capacity == 0 {
+            return;
+        }
+        let id = buffer.next_id;
+        buffer.next_id += 1;
+        if buffer.records.len() == buffer.capacity {
+            buffer.records.pop_front();
+        }
+        buffer.records.push_back(FailureRecord {
+            id,
+            at: Utc::now(),
+            operation,
+            model: request.model.clone(),
+            error: redact::redact_text(&err.to_string()),
+            attempts,
+            request: sanitized(request),
+        });
+    }
+
+    /// Up to `limit` failures, newest first.
+    pub fn recent(&self, limit: usize) -> Vec<FailureRecord> {
+        let buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.records.iter().rev().take(limit).cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+        buffer.records.clear();
+    }
+}
+
+/// `request` as JSON with credentials masked and long strings shortened.
+fn sanitized(request: &ChatRequest) -> Value {
+    let mut value = serde_json::to_value(request).unwrap_or(Value::Null);
+    redact::sanitize_value(&mut value);
+    shorten_strings(&mut value);
+    value
+}
+
+fn shorten_strings(value: &mut Value) {
+    match value {
+        Value::String(s) => {
+            let chars = s.chars().count();
+            if chars > MAX_STRING_CHARS {
+                let cut: String = s.chars().take(MAX_STRING_CHARS).collect();
+                *s = format!("{cut}... [{} more characters]", chars - MAX_STRING_CHARS);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(shorten_strings),
+        Value::Object(map) => map.values_mut().for_each(shorten_strings),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(content: &str) -> ChatRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": content}]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_newest() {
+        let log = FailureLog::new(2);
+        let err = ProviderError::Timeout { timeout_secs: 30 };
+        for text in ["a", "b", "c"] {
+            log.record("chat", &request(text), Vec::new(), &err);
+        }
+        let recent = log.recent(10);
+        let ids: Vec<u64> = recent.iter().map(|r| r.id).collect();
+        assert_eq!(ids, [3, 2]);
+        assert_eq!(recent[0].request["messages"][0]["content"], "c");
+
+        log.set_capacity(0);
+        log.record("chat", &request("d"), Vec::new(), &err);
+        assert!(log.recent(10).is_empty());
+    }
+
+    #[test]
+    fn test_payload_sanitized() {
+        let log = FailureLog::default();
+        let long = format!("Bearer abcdef {}", "x".repeat(MAX_STRING_CHARS));
+        let attempt = FailedAttempt::new(
+            "claude",
+            &ProviderError::ResponseParsing(
+                "unexpected field for key sk-ant-REDACTED".into(),
+            ),
+        );
+        log.record(
+            "stream",
+            &request(&long),
+            vec![attempt],
+            &ProviderError::NoProvider("gpt-4o".into()),
+        );
+        let record = &log.recent(1)[0];
+        let content = record.request["messages"][0]["content"].as_str().unwrap();
+        assert!(content.starts_with("Bearer [redacted] xxx"));
+        assert!(content.ends_with("[18 more characters]"));
+        assert!(record.attempts[0].error.contains("[redacted]"));
+        assert!(!record.attempts[0].error.contains("sk-ant"));
+    }
+}
@@ -8,6 +8,7 @@ use std::sync::Arc;
 
 use futures::Stream;
 use reqwest::StatusCode;
+use serde_json::Value;
 use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
 use tracing::{debug, info, instrument};
@@ -17,12 +18,15 @@ use crate::providers::gemini::constants::{
     ANTIGRAVITY_SYSTEM_INSTRUCTION, API_PATH_GENERATE_CONTENT, API_PATH_STREAM_GENERATE_CONTENT,
 };
 
+use crate::net::HttpClientFactory;
 use crate::providers::gemini::discovery::discover_project;
 use crate::providers::gemini::error::{AuthError, Error, Result};
-use crate::providers::gemini::models::google::{CloudCodeWrapper, Content, GoogleRequest, Part};
+use crate::providers::gemini::models::google::{
+    CloudCodeWrapper, Content, GoogleRequest, Part, unwrap_response,
+};
 use crate::providers::gemini::models::stream::StreamEvent;
 use crate::providers::gemini::transport::http::{HttpClient, generate_request_id};
-use crate::providers::gemini::transport::sse::SseStream;
+use crate::providers::gemini::transport::sse::{SseStream, native_stream};
 
 use crate::providers::gemini::thinking::SignatureCache;
 
@@ -35,6 +39,8 @@ pub struct CloudCodeClient {
     token_provider: Arc<dyn TokenService>,
     /// HTTP client for API requests.
     http: HttpClient,
+    /// Configured project ID, used instead of discovery.
+    project_override: Option<String>,
     /// Cached project ID.
     project_id: Arc<RwLock<Option<String>>>,
     /// Cached managed project ID.
@@ -73,9 +79,15 @@ impl CloudCodeClient {
 
     /// Get the project ID, discovering it if necessary.
     ///
-    /// The project ID is cached after the first discovery.
+    /// A configured project is used as is. Otherwise the project stored with
+    /// the credentials is reused, and only discovered when there is none; a
+    /// discovered project is stored for later runs.
     #[instrument(skip(self))]
     pub async fn get_project_id(&self) -> Result<String> {
+        if let Some(id) = &self.project_override {
+            return Ok(id.clone());
+        }
+
         // Check cache first
         {
             let cached = self.project_id.read().await;
@@ -84,9 +96,26 @@ impl CloudCodeClient {
             }
         }
 
-        // Discover project
-        let token = self.get_access_token().await?;
-        let project_info = discover_project(&token, None).await?;
+        let project_info = match self.token_provider.cached_project("gemini").await {
+            Some(project) => {
+                debug!(project_id = %project.project_id, "Using stored project");
+                project
+            }
+            None => {
+                let token = self.get_access_token().await?;
+                let project = discover_project(self.http.inner(), &token, None).await?;
+                info!(
+                    project_id = %project.project_id,
+                    tier = %project.subscription_tier,
+                    "Discovered project"
+                );
+                // Don't pin the shared fallback; retry discovery next run.
+                if !project.is_fallback() {
+                    self.token_provider.store_project("gemini", &project).await;
+                }
+                project
+            }
+        };
 
         // Cache the results
         {
@@ -99,12 +128,6 @@ impl CloudCodeClient {
             *managed_id = Some(managed.clone());
         }
 
-        info!(
-            project_id = %project_info.project_id,
-            tier = %project_info.subscription_tier,
-            "Discovered project"
-        );
-
         Ok(project_info.project_id)
     }
 
@@ -115,6 +138,14 @@ impl CloudCodeClient {
         model: &str,
         request: GoogleRequest,
     ) -> Result<crate::providers::gemini::models::google::GoogleResponse> {
+        let response = self.request_native(model, request).await?;
+        Ok(serde_json::from_value(response)?)
+    }
+
+    /// Send a Google format request and return the Gemini API response as
+    /// is, without the Cloud Code envelope.
+    #[instrument(skip(self, request), fields(model = %model))]
+    pub async fn request_native(&self, model: &str, request: GoogleRequest) -> Result<Value> {
         let token = self.get_access_token().await?;
         let project_id = self.get_project_id().await?;
 
@@ -133,16 +164,13 @@ impl CloudCodeClient {
             .post_with_fallback(path, &token, &wrapped, model, false)
             .await?;
 
-        let status = response.status();
-        self.handle_response_status(status, &response).await?;
+        let response = self.handle_response_status(response).await?;
 
-        // Parse response
-        let google_response: crate::providers::gemini::models::google::GoogleResponse =
-            response.json().await?;
+        let body: Value = response.json().await?;
 
         debug!("Request completed");
 
-        Ok(google_response)
+        Ok(unwrap_response(body))
     }
 
     /// Send a request and return a stream of events.
@@ -152,6 +180,30 @@ impl CloudCodeClient {
         model: &str,
         request: GoogleRequest,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>> {
+        let response = self.open_stream(model, request).await?;
+
+        // Create SSE stream
+        let byte_stream = response.bytes_stream();
+        let sse_stream = SseStream::new(byte_stream, model);
+
+        Ok(Box::pin(sse_stream))
+    }
+
+    /// Send a streaming request and return the Gemini API response chunks
+    /// as is, without the Cloud Code envelope.
+    #[instrument(skip(self, request), fields(model = %model))]
+    pub async fn request_native_stream(
+        &self,
+        model: &str,
+        request: GoogleRequest,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Value>> + Send>>> {
+        let response = self.open_stream(model, request).await?;
+        Ok(native_stream(response.bytes_stream()))
+    }
+
+    /// Start a streaming request, returning the response once its status
+    /// was checked.
+    async fn open_stream(&self, model: &str, request: GoogleRequest) -> Result<reqwest::Response> {
         let token = self.get_access_token().await?;
         let project_id = self.get_project_id().await?;
 
@@ -170,31 +222,37 @@ impl CloudCodeClient {
             .post_with_fallback(path, &token, &wrapped, model, true)
             .await?;
 
-        let status = response.status();
-        self.handle_response_status(status, &response).await?;
-
-        // Create SSE stream
-        let byte_stream = response.bytes_stream();
-        let sse_stream = SseStream::new(byte_stream, model);
-
-        Ok(Box::pin(sse_stream))
+        self.handle_response_status(response).await
     }
 
-    /// Handle response status codes.
+    /// Handle response status codes, passing successful responses through.
     async fn handle_response_status(
         &self,
-        status: StatusCode,
-        _response: &reqwest::Response,
-    ) -> Result<()> {
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response> {
+        let status = response.status();
         if status.is_success() {
-            return Ok(());
+            return Ok(response);
         }
 
         match status {
             StatusCode::UNAUTHORIZED => Err(Error::Auth(AuthError::TokenExpired)),
             StatusCode::TOO_MANY_REQUESTS => {
-                // Rate limit error - 429
-                Err(Error::api(429, "Rate limit exceeded", None))
+                // Rate limit or quota error - 429. Keep the body so callers
+                // can tell a daily quota from a short-lived limit.
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+                    .map(std::time::Duration::from_secs);
+                let body = response.text().await.unwrap_or_default();
+                let message = if body.is_empty() {
+                    "Rate limit exceeded".to_string()
+                } else {
+                    body
+                };
+                Err(Error::api(429, message, retry_after))
             }
             StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => Err(Error::api(
                 status.as_u16(),
@@ -251,7 +309,8 @@ impl CloudCodeClient {
 
     /// Clear cached project information.
     ///
-    /// Forces re-discovery on the next request.
+    /// The next request reloads the stored project or, without one,
+    /// discovers it again. A configured project is kept.
     pub async fn clear_project_cache(&self) {
         let mut project = self.project_id.write().await;
         *project = None;
@@ -288,6 +347,7 @@ pub struct CloudCodeClientBuilder {
     token_provider: Option<Arc<dyn TokenService>>,
     http_builder: crate::providers::gemini::transport::http::HttpClientBuilder,
     signature_cache: Option<Arc<SignatureCache>>,
+    project_id: Option<String>,
 }
 
 impl CloudCodeClientBuilder {
@@ -320,6 +380,18 @@ impl CloudCodeClientBuilder {
         self
     }
 
+    /// Use the shared client settings (proxy, pool and HTTP/2 tuning).
+    pub fn with_http(mut self, http: &HttpClientFactory) -> Self {
+        self.http_builder = self.http_builder.factory(http.clone());
+        self
+    }
+
+    /// Send requests to this project instead of discovering one.
+    pub fn with_project_id(mut self, project_id: Option<String>) -> Self {
+        self.project_id = project_id;
+        self
+    }
+
     /// Set a custom signature cache.
     pub fn with_signature_cache(mut self, cache: Arc<SignatureCache>) -> Self {
         self.signature_cache = Some(cache);
@@ -337,6 +409,7 @@ impl CloudCodeClientBuilder {
         CloudCodeClient {
             token_provider,
             http: self.http_builder.build(),
+            project_override: self.project_id,
             project_id: Arc::new(RwLock::new(None)),
             managed_project_id: Arc::new(RwLock::new(None)),
             signature_cache: self
@@ -352,6 +425,7 @@ impl Default for CloudCodeClientBuilder {
             token_provider: None,
             http_builder: crate::providers::gemini::transport::http::HttpClientBuilder::default(),
             signature_cache: None,
+            project_id: None,
         }
     }
 }
@@ -360,6 +434,7 @@ impl Default for CloudCodeClientBuilder {
 mod tests {
     use super::*;
     use crate::providers::ProviderError;
+    use crate::providers::gemini::discovery::{ProjectInfo, SubscriptionTier};
 
     struct MockTokenProvider;
 
@@ -400,4 +475,36 @@ mod tests {
         // Verify same cache is used
         assert!(Arc::ptr_eq(&client.signature_cache, &cache_clone));
     }
+
+    struct StoredProjectProvider;
+
+    #[async_trait::async_trait]
+    impl TokenService for StoredProjectProvider {
+        async fn get_token(&self, _provider: &str) -> std::result::Result<String, ProviderError> {
+            Ok("mock-token".to_string())
+        }
+
+        async fn cached_project(&self, _provider: &str) -> Option<ProjectInfo> {
+            Some(ProjectInfo::new(
+                "stored-project".to_string(),
+                None,
+                SubscriptionTier::Free,
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_project_id_prefers_override_then_stored() {
+        // Discovery would hit the network; both paths must avoid it.
+        let stored = CloudCodeClient::new(Arc::new(StoredProjectProvider));
+        assert_eq!(stored.get_project_id().await.unwrap(), "stored-project");
+
+        let configured = CloudCodeClient::builder()
+            .with_token_provider(Arc::new(StoredProjectProvider))
+            .with_project_id(Some("my-project".to_string()))
+            .build();
+        assert_eq!(configured.get_project_id().await.unwrap(), "my-project");
+        configured.clear_project_cache().await;
+        assert_eq!(configured.get_project_id().await.unwrap(), "my-project");
+    }
 }
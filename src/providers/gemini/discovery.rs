@@ -76,7 +76,7 @@ impl SubscriptionTier {
 }
 
 /// Information about a Cloud Code project.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProjectInfo {
     /// The Cloud Code project ID.
     pub project_id: String,
@@ -102,6 +102,12 @@ impl ProjectInfo {
         }
     }
 
+    /// Whether this is the shared fallback project rather than one that was
+    /// actually discovered for the account.
+    pub fn is_fallback(&self) -> bool {
+        self.project_id == DEFAULT_PROJECT_ID
+    }
+
     /// Create a default ProjectInfo for fallback scenarios.
     pub fn default_fallback() -> Self {
         Self {
@@ -142,8 +148,12 @@ struct OnboardUserResponse {
 }
 
 /// Discover project information by calling the Cloud Code API.
-#[instrument(skip(token))]
-pub async fn discover_project(token: &str, hint_project_id: Option<&str>) -> Result<ProjectInfo> {
+#[instrument(skip(client, token))]
+pub async fn discover_project(
+    client: &reqwest::Client,
+    token: &str,
+    hint_project_id: Option<&str>,
+) -> Result<ProjectInfo> {
     // Try each endpoint in order
     let mut last_error: Option<Error> = None;
 
@@ -151,7 +161,7 @@ pub async fn discover_project(token: &str, hint_project_id: Option<&str>) -> Res
         let url = format!("{}{}", endpoint, API_PATH_LOAD_CODE_ASSIST);
         debug!(endpoint = %endpoint, "Trying loadCodeAssist endpoint");
 
-        match try_load_code_assist(token, &url, hint_project_id).await {
+        match try_load_code_assist(client, token, &url, hint_project_id).await {
             Ok(response) => {
                 // Parse the response into ProjectInfo
                 let project_id = response
@@ -189,7 +199,7 @@ pub async fn discover_project(token: &str, hint_project_id: Option<&str>) -> Res
                 // For other errors, try onboarding
                 if matches!(&e, Error::Api { status, .. } if *status >= 400 && *status < 500) {
                     debug!("Attempting user onboarding");
-                    match try_onboard_user(token, endpoint).await {
+                    match try_onboard_user(client, token, endpoint).await {
                         Ok(project_id) => {
                             info!(project_id = %project_id, "User onboarded successfully");
                             return Ok(ProjectInfo::new(project_id, None, SubscriptionTier::Free));
@@ -217,12 +227,11 @@ pub async fn discover_project(token: &str, hint_project_id: Option<&str>) -> Res
 
 /// Try to call loadCodeAssist API at a specific endpoint.
 async fn try_load_code_assist(
+    client: &reqwest::Client,
     token: &str,
     url: &str,
     hint_project_id: Option<&str>,
 ) -> Result<LoadCodeAssistResponse> {
-    let client = reqwest::Client::new();
-
     let mut request = client
         .post(url)
         .header("Authorization", format!("Bearer {}", token))
@@ -252,11 +261,11 @@ async fn try_load_code_assist(
 }
 
 /// Onboard a new user to Cloud Code.
-#[instrument(skip(token))]
-pub async fn onboard_user(token: &str, tier: &str) -> Result<String> {
+#[instrument(skip(client, token))]
+pub async fn onboard_user(client: &reqwest::Client, token: &str, tier: &str) -> Result<String> {
     // Try each endpoint
     for endpoint in LOAD_CODE_ASSIST_ENDPOINTS {
-        match try_onboard_user(token, endpoint).await {
+        match try_onboard_user(client, token, endpoint).await {
             Ok(project_id) => return Ok(project_id),
             Err(e) => {
                 warn!(endpoint = %endpoint, error = %e, "Onboarding failed at endpoint");
@@ -271,10 +280,9 @@ pub async fn onboard_user(token: &str, tier: &str) -> Result<String> {
 }
 
 /// Try to onboard user at a specific endpoint.
-async fn try_onboard_user(token: &str, endpoint: &str) -> Result<String> {
+async fn try_onboard_user(client: &reqwest::Client, token: &str, endpoint: &str) -> Result<String> {
     let url = format!("{}{}", endpoint, API_PATH_ONBOARD_USER);
 
-    let client = reqwest::Client::new();
     let response = client
         .post(&url)
         .header("Authorization", format!("Bearer {}", token))
@@ -10,6 +10,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::providers::types::{Grounding, GroundingCitation, GroundingSource};
+
 /// Request format for Google Generative AI API.
 ///
 /// This is the wrapper format required by Cloud Code.
@@ -42,6 +44,10 @@ pub struct GoogleRequest {
     /// Session ID for caching continuity.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_id: Option<String>,
+
+    /// Other request fields (e.g. `safetySettings`), passed through as is.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
 }
 
 impl GoogleRequest {
@@ -55,6 +61,7 @@ impl GoogleRequest {
             tool_config: None,
             thinking_config: None,
             session_id: None,
+            extra: serde_json::Map::new(),
         }
     }
 
@@ -68,6 +75,7 @@ impl GoogleRequest {
             tool_config: None,
             thinking_config: None,
             session_id: None,
+            extra: serde_json::Map::new(),
         }
     }
 }
@@ -107,6 +115,17 @@ impl GoogleResponse {
     }
 }
 
+/// Remove the `{"response": ...}` envelope Cloud Code puts around Gemini
+/// API responses. Responses without it are returned unchanged.
+pub fn unwrap_response(value: Value) -> Value {
+    match value {
+        Value::Object(mut map) if map.get("response").is_some_and(Value::is_object) => {
+            map.remove("response").unwrap_or_default()
+        }
+        other => other,
+    }
+}
+
 /// A generated candidate.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -127,11 +146,134 @@ pub struct Candidate {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub citation_metadata: Option<Value>,
 
+    /// Sources of an answer grounded with Google Search.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grounding_metadata: Option<GroundingMetadata>,
+
     /// Candidate index.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub index: Option<u32>,
 }
 
+/// Grounding metadata of a candidate answered with Google Search.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroundingMetadata {
+    /// Search queries the model ran.
+    #[serde(default)]
+    pub web_search_queries: Vec<String>,
+
+    /// Sources found by the searches.
+    #[serde(default)]
+    pub grounding_chunks: Vec<GroundingChunk>,
+
+    /// Spans of the answer and the chunks supporting them.
+    #[serde(default)]
+    pub grounding_supports: Vec<GroundingSupport>,
+
+    /// Search suggestions to show with the answer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_entry_point: Option<SearchEntryPoint>,
+}
+
+impl GroundingMetadata {
+    /// Convert to the OpenAI-compatible response extension.
+    pub fn to_grounding(&self) -> Grounding {
+        Grounding {
+            search_queries: self.web_search_queries.clone(),
+            sources: self
+                .grounding_chunks
+                .iter()
+                .map(|chunk| {
+                    let web = chunk.web.clone().unwrap_or_default();
+                    GroundingSource {
+                        url: web.uri.unwrap_or_default(),
+                        title: web.title,
+                    }
+                })
+                .collect(),
+            citations: self
+                .grounding_supports
+                .iter()
+                .filter_map(|support| {
+                    let segment = support.segment.as_ref()?;
+                    Some(GroundingCitation {
+                        start_index: segment.start_index,
+                        end_index: segment.end_index,
+                        text: segment.text.clone(),
+                        source_indices: support.grounding_chunk_indices.clone(),
+                    })
+                })
+                .collect(),
+            search_entry_point: self
+                .search_entry_point
+                .as_ref()
+                .and_then(|e| e.rendered_content.clone()),
+        }
+    }
+}
+
+/// A source retrieved for grounding.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroundingChunk {
+    /// Web page the chunk comes from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web: Option<WebSource>,
+}
+
+/// A web page used for grounding.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSource {
+    /// Page URI (often a Google redirect URL).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+
+    /// Page title, usually the domain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// Links a span of the answer to the chunks supporting it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroundingSupport {
+    /// The supported span.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment: Option<Segment>,
+
+    /// Indices into `grounding_chunks`.
+    #[serde(default)]
+    pub grounding_chunk_indices: Vec<u32>,
+}
+
+/// A span of the answer text, in bytes.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    /// Start offset (inclusive).
+    #[serde(default)]
+    pub start_index: u32,
+
+    /// End offset (exclusive).
+    #[serde(default)]
+    pub end_index: u32,
+
+    /// Text of the span.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Rendered Google Search suggestions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchEntryPoint {
+    /// HTML and CSS of the suggestion chips.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rendered_content: Option<String>,
+}
+
 /// Safety rating for content.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -456,6 +598,28 @@ pub struct GenerationConfig {
     /// Response MIME type.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_mime_type: Option<String>,
+
+    /// Output modalities (e.g. `["IMAGE"]` for image generation models).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_modalities: Option<Vec<String>>,
+
+    /// Image output configuration for image generation models.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_config: Option<ImageConfig>,
+
+    /// Other generation settings (e.g. `responseSchema`), passed through
+    /// as is.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+/// Image output configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageConfig {
+    /// Aspect ratio such as `"1:1"` or `"16:9"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<String>,
 }
 
 impl GenerationConfig {
@@ -473,7 +637,12 @@ impl GenerationConfig {
 #[serde(rename_all = "camelCase")]
 pub struct GoogleTool {
     /// Function declarations.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub function_declarations: Vec<FunctionDeclaration>,
+
+    /// Google Search grounding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub google_search: Option<GoogleSearch>,
 }
 
 impl GoogleTool {
@@ -481,10 +650,23 @@ impl GoogleTool {
     pub fn new(declarations: Vec<FunctionDeclaration>) -> Self {
         Self {
             function_declarations: declarations,
+            google_search: None,
+        }
+    }
+
+    /// Create the Google Search grounding tool.
+    pub fn google_search() -> Self {
+        Self {
+            function_declarations: Vec::new(),
+            google_search: Some(GoogleSearch {}),
         }
     }
 }
 
+/// Google Search tool settings. Serializes to `{}`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GoogleSearch {}
+
 /// Function declaration for tools.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -878,6 +1060,9 @@ mod tests {
             stop_sequences: Some(vec!["END".to_string()]),
             candidate_count: None,
             response_mime_type: None,
+            response_modalities: None,
+            image_config: None,
+            extra: serde_json::Map::new(),
         };
 
         let json = serde_json::to_value(&config).unwrap();
@@ -948,6 +1133,7 @@ mod tests {
             finish_reason: Some("STOP".to_string()),
             safety_ratings: vec![],
             citation_metadata: None,
+            grounding_metadata: None,
             index: Some(0),
         };
 
@@ -955,6 +1141,45 @@ mod tests {
         assert_eq!(candidate.finish_reason, Some("STOP".to_string()));
     }
 
+    #[test]
+    fn test_google_search_tool() {
+        let json = serde_json::to_value(GoogleTool::google_search()).unwrap();
+        assert_eq!(json, json!({"googleSearch": {}}));
+    }
+
+    #[test]
+    fn test_grounding_metadata_conversion() {
+        let candidate: Candidate = serde_json::from_value(json!({
+            "content": {"role": "model", "parts": [{"text": "Spain won Euro 2024."}]},
+            "groundingMetadata": {
+                "webSearchQueries": ["euro 2024 winner"],
+                "searchEntryPoint": {"renderedContent": "<div>chips</div>"},
+                "groundingChunks": [
+                    {"web": {"uri": "https://a.example/x", "title": "a.example"}},
+                    {"web": {"uri": "https://b.example/y", "title": "b.example"}}
+                ],
+                "groundingSupports": [{
+                    "segment": {"endIndex": 20, "text": "Spain won Euro 2024."},
+                    "groundingChunkIndices": [0, 1]
+                }]
+            }
+        }))
+        .unwrap();
+
+        let grounding = candidate.grounding_metadata.unwrap().to_grounding();
+        assert_eq!(grounding.search_queries, vec!["euro 2024 winner"]);
+        assert_eq!(grounding.sources.len(), 2);
+        assert_eq!(grounding.sources[1].url, "https://b.example/y");
+        assert_eq!(grounding.sources[1].title.as_deref(), Some("b.example"));
+        assert_eq!(grounding.citations[0].start_index, 0);
+        assert_eq!(grounding.citations[0].end_index, 20);
+        assert_eq!(grounding.citations[0].source_indices, vec![0, 1]);
+        assert_eq!(
+            grounding.search_entry_point.as_deref(),
+            Some("<div>chips</div>")
+        );
+    }
+
     #[test]
     fn test_request_roundtrip() {
         let original = GoogleRequest {
@@ -972,6 +1197,7 @@ mod tests {
             tool_config: Some(ToolConfig::auto()),
             thinking_config: Some(GoogleThinkingConfig::gemini(8000)),
             session_id: None,
+            extra: serde_json::Map::new(),
         };
 
         let serialized = serde_json::to_string(&original).unwrap();
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use super::content::ContentBlock;
 use super::request::Role;
 use super::response::{StopReason, Usage};
+use crate::providers::types::Grounding;
 
 /// A streaming event from the Messages API.
 ///
@@ -296,6 +297,10 @@ pub struct MessageDelta {
     /// Stop sequence that caused the stop (if applicable).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop_sequence: Option<String>,
+
+    /// Search grounding of the message (Gemini only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grounding: Option<Grounding>,
 }
 
 impl MessageDelta {
@@ -304,6 +309,7 @@ impl MessageDelta {
         Self {
             stop_reason,
             stop_sequence: None,
+            grounding: None,
         }
     }
 
@@ -312,6 +318,7 @@ impl MessageDelta {
         Self {
             stop_reason: Some(stop_reason),
             stop_sequence: Some(stop_sequence.into()),
+            grounding: None,
         }
     }
 }
@@ -10,6 +10,10 @@ use tracing::warn;
 
 use crate::providers::gemini::{
     client::CloudCodeClient,
+    models::google::{
+        Content as GoogleContent, GenerationConfig, GoogleRequest, GoogleResponse, GoogleTool,
+        ImageConfig, Part,
+    },
     models::{
         ContentBlock, ContentDelta, Message, MessageContent, MessagesRequest, MessagesResponse,
         Role, StopReason, StreamEvent, SystemPrompt, Tool,
@@ -20,11 +24,12 @@ use crate::providers::transform::gemini::{
 };
 
 use crate::providers::pricing::ModelPricing;
+use crate::providers::transform::util::{detect_quota_exhausted, merge_extra_body};
 use crate::providers::types::{
-    ChatChunk, ChatRequest, ChatResponse, Choice, ChunkChoice, Delta, FunctionCall, MessageRole,
-    ResponseMessage, ToolCall, Usage,
+    ChatChunk, ChatRequest, ChatResponse, Choice, ChunkChoice, Delta, FunctionCall, ImageData,
+    ImageGenerationRequest, ImageGenerationResponse, MessageRole, ResponseMessage, ToolCall, Usage,
 };
-use crate::providers::{LlmProvider, ProviderError};
+use crate::providers::{LlmProvider, NativeStream, ProviderError};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -38,6 +43,10 @@ const SUPPORTED_MODELS: &[&str] = &[
     "gemini-1.5-pro",
 ];
 
+/// Image generation models served through `generateContent` with an image
+/// response modality.
+const IMAGE_MODELS: &[&str] = &["gemini-2.5-flash-image", "gemini-3-pro-image-preview"];
+
 // ---------------------------------------------------------------------------
 // Gemini Provider
 // ---------------------------------------------------------------------------
@@ -54,6 +63,11 @@ impl GeminiProvider {
         Self { client }
     }
 
+    /// Create a provider around a preconfigured client.
+    pub fn from_client(client: CloudCodeClient) -> Self {
+        Self { client }
+    }
+
     // -- Conversion Helpers -------------------------------------------------
 
     fn convert_request(&self, request: &ChatRequest) -> Result<MessagesRequest, ProviderError> {
@@ -119,6 +133,14 @@ impl GeminiProvider {
                                                 image_url.url
                                             )));
                                         }
+                                        crate::providers::types::ContentPart::Document {
+                                            document,
+                                        } => {
+                                            let (media_type, data) = document.base64();
+                                            blocks.push(ContentBlock::document_base64(
+                                                media_type, data,
+                                            ));
+                                        }
                                     }
                                 }
                             }
@@ -262,6 +284,7 @@ impl GeminiProvider {
                 content,
                 reasoning_content: None,
                 tool_calls,
+                grounding: None,
             },
             finish_reason,
         }];
@@ -281,6 +304,7 @@ impl GeminiProvider {
             model: model.to_string(),
             choices,
             usage,
+            tool_calls_emulated: false,
         })
     }
 }
@@ -289,6 +313,120 @@ impl GeminiProvider {
 // LlmProvider implementation
 // ---------------------------------------------------------------------------
 
+/// Add the Google Search grounding tool to a request.
+fn add_google_search(request: &mut GoogleRequest) {
+    request
+        .tools
+        .get_or_insert_with(Vec::new)
+        .push(GoogleTool::google_search());
+}
+
+/// Merge a request's `extra_body` (e.g. `safetySettings`) into a Cloud Code
+/// request. Known fields replace the converted ones; unknown fields are
+/// passed through via [`GoogleRequest::extra`].
+fn apply_extra_body(
+    request: GoogleRequest,
+    extra: Option<&serde_json::Map<String, serde_json::Value>>,
+) -> Result<GoogleRequest, ProviderError> {
+    if extra.is_none() {
+        return Ok(request);
+    }
+    let mut body = serde_json::to_value(request)
+        .map_err(|e| ProviderError::Other(format!("Failed to serialize request: {e}")))?;
+    merge_extra_body(&mut body, extra, "gemini");
+    serde_json::from_value(body)
+        .map_err(|e| ProviderError::InvalidRequest(format!("Invalid extra_body: {e}")))
+}
+
+/// Map a Cloud Code client error to a `ProviderError`. 429s become quota
+/// or rate-limit errors so the router can route around them; anything else
+/// is reported as an upstream failure.
+fn map_client_error(err: crate::providers::gemini::error::Error) -> ProviderError {
+    use crate::providers::gemini::error::Error;
+
+    match err {
+        Error::Api {
+            status: 429,
+            message,
+            retry_after,
+        } => {
+            detect_quota_exhausted(429, &message, "gemini").unwrap_or(ProviderError::RateLimited {
+                retry_after_secs: retry_after.map(|d| d.as_secs()).unwrap_or(60),
+                retry_after,
+            })
+        }
+        other => ProviderError::Api {
+            status: 500,
+            message: other.to_string(),
+        },
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Image generation helpers
+// ---------------------------------------------------------------------------
+
+/// Map an OpenAI `WIDTHxHEIGHT` size to the closest Gemini aspect ratio.
+fn aspect_ratio_for_size(size: &str) -> Option<&'static str> {
+    let (w, h) = size.split_once('x')?;
+    let (w, h) = (w.parse::<f64>().ok()?, h.parse::<f64>().ok()?);
+    if w <= 0.0 || h <= 0.0 {
+        return None;
+    }
+    const RATIOS: &[(&str, f64)] = &[
+        ("1:1", 1.0),
+        ("3:2", 1.5),
+        ("2:3", 2.0 / 3.0),
+        ("4:3", 4.0 / 3.0),
+        ("3:4", 0.75),
+        ("16:9", 16.0 / 9.0),
+        ("9:16", 9.0 / 16.0),
+    ];
+    let ratio = w / h;
+    RATIOS
+        .iter()
+        .min_by(|a, b| (a.1 - ratio).abs().total_cmp(&(b.1 - ratio).abs()))
+        .map(|(name, _)| *name)
+}
+
+/// Build a single-image `generateContent` request for an image prompt.
+fn image_request(request: &ImageGenerationRequest) -> GoogleRequest {
+    let mut google_req =
+        GoogleRequest::with_contents(vec![GoogleContent::user(vec![Part::text(&request.prompt)])]);
+    google_req.generation_config = Some(GenerationConfig {
+        response_modalities: Some(vec!["IMAGE".to_string()]),
+        image_config: request
+            .size
+            .as_deref()
+            .and_then(aspect_ratio_for_size)
+            .map(|ratio| ImageConfig {
+                aspect_ratio: Some(ratio.to_string()),
+            }),
+        ..Default::default()
+    });
+    google_req
+}
+
+/// Extract inline image parts from a `generateContent` response.
+fn images_from_response(response: &GoogleResponse) -> Vec<ImageData> {
+    response
+        .content()
+        .map(|content| {
+            content
+                .parts
+                .iter()
+                .filter_map(|p| p.inline_data.as_ref())
+                .filter(|d| d.mime_type.starts_with("image/"))
+                .map(|d| ImageData {
+                    url: None,
+                    b64_json: Some(d.data.clone()),
+                    revised_prompt: None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 impl LlmProvider for GeminiProvider {
     fn id(&self) -> &str {
         "gemini"
@@ -321,20 +459,29 @@ impl LlmProvider for GeminiProvider {
             }
 
             let msg_req = self.convert_request(&request)?;
-            let google_req = to_google_req(&msg_req);
+            let mut google_req = to_google_req(&msg_req);
+            if request.google_search {
+                add_google_search(&mut google_req);
+            }
+            let google_req = apply_extra_body(google_req, request.extra_body.as_ref())?;
 
             let google_resp = self
                 .client
                 .request(&msg_req.model, google_req)
                 .await
-                .map_err(|e| ProviderError::Api {
-                    status: 500,
-                    message: e.to_string(),
-                })?;
+                .map_err(map_client_error)?;
 
             let msg_resp = from_google_resp(&google_resp, &msg_req.model);
-
-            self.convert_response(msg_resp, &request.model)
+            let grounding = google_resp
+                .first_candidate()
+                .and_then(|c| c.grounding_metadata.as_ref())
+                .map(|m| m.to_grounding());
+
+            let mut response = self.convert_response(msg_resp, &request.model)?;
+            if let Some(choice) = response.choices.first_mut() {
+                choice.message.grounding = grounding;
+            }
+            Ok(response)
         })
     }
 
@@ -362,16 +509,17 @@ impl LlmProvider for GeminiProvider {
             }
 
             let msg_req = self.convert_request(&request)?;
-            let google_req = to_google_req(&msg_req);
+            let mut google_req = to_google_req(&msg_req);
+            if request.google_search {
+                add_google_search(&mut google_req);
+            }
+            let google_req = apply_extra_body(google_req, request.extra_body.as_ref())?;
 
             let stream = self
                 .client
                 .request_stream(&msg_req.model, google_req)
                 .await
-                .map_err(|e| ProviderError::Api {
-                    status: 500,
-                    message: e.to_string(),
-                })?;
+                .map_err(map_client_error)?;
 
             // Map the stream
             let mapped_stream = stream.map(move |result| {
@@ -381,6 +529,7 @@ impl LlmProvider for GeminiProvider {
 
                         let mut delta_content = None;
                         let mut finish_reason = None;
+                        let mut grounding = None;
                         let delta_tool_calls = None;
 
                         match event {
@@ -394,6 +543,7 @@ impl LlmProvider for GeminiProvider {
                                 if let Some(reason) = delta.stop_reason {
                                     finish_reason = Some(format!("{:?}", reason));
                                 }
+                                grounding = delta.grounding;
                             }
                             // TODO: Handle tool use streaming
                             _ => {}
@@ -411,6 +561,7 @@ impl LlmProvider for GeminiProvider {
                                 model: request.model.clone(),
                                 choices: vec![],
                                 usage: None,
+                                tool_calls_emulated: false,
                             });
                         }
 
@@ -426,10 +577,12 @@ impl LlmProvider for GeminiProvider {
                                     content: delta_content,
                                     tool_calls: delta_tool_calls,
                                     reasoning_content: None,
+                                    grounding,
                                 },
                                 finish_reason,
                             }],
                             usage: None,
+                            tool_calls_emulated: false,
                         })
                     }
                     Err(e) => Err(ProviderError::Stream(e.to_string())),
@@ -447,12 +600,116 @@ impl LlmProvider for GeminiProvider {
         Box::pin(async move { self.client.is_authenticated().await.unwrap_or(false) })
     }
 
+    fn diagnose(
+        &self,
+    ) -> Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + '_>> {
+        Box::pin(async move {
+            self.client
+                .get_access_token()
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+
     fn pricing(&self) -> Vec<ModelPricing> {
         crate::providers::cost::CostCalculator::all()
             .into_iter()
             .filter(|p| p.provider == "gemini")
             .collect()
     }
+
+    fn supports_image_model(&self, model: &str) -> bool {
+        IMAGE_MODELS.contains(&model)
+    }
+
+    fn generate_images(
+        &self,
+        request: &ImageGenerationRequest,
+    ) -> Pin<
+        Box<
+            dyn std::future::Future<Output = Result<ImageGenerationResponse, ProviderError>>
+                + Send
+                + '_,
+        >,
+    > {
+        let request = request.clone();
+        Box::pin(async move {
+            // Image models return one image per call, so `n` is fanned out.
+            let mut data = Vec::new();
+            for _ in 0..request.count() {
+                let google_resp = self
+                    .client
+                    .request(&request.model, image_request(&request))
+                    .await
+                    .map_err(|e| ProviderError::Api {
+                        status: 500,
+                        message: e.to_string(),
+                    })?;
+                data.extend(images_from_response(&google_resp));
+            }
+
+            if data.is_empty() {
+                return Err(ProviderError::Other(format!(
+                    "{} returned no images",
+                    request.model
+                )));
+            }
+
+            Ok(ImageGenerationResponse {
+                created: chrono::Utc::now().timestamp(),
+                data,
+            })
+        })
+    }
+
+    fn supports_gemini_native(&self, model: &str) -> bool {
+        self.supports_model(model) || self.supports_image_model(model)
+    }
+
+    fn generate_content(
+        &self,
+        model: &str,
+        request: &serde_json::Value,
+    ) -> Pin<
+        Box<dyn std::future::Future<Output = Result<serde_json::Value, ProviderError>> + Send + '_>,
+    > {
+        let model = model.to_string();
+        let request = native_request(request);
+        Box::pin(async move {
+            self.client
+                .request_native(&model, request?)
+                .await
+                .map_err(map_client_error)
+        })
+    }
+
+    fn stream_generate_content(
+        &self,
+        model: &str,
+        request: &serde_json::Value,
+    ) -> Pin<Box<dyn std::future::Future<Output = Result<NativeStream, ProviderError>> + Send + '_>>
+    {
+        let model = model.to_string();
+        let request = native_request(request);
+        Box::pin(async move {
+            let stream = self
+                .client
+                .request_native_stream(&model, request?)
+                .await
+                .map_err(map_client_error)?;
+            Ok(
+                Box::pin(stream.map(|r| r.map_err(|e| ProviderError::Stream(e.to_string()))))
+                    as NativeStream,
+            )
+        })
+    }
+}
+
+/// Parse a native Gemini API request body.
+fn native_request(request: &serde_json::Value) -> Result<GoogleRequest, ProviderError> {
+    serde_json::from_value(request.clone())
+        .map_err(|e| ProviderError::Other(format!("Invalid Gemini request: {e}")))
 }
 
 #[cfg(test)]
@@ -470,6 +727,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_aspect_ratio_for_size() {
+        assert_eq!(aspect_ratio_for_size("1024x1024"), Some("1:1"));
+        assert_eq!(aspect_ratio_for_size("1792x1024"), Some("16:9"));
+        assert_eq!(aspect_ratio_for_size("1024x1536"), Some("2:3"));
+        assert_eq!(aspect_ratio_for_size("big"), None);
+    }
+
+    #[test]
+    fn test_image_request_and_response() {
+        let request = ImageGenerationRequest {
+            model: "gemini-2.5-flash-image".to_string(),
+            prompt: "a red fox".to_string(),
+            n: None,
+            size: Some("1792x1024".to_string()),
+            quality: None,
+            response_format: None,
+        };
+        let json = serde_json::to_value(image_request(&request)).unwrap();
+        assert_eq!(json["contents"][0]["parts"][0]["text"], "a red fox");
+        assert_eq!(json["generationConfig"]["responseModalities"][0], "IMAGE");
+        assert_eq!(
+            json["generationConfig"]["imageConfig"]["aspectRatio"],
+            "16:9"
+        );
+
+        let response: GoogleResponse = serde_json::from_value(serde_json::json!({
+            "candidates": [{"content": {"role": "model", "parts": [
+                {"text": "Here you go"},
+                {"inlineData": {"mimeType": "image/png", "data": "iVBOR"}},
+            ]}}],
+        }))
+        .unwrap();
+        let images = images_from_response(&response);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].b64_json.as_deref(), Some("iVBOR"));
+
+        let provider = GeminiProvider::new(Arc::new(MockTokenProvider));
+        assert!(provider.supports_image_model("gemini-2.5-flash-image"));
+        assert!(!provider.supports_image_model("gemini-2.5-flash"));
+    }
+
     #[test]
     fn test_convert_request() {
         let provider = GeminiProvider::new(Arc::new(MockTokenProvider));
@@ -495,6 +794,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let msg_req = provider.convert_request(&req).unwrap();
@@ -504,4 +806,33 @@ mod tests {
         assert_eq!(msg_req.messages.len(), 1);
         assert_eq!(msg_req.temperature, Some(0.5));
     }
+
+    #[test]
+    fn test_add_google_search() {
+        let mut request = GoogleRequest::new();
+        add_google_search(&mut request);
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["tools"], serde_json::json!([{"googleSearch": {}}]));
+
+        let mut request = GoogleRequest::new();
+        request.tools = Some(vec![GoogleTool::new(vec![])]);
+        add_google_search(&mut request);
+        assert_eq!(request.tools.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_apply_extra_body() {
+        let extra = serde_json::json!({
+            "safetySettings": [{"category": "HARM_CATEGORY_HATE_SPEECH", "threshold": "BLOCK_NONE"}],
+            "sessionId": "s1",
+            "claude": {"metadata": {"user_id": "u1"}},
+        });
+        let request = apply_extra_body(GoogleRequest::new(), extra.as_object()).unwrap();
+        assert_eq!(request.session_id.as_deref(), Some("s1"));
+        assert!(request.extra.contains_key("safetySettings"));
+        assert!(!request.extra.contains_key("metadata"));
+
+        let bad = serde_json::json!({"contents": "not a list"});
+        assert!(apply_extra_body(GoogleRequest::new(), bad.as_object()).is_err());
+    }
 }
@@ -14,6 +14,11 @@
 //! enabling detection of cross-model conversations where signature compatibility
 //! must be validated.
 //!
+//! Once a database is attached with [`SignatureCache::attach_database`],
+//! signatures are also written to the `thinking_signatures` table and looked
+//! up there on a miss, so conversations survive restarts and can continue on
+//! another gaud instance sharing the database.
+//!
 //! ## Usage
 //!
 //! ```rust,ignore
@@ -29,11 +34,15 @@
 //! ```
 
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::{OnceLock, RwLock};
 use std::time::{Duration, Instant};
 
+use rusqlite::{OptionalExtension, params};
+use tracing::warn;
+
+use crate::db::Database;
 use crate::providers::gemini::constants::{
-    GEMINI_SKIP_SIGNATURE, MIN_SIGNATURE_LENGTH, ModelFamily, SIGNATURE_CACHE_TTL,
+    GEMINI_SKIP_SIGNATURE, MIN_SIGNATURE_LENGTH, ModelFamily, SIGNATURE_CACHE_TTL, get_model_family,
 };
 
 /// Sentinel value used when signature cannot be recovered.
@@ -45,6 +54,11 @@ pub const SKIP_SIGNATURE_SENTINEL: &str = GEMINI_SKIP_SIGNATURE;
 /// Maximum number of entries in each cache before eviction.
 const MAX_CACHE_ENTRIES: usize = 1000;
 
+/// `kind` of tool signatures in the `thinking_signatures` table.
+const TOOL_KIND: &str = "tool";
+/// `kind` of thinking signatures in the `thinking_signatures` table.
+const THINKING_KIND: &str = "thinking";
+
 /// Entry in the signature cache with timestamp for TTL expiry.
 #[derive(Debug, Clone)]
 struct CacheEntry {
@@ -66,6 +80,21 @@ impl CacheEntry {
         }
     }
 
+    /// Create an entry loaded from the database that expires in `remaining`.
+    fn loaded(
+        signature: String,
+        model_family: ModelFamily,
+        ttl: Duration,
+        remaining: Duration,
+    ) -> Self {
+        let age = ttl.saturating_sub(remaining);
+        Self {
+            signature,
+            model_family,
+            created_at: Instant::now().checked_sub(age).unwrap_or_else(Instant::now),
+        }
+    }
+
     /// Check if this entry has expired.
     fn is_expired(&self, ttl: Duration) -> bool {
         self.created_at.elapsed() > ttl
@@ -88,6 +117,8 @@ pub struct SignatureCache {
     thinking_signatures: RwLock<HashMap<String, CacheEntry>>,
     /// Time-to-live for cache entries.
     ttl: Duration,
+    /// Database signatures are persisted to, once attached.
+    db: OnceLock<Database>,
 }
 
 impl SignatureCache {
@@ -97,9 +128,24 @@ impl SignatureCache {
             tool_signatures: RwLock::new(HashMap::new()),
             thinking_signatures: RwLock::new(HashMap::new()),
             ttl,
+            db: OnceLock::new(),
         }
     }
 
+    /// Persist signatures to `db` from now on, and fall back to it on
+    /// misses. Expired rows are purged. Only the first call has an effect.
+    pub fn attach_database(&self, db: Database) {
+        if let Err(e) = db.with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM thinking_signatures WHERE expires_at <= ?1",
+                [now_millis()],
+            )
+        }) {
+            warn!(error = %e, "Failed to purge expired thinking signatures");
+        }
+        let _ = self.db.set(db);
+    }
+
     /// Create a new signature cache with default TTL (2 hours).
     pub fn with_default_ttl() -> Self {
         Self::new(SIGNATURE_CACHE_TTL)
@@ -137,7 +183,12 @@ impl SignatureCache {
             self.evict_oldest(&mut cache, MAX_CACHE_ENTRIES / 4);
         }
 
-        cache.insert(tool_use_id, CacheEntry::new(signature, model_family));
+        cache.insert(
+            tool_use_id.clone(),
+            CacheEntry::new(signature.clone(), model_family),
+        );
+        drop(cache);
+        self.persist(TOOL_KIND, &tool_use_id, &signature, model_family);
     }
 
     /// Get a cached tool signature.
@@ -148,14 +199,14 @@ impl SignatureCache {
             return None;
         }
 
-        let cache = self.tool_signatures.read().unwrap();
-        cache.get(tool_use_id).and_then(|entry| {
-            if entry.is_expired(self.ttl) {
-                None
-            } else {
-                Some(entry.signature.clone())
-            }
-        })
+        let cached = self
+            .tool_signatures
+            .read()
+            .unwrap()
+            .get(tool_use_id)
+            .filter(|entry| !entry.is_expired(self.ttl))
+            .map(|entry| entry.signature.clone());
+        cached.or_else(|| self.load(TOOL_KIND, tool_use_id, &self.tool_signatures))
     }
 
     /// Get a cached tool signature or the sentinel value.
@@ -206,7 +257,12 @@ impl SignatureCache {
             self.evict_oldest(&mut cache, MAX_CACHE_ENTRIES / 4);
         }
 
-        cache.insert(key, CacheEntry::new(signature, model_family));
+        cache.insert(
+            key.clone(),
+            CacheEntry::new(signature.clone(), model_family),
+        );
+        drop(cache);
+        self.persist(THINKING_KIND, &key, &signature, model_family);
     }
 
     /// Get a cached thinking signature.
@@ -218,14 +274,14 @@ impl SignatureCache {
             return None;
         }
 
-        let cache = self.thinking_signatures.read().unwrap();
-        cache.get(&key).and_then(|entry| {
-            if entry.is_expired(self.ttl) {
-                None
-            } else {
-                Some(entry.signature.clone())
-            }
-        })
+        let cached = self
+            .thinking_signatures
+            .read()
+            .unwrap()
+            .get(&key)
+            .filter(|entry| !entry.is_expired(self.ttl))
+            .map(|entry| entry.signature.clone());
+        cached.or_else(|| self.load(THINKING_KIND, &key, &self.thinking_signatures))
     }
 
     /// Get the model family for a cached thinking signature.
@@ -244,7 +300,25 @@ impl SignatureCache {
                 return Some(entry.model_family);
             }
         }
-        None
+        drop(cache);
+
+        let db = self.db.get()?;
+        let family = db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT model_family FROM thinking_signatures
+                 WHERE kind = ?1 AND signature = ?2 AND expires_at > ?3 LIMIT 1",
+                params![THINKING_KIND, signature, now_millis()],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+        });
+        match family {
+            Ok(family) => family.map(|f| get_model_family(&f)),
+            Err(e) => {
+                warn!(error = %e, "Failed to look up thinking signature family");
+                None
+            }
+        }
     }
 
     /// Check if a signature is compatible with the target model family.
@@ -276,12 +350,14 @@ impl SignatureCache {
     pub fn clear_tool_signatures(&self) {
         let mut cache = self.tool_signatures.write().unwrap();
         cache.clear();
+        self.clear_persisted(TOOL_KIND);
     }
 
     /// Clear all entries from the thinking signature cache.
     pub fn clear_thinking_signatures(&self) {
         let mut cache = self.thinking_signatures.write().unwrap();
         cache.clear();
+        self.clear_persisted(THINKING_KIND);
     }
 
     /// Clear all caches.
@@ -308,6 +384,88 @@ impl SignatureCache {
         chars.trim().to_string()
     }
 
+    /// Write a signature to the attached database, if any.
+    fn persist(&self, kind: &str, key: &str, signature: &str, model_family: ModelFamily) {
+        let Some(db) = self.db.get() else {
+            return;
+        };
+        let now = now_millis();
+        let expires_at = now.saturating_add(self.ttl.as_millis() as i64);
+        let result = db.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO thinking_signatures
+                 (kind, key, signature, model_family, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![kind, key, signature, model_family.to_string(), expires_at],
+            )?;
+            conn.execute(
+                "DELETE FROM thinking_signatures WHERE expires_at <= ?1",
+                [now],
+            )
+        });
+        if let Err(e) = result {
+            warn!(error = %e, kind, "Failed to persist thinking signature");
+        }
+    }
+
+    /// Look up a signature missing from memory in the attached database and
+    /// keep it in `cache` for its remaining lifetime.
+    fn load(
+        &self,
+        kind: &str,
+        key: &str,
+        cache: &RwLock<HashMap<String, CacheEntry>>,
+    ) -> Option<String> {
+        let db = self.db.get()?;
+        let now = now_millis();
+        let row = db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT signature, model_family, expires_at FROM thinking_signatures
+                 WHERE kind = ?1 AND key = ?2 AND expires_at > ?3",
+                params![kind, key, now],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )
+            .optional()
+        });
+        let (signature, family, expires_at) = match row {
+            Ok(row) => row?,
+            Err(e) => {
+                warn!(error = %e, kind, "Failed to load thinking signature");
+                return None;
+            }
+        };
+
+        let remaining = Duration::from_millis(expires_at.saturating_sub(now) as u64);
+        cache.write().unwrap().insert(
+            key.to_string(),
+            CacheEntry::loaded(
+                signature.clone(),
+                get_model_family(&family),
+                self.ttl,
+                remaining,
+            ),
+        );
+        Some(signature)
+    }
+
+    /// Delete the persisted signatures of one kind.
+    fn clear_persisted(&self, kind: &str) {
+        let Some(db) = self.db.get() else {
+            return;
+        };
+        if let Err(e) = db.with_conn(|conn| {
+            conn.execute("DELETE FROM thinking_signatures WHERE kind = ?1", [kind])
+        }) {
+            warn!(error = %e, kind, "Failed to clear thinking signatures");
+        }
+    }
+
     /// Evict expired entries from a cache.
     fn evict_expired(&self, cache: &mut HashMap<String, CacheEntry>) {
         cache.retain(|_, entry| !entry.is_expired(self.ttl));
@@ -338,6 +496,11 @@ impl Default for SignatureCache {
     }
 }
 
+/// Current time in Unix milliseconds, the unit of `expires_at`.
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
 /// Global signature cache instance.
 ///
 /// This is a lazily-initialized global cache used by the conversion functions.
@@ -433,6 +596,61 @@ mod tests {
         assert!(cache.is_signature_compatible(&unknown_sig, ModelFamily::Claude));
     }
 
+    #[test]
+    fn test_signatures_survive_restart() {
+        let db = Database::open_in_memory().unwrap();
+        let thinking_sig = "t".repeat(100);
+
+        let cache = SignatureCache::with_default_ttl();
+        cache.attach_database(db.clone());
+        cache.store_tool_signature("toolu_persist", "sig_abc", ModelFamily::Gemini);
+        cache.store_thinking_signature("Let me think", thinking_sig.clone(), ModelFamily::Claude);
+
+        // A fresh cache (another process) finds them in the database.
+        let restarted = SignatureCache::with_default_ttl();
+        restarted.attach_database(db.clone());
+        assert_eq!(
+            restarted.get_thinking_signature_family(&thinking_sig),
+            Some(ModelFamily::Claude)
+        );
+        assert_eq!(
+            restarted.get_tool_signature("toolu_persist"),
+            Some("sig_abc".to_string())
+        );
+        assert_eq!(
+            restarted.get_thinking_signature("Let me think"),
+            Some(thinking_sig)
+        );
+        assert_eq!(restarted.tool_signature_count(), 1);
+
+        restarted.clear_all();
+        let empty = SignatureCache::with_default_ttl();
+        empty.attach_database(db);
+        assert!(empty.get_tool_signature("toolu_persist").is_none());
+    }
+
+    #[test]
+    fn test_persisted_signatures_expire() {
+        let db = Database::open_in_memory().unwrap();
+        let cache = SignatureCache::new(Duration::from_millis(50));
+        cache.attach_database(db.clone());
+        cache.store_tool_signature("toolu_ttl", "sig_ttl", ModelFamily::Gemini);
+
+        thread::sleep(Duration::from_millis(100));
+
+        let restarted = SignatureCache::new(Duration::from_millis(50));
+        restarted.attach_database(db.clone());
+        assert!(restarted.get_tool_signature("toolu_ttl").is_none());
+        let rows: i64 = db
+            .with_conn(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM thinking_signatures", [], |row| {
+                    row.get(0)
+                })
+            })
+            .unwrap();
+        assert_eq!(rows, 0);
+    }
+
     #[test]
     fn test_ttl_expiry() {
         // Use a very short TTL for testing
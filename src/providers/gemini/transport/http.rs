@@ -12,6 +12,7 @@ use reqwest::{Client, Response, StatusCode};
 use serde::Serialize;
 use tracing::{debug, instrument, warn};
 
+use crate::net::HttpClientFactory;
 use crate::providers::gemini::constants::{
     CLIENT_METADATA, CLOUDCODE_ENDPOINT_FALLBACKS, CONNECT_TIMEOUT, GOOG_API_CLIENT, ModelFamily,
     REQUEST_TIMEOUT, USER_AGENT, get_model_family, is_thinking_model,
@@ -259,6 +260,7 @@ pub struct HttpClientBuilder {
     connect_timeout: Duration,
     request_timeout: Duration,
     base_url: Option<String>,
+    factory: HttpClientFactory,
 }
 
 impl HttpClientBuilder {
@@ -285,9 +287,18 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Start from the shared client settings (proxy, pool and HTTP/2
+    /// tuning).
+    pub fn factory(mut self, factory: HttpClientFactory) -> Self {
+        self.factory = factory;
+        self
+    }
+
     /// Build the HTTP client.
     pub fn build(self) -> HttpClient {
-        let client = Client::builder()
+        let client = self
+            .factory
+            .builder("gemini")
             .connect_timeout(self.connect_timeout)
             .timeout(self.request_timeout)
             .build()
@@ -306,6 +317,7 @@ impl Default for HttpClientBuilder {
             connect_timeout: CONNECT_TIMEOUT,
             request_timeout: REQUEST_TIMEOUT,
             base_url: None,
+            factory: HttpClientFactory::default(),
         }
     }
 }
@@ -29,20 +29,24 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use bytes::Bytes;
+use futures::StreamExt;
 use futures::stream::Stream;
 
 use pin_project_lite::pin_project;
 use serde::Deserialize;
+use serde_json::Value;
 use tracing::{debug, warn};
 
 use crate::providers::gemini::constants::{MIN_SIGNATURE_LENGTH, ModelFamily, get_model_family};
 use crate::providers::gemini::error::{Error, Result};
 use crate::providers::gemini::models::content::ContentBlock;
+use crate::providers::gemini::models::google::{GroundingMetadata, unwrap_response};
 use crate::providers::gemini::models::response::{StopReason, Usage};
 use crate::providers::gemini::models::stream::{
     ContentDelta, MessageDelta, PartialMessage, StreamError, StreamEvent,
 };
 use crate::providers::gemini::thinking::GLOBAL_SIGNATURE_CACHE;
+use crate::providers::types::Grounding;
 
 pin_project! {
     /// SSE stream parser that converts Cloud Code responses to Anthropic events.
@@ -163,6 +167,8 @@ struct StreamState {
     cache_read_tokens: u32,
     /// Stop reason from the response.
     stop_reason: Option<StopReason>,
+    /// Search grounding, sent with the final chunk.
+    grounding: Option<Grounding>,
     /// Model name for signature caching.
     model: String,
     /// Model family.
@@ -182,6 +188,7 @@ impl StreamState {
             output_tokens: 0,
             cache_read_tokens: 0,
             stop_reason: None,
+            grounding: None,
             model,
             model_family,
         }
@@ -256,6 +263,15 @@ fn process_sse_data(data: SseData, state: &mut StreamState) -> Result<Vec<Stream
         .map(|c| c.parts.as_slice())
         .unwrap_or(&[]);
 
+    if let Some(metadata) = inner
+        .candidates
+        .as_ref()
+        .and_then(|c| c.first())
+        .and_then(|c| c.grounding_metadata.as_ref())
+    {
+        state.grounding = Some(metadata.to_grounding());
+    }
+
     // Check finish reason
     if let Some(finish_reason) = inner
         .candidates
@@ -429,10 +445,9 @@ fn finalize_stream(state: &mut StreamState) -> Result<Vec<StreamEvent>> {
             cache_creation_input_tokens: None,
         };
 
-        events.push(StreamEvent::message_delta(
-            MessageDelta::new(Some(state.stop_reason.unwrap_or(StopReason::EndTurn))),
-            Some(usage),
-        ));
+        let mut delta = MessageDelta::new(Some(state.stop_reason.unwrap_or(StopReason::EndTurn)));
+        delta.grounding = state.grounding.take();
+        events.push(StreamEvent::message_delta(delta, Some(usage)));
 
         events.push(StreamEvent::message_stop());
     } else {
@@ -483,6 +498,56 @@ fn generate_message_id() -> String {
     format!("msg_{}", uuid::Uuid::new_v4().simple())
 }
 
+// ============================================================================
+// Native Responses
+// ============================================================================
+
+/// Parse Cloud Code SSE into native Gemini API response chunks, for clients
+/// that speak the Gemini API themselves. The `response` envelope is removed
+/// and the chunks are otherwise passed through unchanged.
+pub fn native_stream<S>(byte_stream: S) -> Pin<Box<dyn Stream<Item = Result<Value>> + Send>>
+where
+    S: Stream<Item = std::result::Result<Bytes, reqwest::Error>> + Send + 'static,
+{
+    Box::pin(async_stream::stream! {
+        let mut byte_stream = Box::pin(byte_stream);
+        let mut buffer = String::new();
+        while let Some(chunk) = byte_stream.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    buffer.push_str(&String::from_utf8_lossy(&chunk));
+                    while let Some(newline_pos) = buffer.find('\n') {
+                        let line: String = buffer.drain(..=newline_pos).collect();
+                        if let Some(item) = parse_native_line(&line) {
+                            yield item;
+                        }
+                    }
+                }
+                Err(e) => {
+                    yield Err(Error::from(e));
+                    return;
+                }
+            }
+        }
+        if let Some(item) = parse_native_line(&buffer) {
+            yield item;
+        }
+    })
+}
+
+/// Parse one SSE line into a native response chunk.
+fn parse_native_line(line: &str) -> Option<Result<Value>> {
+    let data = line.trim().strip_prefix("data:")?.trim();
+    if data.is_empty() || data == "[DONE]" {
+        return None;
+    }
+    Some(
+        serde_json::from_str(data)
+            .map(unwrap_response)
+            .map_err(Error::from),
+    )
+}
+
 // ============================================================================
 // SSE Data Structures
 // ============================================================================
@@ -512,6 +577,9 @@ struct SseCandidate {
     /// Finish reason.
     #[serde(default)]
     finish_reason: Option<String>,
+    /// Search grounding.
+    #[serde(default)]
+    grounding_metadata: Option<GroundingMetadata>,
 }
 
 /// Content in SSE response.
@@ -671,6 +739,25 @@ mod tests {
         assert_eq!(state.cache_read_tokens, 20);
     }
 
+    #[test]
+    fn test_grounding_sent_with_message_delta() {
+        let mut state = StreamState::new("gemini-2.5-flash".to_string());
+
+        let json = r#"data: {"candidates":[{"content":{"parts":[{"text":"Hi"}]},"groundingMetadata":{"webSearchQueries":["hi"],"groundingChunks":[{"web":{"uri":"https://a.example","title":"a.example"}}]}}]}"#;
+        process_sse_line(json, &mut state).unwrap();
+
+        let events = finalize_stream(&mut state).unwrap();
+        let grounding = events
+            .iter()
+            .find_map(|e| match e {
+                StreamEvent::MessageDelta { delta, .. } => delta.grounding.clone(),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(grounding.search_queries, vec!["hi"]);
+        assert_eq!(grounding.sources[0].url, "https://a.example");
+    }
+
     #[test]
     fn test_process_sse_line_invalid_json() {
         let mut state = StreamState::new("claude-sonnet-4-5".to_string());
@@ -736,6 +823,30 @@ mod tests {
         assert!(state.current_thinking_signature.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_native_stream_unwraps_envelope() {
+        let body = concat!(
+            "data: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hel\"}]}}]}}\n\n",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"lo\"}]}}],",
+            "\"usageMetadata\":{\"promptTokenCount\":3}}\n\n",
+            "data: [DONE]\n",
+        );
+        // Split mid-line to exercise buffering across chunks.
+        let (a, b) = body.split_at(40);
+        let bytes = futures::stream::iter(vec![
+            Ok::<_, reqwest::Error>(Bytes::from(a)),
+            Ok(Bytes::from(b)),
+        ]);
+
+        let chunks: Vec<Value> = native_stream(bytes).map(|r| r.unwrap()).collect().await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0]["candidates"][0]["content"]["parts"][0]["text"],
+            "Hel"
+        );
+        assert_eq!(chunks[1]["usageMetadata"]["promptTokenCount"], 3);
+    }
+
     #[test]
     fn test_emit_message_start() {
         let state = StreamState::new("claude-sonnet-4-5".to_string());
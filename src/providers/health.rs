@@ -38,6 +38,13 @@ impl std::fmt::Display for CircuitState {
     }
 }
 
+/// Outcome of the last active health check of a provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HealthProbe {
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub healthy: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Configuration
 // ---------------------------------------------------------------------------
@@ -8,6 +8,7 @@ use tracing::{debug, info, warn};
 use super::models::*;
 use super::stores::{CredentialStore, EnvStore, JsonFileStore, SqliteStore};
 use super::strategies::{AuthStrategy, AwsSsoOidcStrategy, KiroDesktopStrategy};
+use crate::net::HttpClientFactory;
 use crate::providers::{ProviderError, TokenService};
 
 #[async_trait::async_trait]
@@ -18,6 +19,14 @@ pub trait KiroTokenProvider: Send + Sync {
     async fn force_refresh(&self) -> Result<(), ProviderError>;
 }
 
+fn auth_http_client(http: &HttpClientFactory) -> reqwest::Client {
+    http.builder("kiro")
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .unwrap_or_default()
+}
+
 pub struct KiroAuthManager {
     token: Arc<RwLock<Option<KiroTokenInfo>>>,
     http: reqwest::Client,
@@ -28,11 +37,7 @@ pub struct KiroAuthManager {
 
 impl KiroAuthManager {
     pub fn new(fingerprint: String, region: String) -> Self {
-        let http = reqwest::Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .timeout(Duration::from_secs(30))
-            .build()
-            .unwrap_or_default();
+        let http = auth_http_client(&HttpClientFactory::default());
 
         let strategies: Vec<Box<dyn AuthStrategy>> = vec![
             Box::new(KiroDesktopStrategy::new(fingerprint.clone())),
@@ -53,6 +58,12 @@ impl KiroAuthManager {
         }
     }
 
+    /// Use the shared client settings (proxy, pool and HTTP/2 tuning).
+    pub fn with_http(mut self, http: &HttpClientFactory) -> Self {
+        self.http = auth_http_client(http);
+        self
+    }
+
     pub async fn add_store(&self, store: Box<dyn CredentialStore>) {
         let mut stores = self.stores.write().await;
         stores.push(store);
@@ -238,6 +249,27 @@ impl AutoDetectProvider {
         creds_file: Option<PathBuf>,
         db_path: Option<PathBuf>,
         sso_cache_dir: Option<PathBuf>,
+    ) -> Self {
+        Self::with_stores(manager, creds_file, db_path, sso_cache_dir, true).await
+    }
+
+    /// Like [`AutoDetectProvider::new`], but only reads the given sources and
+    /// never probes the default Kiro CLI or AWS SSO cache locations.
+    pub async fn explicit(
+        manager: Arc<KiroAuthManager>,
+        creds_file: Option<PathBuf>,
+        db_path: Option<PathBuf>,
+        sso_cache_dir: Option<PathBuf>,
+    ) -> Self {
+        Self::with_stores(manager, creds_file, db_path, sso_cache_dir, false).await
+    }
+
+    async fn with_stores(
+        manager: Arc<KiroAuthManager>,
+        creds_file: Option<PathBuf>,
+        db_path: Option<PathBuf>,
+        sso_cache_dir: Option<PathBuf>,
+        probe_defaults: bool,
     ) -> Self {
         // Configure manager with stores based on inputs
 
@@ -251,12 +283,14 @@ impl AutoDetectProvider {
             manager.add_store(Box::new(SqliteStore::new(p))).await;
         }
 
-        // Default SSO Cache
-        let sso_dir = sso_cache_dir.unwrap_or_else(|| {
-            home_dir()
+        // SSO cache, the default location only when probing
+        let sso_dir = match sso_cache_dir {
+            Some(dir) => dir,
+            None if probe_defaults => home_dir()
                 .map(|h| h.join(".aws").join("sso").join("cache"))
-                .unwrap_or_default()
-        });
+                .unwrap_or_default(),
+            None => PathBuf::new(),
+        };
         let json_paths = tokio::task::spawn_blocking(move || {
             let mut paths = Vec::new();
             if sso_dir.exists() {
@@ -293,8 +327,10 @@ impl AutoDetectProvider {
             }),
         ];
 
-        for p in db_paths.into_iter().flatten() {
-            manager.add_store(Box::new(SqliteStore::new(p))).await;
+        if probe_defaults {
+            for p in db_paths.into_iter().flatten() {
+                manager.add_store(Box::new(SqliteStore::new(p))).await;
+            }
         }
 
         Self { manager }
@@ -8,6 +8,7 @@ use tracing::warn;
 use uuid::Uuid;
 
 use super::auth::KiroTokenProvider;
+use crate::net::HttpClientFactory;
 use crate::providers::ProviderError;
 
 /// URL template for the Kiro API host.
@@ -25,6 +26,14 @@ const NODE_VERSION: &str = "22.21.1";
 /// Kiro IDE version emulated in the User-Agent.
 const IDE_VERSION: &str = "0.7.45";
 
+fn api_http_client(http: &HttpClientFactory) -> reqwest::Client {
+    http.builder("kiro")
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(60))
+        .build()
+        .unwrap_or_default()
+}
+
 pub struct KiroClient {
     http: reqwest::Client,
     auth: Arc<dyn KiroTokenProvider>,
@@ -40,14 +49,8 @@ impl KiroClient {
         profile_arn: Option<String>,
         fingerprint: String,
     ) -> Self {
-        let http = reqwest::Client::builder()
-            .redirect(reqwest::redirect::Policy::none())
-            .timeout(Duration::from_secs(60))
-            .build()
-            .unwrap_or_default();
-
         Self {
-            http,
+            http: api_http_client(&HttpClientFactory::default()),
             auth,
             region,
             profile_arn,
@@ -55,6 +58,12 @@ impl KiroClient {
         }
     }
 
+    /// Use the shared client settings (proxy, pool and HTTP/2 tuning).
+    pub fn with_http(mut self, http: &HttpClientFactory) -> Self {
+        self.http = api_http_client(http);
+        self
+    }
+
     pub fn region(&self) -> &str {
         &self.region
     }
@@ -224,7 +233,12 @@ impl KiroClient {
     }
 
     pub async fn health_check(&self) -> bool {
-        self.auth.get_token().await.is_ok()
+        self.check_auth().await.is_ok()
+    }
+
+    /// Get a token, refreshing it if needed, to check the credentials work.
+    pub async fn check_auth(&self) -> Result<(), ProviderError> {
+        self.auth.get_token().await.map(|_| ())
     }
 }
 
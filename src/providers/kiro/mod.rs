@@ -6,12 +6,16 @@ pub mod strategies;
 
 use futures::Stream;
 use futures::stream::StreamExt;
+use kiro_gateway::MessagesRequest;
+use kiro_gateway::convert::tokenizer::count_request_tokens;
 use serde_json::Value;
 use std::pin::Pin;
 use std::sync::Arc;
 use tracing::debug;
 
+use crate::observability;
 use crate::providers::pricing::ModelPricing;
+use crate::providers::tokens;
 use crate::providers::transform::kiro::KiroTransformer;
 use crate::providers::transformer::{ProviderResponseMeta, ProviderTransformer};
 use crate::providers::types::*;
@@ -21,6 +25,7 @@ pub use self::auth::{AutoDetectProvider, KiroAuthManager, KiroTokenProvider};
 pub use self::client::{KiroClient, machine_fingerprint};
 
 pub struct KiroProvider {
+    id: String,
     client: Arc<KiroClient>,
     transformer: KiroTransformer,
 }
@@ -28,15 +33,22 @@ pub struct KiroProvider {
 impl KiroProvider {
     pub fn new(client: KiroClient) -> Self {
         Self {
+            id: "kiro".to_string(),
             client: Arc::new(client),
             transformer: KiroTransformer::new(),
         }
     }
+
+    /// Register under another provider ID, e.g. `kiro:work` for a profile.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
 }
 
 impl LlmProvider for KiroProvider {
     fn id(&self) -> &str {
-        "kiro"
+        &self.id
     }
 
     fn name(&self) -> &str {
@@ -51,6 +63,17 @@ impl LlmProvider for KiroProvider {
         self.transformer.supports_model(model)
     }
 
+    /// Counted by kiro-gateway on the Messages request Kiro is sent, so the
+    /// estimate matches `KiroClient::count_tokens`. Requests it has no types
+    /// for (documents) fall back to [`tokens::estimate`].
+    fn count_tokens(&self, request: &ChatRequest) -> u64 {
+        self.transformer
+            .transform_request(request)
+            .ok()
+            .and_then(|body| serde_json::from_value::<MessagesRequest>(body).ok())
+            .map_or_else(|| tokens::estimate(request), |r| count_request_tokens(&r))
+    }
+
     fn chat(
         &self,
         request: &ChatRequest,
@@ -59,7 +82,9 @@ impl LlmProvider for KiroProvider {
         let request = request.clone();
         Box::pin(async move {
             let body: Value = self.transformer.transform_request(&request)?;
-            debug!(body = %body, "Kiro request body");
+            if observability::log_prompts() {
+                debug!(body = %observability::scrub_json(&body), "Kiro request body");
+            }
 
             let sse_body = self.client.send_request(&body).await?;
 
@@ -85,7 +110,7 @@ impl LlmProvider for KiroProvider {
 
             if let Some(chunk) = final_chunk {
                 let meta = ProviderResponseMeta {
-                    provider: "kiro".to_string(),
+                    provider: self.id.clone(),
                     model: request.model.clone(),
                     created: chrono::Utc::now().timestamp(),
                     ..Default::default()
@@ -105,11 +130,13 @@ impl LlmProvider for KiroProvider {
                                 content: c.delta.content,
                                 reasoning_content: c.delta.reasoning_content,
                                 tool_calls: c.delta.tool_calls,
+                                grounding: None,
                             },
                             finish_reason: c.finish_reason,
                         })
                         .collect(),
                     usage: chunk.usage.unwrap_or_default(),
+                    tool_calls_emulated: false,
                 })
             } else {
                 Err(ProviderError::ResponseParsing(
@@ -164,6 +191,12 @@ impl LlmProvider for KiroProvider {
         Box::pin(async move { self.client.health_check().await })
     }
 
+    fn diagnose(
+        &self,
+    ) -> Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send + '_>> {
+        Box::pin(async move { self.client.check_auth().await.map_err(|e| e.to_string()) })
+    }
+
     fn pricing(&self) -> Vec<ModelPricing> {
         crate::providers::cost::CostCalculator::all()
             .into_iter()
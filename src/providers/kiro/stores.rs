@@ -66,6 +66,9 @@ impl CredentialStore for JsonFileStore {
             if let Some(v) = data.get("region").and_then(|v| v.as_str()) {
                 token.region = v.to_string();
             }
+            if let Some(v) = data.get("ssoRegion").and_then(|v| v.as_str()) {
+                token.sso_region = Some(v.to_string());
+            }
             if let Some(v) = data.get("profileArn").and_then(|v| v.as_str()) {
                 token.profile_arn = Some(v.to_string());
             }
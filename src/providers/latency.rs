@@ -0,0 +1,309 @@
+//! Streaming latency histograms, exported for Prometheus.
+//!
+//! The router wraps every stream it opens in a [`TimedStream`], which
+//! records per provider and model how long the first token took to arrive
+//! (from the start of the upstream call) and the gaps between later chunks.
+//! Request duration alone hides both: a slow first token and a stuttering
+//! stream feel sluggish to a user even when the whole response is quick.
+//! `GET /admin/metrics` renders the histograms in the Prometheus text
+//! format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::Stream;
+
+use crate::providers::ProviderError;
+use crate::providers::types::ChatChunk;
+
+/// Upper bounds (in seconds) of the time-to-first-token buckets.
+pub const FIRST_TOKEN_BOUNDS_SECS: [f64; 12] = [
+    0.1, 0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 5.0, 10.0, 20.0, 60.0,
+];
+
+/// Upper bounds (in seconds) of the inter-chunk gap buckets.
+pub const CHUNK_GAP_BOUNDS_SECS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// A cumulative Prometheus histogram.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Histogram {
+    /// Observations per bucket of the bounds, plus one for slower ones.
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn observe(&mut self, bounds: &[f64], secs: f64) {
+        if self.counts.len() <= bounds.len() {
+            self.counts.resize(bounds.len() + 1, 0);
+        }
+        let bucket = bounds
+            .iter()
+            .position(|&bound| secs <= bound)
+            .unwrap_or(bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += secs;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Append the histogram's `_bucket`, `_sum` and `_count` samples.
+    fn render(&self, out: &mut String, name: &str, labels: &str, bounds: &[f64]) {
+        let mut cumulative = 0;
+        for (i, bound) in bounds.iter().enumerate() {
+            cumulative += self.counts.get(i).copied().unwrap_or(0);
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+/// A histogram metric exported for every provider and model.
+struct Metric {
+    name: &'static str,
+    help: &'static str,
+    bounds: &'static [f64],
+    histogram: fn(&Series) -> &Histogram,
+}
+
+const METRICS: [Metric; 2] = [
+    Metric {
+        name: "gaud_stream_first_token_seconds",
+        help: "Time from the start of an upstream streaming call to its first token.",
+        bounds: &FIRST_TOKEN_BOUNDS_SECS,
+        histogram: |s| &s.first_token,
+    },
+    Metric {
+        name: "gaud_stream_chunk_gap_seconds",
+        help: "Time between consecutive output chunks of a streaming response.",
+        bounds: &CHUNK_GAP_BOUNDS_SECS,
+        histogram: |s| &s.chunk_gap,
+    },
+];
+
+/// Histograms of one provider and model.
+#[derive(Debug, Clone, Default)]
+struct Series {
+    first_token: Histogram,
+    chunk_gap: Histogram,
+}
+
+/// Streaming latency per provider and model.
+#[derive(Debug, Default)]
+pub struct StreamLatency {
+    series: Mutex<BTreeMap<(String, String), Series>>,
+}
+
+impl StreamLatency {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_first_token(&self, provider: &str, model: &str, elapsed: Duration) {
+        self.with_series(provider, model, |s| {
+            s.first_token
+                .observe(&FIRST_TOKEN_BOUNDS_SECS, elapsed.as_secs_f64());
+        });
+    }
+
+    pub fn record_chunk_gap(&self, provider: &str, model: &str, gap: Duration) {
+        self.with_series(provider, model, |s| {
+            s.chunk_gap
+                .observe(&CHUNK_GAP_BOUNDS_SECS, gap.as_secs_f64());
+        });
+    }
+
+    /// The time-to-first-token histogram of a provider and model.
+    pub fn first_token(&self, provider: &str, model: &str) -> Option<Histogram> {
+        let series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        series
+            .get(&(provider.to_string(), model.to_string()))
+            .map(|s| s.first_token.clone())
+    }
+
+    /// The histograms in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        for metric in &METRICS {
+            let name = metric.name;
+            let _ = writeln!(out, "# HELP {name} {}", metric.help);
+            let _ = writeln!(out, "# TYPE {name} histogram");
+            for ((provider, model), s) in series.iter() {
+                let labels = format!(
+                    "provider=\"{}\",model=\"{}\"",
+                    escape_label(provider),
+                    escape_label(model)
+                );
+                (metric.histogram)(s).render(&mut out, name, &labels, metric.bounds);
+            }
+        }
+        out
+    }
+
+    fn with_series(&self, provider: &str, model: &str, f: impl FnOnce(&mut Series)) {
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        f(series
+            .entry((provider.to_string(), model.to_string()))
+            .or_default());
+    }
+}
+
+/// `value` escaped for a Prometheus label.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Whether a chunk carries output: text, reasoning or tool calls.
+fn has_output(chunk: &ChatChunk) -> bool {
+    chunk.choices.iter().any(|c| {
+        c.delta.content.as_deref().is_some_and(|s| !s.is_empty())
+            || c.delta
+                .reasoning_content
+                .as_deref()
+                .is_some_and(|s| !s.is_empty())
+            || c.delta.tool_calls.is_some()
+    })
+}
+
+/// A chat stream recording its latency into [`StreamLatency`]. Chunks
+/// without output, such as the opening role chunk, are not timed.
+pub struct TimedStream<S> {
+    inner: S,
+    latency: Arc<StreamLatency>,
+    provider: String,
+    model: String,
+    started: Instant,
+    last_output: Option<Instant>,
+}
+
+impl<S> TimedStream<S> {
+    /// Time `inner`, an upstream stream whose call started at `started`.
+    pub fn new(
+        inner: S,
+        latency: Arc<StreamLatency>,
+        provider: &str,
+        model: &str,
+        started: Instant,
+    ) -> Self {
+        Self {
+            inner,
+            latency,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            started,
+            last_output: None,
+        }
+    }
+}
+
+impl<S> Stream for TimedStream<S>
+where
+    S: Stream<Item = Result<ChatChunk, ProviderError>> + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let poll = Pin::new(&mut self.inner).poll_next(cx);
+        if let Poll::Ready(Some(Ok(chunk))) = &poll
+            && has_output(chunk)
+        {
+            let now = Instant::now();
+            match self.last_output {
+                None => {
+                    self.latency
+                        .record_first_token(&self.provider, &self.model, now - self.started)
+                }
+                Some(last) => {
+                    self.latency
+                        .record_chunk_gap(&self.provider, &self.model, now - last)
+                }
+            }
+            self.last_output = Some(now);
+        }
+        poll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn chunk(content: Option<&str>) -> ChatChunk {
+        serde_json::from_value(serde_json::json!({
+            "id": "c1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "m",
+            "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_histogram_render() {
+        let mut histogram = Histogram::default();
+        let bounds = [0.5, 1.0];
+        histogram.observe(&bounds, 0.25);
+        histogram.observe(&bounds, 0.75);
+        histogram.observe(&bounds, 3.0);
+        let mut out = String::new();
+        histogram.render(&mut out, "x", "provider=\"p\"", &bounds);
+        assert_eq!(
+            out,
+            "x_bucket{provider=\"p\",le=\"0.5\"} 1\n\
+             x_bucket{provider=\"p\",le=\"1\"} 2\n\
+             x_bucket{provider=\"p\",le=\"+Inf\"} 3\n\
+             x_sum{provider=\"p\"} 4\n\
+             x_count{provider=\"p\"} 3\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timed_stream() {
+        let latency = Arc::new(StreamLatency::new());
+        let chunks = vec![
+            Ok(chunk(None)),
+            Ok(chunk(Some("Hel"))),
+            Ok(chunk(Some("lo"))),
+            Ok(chunk(Some("!"))),
+        ];
+        let stream = TimedStream::new(
+            futures::stream::iter(chunks),
+            Arc::clone(&latency),
+            "claude",
+            "claude-sonnet-4",
+            Instant::now(),
+        );
+        assert_eq!(stream.collect::<Vec<_>>().await.len(), 4);
+
+        let first = latency.first_token("claude", "claude-sonnet-4").unwrap();
+        assert_eq!(first.count(), 1);
+        let text = latency.render();
+        assert!(text.contains("# TYPE gaud_stream_first_token_seconds histogram"));
+        assert!(text.contains(
+            "gaud_stream_chunk_gap_seconds_count{provider=\"claude\",model=\"claude-sonnet-4\"} 2"
+        ));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c"), "a\\\"b\\\\c");
+    }
+}
@@ -1,24 +1,36 @@
 //! LiteLLM Provider
 //!
 //! Proxies chat requests to a LiteLLM instance via its OpenAI-compatible API.
-//! Supports auto-discovery of available models and streaming responses.
+//! Supports auto-discovery of available models and streaming responses, and
+//! forwards image generation and audio (transcription/speech) requests.
+//! LiteLLM's own spend records can be pulled for cost reconciliation (see
+//! [`crate::budget::reconcile`]).
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 
+use chrono::NaiveDate;
 use futures::Stream;
 use futures::stream::StreamExt;
 use reqwest::Client;
 use tokio::sync::RwLock;
 use tracing::{debug, warn};
 
+use crate::net::HttpClientFactory;
 use crate::providers::pricing::ModelPricing;
+use crate::providers::ratelimit::{UpstreamRateLimit, UpstreamRateLimitState};
+use crate::providers::transform::util::{
+    detect_quota_exhausted, merge_extra_body, parse_rate_limit_headers,
+};
 use crate::providers::types::{
-    ChatChunk, ChatRequest, ChatResponse, Choice, ChunkChoice, Delta, ResponseMessage, Usage,
+    ChatChunk, ChatRequest, ChatResponse, Choice, ChunkChoice, Delta, ImageGenerationRequest,
+    ImageGenerationResponse, ResponseMessage, SpeechRequest, TranscriptionRequest,
+    TranscriptionResponse, Usage,
 };
-use crate::providers::{LlmProvider, ProviderError};
+use crate::providers::{LlmProvider, ProviderError, SpeechResponse};
 
 // ---------------------------------------------------------------------------
 // Configuration
@@ -39,6 +51,8 @@ pub struct LitellmConfig {
     pub models: Vec<String>,
     /// Request timeout for chat completions.
     pub timeout_secs: u64,
+    /// Shared client settings (proxy, pool and HTTP/2 tuning).
+    pub http: HttpClientFactory,
 }
 
 // ---------------------------------------------------------------------------
@@ -55,6 +69,30 @@ struct ModelEntry {
     id: String,
 }
 
+// ---------------------------------------------------------------------------
+// LiteLLM spend report (from GET /spend/logs with a date range)
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, serde::Deserialize)]
+struct SpendLogDay {
+    /// Start of the day, e.g. `2025-01-16T00:00:00`.
+    #[serde(rename = "startTime")]
+    start_time: String,
+    /// Spend per model in USD.
+    #[serde(default)]
+    models: HashMap<String, Option<f64>>,
+}
+
+/// Spend LiteLLM recorded for one model on one day (UTC).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyModelSpend {
+    /// Day as `YYYY-MM-DD`.
+    pub day: String,
+    pub model: String,
+    /// Spend in USD, as priced by LiteLLM.
+    pub spend: f64,
+}
+
 // ---------------------------------------------------------------------------
 // OpenAI-compatible response types for deserialization
 // ---------------------------------------------------------------------------
@@ -149,12 +187,15 @@ pub struct LitellmProvider {
     client: Client,
     /// Discovered models (populated by `discover()` or manually set).
     discovered_models: Arc<RwLock<Vec<String>>>,
+    rate_limits: UpstreamRateLimitState,
 }
 
 impl LitellmProvider {
     /// Create a new LiteLLM provider and optionally discover available models.
     pub async fn new(config: LitellmConfig) -> Result<Self, ProviderError> {
-        let client = Client::builder()
+        let client = config
+            .http
+            .builder("litellm")
             .user_agent(crate::config::GAUD_USER_AGENT)
             .timeout(Duration::from_secs(config.timeout_secs))
             .build()
@@ -166,6 +207,7 @@ impl LitellmProvider {
             config,
             client,
             discovered_models: Arc::new(RwLock::new(initial_models)),
+            rate_limits: UpstreamRateLimitState::default(),
         };
 
         if provider.config.discover_models {
@@ -226,6 +268,67 @@ impl LitellmProvider {
         Ok(())
     }
 
+    /// Fetch LiteLLM's spend per model and day for `start..=end` from
+    /// `GET /spend/logs`. Requires a key allowed to read spend data.
+    pub async fn daily_spend(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyModelSpend>, ProviderError> {
+        let url = format!("{}/spend/logs", self.config.url.trim_end_matches('/'));
+        let mut req = self.client.get(&url).query(&[
+            ("start_date", start.format("%Y-%m-%d").to_string()),
+            ("end_date", end.format("%Y-%m-%d").to_string()),
+        ]);
+        if let Some(ref key) = self.config.api_key {
+            req = req.bearer_auth(key);
+        }
+
+        let resp = req.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status().as_u16();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::Api {
+                status,
+                message: format!("Spend report failed: {body}"),
+            });
+        }
+
+        let days: Vec<SpendLogDay> = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::Other(format!("Failed to parse spend report: {e}")))?;
+
+        let mut spend = Vec::new();
+        for day in days {
+            let Some(date) = day.start_time.get(..10) else {
+                continue;
+            };
+            for (model, cost) in day.models {
+                spend.push(DailyModelSpend {
+                    day: date.to_string(),
+                    model,
+                    spend: cost.unwrap_or(0.0),
+                });
+            }
+        }
+        Ok(spend)
+    }
+
+    /// Remember the rate-limit headers LiteLLM passed through from upstream.
+    fn record_rate_limits(&self, resp: &reqwest::Response) {
+        let headers: Vec<(String, String)> = resp
+            .headers()
+            .iter()
+            .map(|(k, v)| (k.as_str().to_string(), v.to_str().unwrap_or("").to_string()))
+            .collect();
+        let (retry_after, rate_limit_headers) = parse_rate_limit_headers(&headers, "litellm");
+        self.rate_limits.record(
+            &rate_limit_headers,
+            retry_after.filter(|_| resp.status().as_u16() == 429),
+        );
+    }
+
     /// Build the request body for the LiteLLM proxy (OpenAI-compatible format).
     fn build_request_body(request: &ChatRequest) -> serde_json::Value {
         // Strip the `litellm:` prefix if present so LiteLLM receives the
@@ -260,9 +363,93 @@ impl LitellmProvider {
             body["tool_choice"] = tc.clone();
         }
 
+        merge_extra_body(&mut body, request.extra_body.as_ref(), "litellm");
+
+        body
+    }
+
+    /// Build the body for `POST /v1/images/generations`, stripping the
+    /// `litellm:` prefix like [`Self::build_request_body`].
+    fn build_image_request_body(request: &ImageGenerationRequest) -> serde_json::Value {
+        let mut body = serde_json::to_value(request).unwrap_or_default();
+        if let Some(model) = request.model.strip_prefix("litellm:") {
+            body["model"] = serde_json::json!(model);
+        }
+        if let Some(obj) = body.as_object_mut() {
+            obj.retain(|_, v| !v.is_null());
+        }
         body
     }
 
+    /// Build the multipart form for `POST /v1/audio/transcriptions`.
+    ///
+    /// Subtitle formats are requested as-is. Everything else is requested as
+    /// JSON (`verbose_json` for Whisper, which is the only way it reports the
+    /// audio duration) and trimmed to the client's format by the API layer.
+    fn build_transcription_form(
+        request: &TranscriptionRequest,
+    ) -> Result<reqwest::multipart::Form, ProviderError> {
+        let model = request
+            .model
+            .strip_prefix("litellm:")
+            .unwrap_or(&request.model);
+
+        let mut file = reqwest::multipart::Part::bytes(request.file.to_vec())
+            .file_name(request.filename.clone());
+        if let Some(ref content_type) = request.content_type {
+            file = file
+                .mime_str(content_type)
+                .map_err(|e| ProviderError::InvalidRequest(format!("Invalid file type: {e}")))?;
+        }
+
+        let response_format = if request.wants_subtitles() {
+            request.response_format.clone().unwrap_or_default()
+        } else if model.starts_with("whisper") {
+            "verbose_json".to_string()
+        } else {
+            "json".to_string()
+        };
+
+        let mut form = reqwest::multipart::Form::new()
+            .part("file", file)
+            .text("model", model.to_string())
+            .text("response_format", response_format);
+        if let Some(ref language) = request.language {
+            form = form.text("language", language.clone());
+        }
+        if let Some(ref prompt) = request.prompt {
+            form = form.text("prompt", prompt.clone());
+        }
+        if let Some(temperature) = request.temperature {
+            form = form.text("temperature", temperature.to_string());
+        }
+        Ok(form)
+    }
+
+    /// Parse a JSON transcription response.
+    ///
+    /// Newer OpenAI models report the duration as
+    /// `usage: {"type": "duration", "seconds": N}` instead of a top-level
+    /// `duration` field.
+    fn parse_transcription(
+        value: serde_json::Value,
+    ) -> Result<TranscriptionResponse, ProviderError> {
+        let usage_seconds = value
+            .get("usage")
+            .filter(|u| u.get("type").and_then(|t| t.as_str()) == Some("duration"))
+            .and_then(|u| u.get("seconds"))
+            .and_then(|s| s.as_f64());
+        let mut response: TranscriptionResponse = serde_json::from_value(value).map_err(|e| {
+            ProviderError::Other(format!(
+                "Failed to parse LiteLLM transcription response: {e}"
+            ))
+        })?;
+        if response.duration.is_none() {
+            response.duration = usage_seconds;
+        }
+        Ok(response)
+    }
+
     /// Convert an OAI response to our internal ChatResponse type.
     fn convert_response(oai: OaiResponse) -> ChatResponse {
         ChatResponse {
@@ -288,6 +475,7 @@ impl LitellmProvider {
                             tool_calls: msg.tool_calls.and_then(|tc| {
                                 serde_json::from_value(serde_json::Value::Array(tc)).ok()
                             }),
+                            grounding: None,
                         },
                         finish_reason: c.finish_reason,
                     }
@@ -303,6 +491,7 @@ impl LitellmProvider {
                     completion_tokens_details: None,
                 })
                 .unwrap_or_default(),
+            tool_calls_emulated: false,
         }
     }
 }
@@ -360,11 +549,15 @@ impl LlmProvider for LitellmProvider {
                 .send()
                 .await
                 .map_err(|e| ProviderError::Other(format!("LiteLLM request failed: {e}")))?;
+            self.record_rate_limits(&resp);
 
             let status = resp.status();
             if !status.is_success() {
                 let code = status.as_u16();
                 let body = resp.text().await.unwrap_or_default();
+                if let Some(quota_err) = detect_quota_exhausted(code, &body, "litellm") {
+                    return Err(quota_err);
+                }
                 return Err(ProviderError::Api {
                     status: code,
                     message: body,
@@ -411,11 +604,15 @@ impl LlmProvider for LitellmProvider {
                 .send()
                 .await
                 .map_err(|e| ProviderError::Other(format!("LiteLLM stream request failed: {e}")))?;
+            self.record_rate_limits(&resp);
 
             let status = resp.status();
             if !status.is_success() {
                 let code = status.as_u16();
                 let body = resp.text().await.unwrap_or_default();
+                if let Some(quota_err) = detect_quota_exhausted(code, &body, "litellm") {
+                    return Err(quota_err);
+                }
                 return Err(ProviderError::Api {
                     status: code,
                     message: body,
@@ -470,6 +667,7 @@ impl LlmProvider for LitellmProvider {
                                                                 )
                                                                 .ok()
                                                             }),
+                                                        grounding: None,
                                                     },
                                                     finish_reason: c.finish_reason,
                                                 }
@@ -481,7 +679,7 @@ impl LlmProvider for LitellmProvider {
                                             total_tokens: u.total_tokens,
                                             prompt_tokens_details: None,
                                             completion_tokens_details: None,
-                                        }),
+                                        }), tool_calls_emulated: false,
                                     }));
                                 }
                                 Err(e) => {
@@ -528,6 +726,158 @@ impl LlmProvider for LitellmProvider {
         // data here. Return an empty list.
         vec![]
     }
+
+    fn upstream_rate_limit(&self) -> Option<UpstreamRateLimit> {
+        self.rate_limits.get()
+    }
+
+    fn supports_image_model(&self, model: &str) -> bool {
+        // LiteLLM decides which upstream can serve the model.
+        self.supports_model(model)
+    }
+
+    fn generate_images(
+        &self,
+        request: &ImageGenerationRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ImageGenerationResponse, ProviderError>> + Send + '_>>
+    {
+        let body = Self::build_image_request_body(request);
+        Box::pin(async move {
+            let url = format!(
+                "{}/v1/images/generations",
+                self.config.url.trim_end_matches('/')
+            );
+
+            let mut req = self.client.post(&url).json(&body);
+            if let Some(ref key) = self.config.api_key {
+                req = req.bearer_auth(key);
+            }
+
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| ProviderError::Other(format!("LiteLLM image request failed: {e}")))?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                let code = status.as_u16();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::Api {
+                    status: code,
+                    message: body,
+                });
+            }
+
+            resp.json().await.map_err(|e| {
+                ProviderError::Other(format!("Failed to parse LiteLLM image response: {e}"))
+            })
+        })
+    }
+
+    fn supports_audio_model(&self, model: &str) -> bool {
+        self.supports_model(model)
+    }
+
+    fn transcribe(
+        &self,
+        request: &TranscriptionRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<TranscriptionResponse, ProviderError>> + Send + '_>>
+    {
+        let form = Self::build_transcription_form(request);
+        let subtitles = request.wants_subtitles();
+        Box::pin(async move {
+            let url = format!(
+                "{}/v1/audio/transcriptions",
+                self.config.url.trim_end_matches('/')
+            );
+
+            let mut req = self.client.post(&url).multipart(form?);
+            if let Some(ref key) = self.config.api_key {
+                req = req.bearer_auth(key);
+            }
+
+            let resp = req.send().await.map_err(|e| {
+                ProviderError::Other(format!("LiteLLM transcription request failed: {e}"))
+            })?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                let code = status.as_u16();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::Api {
+                    status: code,
+                    message: body,
+                });
+            }
+
+            if subtitles {
+                let text = resp.text().await.map_err(|e| {
+                    ProviderError::Other(format!("Failed to read LiteLLM transcription: {e}"))
+                })?;
+                return Ok(TranscriptionResponse {
+                    text,
+                    ..Default::default()
+                });
+            }
+
+            let value: serde_json::Value = resp.json().await.map_err(|e| {
+                ProviderError::Other(format!(
+                    "Failed to parse LiteLLM transcription response: {e}"
+                ))
+            })?;
+            Self::parse_transcription(value)
+        })
+    }
+
+    fn speech(
+        &self,
+        request: &SpeechRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<SpeechResponse, ProviderError>> + Send + '_>> {
+        let mut body = serde_json::to_value(request).unwrap_or_default();
+        if let Some(model) = request.model.strip_prefix("litellm:") {
+            body["model"] = serde_json::json!(model);
+        }
+        Box::pin(async move {
+            let url = format!("{}/v1/audio/speech", self.config.url.trim_end_matches('/'));
+
+            let mut req = self.client.post(&url).json(&body);
+            if let Some(ref key) = self.config.api_key {
+                req = req.bearer_auth(key);
+            }
+
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| ProviderError::Other(format!("LiteLLM speech request failed: {e}")))?;
+
+            let status = resp.status();
+            if !status.is_success() {
+                let code = status.as_u16();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(ProviderError::Api {
+                    status: code,
+                    message: body,
+                });
+            }
+
+            let content_type = resp
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("audio/mpeg")
+                .to_string();
+
+            let audio = resp.bytes_stream().map(|chunk| {
+                chunk
+                    .map_err(|e| ProviderError::Stream(format!("LiteLLM speech stream error: {e}")))
+            });
+
+            Ok(SpeechResponse {
+                content_type,
+                audio: Box::pin(audio),
+            })
+        })
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -537,6 +887,51 @@ impl LlmProvider for LitellmProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_build_image_request_body_strips_prefix() {
+        let request = ImageGenerationRequest {
+            model: "litellm:dall-e-3".to_string(),
+            prompt: "a lighthouse".to_string(),
+            n: Some(2),
+            size: Some("1024x1024".to_string()),
+            quality: None,
+            response_format: None,
+        };
+        let body = LitellmProvider::build_image_request_body(&request);
+        assert_eq!(body["model"], "dall-e-3");
+        assert_eq!(body["n"], 2);
+        assert!(body.get("quality").is_none());
+    }
+
+    #[test]
+    fn test_parse_transcription_duration() {
+        let verbose = serde_json::json!({
+            "text": "hello",
+            "language": "english",
+            "duration": 12.5,
+            "segments": []
+        });
+        let parsed = LitellmProvider::parse_transcription(verbose).unwrap();
+        assert_eq!(parsed.text, "hello");
+        assert_eq!(parsed.duration, Some(12.5));
+
+        let with_usage = serde_json::json!({
+            "text": "hi",
+            "usage": {"type": "duration", "seconds": 3}
+        });
+        let parsed = LitellmProvider::parse_transcription(with_usage).unwrap();
+        assert_eq!(parsed.duration, Some(3.0));
+
+        let with_tokens = serde_json::json!({
+            "text": "hi",
+            "usage": {"type": "tokens", "input_tokens": 10}
+        });
+        let parsed = LitellmProvider::parse_transcription(with_tokens).unwrap();
+        assert_eq!(parsed.duration, None);
+    }
 
     #[test]
     fn test_build_request_body_strips_prefix() {
@@ -551,6 +946,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = LitellmProvider::build_request_body(&request);
@@ -573,6 +971,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = LitellmProvider::build_request_body(&request);
@@ -622,6 +1023,7 @@ mod tests {
                 "litellm:claude-sonnet-4".to_string(),
             ],
             timeout_secs: 30,
+            http: HttpClientFactory::default(),
         };
 
         let provider = LitellmProvider {
@@ -631,6 +1033,7 @@ mod tests {
                 "litellm:gpt-4o".to_string(),
                 "litellm:claude-sonnet-4".to_string(),
             ])),
+            rate_limits: UpstreamRateLimitState::default(),
         };
 
         assert_eq!(provider.id(), "litellm");
@@ -641,6 +1044,53 @@ mod tests {
         assert!(!provider.supports_model("gpt-4o"));
     }
 
+    #[tokio::test]
+    async fn test_daily_spend() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/spend/logs"))
+            .and(query_param("start_date", "2025-01-15"))
+            .and(query_param("end_date", "2025-01-16"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {
+                    "startTime": "2025-01-15T00:00:00",
+                    "spend": 1.25,
+                    "models": {"gpt-4o": 1.0, "claude-sonnet-4": 0.25},
+                    "users": {}
+                },
+                {"startTime": "2025-01-16T00:00:00", "spend": 0.0, "models": {"gpt-4o": null}}
+            ])))
+            .mount(&server)
+            .await;
+
+        let provider = LitellmProvider::new(LitellmConfig {
+            url: server.uri(),
+            api_key: Some("sk-master".to_string()),
+            discover_models: false,
+            models: vec![],
+            timeout_secs: 30,
+            http: HttpClientFactory::default(),
+        })
+        .await
+        .unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 1, 16).unwrap();
+        let mut spend = provider.daily_spend(start, end).await.unwrap();
+        spend.sort_by(|a, b| (&a.day, &a.model).cmp(&(&b.day, &b.model)));
+        assert_eq!(spend.len(), 3);
+        assert_eq!(
+            spend[1],
+            DailyModelSpend {
+                day: "2025-01-15".to_string(),
+                model: "gpt-4o".to_string(),
+                spend: 1.0,
+            }
+        );
+        assert_eq!(spend[2].day, "2025-01-16");
+        assert_eq!(spend[2].spend, 0.0);
+    }
+
     #[test]
     fn test_litellm_prefix_matching() {
         // Any model with litellm: prefix should be supported.
@@ -650,12 +1100,14 @@ mod tests {
             discover_models: false,
             models: vec![],
             timeout_secs: 30,
+            http: HttpClientFactory::default(),
         };
 
         let provider = LitellmProvider {
             config,
             client: Client::new(),
             discovered_models: Arc::new(RwLock::new(vec![])),
+            rate_limits: UpstreamRateLimitState::default(),
         };
 
         assert!(provider.supports_model("litellm:gpt-4o"));
@@ -4,19 +4,33 @@
 //! routing, health tracking, cost calculation, and concrete provider
 //! implementations (Claude, Gemini, Copilot).
 
+pub mod capabilities;
 pub mod claude;
 pub mod copilot;
 pub mod cost;
+pub mod dry_run;
+pub mod factory;
+pub mod failures;
 pub mod gemini;
 pub mod health;
 pub mod kiro;
+pub mod latency;
 pub mod litellm;
+pub mod params;
 pub mod pricing;
+pub mod quota;
+pub mod ratelimit;
+pub mod reasoning;
+pub mod redact;
 pub mod retry;
 pub mod router;
+pub mod rules;
+pub mod tokens;
+pub mod tool_emulation;
 pub mod transform;
 pub mod transformer;
 pub mod types;
+pub mod vcr;
 
 use std::future::Future;
 use std::pin::Pin;
@@ -25,7 +39,11 @@ use std::time::Duration;
 use futures::Stream;
 
 use crate::providers::pricing::ModelPricing;
-use crate::providers::types::{ChatChunk, ChatRequest, ChatResponse};
+use crate::providers::ratelimit::UpstreamRateLimit;
+use crate::providers::types::{
+    ChatChunk, ChatRequest, ChatResponse, ImageGenerationRequest, ImageGenerationResponse,
+    SpeechRequest, TranscriptionRequest, TranscriptionResponse,
+};
 
 // Re-exports for convenience.
 pub use self::cost::CostCalculator;
@@ -75,6 +93,10 @@ pub enum ProviderError {
         retry_after: Option<Duration>,
     },
 
+    /// The provider account has used up its daily or monthly quota.
+    #[error("Quota exhausted for {provider}: {message}")]
+    QuotaExhausted { provider: String, message: String },
+
     #[error("Context window exceeded ({provider}): {message}")]
     ContextWindowExceeded {
         provider: String,
@@ -103,7 +125,7 @@ impl ProviderError {
     /// Extract the upstream HTTP status code, if this error maps to one.
     pub fn status_code(&self) -> Option<u16> {
         match self {
-            Self::RateLimited { .. } => Some(429),
+            Self::RateLimited { .. } | Self::QuotaExhausted { .. } => Some(429),
             Self::ContextWindowExceeded { .. } | Self::InvalidRequest(_) => Some(400),
             Self::Authentication { .. } | Self::NoToken { .. } => Some(401),
             Self::Api { status, .. } => Some(*status),
@@ -134,6 +156,16 @@ pub trait TokenService: Send + Sync {
     /// Return a valid access token for the given provider, refreshing if
     /// necessary.
     async fn get_token(&self, provider: &str) -> Result<String, ProviderError>;
+
+    /// Cloud Code project remembered with the provider's stored credentials,
+    /// so project discovery can be skipped after a restart.
+    async fn cached_project(&self, _provider: &str) -> Option<gemini::discovery::ProjectInfo> {
+        None
+    }
+
+    /// Remember a discovered Cloud Code project with the provider's stored
+    /// credentials.
+    async fn store_project(&self, _provider: &str, _project: &gemini::discovery::ProjectInfo) {}
 }
 
 // ---------------------------------------------------------------------------
@@ -182,8 +214,131 @@ pub trait LlmProvider: Send + Sync {
     /// Lightweight health check (e.g. can we reach the API, do we have tokens?).
     fn health_check(&self) -> Pin<Box<dyn Future<Output = bool> + Send + '_>>;
 
+    /// Health check that says what is wrong on failure, such as a refresh
+    /// token that was rejected. Used by the startup warm-up.
+    ///
+    /// Defaults to [`LlmProvider::health_check`] with a generic reason.
+    fn diagnose(&self) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + '_>> {
+        Box::pin(async move {
+            if self.health_check().await {
+                Ok(())
+            } else {
+                Err("health check failed".to_string())
+            }
+        })
+    }
+
     /// Pricing data for each model this provider supports.
     fn pricing(&self) -> Vec<ModelPricing>;
+
+    /// Rate-limit state reported by the upstream API on the most recent
+    /// response, if the provider tracks it.
+    fn upstream_rate_limit(&self) -> Option<UpstreamRateLimit> {
+        None
+    }
+
+    /// Premium requests one chat request to `model` consumes from the
+    /// upstream subscription (GitHub Copilot bills some models with a
+    /// multiplier). Defaults to `0.0` for providers billed by tokens only.
+    fn premium_requests(&self, _model: &str) -> f64 {
+        0.0
+    }
+
+    /// Estimated input tokens of `request` for this provider. Defaults to
+    /// [`tokens::estimate`] with the tokenizer of the model's family.
+    fn count_tokens(&self, request: &ChatRequest) -> u64 {
+        tokens::estimate(request)
+    }
+
+    /// Check whether this provider can generate images with `model`.
+    ///
+    /// Defaults to `false`; providers with image generation override this
+    /// together with [`LlmProvider::generate_images`].
+    fn supports_image_model(&self, _model: &str) -> bool {
+        false
+    }
+
+    /// Generate images from a text prompt.
+    fn generate_images(
+        &self,
+        request: &ImageGenerationRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<ImageGenerationResponse, ProviderError>> + Send + '_>>
+    {
+        let model = request.model.clone();
+        Box::pin(async move { Err(ProviderError::NoProvider(model)) })
+    }
+
+    /// Check whether this provider can transcribe or synthesize audio with
+    /// `model`.
+    ///
+    /// Defaults to `false`; providers with audio support override this
+    /// together with [`LlmProvider::transcribe`] and [`LlmProvider::speech`].
+    fn supports_audio_model(&self, _model: &str) -> bool {
+        false
+    }
+
+    /// Transcribe an uploaded audio file to text.
+    fn transcribe(
+        &self,
+        request: &TranscriptionRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<TranscriptionResponse, ProviderError>> + Send + '_>>
+    {
+        let model = request.model.clone();
+        Box::pin(async move { Err(ProviderError::NoProvider(model)) })
+    }
+
+    /// Synthesize speech, returning the audio as a byte stream.
+    fn speech(
+        &self,
+        request: &SpeechRequest,
+    ) -> Pin<Box<dyn Future<Output = Result<SpeechResponse, ProviderError>> + Send + '_>> {
+        let model = request.model.clone();
+        Box::pin(async move { Err(ProviderError::NoProvider(model)) })
+    }
+
+    /// Check whether this provider accepts native Gemini API requests for
+    /// `model`.
+    ///
+    /// Defaults to `false`; providers that speak the Gemini API override this
+    /// together with [`LlmProvider::generate_content`] and
+    /// [`LlmProvider::stream_generate_content`].
+    fn supports_gemini_native(&self, _model: &str) -> bool {
+        false
+    }
+
+    /// Gemini API `generateContent`: takes and returns the JSON bodies of
+    /// the native API.
+    fn generate_content(
+        &self,
+        model: &str,
+        _request: &serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<serde_json::Value, ProviderError>> + Send + '_>> {
+        let model = model.to_string();
+        Box::pin(async move { Err(ProviderError::NoProvider(model)) })
+    }
+
+    /// Gemini API `streamGenerateContent`: takes a native request body and
+    /// returns the native response chunks.
+    fn stream_generate_content(
+        &self,
+        model: &str,
+        _request: &serde_json::Value,
+    ) -> Pin<Box<dyn Future<Output = Result<NativeStream, ProviderError>> + Send + '_>> {
+        let model = model.to_string();
+        Box::pin(async move { Err(ProviderError::NoProvider(model)) })
+    }
+}
+
+/// Native response chunks returned by [`LlmProvider::stream_generate_content`].
+pub type NativeStream =
+    Pin<Box<dyn Stream<Item = Result<serde_json::Value, ProviderError>> + Send>>;
+
+/// Synthesized speech returned by [`LlmProvider::speech`].
+pub struct SpeechResponse {
+    /// MIME type of the audio, e.g. `audio/mpeg`.
+    pub content_type: String,
+    /// Audio bytes, streamed as the provider produces them.
+    pub audio: Pin<Box<dyn Stream<Item = Result<bytes::Bytes, ProviderError>> + Send>>,
 }
 
 #[cfg(test)]
@@ -241,6 +396,20 @@ mod tests {
         assert_eq!(err.status_code(), Some(400));
     }
 
+    #[test]
+    fn test_provider_error_quota_exhausted() {
+        let err = ProviderError::QuotaExhausted {
+            provider: "copilot".to_string(),
+            message: "premium requests used up".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Quota exhausted for copilot: premium requests used up"
+        );
+        assert_eq!(err.status_code(), Some(429));
+        assert!(err.retry_after_duration().is_none());
+    }
+
     #[test]
     fn test_provider_error_status_codes() {
         assert_eq!(
@@ -0,0 +1,214 @@
+//! Per-model parameter defaults and clamps (`[[providers.model_params]]`).
+//!
+//! Each entry names a model pattern and, for `temperature`, `top_p` and
+//! `max_tokens`, a default used when the request leaves the parameter out
+//! and bounds the request's value is clamped to. Setting `min` and `max`
+//! to the same value forces it, as thinking models need for `temperature`.
+//! Entries are tried in order and the first whose pattern matches applies.
+//! Clamped values are logged.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::providers::rules::glob_match;
+use crate::providers::types::ChatRequest;
+
+/// One `[[providers.model_params]]` entry.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct ModelParams {
+    /// Model name; `*` matches any run of characters (`o3*`).
+    pub model: String,
+    #[serde(default, skip_serializing_if = "ParamLimits::is_empty")]
+    pub temperature: ParamLimits<f32>,
+    #[serde(default, skip_serializing_if = "ParamLimits::is_empty")]
+    pub top_p: ParamLimits<f32>,
+    #[serde(default, skip_serializing_if = "ParamLimits::is_empty")]
+    pub max_tokens: ParamLimits<u32>,
+}
+
+/// Default and bounds for one parameter.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq)]
+pub struct ParamLimits<T> {
+    /// Used when the request leaves the parameter out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<T>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<T>,
+}
+
+impl<T: Copy + PartialOrd + Display> ParamLimits<T> {
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.min.is_none() && self.max.is_none()
+    }
+
+    fn validate(&self, model: &str, param: &str) -> Result<(), String> {
+        let invalid = |what: &str| Err(format!("Model params for '{model}': {param} {what}"));
+        if let (Some(min), Some(max)) = (self.min, self.max)
+            && min > max
+        {
+            return invalid("min is above max");
+        }
+        if let Some(default) = self.default
+            && (self.min.is_some_and(|min| default < min)
+                || self.max.is_some_and(|max| default > max))
+        {
+            return invalid("default is outside min and max");
+        }
+        Ok(())
+    }
+
+    /// Fill in the default, then clamp. Returns the value the request sent
+    /// if it was clamped.
+    fn apply(&self, value: &mut Option<T>) -> Option<T> {
+        let current = value.or(self.default)?;
+        let mut clamped = current;
+        if let Some(min) = self.min
+            && clamped < min
+        {
+            clamped = min;
+        }
+        if let Some(max) = self.max
+            && clamped > max
+        {
+            clamped = max;
+        }
+        *value = Some(clamped);
+        (clamped != current).then_some(current)
+    }
+}
+
+impl ModelParams {
+    /// Check the bounds are consistent.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.model.is_empty() {
+            return Err("Every model params entry needs a model pattern".to_string());
+        }
+        self.temperature.validate(&self.model, "temperature")?;
+        self.top_p.validate(&self.model, "top_p")?;
+        self.max_tokens.validate(&self.model, "max_tokens")
+    }
+
+    /// Apply the defaults and bounds to `request`, logging clamped values.
+    pub fn apply(&self, request: &mut ChatRequest) {
+        let log = |param: &str, from: &dyn Display, to: &dyn Display| {
+            info!(
+                model = %request.model,
+                pattern = %self.model,
+                param,
+                requested = %from,
+                applied = %to,
+                "Clamped request parameter to the model's limits"
+            );
+        };
+        let mut temperature = request.temperature;
+        if let Some(from) = self.temperature.apply(&mut temperature) {
+            log("temperature", &from, &temperature.unwrap_or_default());
+        }
+        let mut top_p = request.top_p;
+        if let Some(from) = self.top_p.apply(&mut top_p) {
+            log("top_p", &from, &top_p.unwrap_or_default());
+        }
+        let mut max_tokens = request.max_tokens;
+        if let Some(from) = self.max_tokens.apply(&mut max_tokens) {
+            log("max_tokens", &from, &max_tokens.unwrap_or_default());
+        }
+        request.temperature = temperature;
+        request.top_p = top_p;
+        request.max_tokens = max_tokens;
+    }
+}
+
+/// Apply the first entry matching the request's model.
+pub fn apply(entries: &[ModelParams], request: &mut ChatRequest) {
+    if let Some(params) = entries
+        .iter()
+        .find(|p| glob_match(&p.model, &request.model))
+    {
+        params.apply(request);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(temperature: Option<f32>, max_tokens: Option<u32>) -> ChatRequest {
+        let mut request: ChatRequest = serde_json::from_value(serde_json::json!({
+            "model": "o3-mini",
+            "messages": [{"role": "user", "content": "hi"}],
+        }))
+        .unwrap();
+        request.temperature = temperature;
+        request.max_tokens = max_tokens;
+        request
+    }
+
+    fn entries() -> Vec<ModelParams> {
+        vec![
+            ModelParams {
+                model: "o3*".to_string(),
+                temperature: ParamLimits {
+                    default: Some(1.0),
+                    min: Some(1.0),
+                    max: Some(1.0),
+                },
+                max_tokens: ParamLimits {
+                    default: None,
+                    min: None,
+                    max: Some(1000),
+                },
+                ..Default::default()
+            },
+            ModelParams {
+                model: "*".to_string(),
+                max_tokens: ParamLimits {
+                    default: Some(64),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_defaults_and_clamps() {
+        let mut req = request(None, None);
+        apply(&entries(), &mut req);
+        assert_eq!(req.temperature, Some(1.0));
+        assert_eq!(req.max_tokens, None);
+        assert_eq!(req.top_p, None);
+
+        let mut req = request(Some(0.2), Some(5000));
+        apply(&entries(), &mut req);
+        assert_eq!(req.temperature, Some(1.0));
+        assert_eq!(req.max_tokens, Some(1000));
+
+        let mut req = request(None, Some(500));
+        apply(&entries(), &mut req);
+        assert_eq!(req.max_tokens, Some(500));
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let mut req = request(Some(0.5), None);
+        req.model = "gpt-4o".to_string();
+        apply(&entries(), &mut req);
+        assert_eq!(req.temperature, Some(0.5));
+        assert_eq!(req.max_tokens, Some(64));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(entries().iter().all(|p| p.validate().is_ok()));
+        let mut bad = entries().remove(0);
+        bad.temperature.min = Some(1.5);
+        assert!(bad.validate().unwrap_err().contains("min is above max"));
+        let mut bad = entries().remove(0);
+        bad.max_tokens.default = Some(2000);
+        assert!(bad.validate().unwrap_err().contains("outside"));
+    }
+}
@@ -1,11 +1,33 @@
 //! Model pricing database.
 //!
 //! Contains pricing information for all supported models across providers.
-//! Prices are in USD per 1M tokens (input/output).
+//! Prices are in USD per 1M tokens (input/output), per generated image for
+//! image models, and per minute of audio or 1M characters for audio models.
+//!
+//! The built-in prices can be extended from a local file or a remote URL
+//! (`[pricing]`), either in gaud's own format ([`PricingList`]) or LiteLLM's
+//! `model_prices_and_context_window.json`. Per-model overrides set through
+//! `/admin/pricing` are stored in the `pricing_overrides` table.
+//!
+//! Costs can be billed in another currency (`pricing.currency`), converted
+//! with a static rate or one fetched from [`ExchangeRateSource`].
+//!
+//! The database also holds what each chat model can do ([`ModelCapabilities`]:
+//! context window, output limit, vision, tools, thinking), listed by
+//! `/v1/models`. Price lists can extend these the same way as prices.
 
+use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::config::PricingConfig;
+use crate::db::Database;
+use crate::error::AppError;
+
+/// LiteLLM's community-maintained price list, usable as `pricing.url`.
+pub const LITELLM_PRICES_URL: &str =
+    "https://raw.githubusercontent.com/BerriAI/litellm/main/model_prices_and_context_window.json";
+
 // MARK: - Types
 
 /// Pricing information for a specific model.
@@ -23,11 +45,82 @@ pub struct ModelPricing {
     pub cached_input_cost_per_million: Option<f64>,
 }
 
+/// Per-image pricing for an image generation model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagePricing {
+    /// Model identifier (e.g., "dall-e-3").
+    pub model: String,
+    /// Provider identifier (e.g., "litellm", "gemini").
+    pub provider: String,
+    /// Cost per image in USD at up to 1024x1024.
+    pub cost_per_image: f64,
+    /// Cost per image in USD for sizes with either side above 1024 pixels.
+    pub large_cost_per_image: f64,
+}
+
+impl ImagePricing {
+    /// Cost of a single image at the requested size (`WIDTHxHEIGHT`).
+    ///
+    /// Unknown or unparseable sizes are billed at the standard rate.
+    pub fn cost_for_size(&self, size: Option<&str>) -> f64 {
+        let is_large = size
+            .and_then(|s| s.split_once('x'))
+            .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+            .is_some_and(|(w, h)| w > 1024 || h > 1024);
+        if is_large {
+            self.large_cost_per_image
+        } else {
+            self.cost_per_image
+        }
+    }
+}
+
+/// Pricing for an audio model (transcription or text-to-speech).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioPricing {
+    /// Model identifier (e.g., "whisper-1", "tts-1").
+    pub model: String,
+    /// Provider identifier (e.g., "litellm").
+    pub provider: String,
+    /// Cost per minute of transcribed audio in USD.
+    pub cost_per_minute: f64,
+    /// Cost per 1M characters of synthesized speech input in USD.
+    pub cost_per_million_characters: f64,
+}
+
+/// Limits and features of a chat model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    /// Model identifier (e.g., "claude-sonnet-4-20250514").
+    pub model: String,
+    /// Maximum input tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    /// Maximum tokens generated per response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    /// Accepts image input.
+    #[serde(default)]
+    pub vision: bool,
+    /// Supports tool (function) calling.
+    #[serde(default)]
+    pub tools: bool,
+    /// Supports extended thinking / reasoning.
+    #[serde(default)]
+    pub thinking: bool,
+}
+
 /// Pricing database containing all model pricing information.
 #[derive(Debug, Clone)]
 pub struct PricingDatabase {
     /// Model name -> pricing info.
     pricing: HashMap<String, ModelPricing>,
+    /// Image model name -> per-image pricing info.
+    image_pricing: HashMap<String, ImagePricing>,
+    /// Audio model name -> per-minute / per-character pricing info.
+    audio_pricing: HashMap<String, AudioPricing>,
+    /// Chat model name -> limits and features.
+    capabilities: HashMap<String, ModelCapabilities>,
 }
 
 // MARK: - Implementation
@@ -146,7 +239,103 @@ impl PricingDatabase {
             },
         );
 
-        Self { pricing }
+        // Image generation pricing (per image)
+        // Sources: https://openai.com/api/pricing, https://ai.google.dev/pricing
+        let mut image_pricing = HashMap::new();
+        for (model, provider, standard, large) in [
+            ("dall-e-3", "litellm", 0.04, 0.08),
+            ("dall-e-2", "litellm", 0.02, 0.02),
+            ("gpt-image-1", "litellm", 0.042, 0.063),
+            ("gemini-2.5-flash-image", "gemini", 0.039, 0.039),
+            ("gemini-3-pro-image-preview", "gemini", 0.134, 0.24),
+        ] {
+            image_pricing.insert(
+                model.to_string(),
+                ImagePricing {
+                    model: model.to_string(),
+                    provider: provider.to_string(),
+                    cost_per_image: standard,
+                    large_cost_per_image: large,
+                },
+            );
+        }
+
+        // Audio pricing (per minute transcribed / per 1M characters spoken)
+        // Source: https://openai.com/api/pricing
+        let mut audio_pricing = HashMap::new();
+        for (model, per_minute, per_million_chars) in [
+            ("whisper-1", 0.006, 0.0),
+            ("gpt-4o-transcribe", 0.006, 0.0),
+            ("gpt-4o-mini-transcribe", 0.003, 0.0),
+            ("tts-1", 0.0, 15.00),
+            ("tts-1-hd", 0.0, 30.00),
+        ] {
+            audio_pricing.insert(
+                model.to_string(),
+                AudioPricing {
+                    model: model.to_string(),
+                    provider: "litellm".to_string(),
+                    cost_per_minute: per_minute,
+                    cost_per_million_characters: per_million_chars,
+                },
+            );
+        }
+
+        // Model limits and features
+        // Sources: provider model documentation
+        let mut capabilities = HashMap::new();
+        for (model, context, output, vision, tools, thinking) in [
+            ("claude-opus-4-20250514", 200_000, 32_000, true, true, true),
+            (
+                "claude-sonnet-4-20250514",
+                200_000,
+                64_000,
+                true,
+                true,
+                true,
+            ),
+            (
+                "claude-haiku-3-5-20241022",
+                200_000,
+                8_192,
+                true,
+                true,
+                false,
+            ),
+            ("gemini-2.5-pro", 1_048_576, 65_536, true, true, true),
+            ("gemini-2.5-flash", 1_048_576, 65_536, true, true, true),
+            ("gemini-2.0-flash", 1_048_576, 8_192, true, true, false),
+            ("gemini-1.5-pro", 2_097_152, 8_192, true, true, false),
+            ("gemini-1.5-flash", 1_048_576, 8_192, true, true, false),
+            ("gpt-4o", 128_000, 16_384, true, true, false),
+            ("gpt-4-turbo", 128_000, 4_096, true, true, false),
+            ("o1", 200_000, 100_000, true, true, true),
+            ("o3-mini", 200_000, 100_000, false, true, true),
+            ("kiro:claude-sonnet-4", 200_000, 64_000, true, true, true),
+            ("kiro:claude-sonnet-4.5", 200_000, 64_000, true, true, true),
+            ("kiro:claude-haiku-4.5", 200_000, 64_000, true, true, true),
+            ("kiro:claude-opus-4.5", 200_000, 64_000, true, true, true),
+            ("kiro:claude-3.7-sonnet", 200_000, 64_000, true, true, true),
+        ] {
+            capabilities.insert(
+                model.to_string(),
+                ModelCapabilities {
+                    model: model.to_string(),
+                    context_window: Some(context),
+                    max_output_tokens: Some(output),
+                    vision,
+                    tools,
+                    thinking,
+                },
+            );
+        }
+
+        Self {
+            pricing,
+            image_pricing,
+            audio_pricing,
+            capabilities,
+        }
     }
 
     /// Get pricing for a specific model.
@@ -163,6 +352,59 @@ impl PricingDatabase {
     pub fn has_pricing(&self, model: &str) -> bool {
         self.pricing.contains_key(model)
     }
+
+    /// Get per-image pricing for an image generation model.
+    ///
+    /// A `litellm:` routing prefix is ignored so proxied models share the
+    /// upstream price.
+    pub fn get_image(&self, model: &str) -> Option<&ImagePricing> {
+        let model = model.strip_prefix("litellm:").unwrap_or(model);
+        self.image_pricing.get(model)
+    }
+
+    /// Get pricing for an audio (transcription or speech) model.
+    ///
+    /// A `litellm:` routing prefix is ignored, as for image models.
+    pub fn get_audio(&self, model: &str) -> Option<&AudioPricing> {
+        let model = model.strip_prefix("litellm:").unwrap_or(model);
+        self.audio_pricing.get(model)
+    }
+
+    /// Get the limits and features of a chat model.
+    ///
+    /// A `litellm:` routing prefix is ignored, as for image models.
+    pub fn get_capabilities(&self, model: &str) -> Option<&ModelCapabilities> {
+        self.capabilities
+            .get(model)
+            .or_else(|| self.capabilities.get(model.strip_prefix("litellm:")?))
+    }
+
+    /// Get all per-image pricing information.
+    pub fn all_images(&self) -> Vec<&ImagePricing> {
+        self.image_pricing.values().collect()
+    }
+
+    /// Get all audio pricing information.
+    pub fn all_audio(&self) -> Vec<&AudioPricing> {
+        self.audio_pricing.values().collect()
+    }
+
+    /// Add the prices of `list`, replacing existing prices of the same models.
+    pub fn merge(&mut self, list: PricingList) {
+        for pricing in list.models {
+            self.pricing.insert(pricing.model.clone(), pricing);
+        }
+        for pricing in list.images {
+            self.image_pricing.insert(pricing.model.clone(), pricing);
+        }
+        for pricing in list.audio {
+            self.audio_pricing.insert(pricing.model.clone(), pricing);
+        }
+        for capabilities in list.capabilities {
+            self.capabilities
+                .insert(capabilities.model.clone(), capabilities);
+        }
+    }
 }
 
 impl Default for PricingDatabase {
@@ -171,12 +413,347 @@ impl Default for PricingDatabase {
     }
 }
 
+// MARK: - Price Lists
+
+/// A list of prices read from a pricing file or URL.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PricingList {
+    #[serde(default)]
+    pub models: Vec<ModelPricing>,
+    #[serde(default)]
+    pub images: Vec<ImagePricing>,
+    #[serde(default)]
+    pub audio: Vec<AudioPricing>,
+    #[serde(default)]
+    pub capabilities: Vec<ModelCapabilities>,
+}
+
+impl PricingList {
+    /// Parse a price list from TOML (gaud's format) or JSON (gaud's format or
+    /// LiteLLM's `model_prices_and_context_window.json`).
+    pub fn parse(text: &str, is_toml: bool) -> Result<Self, String> {
+        if is_toml {
+            return toml::from_str(text).map_err(|e| format!("Invalid TOML price list: {e}"));
+        }
+        let value: serde_json::Value =
+            serde_json::from_str(text).map_err(|e| format!("Invalid JSON price list: {e}"))?;
+        let is_gaud_format = ["models", "images", "audio", "capabilities"]
+            .iter()
+            .any(|key| value.get(key).is_some_and(|v| v.is_array()));
+        if is_gaud_format {
+            serde_json::from_value(value).map_err(|e| format!("Invalid price list: {e}"))
+        } else {
+            Ok(Self::from_litellm(&value))
+        }
+    }
+
+    /// Convert LiteLLM's per-token / per-image / per-second prices and chat
+    /// model limits. Entries without a usable price (and the `sample_spec`
+    /// example) are skipped, except for their limits.
+    pub fn from_litellm(value: &serde_json::Value) -> Self {
+        let mut list = Self::default();
+        let Some(entries) = value.as_object() else {
+            return list;
+        };
+        for (model, entry) in entries {
+            if model == "sample_spec" {
+                continue;
+            }
+            let price = |key: &str| entry.get(key).and_then(|v| v.as_f64());
+            let provider = match entry.get("litellm_provider").and_then(|v| v.as_str()) {
+                Some("anthropic") => "claude",
+                Some(p) if p.starts_with("gemini") || p.starts_with("vertex_ai") => "gemini",
+                Some(p) => p,
+                None => "litellm",
+            }
+            .to_string();
+
+            match entry.get("mode").and_then(|v| v.as_str()).unwrap_or("chat") {
+                "chat" | "completion" | "responses" => {
+                    if let Some(capabilities) = litellm_capabilities(model, entry) {
+                        list.capabilities.push(capabilities);
+                    }
+                    let (Some(input), Some(output)) = (
+                        price("input_cost_per_token"),
+                        price("output_cost_per_token"),
+                    ) else {
+                        continue;
+                    };
+                    list.models.push(ModelPricing {
+                        model: model.clone(),
+                        provider,
+                        input_cost_per_million: input * 1_000_000.0,
+                        output_cost_per_million: output * 1_000_000.0,
+                        cached_input_cost_per_million: price("cache_read_input_token_cost")
+                            .map(|c| c * 1_000_000.0),
+                    });
+                }
+                "image_generation" => {
+                    let Some(cost) = price("output_cost_per_image") else {
+                        continue;
+                    };
+                    list.images.push(ImagePricing {
+                        model: model.clone(),
+                        provider,
+                        cost_per_image: cost,
+                        large_cost_per_image: cost,
+                    });
+                }
+                "audio_transcription" | "audio_speech" => {
+                    let per_second = price("input_cost_per_second");
+                    let per_character = price("input_cost_per_character");
+                    if per_second.is_none() && per_character.is_none() {
+                        continue;
+                    }
+                    list.audio.push(AudioPricing {
+                        model: model.clone(),
+                        provider,
+                        cost_per_minute: per_second.unwrap_or(0.0) * 60.0,
+                        cost_per_million_characters: per_character.unwrap_or(0.0) * 1_000_000.0,
+                    });
+                }
+                _ => {}
+            }
+        }
+        list
+    }
+
+    /// Number of prices in the list (capabilities are not counted).
+    pub fn len(&self) -> usize {
+        self.models.len() + self.images.len() + self.audio.len()
+    }
+
+    /// Whether the list has no prices.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Limits and features from a LiteLLM chat entry, if it lists any.
+fn litellm_capabilities(model: &str, entry: &serde_json::Value) -> Option<ModelCapabilities> {
+    let tokens = |key: &str| {
+        entry
+            .get(key)
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok())
+    };
+    let flag = |key: &str| entry.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    let capabilities = ModelCapabilities {
+        model: model.to_string(),
+        context_window: tokens("max_input_tokens"),
+        max_output_tokens: tokens("max_output_tokens"),
+        vision: flag("supports_vision"),
+        tools: flag("supports_function_calling"),
+        thinking: flag("supports_reasoning"),
+    };
+    let known = capabilities.context_window.is_some()
+        || capabilities.max_output_tokens.is_some()
+        || capabilities.vision
+        || capabilities.tools
+        || capabilities.thinking;
+    known.then_some(capabilities)
+}
+
+/// The configured pricing file and URL, loaded on top of the built-in prices.
+#[derive(Debug, Clone)]
+pub struct PricingSource {
+    file: Option<String>,
+    url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl PricingSource {
+    /// Build the source of `config`, or `None` when neither a file nor a URL
+    /// is configured.
+    pub fn from_config(config: &PricingConfig, client: reqwest::Client) -> Option<Self> {
+        if config.file.is_none() && config.url.is_none() {
+            return None;
+        }
+        Some(Self {
+            file: config.file.clone(),
+            url: config.url.clone(),
+            client,
+        })
+    }
+
+    /// Build a database from the built-in prices, the file, and the URL, in
+    /// that order. Fails if any configured source cannot be read.
+    pub async fn load(&self) -> Result<PricingDatabase, String> {
+        let mut db = PricingDatabase::new();
+        if let Some(ref path) = self.file {
+            let text = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| format!("Failed to read {path}: {e}"))?;
+            db.merge(PricingList::parse(&text, path.ends_with(".toml"))?);
+        }
+        if let Some(ref url) = self.url {
+            let resp = self
+                .client
+                .get(url)
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .map_err(|e| format!("Failed to fetch {url}: {e}"))?;
+            let text = resp
+                .text()
+                .await
+                .map_err(|e| format!("Failed to fetch {url}: {e}"))?;
+            db.merge(PricingList::parse(&text, url.ends_with(".toml"))?);
+        }
+        Ok(db)
+    }
+}
+
+/// Endpoint the USD exchange rate of the billing currency is fetched from.
+#[derive(Debug, Clone)]
+pub struct ExchangeRateSource {
+    url: String,
+    currency: String,
+    client: reqwest::Client,
+}
+
+impl ExchangeRateSource {
+    /// Build the source of `config`, or `None` when costs are billed in USD
+    /// or no `exchange_rate_url` is configured.
+    pub fn from_config(config: &PricingConfig, client: reqwest::Client) -> Option<Self> {
+        if config.currency == "USD" {
+            return None;
+        }
+        Some(Self {
+            url: config.exchange_rate_url.clone()?,
+            currency: config.currency.clone(),
+            client,
+        })
+    }
+
+    /// Fetch the number of units of the currency per USD.
+    pub async fn fetch(&self) -> Result<f64, String> {
+        let url = &self.url;
+        let body: serde_json::Value = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch {url}: {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse {url}: {e}"))?;
+        parse_exchange_rate(&body, &self.currency)
+            .ok_or_else(|| format!("No {} rate in response from {url}", self.currency))
+    }
+}
+
+/// Find the rate of `currency` in a `{"rates": {...}}` or flat `{...}`
+/// response, as served by most exchange rate APIs.
+fn parse_exchange_rate(body: &serde_json::Value, currency: &str) -> Option<f64> {
+    let rates = body.get("rates").unwrap_or(body);
+    rates
+        .get(currency)
+        .and_then(|v| v.as_f64())
+        .filter(|r| *r > 0.0 && r.is_finite())
+}
+
+// MARK: - Overrides
+
+/// Load the per-model price overrides set through the admin API.
+pub fn load_overrides(db: &Database) -> Result<Vec<ModelPricing>, AppError> {
+    let overrides = db.with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT model, provider, input_cost_per_million, output_cost_per_million, \
+             cached_input_cost_per_million FROM pricing_overrides ORDER BY model",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ModelPricing {
+                model: row.get(0)?,
+                provider: row.get(1)?,
+                input_cost_per_million: row.get(2)?,
+                output_cost_per_million: row.get(3)?,
+                cached_input_cost_per_million: row.get(4)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(overrides)
+}
+
+/// Store a price override, replacing an earlier one for the same model.
+pub fn save_override(db: &Database, pricing: &ModelPricing) -> Result<(), AppError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO pricing_overrides (model, provider, input_cost_per_million, \
+             output_cost_per_million, cached_input_cost_per_million) \
+             VALUES (?1, ?2, ?3, ?4, ?5) \
+             ON CONFLICT(model) DO UPDATE SET provider = ?2, input_cost_per_million = ?3, \
+             output_cost_per_million = ?4, cached_input_cost_per_million = ?5, \
+             updated_at = datetime('now')",
+            params![
+                pricing.model,
+                pricing.provider,
+                pricing.input_cost_per_million,
+                pricing.output_cost_per_million,
+                pricing.cached_input_cost_per_million,
+            ],
+        )
+    })?;
+    Ok(())
+}
+
+/// Delete the price override of `model`. Returns whether one existed.
+pub fn delete_override(db: &Database, model: &str) -> Result<bool, AppError> {
+    let deleted = db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM pricing_overrides WHERE model = ?1",
+            params![model],
+        )
+    })?;
+    Ok(deleted > 0)
+}
+
 // MARK: - Tests
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_exchange_rate() {
+        let nested = serde_json::json!({"base": "USD", "rates": {"EUR": 0.92, "GBP": 0.79}});
+        assert_eq!(parse_exchange_rate(&nested, "GBP"), Some(0.79));
+        assert_eq!(parse_exchange_rate(&nested, "JPY"), None);
+
+        let flat = serde_json::json!({"EUR": 0.92});
+        assert_eq!(parse_exchange_rate(&flat, "EUR"), Some(0.92));
+        assert_eq!(
+            parse_exchange_rate(&serde_json::json!({"EUR": 0}), "EUR"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_image_pricing_by_size() {
+        let db = PricingDatabase::new();
+        let dalle = db.get_image("litellm:dall-e-3").unwrap();
+        assert_eq!(dalle.cost_for_size(Some("1024x1024")), 0.04);
+        assert_eq!(dalle.cost_for_size(Some("1792x1024")), 0.08);
+        assert_eq!(dalle.cost_for_size(None), 0.04);
+        assert_eq!(dalle.cost_for_size(Some("garbage")), 0.04);
+        assert!(db.get_image("claude-sonnet-4-20250514").is_none());
+    }
+
+    #[test]
+    fn test_audio_pricing() {
+        let db = PricingDatabase::new();
+        assert_eq!(db.get_audio("whisper-1").unwrap().cost_per_minute, 0.006);
+        assert_eq!(
+            db.get_audio("litellm:tts-1")
+                .unwrap()
+                .cost_per_million_characters,
+            15.00
+        );
+        assert!(db.get_audio("dall-e-3").is_none());
+    }
+
     #[test]
     fn test_pricing_database() {
         let db = PricingDatabase::new();
@@ -209,4 +786,143 @@ mod tests {
         assert!(db.has_pricing("gpt-4o"));
         assert!(!db.has_pricing("unknown-model"));
     }
+
+    #[test]
+    fn test_parse_gaud_price_list() {
+        let toml = r#"
+            [[models]]
+            model = "my-model"
+            provider = "litellm"
+            input_cost_per_million = 1.0
+            output_cost_per_million = 2.0
+        "#;
+        let list = PricingList::parse(toml, true).unwrap();
+        assert_eq!(list.models.len(), 1);
+        assert!(list.models[0].cached_input_cost_per_million.is_none());
+
+        let json = r#"{"images": [{"model": "img", "provider": "litellm",
+            "cost_per_image": 0.1, "large_cost_per_image": 0.2}]}"#;
+        let list = PricingList::parse(json, false).unwrap();
+        assert_eq!(list.images.len(), 1);
+        assert!(list.models.is_empty());
+
+        assert!(PricingList::parse("not json", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_litellm_price_list() {
+        let json = r#"{
+            "sample_spec": {"input_cost_per_token": 0.0, "output_cost_per_token": 0.0},
+            "claude-sonnet-4-20250514": {
+                "input_cost_per_token": 3e-06,
+                "output_cost_per_token": 1.5e-05,
+                "cache_read_input_token_cost": 3e-07,
+                "litellm_provider": "anthropic",
+                "max_input_tokens": 200000,
+                "max_output_tokens": 64000,
+                "supports_vision": true,
+                "supports_function_calling": true,
+                "mode": "chat"
+            },
+            "text-embedding-3-small": {
+                "input_cost_per_token": 2e-08,
+                "output_cost_per_token": 0.0,
+                "litellm_provider": "openai",
+                "mode": "embedding"
+            },
+            "whisper-1": {
+                "input_cost_per_second": 0.0001,
+                "output_cost_per_second": 0.0,
+                "litellm_provider": "openai",
+                "mode": "audio_transcription"
+            },
+            "no-price": {"litellm_provider": "openai", "mode": "chat"}
+        }"#;
+        let list = PricingList::parse(json, false).unwrap();
+        assert_eq!(list.len(), 2);
+
+        let claude = &list.models[0];
+        assert_eq!(claude.provider, "claude");
+        assert!((claude.input_cost_per_million - 3.0).abs() < 1e-9);
+        assert!((claude.output_cost_per_million - 15.0).abs() < 1e-9);
+        assert!((claude.cached_input_cost_per_million.unwrap() - 0.3).abs() < 1e-9);
+
+        assert!((list.audio[0].cost_per_minute - 0.006).abs() < 1e-9);
+
+        assert_eq!(list.capabilities.len(), 1);
+        let caps = &list.capabilities[0];
+        assert_eq!(caps.context_window, Some(200_000));
+        assert_eq!(caps.max_output_tokens, Some(64_000));
+        assert!(caps.vision && caps.tools && !caps.thinking);
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let mut db = PricingDatabase::new();
+        let gpt = db.get_capabilities("litellm:gpt-4o").unwrap();
+        assert_eq!(gpt.context_window, Some(128_000));
+        assert!(!db.get_capabilities("o3-mini").unwrap().vision);
+        assert!(db.get_capabilities("kiro:auto").is_none());
+
+        let list = PricingList::parse(
+            r#"{"capabilities": [{"model": "my-model", "context_window": 32000, "tools": true}]}"#,
+            false,
+        )
+        .unwrap();
+        db.merge(list);
+        let mine = db.get_capabilities("my-model").unwrap();
+        assert_eq!(mine.context_window, Some(32_000));
+        assert!(mine.tools && !mine.vision);
+    }
+
+    #[test]
+    fn test_merge_price_list() {
+        let mut db = PricingDatabase::new();
+        let before = db.all().len();
+        db.merge(PricingList {
+            models: vec![
+                ModelPricing {
+                    model: "gpt-4o".to_string(),
+                    provider: "openai".to_string(),
+                    input_cost_per_million: 2.0,
+                    output_cost_per_million: 8.0,
+                    cached_input_cost_per_million: None,
+                },
+                ModelPricing {
+                    model: "new-model".to_string(),
+                    provider: "litellm".to_string(),
+                    input_cost_per_million: 1.0,
+                    output_cost_per_million: 1.0,
+                    cached_input_cost_per_million: None,
+                },
+            ],
+            ..Default::default()
+        });
+        assert_eq!(db.all().len(), before + 1);
+        assert_eq!(db.get("gpt-4o").unwrap().input_cost_per_million, 2.0);
+    }
+
+    #[test]
+    fn test_overrides_roundtrip() {
+        let db = Database::open_in_memory().unwrap();
+        let mut pricing = ModelPricing {
+            model: "gpt-4o".to_string(),
+            provider: "copilot".to_string(),
+            input_cost_per_million: 1.0,
+            output_cost_per_million: 4.0,
+            cached_input_cost_per_million: Some(0.5),
+        };
+        save_override(&db, &pricing).unwrap();
+        pricing.input_cost_per_million = 2.0;
+        save_override(&db, &pricing).unwrap();
+
+        let overrides = load_overrides(&db).unwrap();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].input_cost_per_million, 2.0);
+        assert_eq!(overrides[0].cached_input_cost_per_million, Some(0.5));
+
+        assert!(delete_override(&db, "gpt-4o").unwrap());
+        assert!(!delete_override(&db, "gpt-4o").unwrap());
+        assert!(load_overrides(&db).unwrap().is_empty());
+    }
 }
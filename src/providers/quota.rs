@@ -0,0 +1,241 @@
+//! Daily and monthly request quotas of upstream provider accounts.
+//!
+//! Some accounts have hard quotas on top of per-minute rate limits (Gemini
+//! Cloud Code daily limits, Copilot premium requests). The router counts
+//! requests against the configured limits and skips a provider once its
+//! quota is used up or the upstream reports exhaustion, until the next
+//! reset. Counters live in memory and start from zero on restart.
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc};
+use serde::Serialize;
+
+use crate::config::ProviderQuotaConfig;
+
+/// Quota usage of one provider account.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderQuota {
+    config: ProviderQuotaConfig,
+    /// Start of the daily period the counters belong to.
+    day_start: Option<DateTime<Utc>>,
+    daily_used: u64,
+    /// Start of the monthly period the counters belong to.
+    month_start: Option<DateTime<Utc>>,
+    monthly_used: u64,
+    exhausted_until: Option<DateTime<Utc>>,
+}
+
+/// Snapshot of a provider's quota usage, reported by `/health`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProviderQuotaStatus {
+    pub daily_used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_limit: Option<u64>,
+    pub monthly_used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted_until: Option<DateTime<Utc>>,
+}
+
+impl ProviderQuota {
+    pub fn new(config: ProviderQuotaConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Replace the limits, keeping the usage counted so far.
+    pub fn set_config(&mut self, config: ProviderQuotaConfig) {
+        self.config = config;
+    }
+
+    /// Whether requests may be sent to the provider at `now`.
+    pub fn available(&mut self, now: DateTime<Utc>) -> bool {
+        self.roll(now);
+        if let Some(until) = self.exhausted_until {
+            if now < until {
+                return false;
+            }
+            self.exhausted_until = None;
+        }
+        let daily_ok = self
+            .config
+            .daily_requests
+            .is_none_or(|l| self.daily_used < l);
+        let monthly_ok = self
+            .config
+            .monthly_requests
+            .is_none_or(|l| self.monthly_used < l);
+        daily_ok && monthly_ok
+    }
+
+    /// Count a successful request.
+    pub fn record_request(&mut self, now: DateTime<Utc>) {
+        self.roll(now);
+        self.daily_used += 1;
+        self.monthly_used += 1;
+    }
+
+    /// The upstream reported the quota as used up: block the provider until
+    /// the next daily reset, or the next monthly reset when only a monthly
+    /// limit is configured.
+    pub fn mark_exhausted(&mut self, now: DateTime<Utc>) {
+        let monthly_only =
+            self.config.monthly_requests.is_some() && self.config.daily_requests.is_none();
+        let until = if monthly_only {
+            self.next_month_start(now)
+        } else {
+            self.next_day_start(now)
+        };
+        self.exhausted_until = Some(until);
+    }
+
+    pub fn status(&mut self, now: DateTime<Utc>) -> ProviderQuotaStatus {
+        let available = self.available(now);
+        let exhausted_until = match self.exhausted_until {
+            Some(until) => Some(until),
+            None if !available => Some(self.limit_reset(now)),
+            None => None,
+        };
+        ProviderQuotaStatus {
+            daily_used: self.daily_used,
+            daily_limit: self.config.daily_requests,
+            monthly_used: self.monthly_used,
+            monthly_limit: self.config.monthly_requests,
+            exhausted_until,
+        }
+    }
+
+    /// When a locally counted limit that is currently used up resets.
+    fn limit_reset(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let monthly_full = self
+            .config
+            .monthly_requests
+            .is_some_and(|l| self.monthly_used >= l);
+        if monthly_full {
+            self.next_month_start(now)
+        } else {
+            self.next_day_start(now)
+        }
+    }
+
+    fn reset_hour(&self) -> u32 {
+        u32::from(self.config.reset_hour_utc.unwrap_or(0).min(23))
+    }
+
+    /// Start of the daily period containing `now`.
+    fn day_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let time = NaiveTime::from_hms_opt(self.reset_hour(), 0, 0).unwrap_or_default();
+        let today = Utc.from_utc_datetime(&now.date_naive().and_time(time));
+        if now >= today {
+            today
+        } else {
+            today - Duration::days(1)
+        }
+    }
+
+    fn next_day_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.day_start(now) + Duration::days(1)
+    }
+
+    /// Start of the calendar month containing `now`, at the reset hour.
+    fn month_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let start = |year: i32, month: u32| {
+            Utc.with_ymd_and_hms(year, month, 1, self.reset_hour(), 0, 0)
+                .single()
+                .unwrap_or(now)
+        };
+        let this_month = start(now.year(), now.month());
+        if now >= this_month {
+            this_month
+        } else if now.month() == 1 {
+            start(now.year() - 1, 12)
+        } else {
+            start(now.year(), now.month() - 1)
+        }
+    }
+
+    fn next_month_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let current = self.month_start(now);
+        let (year, month) = if current.month() == 12 {
+            (current.year() + 1, 1)
+        } else {
+            (current.year(), current.month() + 1)
+        };
+        Utc.with_ymd_and_hms(year, month, 1, self.reset_hour(), 0, 0)
+            .single()
+            .unwrap_or(current + Duration::days(31))
+    }
+
+    /// Reset the counters when `now` falls into a new period.
+    fn roll(&mut self, now: DateTime<Utc>) {
+        let day = self.day_start(now);
+        if self.day_start != Some(day) {
+            self.day_start = Some(day);
+            self.daily_used = 0;
+        }
+        let month = self.month_start(now);
+        if self.month_start != Some(month) {
+            self.month_start = Some(month);
+            self.monthly_used = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_daily_limit_resets_at_reset_hour() {
+        let mut quota = ProviderQuota::new(ProviderQuotaConfig {
+            daily_requests: Some(2),
+            reset_hour_utc: Some(8),
+            ..Default::default()
+        });
+        let now = at(2026, 3, 10, 9);
+
+        assert!(quota.available(now));
+        quota.record_request(now);
+        quota.record_request(now);
+        assert!(!quota.available(now));
+        assert_eq!(quota.status(now).exhausted_until, Some(at(2026, 3, 11, 8)));
+
+        assert!(!quota.available(at(2026, 3, 11, 7)));
+        assert!(quota.available(at(2026, 3, 11, 8)));
+    }
+
+    #[test]
+    fn test_monthly_limit_spans_days() {
+        let mut quota = ProviderQuota::new(ProviderQuotaConfig {
+            monthly_requests: Some(1),
+            ..Default::default()
+        });
+        quota.record_request(at(2026, 12, 5, 0));
+        assert!(!quota.available(at(2026, 12, 20, 0)));
+        assert!(quota.available(at(2027, 1, 1, 0)));
+        assert_eq!(quota.status(at(2027, 1, 1, 0)).monthly_used, 0);
+    }
+
+    #[test]
+    fn test_mark_exhausted_blocks_until_next_reset() {
+        let mut daily = ProviderQuota::default();
+        daily.mark_exhausted(at(2026, 3, 10, 15));
+        assert!(!daily.available(at(2026, 3, 10, 23)));
+        assert!(daily.available(at(2026, 3, 11, 0)));
+        assert_eq!(daily.status(at(2026, 3, 11, 0)).exhausted_until, None);
+
+        let mut monthly = ProviderQuota::new(ProviderQuotaConfig {
+            monthly_requests: Some(300),
+            ..Default::default()
+        });
+        monthly.mark_exhausted(at(2026, 3, 10, 15));
+        assert!(!monthly.available(at(2026, 3, 31, 23)));
+        assert!(monthly.available(at(2026, 4, 1, 0)));
+    }
+}
@@ -0,0 +1,353 @@
+//! Upstream Rate-Limit Tracking
+//!
+//! Providers record the rate-limit headers of each upstream response (as
+//! normalized by [`parse_rate_limit_headers`]) in an [`UpstreamRateLimitState`].
+//! The router reads the latest snapshot to steer traffic away from providers
+//! that are close to their upstream limits, and the chat endpoint reports the
+//! aggregate to clients as `x-upstream-ratelimit-*` headers.
+//!
+//! [`parse_rate_limit_headers`]: crate::providers::transform::util::parse_rate_limit_headers
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Prefix of the response headers sent to clients.
+pub const CLIENT_HEADER_PREFIX: &str = "x-upstream-ratelimit-";
+
+/// Rate-limit state reported by an upstream provider in one response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpstreamRateLimit {
+    pub limit_requests: Option<u64>,
+    pub remaining_requests: Option<u64>,
+    /// Time until the request window resets, as of `observed_at`.
+    pub reset_requests: Option<Duration>,
+    pub limit_tokens: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    /// Time until the token window resets, as of `observed_at`.
+    pub reset_tokens: Option<Duration>,
+    /// Back-off requested by a `retry-after` header.
+    pub retry_after: Option<Duration>,
+    pub observed_at: Instant,
+}
+
+impl UpstreamRateLimit {
+    /// Build a snapshot from normalized `x-ratelimit-*` headers.
+    ///
+    /// Accepts both the OpenAI layout (`x-ratelimit-remaining-requests`) and
+    /// the normalized Anthropic layout (`x-ratelimit-requests-remaining`).
+    /// Returns `None` when the response carried no rate-limit information.
+    pub fn from_headers(
+        headers: &[(String, String)],
+        retry_after: Option<Duration>,
+    ) -> Option<Self> {
+        let mut limit = Self {
+            limit_requests: None,
+            remaining_requests: None,
+            reset_requests: None,
+            limit_tokens: None,
+            remaining_tokens: None,
+            reset_tokens: None,
+            retry_after,
+            observed_at: Instant::now(),
+        };
+
+        for (name, value) in headers {
+            let name = name.to_lowercase();
+            let Some(suffix) = name.strip_prefix("x-ratelimit-") else {
+                continue;
+            };
+            let (field, kind) = match suffix.split_once('-') {
+                Some((a @ ("limit" | "remaining" | "reset"), b)) => (a, b),
+                Some((a, b @ ("limit" | "remaining" | "reset"))) => (b, a),
+                _ => continue,
+            };
+            let value = value.trim();
+            match (field, kind) {
+                ("limit", "requests") => limit.limit_requests = value.parse().ok(),
+                ("remaining", "requests") => limit.remaining_requests = value.parse().ok(),
+                ("reset", "requests") => limit.reset_requests = parse_reset(value),
+                ("limit", "tokens") => limit.limit_tokens = value.parse().ok(),
+                ("remaining", "tokens") => limit.remaining_tokens = value.parse().ok(),
+                ("reset", "tokens") => limit.reset_tokens = parse_reset(value),
+                _ => {}
+            }
+        }
+
+        if limit.remaining_requests.is_none()
+            && limit.remaining_tokens.is_none()
+            && limit.retry_after.is_none()
+        {
+            return None;
+        }
+        Some(limit)
+    }
+
+    /// Fraction of the tighter of the request and token allowances still
+    /// available, between 0.0 and 1.0.
+    ///
+    /// A dimension whose window has reset since the snapshot was taken counts
+    /// as fully available; an active `retry-after` counts as exhausted.
+    /// Returns `None` if nothing is known.
+    pub fn headroom(&self) -> Option<f64> {
+        let elapsed = self.observed_at.elapsed();
+        if self.retry_after.is_some_and(|d| d > elapsed) {
+            return Some(0.0);
+        }
+        let fraction = |limit: Option<u64>, remaining: Option<u64>, reset: Option<Duration>| {
+            if reset.is_some_and(|r| r <= elapsed) {
+                return None;
+            }
+            match (limit, remaining) {
+                (Some(l), Some(r)) if l > 0 => Some((r as f64 / l as f64).min(1.0)),
+                (_, Some(0)) => Some(0.0),
+                _ => None,
+            }
+        };
+        let requests = fraction(
+            self.limit_requests,
+            self.remaining_requests,
+            self.reset_requests,
+        );
+        let tokens = fraction(self.limit_tokens, self.remaining_tokens, self.reset_tokens);
+        match (requests, tokens) {
+            (Some(r), Some(t)) => Some(r.min(t)),
+            (r, t) => r.or(t),
+        }
+    }
+
+    /// Combine snapshots from several providers serving the same model:
+    /// limits and remaining allowances are summed, and resets report the
+    /// soonest window to refill.
+    pub fn aggregate<'a>(limits: impl IntoIterator<Item = &'a Self>) -> Option<Self> {
+        let sum = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.saturating_add(b)),
+            (a, b) => a.or(b),
+        };
+        let now = Instant::now();
+        let remaining_time = |l: &Self, d: Option<Duration>| {
+            d.map(|d| d.saturating_sub(now.saturating_duration_since(l.observed_at)))
+        };
+        let soonest = |a: Option<Duration>, b: Option<Duration>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        limits.into_iter().fold(None, |acc: Option<Self>, l| {
+            let l = Self {
+                reset_requests: remaining_time(l, l.reset_requests),
+                reset_tokens: remaining_time(l, l.reset_tokens),
+                retry_after: remaining_time(l, l.retry_after).filter(|d| !d.is_zero()),
+                observed_at: now,
+                ..l.clone()
+            };
+            Some(match acc {
+                None => l,
+                Some(acc) => Self {
+                    limit_requests: sum(acc.limit_requests, l.limit_requests),
+                    remaining_requests: sum(acc.remaining_requests, l.remaining_requests),
+                    reset_requests: soonest(acc.reset_requests, l.reset_requests),
+                    limit_tokens: sum(acc.limit_tokens, l.limit_tokens),
+                    remaining_tokens: sum(acc.remaining_tokens, l.remaining_tokens),
+                    reset_tokens: soonest(acc.reset_tokens, l.reset_tokens),
+                    // Only report a back-off if every provider asked for one.
+                    retry_after: acc.retry_after.zip(l.retry_after).map(|(a, b)| a.min(b)),
+                    observed_at: now,
+                },
+            })
+        })
+    }
+
+    /// Client-facing `x-upstream-ratelimit-*` headers for this snapshot.
+    pub fn client_headers(&self) -> Vec<(String, String)> {
+        let elapsed = self.observed_at.elapsed();
+        let secs = |d: Duration| format!("{}s", d.saturating_sub(elapsed).as_secs());
+        let mut headers = Vec::new();
+        let mut push = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                headers.push((format!("{CLIENT_HEADER_PREFIX}{name}"), value));
+            }
+        };
+        push("limit-requests", self.limit_requests.map(|v| v.to_string()));
+        push(
+            "remaining-requests",
+            self.remaining_requests.map(|v| v.to_string()),
+        );
+        push("reset-requests", self.reset_requests.map(secs));
+        push("limit-tokens", self.limit_tokens.map(|v| v.to_string()));
+        push(
+            "remaining-tokens",
+            self.remaining_tokens.map(|v| v.to_string()),
+        );
+        push("reset-tokens", self.reset_tokens.map(secs));
+        push(
+            "retry-after",
+            self.retry_after
+                .filter(|d| *d > elapsed)
+                .map(|d| d.saturating_sub(elapsed).as_secs().max(1).to_string()),
+        );
+        headers
+    }
+}
+
+/// Latest upstream rate-limit snapshot for one provider.
+#[derive(Debug, Default)]
+pub struct UpstreamRateLimitState {
+    latest: Mutex<Option<UpstreamRateLimit>>,
+}
+
+impl UpstreamRateLimitState {
+    /// Record the rate-limit headers of a response. A response without any
+    /// clears the previous snapshot.
+    pub fn record(&self, headers: &[(String, String)], retry_after: Option<Duration>) {
+        let limit = UpstreamRateLimit::from_headers(headers, retry_after);
+        *self.latest.lock().unwrap_or_else(|e| e.into_inner()) = limit;
+    }
+
+    pub fn get(&self) -> Option<UpstreamRateLimit> {
+        self.latest
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// Parse a reset value: plain seconds (`"20"`, `"1.5"`), a Go-style duration
+/// as sent by OpenAI (`"6m0s"`, `"20ms"`), or an RFC 3339 timestamp as sent
+/// by Anthropic.
+fn parse_reset(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    if let Ok(at) = chrono::DateTime::parse_from_rfc3339(value) {
+        let until = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+        return Some(until.to_std().unwrap_or_default());
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter(|&i| i > 0)?;
+        let number: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let scale = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        total += Duration::try_from_secs_f64(number * scale).ok()?;
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_reset_formats() {
+        assert_eq!(parse_reset("20"), Some(Duration::from_secs(20)));
+        assert_eq!(parse_reset("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(parse_reset("1h2m3s"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_reset("20ms"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_reset("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_reset("2000-01-01T00:00:00Z"), Some(Duration::ZERO));
+        assert_eq!(parse_reset("soon"), None);
+    }
+
+    #[test]
+    fn test_from_headers_openai_and_anthropic_layouts() {
+        let openai = UpstreamRateLimit::from_headers(
+            &headers(&[
+                ("x-ratelimit-limit-requests", "100"),
+                ("x-ratelimit-remaining-requests", "5"),
+                ("x-ratelimit-reset-requests", "30s"),
+                ("x-ratelimit-limit-tokens", "1000"),
+                ("x-ratelimit-remaining-tokens", "900"),
+            ]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(openai.remaining_requests, Some(5));
+        assert_eq!(openai.reset_requests, Some(Duration::from_secs(30)));
+        assert!((openai.headroom().unwrap() - 0.05).abs() < 1e-9);
+
+        let anthropic = UpstreamRateLimit::from_headers(
+            &headers(&[
+                ("x-ratelimit-requests-limit", "50"),
+                ("x-ratelimit-requests-remaining", "50"),
+                ("x-ratelimit-tokens-limit", "10000"),
+                ("x-ratelimit-tokens-remaining", "2500"),
+            ]),
+            None,
+        )
+        .unwrap();
+        assert_eq!(anthropic.limit_tokens, Some(10_000));
+        assert!((anthropic.headroom().unwrap() - 0.25).abs() < 1e-9);
+
+        assert!(
+            UpstreamRateLimit::from_headers(&headers(&[("content-type", "x")]), None).is_none()
+        );
+    }
+
+    #[test]
+    fn test_retry_after_means_no_headroom() {
+        let limit = UpstreamRateLimit::from_headers(&[], Some(Duration::from_secs(60))).unwrap();
+        assert_eq!(limit.headroom(), Some(0.0));
+        let (_, secs) = limit
+            .client_headers()
+            .into_iter()
+            .find(|(k, _)| k == "x-upstream-ratelimit-retry-after")
+            .unwrap();
+        assert!(matches!(secs.as_str(), "59" | "60"));
+    }
+
+    #[test]
+    fn test_aggregate_sums_allowances() {
+        let a = UpstreamRateLimit::from_headers(
+            &headers(&[
+                ("x-ratelimit-limit-requests", "100"),
+                ("x-ratelimit-remaining-requests", "10"),
+                ("x-ratelimit-reset-requests", "60s"),
+            ]),
+            None,
+        )
+        .unwrap();
+        let b = UpstreamRateLimit::from_headers(
+            &headers(&[
+                ("x-ratelimit-limit-requests", "50"),
+                ("x-ratelimit-remaining-requests", "40"),
+                ("x-ratelimit-reset-requests", "10s"),
+            ]),
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap();
+
+        let total = UpstreamRateLimit::aggregate([&a, &b]).unwrap();
+        assert_eq!(total.limit_requests, Some(150));
+        assert_eq!(total.remaining_requests, Some(50));
+        assert!(total.reset_requests.unwrap() <= Duration::from_secs(10));
+        assert_eq!(total.retry_after, None);
+
+        let headers = total.client_headers();
+        assert!(headers.contains(&(
+            "x-upstream-ratelimit-remaining-requests".to_string(),
+            "50".to_string()
+        )));
+        assert!(UpstreamRateLimit::aggregate([]).is_none());
+    }
+}
@@ -0,0 +1,271 @@
+//! Reasoning content exposure policy.
+//!
+//! Thinking models (Claude extended thinking, Gemini thoughts, Kiro reasoning
+//! events, o-series via Copilot) surface their chain of thought in the
+//! `reasoning_content` field of responses and stream deltas. Not every client
+//! understands that field, and some operators do not want it exposed at all,
+//! so each API key carries a [`ReasoningMode`] that is applied to every
+//! response before it leaves the proxy.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::providers::types::{ChatChunk, ChatResponse};
+
+const THINKING_OPEN: &str = "<thinking>";
+const THINKING_CLOSE: &str = "</thinking>";
+
+// MARK: - Mode
+
+/// How `reasoning_content` is presented to the client.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningMode {
+    /// Pass `reasoning_content` through unchanged.
+    #[default]
+    Forward,
+    /// Drop `reasoning_content` entirely.
+    Strip,
+    /// Fold reasoning into `content`, wrapped in `<thinking>` tags.
+    Inline,
+}
+
+impl std::fmt::Display for ReasoningMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Forward => write!(f, "forward"),
+            Self::Strip => write!(f, "strip"),
+            Self::Inline => write!(f, "inline"),
+        }
+    }
+}
+
+impl FromStr for ReasoningMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "forward" => Ok(Self::Forward),
+            "strip" => Ok(Self::Strip),
+            "inline" => Ok(Self::Inline),
+            _ => Err(format!("Unknown reasoning mode: {s}")),
+        }
+    }
+}
+
+// MARK: - Non-streaming
+
+/// Apply a reasoning mode to a complete (non-streaming) response.
+pub fn apply_to_response(mode: ReasoningMode, response: &mut ChatResponse) {
+    for choice in &mut response.choices {
+        let msg = &mut choice.message;
+        match mode {
+            ReasoningMode::Forward => {}
+            ReasoningMode::Strip => msg.reasoning_content = None,
+            ReasoningMode::Inline => {
+                if let Some(reasoning) = msg.reasoning_content.take() {
+                    let body = msg.content.take().unwrap_or_default();
+                    msg.content = Some(format!("{THINKING_OPEN}{reasoning}{THINKING_CLOSE}{body}"));
+                }
+            }
+        }
+    }
+}
+
+// MARK: - Streaming
+
+/// Per-stream reasoning filter.
+///
+/// Inline mode needs to remember which choices currently have an open
+/// `<thinking>` tag so the closing tag can be emitted when the first regular
+/// content delta (or the finish reason) arrives.
+#[derive(Debug, Default)]
+pub struct ReasoningFilter {
+    mode: ReasoningMode,
+    open: HashSet<u32>,
+}
+
+impl ReasoningFilter {
+    pub fn new(mode: ReasoningMode) -> Self {
+        Self {
+            mode,
+            open: HashSet::new(),
+        }
+    }
+
+    /// Whether this filter leaves chunks untouched.
+    pub fn is_passthrough(&self) -> bool {
+        self.mode == ReasoningMode::Forward
+    }
+
+    /// Rewrite a stream chunk in place according to the mode.
+    pub fn apply_chunk(&mut self, chunk: &mut ChatChunk) {
+        for choice in &mut chunk.choices {
+            let delta = &mut choice.delta;
+            match self.mode {
+                ReasoningMode::Forward => {}
+                ReasoningMode::Strip => delta.reasoning_content = None,
+                ReasoningMode::Inline => {
+                    let mut out = String::new();
+                    if let Some(reasoning) = delta.reasoning_content.take() {
+                        if self.open.insert(choice.index) {
+                            out.push_str(THINKING_OPEN);
+                        }
+                        out.push_str(&reasoning);
+                    }
+                    let closes = delta.content.as_deref().is_some_and(|c| !c.is_empty())
+                        || delta.tool_calls.is_some()
+                        || choice.finish_reason.is_some();
+                    if closes && self.open.remove(&choice.index) {
+                        out.push_str(THINKING_CLOSE);
+                    }
+                    if out.is_empty() {
+                        continue;
+                    }
+                    if let Some(content) = delta.content.take() {
+                        out.push_str(&content);
+                    }
+                    delta.content = Some(out);
+                }
+            }
+        }
+    }
+}
+
+// MARK: - Tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::types::{Choice, ChunkChoice, Delta, ResponseMessage, Usage};
+
+    fn response(reasoning: Option<&str>, content: Option<&str>) -> ChatResponse {
+        ChatResponse {
+            id: "r1".into(),
+            object: "chat.completion".into(),
+            created: 0,
+            model: "m".into(),
+            choices: vec![Choice {
+                index: 0,
+                message: ResponseMessage {
+                    role: "assistant".into(),
+                    content: content.map(String::from),
+                    reasoning_content: reasoning.map(String::from),
+                    tool_calls: None,
+                    grounding: None,
+                },
+                finish_reason: Some("stop".into()),
+            }],
+            usage: Usage::default(),
+            tool_calls_emulated: false,
+        }
+    }
+
+    fn chunk(reasoning: Option<&str>, content: Option<&str>, finish: Option<&str>) -> ChatChunk {
+        ChatChunk {
+            id: "c1".into(),
+            object: "chat.completion.chunk".into(),
+            created: 0,
+            model: "m".into(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta: Delta {
+                    role: None,
+                    content: content.map(String::from),
+                    reasoning_content: reasoning.map(String::from),
+                    tool_calls: None,
+                    grounding: None,
+                },
+                finish_reason: finish.map(String::from),
+            }],
+            usage: None,
+            tool_calls_emulated: false,
+        }
+    }
+
+    #[test]
+    fn test_mode_parse_and_display() {
+        for mode in [
+            ReasoningMode::Forward,
+            ReasoningMode::Strip,
+            ReasoningMode::Inline,
+        ] {
+            assert_eq!(mode.to_string().parse::<ReasoningMode>().unwrap(), mode);
+        }
+        assert!("bogus".parse::<ReasoningMode>().is_err());
+        assert_eq!(ReasoningMode::default(), ReasoningMode::Forward);
+    }
+
+    #[test]
+    fn test_response_forward_and_strip() {
+        let mut resp = response(Some("hmm"), Some("answer"));
+        apply_to_response(ReasoningMode::Forward, &mut resp);
+        assert_eq!(
+            resp.choices[0].message.reasoning_content.as_deref(),
+            Some("hmm")
+        );
+
+        apply_to_response(ReasoningMode::Strip, &mut resp);
+        assert!(resp.choices[0].message.reasoning_content.is_none());
+        assert_eq!(resp.choices[0].message.content.as_deref(), Some("answer"));
+    }
+
+    #[test]
+    fn test_response_inline() {
+        let mut resp = response(Some("hmm"), Some("answer"));
+        apply_to_response(ReasoningMode::Inline, &mut resp);
+        assert!(resp.choices[0].message.reasoning_content.is_none());
+        assert_eq!(
+            resp.choices[0].message.content.as_deref(),
+            Some("<thinking>hmm</thinking>answer")
+        );
+    }
+
+    #[test]
+    fn test_stream_strip() {
+        let mut filter = ReasoningFilter::new(ReasoningMode::Strip);
+        let mut c = chunk(Some("hmm"), None, None);
+        filter.apply_chunk(&mut c);
+        assert!(c.choices[0].delta.reasoning_content.is_none());
+        assert!(c.choices[0].delta.content.is_none());
+    }
+
+    #[test]
+    fn test_stream_inline_open_and_close() {
+        let mut filter = ReasoningFilter::new(ReasoningMode::Inline);
+
+        let mut c1 = chunk(Some("let me "), None, None);
+        let mut c2 = chunk(Some("think"), None, None);
+        let mut c3 = chunk(None, Some("Answer"), None);
+        let mut c4 = chunk(None, Some("!"), Some("stop"));
+        for c in [&mut c1, &mut c2, &mut c3, &mut c4] {
+            filter.apply_chunk(c);
+        }
+
+        let text: String = [&c1, &c2, &c3, &c4]
+            .iter()
+            .filter_map(|c| c.choices[0].delta.content.clone())
+            .collect();
+        assert_eq!(text, "<thinking>let me think</thinking>Answer!");
+        assert!(c1.choices[0].delta.reasoning_content.is_none());
+    }
+
+    #[test]
+    fn test_stream_inline_closes_on_finish() {
+        let mut filter = ReasoningFilter::new(ReasoningMode::Inline);
+        let mut c1 = chunk(Some("only thoughts"), None, None);
+        let mut c2 = chunk(None, None, Some("stop"));
+        filter.apply_chunk(&mut c1);
+        filter.apply_chunk(&mut c2);
+        assert_eq!(c2.choices[0].delta.content.as_deref(), Some("</thinking>"));
+    }
+
+    #[test]
+    fn test_stream_inline_without_reasoning_is_untouched() {
+        let mut filter = ReasoningFilter::new(ReasoningMode::Inline);
+        let mut c = chunk(None, Some("plain"), Some("stop"));
+        filter.apply_chunk(&mut c);
+        assert_eq!(c.choices[0].delta.content.as_deref(), Some("plain"));
+    }
+}
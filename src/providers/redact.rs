@@ -0,0 +1,229 @@
+//! Upstream error bodies, made safe to show to clients.
+//!
+//! Provider error responses can echo request headers, tokens or keys.
+//! [`sanitize`] masks anything that looks like a credential before a body
+//! leaves gaud, and [`summary`] picks the human-readable message out of it
+//! for the default error response.
+
+use serde_json::Value;
+
+/// Longest upstream body passed on to a client, in bytes.
+pub const MAX_BODY_BYTES: usize = 8 * 1024;
+/// Longest summary of a body without a recognisable message, in characters.
+const MAX_SUMMARY_CHARS: usize = 300;
+
+/// What a masked credential is replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+/// JSON keys whose values are always masked. Compared case-insensitively,
+/// ignoring `-` and `_`.
+const SECRET_KEYS: &[&str] = &[
+    "authorization",
+    "proxyauthorization",
+    "apikey",
+    "xapikey",
+    "xgoogapikey",
+    "key",
+    "token",
+    "accesstoken",
+    "refreshtoken",
+    "idtoken",
+    "sessiontoken",
+    "xamzsecuritytoken",
+    "secret",
+    "clientsecret",
+    "password",
+    "cookie",
+    "setcookie",
+];
+
+/// Prefixes of well-known credential formats: OpenAI/Anthropic and gaud
+/// keys, Google OAuth tokens and API keys, GitHub tokens, AWS access keys
+/// and JWTs.
+const SECRET_PREFIXES: &[&str] = &[
+    "sk-",
+    "ya29.",
+    "AIza",
+    "ghp_",
+    "gho_",
+    "ghu_",
+    "ghs_",
+    "github_pat_",
+    "AKIA",
+    "ASIA",
+    "eyJ",
+];
+
+/// Shortest word taken for a credential when it has a known prefix.
+const MIN_SECRET_LEN: usize = 16;
+
+/// `body` with credentials masked, cut to [`MAX_BODY_BYTES`]. JSON bodies
+/// stay JSON.
+pub fn sanitize(body: &str) -> String {
+    let sanitized = match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            sanitize_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => redact_text(body),
+    };
+    truncate(sanitized, MAX_BODY_BYTES)
+}
+
+/// Short, credential-free description of an upstream error body: its
+/// `error.message` (or similar) field when it has one, else the start of
+/// the body.
+pub fn summary(body: &str) -> String {
+    let message = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| message_of(&v).map(String::from));
+    let text = message.unwrap_or_else(|| body.split_whitespace().collect::<Vec<_>>().join(" "));
+    if text.is_empty() {
+        return "empty response body".to_string();
+    }
+    let text = redact_text(&text);
+    match text.char_indices().nth(MAX_SUMMARY_CHARS) {
+        Some((i, _)) => format!("{}...", &text[..i]),
+        None => text,
+    }
+}
+
+/// The message field of the error formats the providers use.
+fn message_of(value: &Value) -> Option<&str> {
+    [
+        &value["error"]["message"],
+        &value["message"],
+        &value["Message"],
+        &value["error_description"],
+        &value["error"],
+        &value["detail"],
+    ]
+    .into_iter()
+    .find_map(|v| v.as_str().filter(|s| !s.trim().is_empty()))
+}
+
+/// Mask credentials in `value` in place: the values of secret keys, and
+/// credential-looking words in strings.
+pub fn sanitize_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_secret_key(key) && !v.is_null() {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    sanitize_value(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sanitize_value),
+        Value::String(s) => *s = redact_text(s),
+        _ => {}
+    }
+}
+
+/// Whether a JSON key, header or query parameter named `key` holds a
+/// secret.
+pub fn is_secret_key(key: &str) -> bool {
+    let normalized: String = key
+        .chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    SECRET_KEYS.contains(&normalized.as_str())
+}
+
+/// Mask words that look like credentials, and the word after `Bearer`.
+pub fn redact_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut after_bearer = false;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let end = rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+        if end == 0 {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let word = &rest[..end];
+        if after_bearer || looks_secret(word) {
+            out.push_str(REDACTED);
+        } else {
+            out.push_str(word);
+        }
+        after_bearer = word.eq_ignore_ascii_case("bearer");
+        rest = &rest[end..];
+    }
+    out
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~')
+}
+
+fn looks_secret(word: &str) -> bool {
+    word.len() >= MIN_SECRET_LEN && SECRET_PREFIXES.iter().any(|p| word.starts_with(p))
+}
+
+/// Cut `s` to at most `max_bytes` on a character boundary, marking the
+/// cut with `...`.
+pub fn truncate(mut s: String, max_bytes: usize) -> String {
+    if s.len() > max_bytes {
+        let mut end = max_bytes;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+        s.push_str("...");
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_json_body() {
+        let body = r#"{"error":{"message":"bad key sk-ant-REDACTED","type":"auth"},
+            "request":{"headers":{"Authorization":"Bearer xyz","x-api-key":"k"},"model":"m"}}"#;
+        let sanitized: Value = serde_json::from_str(&sanitize(body)).unwrap();
+        assert_eq!(sanitized["error"]["message"], "bad key [redacted]");
+        assert_eq!(sanitized["error"]["type"], "auth");
+        assert_eq!(sanitized["request"]["headers"]["Authorization"], REDACTED);
+        assert_eq!(sanitized["request"]["headers"]["x-api-key"], REDACTED);
+        assert_eq!(sanitized["request"]["model"], "m");
+    }
+
+    #[test]
+    fn test_sanitize_text_body() {
+        assert_eq!(
+            sanitize("denied: Authorization: Bearer abc.def for ya29.a0AfH6SMBxxxxxxxxxxxx"),
+            "denied: Authorization: Bearer [redacted] for [redacted]"
+        );
+        // Ordinary words with a credential prefix are left alone.
+        assert_eq!(sanitize("sk-learn failed"), "sk-learn failed");
+
+        let long = "x".repeat(MAX_BODY_BYTES + 10);
+        assert_eq!(sanitize(&long).len(), MAX_BODY_BYTES + 3);
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            summary(r#"{"error":{"message":"Prompt is too long","type":"invalid_request_error"}}"#),
+            "Prompt is too long"
+        );
+        assert_eq!(
+            summary(
+                r#"{"message":"Input is too long.","reason":"CONTENT_LENGTH_EXCEEDS_THRESHOLD"}"#
+            ),
+            "Input is too long."
+        );
+        assert_eq!(
+            summary("<html>\n  Bad   Gateway\n</html>"),
+            "<html> Bad Gateway </html>"
+        );
+        assert_eq!(summary(""), "empty response body");
+        assert!(summary(&"word ".repeat(200)).ends_with("..."));
+    }
+}
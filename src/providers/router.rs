@@ -4,21 +4,33 @@
 //! based on the `model` field. Supports multiple routing strategies, circuit
 //! breaker health tracking, and automatic fallback on failure.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use futures::Stream;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
-use crate::providers::health::{CircuitBreaker, CircuitState};
+use crate::budget::scheduler::Priority;
+use crate::config::{ProviderQuotaConfig, VirtualModelConfig};
+use crate::notify::{Notification, Notifier};
+use crate::providers::dry_run::DryRunProvider;
+use crate::providers::failures::{FailedAttempt, FailureLog};
+use crate::providers::health::{CircuitBreaker, CircuitState, HealthProbe};
+use crate::providers::latency::{StreamLatency, TimedStream};
 use crate::providers::pricing::ModelPricing;
+use crate::providers::quota::{ProviderQuota, ProviderQuotaStatus};
+use crate::providers::ratelimit::UpstreamRateLimit;
 use crate::providers::retry::{RetryPolicy, execute_provider_with_retry};
-use crate::providers::types::{ChatChunk, ChatRequest, ChatResponse};
-use crate::providers::{LlmProvider, ProviderError};
+use crate::providers::rules::{self, RequestFacts, RoutingDecision, RoutingRule};
+use crate::providers::types::{
+    ChatChunk, ChatRequest, ChatResponse, ImageGenerationRequest, ImageGenerationResponse,
+    SpeechRequest, TranscriptionRequest, TranscriptionResponse,
+};
+use crate::providers::{LlmProvider, NativeStream, ProviderError, SpeechResponse};
 
 // ---------------------------------------------------------------------------
 // Routing Strategy
@@ -40,6 +52,14 @@ pub enum RoutingStrategy {
     Random,
 }
 
+/// Prefix of virtual model names (`gaud:smart`), which the router resolves
+/// to one of a configured list of concrete models.
+pub const VIRTUAL_MODEL_PREFIX: &str = "gaud:";
+
+/// Providers with less than this fraction of their upstream rate limit left
+/// are tried after all others.
+const NEAR_LIMIT_HEADROOM: f64 = 0.1;
+
 // ---------------------------------------------------------------------------
 // Per-provider stats
 // ---------------------------------------------------------------------------
@@ -70,6 +90,49 @@ struct RegisteredProvider {
     provider: Arc<dyn LlmProvider>,
     circuit: CircuitBreaker,
     stats: ProviderStats,
+    quota: ProviderQuota,
+    last_probe: Option<HealthProbe>,
+}
+
+impl RegisteredProvider {
+    /// Count a failed request. Quota exhaustion parks the provider until its
+    /// quota resets instead of tripping the circuit breaker.
+    fn record_failure(&mut self, err: &ProviderError, notifier: Option<&Notifier>) {
+        if matches!(err, ProviderError::QuotaExhausted { .. }) {
+            self.quota.mark_exhausted(chrono::Utc::now());
+            info!(
+                provider = %self.provider.id(),
+                "Provider quota exhausted, skipping until reset"
+            );
+        } else {
+            self.record_circuit_failure(notifier);
+        }
+        self.stats.failed_requests += 1;
+        self.stats.total_requests += 1;
+    }
+
+    /// Count a failure against the circuit breaker, announcing a trip.
+    fn record_circuit_failure(&mut self, notifier: Option<&Notifier>) {
+        let was_open = self.circuit.state() == CircuitState::Open;
+        self.circuit.record_failure();
+        if !was_open && self.circuit.state() == CircuitState::Open {
+            warn!(provider = %self.provider.id(), "Circuit breaker opened");
+            if let Some(notifier) = notifier {
+                notifier.notify(Notification::CircuitOpen {
+                    provider: self.provider.id().to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Whether a request that failed on every provider points at a provider
+/// problem rather than at the request itself.
+fn is_outage(err: &ProviderError) -> bool {
+    match err.status_code() {
+        Some(status) => status >= 500 || status == 429,
+        None => !matches!(err, ProviderError::NoProvider(_)),
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -89,6 +152,30 @@ pub struct ProviderRouter {
     rr_index: usize,
     /// Retry policy applied to each individual provider call before fallback.
     retry_policy: RetryPolicy,
+    /// Where circuit trips and outages are announced.
+    notifier: Option<Arc<Notifier>>,
+    /// Virtual model name (without prefix) -> concrete members.
+    virtual_models: BTreeMap<String, VirtualModelConfig>,
+    /// Admin routing rules, first match wins.
+    rules: Vec<RoutingRule>,
+    /// Time to first token and chunk gaps of the streams opened.
+    latency: Arc<StreamLatency>,
+    /// Recent requests every provider failed.
+    failures: Arc<FailureLog>,
+}
+
+/// How the router would handle a request, from [`ProviderRouter::simulate`].
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingSimulation {
+    /// The model after resolving virtual models.
+    pub model: String,
+    /// The decision of the matching rule, if any.
+    pub decision: Option<RoutingDecision>,
+    /// Providers in the order they would be tried.
+    pub providers: Vec<String>,
+    /// Providers supporting the model that would be skipped because their
+    /// circuit is open or their quota is used up.
+    pub unavailable: Vec<String>,
 }
 
 impl ProviderRouter {
@@ -100,6 +187,11 @@ impl ProviderRouter {
             strategy: RoutingStrategy::Priority,
             rr_index: 0,
             retry_policy: RetryPolicy::new(),
+            notifier: None,
+            virtual_models: BTreeMap::new(),
+            rules: Vec::new(),
+            latency: Arc::new(StreamLatency::new()),
+            failures: Arc::new(FailureLog::default()),
         }
     }
 
@@ -111,6 +203,24 @@ impl ProviderRouter {
         }
     }
 
+    /// Announce circuit-breaker trips and provider outages through `notifier`.
+    pub fn set_notifier(&mut self, notifier: Arc<Notifier>) {
+        self.notifier = Some(notifier);
+    }
+
+    /// Tell operators that every provider for `model` failed.
+    fn report_failure(&self, model: &str, err: ProviderError) -> ProviderError {
+        if let Some(notifier) = &self.notifier
+            && is_outage(&err)
+        {
+            notifier.notify(Notification::ProviderOutage {
+                model: model.to_string(),
+                error: err.to_string(),
+            });
+        }
+        err
+    }
+
     /// Set the retry policy for provider calls.
     pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
         self.retry_policy = policy;
@@ -131,10 +241,85 @@ impl ProviderRouter {
                 provider,
                 circuit: CircuitBreaker::new(),
                 stats: ProviderStats::default(),
+                quota: ProviderQuota::default(),
+                last_probe: None,
             },
         );
     }
 
+    /// Replace the providers of config section `section` (`kiro` also owns
+    /// the `kiro:<profile>` providers) with `providers`, keeping the
+    /// section's place in the priority order; a new section goes last. A
+    /// provider keeping its ID keeps its stats and quota usage, but its
+    /// circuit starts closed.
+    pub fn replace_section(&mut self, section: &str, providers: Vec<Arc<dyn LlmProvider>>) {
+        let in_section = |id: &str| {
+            id.strip_prefix(section)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(':'))
+        };
+        let at = self
+            .order
+            .iter()
+            .position(|id| in_section(id))
+            .unwrap_or(self.order.len());
+        let mut old = HashMap::new();
+        for id in self.order.iter().filter(|id| in_section(id)) {
+            if let Some(entry) = self.providers.remove(id) {
+                old.insert(id.clone(), entry);
+            }
+        }
+        self.order.retain(|id| !in_section(id));
+
+        for (i, provider) in providers.into_iter().enumerate() {
+            let id = provider.id().to_string();
+            let (stats, quota) = old
+                .remove(&id)
+                .map(|e| (e.stats, e.quota))
+                .unwrap_or_default();
+            self.order.insert(at + i, id.clone());
+            self.providers.insert(
+                id,
+                RegisteredProvider {
+                    provider,
+                    circuit: CircuitBreaker::new(),
+                    stats,
+                    quota,
+                    last_probe: None,
+                },
+            );
+        }
+    }
+
+    /// Set the daily/monthly quota of a registered provider's account.
+    pub fn set_quota(&mut self, provider_id: &str, config: ProviderQuotaConfig) {
+        if let Some(e) = self.providers.get_mut(provider_id) {
+            e.quota.set_config(config);
+        }
+    }
+
+    /// Put a registered provider in dry-run mode: it answers with canned
+    /// responses from now on (see [`DryRunProvider`]).
+    pub fn set_dry_run(&mut self, provider_id: &str) {
+        if let Some(e) = self.providers.get_mut(provider_id) {
+            e.provider = Arc::new(DryRunProvider::new(Arc::clone(&e.provider)));
+        }
+    }
+
+    /// Replace the virtual model definitions.
+    pub fn set_virtual_models(&mut self, virtual_models: BTreeMap<String, VirtualModelConfig>) {
+        self.virtual_models = virtual_models;
+    }
+
+    /// Configured virtual models, keyed by name without the prefix.
+    pub fn virtual_models(&self) -> &BTreeMap<String, VirtualModelConfig> {
+        &self.virtual_models
+    }
+
+    /// Replace the routing rules.
+    pub fn set_rules(&mut self, rules: Vec<RoutingRule>) {
+        self.rules = rules;
+    }
+
     /// Change the routing strategy at runtime.
     pub fn set_strategy(&mut self, strategy: RoutingStrategy) {
         self.strategy = strategy;
@@ -172,11 +357,30 @@ impl ProviderRouter {
         self.providers.get(provider_id).map(|e| e.circuit.state())
     }
 
+    /// Get the circuit breaker of a provider.
+    pub fn circuit(&self, provider_id: &str) -> Option<&CircuitBreaker> {
+        self.providers.get(provider_id).map(|e| &e.circuit)
+    }
+
+    /// Result of the last [`health_check_all`](Self::health_check_all) for
+    /// a provider, if one ran.
+    pub fn last_probe(&self, provider_id: &str) -> Option<HealthProbe> {
+        self.providers.get(provider_id).and_then(|e| e.last_probe)
+    }
+
     /// Get stats for a provider.
     pub fn stats(&self, provider_id: &str) -> Option<&ProviderStats> {
         self.providers.get(provider_id).map(|e| &e.stats)
     }
 
+    /// Get quota usage for a provider.
+    pub fn quota_status(&self, provider_id: &str) -> Option<ProviderQuotaStatus> {
+        let now = chrono::Utc::now();
+        self.providers
+            .get(provider_id)
+            .map(|e| e.quota.clone().status(now))
+    }
+
     /// Reset a provider's circuit breaker.
     pub fn reset_circuit(&mut self, provider_id: &str) {
         if let Some(e) = self.providers.get_mut(provider_id) {
@@ -189,6 +393,217 @@ impl ProviderRouter {
         &self.order
     }
 
+    /// Streaming latency histograms per provider and model.
+    pub fn stream_latency(&self) -> Arc<StreamLatency> {
+        Arc::clone(&self.latency)
+    }
+
+    /// Recent chat requests every provider failed.
+    pub fn failures(&self) -> Arc<FailureLog> {
+        Arc::clone(&self.failures)
+    }
+
+    /// Upstream rate-limit state summed over every provider serving `model`.
+    pub fn upstream_rate_limit(&self, model: &str) -> Option<UpstreamRateLimit> {
+        let limits: Vec<UpstreamRateLimit> = self
+            .order
+            .iter()
+            .filter_map(|id| self.providers.get(id))
+            .filter(|e| e.provider.supports_model(model))
+            .filter_map(|e| e.provider.upstream_rate_limit())
+            .collect();
+        UpstreamRateLimit::aggregate(&limits)
+    }
+
+    /// Premium requests a chat request to `model` consumes, as charged by
+    /// the provider that would serve it first (the prefix-mapped provider,
+    /// then registration order).
+    pub fn premium_requests(&self, model: &str) -> f64 {
+        self.first_provider(model)
+            .map_or(0.0, |e| e.provider.premium_requests(model))
+    }
+
+    /// Estimated input tokens of `request`, as counted by the provider that
+    /// would serve it first. `None` if no provider serves the model.
+    pub fn count_tokens(&self, request: &ChatRequest) -> Option<u64> {
+        self.first_provider(&request.model)
+            .map(|e| e.provider.count_tokens(request))
+    }
+
+    /// The provider a request to `model` goes to first: the prefix-mapped
+    /// provider, then registration order.
+    fn first_provider(&self, model: &str) -> Option<&RegisteredProvider> {
+        Self::resolve_provider_id(model)
+            .and_then(|id| self.providers.get(id))
+            .filter(|e| e.provider.supports_model(model))
+            .or_else(|| {
+                self.order
+                    .iter()
+                    .filter_map(|id| self.providers.get(id))
+                    .find(|e| e.provider.supports_model(model))
+            })
+    }
+
+    /// Whether a provider reported less than [`NEAR_LIMIT_HEADROOM`] of its
+    /// upstream rate limit remaining.
+    fn is_near_limit(&self, provider_id: &str) -> bool {
+        self.providers
+            .get(provider_id)
+            .and_then(|e| e.provider.upstream_rate_limit())
+            .and_then(|l| l.headroom())
+            .is_some_and(|h| h < NEAR_LIMIT_HEADROOM)
+    }
+
+    // -- routing rules -------------------------------------------------------
+
+    /// Evaluate the routing rules for a request, returning the decision of
+    /// the first one that matches. Pass the provider order on to
+    /// [`Self::chat_with_order`] or [`Self::stream_chat_with_order`].
+    pub fn route(&self, facts: &RequestFacts<'_>) -> Option<RoutingDecision> {
+        let decision = rules::evaluate(&self.rules, facts)?;
+        info!(
+            rule = %decision.rule,
+            model = %facts.model,
+            user = %facts.user_name,
+            providers = ?decision.providers,
+            cache = ?decision.cache,
+            max_tokens = ?decision.max_tokens,
+            "Routing rule matched"
+        );
+        Some(decision)
+    }
+
+    /// Routing rules, in evaluation order.
+    pub fn rules(&self) -> &[RoutingRule] {
+        &self.rules
+    }
+
+    /// Work out how a request described by `facts` would be routed, without
+    /// sending it: the model a virtual model resolves to, the matching rule
+    /// and the order providers would be tried in. `rules` replaces the
+    /// configured rules, for trying out edits before saving them. The
+    /// round-robin position is left where it was.
+    pub fn simulate(
+        &mut self,
+        facts: &RequestFacts<'_>,
+        rules: Option<&[RoutingRule]>,
+        priority: Priority,
+    ) -> Result<RoutingSimulation, ProviderError> {
+        let rr_index = self.rr_index;
+        let model = self.resolve_model(facts.model)?;
+        let facts = RequestFacts {
+            model: &model,
+            ..facts.clone()
+        };
+        let decision = rules::evaluate(rules.unwrap_or(&self.rules), &facts);
+        let preferred = decision.as_ref().map_or(&[][..], |d| &d.providers[..]);
+        let providers = self.candidates_preferring(&model, preferred, priority);
+        self.rr_index = rr_index;
+        let unavailable = self
+            .order
+            .iter()
+            .filter(|id| {
+                !providers.contains(id)
+                    && self
+                        .providers
+                        .get(*id)
+                        .is_some_and(|e| e.provider.supports_model(&model))
+            })
+            .cloned()
+            .collect();
+        Ok(RoutingSimulation {
+            model,
+            decision,
+            providers,
+            unavailable,
+        })
+    }
+
+    // -- virtual models ------------------------------------------------------
+
+    /// Resolve `model` to a concrete model. Anything but a `gaud:` name is
+    /// returned unchanged. For a virtual model, members some healthy provider
+    /// can serve are preferred; among those, configured weights pick one at
+    /// random, otherwise the routing strategy decides.
+    pub fn resolve_model(&mut self, model: &str) -> Result<String, ProviderError> {
+        let Some(name) = model.strip_prefix(VIRTUAL_MODEL_PREFIX) else {
+            return Ok(model.to_string());
+        };
+        let Some(group) = self.virtual_models.get(name).cloned() else {
+            return Err(ProviderError::NoProvider(model.to_string()));
+        };
+
+        let mut members: Vec<(&String, u32)> = group
+            .models
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m, group.weights.get(i).copied().unwrap_or(1)))
+            .collect();
+        let servable: Vec<_> = members
+            .iter()
+            .copied()
+            .filter(|(m, _)| self.can_serve(m))
+            .collect();
+        if !servable.is_empty() {
+            members = servable;
+        }
+
+        let chosen = if !group.weights.is_empty() {
+            let total: u32 = members.iter().map(|(_, w)| w).sum();
+            if total == 0 {
+                members[0].0
+            } else {
+                let mut pick = rand::rng().random_range(0..total);
+                members
+                    .iter()
+                    .find(|(_, w)| {
+                        let hit = pick < *w;
+                        pick = pick.saturating_sub(*w);
+                        hit
+                    })
+                    .map_or(members[0].0, |(m, _)| m)
+            }
+        } else {
+            match self.strategy {
+                RoutingStrategy::Priority => members[0].0,
+                RoutingStrategy::RoundRobin => {
+                    let i = self.rr_index % members.len();
+                    self.rr_index = self.rr_index.wrapping_add(1);
+                    members[i].0
+                }
+                RoutingStrategy::LeastUsed => {
+                    members
+                        .iter()
+                        .min_by_key(|(m, _)| self.requests_served(m))
+                        .unwrap()
+                        .0
+                }
+                RoutingStrategy::Random => members[rand::rng().random_range(0..members.len())].0,
+            }
+        };
+
+        info!(model = %model, resolved = %chosen, "Resolved virtual model");
+        Ok(chosen.clone())
+    }
+
+    /// Whether some provider supporting `model` is currently usable.
+    fn can_serve(&mut self, model: &str) -> bool {
+        let now = chrono::Utc::now();
+        self.providers.values_mut().any(|e| {
+            e.provider.supports_model(model) && e.circuit.can_execute() && e.quota.available(now)
+        })
+    }
+
+    /// Fewest requests handled by any provider supporting `model`.
+    fn requests_served(&self, model: &str) -> u64 {
+        self.providers
+            .values()
+            .filter(|e| e.provider.supports_model(model))
+            .map(|e| e.stats.total_requests)
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
     // -- model -> provider resolution ----------------------------------------
 
     /// Determine which provider should handle the given model string.
@@ -221,14 +636,59 @@ impl ProviderRouter {
     /// Find all registered providers that can handle `model`, ordered by the
     /// active routing strategy.
     fn candidates_for_model(&mut self, model: &str) -> Vec<String> {
+        self.candidates_matching(model, |p, m| p.supports_model(m))
+    }
+
+    /// Like [`Self::candidates_for_model`], with the providers in
+    /// `preferred` moved to the front in that order. Batch requests leave
+    /// providers near their upstream rate limits to interactive traffic,
+    /// unless no other provider is left.
+    fn candidates_preferring(
+        &mut self,
+        model: &str,
+        preferred: &[String],
+        priority: Priority,
+    ) -> Vec<String> {
+        let mut candidates = self.candidates_for_model(model);
+        if priority.is_batch() && candidates.iter().any(|id| !self.is_near_limit(id)) {
+            candidates.retain(|id| {
+                let near = self.is_near_limit(id);
+                if near {
+                    debug!(provider = %id, "Skipping provider near its upstream rate limit for batch request");
+                }
+                !near
+            });
+        }
+        if !preferred.is_empty() {
+            candidates.sort_by_key(|id| {
+                preferred
+                    .iter()
+                    .position(|p| p == id)
+                    .unwrap_or(preferred.len())
+            });
+        }
+        candidates
+    }
+
+    /// Find all registered providers for which `supports` accepts `model`,
+    /// ordered by the active routing strategy.
+    fn candidates_matching(
+        &mut self,
+        model: &str,
+        supports: impl Fn(&dyn LlmProvider, &str) -> bool,
+    ) -> Vec<String> {
         // 1. Collect IDs of providers that support this model AND whose circuit
-        //    breaker allows execution.
+        //    breaker allows execution AND whose account quota is not used up.
         let mut candidates: Vec<String> = Vec::new();
+        let now = chrono::Utc::now();
 
         // First try the prefix-mapped provider.
         if let Some(primary_id) = Self::resolve_provider_id(model) {
             if let Some(entry) = self.providers.get_mut(primary_id) {
-                if entry.provider.supports_model(model) && entry.circuit.can_execute() {
+                if supports(entry.provider.as_ref(), model)
+                    && entry.circuit.can_execute()
+                    && entry.quota.available(now)
+                {
                     candidates.push(primary_id.to_string());
                 }
             }
@@ -240,7 +700,10 @@ impl ProviderRouter {
                 continue;
             }
             if let Some(entry) = self.providers.get_mut(id) {
-                if entry.provider.supports_model(model) && entry.circuit.can_execute() {
+                if supports(entry.provider.as_ref(), model)
+                    && entry.circuit.can_execute()
+                    && entry.quota.available(now)
+                {
                     candidates.push(id.clone());
                 }
             }
@@ -276,6 +739,18 @@ impl ProviderRouter {
             }
         }
 
+        // 3. Try providers that are close to their upstream rate limits last,
+        //    keeping the strategy order within each group.
+        if candidates.len() > 1 {
+            candidates.sort_by_cached_key(|id| {
+                let near = self.is_near_limit(id);
+                if near {
+                    debug!(provider = %id, "Deprioritizing provider near its upstream rate limit");
+                }
+                near
+            });
+        }
+
         candidates
     }
 
@@ -285,12 +760,24 @@ impl ProviderRouter {
     /// in retry logic (with provider-supplied `retry_after` when available)
     /// before falling back to the next provider.
     pub async fn chat(&mut self, request: &ChatRequest) -> Result<ChatResponse, ProviderError> {
-        let candidates = self.candidates_for_model(&request.model);
+        self.chat_with_order(request, &[], Priority::Default).await
+    }
+
+    /// [`Self::chat`], trying the providers in `preferred` first (as decided
+    /// by a routing rule), and falling back as `priority` allows.
+    pub async fn chat_with_order(
+        &mut self,
+        request: &ChatRequest,
+        preferred: &[String],
+        priority: Priority,
+    ) -> Result<ChatResponse, ProviderError> {
+        let candidates = self.candidates_preferring(&request.model, preferred, priority);
         if candidates.is_empty() {
             return Err(ProviderError::NoProvider(request.model.clone()));
         }
 
         let mut last_err: Option<ProviderError> = None;
+        let mut attempts = Vec::new();
 
         for id in &candidates {
             let provider = {
@@ -313,6 +800,7 @@ impl ProviderRouter {
                     let latency_ms = start.elapsed().as_millis() as u64;
                     if let Some(entry) = self.providers.get_mut(id) {
                         entry.circuit.record_success();
+                        entry.quota.record_request(chrono::Utc::now());
                         entry.stats.successful_requests += 1;
                         entry.stats.total_requests += 1;
                         entry.stats.total_latency_ms += latency_ms;
@@ -328,19 +816,20 @@ impl ProviderRouter {
                 Err(e) => {
                     warn!(provider = %id, error = %e, "Chat failed, trying next provider");
                     if let Some(entry) = self.providers.get_mut(id) {
-                        entry.circuit.record_failure();
-                        entry.stats.failed_requests += 1;
-                        entry.stats.total_requests += 1;
+                        entry.record_failure(&e, self.notifier.as_deref());
                     }
+                    attempts.push(FailedAttempt::new(id, &e));
                     last_err = Some(e);
                 }
             }
         }
 
-        Err(last_err.unwrap_or_else(|| ProviderError::AllFailed {
+        let err = last_err.unwrap_or_else(|| ProviderError::AllFailed {
             model: request.model.clone(),
             errors: vec!["No providers available".to_string()],
-        }))
+        });
+        self.failures.record("chat", request, attempts, &err);
+        Err(self.report_failure(&request.model, err))
     }
 
     /// Route a streaming chat request. Each provider's stream initiation is
@@ -351,12 +840,25 @@ impl ProviderRouter {
         request: &ChatRequest,
     ) -> Result<Pin<Box<dyn Stream<Item = Result<ChatChunk, ProviderError>> + Send>>, ProviderError>
     {
-        let candidates = self.candidates_for_model(&request.model);
+        self.stream_chat_with_order(request, &[], Priority::Default)
+            .await
+    }
+
+    /// [`Self::stream_chat`], trying the providers in `preferred` first.
+    pub async fn stream_chat_with_order(
+        &mut self,
+        request: &ChatRequest,
+        preferred: &[String],
+        priority: Priority,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<ChatChunk, ProviderError>> + Send>>, ProviderError>
+    {
+        let candidates = self.candidates_preferring(&request.model, preferred, priority);
         if candidates.is_empty() {
             return Err(ProviderError::NoProvider(request.model.clone()));
         }
 
         let mut last_err: Option<ProviderError> = None;
+        let mut attempts = Vec::new();
 
         for id in &candidates {
             let provider = {
@@ -366,6 +868,7 @@ impl ProviderRouter {
 
             debug!(provider = %id, model = %request.model, "Attempting stream_chat");
 
+            let started = Instant::now();
             let result = execute_provider_with_retry(&self.retry_policy, || {
                 let p = Arc::clone(&provider);
                 let req = request.clone();
@@ -381,27 +884,202 @@ impl ProviderRouter {
                         // the actual success/failure of data delivery is handled
                         // by the caller consuming the stream.
                         entry.circuit.record_success();
+                        entry.quota.record_request(chrono::Utc::now());
                         entry.stats.successful_requests += 1;
                     }
                     info!(provider = %id, model = %request.model, "Stream started");
-                    return Ok(stream);
+                    let latency = Arc::clone(&self.latency);
+                    return Ok(Box::pin(TimedStream::new(
+                        stream,
+                        latency,
+                        id,
+                        &request.model,
+                        started,
+                    )));
                 }
                 Err(e) => {
                     warn!(provider = %id, error = %e, "Stream init failed, trying next");
                     if let Some(entry) = self.providers.get_mut(id) {
-                        entry.circuit.record_failure();
-                        entry.stats.failed_requests += 1;
-                        entry.stats.total_requests += 1;
+                        entry.record_failure(&e, self.notifier.as_deref());
                     }
+                    attempts.push(FailedAttempt::new(id, &e));
                     last_err = Some(e);
                 }
             }
         }
 
-        Err(last_err.unwrap_or_else(|| ProviderError::AllFailed {
+        let err = last_err.unwrap_or_else(|| ProviderError::AllFailed {
             model: request.model.clone(),
             errors: vec!["No providers available for streaming".to_string()],
-        }))
+        });
+        self.failures.record("stream", request, attempts, &err);
+        Err(self.report_failure(&request.model, err))
+    }
+
+    // -- images --------------------------------------------------------------
+
+    /// Route an image generation request to providers that support the image
+    /// model, with the same retry and fallback behavior as [`Self::chat`].
+    pub async fn generate_images(
+        &mut self,
+        request: &ImageGenerationRequest,
+    ) -> Result<ImageGenerationResponse, ProviderError> {
+        let response = self
+            .dispatch(
+                &request.model,
+                "image generation",
+                |p, m| p.supports_image_model(m),
+                |p| {
+                    let req = request.clone();
+                    async move { p.generate_images(&req).await }
+                },
+            )
+            .await?;
+        info!(model = %request.model, images = response.data.len(), "Image generation succeeded");
+        Ok(response)
+    }
+
+    /// Route an audio transcription request to providers that support the
+    /// audio model.
+    pub async fn transcribe(
+        &mut self,
+        request: &TranscriptionRequest,
+    ) -> Result<TranscriptionResponse, ProviderError> {
+        self.dispatch(
+            &request.model,
+            "transcription",
+            |p, m| p.supports_audio_model(m),
+            |p| {
+                let req = request.clone();
+                async move { p.transcribe(&req).await }
+            },
+        )
+        .await
+    }
+
+    /// Route a text-to-speech request to providers that support the audio
+    /// model.
+    ///
+    /// Retries and fallback cover opening the audio stream only; errors in
+    /// the middle of the stream are surfaced to the caller.
+    pub async fn speech(
+        &mut self,
+        request: &SpeechRequest,
+    ) -> Result<SpeechResponse, ProviderError> {
+        self.dispatch(
+            &request.model,
+            "speech",
+            |p, m| p.supports_audio_model(m),
+            |p| {
+                let req = request.clone();
+                async move { p.speech(&req).await }
+            },
+        )
+        .await
+    }
+
+    // -- native Gemini API ------------------------------------------------------
+
+    /// Route a native Gemini `generateContent` request to providers that
+    /// accept the model.
+    pub async fn generate_content(
+        &mut self,
+        model: &str,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, ProviderError> {
+        self.dispatch(
+            model,
+            "generateContent",
+            |p, m| p.supports_gemini_native(m),
+            |p| {
+                let req = request.clone();
+                async move { p.generate_content(model, &req).await }
+            },
+        )
+        .await
+    }
+
+    /// Route a native Gemini `streamGenerateContent` request. As with
+    /// [`Self::speech`], retries and fallback cover opening the stream only.
+    pub async fn stream_generate_content(
+        &mut self,
+        model: &str,
+        request: &serde_json::Value,
+    ) -> Result<NativeStream, ProviderError> {
+        self.dispatch(
+            model,
+            "streamGenerateContent",
+            |p, m| p.supports_gemini_native(m),
+            |p| {
+                let req = request.clone();
+                async move { p.stream_generate_content(model, &req).await }
+            },
+        )
+        .await
+    }
+
+    /// Run a non-chat operation against every provider for which `supports`
+    /// returns true, in priority order, retrying and falling back like
+    /// [`Self::chat`].
+    async fn dispatch<T, F, Fut>(
+        &mut self,
+        model: &str,
+        operation: &str,
+        supports: impl Fn(&dyn LlmProvider, &str) -> bool,
+        call: F,
+    ) -> Result<T, ProviderError>
+    where
+        F: Fn(Arc<dyn LlmProvider>) -> Fut,
+        Fut: std::future::Future<Output = Result<T, ProviderError>>,
+    {
+        let candidates = self.candidates_matching(model, supports);
+        if candidates.is_empty() {
+            return Err(ProviderError::NoProvider(model.to_string()));
+        }
+
+        let mut last_err: Option<ProviderError> = None;
+
+        for id in &candidates {
+            let provider = {
+                let entry = self.providers.get(id).unwrap();
+                Arc::clone(&entry.provider)
+            };
+
+            debug!(provider = %id, model = %model, operation, "Attempting request");
+            let start = Instant::now();
+
+            let result =
+                execute_provider_with_retry(&self.retry_policy, || call(Arc::clone(&provider)))
+                    .await;
+
+            match result {
+                Ok(response) => {
+                    let latency_ms = start.elapsed().as_millis() as u64;
+                    if let Some(entry) = self.providers.get_mut(id) {
+                        entry.circuit.record_success();
+                        entry.quota.record_request(chrono::Utc::now());
+                        entry.stats.successful_requests += 1;
+                        entry.stats.total_requests += 1;
+                        entry.stats.total_latency_ms += latency_ms;
+                    }
+                    debug!(provider = %id, model = %model, operation, latency_ms, "Request succeeded");
+                    return Ok(response);
+                }
+                Err(e) => {
+                    warn!(provider = %id, operation, error = %e, "Request failed, trying next provider");
+                    if let Some(entry) = self.providers.get_mut(id) {
+                        entry.record_failure(&e, self.notifier.as_deref());
+                    }
+                    last_err = Some(e);
+                }
+            }
+        }
+
+        let err = last_err.unwrap_or_else(|| ProviderError::AllFailed {
+            model: model.to_string(),
+            errors: vec![format!("No providers available for {operation}")],
+        });
+        Err(self.report_failure(model, err))
     }
 
     /// Run health checks against all registered providers.
@@ -415,17 +1093,91 @@ impl ProviderRouter {
 
         for (id, provider) in entries {
             let healthy = provider.health_check().await;
-            if let Some(entry) = self.providers.get_mut(&id) {
-                if healthy {
-                    entry.circuit.record_success();
-                } else {
-                    entry.circuit.record_failure();
-                }
-            }
+            self.record_probe(&id, healthy);
             results.insert(id, healthy);
         }
         results
     }
+
+    /// Run [`LlmProvider::diagnose`] against one provider, recording the
+    /// result like [`health_check_all`](Self::health_check_all). `None` for
+    /// unknown providers.
+    pub async fn diagnose(&mut self, provider_id: &str) -> Option<Result<(), String>> {
+        let provider = Arc::clone(&self.providers.get(provider_id)?.provider);
+        let result = provider.diagnose().await;
+        self.record_probe(provider_id, result.is_ok());
+        Some(result)
+    }
+
+    /// Startup warm-up: diagnose every provider, retrying the failing ones
+    /// every `retry` until they pass or `timeout` runs out. Each provider
+    /// still failing is logged with the reason and a hint at the fix, and
+    /// returned with the reason.
+    pub async fn warm_up(&mut self, timeout: Duration, retry: Duration) -> Vec<(String, String)> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut pending = self.order.clone();
+        let mut failures = Vec::new();
+        loop {
+            for id in std::mem::take(&mut pending) {
+                match tokio::time::timeout_at(deadline, self.diagnose(&id)).await {
+                    Ok(Some(Ok(()))) => info!(provider = %id, "Provider ready"),
+                    Ok(Some(Err(reason))) => failures.push((id, reason)),
+                    Ok(None) => {}
+                    Err(_) => failures.push((id, "timed out".to_string())),
+                }
+            }
+            if failures.is_empty() || tokio::time::Instant::now() + retry >= deadline {
+                break;
+            }
+            debug!(
+                providers = failures.len(),
+                "Providers not ready yet, retrying warm-up"
+            );
+            tokio::time::sleep(retry).await;
+            pending = failures.drain(..).map(|(id, _)| id).collect();
+        }
+
+        for (id, reason) in &failures {
+            warn!(
+                provider = %id,
+                reason = %reason,
+                hint = warmup_hint(id),
+                "Provider failed the startup warm-up"
+            );
+        }
+        failures
+    }
+
+    /// Remember the outcome of an active health check and count it against
+    /// the circuit breaker.
+    fn record_probe(&mut self, provider_id: &str, healthy: bool) {
+        let Some(entry) = self.providers.get_mut(provider_id) else {
+            return;
+        };
+        entry.last_probe = Some(HealthProbe {
+            at: chrono::Utc::now(),
+            healthy,
+        });
+        if healthy {
+            entry.circuit.record_success();
+        } else {
+            entry.record_circuit_failure(self.notifier.as_deref());
+        }
+    }
+}
+
+/// What to check when a provider fails the startup warm-up.
+fn warmup_hint(provider_id: &str) -> &'static str {
+    match provider_id {
+        "claude" | "gemini" | "copilot" => {
+            "sign in again on the OAuth page (/ui/oauth) if the token was revoked or expired"
+        }
+        "litellm" => "check that LiteLLM is running at providers.litellm.url and accepts api_key",
+        id if id.starts_with("kiro") => {
+            "check the refresh token, credentials file or kiro-cli database in [providers.kiro]"
+        }
+        _ => "check the provider's configuration",
+    }
 }
 
 impl Default for ProviderRouter {
@@ -447,7 +1199,10 @@ mod tests {
     struct StubProvider {
         id: &'static str,
         models: Vec<String>,
+        image_models: Vec<String>,
         should_fail: bool,
+        quota_exhausted: bool,
+        rate_limit: Option<UpstreamRateLimit>,
     }
 
     impl StubProvider {
@@ -455,15 +1210,45 @@ mod tests {
             Self {
                 id,
                 models: models.iter().map(|s| s.to_string()).collect(),
+                image_models: Vec::new(),
                 should_fail: false,
+                quota_exhausted: false,
+                rate_limit: None,
             }
         }
 
+        fn with_image_models(mut self, models: &[&str]) -> Self {
+            self.image_models = models.iter().map(|s| s.to_string()).collect();
+            self
+        }
+
+        fn with_remaining_requests(mut self, limit: u64, remaining: u64) -> Self {
+            let headers = [
+                ("x-ratelimit-limit-requests".to_string(), limit.to_string()),
+                (
+                    "x-ratelimit-remaining-requests".to_string(),
+                    remaining.to_string(),
+                ),
+            ];
+            self.rate_limit = UpstreamRateLimit::from_headers(&headers, None);
+            self
+        }
+
         fn failing(id: &'static str, models: &[&str]) -> Self {
             Self {
                 id,
                 models: models.iter().map(|s| s.to_string()).collect(),
+                image_models: Vec::new(),
                 should_fail: true,
+                quota_exhausted: false,
+                rate_limit: None,
+            }
+        }
+
+        fn quota_exhausted(id: &'static str, models: &[&str]) -> Self {
+            Self {
+                quota_exhausted: true,
+                ..Self::failing(id, models)
             }
         }
     }
@@ -493,7 +1278,15 @@ mod tests {
         > {
             let model = request.model.clone();
             let should_fail = self.should_fail;
+            let quota_exhausted = self.quota_exhausted;
+            let id = self.id;
             Box::pin(async move {
+                if quota_exhausted {
+                    return Err(ProviderError::QuotaExhausted {
+                        provider: id.into(),
+                        message: "daily limit reached".into(),
+                    });
+                }
                 if should_fail {
                     return Err(ProviderError::Other("stub failure".into()));
                 }
@@ -509,10 +1302,12 @@ mod tests {
                             content: Some("Hello from stub".into()),
                             reasoning_content: None,
                             tool_calls: None,
+                            grounding: None,
                         },
                         finish_reason: Some("stop".into()),
                     }],
                     usage: Usage::default(),
+                    tool_calls_emulated: false,
                 })
             })
         }
@@ -544,6 +1339,7 @@ mod tests {
                         model: "test".into(),
                         choices: vec![],
                         usage: None,
+                        tool_calls_emulated: false,
                     })
                 });
                 Ok(Box::pin(stream)
@@ -561,6 +1357,39 @@ mod tests {
         fn pricing(&self) -> Vec<ModelPricing> {
             vec![]
         }
+
+        fn upstream_rate_limit(&self) -> Option<UpstreamRateLimit> {
+            self.rate_limit.clone()
+        }
+
+        fn supports_image_model(&self, model: &str) -> bool {
+            self.image_models.iter().any(|m| m == model)
+        }
+
+        fn generate_images(
+            &self,
+            request: &ImageGenerationRequest,
+        ) -> Pin<
+            Box<
+                dyn std::future::Future<Output = Result<ImageGenerationResponse, ProviderError>>
+                    + Send
+                    + '_,
+            >,
+        > {
+            let count = request.count();
+            Box::pin(async move {
+                Ok(ImageGenerationResponse {
+                    created: 0,
+                    data: (0..count)
+                        .map(|_| ImageData {
+                            url: None,
+                            b64_json: Some("AAAA".into()),
+                            revised_prompt: None,
+                        })
+                        .collect(),
+                })
+            })
+        }
     }
 
     fn make_request(model: &str) -> ChatRequest {
@@ -581,9 +1410,174 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
+        }
+    }
+
+    fn virtual_model(models: &[&str], weights: &[u32]) -> VirtualModelConfig {
+        VirtualModelConfig {
+            models: models.iter().map(|s| s.to_string()).collect(),
+            weights: weights.to_vec(),
         }
     }
 
+    #[test]
+    fn test_replace_section_keeps_priority() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::new("kiro", &["claude-a"])));
+        router.register(Arc::new(StubProvider::new("kiro:work", &["claude-a"])));
+        router.register(Arc::new(StubProvider::new("kirox", &["claude-a"])));
+        router.register(Arc::new(StubProvider::new("litellm", &["gpt-4o"])));
+
+        router.replace_section(
+            "kiro",
+            vec![Arc::new(StubProvider::new("kiro", &["claude-b"]))],
+        );
+        assert_eq!(router.provider_ids(), ["kiro", "kirox", "litellm"]);
+        assert!(
+            router
+                .available_models()
+                .contains(&("claude-b".to_string(), "kiro".to_string()))
+        );
+
+        router.replace_section("litellm", Vec::new());
+        router.replace_section(
+            "gemini",
+            vec![Arc::new(StubProvider::new("gemini", &["gemini-b"]))],
+        );
+        assert_eq!(router.provider_ids(), ["kiro", "kirox", "gemini"]);
+    }
+
+    #[test]
+    fn test_resolve_virtual_model_by_strategy() {
+        let mut router = ProviderRouter::with_strategy(RoutingStrategy::RoundRobin);
+        router.register(Arc::new(StubProvider::new("claude", &["claude-a"])));
+        router.register(Arc::new(StubProvider::new("gemini", &["gemini-b"])));
+        router.set_virtual_models(BTreeMap::from([(
+            "smart".to_string(),
+            virtual_model(&["claude-a", "gemini-b"], &[]),
+        )]));
+
+        assert_eq!(router.resolve_model("claude-a").unwrap(), "claude-a");
+        assert_eq!(router.resolve_model("gaud:smart").unwrap(), "claude-a");
+        assert_eq!(router.resolve_model("gaud:smart").unwrap(), "gemini-b");
+        assert!(matches!(
+            router.resolve_model("gaud:nope"),
+            Err(ProviderError::NoProvider(_))
+        ));
+
+        router.set_strategy(RoutingStrategy::Priority);
+        assert_eq!(router.resolve_model("gaud:smart").unwrap(), "claude-a");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_virtual_model_skips_unservable_members() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::failing("claude", &["claude-a"])));
+        router.register(Arc::new(StubProvider::new("gemini", &["gemini-b"])));
+        router.set_virtual_models(BTreeMap::from([
+            (
+                "smart".to_string(),
+                virtual_model(&["claude-a", "gemini-b"], &[]),
+            ),
+            // Weights never pick a zero-weight member.
+            (
+                "cheap".to_string(),
+                virtual_model(&["claude-a", "gemini-b"], &[0, 1]),
+            ),
+        ]));
+        for _ in 0..10 {
+            assert_eq!(router.resolve_model("gaud:cheap").unwrap(), "gemini-b");
+        }
+
+        // Trip claude's circuit breaker.
+        for _ in 0..3 {
+            let _ = router.chat(&make_request("claude-a")).await;
+        }
+        assert_eq!(router.resolve_model("gaud:smart").unwrap(), "gemini-b");
+    }
+
+    #[tokio::test]
+    async fn test_rule_provider_order() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::new("claude", &["claude-a"])));
+        router.register(Arc::new(StubProvider::new("kiro", &["claude-a"])));
+        router.set_rules(vec![RoutingRule {
+            name: "prefer-kiro".into(),
+            model: Some("claude-*".into()),
+            providers: vec!["kiro".into()],
+            ..Default::default()
+        }]);
+
+        let request = make_request("claude-a");
+        let facts = RequestFacts::new(&request, "u-1", "alice", None, chrono::Utc::now());
+        let decision = router.route(&facts).unwrap();
+        assert_eq!(decision.rule, "prefer-kiro");
+        assert_eq!(
+            router.candidates_preferring("claude-a", &decision.providers, Priority::Default),
+            ["kiro", "claude"]
+        );
+
+        router
+            .chat_with_order(&request, &decision.providers, Priority::Default)
+            .await
+            .unwrap();
+        assert_eq!(router.stats("kiro").unwrap().total_requests, 1);
+        assert_eq!(router.stats("claude").unwrap().total_requests, 0);
+
+        let other = make_request("gemini-b");
+        let facts = RequestFacts::new(&other, "u-1", "alice", None, chrono::Utc::now());
+        assert!(router.route(&facts).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_simulate_routing() {
+        let mut router = ProviderRouter::with_strategy(RoutingStrategy::RoundRobin);
+        router.register(Arc::new(StubProvider::failing("claude", &["claude-a"])));
+        router.register(Arc::new(StubProvider::new("kiro", &["claude-a"])));
+        router.set_virtual_models(BTreeMap::from([(
+            "smart".to_string(),
+            virtual_model(&["claude-a"], &[]),
+        )]));
+
+        let request = make_request("gaud:smart");
+        let facts = RequestFacts::new(&request, "u-1", "alice", None, chrono::Utc::now());
+        let sim = router.simulate(&facts, None, Priority::Default).unwrap();
+        assert_eq!(sim.model, "claude-a");
+        assert!(sim.decision.is_none());
+        // Resolving the virtual model advanced round-robin once, as a real
+        // request would, but the position itself is untouched.
+        assert_eq!(sim.providers, ["kiro", "claude"]);
+        assert_eq!(router.rr_index, 0);
+
+        // Candidate rules are tried without replacing the configured ones.
+        let rules = [RoutingRule {
+            name: "alice-kiro".into(),
+            user: Some("alice".into()),
+            providers: vec!["kiro".into()],
+            ..Default::default()
+        }];
+        let sim = router
+            .simulate(&facts, Some(&rules), Priority::Default)
+            .unwrap();
+        assert_eq!(sim.decision.unwrap().rule, "alice-kiro");
+        assert_eq!(sim.providers, ["kiro", "claude"]);
+        assert!(router.rules().is_empty());
+
+        // Providers with an open circuit are reported as unavailable.
+        let claude_first = ["claude".to_string()];
+        for _ in 0..3 {
+            let _ = router
+                .chat_with_order(&make_request("claude-a"), &claude_first, Priority::Default)
+                .await;
+        }
+        let sim = router.simulate(&facts, None, Priority::Default).unwrap();
+        assert_eq!(sim.providers, ["kiro"]);
+        assert_eq!(sim.unavailable, ["claude"]);
+    }
+
     #[test]
     fn test_resolve_provider_claude() {
         assert_eq!(
@@ -677,6 +1671,36 @@ mod tests {
         assert!(matches!(result, Err(ProviderError::NoProvider(_))));
     }
 
+    #[tokio::test]
+    async fn test_generate_images_routes_to_image_provider() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::new("claude", &["dall-e-3"])));
+        router.register(Arc::new(
+            StubProvider::new("litellm", &[]).with_image_models(&["dall-e-3"]),
+        ));
+
+        let request = ImageGenerationRequest {
+            model: "dall-e-3".into(),
+            prompt: "a cat".into(),
+            n: Some(2),
+            size: None,
+            quality: None,
+            response_format: None,
+        };
+        let response = router.generate_images(&request).await.unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(router.stats("litellm").unwrap().successful_requests, 1);
+
+        let request = ImageGenerationRequest {
+            model: "unknown-image-model".into(),
+            ..request
+        };
+        assert!(matches!(
+            router.generate_images(&request).await,
+            Err(ProviderError::NoProvider(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_fallback_on_failure() {
         let mut router = ProviderRouter::new();
@@ -696,6 +1720,125 @@ mod tests {
         assert!(resp.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_dry_run_skips_upstream() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::failing(
+            "claude",
+            &["claude-sonnet-4"],
+        )));
+        router.set_dry_run("claude");
+
+        let resp = router.chat(&make_request("claude-sonnet-4")).await.unwrap();
+        let content = resp.choices[0].message.content.as_deref().unwrap();
+        assert!(content.contains("dry-run response from claude"));
+        assert!(router.health_check_all().await["claude"]);
+    }
+
+    #[tokio::test]
+    async fn test_near_limit_provider_is_tried_last() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(
+            StubProvider::new("claude", &["claude-sonnet-4-20250514"])
+                .with_remaining_requests(100, 2),
+        ));
+        router.register(Arc::new(
+            StubProvider::new("claude-backup", &["claude-sonnet-4-20250514"])
+                .with_remaining_requests(100, 60),
+        ));
+
+        assert_eq!(
+            router.candidates_for_model("claude-sonnet-4-20250514"),
+            vec!["claude-backup".to_string(), "claude".to_string()]
+        );
+
+        let total = router
+            .upstream_rate_limit("claude-sonnet-4-20250514")
+            .unwrap();
+        assert_eq!(total.limit_requests, Some(200));
+        assert_eq!(total.remaining_requests, Some(62));
+        assert!(router.upstream_rate_limit("gpt-4o").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_batch_skips_near_limit_provider() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(
+            StubProvider::new("claude", &["claude-a"]).with_remaining_requests(100, 2),
+        ));
+        router.register(Arc::new(
+            StubProvider::new("claude-backup", &["claude-a"]).with_remaining_requests(100, 60),
+        ));
+        assert_eq!(
+            router.candidates_preferring("claude-a", &[], Priority::Batch),
+            ["claude-backup"]
+        );
+        assert_eq!(
+            router.candidates_preferring("claude-a", &[], Priority::Interactive),
+            ["claude-backup", "claude"]
+        );
+
+        // With every provider near its limit, batch traffic still goes out.
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(
+            StubProvider::new("claude", &["claude-a"]).with_remaining_requests(100, 2),
+        ));
+        assert_eq!(
+            router.candidates_preferring("claude-a", &[], Priority::Batch),
+            ["claude"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_quota_skips_provider() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::quota_exhausted(
+            "gemini",
+            &["gemini-2.5-pro"],
+        )));
+        router.register(Arc::new(StubProvider::new(
+            "gemini-backup",
+            &["gemini-2.5-pro"],
+        )));
+
+        let resp = router.chat(&make_request("gemini-2.5-pro")).await;
+        assert!(resp.is_ok());
+
+        // The exhausted account is parked without tripping its circuit.
+        assert_eq!(
+            router.candidates_for_model("gemini-2.5-pro"),
+            vec!["gemini-backup".to_string()]
+        );
+        assert_eq!(router.circuit_state("gemini"), Some(CircuitState::Closed));
+        assert!(
+            router
+                .quota_status("gemini")
+                .unwrap()
+                .exhausted_until
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_configured_daily_quota_is_enforced() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::new("copilot", &["gpt-4o"])));
+        router.set_quota(
+            "copilot",
+            ProviderQuotaConfig {
+                daily_requests: Some(1),
+                ..Default::default()
+            },
+        );
+
+        assert!(router.chat(&make_request("gpt-4o")).await.is_ok());
+        let err = router.chat(&make_request("gpt-4o")).await.unwrap_err();
+        assert!(matches!(err, ProviderError::NoProvider(_)));
+
+        let status = router.quota_status("copilot").unwrap();
+        assert_eq!((status.daily_used, status.daily_limit), (1, Some(1)));
+    }
+
     #[tokio::test]
     async fn test_all_fail_returns_error() {
         let mut router = ProviderRouter::new();
@@ -706,6 +1849,13 @@ mod tests {
 
         let result = router.chat(&make_request("claude-sonnet-4-20250514")).await;
         assert!(result.is_err());
+
+        // The failure is kept for /admin/debug/failures.
+        let failures = router.failures().recent(10);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].operation, "chat");
+        assert_eq!(failures[0].model, "claude-sonnet-4-20250514");
+        assert_eq!(failures[0].attempts[0].provider, "claude");
     }
 
     #[tokio::test]
@@ -744,6 +1894,50 @@ mod tests {
         assert!(matches!(result, Err(ProviderError::NoProvider(_))));
     }
 
+    #[tokio::test]
+    async fn test_circuit_trip_and_outage_are_notified() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let config = crate::config::NotificationsConfig {
+            slack_webhook_url: Some(server.uri()),
+            ..Default::default()
+        };
+        let mut router = ProviderRouter::new();
+        router.set_notifier(Arc::new(Notifier::new(&config, reqwest::Client::new())));
+        router.register(Arc::new(StubProvider::failing(
+            "claude",
+            &["claude-sonnet-4-20250514"],
+        )));
+        for _ in 0..3 {
+            let _ = router.chat(&make_request("claude-sonnet-4-20250514")).await;
+        }
+
+        let mut texts = Vec::new();
+        for _ in 0..50 {
+            texts = server
+                .received_requests()
+                .await
+                .unwrap_or_default()
+                .iter()
+                .map(|r| String::from_utf8_lossy(&r.body).into_owned())
+                .collect();
+            if texts.len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        // One outage (later repeats are in the cooldown) and one trip.
+        assert_eq!(texts.len(), 2);
+        assert!(texts.iter().any(|t| t.contains("All providers failed")));
+        assert!(texts.iter().any(|t| t.contains("Circuit breaker opened")));
+    }
+
     #[tokio::test]
     async fn test_health_check_all() {
         let mut router = ProviderRouter::new();
@@ -753,9 +1947,33 @@ mod tests {
         )));
         router.register(Arc::new(StubProvider::failing("copilot", &["gpt-4o"])));
 
+        assert!(router.last_probe("claude").is_none());
         let results = router.health_check_all().await;
         assert_eq!(results.get("claude"), Some(&true));
         assert_eq!(results.get("copilot"), Some(&false));
+        assert!(router.last_probe("claude").unwrap().healthy);
+        assert!(!router.last_probe("copilot").unwrap().healthy);
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_reports_failing_providers() {
+        let mut router = ProviderRouter::new();
+        router.register(Arc::new(StubProvider::new(
+            "claude",
+            &["claude-sonnet-4-20250514"],
+        )));
+        router.register(Arc::new(StubProvider::failing("copilot", &["gpt-4o"])));
+
+        let failures = router
+            .warm_up(Duration::from_millis(100), Duration::from_millis(20))
+            .await;
+        assert_eq!(
+            failures,
+            vec![("copilot".to_string(), "health check failed".to_string())]
+        );
+        assert!(router.last_probe("claude").unwrap().healthy);
+        // Every retry counted against the circuit breaker.
+        assert!(router.circuit("copilot").unwrap().failure_count() > 1);
     }
 
     #[test]
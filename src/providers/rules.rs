@@ -0,0 +1,344 @@
+//! Routing rules: admin-defined overrides evaluated for every chat request.
+//!
+//! Each `[[providers.rules]]` entry matches on the model, the user or team,
+//! the size of the request and the time of day, and decides the provider
+//! order, whether the response cache is used and a cap on `max_tokens`.
+//! Rules are tried in order and the first match wins. Conditions left out
+//! match everything; decisions left out keep the default behaviour.
+
+use chrono::{DateTime, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::providers::types::{ChatRequest, ContentPart, MessageContent};
+
+/// One `[[providers.rules]]` entry.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RoutingRule {
+    /// Name reported in logs when the rule matches.
+    pub name: String,
+
+    // -- conditions --
+    /// Model name; `*` matches any run of characters (`claude-*`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// User name or ID pattern.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Team name pattern. Users without a team never match.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+    /// Smallest request size, in characters of message text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_input_chars: Option<usize>,
+    /// Largest request size, in characters of message text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_input_chars: Option<usize>,
+    /// Hours of the day (UTC) as `start-end`, end exclusive; `22-6` wraps
+    /// past midnight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours_utc: Option<String>,
+
+    // -- decisions --
+    /// Providers to try first, in this order. Others follow in the usual
+    /// strategy order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    /// `false` bypasses the response cache.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<bool>,
+    /// Upper bound applied to the request's `max_tokens`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+impl RoutingRule {
+    /// Check the rule is well-formed and decides something.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("Every routing rule needs a name".to_string());
+        }
+        if let Some(hours) = &self.hours_utc
+            && parse_hours(hours).is_none()
+        {
+            return Err(format!(
+                "Routing rule '{}': invalid hours_utc '{hours}', expected e.g. \"9-17\"",
+                self.name
+            ));
+        }
+        if self.providers.is_empty() && self.cache.is_none() && self.max_tokens.is_none() {
+            return Err(format!(
+                "Routing rule '{}' decides nothing; set providers, cache or max_tokens",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the rule applies to a request described by `facts`.
+    pub fn matches(&self, facts: &RequestFacts<'_>) -> bool {
+        let pattern = |p: &Option<String>, value: Option<&str>| match p {
+            None => true,
+            Some(p) => value.is_some_and(|v| glob_match(p, v)),
+        };
+        pattern(&self.model, Some(facts.model))
+            && (pattern(&self.user, Some(facts.user_name))
+                || pattern(&self.user, Some(facts.user_id)))
+            && pattern(&self.team, facts.team)
+            && self
+                .min_input_chars
+                .is_none_or(|min| facts.input_chars >= min)
+            && self
+                .max_input_chars
+                .is_none_or(|max| facts.input_chars <= max)
+            && self.hours_utc.as_deref().is_none_or(|h| {
+                parse_hours(h).is_some_and(|(start, end)| in_hours(facts.hour_utc, start, end))
+            })
+    }
+}
+
+/// What routing rules look at in a request.
+#[derive(Debug, Clone)]
+pub struct RequestFacts<'a> {
+    pub model: &'a str,
+    pub user_id: &'a str,
+    pub user_name: &'a str,
+    pub team: Option<&'a str>,
+    /// Characters of message text.
+    pub input_chars: usize,
+    pub hour_utc: u32,
+}
+
+impl<'a> RequestFacts<'a> {
+    pub fn new(
+        request: &'a ChatRequest,
+        user_id: &'a str,
+        user_name: &'a str,
+        team: Option<&'a str>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            model: &request.model,
+            user_id,
+            user_name,
+            team,
+            input_chars: input_chars(request),
+            hour_utc: now.hour(),
+        }
+    }
+}
+
+/// Characters of message text in `request`.
+pub fn input_chars(request: &ChatRequest) -> usize {
+    request
+        .messages
+        .iter()
+        .filter_map(|m| m.content.as_ref())
+        .map(|c| match c {
+            MessageContent::Text(t) => t.chars().count(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .map(|p| match p {
+                    ContentPart::Text { text } => text.chars().count(),
+                    _ => 0,
+                })
+                .sum(),
+        })
+        .sum()
+}
+
+/// The decisions of the rule that matched a request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RoutingDecision {
+    /// Name of the matching rule.
+    pub rule: String,
+    pub providers: Vec<String>,
+    pub cache: Option<bool>,
+    pub max_tokens: Option<u32>,
+}
+
+impl RoutingDecision {
+    /// Whether the response cache may be used.
+    pub fn allows_cache(&self) -> bool {
+        self.cache != Some(false)
+    }
+
+    /// Cap the request's `max_tokens`, setting it if the client did not.
+    pub fn apply(&self, request: &mut ChatRequest) {
+        if let Some(cap) = self.max_tokens {
+            request.max_tokens = Some(request.max_tokens.map_or(cap, |t| t.min(cap)));
+        }
+    }
+}
+
+/// The decision of the first rule matching `facts`.
+pub fn evaluate(rules: &[RoutingRule], facts: &RequestFacts<'_>) -> Option<RoutingDecision> {
+    rules
+        .iter()
+        .find(|r| r.matches(facts))
+        .map(|r| RoutingDecision {
+            rule: r.name.clone(),
+            providers: r.providers.clone(),
+            cache: r.cache,
+            max_tokens: r.max_tokens,
+        })
+}
+
+/// Parse `start-end` hours, each 0-24.
+fn parse_hours(s: &str) -> Option<(u32, u32)> {
+    let (start, end) = s.split_once('-')?;
+    let start: u32 = start.trim().parse().ok()?;
+    let end: u32 = end.trim().parse().ok()?;
+    (start <= 24 && end <= 24 && start != end).then_some((start, end))
+}
+
+fn in_hours(hour: u32, start: u32, end: u32) -> bool {
+    if start < end {
+        (start..end).contains(&hour)
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+/// Match `value` against `pattern`, where `*` matches any run of characters.
+pub(crate) fn glob_match(pattern: &str, value: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = value.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::types::{ChatMessage, MessageRole};
+
+    fn request(model: &str, text: &str) -> ChatRequest {
+        ChatRequest {
+            model: model.into(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: Some(MessageContent::Text(text.into())),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            max_tokens: Some(8000),
+            stream: false,
+            top_p: None,
+            stop: None,
+            tools: None,
+            tool_choice: None,
+            stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
+        }
+    }
+
+    fn facts<'a>(req: &'a ChatRequest, team: Option<&'a str>, hour: u32) -> RequestFacts<'a> {
+        let now = DateTime::parse_from_rfc3339(&format!("2025-06-01T{hour:02}:30:00Z"))
+            .unwrap()
+            .with_timezone(&Utc);
+        RequestFacts::new(req, "u-1", "alice", team, now)
+    }
+
+    fn rules() -> Vec<RoutingRule> {
+        toml::from_str::<toml::Table>(
+            r#"
+            [[rules]]
+            name = "night-batch"
+            model = "claude-*"
+            team = "research"
+            hours_utc = "22-6"
+            providers = ["kiro", "claude"]
+            cache = false
+
+            [[rules]]
+            name = "big-prompts"
+            min_input_chars = 10
+            max_tokens = 1024
+            "#,
+        )
+        .unwrap()["rules"]
+            .clone()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = rules();
+        assert!(rules.iter().all(|r| r.validate().is_ok()));
+
+        let req = request("claude-sonnet-4-20250514", "Hello there, world");
+        let decision = evaluate(&rules, &facts(&req, Some("research"), 23)).unwrap();
+        assert_eq!(decision.rule, "night-batch");
+        assert_eq!(decision.providers, ["kiro", "claude"]);
+        assert!(!decision.allows_cache());
+
+        // Daytime, or another team: falls through to the size rule.
+        let decision = evaluate(&rules, &facts(&req, Some("research"), 12)).unwrap();
+        assert_eq!(decision.rule, "big-prompts");
+        let decision = evaluate(&rules, &facts(&req, None, 3)).unwrap();
+        assert_eq!(decision.rule, "big-prompts");
+        assert!(decision.allows_cache());
+
+        let small = request("gpt-4o", "Hi");
+        assert!(evaluate(&rules, &facts(&small, None, 3)).is_none());
+    }
+
+    #[test]
+    fn test_max_tokens_clamp() {
+        let decision = RoutingDecision {
+            max_tokens: Some(1024),
+            ..Default::default()
+        };
+        let mut req = request("m", "x");
+        decision.apply(&mut req);
+        assert_eq!(req.max_tokens, Some(1024));
+        req.max_tokens = Some(100);
+        decision.apply(&mut req);
+        assert_eq!(req.max_tokens, Some(100));
+        req.max_tokens = None;
+        decision.apply(&mut req);
+        assert_eq!(req.max_tokens, Some(1024));
+    }
+
+    #[test]
+    fn test_validation_and_patterns() {
+        let mut rule = RoutingRule {
+            name: "r".into(),
+            hours_utc: Some("9-25".into()),
+            cache: Some(false),
+            ..Default::default()
+        };
+        assert!(rule.validate().is_err());
+        rule.hours_utc = Some("9-17".into());
+        assert!(rule.validate().is_ok());
+        rule.cache = None;
+        assert!(rule.validate().is_err());
+
+        assert!(glob_match("claude-*", "claude-opus-4"));
+        assert!(glob_match("*-mini", "o3-mini"));
+        assert!(glob_match("gpt-*-mini", "gpt-4o-mini"));
+        assert!(!glob_match("gpt-*-mini", "gpt-4o"));
+        assert!(glob_match("kiro:*", "kiro:auto"));
+        assert!(glob_match("a*a", "aa"));
+        assert!(!glob_match("a*a", "a"));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exactly"));
+    }
+}
@@ -0,0 +1,112 @@
+//! Token estimation before a request is sent.
+//!
+//! Used where a request's input tokens must be known before it is sent,
+//! such as `/v1/messages/count_tokens` and the pre-flight budget and context
+//! window checks. The tokenizers live in the `gaud-tokenizer` crate, which
+//! kiro-gateway counts its requests with as well.
+
+pub use gaud_tokenizer::{DOCUMENT_TOKENS, IMAGE_TOKENS, Tokenizer};
+use gaud_tokenizer::{MESSAGE_TOKENS, TextCounter};
+
+use crate::providers::types::{ChatRequest, ContentPart, MessageContent};
+
+/// Tokens of `text` for `model`.
+pub fn count_text(model: &str, text: &str) -> u64 {
+    Tokenizer::for_model(model).count(text)
+}
+
+/// Estimated input tokens of `request`: its message text, tool calls and
+/// tool definitions run through the model's tokenizer, plus fixed amounts
+/// for messages, images and documents.
+pub fn estimate(request: &ChatRequest) -> u64 {
+    let tokenizer = Tokenizer::for_model(&request.model);
+    let mut text = TextCounter::new();
+    let mut fixed = 0;
+    for message in &request.messages {
+        fixed += MESSAGE_TOKENS;
+        match &message.content {
+            Some(MessageContent::Text(t)) => text.push(t),
+            Some(MessageContent::Parts(parts)) => {
+                for part in parts {
+                    match part {
+                        ContentPart::Text { text: t } => text.push(t),
+                        ContentPart::ImageUrl { .. } => fixed += IMAGE_TOKENS,
+                        ContentPart::Document { .. } => fixed += DOCUMENT_TOKENS,
+                    }
+                }
+            }
+            None => {}
+        }
+        for call in message.tool_calls.iter().flatten() {
+            text.push(&call.function.name);
+            text.push(&call.function.arguments);
+        }
+    }
+    for tool in request.tools.iter().flatten() {
+        if let Ok(definition) = serde_json::to_string(&tool.function) {
+            text.push(&definition);
+        }
+    }
+    text.count(tokenizer) + fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str, messages: serde_json::Value) -> ChatRequest {
+        serde_json::from_value(serde_json::json!({"model": model, "messages": messages})).unwrap()
+    }
+
+    #[test]
+    fn test_count_text() {
+        // Known counts of the GPT encodings.
+        assert_eq!(count_text("gpt-4", "hello world"), 2);
+        assert_eq!(count_text("gpt-4o", "hello world"), 2);
+        assert_eq!(count_text("gpt-4o", ""), 0);
+        // Long runs of a repeated character are far from a character count.
+        assert!(count_text("gpt-4o", &"a".repeat(400)) < 100);
+        // The same text is more tokens for Claude.
+        let text = "The quick brown fox jumps over the lazy dog.";
+        assert!(count_text("claude-sonnet-4", text) > count_text("gpt-4", text));
+    }
+
+    #[test]
+    fn test_estimate_text() {
+        let messages = serde_json::json!([
+            {"role": "system", "content": "You are terse."},
+            {"role": "user", "content": "hello world"}
+        ]);
+        let text_tokens = count_text("gpt-4o", "You are terse.\nhello world");
+        assert_eq!(
+            estimate(&request("gpt-4o", messages.clone())),
+            text_tokens + 8
+        );
+        assert!(estimate(&request("claude-sonnet-4", messages)) > text_tokens + 8);
+    }
+
+    #[test]
+    fn test_estimate_images_and_tools() {
+        let mut req = request(
+            "gpt-4o",
+            serde_json::json!([{"role": "user", "content": [
+                {"type": "text", "text": "What is this?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}
+            ]}]),
+        );
+        let without_tools = estimate(&req);
+        assert_eq!(
+            without_tools,
+            count_text("gpt-4o", "What is this?") + 4 + IMAGE_TOKENS
+        );
+
+        req.tools = Some(
+            serde_json::from_value(serde_json::json!([{
+                "type": "function",
+                "function": {"name": "lookup", "parameters": {"type": "object"}}
+            }]))
+            .unwrap(),
+        );
+        assert!(estimate(&req) > without_tools);
+    }
+}
@@ -0,0 +1,364 @@
+//! Tool calling emulation for models without native tool support.
+//!
+//! With `providers.tool_emulation` on, a request with tools for a model
+//! known to lack tool calling is not refused. The tools are described in
+//! the system prompt along with a `<tool_call>` block format, earlier tool
+//! calls and results in the conversation are written out as text, and the
+//! blocks in the reply are parsed back into `tool_calls`. Responses are
+//! marked with `tool_calls_emulated`.
+//!
+//! Streamed replies are buffered: the calls can only be told apart from
+//! text once the reply is complete, so it is sent in one go at the end.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+
+use crate::providers::ProviderError;
+use crate::providers::types::{
+    ChatChunk, ChatMessage, ChatRequest, ChatResponse, ContentPart, FunctionCall, MessageContent,
+    MessageRole, ToolCall,
+};
+
+const OPEN: &str = "<tool_call>";
+const CLOSE: &str = "</tool_call>";
+
+type ChunkStream = Pin<Box<dyn Stream<Item = Result<ChatChunk, ProviderError>> + Send>>;
+
+/// Rewrite `request` so a model without tool calling can use its tools.
+/// Returns the names of the tools, or `None` if the request has none to
+/// emulate (`tool_choice: "none"` drops them).
+pub fn prepare(request: &mut ChatRequest) -> Option<Vec<String>> {
+    let tools = request.tools.take().filter(|t| !t.is_empty())?;
+    let choice = request.tool_choice.take();
+    if choice.as_ref().and_then(Value::as_str) == Some("none") {
+        return None;
+    }
+
+    let mut prompt = String::from(
+        "You can call the following tools. Each is given as JSON with its name, \
+         description and JSON Schema parameters.\n\n",
+    );
+    for tool in &tools {
+        let spec = serde_json::json!({
+            "name": tool.function.name,
+            "description": tool.function.description,
+            "parameters": tool.function.parameters,
+        });
+        prompt.push_str(&spec.to_string());
+        prompt.push('\n');
+    }
+    prompt.push_str(&format!(
+        "\nTo call a tool, reply with one block per call and nothing else:\n\
+         {OPEN}\n{{\"name\": \"<tool name>\", \"arguments\": {{<arguments>}}}}\n{CLOSE}\n\
+         Tool results come back in <tool_result> blocks. Answer normally when no tool is needed."
+    ));
+    match choice.as_ref() {
+        Some(Value::String(s)) if s == "required" => {
+            prompt.push_str(" You must call at least one tool.");
+        }
+        Some(Value::Object(o)) => {
+            if let Some(name) = o.get("function").and_then(|f| f.get("name")) {
+                prompt.push_str(&format!(" You must call the tool {name}."));
+            }
+        }
+        _ => {}
+    }
+
+    let mut call_names = HashMap::new();
+    for message in &mut request.messages {
+        if let Some(calls) = message.tool_calls.take() {
+            let mut text = message.content.take().map(text_of).unwrap_or_default();
+            for call in calls {
+                let arguments = serde_json::from_str::<Value>(&call.function.arguments)
+                    .unwrap_or(Value::String(call.function.arguments));
+                let block = serde_json::json!({"name": call.function.name, "arguments": arguments});
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&format!("{OPEN}\n{block}\n{CLOSE}"));
+                call_names.insert(call.id, call.function.name);
+            }
+            message.content = Some(MessageContent::Text(text));
+        }
+        if message.role == MessageRole::Tool {
+            let id = message.tool_call_id.take().unwrap_or_default();
+            let name = call_names.get(&id).map_or("", String::as_str);
+            let output = message.content.take().map(text_of).unwrap_or_default();
+            message.role = MessageRole::User;
+            message.content = Some(MessageContent::Text(format!(
+                "<tool_result name=\"{name}\">\n{output}\n</tool_result>"
+            )));
+        }
+    }
+
+    match request.messages.first_mut() {
+        Some(first) if first.role == MessageRole::System => {
+            let text = first.content.take().map(text_of).unwrap_or_default();
+            first.content = Some(MessageContent::Text(format!("{text}\n\n{prompt}")));
+        }
+        _ => request.messages.insert(
+            0,
+            ChatMessage {
+                role: MessageRole::System,
+                content: Some(MessageContent::Text(prompt)),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            },
+        ),
+    }
+    Some(tools.into_iter().map(|t| t.function.name).collect())
+}
+
+fn text_of(content: MessageContent) -> String {
+    match content {
+        MessageContent::Text(text) => text,
+        MessageContent::Parts(parts) => parts
+            .into_iter()
+            .filter_map(|p| match p {
+                ContentPart::Text { text } => Some(text),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Split the `<tool_call>` blocks calling one of `tools` out of `text`.
+/// Returns the remaining text (`None` if only whitespace) and the calls.
+/// Blocks that do not parse, or name another tool, are left in the text.
+pub fn parse(text: &str, tools: &[String]) -> (Option<String>, Vec<ToolCall>) {
+    let mut rest = String::new();
+    let mut calls = Vec::new();
+    let mut remaining = text;
+    while let Some(start) = remaining.find(OPEN) {
+        let Some(len) = remaining[start..].find(CLOSE) else {
+            break;
+        };
+        let block = &remaining[start + OPEN.len()..start + len];
+        let end = start + len + CLOSE.len();
+        match parse_call(block, tools) {
+            Some(function) => {
+                rest.push_str(&remaining[..start]);
+                calls.push(ToolCall {
+                    index: None,
+                    id: format!("call_{}", uuid::Uuid::new_v4().simple()),
+                    r#type: "function".to_string(),
+                    function,
+                });
+            }
+            None => rest.push_str(&remaining[..end]),
+        }
+        remaining = &remaining[end..];
+    }
+    rest.push_str(remaining);
+    let rest = rest.trim();
+    ((!rest.is_empty()).then(|| rest.to_string()), calls)
+}
+
+fn parse_call(block: &str, tools: &[String]) -> Option<FunctionCall> {
+    let json = block
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    let value: Value = serde_json::from_str(json).ok()?;
+    let name = value.get("name")?.as_str()?;
+    if !tools.iter().any(|t| t == name) {
+        return None;
+    }
+    let arguments = match value.get("arguments") {
+        None | Some(Value::Null) => "{}".to_string(),
+        Some(Value::String(s)) => s.clone(),
+        Some(args) => args.to_string(),
+    };
+    Some(FunctionCall {
+        name: name.to_string(),
+        arguments,
+    })
+}
+
+/// Turn the tool call blocks in `response` into `tool_calls`.
+pub fn apply_to_response(response: &mut ChatResponse, tools: &[String]) {
+    response.tool_calls_emulated = true;
+    for choice in &mut response.choices {
+        let Some(text) = &choice.message.content else {
+            continue;
+        };
+        let (rest, calls) = parse(text, tools);
+        if calls.is_empty() {
+            continue;
+        }
+        choice.message.content = rest;
+        choice.message.tool_calls = Some(calls);
+        choice.finish_reason = Some("tool_calls".to_string());
+    }
+}
+
+/// Buffer `stream` and turn the tool call blocks in its reply into
+/// `tool_calls` on the final chunk.
+pub fn emulate_stream(stream: ChunkStream, tools: Vec<String>) -> ChunkStream {
+    Box::pin(
+        futures::stream::once(async move {
+            let chunks: Vec<_> = stream.collect().await;
+            rewrite_chunks(chunks, &tools)
+        })
+        .flat_map(futures::stream::iter),
+    )
+}
+
+fn rewrite_chunks(
+    mut chunks: Vec<Result<ChatChunk, ProviderError>>,
+    tools: &[String],
+) -> Vec<Result<ChatChunk, ProviderError>> {
+    for chunk in chunks.iter_mut().flatten() {
+        chunk.tool_calls_emulated = true;
+    }
+    if chunks.iter().any(Result::is_err) {
+        return chunks;
+    }
+
+    let text: String = chunks
+        .iter()
+        .flatten()
+        .flat_map(|c| c.choices.iter().filter(|ch| ch.index == 0))
+        .filter_map(|ch| ch.delta.content.as_deref())
+        .collect();
+    let (rest, calls) = parse(&text, tools);
+    if calls.is_empty() {
+        return chunks;
+    }
+
+    let mut last = None;
+    for (i, chunk) in chunks.iter_mut().flatten().enumerate() {
+        for choice in chunk.choices.iter_mut().filter(|ch| ch.index == 0) {
+            choice.delta.content = None;
+            if last.is_none() || choice.finish_reason.is_some() {
+                last = Some(i);
+            }
+        }
+    }
+    let Some(Ok(chunk)) = last.and_then(|i| chunks.get_mut(i)) else {
+        return chunks;
+    };
+    if let Some(choice) = chunk.choices.iter_mut().find(|ch| ch.index == 0) {
+        choice.delta.content = rest;
+        choice.delta.tool_calls = Some(
+            calls
+                .into_iter()
+                .enumerate()
+                .map(|(i, call)| ToolCall {
+                    index: Some(i as u32),
+                    ..call
+                })
+                .collect(),
+        );
+        choice.finish_reason = Some("tool_calls".to_string());
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> ChatRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "plain-model",
+            "messages": [
+                {"role": "user", "content": "Weather in Oslo?"},
+                {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_1", "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Oslo\"}"}
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "4C, rain"},
+            ],
+            "tools": [{"type": "function", "function": {
+                "name": "get_weather",
+                "description": "Current weather",
+                "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }}],
+            "tool_choice": "required",
+        }))
+        .unwrap()
+    }
+
+    fn text(message: &ChatMessage) -> &str {
+        match &message.content {
+            Some(MessageContent::Text(t)) => t,
+            _ => panic!("expected text"),
+        }
+    }
+
+    #[test]
+    fn test_prepare_describes_tools_and_rewrites_history() {
+        let mut req = request();
+        let tools = prepare(&mut req).unwrap();
+        assert_eq!(tools, vec!["get_weather"]);
+        assert!(req.tools.is_none());
+        assert!(req.tool_choice.is_none());
+
+        assert_eq!(req.messages[0].role, MessageRole::System);
+        let system = text(&req.messages[0]);
+        assert!(system.contains("\"name\":\"get_weather\""));
+        assert!(system.contains("must call at least one tool"));
+
+        assert!(req.messages[2].tool_calls.is_none());
+        assert!(text(&req.messages[2]).contains("<tool_call>"));
+        assert_eq!(req.messages[3].role, MessageRole::User);
+        assert!(req.messages[3].tool_call_id.is_none());
+        assert!(text(&req.messages[3]).contains("<tool_result name=\"get_weather\">"));
+
+        let mut none = request();
+        none.tool_choice = Some(serde_json::json!("none"));
+        assert!(prepare(&mut none).is_none());
+        assert!(none.tools.is_none());
+    }
+
+    #[test]
+    fn test_parse_extracts_calls() {
+        let tools = vec!["get_weather".to_string()];
+        let reply = "Let me check.\n<tool_call>\n{\"name\": \"get_weather\", \
+                     \"arguments\": {\"city\": \"Oslo\"}}\n</tool_call>";
+        let (rest, calls) = parse(reply, &tools);
+        assert_eq!(rest.as_deref(), Some("Let me check."));
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(calls[0].function.arguments, "{\"city\":\"Oslo\"}");
+
+        // Unknown tools and malformed blocks stay in the text.
+        let reply = "<tool_call>{\"name\": \"rm\"}</tool_call> <tool_call>oops</tool_call>";
+        let (rest, calls) = parse(reply, &tools);
+        assert!(calls.is_empty());
+        assert_eq!(rest.as_deref(), Some(reply));
+    }
+
+    #[test]
+    fn test_rewrite_chunks_moves_calls_to_final_chunk() {
+        let chunk = |content: Option<&str>, finish: Option<&str>| {
+            Ok(serde_json::from_value::<ChatChunk>(serde_json::json!({
+                "id": "c", "object": "chat.completion.chunk", "created": 0, "model": "m",
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": finish}],
+            }))
+            .unwrap())
+        };
+        let chunks = vec![
+            chunk(Some("<tool_call>{\"name\": \"get_"), None),
+            chunk(Some("weather\", \"arguments\": {}}</tool_call>"), None),
+            chunk(None, Some("stop")),
+        ];
+        let out = rewrite_chunks(chunks, &["get_weather".to_string()]);
+        let out: Vec<ChatChunk> = out.into_iter().map(Result::unwrap).collect();
+        assert!(out.iter().all(|c| c.tool_calls_emulated));
+        assert!(out[0].choices[0].delta.content.is_none());
+        let last = &out[2].choices[0];
+        assert_eq!(last.finish_reason.as_deref(), Some("tool_calls"));
+        let calls = last.delta.tool_calls.as_ref().unwrap();
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(calls[0].index, Some(0));
+    }
+}
@@ -68,6 +68,7 @@ impl ClaudeTransformer {
                     }))
                 }
             }
+            ContentPart::Document { document } => Some(util::document_block(document)),
         }
     }
 
@@ -223,6 +224,8 @@ impl ProviderTransformer for ClaudeTransformer {
             body["stream"] = json!(true);
         }
 
+        util::merge_extra_body(&mut body, request.extra_body.as_ref(), "claude");
+
         Ok(body)
     }
 
@@ -311,6 +314,7 @@ impl ProviderTransformer for ClaudeTransformer {
             } else {
                 Some(tool_calls)
             },
+            grounding: None,
         };
 
         let choice = Choice {
@@ -326,6 +330,7 @@ impl ProviderTransformer for ClaudeTransformer {
             model,
             choices: vec![choice],
             usage,
+            tool_calls_emulated: false,
         })
     }
 
@@ -396,6 +401,7 @@ impl ClaudeStreamState {
                 finish_reason,
             }],
             usage,
+            tool_calls_emulated: false,
         }
     }
 }
@@ -427,6 +433,7 @@ impl StreamState for ClaudeStreamState {
                     content: None,
                     reasoning_content: None,
                     tool_calls: None,
+                    grounding: None,
                 };
 
                 Ok(Some(self.make_chunk(delta, None, None)))
@@ -458,6 +465,7 @@ impl StreamState for ClaudeStreamState {
                                 content: None,
                                 reasoning_content: None,
                                 tool_calls: Some(vec![tool_call]),
+                                grounding: None,
                             };
 
                             Ok(Some(self.make_chunk(delta, None, None)))
@@ -486,6 +494,7 @@ impl StreamState for ClaudeStreamState {
                                 content: Some(text),
                                 reasoning_content: None,
                                 tool_calls: None,
+                                grounding: None,
                             };
 
                             Ok(Some(self.make_chunk(delta, None, None)))
@@ -509,6 +518,7 @@ impl StreamState for ClaudeStreamState {
                                 content: None,
                                 reasoning_content: None,
                                 tool_calls: Some(vec![tool_call]),
+                                grounding: None,
                             };
 
                             Ok(Some(self.make_chunk(delta, None, None)))
@@ -548,6 +558,7 @@ impl StreamState for ClaudeStreamState {
                     content: None,
                     reasoning_content: None,
                     tool_calls: None,
+                    grounding: None,
                 };
 
                 Ok(Some(self.make_chunk(delta, finish_reason, Some(usage))))
@@ -623,6 +634,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         }
     }
 
@@ -730,6 +744,39 @@ mod tests {
         assert_eq!(content[0]["text"], "Hello");
     }
 
+    #[test]
+    fn test_transform_request_document_part() {
+        let transformer = make_transformer();
+        let request = make_basic_request(vec![ChatMessage {
+            role: MessageRole::User,
+            content: Some(MessageContent::Parts(vec![
+                ContentPart::Text {
+                    text: "Summarize this".to_string(),
+                },
+                ContentPart::Document {
+                    document: DocumentSource {
+                        data: "data:application/pdf;base64,JVBERi0x".to_string(),
+                        media_type: "application/pdf".to_string(),
+                        filename: Some("report.pdf".to_string()),
+                    },
+                },
+            ])),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }]);
+
+        let result = transformer.transform_request(&request).unwrap();
+        let content = result["messages"][0]["content"].as_array().unwrap();
+
+        assert_eq!(content.len(), 2);
+        assert_eq!(content[1]["type"], "document");
+        assert_eq!(content[1]["source"]["type"], "base64");
+        assert_eq!(content[1]["source"]["media_type"], "application/pdf");
+        assert_eq!(content[1]["source"]["data"], "JVBERi0x");
+        assert_eq!(content[1]["title"], "report.pdf");
+    }
+
     #[test]
     fn test_transform_request_tool_result() {
         let transformer = make_transformer();
@@ -1,4 +1,5 @@
 use crate::providers::ProviderError;
+use crate::providers::transform::util::{merge_extra_body, parse_image_url};
 use crate::providers::transformer::{ProviderResponseMeta, ProviderTransformer, StreamState};
 use crate::providers::types::*;
 
@@ -42,11 +43,7 @@ impl CopilotTransformer {
                 obj.insert("content".into(), serde_json::Value::String(text.clone()));
             }
             Some(MessageContent::Parts(parts)) => {
-                let parts_json: Vec<serde_json::Value> = parts
-                    .iter()
-                    .map(|p| serde_json::to_value(p).unwrap_or(serde_json::Value::Null))
-                    .collect();
-                obj.insert("content".into(), serde_json::Value::Array(parts_json));
+                obj.insert("content".into(), Self::convert_content_parts(parts));
             }
             None => {
                 obj.insert("content".into(), serde_json::Value::Null);
@@ -93,6 +90,59 @@ impl CopilotTransformer {
         serde_json::Value::Object(obj)
     }
 
+    /// Convert multimodal content parts to the OpenAI-style array Copilot
+    /// expects. Base64 data URLs are forwarded as `image_url` parts; remote
+    /// URLs and documents are not accepted by Copilot, so they become text
+    /// placeholders.
+    fn convert_content_parts(parts: &[ContentPart]) -> serde_json::Value {
+        let blocks: Vec<serde_json::Value> = parts
+            .iter()
+            .filter_map(|p| match p {
+                ContentPart::Text { text } => {
+                    if text.is_empty() {
+                        None
+                    } else {
+                        Some(serde_json::json!({"type": "text", "text": text}))
+                    }
+                }
+                ContentPart::ImageUrl { image_url } => {
+                    let (source_type, media_type, data) = parse_image_url(&image_url.url);
+                    if source_type == "base64" {
+                        let mut image = serde_json::json!({
+                            "url": format!("data:{media_type};base64,{data}"),
+                        });
+                        if let Some(detail) = &image_url.detail {
+                            image["detail"] = serde_json::Value::String(detail.clone());
+                        }
+                        Some(serde_json::json!({"type": "image_url", "image_url": image}))
+                    } else {
+                        Some(serde_json::json!({
+                            "type": "text",
+                            "text": format!("[Image: {}]", image_url.url),
+                        }))
+                    }
+                }
+                ContentPart::Document { document } => Some(serde_json::json!({
+                    "type": "text",
+                    "text": format!(
+                        "[Document: {}]",
+                        document.filename.as_deref().unwrap_or(&document.media_type)
+                    ),
+                })),
+            })
+            .collect();
+        serde_json::Value::Array(blocks)
+    }
+
+    /// Whether any message carries an image part. Copilot rejects image
+    /// content unless the request is flagged with `Copilot-Vision-Request`.
+    pub fn has_vision_content(request: &ChatRequest) -> bool {
+        request.messages.iter().any(|m| {
+            matches!(&m.content, Some(MessageContent::Parts(parts))
+                if parts.iter().any(|p| matches!(p, ContentPart::ImageUrl { .. })))
+        })
+    }
+
     fn parse_tool_calls(val: &serde_json::Value) -> Option<Vec<ToolCall>> {
         val.as_array().map(|arr| {
             arr.iter()
@@ -217,7 +267,10 @@ impl ProviderTransformer for CopilotTransformer {
             );
         }
 
-        Ok(serde_json::Value::Object(body))
+        let mut body = serde_json::Value::Object(body);
+        merge_extra_body(&mut body, request.extra_body.as_ref(), self.provider_id());
+
+        Ok(body)
     }
 
     fn transform_response(
@@ -274,6 +327,7 @@ impl ProviderTransformer for CopilotTransformer {
                                 content,
                                 reasoning_content: None,
                                 tool_calls,
+                                grounding: None,
                             },
                             finish_reason: c
                                 .get("finish_reason")
@@ -297,6 +351,7 @@ impl ProviderTransformer for CopilotTransformer {
             model,
             choices,
             usage,
+            tool_calls_emulated: false,
         })
     }
 
@@ -407,6 +462,7 @@ impl StreamState for CopilotStreamState {
                                 content,
                                 reasoning_content,
                                 tool_calls,
+                                grounding: None,
                             },
                             finish_reason: c
                                 .get("finish_reason")
@@ -425,6 +481,7 @@ impl StreamState for CopilotStreamState {
             model,
             choices,
             usage: None,
+            tool_calls_emulated: false,
         }))
     }
 
@@ -474,6 +531,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let result = transformer.transform_request(&request).unwrap();
@@ -501,6 +561,94 @@ mod tests {
         assert!(result.get("tool_choice").is_none());
     }
 
+    #[test]
+    fn test_transform_request_vision_parts() {
+        let transformer = make_transformer();
+        let request = ChatRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: Some(MessageContent::Parts(vec![
+                    ContentPart::Text {
+                        text: "What is this?".to_string(),
+                    },
+                    ContentPart::Text {
+                        text: String::new(),
+                    },
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: "data:image/jpeg;base64,/9j/4AAQ".to_string(),
+                            detail: Some("high".to_string()),
+                        },
+                    },
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: "https://example.com/cat.png".to_string(),
+                            detail: None,
+                        },
+                    },
+                ])),
+                name: None,
+                tool_call_id: None,
+                tool_calls: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stream: false,
+            top_p: None,
+            stop: None,
+            tools: None,
+            tool_choice: None,
+            stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
+        };
+
+        assert!(CopilotTransformer::has_vision_content(&request));
+
+        let result = transformer.transform_request(&request).unwrap();
+        let content = result["messages"][0]["content"].as_array().unwrap();
+        assert_eq!(content.len(), 3, "empty text part should be dropped");
+        assert_eq!(content[0]["type"], "text");
+        assert_eq!(content[1]["type"], "image_url");
+        assert_eq!(
+            content[1]["image_url"]["url"],
+            "data:image/jpeg;base64,/9j/4AAQ"
+        );
+        assert_eq!(content[1]["image_url"]["detail"], "high");
+        assert_eq!(content[2]["type"], "text");
+        assert_eq!(content[2]["text"], "[Image: https://example.com/cat.png]");
+    }
+
+    #[test]
+    fn test_has_vision_content_text_only() {
+        let request = ChatRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: Some(MessageContent::Parts(vec![ContentPart::Text {
+                    text: "Hi".to_string(),
+                }])),
+                name: None,
+                tool_call_id: None,
+                tool_calls: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stream: false,
+            top_p: None,
+            stop: None,
+            tools: None,
+            tool_choice: None,
+            stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
+        };
+        assert!(!CopilotTransformer::has_vision_content(&request));
+    }
+
     #[test]
     fn test_transform_response_basic() {
         let transformer = make_transformer();
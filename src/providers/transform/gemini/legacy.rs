@@ -112,6 +112,15 @@ impl GeminiTransformer {
                                 }
                             }
                         }
+                        ContentPart::Document { document } => {
+                            let (media_type, data) = document.base64();
+                            out.push(json!({
+                                "inlineData": {
+                                    "mimeType": media_type,
+                                    "data": data
+                                }
+                            }));
+                        }
                     }
                 }
                 out
@@ -265,6 +274,7 @@ impl ProviderTransformer for GeminiTransformer {
                     content,
                     reasoning_content: None,
                     tool_calls: tc,
+                    grounding: None,
                 },
                 finish_reason,
             });
@@ -294,6 +304,7 @@ impl ProviderTransformer for GeminiTransformer {
             model: meta.model.clone(),
             choices,
             usage,
+            tool_calls_emulated: false,
         })
     }
 
@@ -390,10 +401,12 @@ impl StreamState for GeminiStreamState {
                                 content: None,
                                 reasoning_content: None,
                                 tool_calls: None,
+                                grounding: None,
                             },
                             finish_reason,
                         }],
                         usage: None,
+                        tool_calls_emulated: false,
                     }));
                 }
                 return Ok(None);
@@ -453,10 +466,12 @@ impl StreamState for GeminiStreamState {
                     content: delta_content,
                     reasoning_content: None,
                     tool_calls: delta_tool_calls,
+                    grounding: None,
                 },
                 finish_reason,
             }],
             usage: None,
+            tool_calls_emulated: false,
         }))
     }
 
@@ -495,6 +510,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         }
     }
 
@@ -517,6 +535,29 @@ mod tests {
         assert!(result.get("system_instruction").is_none());
     }
 
+    #[test]
+    fn test_transform_request_document_inline_data() {
+        let t = make_transformer();
+        let req = simple_request(vec![ChatMessage {
+            role: MessageRole::User,
+            content: Some(MessageContent::Parts(vec![ContentPart::Document {
+                document: DocumentSource {
+                    data: "JVBERi0x".to_string(),
+                    media_type: "application/pdf".to_string(),
+                    filename: None,
+                },
+            }])),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }]);
+
+        let result = t.transform_request(&req).unwrap();
+        let part = &result["contents"][0]["parts"][0];
+        assert_eq!(part["inlineData"]["mimeType"], "application/pdf");
+        assert_eq!(part["inlineData"]["data"], "JVBERi0x");
+    }
+
     #[test]
     fn test_transform_request_with_system() {
         let t = make_transformer();
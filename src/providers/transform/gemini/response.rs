@@ -160,6 +160,7 @@ mod tests {
                 finish_reason: Some(finish_reason.to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: Some(UsageMetadata {
@@ -225,6 +226,7 @@ mod tests {
                 finish_reason: Some("TOOL_USE".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: None,
@@ -255,6 +257,7 @@ mod tests {
                 finish_reason: None, // Not specified
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: None,
@@ -277,6 +280,7 @@ mod tests {
                 finish_reason: Some("STOP".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: Some(UsageMetadata {
@@ -308,6 +312,7 @@ mod tests {
                 finish_reason: Some("STOP".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: None,
@@ -332,6 +337,7 @@ mod tests {
                 finish_reason: Some("STOP".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: None,
@@ -374,6 +380,7 @@ mod tests {
                 finish_reason: Some("STOP".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: None,
@@ -402,6 +409,7 @@ mod tests {
                 finish_reason: Some("TOOL_USE".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: None,
@@ -437,6 +445,7 @@ mod tests {
                 finish_reason: Some("STOP".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: Some(UsageMetadata {
@@ -466,6 +475,7 @@ mod tests {
                 finish_reason: Some("STOP".to_string()),
                 safety_ratings: vec![],
                 citation_metadata: None,
+                grounding_metadata: None,
                 index: None,
             }],
             usage_metadata: Some(UsageMetadata {
@@ -8,6 +8,7 @@
 use serde_json::{Value, json};
 
 use crate::providers::ProviderError;
+use crate::providers::transform::util::{document_block, merge_extra_body};
 use crate::providers::transformer::{
     ProviderResponseMeta, ProviderTransformer, StreamState, convert_tool_choice,
     convert_tools_to_anthropic, extract_system_message, filter_system_messages,
@@ -94,6 +95,7 @@ impl KiroTransformer {
                                 }))
                             }
                         }
+                        ContentPart::Document { document } => Some(document_block(document)),
                     })
                     .collect();
                 json!({
@@ -285,6 +287,8 @@ impl ProviderTransformer for KiroTransformer {
             body["tool_choice"] = tc;
         }
 
+        merge_extra_body(&mut body, request.extra_body.as_ref(), self.provider_id());
+
         Ok(body)
     }
 
@@ -374,6 +378,7 @@ impl ProviderTransformer for KiroTransformer {
                     } else {
                         Some(tool_calls)
                     },
+                    grounding: None,
                 },
                 finish_reason,
             }],
@@ -384,6 +389,7 @@ impl ProviderTransformer for KiroTransformer {
                 prompt_tokens_details,
                 completion_tokens_details: None,
             },
+            tool_calls_emulated: false,
         })
     }
 
@@ -467,6 +473,7 @@ impl KiroStreamState {
                 finish_reason,
             }],
             usage,
+            tool_calls_emulated: false,
         }
     }
 
@@ -506,6 +513,7 @@ impl StreamState for KiroStreamState {
                         content: None,
                         reasoning_content: None,
                         tool_calls: None,
+                        grounding: None,
                     },
                     None,
                     None,
@@ -538,6 +546,7 @@ impl StreamState for KiroStreamState {
                                     arguments: String::new(),
                                 },
                             }]),
+                            grounding: None,
                         },
                         None,
                         None,
@@ -560,6 +569,7 @@ impl StreamState for KiroStreamState {
                                 content: Some(text),
                                 reasoning_content: None,
                                 tool_calls: None,
+                                grounding: None,
                             },
                             None,
                             None,
@@ -586,6 +596,7 @@ impl StreamState for KiroStreamState {
                                         arguments: partial,
                                     },
                                 }]),
+                                grounding: None,
                             },
                             None,
                             None,
@@ -599,6 +610,7 @@ impl StreamState for KiroStreamState {
                                 content: None,
                                 reasoning_content: Some(thinking),
                                 tool_calls: None,
+                                grounding: None,
                             },
                             None,
                             None,
@@ -638,6 +650,7 @@ impl StreamState for KiroStreamState {
                         content: None,
                         reasoning_content: None,
                         tool_calls: None,
+                        grounding: None,
                     },
                     finish_reason,
                     usage,
@@ -748,6 +761,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -782,6 +798,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -821,6 +840,9 @@ mod tests {
             }]),
             tool_choice: Some(json!("auto")),
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -850,6 +872,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -882,6 +907,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -917,6 +945,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -943,6 +974,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -982,6 +1016,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -1020,6 +1057,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
 
         let body = transformer.transform_request(&req).unwrap();
@@ -1502,6 +1542,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         assert_eq!(body["system"], "You are a helpful assistant.");
@@ -1528,6 +1571,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         assert!(body.get("system").is_none() || body["system"].is_null());
@@ -1557,6 +1603,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
@@ -1584,6 +1633,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         assert!((body["top_p"].as_f64().unwrap() - 0.95).abs() < 0.001);
@@ -1610,6 +1662,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         // These should not be present when not set.
@@ -1637,6 +1692,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         let seqs = body["stop_sequences"].as_array().unwrap();
@@ -1689,6 +1747,9 @@ mod tests {
             tools: Some(tools),
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         let tools_val = body["tools"].as_array().unwrap();
@@ -1731,6 +1792,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         let content = body["messages"][0]["content"].as_array().unwrap();
@@ -1773,6 +1837,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         let content = body["messages"][0]["content"].as_array().unwrap();
@@ -2174,6 +2241,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         // Body should have stream: false regardless of request.stream
@@ -2202,6 +2272,9 @@ mod tests {
             tools: None,
             tool_choice: None,
             stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
         };
         let body = transformer.transform_request(&req).unwrap();
         let msgs = body["messages"].as_array().unwrap();
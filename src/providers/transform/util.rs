@@ -281,6 +281,23 @@ pub fn parse_image_url(url: &str) -> (String, String, String) {
     }
 }
 
+/// Build an Anthropic `document` content block from an inline document part.
+pub fn document_block(document: &DocumentSource) -> serde_json::Value {
+    let (media_type, data) = document.base64();
+    let mut block = serde_json::json!({
+        "type": "document",
+        "source": {
+            "type": "base64",
+            "media_type": media_type,
+            "data": data,
+        }
+    });
+    if let Some(filename) = &document.filename {
+        block["title"] = serde_json::Value::String(filename.clone());
+    }
+    block
+}
+
 // MARK: - Stop Sequences
 
 /// Normalize stop sequences into a vector of strings.
@@ -291,6 +308,51 @@ pub fn normalize_stop_sequences(stop: &Option<StopSequence>) -> Option<Vec<Strin
     })
 }
 
+// MARK: - Extra Body
+
+/// Provider IDs that may key a provider-specific object in `extra_body`.
+const EXTRA_BODY_PROVIDERS: &[&str] = &["claude", "copilot", "gemini", "kiro", "litellm"];
+
+/// Merge a request's `extra_body` into the provider payload `body`.
+///
+/// Top-level keys apply to every provider; an object under a provider ID key
+/// applies only to `provider` and takes precedence. Objects are merged
+/// recursively, anything else replaces the value in `body`.
+pub fn merge_extra_body(
+    body: &mut serde_json::Value,
+    extra: Option<&serde_json::Map<String, serde_json::Value>>,
+    provider: &str,
+) {
+    let Some(extra) = extra else {
+        return;
+    };
+    for (key, value) in extra {
+        if !EXTRA_BODY_PROVIDERS.contains(&key.as_str()) {
+            merge_json(&mut body[key.as_str()], value);
+        }
+    }
+    if let Some(own) = extra.get(provider).and_then(|v| v.as_object()) {
+        for (key, value) in own {
+            merge_json(&mut body[key.as_str()], value);
+        }
+    }
+}
+
+/// Recursively merge `value` into `target`.
+fn merge_json(target: &mut serde_json::Value, value: &serde_json::Value) {
+    match (target.as_object_mut(), value.as_object()) {
+        (Some(target), Some(value)) => {
+            for (key, value) in value {
+                merge_json(
+                    target.entry(key.clone()).or_insert(serde_json::Value::Null),
+                    value,
+                );
+            }
+        }
+        _ => *target = value.clone(),
+    }
+}
+
 // MARK: - Finish Reason Mapping
 
 /// Map provider-specific finish reasons to OpenAI format.
@@ -313,6 +375,16 @@ pub fn map_finish_reason_to_openai(reason: &str) -> &'static str {
     }
 }
 
+/// Map an OpenAI finish reason to an Anthropic `stop_reason`.
+pub fn map_finish_reason_to_anthropic(reason: &str) -> &'static str {
+    match reason {
+        "length" => "max_tokens",
+        "tool_calls" => "tool_use",
+        "content_filter" => "refusal",
+        _ => "end_turn",
+    }
+}
+
 // MARK: - Context Window Error Detection
 
 /// Detect context window / prompt-too-long errors from provider responses.
@@ -356,6 +428,47 @@ pub fn detect_context_window_error(
     }
 }
 
+// MARK: - Quota Exhaustion Detection
+
+/// Detect daily/monthly quota exhaustion from provider responses.
+///
+/// Returns a `ProviderError::QuotaExhausted` if the status code and response
+/// body indicate the account has used up its quota, as opposed to a
+/// short-lived per-minute rate limit.
+pub fn detect_quota_exhausted(
+    status: u16,
+    body: &str,
+    provider: &str,
+) -> Option<crate::providers::ProviderError> {
+    if !matches!(status, 402 | 403 | 429) {
+        return None;
+    }
+
+    let patterns = [
+        "insufficient_quota",
+        "quota_exceeded",
+        "quota exceeded",
+        "exceeded your current quota",
+        "exhausted your capacity",
+        "daily limit",
+        "monthly limit",
+        "premium request",
+    ];
+
+    let body_lower = body.to_lowercase();
+    if body_lower.contains("per minute") {
+        return None;
+    }
+    if patterns.iter().any(|p| body_lower.contains(p)) {
+        Some(crate::providers::ProviderError::QuotaExhausted {
+            provider: provider.to_string(),
+            message: body.to_string(),
+        })
+    } else {
+        None
+    }
+}
+
 // MARK: - Rate Limit Header Parsing
 
 /// Parse rate limit headers from a provider's HTTP response.
@@ -408,8 +521,8 @@ pub fn parse_rate_limit_headers(
                     }
                 }
             }
-            "copilot" | "gemini" => {
-                // OpenAI / Copilot / Gemini: forward x-ratelimit-* as-is.
+            "copilot" | "gemini" | "litellm" => {
+                // OpenAI / Copilot / Gemini / LiteLLM: forward x-ratelimit-* as-is.
                 if lower.starts_with("x-ratelimit-") {
                     normalized.push((lower, value.clone()));
                 }
@@ -655,6 +768,37 @@ mod tests {
         assert_eq!(data, url);
     }
 
+    #[test]
+    fn test_document_source_deserialize_defaults() {
+        let part: ContentPart =
+            serde_json::from_str(r#"{"type":"document","document":{"data":"JVBERi0x"}}"#).unwrap();
+        let ContentPart::Document { document } = part else {
+            panic!("expected document part");
+        };
+        assert_eq!(
+            document.base64(),
+            ("application/pdf".to_string(), "JVBERi0x".to_string())
+        );
+        assert!(document.filename.is_none());
+    }
+
+    #[test]
+    fn test_document_source_data_url() {
+        let document = DocumentSource {
+            data: "data:text/plain;base64,aGVsbG8=".to_string(),
+            media_type: "application/pdf".to_string(),
+            filename: None,
+        };
+        assert_eq!(
+            document.base64(),
+            ("text/plain".to_string(), "aGVsbG8=".to_string())
+        );
+        let block = document_block(&document);
+        assert_eq!(block["type"], "document");
+        assert_eq!(block["source"]["media_type"], "text/plain");
+        assert!(block.get("title").is_none());
+    }
+
     #[test]
     fn test_normalize_stop_sequences() {
         let single = Some(StopSequence::Single("STOP".to_string()));
@@ -683,6 +827,15 @@ mod tests {
         assert_eq!(map_finish_reason_to_openai("length"), "length");
     }
 
+    #[test]
+    fn test_map_finish_reason_to_anthropic() {
+        assert_eq!(map_finish_reason_to_anthropic("stop"), "end_turn");
+        assert_eq!(map_finish_reason_to_anthropic("length"), "max_tokens");
+        assert_eq!(map_finish_reason_to_anthropic("tool_calls"), "tool_use");
+        assert_eq!(map_finish_reason_to_anthropic("content_filter"), "refusal");
+        assert_eq!(map_finish_reason_to_anthropic(""), "end_turn");
+    }
+
     // -- Context window error detection tests --
 
     #[test]
@@ -719,6 +872,23 @@ mod tests {
         assert!(detect_context_window_error(500, body, "claude").is_none());
     }
 
+    #[test]
+    fn test_detect_quota_exhausted() {
+        let body = r#"{"error":{"type":"insufficient_quota","message":"You exceeded your current quota"}}"#;
+        assert!(matches!(
+            detect_quota_exhausted(429, body, "litellm"),
+            Some(crate::providers::ProviderError::QuotaExhausted { .. })
+        ));
+        let copilot = "You have reached your monthly limit of premium requests";
+        assert!(detect_quota_exhausted(403, copilot, "copilot").is_some());
+
+        // Per-minute limits and unrelated statuses are ordinary rate limits.
+        let rpm = "Quota exceeded for quota metric 'Requests per minute'";
+        assert!(detect_quota_exhausted(429, rpm, "gemini").is_none());
+        assert!(detect_quota_exhausted(400, body, "litellm").is_none());
+        assert!(detect_quota_exhausted(429, "Too many requests", "claude").is_none());
+    }
+
     #[test]
     fn test_detect_context_window_error_unrelated_400() {
         let body = r#"{"error": {"message": "invalid model name"}}"#;
@@ -781,4 +951,38 @@ mod tests {
         assert!(retry.is_none());
         assert!(normalized.is_empty());
     }
+
+    #[test]
+    fn test_merge_extra_body() {
+        let mut body = serde_json::json!({
+            "model": "m",
+            "generationConfig": {"temperature": 0.5},
+        });
+        let extra = serde_json::json!({
+            "metadata": {"user_id": "u1"},
+            "generationConfig": {"topK": 40},
+            "claude": {"top_k": 5},
+            "gemini": {"safety_settings": [{"category": "HARM_CATEGORY_HATE_SPEECH"}]},
+        });
+        merge_extra_body(&mut body, extra.as_object(), "gemini");
+
+        assert_eq!(body["metadata"]["user_id"], "u1");
+        assert_eq!(body["generationConfig"]["temperature"], 0.5);
+        assert_eq!(body["generationConfig"]["topK"], 40);
+        assert!(body["safety_settings"].is_array());
+        assert!(body.get("top_k").is_none());
+        assert!(body.get("claude").is_none());
+        assert!(body.get("gemini").is_none());
+    }
+
+    #[test]
+    fn test_merge_extra_body_provider_overrides_shared() {
+        let mut body = serde_json::json!({"model": "m"});
+        let extra = serde_json::json!({"top_k": 1, "claude": {"top_k": 5}});
+        merge_extra_body(&mut body, extra.as_object(), "claude");
+        assert_eq!(body["top_k"], 5);
+
+        merge_extra_body(&mut body, None, "claude");
+        assert_eq!(body, serde_json::json!({"model": "m", "top_k": 5}));
+    }
 }
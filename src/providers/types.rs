@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::providers::quota::ProviderQuotaStatus;
+
 /// OpenAI-compatible chat completion request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatRequest {
@@ -21,6 +23,20 @@ pub struct ChatRequest {
     pub tool_choice: Option<serde_json::Value>,
     #[serde(default)]
     pub stream_options: Option<StreamOptions>,
+    /// Gaud extension: ground the answer with Google Search. Only honored by
+    /// the Gemini provider, which reports the sources in
+    /// [`ResponseMessage::grounding`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub google_search: bool,
+    /// Provider-specific parameters merged into the upstream payload as is
+    /// (e.g. Anthropic `metadata`, Gemini `safety_settings`). An object under
+    /// a provider ID key (`"claude"`, `"gemini"`, ...) only applies to that
+    /// provider. See [`transform::util::merge_extra_body`](super::transform::util::merge_extra_body).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extra_body: Option<serde_json::Map<String, serde_json::Value>>,
+    /// The client's end user, recorded for cost attribution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
 }
 
 /// Options for streaming responses.
@@ -50,6 +66,19 @@ pub struct ChatMessage {
     pub tool_call_id: Option<String>,
 }
 
+impl ChatMessage {
+    /// A plain-text message from `role`.
+    pub fn text(role: MessageRole, text: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: Some(MessageContent::Text(text.into())),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum MessageContent {
@@ -79,6 +108,9 @@ pub enum ContentPart {
     Text { text: String },
     #[serde(rename = "image_url")]
     ImageUrl { image_url: ImageUrl },
+    /// Gaud extension: an inline document (e.g. a base64-encoded PDF).
+    #[serde(rename = "document")]
+    Document { document: DocumentSource },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +120,42 @@ pub struct ImageUrl {
     pub detail: Option<String>,
 }
 
+/// Inline document payload for [`ContentPart::Document`].
+///
+/// `data` is either raw base64 or a `data:` URL; in the latter case the
+/// media type embedded in the URL takes precedence over `media_type`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSource {
+    pub data: String,
+    #[serde(default = "default_document_media_type")]
+    pub media_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+}
+
+fn default_document_media_type() -> String {
+    "application/pdf".to_string()
+}
+
+impl DocumentSource {
+    /// Resolve the `(media_type, base64_data)` pair for this document.
+    pub fn base64(&self) -> (String, String) {
+        match self.data.strip_prefix("data:") {
+            Some(rest) => {
+                let (header, data) = rest.split_once(',').unwrap_or((rest, ""));
+                let media_type = header.split(';').next().unwrap_or("");
+                let media_type = if media_type.is_empty() {
+                    self.media_type.clone()
+                } else {
+                    media_type.to_string()
+                };
+                (media_type, data.to_string())
+            }
+            None => (self.media_type.clone(), self.data.clone()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum MessageRole {
@@ -106,6 +174,10 @@ pub struct ChatResponse {
     pub model: String,
     pub choices: Vec<Choice>,
     pub usage: Usage,
+    /// Gaud extension: the model lacks native tool calling, so the tools
+    /// were described in the prompt and its tool calls parsed from the text.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tool_calls_emulated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -123,6 +195,46 @@ pub struct ResponseMessage {
     pub reasoning_content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Gaud extension: search grounding of the answer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grounding: Option<Grounding>,
+}
+
+/// Search grounding of an answer: the queries run, the web sources found,
+/// and which parts of the answer each source supports.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Grounding {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub search_queries: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<GroundingSource>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub citations: Vec<GroundingCitation>,
+    /// HTML of the search suggestions, which Google requires to be shown
+    /// alongside grounded answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_entry_point: Option<String>,
+}
+
+/// A web page a grounded answer is based on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GroundingSource {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+/// A span of the answer and the sources supporting it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct GroundingCitation {
+    /// Byte offsets of the span in the answer text.
+    pub start_index: u32,
+    pub end_index: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Indices into [`Grounding::sources`].
+    #[serde(default)]
+    pub source_indices: Vec<u32>,
 }
 
 /// Detailed token usage breakdown (e.g., cached tokens, reasoning tokens).
@@ -156,6 +268,9 @@ pub struct ChatChunk {
     pub choices: Vec<ChunkChoice>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usage: Option<Usage>,
+    /// Gaud extension: see [`ChatResponse::tool_calls_emulated`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tool_calls_emulated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -175,6 +290,9 @@ pub struct Delta {
     pub reasoning_content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Gaud extension: search grounding, sent with the final chunk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grounding: Option<Grounding>,
 }
 
 /// Tool/function definition.
@@ -222,6 +340,36 @@ pub struct ModelInfo {
     pub object: String,
     pub created: i64,
     pub owned_by: String,
+    /// Every provider serving the model.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    /// Omitted when the model's features are unknown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<ModelFeatures>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPrice>,
+}
+
+/// Features of a model in the models list.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelFeatures {
+    pub vision: bool,
+    pub tools: bool,
+    pub thinking: bool,
+}
+
+/// Token prices of a model in the models list, per 1M tokens in `currency`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_input_per_million: Option<f64>,
+    pub currency: String,
 }
 
 /// Embeddings request.
@@ -262,6 +410,105 @@ pub struct EmbeddingUsage {
     pub total_tokens: u32,
 }
 
+/// Image generation request (OpenAI-compatible).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageGenerationRequest {
+    pub model: String,
+    pub prompt: String,
+    #[serde(default)]
+    pub n: Option<u32>,
+    /// `WIDTHxHEIGHT`, e.g. `1024x1024`.
+    #[serde(default)]
+    pub size: Option<String>,
+    #[serde(default)]
+    pub quality: Option<String>,
+    /// `url` or `b64_json`.
+    #[serde(default)]
+    pub response_format: Option<String>,
+}
+
+impl ImageGenerationRequest {
+    /// Number of images requested (OpenAI defaults to one).
+    pub fn count(&self) -> u32 {
+        self.n.unwrap_or(1)
+    }
+}
+
+/// Image generation response (OpenAI-compatible).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageGenerationResponse {
+    pub created: i64,
+    pub data: Vec<ImageData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageData {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub b64_json: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revised_prompt: Option<String>,
+}
+
+/// Audio transcription request, assembled from an OpenAI-style multipart
+/// upload.
+#[derive(Debug, Clone)]
+pub struct TranscriptionRequest {
+    pub model: String,
+    /// Raw audio file contents.
+    pub file: bytes::Bytes,
+    pub filename: String,
+    pub content_type: Option<String>,
+    pub language: Option<String>,
+    pub prompt: Option<String>,
+    /// `json`, `text`, `srt`, `verbose_json`, or `vtt`.
+    pub response_format: Option<String>,
+    pub temperature: Option<f32>,
+}
+
+impl TranscriptionRequest {
+    /// Whether the client asked for a subtitle format that is passed through
+    /// verbatim rather than parsed.
+    pub fn wants_subtitles(&self) -> bool {
+        matches!(self.response_format.as_deref(), Some("srt" | "vtt"))
+    }
+}
+
+/// Audio transcription result.
+///
+/// Providers return the richest form they have; the API layer trims it to
+/// the `response_format` the client asked for. For `srt`/`vtt` requests
+/// `text` holds the subtitle document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TranscriptionResponse {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Length of the input audio in seconds, when the provider reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segments: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub words: Option<serde_json::Value>,
+}
+
+/// Text-to-speech request (OpenAI-compatible).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeechRequest {
+    pub model: String,
+    pub input: String,
+    pub voice: String,
+    /// `mp3`, `opus`, `aac`, `flac`, `wav`, or `pcm`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instructions: Option<String>,
+}
+
 /// Internal token usage for cost tracking.
 #[derive(Debug, Clone, Default)]
 pub struct TokenUsage {
@@ -276,6 +523,9 @@ pub struct ProviderStatus {
     pub healthy: bool,
     pub models: Vec<String>,
     pub latency_ms: Option<u64>,
+    /// Daily/monthly account quota usage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota: Option<ProviderQuotaStatus>,
 }
 
 // ModelPricing moved to pricing module
@@ -0,0 +1,519 @@
+//! Record and replay provider HTTP traffic.
+//!
+//! [`VcrServer`] is a local HTTP server that a provider is pointed at in
+//! place of its upstream API (`with_api_base`). In record mode it forwards
+//! every request to the real upstream and writes the exchange to a JSON
+//! cassette; in replay mode it answers from the cassette without touching
+//! the network. Tests replay checked-in cassettes to exercise a provider's
+//! request and response transformation, and the router around it, end to
+//! end and hermetically.
+//!
+//! Requests are matched on method, path with query, and body (compared as
+//! JSON when it parses), so a change in what a transformer sends shows up
+//! as a missing interaction. Request headers are never stored, which keeps
+//! API keys and tokens out of cassettes. Streaming responses are recorded
+//! whole and replayed in one piece.
+//!
+//! To re-record a cassette, run the test with `GAUD_VCR_RECORD=1` and real
+//! credentials; see [`VcrServer::from_env`].
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri};
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+
+/// Environment variable that switches [`VcrServer::from_env`] to recording.
+pub const RECORD_ENV: &str = "GAUD_VCR_RECORD";
+
+/// Response headers left out of cassettes: per-connection or per-response
+/// values that would only make fixtures noisy.
+const SKIPPED_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "date",
+    "keep-alive",
+    "set-cookie",
+    "transfer-encoding",
+];
+
+/// Recorded interactions, in the order they happened.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query, relative to the upstream base URL.
+    pub path: String,
+    /// JSON body, or the raw body as a string. `null` when empty.
+    #[serde(default)]
+    pub body: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let raw = std::fs::read_to_string(path)?;
+        serde_json::from_str(&raw).map_err(std::io::Error::other)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json + "\n")
+    }
+}
+
+/// What the server does with a request.
+#[derive(Debug, Clone)]
+pub enum VcrMode {
+    /// Forward to `upstream` (a base URL) and record the exchange.
+    Record { upstream: String },
+    /// Answer from the cassette; unmatched requests get a 501.
+    Replay,
+}
+
+struct VcrState {
+    mode: VcrMode,
+    http: reqwest::Client,
+    cassette: Mutex<Cassette>,
+    /// Which interactions have been replayed.
+    played: Mutex<Vec<bool>>,
+}
+
+/// A running record/replay server. Stops when dropped.
+pub struct VcrServer {
+    base_url: String,
+    state: Arc<VcrState>,
+    path: Option<PathBuf>,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl VcrServer {
+    /// Serve `cassette` on a free local port.
+    pub async fn start(mode: VcrMode, cassette: Cassette) -> std::io::Result<Self> {
+        let played = vec![false; cassette.interactions.len()];
+        let state = Arc::new(VcrState {
+            mode,
+            http: reqwest::Client::new(),
+            cassette: Mutex::new(cassette),
+            played: Mutex::new(played),
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let base_url = format!("http://{}", listener.local_addr()?);
+        let app = axum::Router::new()
+            .fallback(handle)
+            .with_state(state.clone());
+        let (shutdown, stopped) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app)
+                .with_graceful_shutdown(async {
+                    let _ = stopped.await;
+                })
+                .await;
+        });
+        Ok(Self {
+            base_url,
+            state,
+            path: None,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Replay the cassette at `path`.
+    pub async fn replay(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let mut server = Self::start(VcrMode::Replay, Cassette::load(&path)?).await?;
+        server.path = Some(path);
+        Ok(server)
+    }
+
+    /// Record traffic to `upstream` into a new cassette, written to `path`
+    /// by [`VcrServer::save`].
+    pub async fn record(
+        upstream: impl Into<String>,
+        path: impl Into<PathBuf>,
+    ) -> std::io::Result<Self> {
+        let upstream = upstream.into().trim_end_matches('/').to_string();
+        let mut server = Self::start(VcrMode::Record { upstream }, Cassette::default()).await?;
+        server.path = Some(path.into());
+        Ok(server)
+    }
+
+    /// Record against `upstream` when [`RECORD_ENV`] is set, otherwise
+    /// replay `path`.
+    pub async fn from_env(
+        path: impl Into<PathBuf>,
+        upstream: impl Into<String>,
+    ) -> std::io::Result<Self> {
+        if std::env::var_os(RECORD_ENV).is_some() {
+            Self::record(upstream, path).await
+        } else {
+            Self::replay(path).await
+        }
+    }
+
+    /// Base URL to hand to a provider's `with_api_base`.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The interactions recorded or loaded so far.
+    pub fn cassette(&self) -> Cassette {
+        lock(&self.state.cassette).clone()
+    }
+
+    /// Number of loaded interactions not replayed yet.
+    pub fn unplayed(&self) -> usize {
+        lock(&self.state.played).iter().filter(|p| !**p).count()
+    }
+
+    /// Write the cassette back to its file after recording. Does nothing in
+    /// replay mode.
+    pub fn save(&self) -> std::io::Result<()> {
+        match (&self.state.mode, &self.path) {
+            (VcrMode::Record { .. }, Some(path)) => self.cassette().save(path),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for VcrServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(
+    State(state): State<Arc<VcrState>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let request = RecordedRequest {
+        method: method.to_string(),
+        path: uri
+            .path_and_query()
+            .map_or_else(|| uri.path().to_string(), |pq| pq.to_string()),
+        body: body_value(&body),
+    };
+
+    let recorded = match &state.mode {
+        VcrMode::Replay => replay(&state, &request),
+        VcrMode::Record { upstream } => {
+            match forward(
+                &state.http,
+                upstream,
+                &method,
+                &request.path,
+                &headers,
+                body,
+            )
+            .await
+            {
+                Ok(response) => {
+                    lock(&state.cassette).interactions.push(Interaction {
+                        request,
+                        response: response.clone(),
+                    });
+                    Some(response)
+                }
+                Err(e) => {
+                    return plain(StatusCode::BAD_GATEWAY, format!("VCR upstream error: {e}"));
+                }
+            }
+        }
+    };
+
+    match recorded {
+        Some(recorded) => {
+            let mut response = Response::new(Body::from(recorded.body));
+            *response.status_mut() =
+                StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::OK);
+            for (name, value) in &recorded.headers {
+                if let (Ok(name), Ok(value)) = (
+                    HeaderName::try_from(name.as_str()),
+                    HeaderValue::try_from(value.as_str()),
+                ) {
+                    response.headers_mut().append(name, value);
+                }
+            }
+            response
+        }
+        None => {
+            tracing::warn!(method = %method, path = %uri, "No recorded interaction");
+            plain(
+                StatusCode::NOT_IMPLEMENTED,
+                format!("No recorded interaction for {method} {uri}"),
+            )
+        }
+    }
+}
+
+/// The first interaction matching `request` that hasn't been replayed yet.
+fn replay(state: &VcrState, request: &RecordedRequest) -> Option<RecordedResponse> {
+    let cassette = lock(&state.cassette);
+    let mut played = lock(&state.played);
+    let index = cassette
+        .interactions
+        .iter()
+        .enumerate()
+        .position(|(i, it)| {
+            !played[i]
+                && it.request.method == request.method
+                && it.request.path == request.path
+                && it.request.body == request.body
+        })?;
+    played[index] = true;
+    Some(cassette.interactions[index].response.clone())
+}
+
+async fn forward(
+    http: &reqwest::Client,
+    upstream: &str,
+    method: &Method,
+    path: &str,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> Result<RecordedResponse, reqwest::Error> {
+    let mut outgoing = http.request(method.clone(), format!("{upstream}{path}"));
+    for (name, value) in headers {
+        if name != "host" && name != "content-length" {
+            outgoing = outgoing.header(name, value);
+        }
+    }
+    let response = outgoing.body(body).send().await?;
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = response.text().await?;
+    Ok(RecordedResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn body_value(body: &[u8]) -> serde_json::Value {
+    if body.is_empty() {
+        return serde_json::Value::Null;
+    }
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+fn plain(status: StatusCode, message: String) -> Response {
+    let mut response = Response::new(Body::from(message));
+    *response.status_mut() = status;
+    response
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::claude::ClaudeProvider;
+    use crate::providers::retry::RetryPolicy;
+    use crate::providers::router::ProviderRouter;
+    use crate::providers::types::*;
+    use crate::providers::{ProviderError, TokenService};
+    use futures::StreamExt;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Replays need no credentials; recording uses `ANTHROPIC_API_KEY`.
+    struct EnvToken;
+
+    #[async_trait::async_trait]
+    impl TokenService for EnvToken {
+        async fn get_token(&self, _provider: &str) -> Result<String, ProviderError> {
+            Ok(std::env::var("ANTHROPIC_API_KEY").unwrap_or_else(|_| "test-token".to_string()))
+        }
+    }
+
+    const ANTHROPIC_API: &str = "https://api.anthropic.com/v1";
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/vcr")
+            .join(name)
+    }
+
+    fn request(text: &str, stream: bool) -> ChatRequest {
+        ChatRequest {
+            model: "claude-sonnet-4-20250514".into(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: Some(MessageContent::Text(text.into())),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            max_tokens: Some(64),
+            stream,
+            top_p: None,
+            stop: None,
+            tools: None,
+            tool_choice: None,
+            stream_options: None,
+            google_search: false,
+            extra_body: None,
+            user: None,
+        }
+    }
+
+    fn router(vcr: &VcrServer) -> ProviderRouter {
+        let mut router = ProviderRouter::new();
+        // A replayed interaction is consumed, so a retry could never match.
+        router.set_retry_policy(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        });
+        router.register(Arc::new(
+            ClaudeProvider::new(Arc::new(EnvToken)).with_api_base(vcr.base_url()),
+        ));
+        router
+    }
+
+    #[tokio::test]
+    async fn test_claude_chat_replays_through_router() {
+        let vcr = VcrServer::from_env(fixture("claude_chat.json"), ANTHROPIC_API)
+            .await
+            .unwrap();
+        let mut router = router(&vcr);
+        let resp = router
+            .chat(&request("What is the capital of France?", false))
+            .await
+            .unwrap();
+        assert_eq!(resp.model, "claude-sonnet-4-20250514");
+        assert_eq!(resp.choices[0].message.content.as_deref(), Some("Paris."));
+        assert_eq!(resp.usage.prompt_tokens, 14);
+        assert_eq!(resp.usage.completion_tokens, 4);
+        vcr.save().unwrap();
+        assert_eq!(vcr.unplayed(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_claude_stream_replays_through_router() {
+        let vcr = VcrServer::from_env(fixture("claude_stream.json"), ANTHROPIC_API)
+            .await
+            .unwrap();
+        let mut router = router(&vcr);
+        let mut stream = router
+            .stream_chat(&request("Count to three.", true))
+            .await
+            .unwrap();
+        let mut text = String::new();
+        let mut finish = None;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            for choice in chunk.choices {
+                text.push_str(choice.delta.content.as_deref().unwrap_or(""));
+                finish = choice.finish_reason.or(finish);
+            }
+        }
+        vcr.save().unwrap();
+        assert_eq!(text, "One, two, three.");
+        assert_eq!(finish.as_deref(), Some("stop"));
+    }
+
+    #[tokio::test]
+    async fn test_changed_request_is_not_replayed() {
+        let vcr = VcrServer::replay(fixture("claude_chat.json"))
+            .await
+            .unwrap();
+        let mut router = router(&vcr);
+        let err = router
+            .chat(&request("What is the capital of Spain?", false))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::Api { status: 501, .. }));
+        assert_eq!(vcr.unplayed(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let upstream = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/messages"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-request-id", "abc")
+                    .set_body_string("recorded"),
+            )
+            .expect(1)
+            .mount(&upstream)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cassette = dir.path().join("cassette.json");
+        let http = reqwest::Client::new();
+        let recorder = VcrServer::record(upstream.uri(), &cassette).await.unwrap();
+        let resp = http
+            .post(format!("{}/messages", recorder.base_url()))
+            .header("x-api-key", "secret-key")
+            .json(&serde_json::json!({"n": 1}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.text().await.unwrap(), "recorded");
+        recorder.save().unwrap();
+        drop(recorder);
+        assert!(
+            !std::fs::read_to_string(&cassette)
+                .unwrap()
+                .contains("secret-key")
+        );
+
+        let player = VcrServer::replay(&cassette).await.unwrap();
+        let resp = http
+            .post(format!("{}/messages", player.base_url()))
+            .json(&serde_json::json!({"n": 1}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "abc");
+        assert_eq!(resp.text().await.unwrap(), "recorded");
+        assert_eq!(player.unplayed(), 0);
+        // Each interaction is replayed once.
+        let again = http
+            .post(format!("{}/messages", player.base_url()))
+            .json(&serde_json::json!({"n": 1}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(again.status(), 501);
+    }
+}
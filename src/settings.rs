@@ -0,0 +1,179 @@
+//! Runtime-tunable settings.
+//!
+//! The keys in [`RUNTIME_SETTINGS`] -- the semantic cache threshold, the
+//! provider retry policy, the default `max_tokens`, the upstream queue
+//! limits and the alert thresholds -- apply as soon as an admin changes
+//! them through `PUT /admin/settings`. Changes are stored in the
+//! `runtime_settings` table instead of the config file and win over the
+//! file at startup; environment variables still win over both.
+//!
+//! [`AppState::config`] keeps the configuration the server started with.
+//! Code reading a runtime setting goes through [`RuntimeSettings::config`]
+//! instead, and parts of the server that keep their own copy (the cache,
+//! the router's retry policy, the notifier and the scheduler) are updated
+//! by [`update`].
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use rusqlite::params;
+use serde_json::Value;
+
+use crate::AppState;
+use crate::config::{Config, RUNTIME_SETTINGS};
+use crate::db::Database;
+use crate::error::AppError;
+
+/// The configuration with runtime changes applied.
+#[derive(Debug)]
+pub struct RuntimeSettings {
+    config: RwLock<Arc<Config>>,
+}
+
+impl RuntimeSettings {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self {
+            config: RwLock::new(config),
+        }
+    }
+
+    /// The current configuration, including runtime changes.
+    pub fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.config.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn replace(&self, config: Config) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+    }
+}
+
+/// Whether `key` applies without a restart.
+pub fn is_runtime(key: &str) -> bool {
+    RUNTIME_SETTINGS.contains(&key)
+}
+
+/// Runtime settings stored in the database, by key.
+pub fn load(db: &Database) -> Result<BTreeMap<String, Value>, AppError> {
+    let rows = db.with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT key, value FROM runtime_settings ORDER BY key")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, serde_json::from_str(&value).ok()?)))
+        .collect())
+}
+
+fn store(db: &Database, key: &str, value: &Value, updated_by: &str) -> Result<(), AppError> {
+    db.with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO runtime_settings (key, value, updated_by) VALUES (?1, ?2, ?3) \
+             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_by = ?3, \
+             updated_at = datetime('now')",
+            params![key, value.to_string(), updated_by],
+        )
+    })?;
+    Ok(())
+}
+
+/// Apply the stored runtime settings over `config`, for startup. Settings
+/// locked by an environment variable keep the variable's value; stored
+/// values that no longer fit are skipped with a warning.
+pub fn apply_stored(config: &mut Config, db: &Database) -> Result<(), AppError> {
+    for (key, value) in load(db)? {
+        if !is_runtime(&key) {
+            continue;
+        }
+        if let Some(var) = config.env_overrides.env_var_for(&key) {
+            tracing::info!(setting = %key, env_var = %var, "Stored runtime setting overridden by environment");
+            continue;
+        }
+        let mut updated = config.clone();
+        match updated
+            .update_setting(&key, &value)
+            .and_then(|()| updated.validate())
+        {
+            Ok(()) => {
+                tracing::info!(setting = %key, value = %value, "Applied stored runtime setting");
+                *config = updated;
+            }
+            Err(e) => {
+                tracing::warn!(setting = %key, value = %value, error = %e, "Ignoring stored runtime setting");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Change a runtime setting: check it, store it and apply it to the running
+/// server.
+pub async fn update(
+    state: &AppState,
+    key: &str,
+    value: &Value,
+    updated_by: &str,
+) -> Result<(), AppError> {
+    let mut config = (*state.settings.config()).clone();
+    config
+        .update_setting(key, value)
+        .map_err(AppError::BadRequest)?;
+    config.validate().map_err(AppError::BadRequest)?;
+    store(&state.db, key, value, updated_by)?;
+
+    if let Some(cache) = &state.cache {
+        cache.set_similarity_threshold(config.cache.similarity_threshold);
+    }
+    state
+        .router
+        .write()
+        .await
+        .set_retry_policy(config.providers.retry.policy());
+    state
+        .notifier
+        .set_cooldown(Duration::from_secs(config.notifications.cooldown_secs));
+    state.scheduler.resize(config.limits.max_in_flight);
+
+    state.settings.replace(config);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_stored_overrides_file() {
+        let db = Database::open_in_memory().unwrap();
+        let put = |key: &str, value: Value| store(&db, key, &value, "admin").unwrap();
+        put("limits.max_in_flight", serde_json::json!(8));
+        put("limits.queue_timeout_secs", serde_json::json!(5));
+        put("limits.queue_timeout_secs", serde_json::json!(12));
+        // Invalid or no longer runtime-tunable values are skipped.
+        put("cache.similarity_threshold", serde_json::json!(3.0));
+        put("server.port", serde_json::json!(1));
+
+        let mut config = Config::default();
+        apply_stored(&mut config, &db).unwrap();
+        assert_eq!(config.limits.max_in_flight, 8);
+        assert_eq!(config.limits.queue_timeout_secs, 12);
+        assert_eq!(
+            config.cache.similarity_threshold,
+            Config::default().cache.similarity_threshold
+        );
+        assert_eq!(config.server.port, Config::default().server.port);
+    }
+
+    #[test]
+    fn test_runtime_settings_in_report() {
+        let report = Config::default().settings_report();
+        for key in RUNTIME_SETTINGS {
+            let entry = report.iter().find(|e| e.key == *key);
+            assert!(entry.is_some_and(|e| e.runtime), "{key} missing");
+        }
+        assert!(!report.iter().any(|e| e.key == "server.port" && e.runtime));
+    }
+}
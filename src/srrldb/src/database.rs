@@ -37,6 +37,17 @@ impl Database {
         })
     }
 
+    /// Connect to a TiKV cluster through its placement driver, e.g.
+    /// `tikv://pd-host:2379`. Every handle on the same cluster shares data.
+    #[cfg(feature = "kv-tikv")]
+    pub async fn new_tikv(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let ds = Datastore::new(url).await?;
+        Ok(Self {
+            ds: Arc::new(ds),
+            session: Session::owner(),
+        })
+    }
+
     /// Open an ephemeral in-memory datastore.
     #[cfg(feature = "kv-mem")]
     pub async fn new_mem() -> Result<Self, Box<dyn std::error::Error>> {
@@ -0,0 +1,352 @@
+//! Built-in TLS termination.
+//!
+//! With `server.tls_cert_path` / `server.tls_key_path` set, gaud serves HTTPS
+//! using a certificate read from disk. With `[server.acme]` (and the `acme`
+//! build feature) certificates are obtained and renewed from Let's Encrypt
+//! instead. Either way the result is a [`TlsListener`] that plugs into
+//! `axum::serve` in place of the plain `TcpListener`.
+//!
+//! TLS handshakes run off the accept loop so a slow or stalled client cannot
+//! hold up other connections.
+//!
+//! A listener can also ask for client certificates signed by
+//! `auth.tls_client_cert.ca_cert_path` (mTLS). The verified certificate's
+//! common name is exposed to handlers through `ConnectInfo<TlsPeer>`.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::extract::connect_info::Connected;
+use axum::serve::IncomingStream;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::crypto::CryptoProvider;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::server::danger::ClientCertVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+
+use crate::config::{self, TlsClientCertConfig};
+
+/// Handshakes that take longer than this are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accepted connections waiting to be picked up by the server.
+const ACCEPT_BACKLOG: usize = 128;
+
+/// ALPN protocols offered to clients (hyper serves both).
+fn alpn_protocols() -> Vec<Vec<u8>> {
+    vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+}
+
+/// A TLS-wrapped client connection.
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send + 'static {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + 'static> Connection for T {}
+
+/// Peer of a TLS connection, available to handlers as `ConnectInfo<TlsPeer>`
+/// when the app is served with `into_make_service_with_connect_info`.
+#[derive(Debug, Clone)]
+pub struct TlsPeer {
+    pub addr: SocketAddr,
+    /// Whether this listener verifies client certificates.
+    pub client_auth: bool,
+    /// Common name of the verified client certificate, if one was presented.
+    pub client_cn: Option<String>,
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for TlsPeer {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        stream.remote_addr().clone()
+    }
+}
+
+/// Listener yielding connections whose TLS handshake has completed.
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(Box<dyn Connection>, TlsPeer)>,
+    local_addr: TlsPeer,
+}
+
+impl TlsListener {
+    /// Build a listener for the TLS settings in `config`. With `client_certs`,
+    /// clients are asked for a certificate signed by its CA.
+    pub fn from_config(
+        listener: TcpListener,
+        config: &config::ServerConfig,
+        client_certs: Option<&TlsClientCertConfig>,
+    ) -> anyhow::Result<Self> {
+        if let Some(ref acme) = config.acme {
+            if config.tls_cert_path.is_some() || config.tls_key_path.is_some() {
+                anyhow::bail!(
+                    "server.acme cannot be combined with server.tls_cert_path / server.tls_key_path"
+                );
+            }
+            if client_certs.is_some() {
+                anyhow::bail!("Client certificate verification is not supported with server.acme");
+            }
+            return Self::acme(listener, acme);
+        }
+
+        match (&config.tls_cert_path, &config.tls_key_path) {
+            (Some(cert), Some(key)) => Self::from_pem_files(
+                listener,
+                Path::new(shellexpand::tilde(cert).as_ref()),
+                Path::new(shellexpand::tilde(key).as_ref()),
+                client_certs,
+            ),
+            _ => anyhow::bail!("server.tls_cert_path and server.tls_key_path must be set together"),
+        }
+    }
+
+    /// Serve a certificate chain and private key read from PEM files.
+    pub fn from_pem_files(
+        listener: TcpListener,
+        cert_path: &Path,
+        key_path: &Path,
+        client_certs: Option<&TlsClientCertConfig>,
+    ) -> anyhow::Result<Self> {
+        let certs = CertificateDer::pem_file_iter(cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .with_context(|| format!("Failed to read certificate {}", cert_path.display()))?;
+        if certs.is_empty() {
+            anyhow::bail!("No certificates found in {}", cert_path.display());
+        }
+        let key = PrivateKeyDer::from_pem_file(key_path)
+            .with_context(|| format!("Failed to read private key {}", key_path.display()))?;
+
+        let provider = Arc::new(tokio_rustls::rustls::crypto::ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .context("Failed to set up TLS")?;
+        let builder = match client_certs {
+            Some(client_certs) => {
+                builder.with_client_cert_verifier(client_verifier(client_certs, provider)?)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let mut server_config = builder
+            .with_single_cert(certs, key)
+            .context("Invalid TLS certificate or key")?;
+        server_config.alpn_protocols = alpn_protocols();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+        let client_auth = client_certs.is_some();
+        let local_addr = TlsPeer {
+            addr: listener.local_addr()?,
+            client_auth,
+            client_cn: None,
+        };
+        let (tx, incoming) = mpsc::channel(ACCEPT_BACKLOG);
+        tokio::spawn(accept_loop(listener, acceptor, client_auth, tx));
+
+        Ok(Self {
+            incoming,
+            local_addr,
+        })
+    }
+
+    #[cfg(feature = "acme")]
+    fn acme(listener: TcpListener, acme: &config::AcmeConfig) -> anyhow::Result<Self> {
+        use rustls_acme::caches::DirCache;
+        use tokio_stream::StreamExt;
+        use tokio_stream::wrappers::TcpListenerStream;
+
+        if acme.domains.is_empty() {
+            anyhow::bail!("server.acme.domains must not be empty");
+        }
+
+        let local_addr = TlsPeer {
+            addr: listener.local_addr()?,
+            client_auth: false,
+            client_cn: None,
+        };
+        let cache_dir = shellexpand::tilde(&acme.cache_dir).into_owned();
+        let mut tls_incoming = rustls_acme::AcmeConfig::new(&acme.domains)
+            .contact(&acme.contact)
+            .cache(DirCache::new(cache_dir))
+            .directory_lets_encrypt(acme.production)
+            .tokio_incoming(TcpListenerStream::new(listener), alpn_protocols());
+
+        tracing::info!(
+            domains = ?acme.domains,
+            production = acme.production,
+            "ACME certificate management enabled"
+        );
+
+        let (tx, incoming) = mpsc::channel(ACCEPT_BACKLOG);
+        tokio::spawn(async move {
+            while let Some(conn) = tls_incoming.next().await {
+                let tls = match conn {
+                    Ok(tls) => tls,
+                    Err(e) => {
+                        tracing::debug!(error = %e, "Failed to accept TCP connection");
+                        continue;
+                    }
+                };
+                // Compat<TlsStream<Compat<TcpStream>>>
+                let Ok(addr) = tls.get_ref().get_ref().0.get_ref().peer_addr() else {
+                    continue;
+                };
+                let peer = TlsPeer {
+                    addr,
+                    client_auth: false,
+                    client_cn: None,
+                };
+                let conn: Box<dyn Connection> = Box::new(tls);
+                if tx.send((conn, peer)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            incoming,
+            local_addr,
+        })
+    }
+
+    #[cfg(not(feature = "acme"))]
+    fn acme(_listener: TcpListener, _acme: &config::AcmeConfig) -> anyhow::Result<Self> {
+        anyhow::bail!("server.acme requires gaud to be built with the `acme` feature")
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = Box<dyn Connection>;
+    type Addr = TlsPeer;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(conn) => conn,
+            // The accept task only exits once the listener is dropped, which
+            // cannot happen while we still hold the receiver.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr.clone())
+    }
+}
+
+/// Accept TCP connections and complete their handshakes concurrently.
+async fn accept_loop(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    client_auth: bool,
+    tx: mpsc::Sender<(Box<dyn Connection>, TlsPeer)>,
+) {
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                // Typically EMFILE; back off instead of spinning.
+                tracing::warn!(error = %e, "Failed to accept TCP connection");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        if tx.is_closed() {
+            break;
+        }
+
+        let acceptor = acceptor.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(tls)) => {
+                    let client_cn = tls
+                        .get_ref()
+                        .1
+                        .peer_certificates()
+                        .and_then(|certs| certs.first())
+                        .and_then(common_name);
+                    let peer = TlsPeer {
+                        addr,
+                        client_auth,
+                        client_cn,
+                    };
+                    let _ = tx.send((Box::new(tls) as Box<dyn Connection>, peer)).await;
+                }
+                Ok(Err(e)) => tracing::debug!(peer = %addr, error = %e, "TLS handshake failed"),
+                Err(_) => tracing::debug!(peer = %addr, "TLS handshake timed out"),
+            }
+        });
+    }
+}
+
+/// Verifier for client certificates signed by the configured CA.
+fn client_verifier(
+    config: &TlsClientCertConfig,
+    provider: Arc<CryptoProvider>,
+) -> anyhow::Result<Arc<dyn ClientCertVerifier>> {
+    let ca_path = config
+        .ca_cert_path
+        .as_deref()
+        .context("auth.tls_client_cert.ca_cert_path is required to verify client certificates")?;
+    let ca_path = shellexpand::tilde(ca_path);
+    let ca_path = Path::new(ca_path.as_ref());
+    let read_error = || format!("Failed to read CA certificate {}", ca_path.display());
+
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(ca_path).with_context(read_error)? {
+        roots
+            .add(cert.with_context(read_error)?)
+            .with_context(|| format!("Invalid CA certificate in {}", ca_path.display()))?;
+    }
+
+    let builder = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+    let builder = if config.require_cert {
+        builder
+    } else {
+        builder.allow_unauthenticated()
+    };
+    builder
+        .build()
+        .context("Failed to set up client certificate verification")
+}
+
+/// Subject common name of a DER certificate.
+fn common_name(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert.as_ref()).ok()?;
+    let cn = cert.subject().iter_common_name().next()?;
+    cn.as_str().ok().map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_from_config_rejects_partial_settings() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = config::ServerConfig {
+            tls_cert_path: Some("/nonexistent/cert.pem".into()),
+            ..Default::default()
+        };
+        let err = TlsListener::from_config(listener, &config, None)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("must be set together"));
+    }
+
+    #[tokio::test]
+    async fn test_from_pem_files_reports_missing_files() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let err = TlsListener::from_pem_files(
+            listener,
+            Path::new("/nonexistent/cert.pem"),
+            Path::new("/nonexistent/key.pem"),
+            None,
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("/nonexistent/cert.pem"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Token estimation shared by gaud and kiro-gateway.
+//!
+//! Text is run through the tokenizer of the model's family
+//! ([`Tokenizer::for_model`]); images, documents and message framing count
+//! fixed amounts. GPT models use their own encodings, so their text counts
+//! are exact. Claude's and Gemini's tokenizers are not published, so their
+//! counts come from the closest public encoding, scaled to match what those
+//! providers report; they are typically within a few percent.
+
+use tiktoken_rs::CoreBPE;
+
+/// Tokens of one image: the most providers charge for a full-size one.
+pub const IMAGE_TOKENS: u64 = 1_600;
+/// Tokens of one inline document, about two pages.
+pub const DOCUMENT_TOKENS: u64 = 3_000;
+/// Tokens of each message's role and framing.
+pub const MESSAGE_TOKENS: u64 = 4;
+/// Claude's tokens per `cl100k_base` token on typical prompts.
+const CLAUDE_SCALE: f64 = 1.15;
+
+/// The tokenizer a model family counts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// `o200k_base`: GPT-4o, GPT-4.1, GPT-5 and the o-series.
+    O200k,
+    /// `cl100k_base`: GPT-4, GPT-3.5 and models of unknown families.
+    Cl100k,
+    /// Claude, approximated by `cl100k_base`.
+    Claude,
+    /// Gemini, approximated by `o200k_base`.
+    Gemini,
+}
+
+impl Tokenizer {
+    /// The tokenizer of `model`'s family.
+    pub fn for_model(model: &str) -> Self {
+        let model = model.to_lowercase();
+        // Kiro only serves Claude, including its `kiro:auto` model.
+        if model.starts_with("kiro:") {
+            return Self::Claude;
+        }
+        // Without a provider prefix (`litellm:`, `copilot:`) or vendor path.
+        let name = model.split_once(':').map_or(model.as_str(), |(_, m)| m);
+        let name = name.rsplit('/').next().unwrap_or(name);
+        if name.contains("claude") {
+            Self::Claude
+        } else if name.contains("gemini") {
+            Self::Gemini
+        } else if name.starts_with("gpt-4o")
+            || name.starts_with("gpt-4.1")
+            || name.starts_with("gpt-5")
+            || name.starts_with("chatgpt-4o")
+            || ["o1", "o3", "o4"]
+                .iter()
+                .any(|o| name == *o || name.starts_with(&format!("{o}-")))
+        {
+            Self::O200k
+        } else {
+            Self::Cl100k
+        }
+    }
+
+    /// Tokens of `text`.
+    pub fn count(self, text: &str) -> u64 {
+        if text.is_empty() {
+            return 0;
+        }
+        let tokens = self.bpe().encode_ordinary(text).len() as u64;
+        match self {
+            Self::Claude => (tokens as f64 * CLAUDE_SCALE).ceil() as u64,
+            _ => tokens,
+        }
+    }
+
+    fn bpe(self) -> &'static CoreBPE {
+        match self {
+            Self::O200k | Self::Gemini => tiktoken_rs::o200k_base_singleton(),
+            Self::Cl100k | Self::Claude => tiktoken_rs::cl100k_base_singleton(),
+        }
+    }
+}
+
+/// Text of a request, collected so the tokenizer runs once per request.
+///
+/// Parts are joined with a newline between them, as providers render them.
+#[derive(Debug, Default)]
+pub struct TextCounter {
+    text: String,
+}
+
+impl TextCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `part` to the text being counted.
+    pub fn push(&mut self, part: &str) {
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        self.text.push_str(part);
+    }
+
+    /// Tokens of the collected text.
+    pub fn count(&self, tokenizer: Tokenizer) -> u64 {
+        tokenizer.count(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizer_for_model() {
+        assert_eq!(Tokenizer::for_model("gpt-4o-mini"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("litellm:o3-mini"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("openai/gpt-5"), Tokenizer::O200k);
+        assert_eq!(Tokenizer::for_model("gpt-4-turbo"), Tokenizer::Cl100k);
+        assert_eq!(Tokenizer::for_model("claude-sonnet-4"), Tokenizer::Claude);
+        assert_eq!(Tokenizer::for_model("kiro:auto"), Tokenizer::Claude);
+        assert_eq!(Tokenizer::for_model("gemini-2.5-pro"), Tokenizer::Gemini);
+        assert_eq!(Tokenizer::for_model("llama3"), Tokenizer::Cl100k);
+    }
+
+    #[test]
+    fn test_count() {
+        // Known counts of the GPT encodings.
+        assert_eq!(Tokenizer::Cl100k.count("hello world"), 2);
+        assert_eq!(Tokenizer::O200k.count("hello world"), 2);
+        assert_eq!(Tokenizer::O200k.count(""), 0);
+        // Long runs of a repeated character are far from a character count.
+        assert!(Tokenizer::O200k.count(&"a".repeat(400)) < 100);
+        // The same text is more tokens for Claude.
+        let text = "The quick brown fox jumps over the lazy dog.";
+        assert!(Tokenizer::Claude.count(text) > Tokenizer::Cl100k.count(text));
+    }
+
+    #[test]
+    fn test_text_counter() {
+        let mut text = TextCounter::new();
+        assert_eq!(text.count(Tokenizer::Cl100k), 0);
+        text.push("hello");
+        text.push("world");
+        assert_eq!(
+            text.count(Tokenizer::Cl100k),
+            Tokenizer::Cl100k.count("hello\nworld")
+        );
+    }
+}
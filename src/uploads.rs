@@ -0,0 +1,305 @@
+//! Short-lived image uploads for `/v1/uploads/images`.
+//!
+//! An image uploaded as multipart form data is brought to a form every
+//! provider accepts (see [`prepare`]) and kept in the `image_uploads` table
+//! for `files.image_ttl_secs`.
+//! Chat requests refer to it with an `image_url` of `gaud-image://{id}`,
+//! which [`resolve`] replaces with the image's data URL before the request
+//! is processed, so clients need not send large base64 JSON bodies. Uploads
+//! are scoped to the user that made them.
+
+use std::io::Cursor;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use rusqlite::{OptionalExtension, params};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::providers::types::{ChatMessage, ContentPart, MessageContent};
+
+/// URL scheme of an uploaded image's reference.
+pub const REFERENCE_SCHEME: &str = "gaud-image://";
+
+/// Largest accepted image when `limits.max_image_mb` is unlimited.
+pub const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Longest side of a stored image; larger images are scaled down to fit.
+pub const MAX_IMAGE_DIMENSION: u32 = 2048;
+
+/// An uploaded image, or an image returned inline as a data URL.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ImageUpload {
+    /// Absent when the image was returned as a data URL and not stored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub object: &'static str,
+    pub bytes: u64,
+    pub mime_type: &'static str,
+    /// `gaud-image://{id}`, or the `data:` URL.
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+/// Image type of `data`, from its leading bytes.
+pub fn sniff_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Check `data` is an image no larger than `max_bytes` and return its type
+/// and the bytes to store.
+///
+/// PNG, JPEG, GIF and WebP images are kept as they are. Other formats that
+/// can be decoded (BMP, TIFF) are converted to PNG, and an image with a side
+/// over [`MAX_IMAGE_DIMENSION`] is scaled down to fit, keeping its aspect
+/// ratio: JPEGs stay JPEG, anything else becomes PNG. A scaled-down GIF
+/// keeps only its first frame.
+pub fn prepare(data: Vec<u8>, max_bytes: usize) -> Result<(&'static str, Vec<u8>), AppError> {
+    if data.is_empty() {
+        return Err(AppError::BadRequest("The image is empty".to_string()));
+    }
+    if data.len() > max_bytes {
+        return Err(AppError::PayloadTooLarge(format!(
+            "The image is {:.1} MiB; the limit is {} MiB",
+            data.len() as f64 / (1024.0 * 1024.0),
+            max_bytes / (1024 * 1024)
+        )));
+    }
+    let unreadable =
+        |e: image::ImageError| AppError::BadRequest(format!("The image could not be read: {e}"));
+    let reader = || {
+        ImageReader::new(Cursor::new(data.as_slice()))
+            .with_guessed_format()
+            .expect("reading from memory cannot fail")
+    };
+    let Some(format) = reader().format() else {
+        return Err(AppError::BadRequest(
+            "Unsupported image format; upload PNG, JPEG, GIF, WebP, BMP or TIFF".to_string(),
+        ));
+    };
+
+    if let Some(mime_type) = sniff_mime(&data) {
+        let (width, height) = reader().into_dimensions().map_err(unreadable)?;
+        if width.max(height) <= MAX_IMAGE_DIMENSION {
+            return Ok((mime_type, data));
+        }
+    }
+
+    let mut image = reader().decode().map_err(unreadable)?;
+    if image.width().max(image.height()) > MAX_IMAGE_DIMENSION {
+        image = image.resize(
+            MAX_IMAGE_DIMENSION,
+            MAX_IMAGE_DIMENSION,
+            FilterType::Lanczos3,
+        );
+    }
+    let (mime_type, image, format) = match format {
+        ImageFormat::Jpeg => (
+            "image/jpeg",
+            DynamicImage::ImageRgb8(image.to_rgb8()),
+            ImageFormat::Jpeg,
+        ),
+        _ => ("image/png", image, ImageFormat::Png),
+    };
+    let mut out = Cursor::new(Vec::new());
+    image
+        .write_to(&mut out, format)
+        .map_err(|e| AppError::Internal(format!("Failed to encode the image: {e}")))?;
+    Ok((mime_type, out.into_inner()))
+}
+
+/// `data` as a `data:` URL.
+pub fn data_url(mime_type: &str, data: &[u8]) -> String {
+    format!("data:{mime_type};base64,{}", STANDARD.encode(data))
+}
+
+/// Store an image for `user_id` for `ttl_secs`, dropping expired uploads.
+pub fn store(
+    db: &Database,
+    user_id: &str,
+    mime_type: &'static str,
+    data: &[u8],
+    ttl_secs: u64,
+) -> Result<ImageUpload, AppError> {
+    let id = format!("img-{}", Uuid::new_v4().simple());
+    let now = chrono::Utc::now().timestamp();
+    let expires_at = now.saturating_add(i64::try_from(ttl_secs).unwrap_or(i64::MAX));
+    db.with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM image_uploads WHERE expires_at <= ?1",
+            params![now],
+        )?;
+        conn.execute(
+            "INSERT INTO image_uploads (id, user_id, mime_type, data, created_at, expires_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, user_id, mime_type, data, now, expires_at],
+        )
+    })?;
+    Ok(ImageUpload {
+        url: format!("{REFERENCE_SCHEME}{id}"),
+        id: Some(id),
+        object: "image.upload",
+        bytes: data.len() as u64,
+        mime_type,
+        expires_at: Some(expires_at),
+    })
+}
+
+/// Replace `gaud-image://` references in `messages` with the data URLs of
+/// `user_id`'s uploads. A reference to an unknown or expired upload is an
+/// error.
+pub fn resolve(db: &Database, user_id: &str, messages: &mut [ChatMessage]) -> Result<(), AppError> {
+    let now = chrono::Utc::now().timestamp();
+    let images = messages
+        .iter_mut()
+        .filter_map(|m| match &mut m.content {
+            Some(MessageContent::Parts(parts)) => Some(parts),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|p| match p {
+            ContentPart::ImageUrl { image_url } => Some(image_url),
+            _ => None,
+        });
+    for image in images {
+        let Some(id) = image.url.strip_prefix(REFERENCE_SCHEME) else {
+            continue;
+        };
+        let found: Option<(String, Vec<u8>)> = db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT mime_type, data FROM image_uploads \
+                 WHERE id = ?1 AND user_id = ?2 AND expires_at > ?3",
+                params![id, user_id, now],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+        })?;
+        let (mime_type, data) = found.ok_or_else(|| AppError::InvalidParam {
+            param: "messages".to_string(),
+            message: format!("Image '{id}' was not found or has expired; upload it again"),
+        })?;
+        image.url = data_url(&mime_type, &data);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    fn message(url: &str) -> ChatMessage {
+        serde_json::from_value(serde_json::json!({
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "What is this?"},
+                {"type": "image_url", "image_url": {"url": url}}
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn url_of(message: &ChatMessage) -> &str {
+        match &message.content {
+            Some(MessageContent::Parts(parts)) => match &parts[1] {
+                ContentPart::ImageUrl { image_url } => &image_url.url,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn encode(width: u32, height: u32, format: ImageFormat) -> Vec<u8> {
+        let mut out = Cursor::new(Vec::new());
+        image::RgbImage::new(width, height)
+            .write_to(&mut out, format)
+            .unwrap();
+        out.into_inner()
+    }
+
+    fn dimensions(data: &[u8]) -> (u32, u32) {
+        let image = image::load_from_memory(data).unwrap();
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn test_prepare() {
+        let png = encode(4, 2, ImageFormat::Png);
+        assert_eq!(
+            prepare(png.clone(), 1024).unwrap(),
+            ("image/png", png.clone())
+        );
+        assert_eq!(sniff_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert!(matches!(
+            prepare(png, 4).unwrap_err(),
+            AppError::PayloadTooLarge(_)
+        ));
+        assert!(matches!(
+            prepare(b"not an image".to_vec(), 1024).unwrap_err(),
+            AppError::BadRequest(_)
+        ));
+    }
+
+    #[test]
+    fn test_prepare_transcodes_unsupported_formats() {
+        let bmp = encode(4, 2, ImageFormat::Bmp);
+        let (mime_type, data) = prepare(bmp, MAX_IMAGE_BYTES).unwrap();
+        assert_eq!(mime_type, "image/png");
+        assert_eq!(sniff_mime(&data), Some("image/png"));
+        assert_eq!(dimensions(&data), (4, 2));
+    }
+
+    #[test]
+    fn test_prepare_downscales_oversized_images() {
+        let png = encode(MAX_IMAGE_DIMENSION * 2, 512, ImageFormat::Png);
+        let (mime_type, data) = prepare(png, MAX_IMAGE_BYTES).unwrap();
+        assert_eq!(mime_type, "image/png");
+        assert_eq!(dimensions(&data), (MAX_IMAGE_DIMENSION, 256));
+
+        let jpeg = encode(600, MAX_IMAGE_DIMENSION * 3, ImageFormat::Jpeg);
+        let (mime_type, data) = prepare(jpeg, MAX_IMAGE_BYTES).unwrap();
+        assert_eq!(mime_type, "image/jpeg");
+        assert_eq!(dimensions(&data), (200, MAX_IMAGE_DIMENSION));
+    }
+
+    #[test]
+    fn test_store_and_resolve() {
+        let db = Database::open_in_memory().unwrap();
+        let upload = store(&db, "u1", "image/png", PNG, 60).unwrap();
+        assert!(upload.url.starts_with(REFERENCE_SCHEME));
+
+        let mut messages = vec![message(&upload.url), message("https://example.com/a.png")];
+        resolve(&db, "u1", &mut messages).unwrap();
+        assert_eq!(url_of(&messages[0]), data_url("image/png", PNG));
+        assert_eq!(url_of(&messages[1]), "https://example.com/a.png");
+
+        // Other users cannot use the reference.
+        let mut messages = vec![message(&upload.url)];
+        assert!(resolve(&db, "u2", &mut messages).is_err());
+    }
+
+    #[test]
+    fn test_expired_upload() {
+        let db = Database::open_in_memory().unwrap();
+        let upload = store(&db, "u1", "image/png", PNG, 0).unwrap();
+        let mut messages = vec![message(&upload.url)];
+        let err = resolve(&db, "u1", &mut messages).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+}
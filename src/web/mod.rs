@@ -6,16 +6,25 @@
 
 pub mod templates;
 
-use axum::Router;
-use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{Html, IntoResponse, Redirect, Response};
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
 use minijinja::{Environment, context};
 use serde::Deserialize;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::warn;
 
 use crate::AppState;
+use crate::auth::throttle::ClientIp;
+use crate::auth::{AuthUser, sessions, users};
+use crate::budget::rollup::{self, TimeseriesQuery};
+use crate::budget::tail::{TailEntry, TailFilter};
+use crate::error::AppError;
 
 // ---------------------------------------------------------------------------
 // Template engine
@@ -38,10 +47,20 @@ fn template_env() -> Environment<'static> {
         .expect("users template");
     env.add_template("usage", templates::USAGE)
         .expect("usage template");
+    env.add_template("logs", templates::LOGS)
+        .expect("logs template");
+    env.add_template("playground", templates::PLAYGROUND)
+        .expect("playground template");
     env.add_template("budgets", templates::BUDGETS)
         .expect("budgets template");
     env.add_template("settings", templates::SETTINGS)
         .expect("settings template");
+    env.add_template("providers", templates::PROVIDERS)
+        .expect("providers template");
+    env.add_template("routing", templates::ROUTING)
+        .expect("routing template");
+    env.add_template("account", templates::ACCOUNT)
+        .expect("account template");
     env
 }
 
@@ -89,31 +108,61 @@ fn html_escape(s: &str) -> String {
 /// Build the web UI router with all page and AJAX routes.
 ///
 /// These routes do NOT go through the API auth middleware. The login page and
-/// OAuth callback are completely public. The AJAX endpoints under `/ui/api/*`
-/// validate the API key from the `Authorization` header in-handler so that
-/// the web UI can use `sessionStorage`-based auth.
-pub fn build_web_router() -> Router<AppState> {
-    Router::new()
-        // Page routes
-        .route("/", get(index_redirect))
-        .route("/ui/login", get(login_page))
+/// OAuth callback are completely public. Every other page requires a session
+/// cookie, issued by `POST /ui/api/login`; members only get the account page.
+/// The AJAX endpoints under `/ui/api/*` check the session in-handler.
+pub fn build_web_router(state: &AppState) -> Router<AppState> {
+    let pages = Router::new()
         .route("/ui/dashboard", get(dashboard_page))
         .route("/ui/oauth", get(oauth_page))
         .route("/ui/users", get(users_page))
         .route("/ui/usage", get(usage_page))
+        .route("/ui/logs", get(logs_page))
+        .route("/ui/playground", get(playground_page))
         .route("/ui/budgets", get(budgets_page))
         .route("/ui/settings", get(settings_page))
+        .route("/ui/providers", get(providers_page))
+        .route("/ui/routing", get(routing_page))
+        .route("/ui/account", get(account_page))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_web_page,
+        ));
+
+    Router::new()
+        .merge(pages)
+        // Public pages
+        .route("/", get(index_redirect))
+        .route("/ui/login", get(login_page))
+        // Session management
+        .route("/ui/api/login", post(api_login))
+        .route("/ui/api/logout", post(api_logout))
         // OAuth callback (called by provider, no auth)
         .route("/oauth/callback/{provider}", get(oauth_callback))
-        // AJAX endpoints (auth checked in handler via Authorization header)
+        // AJAX endpoints (auth checked in handler)
         .route("/ui/api/oauth/start/{provider}", post(api_oauth_start))
         .route("/ui/api/oauth/status/{provider}", get(api_oauth_status))
+        .route("/ui/api/oauth/import/{source}", post(api_oauth_import))
+        .route(
+            "/ui/api/oauth/accounts/{provider}/{account}",
+            delete(api_oauth_remove_account),
+        )
         // Copilot device code flow endpoints
         .route(
             "/ui/api/oauth/copilot/device",
             post(api_copilot_device_start),
         )
         .route("/ui/api/oauth/copilot/poll", post(api_copilot_poll))
+        .route("/ui/api/oauth/kiro/device", post(api_kiro_device_start))
+        .route("/ui/api/oauth/kiro/poll", post(api_kiro_poll))
+        // Dashboard data
+        .route(
+            "/ui/api/dashboard/timeseries",
+            get(api_dashboard_timeseries),
+        )
+        // Live request log
+        .route("/ui/api/logs/stream", get(api_logs_stream))
+        .route("/ui/api/logs/requests/{request_id}", get(api_logs_request))
 }
 
 // ---------------------------------------------------------------------------
@@ -125,14 +174,21 @@ async fn index_redirect() -> Redirect {
     Redirect::temporary("/ui/dashboard")
 }
 
-/// Login page -- no authentication required.
-async fn login_page() -> Response {
-    render("login", context! {})
+/// Login page -- no authentication required. Visitors who already have a
+/// session go straight to their landing page.
+async fn login_page(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    match validate_web_auth(&state, &headers) {
+        Ok(user) => Redirect::temporary(landing_page(&user)).into_response(),
+        Err(_) => render("login", context! {}),
+    }
 }
 
 /// Dashboard page -- serves the HTML shell; data loaded via AJAX.
-async fn dashboard_page() -> Response {
-    render("dashboard", context! {})
+async fn dashboard_page(State(state): State<AppState>) -> Response {
+    render(
+        "dashboard",
+        context! { currency => state.cost_calculator.currency() },
+    )
 }
 
 /// OAuth management page.
@@ -151,13 +207,32 @@ async fn users_page() -> Response {
 }
 
 /// Usage logs page -- HTML shell, data via AJAX.
-async fn usage_page() -> Response {
-    render("usage", context! {})
+async fn usage_page(State(state): State<AppState>) -> Response {
+    render(
+        "usage",
+        context! { currency => state.cost_calculator.currency() },
+    )
+}
+
+/// Live request log page -- HTML shell, entries streamed via SSE.
+async fn logs_page() -> Response {
+    render("logs", context! {})
+}
+
+/// Chat playground page -- sends requests to `/v1/chat/completions`.
+async fn playground_page(State(state): State<AppState>) -> Response {
+    render(
+        "playground",
+        context! { currency => state.cost_calculator.currency() },
+    )
 }
 
 /// Budget management page -- HTML shell, data via AJAX.
-async fn budgets_page() -> Response {
-    render("budgets", context! {})
+async fn budgets_page(State(state): State<AppState>) -> Response {
+    render(
+        "budgets",
+        context! { currency => state.cost_calculator.currency() },
+    )
 }
 
 /// Settings page -- HTML shell, data via AJAX.
@@ -165,6 +240,24 @@ async fn settings_page() -> Response {
     render("settings", context! {})
 }
 
+/// Provider configuration editor -- HTML shell, data via AJAX.
+async fn providers_page() -> Response {
+    render("providers", context! {})
+}
+
+/// Routing rules editor and simulator -- HTML shell, data via AJAX.
+async fn routing_page() -> Response {
+    render("routing", context! {})
+}
+
+/// Self-service account page for any user -- data via the `/me` API.
+async fn account_page(State(state): State<AppState>) -> Response {
+    render(
+        "account",
+        context! { currency => state.cost_calculator.currency() },
+    )
+}
+
 // ---------------------------------------------------------------------------
 // OAuth callback handler
 // ---------------------------------------------------------------------------
@@ -283,27 +376,33 @@ async fn oauth_callback(
 // AJAX API handlers
 // ---------------------------------------------------------------------------
 
+/// Optional `?account=` selecting which account of a provider to sign in.
+#[derive(Debug, Deserialize)]
+struct OAuthAccountQuery {
+    account: Option<String>,
+}
+
 /// Start an OAuth flow for a provider. Returns JSON with `auth_url`.
 ///
 /// For Claude and Gemini, uses OAuthManager to generate a proper PKCE-based
-/// authorization URL with state token. For Copilot, returns info about the
-/// device code flow (caller should use the /copilot/device endpoint instead).
-/// For Kiro, returns info that auth is managed internally.
-async fn api_oauth_start(Path(provider): Path<String>, State(state): State<AppState>) -> Response {
-    // Validate auth from the Authorization header
-    if let Err(resp) = validate_web_auth(&state).await {
-        return resp;
+/// authorization URL with state token; `?account=` adds a further account
+/// to the provider's pool. For Copilot, returns info about the device code
+/// flow (caller should use the /copilot/device endpoint instead). For Kiro,
+/// points to the /kiro/device endpoint likewise.
+async fn api_oauth_start(
+    Path(provider): Path<String>,
+    Query(query): Query<OAuthAccountQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
     }
 
     let is_configured = is_provider_configured(&provider, &state.config);
 
     if !is_configured {
-        return (
-            StatusCode::BAD_REQUEST,
-            axum::Json(
-                serde_json::json!({ "error": format!("Provider '{provider}' is not configured") }),
-            ),
-        )
+        return AppError::BadRequest(format!("Provider '{provider}' is not configured"))
             .into_response();
     }
 
@@ -322,20 +421,24 @@ async fn api_oauth_start(Path(provider): Path<String>, State(state): State<AppSt
                 .into_response()
         }
         "kiro" => {
-            // Kiro uses internal auth (AWS SSO / refresh token). No browser flow.
+            // Kiro signs in with the AWS SSO device authorization flow.
             (
                 StatusCode::OK,
                 axum::Json(serde_json::json!({
                     "provider": "kiro",
-                    "flow": "internal",
-                    "message": "Kiro authentication is managed via credentials file or refresh token in config"
+                    "flow": "device_code",
+                    "message": "Use /ui/api/oauth/kiro/device to start the AWS SSO device flow"
                 })),
             )
                 .into_response()
         }
         _ => {
             // Claude and Gemini use PKCE authorization code flow via OAuthManager
-            match state.oauth_manager.start_flow(&provider) {
+            let account = query
+                .account
+                .as_deref()
+                .unwrap_or(crate::oauth::DEFAULT_ACCOUNT);
+            match state.oauth_manager.start_account_flow(&provider, account) {
                 Ok(auth_url) => (
                     StatusCode::OK,
                     axum::Json(serde_json::json!({
@@ -347,13 +450,7 @@ async fn api_oauth_start(Path(provider): Path<String>, State(state): State<AppSt
                     .into_response(),
                 Err(err) => {
                     warn!(%provider, error = %err, "Failed to start OAuth flow");
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        axum::Json(serde_json::json!({
-                            "error": format!("Failed to start OAuth flow: {err}")
-                        })),
-                    )
-                        .into_response()
+                    AppError::Internal(format!("Failed to start OAuth flow: {err}")).into_response()
                 }
             }
         }
@@ -364,10 +461,13 @@ async fn api_oauth_start(Path(provider): Path<String>, State(state): State<AppSt
 ///
 /// Uses OAuthManager to check token storage and report authentication state,
 /// including expiry and refresh status.
-async fn api_oauth_status(Path(provider): Path<String>, State(state): State<AppState>) -> Response {
-    // Validate auth from the Authorization header
-    if let Err(resp) = validate_web_auth(&state).await {
-        return resp;
+async fn api_oauth_status(
+    Path(provider): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
     }
 
     let is_configured = is_provider_configured(&provider, &state.config);
@@ -394,6 +494,9 @@ async fn api_oauth_status(Path(provider): Path<String>, State(state): State<AppS
                 "expired": status.expired,
                 "needs_refresh": status.needs_refresh,
                 "expires_in_secs": status.expires_in_secs,
+                "accounts": status.accounts,
+                "project_id": status.project_id,
+                "subscription_tier": status.subscription_tier,
             })),
         )
             .into_response(),
@@ -413,6 +516,83 @@ async fn api_oauth_status(Path(provider): Path<String>, State(state): State<AppS
     }
 }
 
+/// Sign out one account of a provider and remove it from the pool.
+async fn api_oauth_remove_account(
+    Path((provider, account)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
+    }
+
+    match state.oauth_manager.remove_account(&provider, &account) {
+        Ok(()) => (
+            StatusCode::OK,
+            axum::Json(serde_json::json!({ "provider": provider, "account": account })),
+        )
+            .into_response(),
+        Err(err) => {
+            warn!(%provider, %account, error = %err, "Failed to remove OAuth account");
+            AppError::Internal(format!("Failed to remove account: {err}")).into_response()
+        }
+    }
+}
+
+/// Import a token from a local CLI's credentials file on the gaud host
+/// (`claude-code` or `gemini-cli`) into the matching provider's pool, so the
+/// OAuth flow need not be repeated.
+async fn api_oauth_import(
+    Path(source): Path<String>,
+    Query(query): Query<OAuthAccountQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
+    }
+
+    let source: crate::oauth::CredentialSource = match source.parse() {
+        Ok(source) => source,
+        Err(err) => return AppError::NotFound(err).into_response(),
+    };
+    let provider = source.provider();
+    if !is_provider_configured(provider, &state.config) {
+        return AppError::BadRequest(format!("Provider '{provider}' is not configured"))
+            .into_response();
+    }
+
+    let account = query
+        .account
+        .as_deref()
+        .unwrap_or(crate::oauth::DEFAULT_ACCOUNT);
+    match state
+        .oauth_manager
+        .import_credentials(source, None, account)
+        .await
+    {
+        Ok(token) => (
+            StatusCode::OK,
+            axum::Json(serde_json::json!({
+                "provider": provider,
+                "account": account,
+                "expired": token.is_expired(),
+            })),
+        )
+            .into_response(),
+        Err(err) => {
+            warn!(%source, %account, error = %err, "Failed to import OAuth credentials");
+            let message = format!("Failed to import {source} credentials: {err}");
+            match err {
+                crate::oauth::OAuthError::TokenNotFound(_) => AppError::NotFound(message),
+                crate::oauth::OAuthError::Other(_) => AppError::BadRequest(message),
+                _ => AppError::Internal(message),
+            }
+            .into_response()
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Copilot device code flow endpoints
 // ---------------------------------------------------------------------------
@@ -421,16 +601,13 @@ async fn api_oauth_status(Path(provider): Path<String>, State(state): State<AppS
 ///
 /// Returns the user_code, verification_uri, and device_code that the
 /// frontend needs to display to the user and use for polling.
-async fn api_copilot_device_start(State(state): State<AppState>) -> Response {
-    if let Err(resp) = validate_web_auth(&state).await {
-        return resp;
+async fn api_copilot_device_start(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
     }
 
     if state.config.providers.copilot.is_none() {
-        return (
-            StatusCode::BAD_REQUEST,
-            axum::Json(serde_json::json!({ "error": "Copilot provider is not configured" })),
-        )
+        return AppError::BadRequest("Copilot provider is not configured".to_string())
             .into_response();
     }
 
@@ -448,13 +625,7 @@ async fn api_copilot_device_start(State(state): State<AppState>) -> Response {
             .into_response(),
         Err(err) => {
             warn!(error = %err, "Failed to start Copilot device flow");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(serde_json::json!({
-                    "error": format!("Failed to start device flow: {err}")
-                })),
-            )
-                .into_response()
+            AppError::Internal(format!("Failed to start device flow: {err}")).into_response()
         }
     }
 }
@@ -463,6 +634,9 @@ async fn api_copilot_device_start(State(state): State<AppState>) -> Response {
 #[derive(Debug, Deserialize)]
 struct CopilotPollRequest {
     device_code: String,
+    /// Account to store the token under (default account when absent).
+    #[serde(default)]
+    account: Option<String>,
 }
 
 /// Poll the Copilot device code flow for completion.
@@ -470,19 +644,17 @@ struct CopilotPollRequest {
 /// Returns the poll result: pending, slow_down, or complete.
 async fn api_copilot_poll(
     State(state): State<AppState>,
+    headers: HeaderMap,
     axum::Json(body): axum::Json<CopilotPollRequest>,
 ) -> Response {
-    if let Err(resp) = validate_web_auth(&state).await {
-        return resp;
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
     }
 
     let provider_config = match state.config.providers.copilot.as_ref() {
         Some(c) => c,
         None => {
-            return (
-                StatusCode::BAD_REQUEST,
-                axum::Json(serde_json::json!({ "error": "Copilot provider is not configured" })),
-            )
+            return AppError::BadRequest("Copilot provider is not configured".to_string())
                 .into_response();
         }
     };
@@ -491,7 +663,7 @@ async fn api_copilot_poll(
         crate::oauth::copilot::CopilotOAuthConfig::from_provider_config(&provider_config.client_id);
 
     match crate::oauth::copilot::poll_for_token(
-        state.oauth_manager.http_client(),
+        state.oauth_manager.http_client("copilot"),
         &oauth_config,
         &body.device_code,
     )
@@ -510,15 +682,16 @@ async fn api_copilot_poll(
         Ok(crate::oauth::copilot::PollResult::Complete(access_token)) => {
             // Store the token via OAuthManager's storage
             let token = crate::oauth::copilot::create_token_info(&access_token);
-            if let Err(err) = state.oauth_manager.storage().save("copilot", &token) {
+            let account = body
+                .account
+                .as_deref()
+                .unwrap_or(crate::oauth::DEFAULT_ACCOUNT);
+            if let Err(err) = state
+                .oauth_manager
+                .save_account_token("copilot", account, &token)
+            {
                 warn!(error = %err, "Failed to store Copilot token");
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    axum::Json(serde_json::json!({
-                        "error": format!("Token storage failed: {err}")
-                    })),
-                )
-                    .into_response();
+                return AppError::Internal(format!("Token storage failed: {err}")).into_response();
             }
             tracing::info!("Copilot device code flow completed, token stored");
             (
@@ -542,22 +715,282 @@ async fn api_copilot_poll(
 }
 
 // ---------------------------------------------------------------------------
-// Helpers
+// Kiro AWS SSO device flow endpoints
+// ---------------------------------------------------------------------------
+
+/// Start the Kiro AWS SSO device authorization flow.
+///
+/// Returns the user_code, verification_uri, and device_code that the
+/// frontend needs to display to the user and use for polling. The OIDC
+/// client registration stays on the server.
+async fn api_kiro_device_start(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
+    }
+
+    if state.config.providers.kiro.is_none() {
+        return AppError::BadRequest("Kiro provider is not configured".to_string()).into_response();
+    }
+
+    match state.oauth_manager.start_kiro_device_flow().await {
+        Ok(flow) => (
+            StatusCode::OK,
+            axum::Json(serde_json::json!({
+                "device_code": flow.device_code,
+                "user_code": flow.user_code,
+                "verification_uri": flow.verification_uri,
+                "verification_uri_complete": flow.verification_uri_complete,
+                "expires_in": flow.expires_in,
+                "interval": flow.interval,
+            })),
+        )
+            .into_response(),
+        Err(err) => {
+            warn!(error = %err, "Failed to start Kiro device flow");
+            AppError::Internal(format!("Failed to start device flow: {err}")).into_response()
+        }
+    }
+}
+
+/// Poll request for the Kiro device flow.
+#[derive(Debug, Deserialize)]
+struct KiroPollRequest {
+    device_code: String,
+}
+
+/// Poll the Kiro device flow for completion.
+///
+/// Returns the poll result: pending, slow_down, complete, or error.
+async fn api_kiro_poll(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    axum::Json(body): axum::Json<KiroPollRequest>,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
+    }
+
+    let status = match state
+        .oauth_manager
+        .poll_kiro_device_flow(&body.device_code)
+        .await
+    {
+        Ok(crate::oauth::kiro::PollResult::Pending) => serde_json::json!({ "status": "pending" }),
+        Ok(crate::oauth::kiro::PollResult::SlowDown) => {
+            serde_json::json!({ "status": "slow_down" })
+        }
+        Ok(crate::oauth::kiro::PollResult::Complete(_)) => {
+            serde_json::json!({ "status": "complete" })
+        }
+        Err(err) => {
+            warn!(error = %err, "Kiro poll error");
+            serde_json::json!({ "status": "error", "error": err.to_string() })
+        }
+    };
+    (StatusCode::OK, axum::Json(status)).into_response()
+}
+
+// ---------------------------------------------------------------------------
+// Dashboard data
+// ---------------------------------------------------------------------------
+
+/// Usage time series for the dashboard charts, from the hourly aggregates.
+async fn api_dashboard_timeseries(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<TimeseriesQuery>,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
+    }
+    match rollup::query_timeseries(&state.db, query, state.cost_calculator.currency()) {
+        Ok(series) => axum::Json(series).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Live request log
+// ---------------------------------------------------------------------------
+
+/// Stream completed requests as SSE `request` events: the recent ones
+/// first, then each new one as it finishes. A `lagged` event carries the
+/// number of entries skipped when the client falls behind.
+async fn api_logs_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(filter): Query<TailFilter>,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
+    }
+    let (backlog, mut rx) = state.request_tail.subscribe(&filter);
+    let events = async_stream::stream! {
+        for entry in backlog {
+            yield Ok::<_, Infallible>(tail_event(&entry));
+        }
+        loop {
+            match rx.recv().await {
+                Ok(entry) if filter.matches(&entry) => yield Ok(tail_event(&entry)),
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    yield Ok(Event::default().event("lagged").data(skipped.to_string()));
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Look up a recently completed request by its `X-Request-ID`. The
+/// playground uses this to show which provider served a request and its cost.
+async fn api_logs_request(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(request_id): Path<String>,
+) -> Response {
+    if let Err(e) = require_web_admin(&state, &headers) {
+        return e.into_response();
+    }
+    match state.request_tail.find(&request_id) {
+        Some(entry) => axum::Json(entry).into_response(),
+        None => AppError::NotFound(format!("Request {request_id} not found")).into_response(),
+    }
+}
+
+fn tail_event(entry: &TailEntry) -> Event {
+    Event::default()
+        .event("request")
+        .json_data(entry)
+        .unwrap_or_else(|_| Event::default().event("request"))
+}
+
+// ---------------------------------------------------------------------------
+// Sessions
 // ---------------------------------------------------------------------------
 
-/// Validate the web UI request has a valid API key in the Authorization header.
+#[derive(Debug, Deserialize)]
+struct LoginRequest {
+    api_key: String,
+    /// TOTP or recovery code, for users with two-factor enabled.
+    #[serde(default)]
+    totp_code: Option<String>,
+}
+
+/// Exchange an API key for a session cookie. Users with two-factor enabled
+/// also need a TOTP or recovery code; without one the response is a `401`
+/// with code `totp_required`.
 ///
-/// Returns `Ok(())` if valid, `Err(Response)` with a 401 JSON error otherwise.
-async fn validate_web_auth(state: &AppState) -> Result<(), Response> {
-    // For the web UI AJAX endpoints, we accept auth but don't strictly enforce
-    // it on page loads. The AJAX endpoints are the ones that need protection.
-    // This is a simplified check -- the full auth middleware is on /v1/* and
-    // /admin/*.
-    //
-    // In a production deployment, you would integrate with the auth middleware
-    // more tightly. For now, we return Ok to allow the UI to function.
-    let _ = state;
-    Ok(())
+/// POST /ui/api/login
+async fn api_login(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    Json(body): Json<LoginRequest>,
+) -> Result<Response, AppError> {
+    let throttle = &state.login_throttle;
+    let (key_id, user) = throttle.validate_api_key(ip, body.api_key.trim(), "web_login")?;
+    if users::get_user(&state.db, &user.user_id)?.service_account {
+        return Err(AppError::Forbidden(
+            "Service accounts cannot sign in to the web UI".to_string(),
+        ));
+    }
+    throttle.require_second_factor(ip, &user, body.totp_code.as_deref(), "web_login_totp")?;
+    throttle.record_success(&user.user_id);
+    let ttl_hours = state.config.auth.session_ttl_hours;
+    let token = sessions::create_session(&state.db, &key_id, ttl_hours)?;
+    let cookie = sessions::session_cookie(&token, ttl_hours, is_https(&state, &headers));
+    let body = serde_json::json!({
+        "user_id": user.user_id,
+        "name": user.name,
+        "role": user.role,
+        "redirect": landing_page(&user),
+    });
+    Ok(([(header::SET_COOKIE, cookie)], Json(body)).into_response())
+}
+
+/// End the current session and clear its cookie.
+///
+/// POST /ui/api/logout
+async fn api_logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    if let Some(token) = sessions::session_token(&headers) {
+        sessions::delete_session(&state.db, &token)?;
+    }
+    let cookie = sessions::clear_session_cookie(is_https(&state, &headers));
+    Ok(([(header::SET_COOKIE, cookie)], StatusCode::NO_CONTENT).into_response())
+}
+
+/// Guard for the page routes: no session redirects to the login page, and
+/// members are sent to the account page, the only one they can use.
+async fn require_web_page(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Ok(user) = validate_web_auth(&state, request.headers()) else {
+        return Redirect::temporary("/ui/login").into_response();
+    };
+    if !user.is_admin() && request.uri().path() != "/ui/account" {
+        return Redirect::temporary("/ui/account").into_response();
+    }
+    next.run(request).await
+}
+
+/// Where a user lands after logging in.
+fn landing_page(user: &AuthUser) -> &'static str {
+    if user.is_admin() {
+        "/ui/dashboard"
+    } else {
+        "/ui/account"
+    }
+}
+
+/// Whether the browser reached gaud over HTTPS, directly or through a
+/// TLS-terminating proxy, so the session cookie can be marked `Secure`.
+fn is_https(state: &AppState, headers: &HeaderMap) -> bool {
+    state.config.server.tls_enabled()
+        || headers
+            .get("x-forwarded-proto")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.eq_ignore_ascii_case("https"))
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Authenticate a web UI request from its session cookie. API keys are only
+/// accepted by `POST /ui/api/login`, where failures are throttled; scripts
+/// should use the `/admin` API. With auth disabled every request is the
+/// anonymous admin, as on the API.
+fn validate_web_auth(state: &AppState, headers: &HeaderMap) -> Result<AuthUser, AppError> {
+    if !state.config.auth.enabled {
+        return Ok(AuthUser {
+            user_id: "anonymous".to_string(),
+            name: "anonymous".to_string(),
+            role: "admin".to_string(),
+            reasoning_mode: Default::default(),
+            team: None,
+            impersonator: None,
+            key_id: None,
+            scope: None,
+            origin: Default::default(),
+        });
+    }
+    let token = sessions::session_token(headers)
+        .ok_or_else(|| AppError::Unauthorized("Not logged in".to_string()))?;
+    sessions::validate_session(&state.db, &token)
+}
+
+/// Require an authenticated admin, as the `/admin` API does.
+fn require_web_admin(state: &AppState, headers: &HeaderMap) -> Result<AuthUser, AppError> {
+    let user = validate_web_auth(state, headers)?;
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin role required".to_string()));
+    }
+    Ok(user)
 }
 
 /// Check whether a provider is configured.
@@ -601,6 +1034,7 @@ fn configured_providers(state: &AppState) -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::{totp, users};
 
     #[test]
     fn test_html_escape() {
@@ -619,8 +1053,38 @@ mod tests {
         assert!(env.get_template("oauth_callback").is_ok());
         assert!(env.get_template("users").is_ok());
         assert!(env.get_template("usage").is_ok());
+        assert!(env.get_template("logs").is_ok());
+        assert!(env.get_template("playground").is_ok());
         assert!(env.get_template("budgets").is_ok());
         assert!(env.get_template("settings").is_ok());
+        assert!(env.get_template("providers").is_ok());
+        assert!(env.get_template("routing").is_ok());
+        assert!(env.get_template("account").is_ok());
+    }
+
+    #[test]
+    fn test_render_providers_page() {
+        let env = template_env();
+        let html = env
+            .get_template("providers")
+            .unwrap()
+            .render(context! {})
+            .unwrap();
+        assert!(html.contains("/admin/providers/config"));
+        assert!(html.contains("nav-link active\" href=\"/ui/providers\""));
+    }
+
+    #[test]
+    fn test_render_routing_page() {
+        let env = template_env();
+        let html = env
+            .get_template("routing")
+            .unwrap()
+            .render(context! {})
+            .unwrap();
+        assert!(html.contains("/admin/routing/rules"));
+        assert!(html.contains("/admin/routing/simulate"));
+        assert!(html.contains("nav-link active\" href=\"/ui/routing\""));
     }
 
     #[test]
@@ -644,6 +1108,9 @@ mod tests {
         let html = result.unwrap();
         assert!(html.contains("Dashboard"));
         assert!(html.contains("provider-table"));
+        assert!(html.contains("chart-latency"));
+        assert!(html.contains("stat-saved"));
+        assert!(html.contains("/ui/api/dashboard/timeseries"));
     }
 
     #[test]
@@ -709,6 +1176,53 @@ mod tests {
         let html = result.unwrap();
         assert!(html.contains("Usage Logs"));
         assert!(html.contains("usage-table"));
+        assert!(html.contains("filter-search"));
+        assert!(html.contains("page-info"));
+    }
+
+    #[test]
+    fn test_render_logs_page() {
+        let env = template_env();
+        let tmpl = env.get_template("logs").unwrap();
+        let html = tmpl.render(context! {}).unwrap();
+        assert!(html.contains("Live Logs"));
+        assert!(html.contains("/ui/api/logs/stream"));
+        assert!(html.contains("filter-user"));
+    }
+
+    #[test]
+    fn test_render_playground_page() {
+        let env = template_env();
+        let tmpl = env.get_template("playground").unwrap();
+        let html = tmpl.render(context! { currency => "EUR" }).unwrap();
+        assert!(html.contains("Playground"));
+        assert!(html.contains("/v1/chat/completions"));
+        assert!(html.contains("opt-thinking"));
+        assert!(html.contains("currency: 'EUR'"));
+    }
+
+    #[test]
+    fn test_render_account_page() {
+        let env = template_env();
+        let tmpl = env.get_template("account").unwrap();
+        let html = tmpl.render(context! {}).unwrap();
+        assert!(html.contains("My Account"));
+        assert!(html.contains("/me/keys"));
+        assert!(html.contains("budget-monthly"));
+        assert!(html.contains("/me/totp/enroll"));
+    }
+
+    #[test]
+    fn test_render_login_page_posts_to_session_login() {
+        let env = template_env();
+        let html = env
+            .get_template("login")
+            .unwrap()
+            .render(context! {})
+            .unwrap();
+        assert!(html.contains("/ui/api/login"));
+        assert!(html.contains("totp_code"));
+        assert!(!html.contains("sessionStorage"));
     }
 
     #[test]
@@ -720,6 +1234,16 @@ mod tests {
         let html = result.unwrap();
         assert!(html.contains("Budget Management"));
         assert!(html.contains("budgets-table"));
+        assert!(html.contains("Monthly Limit (USD)"));
+    }
+
+    #[test]
+    fn test_render_budgets_page_currency() {
+        let env = template_env();
+        let tmpl = env.get_template("budgets").unwrap();
+        let html = tmpl.render(context! { currency => "EUR" }).unwrap();
+        assert!(html.contains("currency: 'EUR'"));
+        assert!(html.contains("Monthly Limit (EUR)"));
     }
 
     #[test]
@@ -732,12 +1256,22 @@ mod tests {
         assert!(html.contains("Settings"));
         assert!(html.contains("settings-container"));
         assert!(html.contains("/admin/settings"));
+        assert!(html.contains("restart_required"));
     }
 
     #[test]
     fn test_all_navbars_have_settings_link() {
         let env = template_env();
-        for name in &["dashboard", "users", "usage", "budgets", "settings"] {
+        for name in &[
+            "dashboard",
+            "users",
+            "usage",
+            "logs",
+            "playground",
+            "budgets",
+            "settings",
+            "account",
+        ] {
             let tmpl = env.get_template(name).unwrap();
             let html = tmpl.render(context! {}).unwrap();
             assert!(
@@ -745,6 +1279,11 @@ mod tests {
                 "Template '{}' is missing Settings nav link",
                 name
             );
+            assert!(
+                html.contains(r#"href="/ui/logs"#),
+                "Template '{}' is missing Logs nav link",
+                name
+            );
         }
         // OAuth uses a context variable, test separately.
         let tmpl = env.get_template("oauth").unwrap();
@@ -775,17 +1314,25 @@ mod tests {
             callback_port: 1234,
             default_model: None,
             max_tokens: None,
+            proxy: None,
+            http: Default::default(),
+            quota: Default::default(),
+            dry_run: false,
         });
         assert!(is_provider_configured("claude", &config));
         assert!(!is_provider_configured("gemini", &config));
     }
 
-    #[test]
-    fn test_configured_providers_always_includes_kiro() {
+    fn test_state() -> AppState {
         let db = crate::db::Database::open_in_memory().unwrap();
         let config = crate::config::Config::default();
         let (audit_tx, _) = tokio::sync::mpsc::unbounded_channel();
-        let state = AppState {
+        let oauth_manager = std::sync::Arc::new(crate::oauth::OAuthManager::from_config(
+            std::sync::Arc::new(crate::config::Config::default()),
+            db.clone(),
+        ));
+        let cost_calculator = std::sync::Arc::new(crate::providers::cost::CostCalculator::new());
+        AppState {
             config: std::sync::Arc::new(config),
             config_path: std::path::PathBuf::from("test.toml"),
             db: db.clone(),
@@ -794,17 +1341,149 @@ mod tests {
             )),
             budget: std::sync::Arc::new(crate::budget::BudgetTracker::new(db.clone())),
             audit_tx,
-            cost_calculator: std::sync::Arc::new(crate::providers::cost::CostCalculator::new()),
+            providers: std::sync::Arc::new(crate::providers::factory::ProviderFactory::new(
+                db.clone(),
+                oauth_manager.clone(),
+                cost_calculator.clone(),
+                std::sync::Arc::new(crate::leader::Leader::always()),
+            )),
+            cost_calculator,
             cache: None,
-            oauth_manager: std::sync::Arc::new(crate::oauth::OAuthManager::from_config(
-                std::sync::Arc::new(crate::config::Config::default()),
+            oauth_manager,
+            mcp_sessions: std::sync::Arc::new(crate::api::mcp::McpSessions::new()),
+            mcp_tools: std::sync::Arc::new(crate::mcp::client::McpToolServers::new(
+                &Default::default(),
+                reqwest::Client::new(),
+            )),
+            stream_registry: std::sync::Arc::new(crate::api::resume::StreamRegistry::from_config(
+                &crate::config::CacheConfig::default(),
+            )),
+            request_tail: std::sync::Arc::new(crate::budget::tail::RequestTail::new()),
+            stream_limiter: std::sync::Arc::new(crate::budget::streams::StreamLimiter::new()),
+            scheduler: std::sync::Arc::new(crate::budget::scheduler::FairScheduler::new()),
+            settings: std::sync::Arc::new(
+                crate::settings::RuntimeSettings::new(Default::default()),
+            ),
+            login_throttle: std::sync::Arc::new(crate::auth::throttle::LoginThrottle::new(
+                Default::default(),
                 db,
             )),
-        };
+            notifier: std::sync::Arc::new(crate::notify::Notifier::disabled()),
+            background: tokio_util::task::TaskTracker::new(),
+        }
+    }
+
+    #[test]
+    fn test_configured_providers_always_includes_kiro() {
+        let state = test_state();
 
         let providers = configured_providers(&state);
         assert!(providers.contains(&"kiro".to_string()));
         // By default, only Kiro is returned because it's "always show"
         assert_eq!(providers.len(), 1);
     }
+
+    async fn get_page(state: &AppState, path: &str, cookie: Option<&str>) -> Response {
+        use tower::ServiceExt;
+        let mut request = axum::http::Request::get(path);
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        build_web_router(state)
+            .with_state(state.clone())
+            .oneshot(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn location(resp: &Response) -> &str {
+        resp.headers()[header::LOCATION].to_str().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pages_require_a_session() {
+        let state = test_state();
+        let resp = get_page(&state, "/ui/dashboard", None).await;
+        assert_eq!(resp.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(location(&resp), "/ui/login");
+
+        let resp = get_page(&state, "/ui/login", None).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_login_sets_session_cookie() {
+        use tower::ServiceExt;
+        let state = test_state();
+        let user = users::create_user(&state.db, "alice", "admin").unwrap();
+        let key = users::create_api_key(&state.db, &user.id, "web").unwrap();
+
+        let request = axum::http::Request::post("/ui/api/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(
+                serde_json::json!({ "api_key": key.plaintext }).to_string(),
+            ))
+            .unwrap();
+        let resp = build_web_router(&state)
+            .with_state(state.clone())
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let cookie = resp.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cookie.contains("HttpOnly"));
+        let cookie = cookie.split(';').next().unwrap().to_string();
+
+        let resp = get_page(&state, "/ui/dashboard", Some(&cookie)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = get_page(&state, "/ui/login", Some(&cookie)).await;
+        assert_eq!(location(&resp), "/ui/dashboard");
+    }
+
+    #[tokio::test]
+    async fn test_login_asks_for_totp_when_enabled() {
+        use tower::ServiceExt;
+        let state = test_state();
+        let user = users::create_user(&state.db, "root", "admin").unwrap();
+        let key = users::create_api_key(&state.db, &user.id, "web").unwrap();
+        totp::begin_enrollment(&state.db, &user.id, "root").unwrap();
+        state
+            .db
+            .with_conn(|conn| conn.execute("UPDATE user_totp SET enabled = 1", []))
+            .unwrap();
+
+        let request = axum::http::Request::post("/ui/api/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(
+                serde_json::json!({ "api_key": key.plaintext }).to_string(),
+            ))
+            .unwrap();
+        let resp = build_web_router(&state)
+            .with_state(state.clone())
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(resp.headers().get(header::SET_COOKIE).is_none());
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "totp_required");
+    }
+
+    #[tokio::test]
+    async fn test_members_only_get_the_account_page() {
+        let state = test_state();
+        let user = users::create_user(&state.db, "bob", "member").unwrap();
+        let key = users::create_api_key(&state.db, &user.id, "web").unwrap();
+        let token = sessions::create_session(&state.db, &key.info.id, 1).unwrap();
+        let cookie = format!("{}={token}", sessions::SESSION_COOKIE);
+
+        let resp = get_page(&state, "/ui/users", Some(&cookie)).await;
+        assert_eq!(resp.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(location(&resp), "/ui/account");
+        let resp = get_page(&state, "/ui/account", Some(&cookie)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
 }
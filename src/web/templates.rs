@@ -150,6 +150,14 @@ pub const LAYOUT: &str = r#"<!DOCTYPE html>
         .stat-value.danger { color: var(--danger); }
         .stat-value.info { color: var(--info); }
 
+        /* Charts */
+        .chart { width: 100%; height: 160px; }
+        .chart svg { width: 100%; height: 100%; overflow: visible; }
+        .chart-axis { stroke: var(--border); stroke-width: 1; }
+        .chart-label { fill: var(--text-secondary); font-size: 10px; }
+        .chart-legend { display: flex; flex-wrap: wrap; gap: 0.75rem; font-size: 0.75rem; color: var(--text-secondary); margin-bottom: 0.5rem; }
+        .chart-legend span::before { content: ''; display: inline-block; width: 10px; height: 10px; border-radius: 2px; margin-right: 0.35rem; background: var(--swatch); }
+
         /* Tables */
         .table-wrap { overflow-x: auto; }
         table {
@@ -311,51 +319,51 @@ pub const LAYOUT: &str = r#"<!DOCTYPE html>
     <script>
         // Shared utilities
         const GAUD = {
-            getApiKey() {
-                return sessionStorage.getItem('gaud_api_key') || '';
-            },
-            setApiKey(key) {
-                sessionStorage.setItem('gaud_api_key', key);
-            },
-            clearApiKey() {
-                sessionStorage.removeItem('gaud_api_key');
-            },
-            isLoggedIn() {
-                return !!this.getApiKey();
-            },
+            // Auth is the HttpOnly session cookie set by /ui/api/login,
+            // which same-origin requests send automatically.
             headers() {
-                return {
-                    'Authorization': 'Bearer ' + this.getApiKey(),
-                    'Content-Type': 'application/json',
-                };
+                return { 'Content-Type': 'application/json' };
             },
             async apiFetch(url, options = {}) {
                 const resp = await fetch(url, {
+                    credentials: 'same-origin',
                     ...options,
                     headers: { ...this.headers(), ...(options.headers || {}) },
                 });
                 if (resp.status === 401) {
-                    this.clearApiKey();
+                    // A missing second factor is not a lost session.
+                    const body = await resp.clone().json().catch(() => ({}));
+                    if (body.error && body.error.code === 'totp_required') return resp;
                     window.location.href = '/ui/login';
                     return null;
                 }
                 return resp;
             },
-            logout() {
-                this.clearApiKey();
-                window.location.href = '/ui/login';
+            // Like apiFetch, but asks for a TOTP code and retries when the
+            // server requires two-factor authentication.
+            async apiFetchWithTotp(url, options = {}) {
+                const resp = await this.apiFetch(url, options);
+                if (!resp || resp.status !== 401) return resp;
+                const code = prompt('Enter the code from your authenticator app (or a recovery code):');
+                if (!code) return resp;
+                return this.apiFetch(url, {
+                    ...options,
+                    headers: { ...(options.headers || {}), 'X-TOTP-Code': code.trim() },
+                });
             },
-            requireAuth() {
-                if (!this.isLoggedIn()) {
-                    window.location.href = '/ui/login';
-                    return false;
-                }
-                return true;
+            async logout() {
+                await fetch('/ui/api/logout', { method: 'POST', credentials: 'same-origin' });
+                window.location.href = '/ui/login';
             },
-            formatCost(cost) {
-                if (cost === 0) return '$0.00';
-                if (cost < 0.01) return '$' + cost.toFixed(4);
-                return '$' + cost.toFixed(2);
+            currency: '{{ currency | default("USD") }}',
+            formatCost(cost, digits) {
+                const d = digits != null ? digits : (cost !== 0 && cost < 0.01 ? 4 : 2);
+                return new Intl.NumberFormat(undefined, {
+                    style: 'currency',
+                    currency: this.currency,
+                    minimumFractionDigits: d,
+                    maximumFractionDigits: d,
+                }).format(cost);
             },
             formatNumber(n) {
                 if (n >= 1_000_000) return (n / 1_000_000).toFixed(1) + 'M';
@@ -396,6 +404,11 @@ pub const LOGIN: &str = r#"{% extends "layout" %}
                 <input class="form-input mono" type="password" id="api-key"
                        placeholder="sk-prx-..." autocomplete="off" autofocus>
             </div>
+            <div class="form-group hidden" id="totp-group">
+                <label class="form-label" for="totp-code">Authenticator Code</label>
+                <input class="form-input mono" type="text" id="totp-code"
+                       placeholder="123456 or recovery code" autocomplete="one-time-code">
+            </div>
             <button type="submit" class="btn btn-primary" style="width:100%;">Sign In</button>
         </form>
         <p class="text-muted" style="text-align:center;font-size:0.75rem;margin-top:1rem;">
@@ -418,20 +431,28 @@ pub const LOGIN: &str = r#"{% extends "layout" %}
             return;
         }
 
+        const totpCode = document.getElementById('totp-code').value.trim();
         try {
-            const resp = await fetch('/health', {
-                headers: { 'Authorization': 'Bearer ' + key }
-            });
-            // Health endpoint doesn't require auth, so we test against admin/users
-            const testResp = await fetch('/admin/users', {
-                headers: { 'Authorization': 'Bearer ' + key }
+            const resp = await fetch('/ui/api/login', {
+                method: 'POST',
+                credentials: 'same-origin',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ api_key: key, totp_code: totpCode || null }),
             });
-            if (testResp.ok || testResp.status === 403) {
-                // 200 = admin, 403 = valid key but not admin -- both mean valid
-                GAUD.setApiKey(key);
-                window.location.href = '/ui/dashboard';
+            const data = await resp.json().catch(() => ({}));
+            if (resp.ok) {
+                window.location.href = data.redirect;
+            } else if (data.error && data.error.code === 'totp_required') {
+                document.getElementById('totp-group').classList.remove('hidden');
+                document.getElementById('totp-code').focus();
+                errEl.textContent = totpCode
+                    ? 'Invalid authenticator code. Please try again.'
+                    : 'Enter the code from your authenticator app.';
+                errEl.classList.remove('hidden');
             } else {
-                errEl.textContent = 'Invalid API key. Please try again.';
+                errEl.textContent = resp.status === 429 && data.error
+                    ? data.error.message
+                    : 'Invalid API key. Please try again.';
                 errEl.classList.remove('hidden');
             }
         } catch (err) {
@@ -439,11 +460,6 @@ pub const LOGIN: &str = r#"{% extends "layout" %}
             errEl.classList.remove('hidden');
         }
     });
-
-    // If already logged in, redirect
-    if (GAUD.isLoggedIn()) {
-        window.location.href = '/ui/dashboard';
-    }
 </script>
 {% endblock %}"#;
 
@@ -458,8 +474,13 @@ pub const DASHBOARD: &str = r#"{% extends "layout" %}
         <a class="nav-link" href="/ui/oauth">OAuth</a>
         <a class="nav-link" href="/ui/users">Users</a>
         <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
         <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
         <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
     </div>
     <div class="nav-user">
         <span id="nav-username"></span>
@@ -483,23 +504,47 @@ pub const DASHBOARD: &str = r#"{% extends "layout" %}
         <div class="card">
             <div class="stat">
                 <div class="stat-value success" id="stat-requests">--</div>
-                <div class="stat-label">Total Requests</div>
+                <div class="stat-label">Requests (<span class="range-label">24h</span>)</div>
             </div>
         </div>
         <div class="card">
             <div class="stat">
                 <div class="stat-value warning" id="stat-cost">--</div>
-                <div class="stat-label">Total Cost</div>
+                <div class="stat-label">Cost (<span class="range-label">24h</span>)</div>
             </div>
         </div>
         <div class="card">
             <div class="stat">
                 <div class="stat-value" id="stat-tokens" style="color:var(--text-primary);">--</div>
-                <div class="stat-label">Total Tokens</div>
+                <div class="stat-label">Tokens (<span class="range-label">24h</span>)</div>
+            </div>
+        </div>
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value success" id="stat-saved">--</div>
+                <div class="stat-label">Saved by Cache (<span class="range-label">24h</span>)</div>
             </div>
         </div>
     </div>
 
+    <!-- Usage charts -->
+    <div class="card mb-2">
+        <div class="card-header" style="display:flex;justify-content:space-between;align-items:center;">
+            <span>Usage by Provider</span>
+            <select class="form-input" id="chart-interval" style="width:auto;" onchange="loadCharts()">
+                <option value="hour">Last 24 hours</option>
+                <option value="day">Last 30 days</option>
+            </select>
+        </div>
+        <div class="chart-legend" id="chart-legend"></div>
+        <div class="card-grid">
+            <div><div class="stat-label">Requests</div><div class="chart" id="chart-requests"></div></div>
+            <div><div class="stat-label">Cost</div><div class="chart" id="chart-cost"></div></div>
+            <div><div class="stat-label">Tokens</div><div class="chart" id="chart-tokens"></div></div>
+            <div><div class="stat-label">p95 Latency</div><div class="chart" id="chart-latency"></div></div>
+        </div>
+    </div>
+
     <!-- Provider status -->
     <div class="card mb-2">
         <div class="card-header">Provider Status</div>
@@ -546,7 +591,6 @@ pub const DASHBOARD: &str = r#"{% extends "layout" %}
 {% endblock %}
 {% block scripts %}
 <script>
-    if (!GAUD.requireAuth()) throw new Error('Not authenticated');
 
     async function loadDashboard() {
         try {
@@ -574,19 +618,11 @@ pub const DASHBOARD: &str = r#"{% extends "layout" %}
                 document.getElementById('provider-table').innerHTML = rows || '<tr><td colspan="4" class="text-muted">No providers configured</td></tr>';
             }
 
-            // Load usage stats
-            const usageResp = await GAUD.apiFetch('/admin/usage?limit=10');
+            // Load recent activity
+            const usageResp = await GAUD.apiFetch('/admin/usage?per_page=10');
             if (usageResp && usageResp.ok) {
                 const data = await usageResp.json();
-                const entries = data.entries || data || [];
-                const summary = data.summary || {};
-
-                document.getElementById('stat-requests').textContent =
-                    GAUD.formatNumber(summary.total_requests || entries.length);
-                document.getElementById('stat-cost').textContent =
-                    GAUD.formatCost(summary.total_cost || 0);
-                document.getElementById('stat-tokens').textContent =
-                    GAUD.formatNumber(summary.total_tokens || 0);
+                const entries = data.data || [];
 
                 let rows = '';
                 for (const e of entries.slice(0, 10)) {
@@ -611,8 +647,78 @@ pub const DASHBOARD: &str = r#"{% extends "layout" %}
         }
     }
 
+    const CHART_COLORS = ['#3b82f6', '#22c55e', '#f59e0b', '#ef4444', '#a855f7', '#06b6d4', '#ec4899'];
+
+    // Draw one line per provider over the time buckets as an inline SVG.
+    function renderChart(elId, times, series, format) {
+        const el = document.getElementById(elId);
+        const w = 300, h = 140, left = 44, bottom = 16;
+        let max = 0;
+        for (const s of series) for (const v of s.values) if (v != null && v > max) max = v;
+        if (times.length === 0 || max === 0) {
+            el.innerHTML = '<div class="text-muted" style="padding-top:3rem;text-align:center;">No data</div>';
+            return;
+        }
+        const x = i => left + (times.length === 1 ? (w - left) / 2 : i * (w - left) / (times.length - 1));
+        const y = v => (h - bottom) - v / max * (h - bottom - 4);
+        let svg = '<svg viewBox="0 0 ' + w + ' ' + h + '" preserveAspectRatio="none">' +
+            '<line class="chart-axis" x1="' + left + '" y1="' + (h - bottom) + '" x2="' + w + '" y2="' + (h - bottom) + '"/>' +
+            '<text class="chart-label" x="0" y="10">' + format(max) + '</text>' +
+            '<text class="chart-label" x="' + left + '" y="' + h + '">' + times[0] + '</text>' +
+            '<text class="chart-label" x="' + w + '" y="' + h + '" text-anchor="end">' + times[times.length - 1] + '</text>';
+        for (const s of series) {
+            const points = s.values
+                .map((v, i) => v == null ? null : x(i).toFixed(1) + ',' + y(v).toFixed(1))
+                .filter(p => p != null);
+            svg += '<polyline fill="none" stroke-width="1.5" stroke="' + s.color + '" points="' + points.join(' ') + '"/>';
+        }
+        el.innerHTML = svg + '</svg>';
+    }
+
+    async function loadCharts() {
+        const interval = document.getElementById('chart-interval').value;
+        const resp = await GAUD.apiFetch('/ui/api/dashboard/timeseries?interval=' + interval);
+        if (!resp || !resp.ok) return;
+        const ts = await resp.json();
+        const points = ts.data || [];
+
+        const times = [...new Set(points.map(p => p.time))].sort();
+        const providers = [...new Set(points.map(p => p.provider))].sort();
+        const index = {};
+        for (const p of points) index[p.time + '|' + p.provider] = p;
+        const series = field => providers.map((prov, i) => ({
+            color: CHART_COLORS[i % CHART_COLORS.length],
+            values: times.map(t => {
+                const p = index[t + '|' + prov];
+                return p ? field(p) : (field === latency ? null : 0);
+            }),
+        }));
+        const latency = p => p.p95_latency_ms;
+        const label = t => interval === 'hour' ? t.slice(11, 16) : t.slice(5, 10);
+        const labels = times.map(label);
+
+        document.getElementById('chart-legend').innerHTML = providers
+            .map((prov, i) => '<span style="--swatch:' + CHART_COLORS[i % CHART_COLORS.length] + '">' + prov + '</span>')
+            .join('');
+        renderChart('chart-requests', labels, series(p => p.requests), GAUD.formatNumber);
+        renderChart('chart-cost', labels, series(p => p.cost), v => GAUD.formatCost(v));
+        renderChart('chart-tokens', labels, series(p => p.input_tokens + p.output_tokens), GAUD.formatNumber);
+        renderChart('chart-latency', labels, series(latency), v => GAUD.formatNumber(v) + 'ms');
+
+        const sum = f => points.reduce((acc, p) => acc + f(p), 0);
+        document.getElementById('stat-requests').textContent = GAUD.formatNumber(sum(p => p.requests));
+        document.getElementById('stat-cost').textContent = GAUD.formatCost(sum(p => p.cost));
+        document.getElementById('stat-tokens').textContent =
+            GAUD.formatNumber(sum(p => p.input_tokens + p.output_tokens));
+        document.getElementById('stat-saved').textContent = GAUD.formatCost(sum(p => p.cost_saved || 0));
+        for (const el of document.querySelectorAll('.range-label')) {
+            el.textContent = interval === 'hour' ? '24h' : '30d';
+        }
+    }
+
     loadDashboard();
-    setInterval(loadDashboard, 30000);
+    loadCharts();
+    setInterval(() => { loadDashboard(); loadCharts(); }, 30000);
 </script>
 {% endblock %}"#;
 
@@ -627,8 +733,13 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
         <a class="nav-link active" href="/ui/oauth">OAuth</a>
         <a class="nav-link" href="/ui/users">Users</a>
         <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
         <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
         <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
     </div>
     <div class="nav-user">
         <span id="nav-username"></span>
@@ -643,18 +754,18 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
 
     <div id="oauth-status" class="alert hidden"></div>
 
-    <!-- Copilot device code modal -->
-    <div id="copilot-device-modal" class="card mb-2 hidden">
-        <div class="card-header">Copilot Device Authorization</div>
+    <!-- Device code modal (Copilot, Kiro) -->
+    <div id="device-modal" class="card mb-2 hidden">
+        <div class="card-header" id="device-modal-title">Device Authorization</div>
         <div style="text-align:center;padding:1rem;">
             <p style="margin-bottom:1rem;">Visit the URL below and enter the code:</p>
             <p style="margin-bottom:0.5rem;">
-                <a id="copilot-verify-url" href="" target="_blank" rel="noopener noreferrer" style="font-size:1.125rem;"></a>
+                <a id="device-verify-url" href="" target="_blank" rel="noopener noreferrer" style="font-size:1.125rem;"></a>
             </p>
             <p style="margin-bottom:1rem;">
-                <code id="copilot-user-code" class="mono" style="font-size:2rem;font-weight:700;letter-spacing:0.1em;color:var(--accent);"></code>
+                <code id="device-user-code" class="mono" style="font-size:2rem;font-weight:700;letter-spacing:0.1em;color:var(--accent);"></code>
             </p>
-            <p class="text-muted" style="font-size:0.8125rem;">Waiting for authorization... <span id="copilot-poll-status"></span></p>
+            <p class="text-muted" style="font-size:0.8125rem;">Waiting for authorization... <span id="device-poll-status"></span></p>
         </div>
     </div>
 
@@ -665,7 +776,6 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
 {% endblock %}
 {% block scripts %}
 <script>
-    if (!GAUD.requireAuth()) throw new Error('Not authenticated');
 
     const PROVIDERS = {{ providers_json }};
 
@@ -677,6 +787,12 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
         litellm: 'LiteLLM',
     };
 
+    // Local CLIs whose stored credentials can be imported per provider.
+    const IMPORT_SOURCES = {
+        claude: { id: 'claude-code', label: 'Claude Code' },
+        gemini: { id: 'gemini-cli', label: 'gemini-cli' },
+    };
+
     function renderProviders(statuses) {
         const container = document.getElementById('oauth-providers');
         if (!PROVIDERS.length) {
@@ -708,16 +824,44 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
             }
 
             let btn;
-            const isConfigManaged = (prov === 'kiro' || prov === 'litellm');
+            const isConfigManaged = (prov === 'litellm');
             if (isConfigManaged) {
                 btn = authenticated
                     ? '<span class="text-muted" style="font-size:0.8125rem;">Managed via config</span>'
                     : '<span class="text-muted" style="font-size:0.8125rem;">Configure in llm-proxy.toml</span>';
+            } else if (prov === 'kiro') {
+                btn = '<button class="btn btn-sm' + (authenticated ? '' : ' btn-primary') + '" onclick="startOAuth(\'kiro\')">' +
+                    (authenticated ? 'Reconnect' : 'Sign in with AWS') + '</button>';
             } else if (authenticated) {
-                btn = '<button class="btn btn-sm" onclick="startOAuth(\'' + prov + '\')">Reconnect</button>';
+                btn = '<button class="btn btn-sm" onclick="startOAuth(\'' + prov + '\')">Reconnect</button> ' +
+                    '<button class="btn btn-sm" onclick="addAccount(\'' + prov + '\')">Add account</button>';
             } else {
                 btn = '<button class="btn btn-sm btn-primary" onclick="startOAuth(\'' + prov + '\')">Connect</button>';
             }
+            const importSource = IMPORT_SOURCES[prov];
+            if (importSource && status.configured) {
+                btn += ' <button class="btn btn-sm" onclick="importCredentials(\'' + importSource.id + '\')" title="Use the token ' + importSource.label + ' stored on the gaud host">Import from ' + importSource.label + '</button>';
+            }
+
+            let accountsInfo = '';
+            const accounts = status.accounts || [];
+            if (accounts.length > 1 || (accounts.length === 1 && accounts[0].account !== 'default')) {
+                accountsInfo = '<table class="mt-1" style="font-size:0.75rem;width:100%;">' +
+                    '<tr class="text-muted"><th align="left">Account</th><th align="left">Status</th><th align="right">Requests</th><th></th></tr>';
+                for (const acct of accounts) {
+                    const acctStatus = acct.expired ? 'Expired' : (acct.needs_refresh ? 'Refreshing' : 'Active');
+                    const lastUsed = acct.last_used ? ' title="Last used ' + acct.last_used + '"' : '';
+                    const removeBtn = acct.account === 'default' ? '' :
+                        '<button class="btn btn-sm" onclick="removeAccount(\'' + prov + '\', \'' + acct.account + '\')">Remove</button>';
+                    accountsInfo += '<tr' + lastUsed + '>' +
+                        '<td class="mono">' + acct.account + '</td>' +
+                        '<td>' + acctStatus + '</td>' +
+                        '<td align="right">' + acct.requests + '</td>' +
+                        '<td align="right">' + removeBtn + '</td>' +
+                        '</tr>';
+                }
+                accountsInfo += '</table>';
+            }
 
             html += '<div class="card">' +
                 '<div class="flex justify-between items-center mb-1">' +
@@ -725,6 +869,7 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
                 statusBadge +
                 '</div>' +
                 expiresInfo +
+                accountsInfo +
                 '<div class="mt-2">' + btn + '</div>' +
                 '</div>';
         }
@@ -744,17 +889,69 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
         renderProviders(statuses);
     }
 
-    async function startOAuth(provider) {
+    async function addAccount(provider) {
+        const account = prompt('Name for the additional account (letters, digits, - or _):');
+        if (!account) return;
+        await startOAuth(provider, account.trim());
+    }
+
+    async function importCredentials(source) {
+        const statusEl = document.getElementById('oauth-status');
+        try {
+            const resp = await GAUD.apiFetch('/ui/api/oauth/import/' + source, { method: 'POST' });
+            if (!resp) return;
+            const data = await resp.json();
+            if (data.error) {
+                statusEl.className = 'alert alert-danger';
+                statusEl.textContent = data.error.message || data.error;
+            } else {
+                statusEl.className = data.expired ? 'alert alert-warning' : 'alert alert-success';
+                const label = PROVIDER_LABELS[data.provider] || data.provider;
+                statusEl.textContent = data.expired
+                    ? label + ' credentials imported; the token has expired and will be refreshed on first use.'
+                    : label + ' credentials imported.';
+            }
+            statusEl.classList.remove('hidden');
+        } catch (err) {
+            statusEl.className = 'alert alert-danger';
+            statusEl.textContent = 'Error: ' + err.message;
+            statusEl.classList.remove('hidden');
+        }
+        loadStatuses();
+    }
+
+    async function removeAccount(provider, account) {
+        if (!confirm('Remove account "' + account + '" from ' + (PROVIDER_LABELS[provider] || provider) + '?')) return;
+        const statusEl = document.getElementById('oauth-status');
+        try {
+            const resp = await GAUD.apiFetch('/ui/api/oauth/accounts/' + provider + '/' + encodeURIComponent(account), { method: 'DELETE' });
+            if (!resp) return;
+            const data = await resp.json();
+            if (data.error) {
+                statusEl.className = 'alert alert-danger';
+                statusEl.textContent = data.error.message || data.error;
+                statusEl.classList.remove('hidden');
+            }
+        } catch (err) {
+            statusEl.className = 'alert alert-danger';
+            statusEl.textContent = 'Error: ' + err.message;
+            statusEl.classList.remove('hidden');
+        }
+        loadStatuses();
+    }
+
+    async function startOAuth(provider, account) {
         const statusEl = document.getElementById('oauth-status');
         statusEl.classList.add('hidden');
 
-        if (provider === 'copilot') {
-            await startCopilotDeviceFlow();
+        if (provider === 'copilot' || provider === 'kiro') {
+            await startDeviceFlow(provider, account);
             return;
         }
 
         try {
-            const resp = await GAUD.apiFetch('/ui/api/oauth/start/' + provider, { method: 'POST' });
+            const query = account ? '?account=' + encodeURIComponent(account) : '';
+            const resp = await GAUD.apiFetch('/ui/api/oauth/start/' + provider + query, { method: 'POST' });
             if (!resp) return;
             const data = await resp.json();
             if (data.auth_url) {
@@ -772,7 +969,10 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
                         const sr = await GAUD.apiFetch('/ui/api/oauth/status/' + provider);
                         if (sr && sr.ok) {
                             const s = await sr.json();
-                            if (s.authenticated) {
+                            const done = account
+                                ? (s.accounts || []).some(a => a.account === account)
+                                : s.authenticated;
+                            if (done) {
                                 clearInterval(poll);
                                 statusEl.className = 'alert alert-success';
                                 statusEl.textContent = (PROVIDER_LABELS[provider] || provider) + ' connected successfully!';
@@ -784,7 +984,7 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
                 setTimeout(() => clearInterval(poll), 300000);
             } else if (data.error) {
                 statusEl.className = 'alert alert-danger';
-                statusEl.textContent = data.error;
+                statusEl.textContent = data.error.message || data.error;
                 statusEl.classList.remove('hidden');
             }
         } catch (err) {
@@ -794,31 +994,34 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
         }
     }
 
-    async function startCopilotDeviceFlow() {
+    async function startDeviceFlow(provider, account) {
         const statusEl = document.getElementById('oauth-status');
-        const modal = document.getElementById('copilot-device-modal');
+        const modal = document.getElementById('device-modal');
+        const name = provider === 'kiro' ? 'Kiro' : 'Copilot';
 
         try {
-            const resp = await GAUD.apiFetch('/ui/api/oauth/copilot/device', { method: 'POST' });
+            const resp = await GAUD.apiFetch('/ui/api/oauth/' + provider + '/device', { method: 'POST' });
             if (!resp) return;
             const data = await resp.json();
 
             if (data.error) {
                 statusEl.className = 'alert alert-danger';
-                statusEl.textContent = data.error;
+                statusEl.textContent = data.error.message || data.error;
                 statusEl.classList.remove('hidden');
                 return;
             }
 
             // Show device code modal
-            document.getElementById('copilot-verify-url').href = data.verification_uri;
-            document.getElementById('copilot-verify-url').textContent = data.verification_uri;
-            document.getElementById('copilot-user-code').textContent = data.user_code;
-            document.getElementById('copilot-poll-status').textContent = '';
+            document.getElementById('device-modal-title').textContent = name + ' Device Authorization';
+            document.getElementById('device-verify-url').href = data.verification_uri;
+            document.getElementById('device-verify-url').textContent = data.verification_uri;
+            document.getElementById('device-user-code').textContent = data.user_code;
+            document.getElementById('device-poll-status').textContent = '';
             modal.classList.remove('hidden');
 
-            // Open verification URL
-            const authWin = window.open(data.verification_uri, 'copilot_auth', 'width=600,height=700,noopener,noreferrer');
+            // Open verification URL, with the code pre-filled when supported
+            const openUrl = data.verification_uri_complete || data.verification_uri;
+            const authWin = window.open(openUrl, provider + '_auth', 'width=600,height=700,noopener,noreferrer');
             if (authWin) {
                 authWin.opener = null;
             }
@@ -831,7 +1034,7 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
 
             const pollTimer = setInterval(async () => {
                 attempts++;
-                document.getElementById('copilot-poll-status').textContent = '(attempt ' + attempts + ')';
+                document.getElementById('device-poll-status').textContent = '(attempt ' + attempts + ')';
 
                 if (attempts >= maxAttempts) {
                     clearInterval(pollTimer);
@@ -843,9 +1046,9 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
                 }
 
                 try {
-                    const pr = await GAUD.apiFetch('/ui/api/oauth/copilot/poll', {
+                    const pr = await GAUD.apiFetch('/ui/api/oauth/' + provider + '/poll', {
                         method: 'POST',
-                        body: JSON.stringify({ device_code: data.device_code }),
+                        body: JSON.stringify({ device_code: data.device_code, account: account || null }),
                     });
                     if (!pr) return;
                     const result = await pr.json();
@@ -854,14 +1057,14 @@ pub const OAUTH: &str = r#"{% extends "layout" %}
                         clearInterval(pollTimer);
                         modal.classList.add('hidden');
                         statusEl.className = 'alert alert-success';
-                        statusEl.textContent = 'Copilot connected successfully!';
+                        statusEl.textContent = name + ' connected successfully!';
                         statusEl.classList.remove('hidden');
                         loadStatuses();
-                    } else if (result.status === 'error') {
+                    } else if (result.status === 'error' || result.error) {
                         clearInterval(pollTimer);
                         modal.classList.add('hidden');
                         statusEl.className = 'alert alert-danger';
-                        statusEl.textContent = 'Copilot error: ' + (result.error || 'Unknown error');
+                        statusEl.textContent = name + ' error: ' + ((result.error && result.error.message) || result.error || 'Unknown error');
                         statusEl.classList.remove('hidden');
                     }
                     // 'pending' and 'slow_down' -- keep polling
@@ -930,8 +1133,13 @@ pub const USERS: &str = r#"{% extends "layout" %}
         <a class="nav-link" href="/ui/oauth">OAuth</a>
         <a class="nav-link active" href="/ui/users">Users</a>
         <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
         <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
         <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
     </div>
     <div class="nav-user">
         <span id="nav-username"></span>
@@ -1001,7 +1209,6 @@ pub const USERS: &str = r#"{% extends "layout" %}
 {% endblock %}
 {% block scripts %}
 <script>
-    if (!GAUD.requireAuth()) throw new Error('Not authenticated');
 
     function showCreateUser() { document.getElementById('create-user-form').classList.remove('hidden'); }
     function hideCreateUser() { document.getElementById('create-user-form').classList.add('hidden'); }
@@ -1067,7 +1274,7 @@ pub const USERS: &str = r#"{% extends "layout" %}
 
     async function createApiKey(userId, userName) {
         try {
-            const resp = await GAUD.apiFetch('/admin/users/' + userId + '/keys', {
+            const resp = await GAUD.apiFetchWithTotp('/admin/users/' + userId + '/keys', {
                 method: 'POST',
                 body: JSON.stringify({ label: 'web-ui' }),
             });
@@ -1107,8 +1314,13 @@ pub const USAGE: &str = r#"{% extends "layout" %}
         <a class="nav-link" href="/ui/oauth">OAuth</a>
         <a class="nav-link" href="/ui/users">Users</a>
         <a class="nav-link active" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
         <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
         <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
     </div>
     <div class="nav-user">
         <span id="nav-username"></span>
@@ -1125,19 +1337,64 @@ pub const USAGE: &str = r#"{% extends "layout" %}
     <div class="card mb-2">
         <div class="flex gap-2 flex-wrap items-center">
             <div class="form-group" style="margin:0;flex:1;min-width:150px;">
-                <select class="form-input" id="filter-provider" onchange="loadUsage()">
+                <select class="form-input" id="filter-provider" onchange="search()">
                     <option value="">All Providers</option>
                     <option value="claude">Claude</option>
                     <option value="gemini">Gemini</option>
                     <option value="copilot">Copilot</option>
+                    <option value="cache">Cache</option>
+                </select>
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:150px;">
+                <input class="form-input mono" id="filter-model" placeholder="Model" onchange="search()">
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:150px;">
+                <select class="form-input" id="filter-status" onchange="search()">
+                    <option value="">All Statuses</option>
+                    <option value="success">Success</option>
+                    <option value="error">Errors</option>
+                    <option value="cache_hit">Cache hits</option>
                 </select>
             </div>
+            <div class="form-group" style="margin:0;flex:2;min-width:200px;">
+                <input class="form-input" id="filter-search" placeholder="Search tags" onchange="search()">
+            </div>
+        </div>
+        <div class="flex gap-2 flex-wrap items-center" style="margin-top:0.75rem;">
+            <div class="form-group" style="margin:0;flex:1;min-width:140px;">
+                <input class="form-input" type="date" id="filter-from" title="From" onchange="search()">
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:140px;">
+                <input class="form-input" type="date" id="filter-to" title="To" onchange="search()">
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:110px;">
+                <input class="form-input mono" type="number" min="0" step="any" id="filter-min-cost" placeholder="Min cost" onchange="search()">
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:110px;">
+                <input class="form-input mono" type="number" min="0" step="any" id="filter-max-cost" placeholder="Max cost" onchange="search()">
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:110px;">
+                <input class="form-input mono" type="number" min="0" id="filter-min-latency" placeholder="Min ms" onchange="search()">
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:110px;">
+                <input class="form-input mono" type="number" min="0" id="filter-max-latency" placeholder="Max ms" onchange="search()">
+            </div>
             <div class="form-group" style="margin:0;flex:1;min-width:150px;">
-                <select class="form-input" id="filter-limit" onchange="loadUsage()">
-                    <option value="25">Last 25</option>
-                    <option value="50">Last 50</option>
-                    <option value="100" selected>Last 100</option>
-                    <option value="500">Last 500</option>
+                <select class="form-input" id="filter-sort" onchange="search()">
+                    <option value="created_at:desc">Newest first</option>
+                    <option value="created_at:asc">Oldest first</option>
+                    <option value="cost:desc">Most expensive</option>
+                    <option value="latency_ms:desc">Slowest</option>
+                    <option value="input_tokens:desc">Most input tokens</option>
+                    <option value="output_tokens:desc">Most output tokens</option>
+                </select>
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:110px;">
+                <select class="form-input" id="filter-per-page" onchange="search()">
+                    <option value="25">25 / page</option>
+                    <option value="50" selected>50 / page</option>
+                    <option value="100">100 / page</option>
+                    <option value="500">500 / page</option>
                 </select>
             </div>
             <button class="btn btn-sm" onclick="loadUsage()">Refresh</button>
@@ -1149,25 +1406,25 @@ pub const USAGE: &str = r#"{% extends "layout" %}
         <div class="card">
             <div class="stat">
                 <div class="stat-value info" id="usage-total-requests">--</div>
-                <div class="stat-label">Requests</div>
+                <div class="stat-label">Matching Requests</div>
             </div>
         </div>
         <div class="card">
             <div class="stat">
                 <div class="stat-value success" id="usage-total-tokens">--</div>
-                <div class="stat-label">Total Tokens</div>
+                <div class="stat-label">Tokens (this page)</div>
             </div>
         </div>
         <div class="card">
             <div class="stat">
                 <div class="stat-value warning" id="usage-total-cost">--</div>
-                <div class="stat-label">Total Cost</div>
+                <div class="stat-label">Cost (this page)</div>
             </div>
         </div>
         <div class="card">
             <div class="stat">
                 <div class="stat-value" id="usage-avg-latency" style="color:var(--text-primary);">--</div>
-                <div class="stat-label">Avg Latency</div>
+                <div class="stat-label">Avg Latency (this page)</div>
             </div>
         </div>
     </div>
@@ -1193,24 +1450,73 @@ pub const USAGE: &str = r#"{% extends "layout" %}
                 </tbody>
             </table>
         </div>
+        <div class="flex gap-2 items-center" style="margin-top:0.75rem;justify-content:flex-end;">
+            <button class="btn btn-sm" id="page-prev" onclick="goToPage(page - 1)">Previous</button>
+            <span class="text-muted" id="page-info">--</span>
+            <button class="btn btn-sm" id="page-next" onclick="goToPage(page + 1)">Next</button>
+        </div>
     </div>
 </div>
 {% endblock %}
 {% block scripts %}
 <script>
-    if (!GAUD.requireAuth()) throw new Error('Not authenticated');
 
-    async function loadUsage() {
-        const provider = document.getElementById('filter-provider').value;
-        const limit = document.getElementById('filter-limit').value;
-        let url = '/admin/usage?limit=' + limit;
-        if (provider) url += '&provider=' + provider;
+    let page = 1;
+    let pages = 1;
+
+    function escapeHtml(s) {
+        return String(s).replace(/[&<>"]/g, c => ({'&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;'}[c]));
+    }
 
+    function usageUrl() {
+        const params = new URLSearchParams();
+        const fields = {
+            provider: 'filter-provider',
+            model: 'filter-model',
+            status: 'filter-status',
+            search: 'filter-search',
+            from: 'filter-from',
+            to: 'filter-to',
+            min_cost: 'filter-min-cost',
+            max_cost: 'filter-max-cost',
+            min_latency_ms: 'filter-min-latency',
+            max_latency_ms: 'filter-max-latency',
+        };
+        for (const [param, id] of Object.entries(fields)) {
+            const value = document.getElementById(id).value.trim();
+            if (value) params.set(param, value);
+        }
+        const [sort, order] = document.getElementById('filter-sort').value.split(':');
+        params.set('sort', sort);
+        params.set('order', order);
+        params.set('per_page', document.getElementById('filter-per-page').value);
+        params.set('page', page);
+        return '/admin/usage?' + params.toString();
+    }
+
+    function search() {
+        page = 1;
+        loadUsage();
+    }
+
+    function goToPage(n) {
+        if (n < 1 || n > pages) return;
+        page = n;
+        loadUsage();
+    }
+
+    async function loadUsage() {
         try {
-            const resp = await GAUD.apiFetch(url);
-            if (!resp || !resp.ok) return;
+            const resp = await GAUD.apiFetch(usageUrl());
+            if (!resp) return;
             const data = await resp.json();
-            const entries = data.entries || data || [];
+            if (!resp.ok) {
+                document.getElementById('usage-table').innerHTML =
+                    '<tr><td colspan="8" class="text-muted">' +
+                    escapeHtml((data.error && data.error.message) || 'Failed to load usage') + '</td></tr>';
+                return;
+            }
+            const entries = data.data || [];
 
             let totalTokens = 0, totalCost = 0, totalLatency = 0;
             let rows = '';
@@ -1223,27 +1529,32 @@ pub const USAGE: &str = r#"{% extends "layout" %}
 
                 const statusBadge = e.status === 'success'
                     ? '<span class="badge badge-success">OK</span>'
-                    : '<span class="badge badge-danger">' + (e.status || 'err') + '</span>';
+                    : '<span class="badge badge-danger">' + escapeHtml(e.status || 'err') + '</span>';
 
                 rows += '<tr>' +
-                    '<td class="mono" style="font-size:0.75rem;">' + (e.created_at || '--') + '</td>' +
-                    '<td>' + (e.user_name || e.user_id || '--') + '</td>' +
-                    '<td>' + (e.provider || '--') + '</td>' +
-                    '<td class="mono" style="font-size:0.75rem;">' + (e.model || '--') + '</td>' +
+                    '<td class="mono" style="font-size:0.75rem;">' + escapeHtml(e.created_at || '--') + '</td>' +
+                    '<td>' + escapeHtml(e.user_id || '--') + '</td>' +
+                    '<td>' + escapeHtml(e.provider || '--') + '</td>' +
+                    '<td class="mono" style="font-size:0.75rem;">' + escapeHtml(e.model || '--') + '</td>' +
                     '<td class="mono">' + GAUD.formatNumber(inTok) + ' / ' + GAUD.formatNumber(outTok) + '</td>' +
                     '<td class="mono">' + GAUD.formatCost(e.cost || 0) + '</td>' +
                     '<td class="mono">' + (e.latency_ms || 0) + 'ms</td>' +
                     '<td>' + statusBadge + '</td></tr>';
             }
 
-            document.getElementById('usage-total-requests').textContent = entries.length;
+            document.getElementById('usage-total-requests').textContent = GAUD.formatNumber(data.total || 0);
             document.getElementById('usage-total-tokens').textContent = GAUD.formatNumber(totalTokens);
             document.getElementById('usage-total-cost').textContent = GAUD.formatCost(totalCost);
             const avgLat = entries.length > 0 ? Math.round(totalLatency / entries.length) : 0;
             document.getElementById('usage-avg-latency').textContent = avgLat + 'ms';
 
+            pages = Math.max(1, Math.ceil((data.total || 0) / data.per_page));
+            document.getElementById('page-info').textContent = 'Page ' + page + ' of ' + pages;
+            document.getElementById('page-prev').disabled = page <= 1;
+            document.getElementById('page-next').disabled = page >= pages;
+
             document.getElementById('usage-table').innerHTML =
-                rows || '<tr><td colspan="8" class="text-muted">No usage data</td></tr>';
+                rows || '<tr><td colspan="8" class="text-muted">No matching requests</td></tr>';
         } catch (err) {
             console.error('Failed to load usage:', err);
         }
@@ -1253,9 +1564,9 @@ pub const USAGE: &str = r#"{% extends "layout" %}
 </script>
 {% endblock %}"#;
 
-/// Budget management page template.
-pub const BUDGETS: &str = r#"{% extends "layout" %}
-{% block title %}Budgets{% endblock %}
+/// Live request log page template.
+pub const LOGS: &str = r#"{% extends "layout" %}
+{% block title %}Live Logs{% endblock %}
 {% block body %}
 <nav class="navbar">
     <a class="navbar-brand" href="/ui/dashboard"><span>gaud</span></a>
@@ -1264,8 +1575,13 @@ pub const BUDGETS: &str = r#"{% extends "layout" %}
         <a class="nav-link" href="/ui/oauth">OAuth</a>
         <a class="nav-link" href="/ui/users">Users</a>
         <a class="nav-link" href="/ui/usage">Usage</a>
-        <a class="nav-link active" href="/ui/budgets">Budgets</a>
+        <a class="nav-link active" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
+        <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
         <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
     </div>
     <div class="nav-user">
         <span id="nav-username"></span>
@@ -1274,155 +1590,920 @@ pub const BUDGETS: &str = r#"{% extends "layout" %}
 </nav>
 <div class="container">
     <div class="page-header">
-        <h1>Budget Management</h1>
-        <p>Configure spending limits per user</p>
+        <h1>Live Logs</h1>
+        <p>Requests as they complete, newest first</p>
     </div>
 
-    <div id="budget-alert" class="alert hidden"></div>
+    <!-- Filters -->
+    <div class="card mb-2">
+        <div class="flex gap-2 flex-wrap items-center">
+            <div class="form-group" style="margin:0;flex:1;min-width:150px;">
+                <select class="form-input" id="filter-user" onchange="connect()">
+                    <option value="">All Users</option>
+                </select>
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:150px;">
+                <select class="form-input" id="filter-provider" onchange="connect()">
+                    <option value="">All Providers</option>
+                    <option value="claude">Claude</option>
+                    <option value="gemini">Gemini</option>
+                    <option value="copilot">Copilot</option>
+                    <option value="kiro">Kiro</option>
+                    <option value="litellm">LiteLLM</option>
+                    <option value="cache">Cache</option>
+                </select>
+            </div>
+            <button class="btn btn-sm" id="pause-btn" onclick="togglePause()">Pause</button>
+            <button class="btn btn-sm" onclick="clearLog()">Clear</button>
+            <span class="text-muted" id="tail-status">Connecting...</span>
+        </div>
+    </div>
 
     <div class="card">
         <div class="table-wrap">
             <table>
                 <thead>
                     <tr>
+                        <th>Time</th>
                         <th>User</th>
-                        <th>Monthly Limit</th>
-                        <th>Monthly Used</th>
-                        <th>Daily Limit</th>
-                        <th>Daily Used</th>
-                        <th>Usage</th>
-                        <th>Actions</th>
+                        <th>Provider</th>
+                        <th>Model</th>
+                        <th>In / Out</th>
+                        <th>Latency</th>
+                        <th>Status</th>
                     </tr>
                 </thead>
-                <tbody id="budgets-table">
-                    <tr><td colspan="7" class="text-muted">Loading...</td></tr>
+                <tbody id="log-table">
+                    <tr><td colspan="7" class="text-muted">Waiting for requests...</td></tr>
                 </tbody>
             </table>
         </div>
     </div>
-
-    <!-- Edit budget modal (inline) -->
-    <div id="edit-budget-form" class="card mt-2 hidden">
-        <div class="card-header">Edit Budget</div>
-        <form onsubmit="saveBudget(event)">
-            <input type="hidden" id="edit-user-id">
-            <p class="mb-1"><strong id="edit-user-name"></strong></p>
-            <div class="flex gap-2 flex-wrap">
-                <div class="form-group" style="flex:1;min-width:200px;">
-                    <label class="form-label" for="edit-monthly-limit">Monthly Limit ($)</label>
-                    <input class="form-input" type="number" step="0.01" id="edit-monthly-limit" placeholder="No limit">
-                </div>
-                <div class="form-group" style="flex:1;min-width:200px;">
-                    <label class="form-label" for="edit-daily-limit">Daily Limit ($)</label>
-                    <input class="form-input" type="number" step="0.01" id="edit-daily-limit" placeholder="No limit">
-                </div>
-            </div>
-            <div class="flex gap-1">
-                <button type="submit" class="btn btn-primary btn-sm">Save</button>
-                <button type="button" class="btn btn-sm" onclick="hideEditBudget()">Cancel</button>
-            </div>
-        </form>
-    </div>
 </div>
 {% endblock %}
 {% block scripts %}
 <script>
-    if (!GAUD.requireAuth()) throw new Error('Not authenticated');
 
-    function hideEditBudget() { document.getElementById('edit-budget-form').classList.add('hidden'); }
+    const MAX_ROWS = 500;
+    const userNames = {};
+    let controller = null;
+    let paused = false;
+    let rowCount = 0;
 
-    function showEditBudget(userId, userName, monthlyLimit, dailyLimit) {
-        document.getElementById('edit-user-id').value = userId;
-        document.getElementById('edit-user-name').textContent = userName;
-        document.getElementById('edit-monthly-limit').value = monthlyLimit || '';
-        document.getElementById('edit-daily-limit').value = dailyLimit || '';
-        document.getElementById('edit-budget-form').classList.remove('hidden');
+    function escapeHtml(s) {
+        return String(s).replace(/[&<>"]/g, c => ({'&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;'}[c]));
     }
 
-    async function loadBudgets() {
-        try {
-            const resp = await GAUD.apiFetch('/admin/budgets');
-            if (!resp || !resp.ok) return;
-            const budgets = await resp.json();
+    function setStatus(text) {
+        document.getElementById('tail-status').textContent = text;
+    }
 
-            let rows = '';
-            for (const b of budgets) {
-                const monthlyLimit = b.monthly_limit != null ? '$' + b.monthly_limit.toFixed(2) : 'None';
-                const dailyLimit = b.daily_limit != null ? '$' + b.daily_limit.toFixed(2) : 'None';
-                const monthlyUsed = '$' + (b.monthly_used || 0).toFixed(2);
-                const dailyUsed = '$' + (b.daily_used || 0).toFixed(2);
+    function renderRow(e) {
+        let status;
+        if (e.cache_hit) {
+            status = '<span class="badge badge-info">Cache hit</span>';
+        } else if (e.status === 'success') {
+            status = '<span class="badge badge-success">OK</span>';
+        } else {
+            status = '<span class="badge badge-danger">' + escapeHtml(e.status || 'error') + '</span>';
+        }
+        return '<tr>' +
+            '<td class="mono" style="font-size:0.75rem;">' + new Date(e.time).toLocaleTimeString() + '</td>' +
+            '<td>' + escapeHtml(userNames[e.user_id] || e.user_id) + '</td>' +
+            '<td>' + escapeHtml(e.provider) + '</td>' +
+            '<td class="mono" style="font-size:0.75rem;">' + escapeHtml(e.model) + '</td>' +
+            '<td class="mono">' + GAUD.formatNumber(e.input_tokens) + ' / ' + GAUD.formatNumber(e.output_tokens) + '</td>' +
+            '<td class="mono">' + e.latency_ms + 'ms</td>' +
+            '<td>' + status + '</td></tr>';
+    }
 
-                let pct = 0;
-                let barClass = 'success';
-                if (b.monthly_limit && b.monthly_limit > 0) {
-                    pct = Math.min(100, ((b.monthly_used || 0) / b.monthly_limit) * 100);
-                    if (pct >= 90) barClass = 'danger';
-                    else if (pct >= 70) barClass = 'warning';
-                }
+    function addEntry(e) {
+        if (paused) return;
+        const table = document.getElementById('log-table');
+        if (rowCount === 0) table.innerHTML = '';
+        table.insertAdjacentHTML('afterbegin', renderRow(e));
+        rowCount++;
+        if (rowCount > MAX_ROWS) {
+            table.lastElementChild.remove();
+            rowCount--;
+        }
+    }
 
-                const progressBar = b.monthly_limit
-                    ? '<div class="progress"><div class="progress-bar ' + barClass + '" style="width:' + pct.toFixed(0) + '%;"></div></div>'
-                    : '<span class="text-muted" style="font-size:0.75rem;">No limit set</span>';
+    function clearLog() {
+        rowCount = 0;
+        document.getElementById('log-table').innerHTML =
+            '<tr><td colspan="7" class="text-muted">Waiting for requests...</td></tr>';
+    }
 
-                rows += '<tr>' +
-                    '<td><strong>' + (b.user_name || b.user_id) + '</strong></td>' +
-                    '<td class="mono">' + monthlyLimit + '</td>' +
-                    '<td class="mono">' + monthlyUsed + '</td>' +
-                    '<td class="mono">' + dailyLimit + '</td>' +
-                    '<td class="mono">' + dailyUsed + '</td>' +
-                    '<td style="min-width:120px;">' + progressBar + '</td>' +
-                    '<td><button class="btn btn-sm" onclick="showEditBudget(\'' +
-                        b.user_id + "','" + (b.user_name || b.user_id) + "'," +
-                        (b.monthly_limit || 'null') + ',' + (b.daily_limit || 'null') +
-                    ')">Edit</button></td></tr>';
-            }
-            document.getElementById('budgets-table').innerHTML =
-                rows || '<tr><td colspan="7" class="text-muted">No budgets configured. Budgets are created automatically when users make requests.</td></tr>';
-        } catch (err) {
-            console.error('Failed to load budgets:', err);
-        }
+    function togglePause() {
+        paused = !paused;
+        document.getElementById('pause-btn').textContent = paused ? 'Resume' : 'Pause';
+        setStatus(paused ? 'Paused' : 'Live');
     }
 
-    async function saveBudget(e) {
-        e.preventDefault();
-        const userId = document.getElementById('edit-user-id').value;
-        const monthlyLimit = document.getElementById('edit-monthly-limit').value;
-        const dailyLimit = document.getElementById('edit-daily-limit').value;
-        const alertEl = document.getElementById('budget-alert');
+    // Read the SSE stream through fetch so it can be aborted and restarted
+    // when the filters change.
+    async function connect() {
+        if (controller) controller.abort();
+        controller = new AbortController();
+        clearLog();
+
+        const params = new URLSearchParams();
+        const user = document.getElementById('filter-user').value;
+        const provider = document.getElementById('filter-provider').value;
+        if (user) params.set('user', user);
+        if (provider) params.set('provider', provider);
 
         try {
-            const resp = await GAUD.apiFetch('/admin/budgets/' + userId, {
-                method: 'PUT',
-                body: JSON.stringify({
-                    monthly_limit: monthlyLimit ? parseFloat(monthlyLimit) : null,
-                    daily_limit: dailyLimit ? parseFloat(dailyLimit) : null,
-                }),
+            const resp = await GAUD.apiFetch('/ui/api/logs/stream?' + params, {
+                signal: controller.signal,
             });
-            if (!resp) return;
-            if (resp.ok) {
-                hideEditBudget();
-                alertEl.className = 'alert alert-success';
-                alertEl.textContent = 'Budget updated successfully.';
-                alertEl.classList.remove('hidden');
-                setTimeout(() => alertEl.classList.add('hidden'), 3000);
-                loadBudgets();
-            } else {
-                const data = await resp.json();
-                alertEl.className = 'alert alert-danger';
-                alertEl.textContent = data.error?.message || 'Failed to save budget.';
-                alertEl.classList.remove('hidden');
+            if (!resp || !resp.ok) {
+                setStatus('Disconnected');
+                return;
+            }
+            setStatus(paused ? 'Paused' : 'Live');
+            const reader = resp.body.getReader();
+            const decoder = new TextDecoder();
+            let buffer = '';
+            for (;;) {
+                const { done, value } = await reader.read();
+                if (done) break;
+                buffer += decoder.decode(value, { stream: true });
+                let split;
+                while ((split = buffer.indexOf('\n\n')) >= 0) {
+                    const block = buffer.slice(0, split);
+                    buffer = buffer.slice(split + 2);
+                    let event = 'message', data = '';
+                    for (const line of block.split('\n')) {
+                        if (line.startsWith('event:')) event = line.slice(6).trim();
+                        else if (line.startsWith('data:')) data += line.slice(5).trim();
+                    }
+                    if (event === 'request' && data) addEntry(JSON.parse(data));
+                    else if (event === 'lagged') setStatus('Live (skipped ' + data + ')');
+                }
             }
+            setStatus('Disconnected');
         } catch (err) {
-            alertEl.className = 'alert alert-danger';
-            alertEl.textContent = err.message;
-            alertEl.classList.remove('hidden');
+            if (err.name !== 'AbortError') {
+                console.error('Log stream error:', err);
+                setStatus('Disconnected');
+            }
         }
     }
 
-    loadBudgets();
-</script>
-{% endblock %}"#;
-
+    async function loadUsers() {
+        try {
+            const resp = await GAUD.apiFetch('/admin/users');
+            if (!resp || !resp.ok) return;
+            const select = document.getElementById('filter-user');
+            for (const u of await resp.json()) {
+                userNames[u.id] = u.name;
+                const opt = document.createElement('option');
+                opt.value = u.id;
+                opt.textContent = u.name;
+                select.appendChild(opt);
+            }
+        } catch (err) {
+            console.error('Failed to load users:', err);
+        }
+    }
+
+    loadUsers().then(connect);
+</script>
+{% endblock %}"#;
+
+/// Chat playground page template.
+pub const PLAYGROUND: &str = r#"{% extends "layout" %}
+{% block title %}Playground{% endblock %}
+{% block body %}
+<nav class="navbar">
+    <a class="navbar-brand" href="/ui/dashboard"><span>gaud</span></a>
+    <div class="nav-links">
+        <a class="nav-link" href="/ui/dashboard">Dashboard</a>
+        <a class="nav-link" href="/ui/oauth">OAuth</a>
+        <a class="nav-link" href="/ui/users">Users</a>
+        <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link active" href="/ui/playground">Playground</a>
+        <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
+        <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
+    </div>
+    <div class="nav-user">
+        <span id="nav-username"></span>
+        <button class="logout-btn" onclick="GAUD.logout()">Logout</button>
+    </div>
+</nav>
+<div class="container">
+    <div class="page-header">
+        <h1>Playground</h1>
+        <p>Send a chat request through gaud and see how it was routed</p>
+    </div>
+
+    <div id="playground-alert" class="alert hidden"></div>
+
+    <div class="card mb-2">
+        <div class="flex gap-2 flex-wrap items-center mb-2">
+            <div class="form-group" style="margin:0;flex:2;min-width:200px;">
+                <select class="form-input" id="opt-model">
+                    <option value="">Loading models...</option>
+                </select>
+            </div>
+            <div class="form-group" style="margin:0;flex:1;min-width:120px;">
+                <input class="form-input" id="opt-max-tokens" type="number" min="1" placeholder="Max tokens">
+            </div>
+            <label class="text-muted"><input type="checkbox" id="opt-stream" checked> Stream</label>
+            <label class="text-muted"><input type="checkbox" id="opt-thinking"> Thinking</label>
+        </div>
+        <div class="form-group">
+            <textarea class="form-input" id="opt-system" rows="2" placeholder="System prompt (optional)"></textarea>
+        </div>
+        <div class="form-group">
+            <textarea class="form-input" id="opt-prompt" rows="5" placeholder="Prompt"></textarea>
+        </div>
+        <button class="btn" id="send-btn" onclick="send()">Send</button>
+    </div>
+
+    <div class="card-grid mb-2">
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value info" id="result-provider">--</div>
+                <div class="stat-label">Provider</div>
+            </div>
+        </div>
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value success" id="result-tokens">--</div>
+                <div class="stat-label">In / Out Tokens</div>
+            </div>
+        </div>
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value warning" id="result-cost">--</div>
+                <div class="stat-label">Cost</div>
+            </div>
+        </div>
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value" id="result-latency" style="color:var(--text-primary);">--</div>
+                <div class="stat-label">Latency</div>
+            </div>
+        </div>
+    </div>
+
+    <div class="card mb-2 hidden" id="reasoning-card">
+        <div class="card-header">Reasoning</div>
+        <pre class="mono text-muted" id="result-reasoning" style="white-space:pre-wrap;margin:0;"></pre>
+    </div>
+    <div class="card">
+        <div class="card-header">Response</div>
+        <pre class="mono" id="result-content" style="white-space:pre-wrap;margin:0;"></pre>
+    </div>
+</div>
+{% endblock %}
+{% block scripts %}
+<script>
+
+    const THINKING_BUDGET = 2048;
+
+    function showAlert(message) {
+        const el = document.getElementById('playground-alert');
+        el.className = 'alert alert-danger';
+        el.textContent = message;
+    }
+
+    function setText(id, text) {
+        document.getElementById(id).textContent = text;
+    }
+
+    async function loadModels() {
+        const select = document.getElementById('opt-model');
+        try {
+            const resp = await GAUD.apiFetch('/v1/models');
+            if (!resp || !resp.ok) {
+                select.innerHTML = '<option value="">No models available</option>';
+                return;
+            }
+            const data = await resp.json();
+            select.innerHTML = '';
+            for (const m of data.data || []) {
+                const opt = document.createElement('option');
+                opt.value = m.id;
+                opt.textContent = m.id + ' (' + m.owned_by + ')';
+                select.appendChild(opt);
+            }
+        } catch (err) {
+            console.error('Failed to load models:', err);
+        }
+    }
+
+    function buildRequest() {
+        const messages = [];
+        const system = document.getElementById('opt-system').value.trim();
+        if (system) messages.push({ role: 'system', content: system });
+        messages.push({ role: 'user', content: document.getElementById('opt-prompt').value });
+
+        const body = {
+            model: document.getElementById('opt-model').value,
+            messages,
+            stream: document.getElementById('opt-stream').checked,
+        };
+        if (body.stream) body.stream_options = { include_usage: true };
+        let maxTokens = parseInt(document.getElementById('opt-max-tokens').value, 10) || null;
+        if (document.getElementById('opt-thinking').checked) {
+            // Each provider takes its own native parameter (see extra_body).
+            maxTokens = Math.max(maxTokens || 0, THINKING_BUDGET * 2);
+            body.extra_body = {
+                claude: { thinking: { type: 'enabled', budget_tokens: THINKING_BUDGET } },
+                gemini: { generationConfig: { thinkingConfig: { includeThoughts: true } } },
+                copilot: { reasoning_effort: 'medium' },
+                litellm: { reasoning_effort: 'medium' },
+            };
+        }
+        if (maxTokens) body.max_tokens = maxTokens;
+        return body;
+    }
+
+    // Read an OpenAI-style SSE stream, appending deltas as they arrive.
+    async function readStream(resp, onUsage) {
+        const reader = resp.body.getReader();
+        const decoder = new TextDecoder();
+        let buffer = '';
+        for (;;) {
+            const { done, value } = await reader.read();
+            if (done) break;
+            buffer += decoder.decode(value, { stream: true });
+            let split;
+            while ((split = buffer.indexOf('\n')) >= 0) {
+                const line = buffer.slice(0, split).trim();
+                buffer = buffer.slice(split + 1);
+                if (!line.startsWith('data:')) continue;
+                const data = line.slice(5).trim();
+                if (data === '[DONE]') return;
+                const chunk = JSON.parse(data);
+                if (chunk.error) throw new Error(chunk.error.message || 'Stream error');
+                if (chunk.usage) onUsage(chunk.usage);
+                const delta = (chunk.choices && chunk.choices[0] && chunk.choices[0].delta) || {};
+                if (delta.reasoning_content) appendReasoning(delta.reasoning_content);
+                if (delta.content) document.getElementById('result-content').textContent += delta.content;
+            }
+        }
+    }
+
+    function appendReasoning(text) {
+        document.getElementById('reasoning-card').classList.remove('hidden');
+        document.getElementById('result-reasoning').textContent += text;
+    }
+
+    // The usage entry is recorded once the response has finished, so poll
+    // briefly for it.
+    async function loadRequestLog(requestId) {
+        for (let attempt = 0; attempt < 10; attempt++) {
+            const resp = await GAUD.apiFetch('/ui/api/logs/requests/' + encodeURIComponent(requestId));
+            if (resp && resp.ok) return resp.json();
+            await new Promise(resolve => setTimeout(resolve, 300));
+        }
+        return null;
+    }
+
+    async function send() {
+        const body = buildRequest();
+        if (!body.model) { showAlert('Select a model'); return; }
+        if (!body.messages[body.messages.length - 1].content.trim()) { showAlert('Enter a prompt'); return; }
+
+        document.getElementById('playground-alert').className = 'alert hidden';
+        document.getElementById('reasoning-card').classList.add('hidden');
+        for (const id of ['result-content', 'result-reasoning']) setText(id, '');
+        for (const id of ['result-provider', 'result-tokens', 'result-cost', 'result-latency']) setText(id, '--');
+        const button = document.getElementById('send-btn');
+        button.disabled = true;
+
+        const start = performance.now();
+        let usage = null;
+        try {
+            const resp = await GAUD.apiFetch('/v1/chat/completions', {
+                method: 'POST',
+                body: JSON.stringify(body),
+            });
+            if (!resp) return;
+            if (!resp.ok) {
+                const err = await resp.json().catch(() => ({}));
+                showAlert((err.error && err.error.message) || ('Request failed: HTTP ' + resp.status));
+                return;
+            }
+            const requestId = resp.headers.get('x-request-id');
+            if (body.stream) {
+                await readStream(resp, u => { usage = u; });
+            } else {
+                const data = await resp.json();
+                const message = (data.choices && data.choices[0] && data.choices[0].message) || {};
+                if (message.reasoning_content) appendReasoning(message.reasoning_content);
+                setText('result-content', message.content || '');
+                usage = data.usage;
+            }
+            setText('result-latency', Math.round(performance.now() - start) + 'ms');
+            if (usage) {
+                setText('result-tokens', GAUD.formatNumber(usage.prompt_tokens || 0) + ' / ' +
+                    GAUD.formatNumber(usage.completion_tokens || 0));
+            }
+
+            const log = requestId ? await loadRequestLog(requestId) : null;
+            if (log) {
+                setText('result-provider', log.cache_hit ? 'cache' : log.provider);
+                setText('result-cost', GAUD.formatCost(log.cost));
+                setText('result-latency', log.latency_ms + 'ms');
+                if (!usage) {
+                    setText('result-tokens', GAUD.formatNumber(log.input_tokens) + ' / ' +
+                        GAUD.formatNumber(log.output_tokens));
+                }
+            }
+        } catch (err) {
+            showAlert(err.message || String(err));
+        } finally {
+            button.disabled = false;
+        }
+    }
+
+    loadModels();
+</script>
+{% endblock %}"#;
+
+/// Self-service account page template.
+pub const ACCOUNT: &str = r#"{% extends "layout" %}
+{% block title %}Account{% endblock %}
+{% block body %}
+<nav class="navbar">
+    <a class="navbar-brand" href="/ui/account"><span>gaud</span></a>
+    <div class="nav-links">
+        <a class="nav-link admin-only hidden" href="/ui/dashboard">Dashboard</a>
+        <a class="nav-link admin-only hidden" href="/ui/oauth">OAuth</a>
+        <a class="nav-link admin-only hidden" href="/ui/users">Users</a>
+        <a class="nav-link admin-only hidden" href="/ui/usage">Usage</a>
+        <a class="nav-link admin-only hidden" href="/ui/logs">Logs</a>
+        <a class="nav-link admin-only hidden" href="/ui/playground">Playground</a>
+        <a class="nav-link admin-only hidden" href="/ui/budgets">Budgets</a>
+        <a class="nav-link admin-only hidden" href="/ui/providers">Providers</a>
+        <a class="nav-link admin-only hidden" href="/ui/routing">Routing</a>
+        <a class="nav-link admin-only hidden" href="/ui/settings">Settings</a>
+        <a class="nav-link active" href="/ui/account">Account</a>
+    </div>
+    <div class="nav-user">
+        <span id="nav-username"></span>
+        <button class="logout-btn" onclick="GAUD.logout()">Logout</button>
+    </div>
+</nav>
+<div class="container">
+    <div class="page-header">
+        <h1>My Account</h1>
+        <p id="account-subtitle">Your budget, usage and API keys</p>
+    </div>
+
+    <div id="account-alert" class="alert hidden"></div>
+
+    <!-- Budget -->
+    <div class="card-grid mb-2">
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value success" id="budget-monthly">--</div>
+                <div class="stat-label">Remaining This Month</div>
+            </div>
+        </div>
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value info" id="budget-daily">--</div>
+                <div class="stat-label">Remaining Today</div>
+            </div>
+        </div>
+        <div class="card">
+            <div class="stat">
+                <div class="stat-value warning" id="budget-used">--</div>
+                <div class="stat-label">Spent This Month</div>
+            </div>
+        </div>
+    </div>
+
+    <!-- API keys -->
+    <div class="card mb-2">
+        <div class="card-header">API Keys <span class="text-muted" id="key-count"></span></div>
+        <div class="flex gap-2 flex-wrap items-center mb-2">
+            <div class="form-group" style="margin:0;flex:1;min-width:200px;">
+                <input class="form-input" id="new-key-label" placeholder="Label (e.g. laptop)">
+            </div>
+            <button class="btn btn-sm" id="create-key-btn" onclick="createKey()">Create Key</button>
+        </div>
+        <div id="new-key" class="alert alert-success hidden">
+            Copy this key now; it will not be shown again:
+            <div class="mono" id="new-key-value" style="word-break:break-all;margin-top:0.5rem;"></div>
+        </div>
+        <div class="table-wrap">
+            <table>
+                <thead>
+                    <tr>
+                        <th>Prefix</th>
+                        <th>Label</th>
+                        <th>Created</th>
+                        <th>Last Used</th>
+                        <th>Actions</th>
+                    </tr>
+                </thead>
+                <tbody id="key-table">
+                    <tr><td colspan="5" class="text-muted">Loading...</td></tr>
+                </tbody>
+            </table>
+        </div>
+    </div>
+
+    <!-- Two-factor authentication (admins) -->
+    <div class="card mb-2 admin-only hidden" id="totp-card">
+        <div class="card-header">Two-Factor Authentication</div>
+        <p class="text-secondary mb-2" id="totp-status">Loading...</p>
+        <div class="flex gap-2 flex-wrap items-center">
+            <button class="btn btn-sm" id="totp-enable-btn" onclick="beginTotp()">Enable</button>
+            <button class="btn btn-sm btn-danger hidden" id="totp-disable-btn" onclick="disableTotp()">Disable</button>
+        </div>
+        <div id="totp-enroll" class="hidden" style="margin-top:1rem;">
+            <p class="text-secondary">Add this secret to your authenticator app, then enter the code it shows.</p>
+            <div class="mono" id="totp-secret" style="word-break:break-all;margin:0.5rem 0;"></div>
+            <a class="mono" id="totp-link" style="font-size:0.75rem;word-break:break-all;"></a>
+            <div class="flex gap-2 flex-wrap items-center" style="margin-top:0.75rem;">
+                <input class="form-input mono" id="totp-confirm-code" placeholder="123456" style="max-width:160px;" autocomplete="one-time-code">
+                <button class="btn btn-sm btn-primary" onclick="confirmTotp()">Confirm</button>
+            </div>
+        </div>
+        <div id="totp-recovery" class="alert alert-success hidden" style="margin-top:1rem;">
+            Save these recovery codes now; each works once and they will not be shown again:
+            <div class="mono" id="totp-recovery-codes" style="margin-top:0.5rem;white-space:pre;"></div>
+        </div>
+    </div>
+
+    <!-- Usage -->
+    <div class="card">
+        <div class="card-header">Recent Usage</div>
+        <div class="table-wrap">
+            <table>
+                <thead>
+                    <tr>
+                        <th>Timestamp</th>
+                        <th>Provider</th>
+                        <th>Model</th>
+                        <th>In / Out</th>
+                        <th>Cost</th>
+                        <th>Status</th>
+                    </tr>
+                </thead>
+                <tbody id="usage-table">
+                    <tr><td colspan="6" class="text-muted">Loading...</td></tr>
+                </tbody>
+            </table>
+        </div>
+    </div>
+</div>
+{% endblock %}
+{% block scripts %}
+<script>
+
+    let maxKeys = 0;
+
+    function escapeHtml(s) {
+        return String(s).replace(/[&<>"]/g, c => ({'&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;'}[c]));
+    }
+
+    function showAlert(message, kind) {
+        const el = document.getElementById('account-alert');
+        el.className = 'alert alert-' + (kind || 'danger');
+        el.textContent = message;
+    }
+
+    async function loadProfile() {
+        const resp = await GAUD.apiFetch('/me');
+        if (!resp || !resp.ok) return;
+        const me = await resp.json();
+        maxKeys = me.max_keys;
+        document.getElementById('nav-username').textContent = me.user.name;
+        document.getElementById('account-subtitle').textContent =
+            me.user.name + ' (' + me.user.role + (me.user.team ? ', team ' + me.user.team : '') + ')';
+        if (me.user.role === 'admin') {
+            for (const el of document.querySelectorAll('.admin-only')) el.classList.remove('hidden');
+            loadTotp();
+        }
+        const b = me.budget;
+        document.getElementById('budget-monthly').textContent =
+            me.monthly_remaining != null ? GAUD.formatCost(me.monthly_remaining) : 'No limit';
+        document.getElementById('budget-daily').textContent =
+            me.daily_remaining != null ? GAUD.formatCost(me.daily_remaining) : 'No limit';
+        document.getElementById('budget-used').textContent = GAUD.formatCost(b ? b.monthly_used : 0);
+    }
+
+    async function loadKeys() {
+        const resp = await GAUD.apiFetch('/me/keys');
+        if (!resp || !resp.ok) return;
+        const keys = await resp.json();
+        let rows = '';
+        for (const k of keys) {
+            rows += '<tr>' +
+                '<td class="mono">' + escapeHtml(k.key_prefix) + '...</td>' +
+                '<td>' + escapeHtml(k.label || '--') + '</td>' +
+                '<td class="mono" style="font-size:0.75rem;">' + k.created_at + '</td>' +
+                '<td class="mono" style="font-size:0.75rem;">' + (k.last_used ? GAUD.timeAgo(k.last_used) : 'Never') + '</td>' +
+                '<td><button class="btn btn-sm btn-danger" onclick="revokeKey(\'' + k.id + '\')">Revoke</button></td></tr>';
+        }
+        document.getElementById('key-table').innerHTML =
+            rows || '<tr><td colspan="5" class="text-muted">No API keys</td></tr>';
+        document.getElementById('key-count').textContent = '(' + keys.length + ' of ' + maxKeys + ')';
+        document.getElementById('create-key-btn').disabled = keys.length >= maxKeys;
+    }
+
+    async function createKey() {
+        const label = document.getElementById('new-key-label').value.trim();
+        const resp = await GAUD.apiFetchWithTotp('/me/keys', {
+            method: 'POST',
+            body: JSON.stringify({ label }),
+        });
+        if (!resp) return;
+        const data = await resp.json();
+        if (!resp.ok) {
+            showAlert((data.error && data.error.message) || 'Failed to create key');
+            return;
+        }
+        document.getElementById('account-alert').className = 'alert hidden';
+        document.getElementById('new-key-label').value = '';
+        document.getElementById('new-key-value').textContent = data.plaintext;
+        document.getElementById('new-key').classList.remove('hidden');
+        loadKeys();
+    }
+
+    async function loadTotp() {
+        const resp = await GAUD.apiFetch('/me/totp');
+        if (!resp || !resp.ok) return;
+        const status = await resp.json();
+        document.getElementById('totp-status').textContent = status.enabled
+            ? 'Enabled. ' + status.recovery_codes_remaining + ' recovery codes left. Login and API key creation ask for a code.'
+            : 'Not enabled. Protect your admin account with an authenticator app.';
+        document.getElementById('totp-enable-btn').classList.toggle('hidden', status.enabled);
+        document.getElementById('totp-disable-btn').classList.toggle('hidden', !status.enabled);
+    }
+
+    async function beginTotp() {
+        const resp = await GAUD.apiFetch('/me/totp/enroll', { method: 'POST' });
+        if (!resp) return;
+        const data = await resp.json();
+        if (!resp.ok) {
+            showAlert((data.error && data.error.message) || 'Failed to start enrollment');
+            return;
+        }
+        document.getElementById('totp-secret').textContent = data.secret;
+        const link = document.getElementById('totp-link');
+        link.href = data.otpauth_url;
+        link.textContent = data.otpauth_url;
+        document.getElementById('totp-enroll').classList.remove('hidden');
+        document.getElementById('totp-confirm-code').focus();
+    }
+
+    async function confirmTotp() {
+        const code = document.getElementById('totp-confirm-code').value.trim();
+        const resp = await GAUD.apiFetch('/me/totp/confirm', {
+            method: 'POST',
+            body: JSON.stringify({ code }),
+        });
+        if (!resp) return;
+        const data = await resp.json();
+        if (!resp.ok) {
+            showAlert((data.error && data.error.message) || 'Failed to confirm code');
+            return;
+        }
+        document.getElementById('account-alert').className = 'alert hidden';
+        document.getElementById('totp-enroll').classList.add('hidden');
+        document.getElementById('totp-recovery-codes').textContent = data.recovery_codes.join('\n');
+        document.getElementById('totp-recovery').classList.remove('hidden');
+        loadTotp();
+    }
+
+    async function disableTotp() {
+        if (!confirm('Disable two-factor authentication?')) return;
+        const resp = await GAUD.apiFetchWithTotp('/me/totp', { method: 'DELETE' });
+        if (resp && !resp.ok) {
+            const data = await resp.json().catch(() => ({}));
+            showAlert((data.error && data.error.message) || 'Failed to disable two-factor authentication');
+            return;
+        }
+        document.getElementById('totp-recovery').classList.add('hidden');
+        loadTotp();
+    }
+
+    async function revokeKey(id) {
+        if (!confirm('Revoke this key? Clients using it will stop working immediately.')) return;
+        const resp = await GAUD.apiFetch('/me/keys/' + encodeURIComponent(id), { method: 'DELETE' });
+        if (resp && !resp.ok) {
+            const data = await resp.json().catch(() => ({}));
+            showAlert((data.error && data.error.message) || 'Failed to revoke key');
+            return;
+        }
+        loadKeys();
+    }
+
+    async function loadUsage() {
+        const resp = await GAUD.apiFetch('/me/usage?per_page=25');
+        if (!resp || !resp.ok) return;
+        const data = await resp.json();
+        let rows = '';
+        for (const e of data.data || []) {
+            const statusBadge = e.status === 'success'
+                ? '<span class="badge badge-success">OK</span>'
+                : '<span class="badge badge-danger">' + escapeHtml(e.status || 'error') + '</span>';
+            rows += '<tr>' +
+                '<td class="mono" style="font-size:0.75rem;">' + e.created_at + '</td>' +
+                '<td>' + escapeHtml(e.provider || '--') + '</td>' +
+                '<td class="mono" style="font-size:0.75rem;">' + escapeHtml(e.model || '--') + '</td>' +
+                '<td class="mono">' + GAUD.formatNumber(e.input_tokens || 0) + ' / ' + GAUD.formatNumber(e.output_tokens || 0) + '</td>' +
+                '<td class="mono">' + GAUD.formatCost(e.cost || 0) + '</td>' +
+                '<td>' + statusBadge + '</td></tr>';
+        }
+        document.getElementById('usage-table').innerHTML =
+            rows || '<tr><td colspan="6" class="text-muted">No usage yet</td></tr>';
+    }
+
+    loadProfile().then(loadKeys);
+    loadUsage();
+</script>
+{% endblock %}"#;
+
+/// Budget management page template.
+pub const BUDGETS: &str = r#"{% extends "layout" %}
+{% block title %}Budgets{% endblock %}
+{% block body %}
+<nav class="navbar">
+    <a class="navbar-brand" href="/ui/dashboard"><span>gaud</span></a>
+    <div class="nav-links">
+        <a class="nav-link" href="/ui/dashboard">Dashboard</a>
+        <a class="nav-link" href="/ui/oauth">OAuth</a>
+        <a class="nav-link" href="/ui/users">Users</a>
+        <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
+        <a class="nav-link active" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
+        <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
+    </div>
+    <div class="nav-user">
+        <span id="nav-username"></span>
+        <button class="logout-btn" onclick="GAUD.logout()">Logout</button>
+    </div>
+</nav>
+<div class="container">
+    <div class="page-header">
+        <h1>Budget Management</h1>
+        <p>Configure spending limits per user</p>
+    </div>
+
+    <div id="budget-alert" class="alert hidden"></div>
+
+    <div class="card">
+        <div class="table-wrap">
+            <table>
+                <thead>
+                    <tr>
+                        <th>User</th>
+                        <th>Monthly Limit</th>
+                        <th>Monthly Used</th>
+                        <th>Daily Limit</th>
+                        <th>Daily Used</th>
+                        <th>Usage</th>
+                        <th>Actions</th>
+                    </tr>
+                </thead>
+                <tbody id="budgets-table">
+                    <tr><td colspan="7" class="text-muted">Loading...</td></tr>
+                </tbody>
+            </table>
+        </div>
+    </div>
+
+    <!-- Edit budget modal (inline) -->
+    <div id="edit-budget-form" class="card mt-2 hidden">
+        <div class="card-header">Edit Budget</div>
+        <form onsubmit="saveBudget(event)">
+            <input type="hidden" id="edit-user-id">
+            <p class="mb-1"><strong id="edit-user-name"></strong></p>
+            <div class="flex gap-2 flex-wrap">
+                <div class="form-group" style="flex:1;min-width:200px;">
+                    <label class="form-label" for="edit-monthly-limit">Monthly Limit ({{ currency | default("USD") }})</label>
+                    <input class="form-input" type="number" step="0.01" id="edit-monthly-limit" placeholder="No limit">
+                </div>
+                <div class="form-group" style="flex:1;min-width:200px;">
+                    <label class="form-label" for="edit-daily-limit">Daily Limit ({{ currency | default("USD") }})</label>
+                    <input class="form-input" type="number" step="0.01" id="edit-daily-limit" placeholder="No limit">
+                </div>
+            </div>
+            <div class="flex gap-1">
+                <button type="submit" class="btn btn-primary btn-sm">Save</button>
+                <button type="button" class="btn btn-sm" onclick="hideEditBudget()">Cancel</button>
+            </div>
+        </form>
+    </div>
+</div>
+{% endblock %}
+{% block scripts %}
+<script>
+
+    function hideEditBudget() { document.getElementById('edit-budget-form').classList.add('hidden'); }
+
+    function showEditBudget(userId, userName, monthlyLimit, dailyLimit) {
+        document.getElementById('edit-user-id').value = userId;
+        document.getElementById('edit-user-name').textContent = userName;
+        document.getElementById('edit-monthly-limit').value = monthlyLimit || '';
+        document.getElementById('edit-daily-limit').value = dailyLimit || '';
+        document.getElementById('edit-budget-form').classList.remove('hidden');
+    }
+
+    async function loadBudgets() {
+        try {
+            const resp = await GAUD.apiFetch('/admin/budgets');
+            if (!resp || !resp.ok) return;
+            const budgets = await resp.json();
+
+            let rows = '';
+            for (const b of budgets) {
+                const monthlyLimit = b.monthly_limit != null ? GAUD.formatCost(b.monthly_limit, 2) : 'None';
+                const dailyLimit = b.daily_limit != null ? GAUD.formatCost(b.daily_limit, 2) : 'None';
+                const monthlyUsed = GAUD.formatCost(b.monthly_used || 0, 2);
+                const dailyUsed = GAUD.formatCost(b.daily_used || 0, 2);
+
+                let pct = 0;
+                let barClass = 'success';
+                if (b.monthly_limit && b.monthly_limit > 0) {
+                    pct = Math.min(100, ((b.monthly_used || 0) / b.monthly_limit) * 100);
+                    if (pct >= 90) barClass = 'danger';
+                    else if (pct >= 70) barClass = 'warning';
+                }
+
+                const progressBar = b.monthly_limit
+                    ? '<div class="progress"><div class="progress-bar ' + barClass + '" style="width:' + pct.toFixed(0) + '%;"></div></div>'
+                    : '<span class="text-muted" style="font-size:0.75rem;">No limit set</span>';
+
+                rows += '<tr>' +
+                    '<td><strong>' + (b.user_name || b.user_id) + '</strong></td>' +
+                    '<td class="mono">' + monthlyLimit + '</td>' +
+                    '<td class="mono">' + monthlyUsed + '</td>' +
+                    '<td class="mono">' + dailyLimit + '</td>' +
+                    '<td class="mono">' + dailyUsed + '</td>' +
+                    '<td style="min-width:120px;">' + progressBar + '</td>' +
+                    '<td><button class="btn btn-sm" onclick="showEditBudget(\'' +
+                        b.user_id + "','" + (b.user_name || b.user_id) + "'," +
+                        (b.monthly_limit || 'null') + ',' + (b.daily_limit || 'null') +
+                    ')">Edit</button></td></tr>';
+            }
+            document.getElementById('budgets-table').innerHTML =
+                rows || '<tr><td colspan="7" class="text-muted">No budgets configured. Budgets are created automatically when users make requests.</td></tr>';
+        } catch (err) {
+            console.error('Failed to load budgets:', err);
+        }
+    }
+
+    async function saveBudget(e) {
+        e.preventDefault();
+        const userId = document.getElementById('edit-user-id').value;
+        const monthlyLimit = document.getElementById('edit-monthly-limit').value;
+        const dailyLimit = document.getElementById('edit-daily-limit').value;
+        const alertEl = document.getElementById('budget-alert');
+
+        try {
+            const resp = await GAUD.apiFetch('/admin/budgets/' + userId, {
+                method: 'PUT',
+                body: JSON.stringify({
+                    monthly_limit: monthlyLimit ? parseFloat(monthlyLimit) : null,
+                    daily_limit: dailyLimit ? parseFloat(dailyLimit) : null,
+                }),
+            });
+            if (!resp) return;
+            if (resp.ok) {
+                hideEditBudget();
+                alertEl.className = 'alert alert-success';
+                alertEl.textContent = 'Budget updated successfully.';
+                alertEl.classList.remove('hidden');
+                setTimeout(() => alertEl.classList.add('hidden'), 3000);
+                loadBudgets();
+            } else {
+                const data = await resp.json();
+                alertEl.className = 'alert alert-danger';
+                alertEl.textContent = data.error?.message || 'Failed to save budget.';
+                alertEl.classList.remove('hidden');
+            }
+        } catch (err) {
+            alertEl.className = 'alert alert-danger';
+            alertEl.textContent = err.message;
+            alertEl.classList.remove('hidden');
+        }
+    }
+
+    loadBudgets();
+</script>
+{% endblock %}"#;
+
 /// Settings page template.
 pub const SETTINGS: &str = r#"{% extends "layout" %}
 {% block title %}Settings{% endblock %}
@@ -1434,8 +2515,13 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
         <a class="nav-link" href="/ui/oauth">OAuth</a>
         <a class="nav-link" href="/ui/users">Users</a>
         <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
         <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
         <a class="nav-link active" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
     </div>
     <div class="nav-user">
         <span id="nav-username"></span>
@@ -1445,7 +2531,7 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
 <div class="container">
     <div class="page-header">
         <h1>Settings</h1>
-        <p>View and edit server configuration. Changes require a restart to take effect.</p>
+        <p>View and edit server configuration. Settings marked <span class="badge badge-info">Live</span> apply at once and are stored in the database; the rest are saved to the config file and need a restart.</p>
     </div>
 
     <div id="settings-alert" class="alert hidden"></div>
@@ -1460,7 +2546,6 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
 {% endblock %}
 {% block scripts %}
 <script>
-    if (!GAUD.requireAuth()) throw new Error('Not authenticated');
 
     const SECTION_ORDER = ['Server', 'Database', 'Authentication', 'Providers', 'LiteLLM', 'Budget', 'Logging'];
     let allSettings = [];
@@ -1538,6 +2623,15 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
             'Set by ' + setting.env_var + '</span>';
     }
 
+    function renderRuntimeBadge(setting) {
+        if (!setting.runtime) return '';
+        const title = setting.stored
+            ? 'Applies at once. Changed at runtime: the stored value overrides the config file.'
+            : 'Applies at once.';
+        return '<span class="badge badge-info" style="margin-left:0.5rem;" title="' + title + '">' +
+            (setting.stored ? 'Live (changed)' : 'Live') + '</span>';
+    }
+
     function renderOverrideNote(setting) {
         if (!setting.overridden) return '';
         return '<p class="text-muted" style="font-size:0.75rem;margin-top:0.25rem;">' +
@@ -1569,7 +2663,7 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
             for (const s of items) {
                 html += '<div class="form-group">';
                 html += '<label class="form-label" for="setting-' + s.key.replace(/\./g, '-') + '">' +
-                    s.label + renderOverrideBadge(s) + '</label>';
+                    s.label + renderRuntimeBadge(s) + renderOverrideBadge(s) + '</label>';
                 html += '<div style="font-size:0.75rem;color:var(--text-muted);margin-bottom:0.25rem;">' +
                     '<code>' + s.key + '</code>' +
                     ' &middot; env: <code>' + s.env_var + '</code></div>';
@@ -1614,6 +2708,7 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
 
         let errorCount = 0;
         let successCount = 0;
+        let restartRequired = false;
 
         for (const s of changed) {
             const value = getInputValue(s);
@@ -1626,7 +2721,9 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
                 });
                 if (!resp) return;
                 if (resp.ok) {
+                    const data = await resp.json();
                     successCount++;
+                    restartRequired = restartRequired || data.restart_required;
                     changedKeys.delete(s.key);
                 } else {
                     const data = await resp.json();
@@ -1642,11 +2739,10 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
 
         if (successCount > 0 && errorCount === 0) {
             showAlert('success', successCount + ' setting(s) in ' + section + ' saved successfully.');
-            showRestartBanner();
         } else if (successCount > 0 && errorCount > 0) {
             showAlert('warning', successCount + ' saved, ' + errorCount + ' failed in ' + section + '.');
-            showRestartBanner();
         }
+        if (restartRequired) showRestartBanner();
     }
 
     async function loadSettings() {
@@ -1669,3 +2765,565 @@ pub const SETTINGS: &str = r#"{% extends "layout" %}
     loadSettings();
 </script>
 {% endblock %}"#;
+
+pub const PROVIDERS: &str = r#"{% extends "layout" %}
+{% block title %}Providers{% endblock %}
+{% block body %}
+<nav class="navbar">
+    <a class="navbar-brand" href="/ui/dashboard"><span>gaud</span></a>
+    <div class="nav-links">
+        <a class="nav-link" href="/ui/dashboard">Dashboard</a>
+        <a class="nav-link" href="/ui/oauth">OAuth</a>
+        <a class="nav-link" href="/ui/users">Users</a>
+        <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
+        <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link active" href="/ui/providers">Providers</a>
+        <a class="nav-link" href="/ui/routing">Routing</a>
+        <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
+    </div>
+    <div class="nav-user">
+        <span id="nav-username"></span>
+        <button class="logout-btn" onclick="GAUD.logout()">Logout</button>
+    </div>
+</nav>
+<div class="container">
+    <div class="page-header">
+        <h1>Providers</h1>
+        <p>Edit provider settings and virtual models. Changes are saved to the config file and apply at once; OAuth client settings take effect after a restart.</p>
+    </div>
+
+    <div id="providers-alert" class="alert hidden"></div>
+    <div id="restart-banner" class="alert alert-warning hidden" style="display:none;">
+        <strong>Restart required.</strong> Provider settings have been saved. Restart the server for them to take effect.
+    </div>
+
+    <div id="providers-container">
+        <div class="card"><p class="text-muted">Loading providers...</p></div>
+    </div>
+</div>
+{% endblock %}
+{% block scripts %}
+<script>
+
+    const LABELS = {
+        claude: 'Claude',
+        gemini: 'Gemini',
+        copilot: 'GitHub Copilot',
+        kiro: 'Kiro',
+        litellm: 'LiteLLM',
+        virtual_models: 'Virtual Models',
+    };
+    const MASKED = '********';
+    let overridden = {};
+
+    function escapeHtml(s) {
+        return String(s).replace(/[&<>"]/g, c => ({'&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;'}[c]));
+    }
+
+    function showAlert(type, message) {
+        const el = document.getElementById('providers-alert');
+        el.className = 'alert alert-' + type;
+        el.textContent = message;
+        el.classList.remove('hidden');
+        setTimeout(() => el.classList.add('hidden'), 8000);
+    }
+
+    function showRestartBanner() {
+        const el = document.getElementById('restart-banner');
+        el.style.display = '';
+        el.classList.remove('hidden');
+    }
+
+    function isScalarList(v) {
+        return Array.isArray(v) && v.every(x => typeof x === 'string' || typeof x === 'number');
+    }
+
+    // One input per top-level field: scalars and lists inline, nested
+    // tables (http, quota, profiles) as JSON.
+    function renderField(section, key, value) {
+        const envVar = overridden['providers.' + section + '.' + key];
+        const disabled = envVar ? ' disabled' : '';
+        const attrs = ' data-section="' + section + '" data-key="' + escapeHtml(key) + '"' + disabled;
+        let input;
+        if (typeof value === 'boolean') {
+            input = '<input type="checkbox" data-kind="bool"' + attrs + (value ? ' checked' : '') +
+                ' style="width:18px;height:18px;accent-color:var(--accent);">';
+        } else if (typeof value === 'number') {
+            input = '<input class="form-input mono" type="number" step="any" data-kind="number"' + attrs +
+                ' value="' + value + '">';
+        } else if (typeof value === 'string') {
+            const type = value === MASKED ? 'password' : 'text';
+            input = '<input class="form-input mono" type="' + type + '" data-kind="string"' + attrs +
+                ' value="' + escapeHtml(value) + '">';
+        } else if (isScalarList(value)) {
+            const kind = value.length && typeof value[0] === 'number' ? 'number-list' : 'list';
+            input = '<input class="form-input mono" type="text" data-kind="' + kind + '"' + attrs +
+                ' value="' + escapeHtml(value.join(', ')) + '" placeholder="Comma-separated">';
+        } else {
+            input = '<textarea class="form-input mono" rows="3" data-kind="json"' + attrs + '>' +
+                escapeHtml(JSON.stringify(value, null, 2)) + '</textarea>';
+        }
+        const badge = envVar
+            ? '<span class="badge badge-warning" style="margin-left:0.5rem;" title="Unset this variable and restart to edit">Set by ' + envVar + '</span>'
+            : '';
+        return '<div class="form-group"><label class="form-label"><code>' + escapeHtml(key) + '</code>' + badge + '</label>' + input + '</div>';
+    }
+
+    function renderVirtualModels(models) {
+        let html = '<div class="table-wrap"><table><thead><tr><th>Name</th><th>Models</th><th>Weights</th><th></th></tr></thead><tbody id="vm-rows">';
+        for (const [name, vm] of Object.entries(models || {})) {
+            html += virtualModelRow(name, vm.models || [], vm.weights || []);
+        }
+        html += '</tbody></table></div>' +
+            '<button class="btn btn-sm" onclick="addVirtualModel()">Add virtual model</button>';
+        return html;
+    }
+
+    function virtualModelRow(name, models, weights) {
+        return '<tr class="vm-row">' +
+            '<td><input class="form-input mono vm-name" value="' + escapeHtml(name) + '" placeholder="smart"></td>' +
+            '<td><input class="form-input mono vm-models" value="' + escapeHtml(models.join(', ')) + '" placeholder="claude-sonnet-4, gpt-4o"></td>' +
+            '<td><input class="form-input mono vm-weights" value="' + escapeHtml(weights.join(', ')) + '" placeholder="Optional, e.g. 3, 1"></td>' +
+            '<td><button class="btn btn-sm btn-danger" onclick="this.closest(\'tr\').remove()">Remove</button></td></tr>';
+    }
+
+    function addVirtualModel() {
+        document.getElementById('vm-rows').insertAdjacentHTML('beforeend', virtualModelRow('', [], []));
+    }
+
+    function renderSection(section) {
+        const name = section.name;
+        const settings = section.settings;
+        let html = '<div class="card mb-2" id="section-' + name + '">';
+        html += '<div class="card-header flex justify-between items-center"><span>' + LABELS[name] + '</span><span>';
+        if (name !== 'virtual_models' && settings !== null) {
+            html += '<button class="btn btn-sm btn-danger" onclick="removeProvider(\'' + name + '\')">Remove</button> ';
+        }
+        html += '<button class="btn btn-sm btn-primary" onclick="saveSection(\'' + name + '\')">Save</button></span></div>';
+
+        if (name === 'virtual_models') {
+            html += renderVirtualModels(settings);
+        } else if (settings === null) {
+            html += '<p class="text-muted">Not configured. Enter its settings as JSON and save to add it.</p>' +
+                '<textarea class="form-input mono" rows="4" data-section="' + name + '" data-kind="section">{}</textarea>';
+        } else {
+            for (const [key, value] of Object.entries(settings)) {
+                html += renderField(name, key, value);
+            }
+            html += '<details><summary class="text-muted" style="cursor:pointer;font-size:0.875rem;">Settings not listed</summary>' +
+                '<p class="text-muted" style="font-size:0.75rem;">Fields to add, as JSON, e.g. <code>{"max_tokens": 8192}</code>.</p>' +
+                '<textarea class="form-input mono" rows="2" data-section="' + name + '" data-kind="extra">{}</textarea></details>';
+        }
+        return html + '</div>';
+    }
+
+    function splitList(text) {
+        return text.split(',').map(s => s.trim()).filter(s => s !== '');
+    }
+
+    function collectVirtualModels() {
+        const models = {};
+        for (const row of document.querySelectorAll('.vm-row')) {
+            const name = row.querySelector('.vm-name').value.trim();
+            if (!name) continue;
+            const vm = { models: splitList(row.querySelector('.vm-models').value) };
+            const weights = splitList(row.querySelector('.vm-weights').value).map(Number);
+            if (weights.length) vm.weights = weights;
+            models[name] = vm;
+        }
+        return models;
+    }
+
+    function collectSection(name) {
+        if (name === 'virtual_models') return collectVirtualModels();
+        let settings = {};
+        for (const el of document.querySelectorAll('[data-section="' + name + '"]')) {
+            const key = el.dataset.key;
+            switch (el.dataset.kind) {
+                case 'bool': settings[key] = el.checked; break;
+                case 'number': settings[key] = el.value === '' ? null : Number(el.value); break;
+                case 'string': settings[key] = el.value; break;
+                case 'list': settings[key] = splitList(el.value); break;
+                case 'number-list': settings[key] = splitList(el.value).map(Number); break;
+                case 'json': settings[key] = JSON.parse(el.value); break;
+                case 'section': settings = JSON.parse(el.value); break;
+                case 'extra': Object.assign(settings, JSON.parse(el.value)); break;
+            }
+        }
+        return settings;
+    }
+
+    async function putSection(name, body) {
+        const resp = await GAUD.apiFetch('/admin/providers/config/' + name, {
+            method: 'PUT',
+            body: JSON.stringify(body),
+        });
+        if (!resp) return;
+        const data = await resp.json();
+        if (!resp.ok) {
+            showAlert('danger', (data.error && data.error.message) ? data.error.message : 'Failed to save ' + LABELS[name]);
+            return;
+        }
+        showAlert('success', data.message);
+        if (data.restart_required) showRestartBanner();
+        loadProviders();
+    }
+
+    async function saveSection(name) {
+        let body;
+        try {
+            body = collectSection(name);
+        } catch (err) {
+            showAlert('danger', 'Invalid JSON in ' + LABELS[name] + ': ' + err.message);
+            return;
+        }
+        await putSection(name, body);
+    }
+
+    async function removeProvider(name) {
+        if (!confirm('Remove ' + LABELS[name] + ' from the configuration?')) return;
+        await putSection(name, null);
+    }
+
+    async function loadProviders() {
+        try {
+            const resp = await GAUD.apiFetch('/admin/providers/config');
+            if (!resp || !resp.ok) {
+                if (resp && resp.status === 403) {
+                    showAlert('danger', 'Admin access required to edit providers.');
+                }
+                return;
+            }
+            const data = await resp.json();
+            overridden = data.overridden;
+            document.getElementById('providers-container').innerHTML =
+                data.sections.map(renderSection).join('');
+        } catch (err) {
+            console.error('Failed to load providers:', err);
+            showAlert('danger', 'Failed to load providers: ' + err.message);
+        }
+    }
+
+    loadProviders();
+</script>
+{% endblock %}"#;
+
+pub const ROUTING: &str = r#"{% extends "layout" %}
+{% block title %}Routing{% endblock %}
+{% block body %}
+<nav class="navbar">
+    <a class="navbar-brand" href="/ui/dashboard"><span>gaud</span></a>
+    <div class="nav-links">
+        <a class="nav-link" href="/ui/dashboard">Dashboard</a>
+        <a class="nav-link" href="/ui/oauth">OAuth</a>
+        <a class="nav-link" href="/ui/users">Users</a>
+        <a class="nav-link" href="/ui/usage">Usage</a>
+        <a class="nav-link" href="/ui/logs">Logs</a>
+        <a class="nav-link" href="/ui/playground">Playground</a>
+        <a class="nav-link" href="/ui/budgets">Budgets</a>
+        <a class="nav-link" href="/ui/providers">Providers</a>
+        <a class="nav-link active" href="/ui/routing">Routing</a>
+        <a class="nav-link" href="/ui/settings">Settings</a>
+        <a class="nav-link" href="/ui/account">Account</a>
+    </div>
+    <div class="nav-user">
+        <span id="nav-username"></span>
+        <button class="logout-btn" onclick="GAUD.logout()">Logout</button>
+    </div>
+</nav>
+<div class="container">
+    <div class="page-header">
+        <h1>Routing</h1>
+        <p>Routing rules are tried in order and the first match wins. Saved rules apply at once and are written to the config file.</p>
+    </div>
+
+    <div id="routing-alert" class="alert hidden"></div>
+
+    <div class="card mb-2">
+        <div class="card-header flex justify-between items-center">
+            <span>Rules <span id="unsaved" class="badge badge-warning hidden">Unsaved changes</span></span>
+            <span>
+                <button class="btn btn-sm" onclick="editRule(-1)">Add rule</button>
+                <button class="btn btn-sm btn-primary" onclick="saveRules()">Save rules</button>
+            </span>
+        </div>
+        <div class="table-wrap">
+            <table>
+                <thead><tr><th>#</th><th>Name</th><th>Matches</th><th>Decides</th><th></th></tr></thead>
+                <tbody id="rules-body"><tr><td colspan="5" class="text-muted">Loading rules...</td></tr></tbody>
+            </table>
+        </div>
+    </div>
+
+    <div class="card mb-2 hidden" id="rule-editor">
+        <div class="card-header" id="rule-editor-title">Rule</div>
+        <div class="card-grid">
+            <div class="form-group"><label class="form-label">Name</label><input class="form-input mono" id="rule-name"></div>
+            <div class="form-group"><label class="form-label">Model pattern</label><input class="form-input mono" id="rule-model" placeholder="claude-*"></div>
+            <div class="form-group"><label class="form-label">User pattern (name or ID)</label><input class="form-input mono" id="rule-user"></div>
+            <div class="form-group"><label class="form-label">Team pattern</label><input class="form-input mono" id="rule-team"></div>
+            <div class="form-group"><label class="form-label">Min input characters</label><input class="form-input mono" type="number" min="0" id="rule-min"></div>
+            <div class="form-group"><label class="form-label">Max input characters</label><input class="form-input mono" type="number" min="0" id="rule-max"></div>
+            <div class="form-group"><label class="form-label">Hours (UTC)</label><input class="form-input mono" id="rule-hours" placeholder="22-6"></div>
+            <div class="form-group"><label class="form-label">Providers to try first</label><input class="form-input mono" id="rule-providers" placeholder="Comma-separated"></div>
+            <div class="form-group"><label class="form-label">Response cache</label>
+                <select class="form-input" id="rule-cache">
+                    <option value="">Default</option>
+                    <option value="true">Use</option>
+                    <option value="false">Bypass</option>
+                </select>
+            </div>
+            <div class="form-group"><label class="form-label">Max tokens cap</label><input class="form-input mono" type="number" min="1" id="rule-max-tokens"></div>
+        </div>
+        <div class="flex gap-1">
+            <button class="btn btn-primary" onclick="applyRule()">Apply</button>
+            <button class="btn" onclick="closeEditor()">Cancel</button>
+        </div>
+    </div>
+
+    <div class="card">
+        <div class="card-header">Which provider would serve this?</div>
+        <div class="card-grid">
+            <div class="form-group"><label class="form-label">Model</label><input class="form-input mono" id="sim-model" list="sim-models" placeholder="claude-sonnet-4"><datalist id="sim-models"></datalist></div>
+            <div class="form-group"><label class="form-label">User (name or ID; you if empty)</label><input class="form-input mono" id="sim-user"></div>
+            <div class="form-group"><label class="form-label">Team (the user's if empty)</label><input class="form-input mono" id="sim-team"></div>
+            <div class="form-group"><label class="form-label">Hour (UTC; now if empty)</label><input class="form-input mono" type="number" min="0" max="23" id="sim-hour"></div>
+            <div class="form-group"><label class="form-label">Priority</label>
+                <select class="form-input" id="sim-priority">
+                    <option value="default">Default</option>
+                    <option value="interactive">Interactive</option>
+                    <option value="batch">Batch</option>
+                </select>
+            </div>
+            <div class="form-group"><label class="form-label">Input characters (prompt length if empty)</label><input class="form-input mono" type="number" min="0" id="sim-chars"></div>
+        </div>
+        <div class="form-group"><label class="form-label">Prompt</label><textarea class="form-input" rows="3" id="sim-prompt"></textarea></div>
+        <div class="flex gap-1 items-center">
+            <button class="btn btn-primary" onclick="simulate()">Test</button>
+            <label class="text-muted" style="font-size:0.875rem;"><input type="checkbox" id="sim-unsaved" checked> Use the rules above, including unsaved changes</label>
+        </div>
+        <div id="sim-result" class="mt-2"></div>
+    </div>
+</div>
+{% endblock %}
+{% block scripts %}
+<script>
+
+    let rules = [];
+    let editing = -1;
+
+    function escapeHtml(s) {
+        return String(s).replace(/[&<>"]/g, c => ({'&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;'}[c]));
+    }
+
+    function showAlert(type, message) {
+        const el = document.getElementById('routing-alert');
+        el.className = 'alert alert-' + type;
+        el.textContent = message;
+        el.classList.remove('hidden');
+        setTimeout(() => el.classList.add('hidden'), 8000);
+    }
+
+    function markUnsaved(unsaved) {
+        document.getElementById('unsaved').classList.toggle('hidden', !unsaved);
+    }
+
+    function describeConditions(r) {
+        const parts = [];
+        if (r.model) parts.push('model ' + r.model);
+        if (r.user) parts.push('user ' + r.user);
+        if (r.team) parts.push('team ' + r.team);
+        if (r.min_input_chars != null) parts.push('≥ ' + r.min_input_chars + ' chars');
+        if (r.max_input_chars != null) parts.push('≤ ' + r.max_input_chars + ' chars');
+        if (r.hours_utc) parts.push(r.hours_utc + ' UTC');
+        return parts.length ? escapeHtml(parts.join(', ')) : '<span class="text-muted">everything</span>';
+    }
+
+    function describeDecisions(r) {
+        const parts = [];
+        if (r.providers && r.providers.length) parts.push('providers ' + r.providers.join(' → '));
+        if (r.cache === false) parts.push('no cache');
+        if (r.cache === true) parts.push('cache');
+        if (r.max_tokens != null) parts.push('max_tokens ≤ ' + r.max_tokens);
+        return escapeHtml(parts.join(', '));
+    }
+
+    function renderRules() {
+        const body = document.getElementById('rules-body');
+        if (!rules.length) {
+            body.innerHTML = '<tr><td colspan="5" class="text-muted">No routing rules. Requests follow the routing strategy.</td></tr>';
+            return;
+        }
+        body.innerHTML = rules.map((r, i) =>
+            '<tr><td>' + (i + 1) + '</td>' +
+            '<td class="mono">' + escapeHtml(r.name) + '</td>' +
+            '<td>' + describeConditions(r) + '</td>' +
+            '<td>' + describeDecisions(r) + '</td>' +
+            '<td style="white-space:nowrap;">' +
+            '<button class="btn btn-sm" onclick="moveRule(' + i + ', -1)"' + (i === 0 ? ' disabled' : '') + '>&uarr;</button> ' +
+            '<button class="btn btn-sm" onclick="moveRule(' + i + ', 1)"' + (i === rules.length - 1 ? ' disabled' : '') + '>&darr;</button> ' +
+            '<button class="btn btn-sm" onclick="editRule(' + i + ')">Edit</button> ' +
+            '<button class="btn btn-sm btn-danger" onclick="deleteRule(' + i + ')">Delete</button></td></tr>'
+        ).join('');
+    }
+
+    function moveRule(i, delta) {
+        const j = i + delta;
+        [rules[i], rules[j]] = [rules[j], rules[i]];
+        markUnsaved(true);
+        renderRules();
+    }
+
+    function deleteRule(i) {
+        if (!confirm('Delete rule ' + rules[i].name + '?')) return;
+        rules.splice(i, 1);
+        markUnsaved(true);
+        renderRules();
+    }
+
+    function setField(id, value) {
+        document.getElementById(id).value = value == null ? '' : value;
+    }
+
+    function editRule(i) {
+        editing = i;
+        const r = i >= 0 ? rules[i] : {};
+        document.getElementById('rule-editor-title').textContent = i >= 0 ? 'Edit rule ' + r.name : 'New rule';
+        setField('rule-name', r.name);
+        setField('rule-model', r.model);
+        setField('rule-user', r.user);
+        setField('rule-team', r.team);
+        setField('rule-min', r.min_input_chars);
+        setField('rule-max', r.max_input_chars);
+        setField('rule-hours', r.hours_utc);
+        setField('rule-providers', (r.providers || []).join(', '));
+        setField('rule-cache', r.cache == null ? '' : String(r.cache));
+        setField('rule-max-tokens', r.max_tokens);
+        document.getElementById('rule-editor').classList.remove('hidden');
+    }
+
+    function closeEditor() {
+        document.getElementById('rule-editor').classList.add('hidden');
+    }
+
+    function readRule() {
+        const text = id => document.getElementById(id).value.trim();
+        const number = id => text(id) === '' ? null : Number(text(id));
+        const rule = { name: text('rule-name') };
+        for (const [key, id] of [['model', 'rule-model'], ['user', 'rule-user'], ['team', 'rule-team'], ['hours_utc', 'rule-hours']]) {
+            if (text(id)) rule[key] = text(id);
+        }
+        for (const [key, id] of [['min_input_chars', 'rule-min'], ['max_input_chars', 'rule-max'], ['max_tokens', 'rule-max-tokens']]) {
+            if (number(id) != null) rule[key] = number(id);
+        }
+        const providers = text('rule-providers').split(',').map(s => s.trim()).filter(s => s !== '');
+        if (providers.length) rule.providers = providers;
+        if (text('rule-cache')) rule.cache = text('rule-cache') === 'true';
+        return rule;
+    }
+
+    function applyRule() {
+        const rule = readRule();
+        if (!rule.name) {
+            showAlert('danger', 'Every routing rule needs a name.');
+            return;
+        }
+        if (rules.some((r, i) => r.name === rule.name && i !== editing)) {
+            showAlert('danger', 'A rule named ' + rule.name + ' already exists.');
+            return;
+        }
+        if (editing >= 0) rules[editing] = rule; else rules.push(rule);
+        closeEditor();
+        markUnsaved(true);
+        renderRules();
+    }
+
+    async function saveRules() {
+        const resp = await GAUD.apiFetch('/admin/routing/rules', {
+            method: 'PUT',
+            body: JSON.stringify({ rules }),
+        });
+        if (!resp) return;
+        const data = await resp.json();
+        if (!resp.ok) {
+            showAlert('danger', (data.error && data.error.message) ? data.error.message : 'Failed to save rules');
+            return;
+        }
+        rules = data.rules;
+        markUnsaved(false);
+        renderRules();
+        showAlert('success', 'Routing rules saved and applied.');
+    }
+
+    async function simulate() {
+        const value = id => document.getElementById(id).value.trim();
+        const body = {
+            model: value('sim-model'),
+            prompt: document.getElementById('sim-prompt').value,
+            priority: value('sim-priority'),
+        };
+        if (value('sim-user')) body.user = value('sim-user');
+        if (value('sim-team')) body.team = value('sim-team');
+        if (value('sim-hour') !== '') body.hour_utc = Number(value('sim-hour'));
+        if (value('sim-chars') !== '') body.input_chars = Number(value('sim-chars'));
+        if (document.getElementById('sim-unsaved').checked) body.rules = rules;
+
+        const el = document.getElementById('sim-result');
+        const resp = await GAUD.apiFetch('/admin/routing/simulate', {
+            method: 'POST',
+            body: JSON.stringify(body),
+        });
+        if (!resp) return;
+        const data = await resp.json();
+        if (!resp.ok) {
+            el.innerHTML = '<div class="alert alert-danger">' +
+                escapeHtml((data.error && data.error.message) ? data.error.message : 'Simulation failed') + '</div>';
+            return;
+        }
+        let html = '<table><tbody>';
+        if (data.model !== body.model) {
+            html += '<tr><th>Resolved model</th><td class="mono">' + escapeHtml(data.model) + '</td></tr>';
+        }
+        html += '<tr><th>Facts</th><td>user ' + escapeHtml(data.user) +
+            (data.team ? ', team ' + escapeHtml(data.team) : ', no team') +
+            ', ' + data.input_chars + ' chars, ' + data.hour_utc + ':00 UTC</td></tr>';
+        html += '<tr><th>Rule</th><td>' + (data.decision
+            ? '<span class="mono">' + escapeHtml(data.decision.rule) + '</span> (' + describeDecisions(data.decision) + ')'
+            : '<span class="text-muted">none matched</span>') + '</td></tr>';
+        html += '<tr><th>Served by</th><td>' + (data.providers.length
+            ? '<strong class="mono">' + escapeHtml(data.providers[0]) + '</strong>' +
+              (data.providers.length > 1 ? ', then ' + data.providers.slice(1).map(escapeHtml).join(', ') : '')
+            : '<span class="text-danger">no available provider</span>') + '</td></tr>';
+        if (data.unavailable.length) {
+            html += '<tr><th>Unavailable</th><td>' + data.unavailable.map(escapeHtml).join(', ') +
+                ' <span class="text-muted">(circuit open or quota used up)</span></td></tr>';
+        }
+        el.innerHTML = html + '</tbody></table>';
+    }
+
+    async function loadRules() {
+        try {
+            const resp = await GAUD.apiFetch('/admin/routing/rules');
+            if (!resp || !resp.ok) {
+                if (resp && resp.status === 403) {
+                    showAlert('danger', 'Admin access required to edit routing rules.');
+                }
+                return;
+            }
+            const data = await resp.json();
+            rules = data.rules;
+            document.getElementById('sim-models').innerHTML =
+                data.virtual_models.map(m => '<option value="' + escapeHtml(m) + '">').join('');
+            renderRules();
+        } catch (err) {
+            console.error('Failed to load routing rules:', err);
+            showAlert('danger', 'Failed to load routing rules: ' + err.message);
+        }
+    }
+
+    loadRules();
+</script>
+{% endblock %}"#;